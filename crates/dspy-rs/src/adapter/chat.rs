@@ -125,6 +125,10 @@ struct FieldView<'a> {
     lm_name: &'a str,
     docs: &'a str,
     ty: &'a FieldType,
+    prefix: Option<&'a str>,
+    hint: Option<&'a str>,
+    examples: &'a [Box<str>],
+    section: Option<&'a str>,
 }
 
 impl<'a> FieldView<'a> {
@@ -133,12 +137,47 @@ impl<'a> FieldView<'a> {
             lm_name: &field.lm_name,
             docs: field.docs.as_deref().unwrap_or(""),
             ty: &field.ty,
+            prefix: field.prefix.as_deref(),
+            hint: field.hint.as_deref(),
+            examples: &field.examples,
+            section: field.section.as_deref(),
         }
     }
 }
 
+/// The fields the LM sees, in prompt order: sorted stably by `order` (unset
+/// counts as 0). Hidden inputs stay in the input map (for templates, metrics,
+/// and tools) but never reach the prompt.
+fn prompt_fields(fields: &[crate::ir::FieldDef]) -> Vec<&crate::ir::FieldDef> {
+    let mut shown: Vec<_> = fields.iter().filter(|field| !field.hidden).collect();
+    shown.sort_by_key(|field| field.order.unwrap_or(0));
+    shown
+}
+
 fn def_views(fields: &[crate::ir::FieldDef]) -> Vec<FieldView<'_>> {
-    fields.iter().map(FieldView::of_def).collect()
+    prompt_fields(fields)
+        .into_iter()
+        .map(FieldView::of_def)
+        .collect()
+}
+
+/// The heading to print before a field: its `section`, when it differs from
+/// the previous field's.
+fn section_heading<'a>(previous: &mut Option<&'a str>, section: Option<&'a str>) -> Option<String> {
+    let changed = section.is_some() && section != *previous;
+    *previous = section;
+    changed.then(|| format!("--- {} ---", section.unwrap_or_default()))
+}
+
+/// Drops a field's `prefix` when the LM echoes it at the top of the section.
+fn strip_prefix<'t>(text: &'t str, prefix: Option<&str>) -> &'t str {
+    match prefix {
+        Some(prefix) => text
+            .trim_start()
+            .strip_prefix(prefix)
+            .map_or(text, str::trim_start),
+        None => text,
+    }
 }
 
 fn format_task_description_view(
@@ -199,7 +238,9 @@ fn format_field_descriptions_view(
 ) -> String {
     let mut lines = Vec::new();
     lines.push("Your input fields are:".to_string());
+    let mut section = None;
     for (i, field) in inputs.iter().enumerate() {
+        lines.extend(section_heading(&mut section, field.section));
         let type_name = type_name(field.ty, None);
         let mut line = format!("{}. `{}` ({type_name})", i + 1, field.lm_name);
         if !field.docs.is_empty() {
@@ -211,7 +252,9 @@ fn format_field_descriptions_view(
 
     lines.push(String::new());
     lines.push("Your output fields are:".to_string());
+    let mut section = None;
     for (i, field) in outputs.iter().enumerate() {
+        lines.extend(section_heading(&mut section, field.section));
         let type_name = type_name(field.ty, Some(types));
         let mut line = format!("{}. `{}` ({type_name})", i + 1, field.lm_name);
         if !field.docs.is_empty() {
@@ -236,6 +279,9 @@ fn format_field_structure_view(
 
     for field in inputs {
         lines.push(format!("[[ ## {} ## ]]", field.lm_name));
+        if let Some(prefix) = field.prefix {
+            lines.push(prefix.to_string());
+        }
         lines.push(field.lm_name.to_string());
         lines.push(String::new());
     }
@@ -244,10 +290,19 @@ fn format_field_structure_view(
         let type_name = type_name(field.ty, Some(types));
         let rendered_schema = schema_block(field.ty, types);
         lines.push(format!("[[ ## {} ## ]]", field.lm_name));
+        if let Some(prefix) = field.prefix {
+            lines.push(prefix.to_string());
+        }
         lines.push(format!(
             "Output field `{}` should be of type: {type_name}",
             field.lm_name
         ));
+        if let Some(hint) = field.hint {
+            lines.push(format!("Formatting: {hint}"));
+        }
+        for example in field.examples {
+            lines.push(format!("Example: {example}"));
+        }
         if !rendered_schema.is_empty() && rendered_schema != type_name {
             lines.push(String::new());
             lines.push(rendered_schema);
@@ -285,9 +340,13 @@ impl ChatAdapter {
     ///
     /// The system message includes:
     /// 1. Field descriptions (names, types, doc comments)
-    /// 2. Field structure template (the `[[ ## field ## ]]` layout the LM should follow)
+    /// 2. Field structure template (the `[[ ## field ## ]]` layout the LM should
+    ///    follow, with field prefixes and output hints/examples)
     /// 3. Response instructions (which fields to produce, in what order)
     /// 4. Task description (the def's instruction or the override)
+    ///
+    /// Hidden input fields are left out of every part. Fields appear in
+    /// `order`, and a field's `section` heads its group in the field list.
     pub fn build_system_def(
         &self,
         def: &SignatureDef,
@@ -309,17 +368,29 @@ impl ChatAdapter {
     /// which output fields to produce.
     ///
     /// Fields absent from `input` are skipped (the historical relaxed path
-    /// navigation for flattened structs). Jinja render templates are compiled
-    /// per call — dynamic defs own their template strings, and a
-    /// process-global cache keyed on them would reintroduce the leak-per-load
-    /// RFC 0002 IR-1 removed.
+    /// navigation for flattened structs), as are hidden fields — their values
+    /// stay visible to other fields' templates. Fields appear in `order`, under
+    /// their `section` heading; a field's `prefix` opens its section.
+    ///
+    /// Jinja render templates are compiled per call — dynamic defs own their
+    /// template strings, and a process-global cache keyed on them would
+    /// reintroduce the leak-per-load RFC 0002 IR-1 removed.
     pub fn format_input_def(&self, def: &SignatureDef, input: &JsonMap) -> String {
         let mut result = String::new();
-        for field in def.inputs.iter() {
+        let mut section = None;
+        for field in prompt_fields(&def.inputs) {
             let Some(value) = input.get(&*field.name) else {
                 continue;
             };
+            if let Some(heading) = section_heading(&mut section, field.section.as_deref()) {
+                result.push_str(&heading);
+                result.push_str("\n\n");
+            }
             result.push_str(&format!("[[ ## {} ## ]]\n", field.lm_name));
+            if let Some(prefix) = &field.prefix {
+                result.push_str(prefix);
+                result.push('\n');
+            }
             result.push_str(&render_input_field_def(def, field, value, input));
             result.push_str("\n\n");
         }
@@ -333,13 +404,19 @@ impl ChatAdapter {
     /// ending with `[[ ## completed ## ]]`.
     ///
     /// Fields absent from `output` are skipped (the historical relaxed path
-    /// navigation for flattened structs).
+    /// navigation for flattened structs). A field's `prefix` opens its section,
+    /// as the structure template asks of the LM.
     pub fn format_output_def(&self, def: &SignatureDef, output: &JsonMap) -> String {
         let mut sections = Vec::new();
-        for field in def.outputs.iter() {
+        for field in prompt_fields(&def.outputs) {
             if let Some(value) = output.get(&*field.name) {
+                let prefix = field
+                    .prefix
+                    .as_deref()
+                    .map(|prefix| format!("{prefix}\n"))
+                    .unwrap_or_default();
                 sections.push(format!(
-                    "[[ ## {} ## ]]\n{}",
+                    "[[ ## {} ## ]]\n{prefix}{}",
                     field.lm_name,
                     format_json_value_for_prompt(value)
                 ));
@@ -356,8 +433,9 @@ impl ChatAdapter {
     ///
     /// The returned [`JsonMap`] is keyed by canonical field name
     /// (`FieldDef::name`); `types` resolves class/enum references during
-    /// coercion. Constraint expressions are compiled per call (owned strings —
-    /// no global cache, no leak).
+    /// coercion. A field's `prefix` echoed at the top of its section is dropped
+    /// before coercion. Constraint expressions are compiled per call (owned
+    /// strings — no global cache, no leak).
     pub fn parse_output_def(
         &self,
         def: &SignatureDef,
@@ -384,7 +462,11 @@ impl ChatAdapter {
                 }
             };
 
-            let coerced = match coerce(raw_text, &field.ty, types) {
+            let coerced = match coerce(
+                strip_prefix(raw_text, field.prefix.as_deref()),
+                &field.ty,
+                types,
+            ) {
                 Ok(value) => value,
                 Err(err) => {
                     let expected_type = type_name(&field.ty, Some(types));
@@ -18,6 +18,14 @@ impl StdError for JsonishError {
     }
 }
 
+impl JsonishError {
+    /// The [`AmbiguousMatch`](crate::typesys::coerce::AmbiguousMatch) behind this error, when
+    /// fuzzy enum/literal matching refused to pick between equally close values.
+    pub fn ambiguous_match(&self) -> Option<&crate::typesys::coerce::AmbiguousMatch> {
+        self.0.downcast_ref()
+    }
+}
+
 impl From<anyhow::Error> for JsonishError {
     fn from(error: anyhow::Error) -> Self {
         Self(error)
@@ -403,11 +403,18 @@ impl From<RigMessage> for Message {
 #[derive(Clone, Debug)]
 pub struct Chat {
     pub messages: Vec<Message>,
+    /// Where an agent conversation stood against its context policy when
+    /// the interpreter returned this chat, so the next turn keeps the held
+    /// summary and pins in place.
+    pub(crate) window: Option<crate::ir::interp::HeldWindow>,
 }
 
 impl Chat {
     pub fn new(messages: Vec<Message>) -> Self {
-        Self { messages }
+        Self {
+            messages,
+            window: None,
+        }
     }
 
     pub fn len(&self) -> usize {
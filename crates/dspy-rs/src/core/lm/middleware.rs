@@ -0,0 +1,100 @@
+//! Interceptors around an [`LM`](super::LM)'s provider calls.
+//!
+//! An [`LmMiddleware`] sits between the LM and its provider client. It sees
+//! every round trip as a rig [`CompletionRequest`] on the way out and a
+//! [`CompletionResponse`] on the way back, and may change either, answer
+//! without calling the provider at all, or fail. It also sees each finished
+//! [`LMResponse`] before the caller does. That covers header and parameter
+//! injection, request logs, prompt rewriting, response post-processing, and
+//! fault injection; rate limiting, caching, and fallback to a second client
+//! fit the same shape.
+//!
+//! Middlewares run in the order they were added: the first one added sees
+//! each request first and each response last. The chain runs inside the
+//! LM's transient-failure retry, once per attempt, so an injected `429` is
+//! retried like a real one. The response cache (`LMConfig::cache`) is
+//! consulted before the chain; a cache hit makes no round trip but still
+//! passes through [`respond`](LmMiddleware::respond).
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use rig::completion::{CompletionError, CompletionRequest, CompletionResponse};
+
+use super::{CompletionProvider, LMClient, LMResponse};
+
+/// One interceptor in an [`LM`](super::LM)'s middleware chain. Both hooks
+/// default to passing through, so implement only what you need.
+///
+/// ```ignore
+/// struct Tag;
+///
+/// #[async_trait::async_trait]
+/// impl LmMiddleware for Tag {
+///     async fn complete(
+///         &self,
+///         mut request: CompletionRequest,
+///         next: Next<'_>,
+///     ) -> Result<CompletionResponse<()>, CompletionError> {
+///         request.additional_params = Some(serde_json::json!({ "user": "billing" }));
+///         next.run(request).await
+///     }
+/// }
+///
+/// let lm = LM::builder().build().await?.with_middleware(Tag);
+/// ```
+#[async_trait::async_trait]
+pub trait LmMiddleware: Send + Sync {
+    /// Handles one provider round trip. Call [`next.run`](Next::run) to pass
+    /// the (possibly rewritten) request on down the chain, or return a
+    /// response or error of your own to short-circuit it. Errors the LM
+    /// treats as transient (HTTP failures, `429`/`5xx` provider errors) are
+    /// retried.
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse<()>, CompletionError> {
+        next.run(request).await
+    }
+
+    /// Post-processes a finished call — after any tool loop, with the full
+    /// chat — before it is returned. Runs innermost first. An error fails
+    /// the call.
+    fn respond(&self, response: LMResponse) -> Result<LMResponse> {
+        Ok(response)
+    }
+}
+
+/// The rest of a middleware chain, ending at the provider client.
+pub struct Next<'a> {
+    chain: &'a [Arc<dyn LmMiddleware>],
+    client: Option<&'a LMClient>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(chain: &'a [Arc<dyn LmMiddleware>], client: Option<&'a LMClient>) -> Self {
+        Self { chain, client }
+    }
+
+    /// Sends `request` to the next middleware, or to the provider once the
+    /// chain is exhausted.
+    pub async fn run(
+        self,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse<()>, CompletionError> {
+        match self.chain.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .complete(request, Next::new(rest, self.client))
+                    .await
+            }
+            None => match self.client {
+                Some(client) => client.completion(request).await,
+                None => Err(CompletionError::ProviderError(
+                    "LM client not initialized and no middleware answered".to_string(),
+                )),
+            },
+        }
+    }
+}
@@ -1,9 +1,13 @@
 pub mod chat;
 pub mod client_registry;
+pub mod middleware;
+pub mod tokenizer;
 pub mod usage;
 
 pub use chat::*;
 pub use client_registry::*;
+pub use middleware::*;
+pub use tokenizer::*;
 pub use usage::*;
 
 use anyhow::Result;
@@ -146,6 +150,18 @@ pub struct LMConfig {
     pub retry_base_delay_ms: u64,
     #[builder(default = false)]
     pub cache: bool,
+    /// The model's context window in tokens, prompt and completion
+    /// together. When set, the interpreter counts every leaf prompt before
+    /// sending it and applies the runtime's
+    /// [`OverflowPolicy`](crate::ir::OverflowPolicy) if it would not fit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// The [`Tokenizer`] to count prompts with, by name: one bound through
+    /// [`RuntimeEnv::bind_tokenizer`](crate::ir::RuntimeEnv::bind_tokenizer)
+    /// or a builtin (see [`builtin_tokenizer`]). `None` picks one from the
+    /// model family.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<String>,
 }
 
 impl Default for LMConfig {
@@ -154,13 +170,15 @@ impl Default for LMConfig {
     }
 }
 
-/// The live half: an [`LMConfig`] plus the initialized provider client and
-/// response cache. Constructed via [`LM::builder()`] or [`LM::from_config`].
+/// The live half: an [`LMConfig`] plus the initialized provider client,
+/// response cache, and [middleware](LmMiddleware) chain. Constructed via
+/// [`LM::builder()`] or [`LM::from_config`].
 #[derive(Clone)]
 pub struct LM {
     pub config: LMConfig,
     pub cache_handler: Option<Arc<ResponseCache>>,
     client: Option<Arc<LMClient>>,
+    middleware: Vec<Arc<dyn LmMiddleware>>,
 }
 
 impl Default for LM {
@@ -176,6 +194,18 @@ impl LM {
         LMConfig::builder()
     }
 
+    /// An [`LM`] with no provider client: it carries its config (for spans,
+    /// budgets, and cost) but every call fails. Dry runs bind these for
+    /// models the environment does not provide.
+    pub(crate) fn detached(config: LMConfig) -> Self {
+        Self {
+            config,
+            cache_handler: None,
+            client: None,
+            middleware: Vec::new(),
+        }
+    }
+
     /// Builds the live [`LM`] from a config, initializing the provider client and
     /// optional response cache.
     ///
@@ -249,6 +279,7 @@ impl LM {
             config,
             cache_handler,
             client: Some(client),
+            middleware: Vec::new(),
         })
     }
 
@@ -258,6 +289,30 @@ impl LM {
             ..self
         })
     }
+
+    /// Appends `middleware` to the chain around every provider call (see
+    /// [`LmMiddleware`]). Middlewares run in the order they were added.
+    pub fn with_middleware(self, middleware: impl LmMiddleware + 'static) -> Self {
+        self.with_middleware_arc(Arc::new(middleware))
+    }
+
+    /// [`with_middleware`](Self::with_middleware) for a shared middleware.
+    pub fn with_middleware_arc(mut self, middleware: Arc<dyn LmMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// The middleware chain, outermost first.
+    pub fn middleware(&self) -> &[Arc<dyn LmMiddleware>] {
+        &self.middleware
+    }
+
+    /// This LM with `outer` wrapped around its existing chain.
+    pub(crate) fn wrapped_in(&self, outer: &[Arc<dyn LmMiddleware>]) -> Self {
+        let mut lm = self.clone();
+        lm.middleware.splice(0..0, outer.iter().cloned());
+        lm
+    }
 }
 
 // Implement build() for all builder states since optional fields don't require setting
@@ -470,7 +525,8 @@ impl LM {
         }
     }
 
-    /// Calls the provider, retrying transient failures with jittered exponential backoff.
+    /// Calls the provider through the middleware chain, retrying transient
+    /// failures with jittered exponential backoff.
     ///
     /// Takes a request *builder* so each attempt constructs its own request from
     /// borrowed parts — no whole-request clone on the common no-retry path.
@@ -478,14 +534,20 @@ impl LM {
     where
         F: Fn() -> CompletionRequest,
     {
-        let client = self
-            .client
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("LM client not initialized. Call build() on LMBuilder."))?;
+        // A detached LM can still be answered by its middleware.
+        if self.client.is_none() && self.middleware.is_empty() {
+            return Err(anyhow::anyhow!(
+                "LM client not initialized. Call build() on LMBuilder."
+            ));
+        }
+        let client = self.client.as_deref();
 
         let mut attempt = 0u32;
         loop {
-            match client.completion(build_request()).await {
+            match Next::new(&self.middleware, client)
+                .run(build_request())
+                .await
+            {
                 Ok(response) => return Ok(response),
                 Err(err) if attempt < self.config.max_retries && is_retryable_completion_error(&err) => {
                     let backoff = self
@@ -756,6 +818,21 @@ impl LM {
         messages: Chat,
         tools: &ToolSet,
         tool_loop_mode: ToolLoopMode,
+    ) -> Result<LMResponse> {
+        let response = self.complete_chat(messages, tools, tool_loop_mode).await?;
+        self.middleware
+            .iter()
+            .rev()
+            .try_fold(response, |response, middleware| middleware.respond(response))
+    }
+
+    /// The body of [`call_with_toolset`](LM::call_with_toolset), before the
+    /// middleware response hooks.
+    async fn complete_chat(
+        &self,
+        messages: Chat,
+        tools: &ToolSet,
+        tool_loop_mode: ToolLoopMode,
     ) -> Result<LMResponse> {
         let system_prompt = messages.system_prompt();
         let chat_history = messages.to_rig_chat_history();
@@ -1202,9 +1279,12 @@ mod tests {
                 max_retries: 0,
                 retry_base_delay_ms: 1,
                 cache: false,
+                context_window: None,
+                tokenizer: None,
             },
             cache_handler: None,
             client: Some(Arc::new(LMClient::Test(model))),
+            middleware: Vec::new(),
         }
     }
 
@@ -0,0 +1,141 @@
+//! Prompt token counting.
+//!
+//! A [`Tokenizer`] turns text into a token count. The interpreter uses one
+//! to check a rendered prompt against the model's
+//! [`context_window`](super::LMConfig::context_window) before sending it.
+//! The builtin tables are offline BPE encodings for the OpenAI families
+//! (behind the default-on `tiktoken` feature) plus [`ByteEstimate`], the
+//! four-bytes-per-token rule the cost model uses. Other families count with
+//! `cl100k_base` as an approximation unless a closer tokenizer is bound
+//! through [`RuntimeEnv::bind_tokenizer`](crate::ir::RuntimeEnv::bind_tokenizer).
+
+use super::Message;
+
+/// Tokens every message costs on top of its content: role and separators.
+pub const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Tokens the provider adds to prime the assistant's reply.
+pub const REPLY_PRIMING_TOKENS: usize = 3;
+
+/// Counts tokens in text for one model family.
+pub trait Tokenizer: Send + Sync {
+    /// The name a model config refers to this tokenizer by.
+    fn name(&self) -> &str;
+
+    /// The number of tokens `text` encodes to.
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Four bytes per token: no table, no dependency, roughly right for English
+/// prose and JSON. Named `"bytes"`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByteEstimate;
+
+impl Tokenizer for ByteEstimate {
+    fn name(&self) -> &str {
+        "bytes"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.len().div_ceil(4)
+    }
+}
+
+/// One of tiktoken's BPE tables, loaded on first use and shared after.
+#[cfg(feature = "tiktoken")]
+pub struct Bpe {
+    name: &'static str,
+    load: fn() -> &'static tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for Bpe {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        (self.load)().encode_ordinary(text).len()
+    }
+}
+
+static BYTES: ByteEstimate = ByteEstimate;
+
+#[cfg(feature = "tiktoken")]
+static O200K: Bpe = Bpe {
+    name: "o200k_base",
+    load: tiktoken_rs::o200k_base_singleton,
+};
+
+#[cfg(feature = "tiktoken")]
+static CL100K: Bpe = Bpe {
+    name: "cl100k_base",
+    load: tiktoken_rs::cl100k_base_singleton,
+};
+
+#[cfg(feature = "tiktoken")]
+static P50K: Bpe = Bpe {
+    name: "p50k_base",
+    load: tiktoken_rs::p50k_base_singleton,
+};
+
+#[cfg(feature = "tiktoken")]
+static R50K: Bpe = Bpe {
+    name: "r50k_base",
+    load: tiktoken_rs::r50k_base_singleton,
+};
+
+/// The builtin tokenizer called `name`: `"bytes"` always, and
+/// `"o200k_base"`, `"cl100k_base"`, `"p50k_base"`, `"r50k_base"` with the
+/// `tiktoken` feature.
+pub fn builtin_tokenizer(name: &str) -> Option<&'static dyn Tokenizer> {
+    match name {
+        "bytes" => Some(&BYTES),
+        #[cfg(feature = "tiktoken")]
+        "o200k_base" => Some(&O200K),
+        #[cfg(feature = "tiktoken")]
+        "cl100k_base" => Some(&CL100K),
+        #[cfg(feature = "tiktoken")]
+        "p50k_base" => Some(&P50K),
+        #[cfg(feature = "tiktoken")]
+        "r50k_base" => Some(&R50K),
+        _ => None,
+    }
+}
+
+/// The tokenizer for a `provider:model` string when its config names none:
+/// the model's own table for OpenAI models, `cl100k_base` for everything
+/// else, and [`ByteEstimate`] without the `tiktoken` feature.
+pub fn default_tokenizer(model: &str) -> &'static dyn Tokenizer {
+    #[cfg(feature = "tiktoken")]
+    {
+        use tiktoken_rs::tokenizer::{Tokenizer as Table, get_tokenizer};
+
+        let name = model.split_once(':').map_or(model, |(_, name)| name);
+        match get_tokenizer(name) {
+            Some(Table::O200kBase) => &O200K,
+            Some(Table::P50kBase | Table::P50kEdit) => &P50K,
+            Some(Table::R50kBase | Table::Gpt2) => &R50K,
+            _ if name.starts_with("gpt-5") => &O200K,
+            _ => &CL100K,
+        }
+    }
+    #[cfg(not(feature = "tiktoken"))]
+    {
+        let _ = model;
+        &BYTES
+    }
+}
+
+/// Tokens a chat costs as a prompt: each message's content plus
+/// [`MESSAGE_OVERHEAD_TOKENS`], plus [`REPLY_PRIMING_TOKENS`] once.
+pub fn count_messages<'a>(
+    tokenizer: &dyn Tokenizer,
+    messages: impl IntoIterator<Item = &'a Message>,
+) -> usize {
+    messages
+        .into_iter()
+        .map(|message| tokenizer.count(&message.content()) + MESSAGE_OVERHEAD_TOKENS)
+        .sum::<usize>()
+        + REPLY_PRIMING_TOKENS
+}
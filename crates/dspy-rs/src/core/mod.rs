@@ -35,6 +35,9 @@ pub use state::{ModuleState, PredictState};
 pub use lm::*;
 pub use module::*;
 pub use predicted::{CallMetadata, ConstraintResult, FieldMeta, Predicted};
-pub use schema::{FieldMetadataSpec, FieldPath, FieldSchema, InputRenderSpec, SignatureSchema};
+pub use schema::{
+    FieldMetadataSpec, FieldPath, FieldPresentationSpec, FieldSchema, InputRenderSpec,
+    SignatureSchema,
+};
 pub use settings::*;
 pub use signature::*;
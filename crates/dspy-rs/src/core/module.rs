@@ -3,6 +3,7 @@ use futures::stream::{self, StreamExt};
 use tracing::debug;
 
 use crate::core::PredictState;
+use crate::ir::{SignatureDef, TypeTable};
 use crate::trace::JsonMap;
 use crate::{Facet, PredictError, Predicted, Schema, SignatureSchema};
 
@@ -28,6 +29,20 @@ pub trait PredictorInfo: Send + Sync {
     /// and docs for optimizer reflection prompts.
     fn schema(&self) -> &'static SignatureSchema;
 
+    /// The value-level [`SignatureDef`] this leaf runs (the def its 1-node
+    /// program declares), for callers that rebuild the leaf in a program —
+    /// [`ir::export_module`](crate::ir::export_module). `None` (the default)
+    /// for leaves that cannot be rebuilt; export refuses them.
+    fn signature_def(&self) -> Option<&'static SignatureDef> {
+        None
+    }
+
+    /// The class/enum definitions reachable from either side of
+    /// [`signature_def`](PredictorInfo::signature_def). Empty by default.
+    fn signature_types(&self) -> TypeTable {
+        TypeTable::default()
+    }
+
     /// The current effective instruction (override if set, else the
     /// signature's default).
     fn instruction(&self) -> String;
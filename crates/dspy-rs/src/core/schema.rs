@@ -56,6 +56,28 @@ pub enum InputRenderSpec {
     Jinja(&'static str),
 }
 
+/// Prompt presentation controls for a single field, emitted by
+/// `#[derive(Signature)]` (`#[input(hidden)]`, `#[prefix]`, `#[hint]`,
+/// `#[example]`, `#[order]`, `#[section]`). All default to "no change" — the
+/// field renders as before.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldPresentationSpec {
+    /// Input only: the value stays in the input map (metrics, tools, holes,
+    /// and other fields' templates read it) but is never shown to the LM.
+    pub hidden: bool,
+    /// Text opening the field's section, ahead of the value.
+    pub prefix: Option<&'static str>,
+    /// Output only: a rendering hint for the LM (e.g. "a bullet list").
+    pub hint: Option<&'static str>,
+    /// Output only: example values shown in the response template.
+    pub examples: &'static [&'static str],
+    /// Position among the fields on its side; fields sort stably by it, unset
+    /// counting as 0.
+    pub order: Option<i32>,
+    /// Heading that opens a group of fields in the prompt.
+    pub section: Option<&'static str>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct FieldMetadataSpec {
     /// The Rust field name as written in the signature struct.
@@ -66,6 +88,8 @@ pub struct FieldMetadataSpec {
     pub constraints: &'static [ConstraintSpec],
     /// Input rendering policy for this field.
     pub input_render: InputRenderSpec,
+    /// Prompt presentation controls (hidden, prefix, hint, examples).
+    pub presentation: FieldPresentationSpec,
 }
 
 /// Complete schema for a single field in a signature, combining Facet shape data with metadata.
@@ -90,6 +114,8 @@ pub struct FieldSchema {
     pub constraints: &'static [ConstraintSpec],
     /// Input rendering policy.
     pub input_render: InputRenderSpec,
+    /// Prompt presentation controls.
+    pub presentation: FieldPresentationSpec,
 }
 
 impl FieldSchema {
@@ -315,6 +341,7 @@ fn emit_field(
     let input_render = inherited
         .map(|meta| meta.input_render)
         .unwrap_or(InputRenderSpec::Default);
+    let presentation = inherited.map(|meta| meta.presentation).unwrap_or_default();
 
     out.push(FieldSchema {
         lm_name,
@@ -325,6 +352,7 @@ fn emit_field(
         path,
         constraints,
         input_render,
+        presentation,
     });
 
     Ok(())
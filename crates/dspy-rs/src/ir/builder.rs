@@ -30,7 +30,7 @@
 //! [`ProgramBuilder::main`] — a dangling reference is a [`BuildError`], never
 //! a panic.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU32;
 
 use cranelift_entity::{EntityRef, PrimaryMap};
@@ -38,10 +38,11 @@ use cranelift_entity::{EntityRef, PrimaryMap};
 use crate::LMConfig;
 use crate::core::Signature;
 use crate::ir::graph::{
-    AgentLoopNode, Binding, CapSet, ForkJoinNode, HoleImpl, HoleNode, Interner, LoopNode, ModelDef,
-    ModelId,
-    Node, NodeBudget, NodeId, PortRef, PredictNode, Program, ProgramMeta, RefineNode, RetryNode,
-    RouteNode, SeqNode, SigId, StopSpec, ToolDef, ToolId, ToolKind,
+    AgentLoopNode, ApproveNode, Binding, CallNode, CapSet, Comments, ForkJoinNode, HoleImpl,
+    HoleNode, ImportDef, ImportId, Interner, LoopNode, MapErrorPolicy, MapNode, MemoPolicy,
+    ModelDef, ModelId, Node, NodeBudget, NodeId, PortRef, PredictNode, Program, ProgramMeta,
+    RefineNode, RetryNode, RouteNode, SeqNode, SigId, StopSpec, Sym, ToolDef, ToolId, ToolKind,
+    TransformNode,
 };
 use crate::ir::params::{
     CodeLang, ContextPolicy, DemoRow, ParamId, ParamKind, ParamOwner, ParamSlot, ParamValue,
@@ -58,6 +59,13 @@ pub enum BuildError {
     MissingModel { at: String },
     #[error("duplicate step name `{name}`")]
     DuplicateStepName { name: String },
+    #[error("`{at}` calls {import}, which this builder never imported")]
+    UnknownImport { at: String, import: ImportId },
+    #[error(
+        "import `{alias}` defines type `{token}` differently from the importing program \
+         (shared class/enum names must have identical definitions)"
+    )]
+    ImportTypeConflict { alias: String, token: String },
     #[error(transparent)]
     Invalid(#[from] ValidateError),
 }
@@ -129,7 +137,8 @@ impl AsNodeName for &NodeSpec {
 
 /// An unregistered node: the builder-side mirror of [`Node`] with name-based
 /// ports and inline children. Constructed by [`predict`], [`cot`], [`agent`],
-/// [`hole`], [`seq`], [`fork`], [`route`], [`retry`], [`refine`], [`loop_`].
+/// [`hole`], [`transform`], [`approve`], [`call`], [`seq`], [`fork`],
+/// [`route`], [`retry`], [`refine`], [`loop_`], [`map`].
 #[derive(Clone, Debug)]
 pub struct NodeSpec {
     kind: SpecKind,
@@ -147,6 +156,7 @@ enum SpecKind {
         instruction: Option<String>,
         demos: Vec<DemoRow>,
         binds: Vec<(String, Port)>,
+        memo: Option<MemoPolicy>,
     },
     Agent {
         name: String,
@@ -164,6 +174,7 @@ enum SpecKind {
         budget: NodeBudget,
         context: ContextPolicy,
         binds: Vec<(String, Port)>,
+        memo: Option<MemoPolicy>,
     },
     Hole {
         name: String,
@@ -171,6 +182,23 @@ enum SpecKind {
         imp: HoleSpecImpl,
         caps: Vec<String>,
         binds: Vec<(String, Port)>,
+        memo: Option<MemoPolicy>,
+    },
+    Transform {
+        name: String,
+        sig: SigId,
+        binds: Vec<(String, Port)>,
+        exprs: Vec<(String, String)>,
+    },
+    Approve {
+        name: String,
+        sig: SigId,
+        binds: Vec<(String, Port)>,
+    },
+    Call {
+        name: String,
+        import: ImportId,
+        binds: Vec<(String, Port)>,
     },
     Seq {
         body: Vec<NodeSpec>,
@@ -183,6 +211,7 @@ enum SpecKind {
     Route {
         on: Port,
         arms: Vec<(String, NodeSpec)>,
+        when: Vec<(String, NodeSpec)>,
         default: Option<Box<NodeSpec>>,
     },
     Retry {
@@ -205,6 +234,14 @@ enum SpecKind {
         carry: Vec<(String, Port)>,
         out: Vec<(String, Port)>,
     },
+    Map {
+        over: Port,
+        item: String,
+        body: Box<NodeSpec>,
+        max_parallel: NonZeroU32,
+        on_error: MapErrorPolicy,
+        collect: Vec<(String, Port)>,
+    },
 }
 
 impl NodeSpec {
@@ -213,7 +250,10 @@ impl NodeSpec {
         match &self.kind {
             SpecKind::Predict { name, .. }
             | SpecKind::Agent { name, .. }
-            | SpecKind::Hole { name, .. } => Some(name),
+            | SpecKind::Hole { name, .. }
+            | SpecKind::Transform { name, .. }
+            | SpecKind::Approve { name, .. }
+            | SpecKind::Call { name, .. } => Some(name),
             _ => self.name.as_deref(),
         }
     }
@@ -231,8 +271,13 @@ impl NodeSpec {
         match &mut self.kind {
             SpecKind::Predict { binds, .. }
             | SpecKind::Agent { binds, .. }
-            | SpecKind::Hole { binds, .. } => binds.push((field.to_string(), port)),
-            _ => panic!("bind() applies to leaf specs (predict/cot/agent/hole)"),
+            | SpecKind::Hole { binds, .. }
+            | SpecKind::Transform { binds, .. }
+            | SpecKind::Approve { binds, .. }
+            | SpecKind::Call { binds, .. } => binds.push((field.to_string(), port)),
+            _ => panic!(
+                "bind() applies to leaf and call specs (predict/cot/agent/hole/transform/approve/call)"
+            ),
         }
         self
     }
@@ -336,6 +381,17 @@ impl NodeSpec {
         self
     }
 
+    /// Memoizes the leaf (Predict/Agent/Hole) under `policy`.
+    pub fn memo(mut self, policy: MemoPolicy) -> Self {
+        match &mut self.kind {
+            SpecKind::Predict { memo, .. }
+            | SpecKind::Agent { memo, .. }
+            | SpecKind::Hole { memo, .. } => *memo = Some(policy),
+            _ => panic!("memo() applies to predict/cot/agent/hole specs"),
+        }
+        self
+    }
+
     /// Exports a field from a `seq`/`loop` scope.
     pub fn out(mut self, field: &str, port: Port) -> Self {
         match &mut self.kind {
@@ -365,6 +421,16 @@ impl NodeSpec {
         self
     }
 
+    /// Adds a predicate route arm: a `#[check]`-style expression over `this`
+    /// (the routed value). Predicates are tried in the order they are added.
+    pub fn when(mut self, predicate: &str, node: NodeSpec) -> Self {
+        match &mut self.kind {
+            SpecKind::Route { when, .. } => when.push((predicate.to_string(), node)),
+            _ => panic!("when() applies to route specs"),
+        }
+        self
+    }
+
     /// Sets the route default (`else ->`).
     pub fn default_arm(mut self, node: NodeSpec) -> Self {
         match &mut self.kind {
@@ -429,6 +495,46 @@ impl NodeSpec {
         }
         self
     }
+
+    /// Bounds how many map elements run at once.
+    pub fn max_parallel(mut self, n: u32) -> Self {
+        match &mut self.kind {
+            SpecKind::Map { max_parallel, .. } => {
+                *max_parallel = NonZeroU32::new(n).expect("max_parallel must be > 0");
+            }
+            _ => panic!("max_parallel() applies to map specs"),
+        }
+        self
+    }
+
+    /// What a failed map element does to the map.
+    pub fn on_error(mut self, policy: MapErrorPolicy) -> Self {
+        match &mut self.kind {
+            SpecKind::Map { on_error, .. } => *on_error = policy,
+            _ => panic!("on_error() applies to map specs"),
+        }
+        self
+    }
+
+    /// Map collect: exports `field` as the list of this port's per-element
+    /// values.
+    pub fn collect(mut self, field: &str, port: Port) -> Self {
+        match &mut self.kind {
+            SpecKind::Map { collect, .. } => collect.push((field.to_string(), port)),
+            _ => panic!("collect() applies to map specs"),
+        }
+        self
+    }
+
+    /// Transform output: computes output `field` with the minijinja
+    /// expression `src` over the signature inputs.
+    pub fn expr(mut self, field: &str, src: &str) -> Self {
+        match &mut self.kind {
+            SpecKind::Transform { exprs, .. } => exprs.push((field.to_string(), src.to_string())),
+            _ => panic!("expr() applies to transform specs"),
+        }
+        self
+    }
 }
 
 /// The output field `cot` prepends to the base signature (RFC 0002 §4.2: `cot`
@@ -450,6 +556,7 @@ pub fn predict(name: &str, sig: SigId) -> NodeSpec {
             instruction: None,
             demos: Vec::new(),
             binds: Vec::new(),
+            memo: None,
         },
         name: None,
     }
@@ -481,6 +588,7 @@ pub fn agent(name: &str, sig: SigId) -> NodeSpec {
             budget: NodeBudget::default(),
             context: ContextPolicy::default(),
             binds: Vec::new(),
+            memo: None,
         },
         name: None,
     }
@@ -495,6 +603,7 @@ pub fn hole(name: &str, sig: SigId, js: &str, caps: &[&str]) -> NodeSpec {
             imp: HoleSpecImpl::Js(js.to_string()),
             caps: caps.iter().map(|c| c.to_string()).collect(),
             binds: Vec::new(),
+            memo: None,
         },
         name: None,
     }
@@ -512,6 +621,48 @@ pub fn extern_hole(name: &str, sig: SigId, hash: u64, caps: &[&str]) -> NodeSpec
             imp: HoleSpecImpl::Host(hash),
             caps: caps.iter().map(|c| c.to_string()).collect(),
             binds: Vec::new(),
+            memo: None,
+        },
+        name: None,
+    }
+}
+
+/// A pure data transform: each signature output is computed by an
+/// [`expr`](NodeSpec::expr) over the bound inputs, without an LM call.
+pub fn transform(name: &str, sig: SigId) -> NodeSpec {
+    NodeSpec {
+        kind: SpecKind::Transform {
+            name: name.to_string(),
+            sig,
+            binds: Vec::new(),
+            exprs: Vec::new(),
+        },
+        name: None,
+    }
+}
+
+/// A human sign-off point: the bound inputs are the pending payload, and
+/// each signature output passes through the same-named input.
+pub fn approve(name: &str, sig: SigId) -> NodeSpec {
+    NodeSpec {
+        kind: SpecKind::Approve {
+            name: name.to_string(),
+            sig,
+            binds: Vec::new(),
+        },
+        name: None,
+    }
+}
+
+/// Embeds the program imported as `import` (see [`ProgramBuilder::import`]).
+/// Its program inputs bind like a leaf's; every name inside it is prefixed
+/// `<name>.` in this program.
+pub fn call(name: &str, import: ImportId) -> NodeSpec {
+    NodeSpec {
+        kind: SpecKind::Call {
+            name: name.to_string(),
+            import,
+            binds: Vec::new(),
         },
         name: None,
     }
@@ -546,12 +697,13 @@ pub fn fork(branches: impl IntoIterator<Item = NodeSpec>) -> NodeSpec {
     }
 }
 
-/// Enum-discriminated branching.
+/// Enum-discriminated (or predicate) branching.
 pub fn route(on: Port) -> NodeSpec {
     NodeSpec {
         kind: SpecKind::Route {
             on,
             arms: Vec::new(),
+            when: Vec::new(),
             default: None,
         },
         name: None,
@@ -599,6 +751,23 @@ pub fn loop_(body: NodeSpec, max_iters: u32) -> NodeSpec {
     }
 }
 
+/// Runs `body` once per element of the list port `over`; the body reads the
+/// element as `$.<item>`. Sequential (`max_parallel 1`) and fail-fast unless
+/// configured.
+pub fn map(item: &str, over: Port, body: NodeSpec) -> NodeSpec {
+    NodeSpec {
+        kind: SpecKind::Map {
+            over,
+            item: item.to_string(),
+            body: Box::new(body),
+            max_parallel: NonZeroU32::MIN,
+            on_error: MapErrorPolicy::Fail,
+            collect: Vec::new(),
+        },
+        name: None,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ProgramBuilder
 // ---------------------------------------------------------------------------
@@ -621,6 +790,9 @@ pub struct ProgramBuilder {
     sigs: PrimaryMap<SigId, SignatureDef>,
     types: crate::typesys::TypeTable,
     tool_specs: Vec<ToolSpec>,
+    imports: PrimaryMap<ImportId, ImportDef>,
+    /// The imported programs, indexed like `imports`.
+    callees: Vec<Program>,
 }
 
 impl ProgramBuilder {
@@ -632,6 +804,8 @@ impl ProgramBuilder {
             sigs: PrimaryMap::new(),
             types: crate::typesys::TypeTable::default(),
             tool_specs: Vec::new(),
+            imports: PrimaryMap::new(),
+            callees: Vec::new(),
         }
     }
 
@@ -692,6 +866,40 @@ impl ProgramBuilder {
         self
     }
 
+    /// Imports `callee` for [`call`] nodes. `path` is recorded as written (the
+    /// text form prints it) and the callee's hash becomes the import's pin.
+    /// The callee's class/enum definitions merge into this program's table;
+    /// a shared token must carry an identical definition.
+    pub fn import(
+        &mut self,
+        alias: &str,
+        path: &str,
+        callee: Program,
+    ) -> Result<ImportId, BuildError> {
+        let conflict = |token: &String| BuildError::ImportTypeConflict {
+            alias: alias.to_string(),
+            token: token.clone(),
+        };
+        for (token, class) in &callee.types.classes {
+            if self.types.classes.get(token).is_some_and(|c| c != class) {
+                return Err(conflict(token));
+            }
+        }
+        for (token, enm) in &callee.types.enums {
+            if self.types.enums.get(token).is_some_and(|e| e != enm) {
+                return Err(conflict(token));
+            }
+        }
+        self.add_types(&callee.types);
+        let id = self.imports.push(ImportDef {
+            alias: alias.into(),
+            path: path.into(),
+            hash: callee.meta.program_hash,
+        });
+        self.callees.push(callee);
+        Ok(id)
+    }
+
     /// Declares an extern host tool: bound by name from the runtime
     /// environment at load. The description becomes a `ToolDesc` param.
     pub fn host_tool(&mut self, name: &str, desc: &str, sig: SigId, caps: &[&str]) -> ToolId {
@@ -730,6 +938,26 @@ impl ProgramBuilder {
     /// ports, type mismatches, cap violations, and structural errors are
     /// [`BuildError`]s.
     pub fn main(self, sig: SigId, root: NodeSpec) -> Result<Program, BuildError> {
+        self.main_all(sig, root)
+            .map_err(|errors| errors.into_iter().next().expect("failures carry an error"))
+    }
+
+    /// [`main`](Self::main), reporting every validation error
+    /// ([`Program::validate_all`]) instead of the first.
+    pub(crate) fn main_all(self, sig: SigId, root: NodeSpec) -> Result<Program, Vec<BuildError>> {
+        let mut program = self.lower_program(sig, root).map_err(|err| vec![err])?;
+        // Validate before sealing: the hash preimage is the canonical printed
+        // text, and printing assumes structurally valid arenas.
+        let errors = program.validate_all();
+        if !errors.is_empty() {
+            return Err(errors.into_iter().map(BuildError::Invalid).collect());
+        }
+        program.seal();
+        Ok(program)
+    }
+
+    /// Lowering without validation: the unsealed program `main` checks.
+    fn lower_program(self, sig: SigId, root: NodeSpec) -> Result<Program, BuildError> {
         // Root is always a Seq in v1 ("main").
         let root = match root.kind {
             SpecKind::Seq { .. } => root,
@@ -743,6 +971,9 @@ impl ProgramBuilder {
             node_names: HashMap::new(),
             tools: PrimaryMap::new(),
             single_model: (self.models.len() == 1).then(|| ModelId::new(0)),
+            models: self.models,
+            imports: self.imports,
+            callees: self.callees,
         };
 
         // Tools first: their param slots exist regardless of node references.
@@ -786,12 +1017,14 @@ impl ProgramBuilder {
                 name: self.name.into(),
                 program_hash: 0,
                 lineage: None,
+                comments: Comments::default(),
             },
             nodes: lower.nodes,
             sigs,
             params: lower.params,
-            models: self.models,
+            models: lower.models,
             tools: lower.tools,
+            imports: lower.imports,
             types: self.types,
             syms: lower.syms,
             caps: self.caps,
@@ -800,10 +1033,6 @@ impl ProgramBuilder {
             param_index: HashMap::new(),
         };
         program.rebuild_param_index().map_err(BuildError::Invalid)?;
-        // Validate before sealing: the hash preimage is the canonical printed
-        // text, and printing assumes structurally valid arenas.
-        program.validate().map_err(BuildError::Invalid)?;
-        program.seal();
         Ok(program)
     }
 }
@@ -818,6 +1047,10 @@ struct Lowering {
     node_names: HashMap<String, NodeId>,
     tools: PrimaryMap<ToolId, ToolDef>,
     single_model: Option<ModelId>,
+    /// Declared models, plus the models of spliced callees.
+    models: PrimaryMap<ModelId, ModelDef>,
+    imports: PrimaryMap<ImportId, ImportDef>,
+    callees: Vec<Program>,
 }
 
 impl Lowering {
@@ -836,6 +1069,7 @@ impl Lowering {
                 instruction,
                 demos,
                 binds,
+                memo,
             } => {
                 let sig = if cot {
                     let augmented = sigs[sig].augmented_with(&[cot_reasoning_field()]);
@@ -881,6 +1115,7 @@ impl Lowering {
                     demos,
                     model,
                     binding,
+                    memo,
                 })
             }
             SpecKind::Agent {
@@ -897,6 +1132,7 @@ impl Lowering {
                 budget,
                 context,
                 binds,
+                memo,
             } => {
                 let name_sym = self.syms.intern(&name);
                 let node_id = NodeId::new(self.nodes.len());
@@ -964,6 +1200,7 @@ impl Lowering {
                     },
                     budget,
                     binding,
+                    memo,
                 })
             }
             SpecKind::Hole {
@@ -972,6 +1209,7 @@ impl Lowering {
                 imp,
                 caps,
                 binds,
+                memo,
             } => {
                 let name_sym = self.syms.intern(&name);
                 let node_id = NodeId::new(self.nodes.len());
@@ -994,6 +1232,57 @@ impl Lowering {
                     imp,
                     caps: caps.iter().map(String::as_str).collect(),
                     binding,
+                    memo,
+                })
+            }
+            SpecKind::Transform {
+                name,
+                sig,
+                binds,
+                exprs,
+            } => {
+                let name_sym = self.syms.intern(&name);
+                let binding = self.lower_binds(binds)?;
+                let exprs = exprs
+                    .into_iter()
+                    .map(|(field, src)| (self.syms.intern(&field), src.into_boxed_str()))
+                    .collect();
+                Node::Transform(TransformNode {
+                    name: name_sym,
+                    sig,
+                    binding,
+                    exprs,
+                })
+            }
+            SpecKind::Approve { name, sig, binds } => {
+                let name_sym = self.syms.intern(&name);
+                let binding = self.lower_binds(binds)?;
+                Node::Approve(ApproveNode {
+                    name: name_sym,
+                    sig,
+                    binding,
+                })
+            }
+            SpecKind::Call {
+                name,
+                import,
+                binds,
+            } => {
+                let callee = self.callees.get(import.index()).cloned().ok_or_else(|| {
+                    BuildError::UnknownImport {
+                        at: name.clone(),
+                        import,
+                    }
+                })?;
+                let (body, sig) = self.splice(&name, &callee, sigs);
+                let name_sym = self.syms.intern(&name);
+                let binding = self.lower_binds(binds)?;
+                Node::Call(CallNode {
+                    name: name_sym,
+                    import,
+                    sig,
+                    binding,
+                    body,
                 })
             }
             SpecKind::Seq { body, out } => {
@@ -1018,7 +1307,12 @@ impl Lowering {
                     join,
                 })
             }
-            SpecKind::Route { on, arms, default } => {
+            SpecKind::Route {
+                on,
+                arms,
+                when,
+                default,
+            } => {
                 let on = self.lower_port(on)?;
                 let mut lowered = Vec::with_capacity(arms.len());
                 for (variant, arm) in arms {
@@ -1026,6 +1320,11 @@ impl Lowering {
                     let arm = self.lower(arm, sigs)?;
                     lowered.push((variant, arm));
                 }
+                let mut predicates = Vec::with_capacity(when.len());
+                for (predicate, arm) in when {
+                    let arm = self.lower(arm, sigs)?;
+                    predicates.push((predicate.into_boxed_str(), arm));
+                }
                 let default = match default {
                     Some(node) => Some(self.lower(*node, sigs)?),
                     None => None,
@@ -1033,6 +1332,7 @@ impl Lowering {
                 Node::Route(RouteNode {
                     on,
                     arms: lowered.into_boxed_slice(),
+                    when: predicates.into_boxed_slice(),
                     default,
                 })
             }
@@ -1090,6 +1390,27 @@ impl Lowering {
                     out,
                 })
             }
+            SpecKind::Map {
+                over,
+                item,
+                body,
+                max_parallel,
+                on_error,
+                collect,
+            } => {
+                let over = self.lower_port(over)?;
+                let item = self.syms.intern(&item);
+                let body = self.lower(*body, sigs)?;
+                let collect = self.lower_binds(collect)?;
+                Node::Map(MapNode {
+                    over,
+                    item,
+                    body,
+                    max_parallel,
+                    on_error,
+                    collect,
+                })
+            }
         };
         let id = self.nodes.push(node);
         if let Some(name) = step_name
@@ -1148,3 +1469,303 @@ impl Lowering {
         })
     }
 }
+
+// ---------------------------------------------------------------------------
+// Splicing (`call` nodes)
+// ---------------------------------------------------------------------------
+
+/// Callee id → spliced id, for every entry one `call` copies in.
+struct SpliceMap<'c> {
+    callee: &'c Program,
+    prefix: &'c str,
+    nodes: HashMap<NodeId, NodeId>,
+    sigs: HashMap<SigId, SigId>,
+    params: HashMap<ParamId, ParamId>,
+    models: HashMap<ModelId, ModelId>,
+    tools: HashMap<ToolId, ToolId>,
+    imports: HashMap<ImportId, ImportId>,
+}
+
+/// Numbers the kept keys from `base` in arena order — the order they are
+/// pushed in.
+fn id_map<K: EntityRef + std::hash::Hash + Eq>(
+    keys: impl Iterator<Item = K>,
+    kept: &HashSet<K>,
+    base: usize,
+) -> HashMap<K, K> {
+    keys.filter(|key| kept.contains(key))
+        .enumerate()
+        .map(|(i, key)| (key, K::new(base + i)))
+        .collect()
+}
+
+impl Lowering {
+    /// Copies everything `callee` reaches from its root into this program,
+    /// every name prefixed `<prefix>.`, and returns the spliced root and
+    /// program signature. Only reachable entries come along, so everything a
+    /// `call` spliced in is owned by it (the printer relies on this). The
+    /// callee is a validated program: every id it holds remaps.
+    fn splice(
+        &mut self,
+        prefix: &str,
+        callee: &Program,
+        sigs: &mut PrimaryMap<SigId, SignatureDef>,
+    ) -> (NodeId, SigId) {
+        let mut nodes = HashSet::new();
+        let mut stack = vec![callee.root];
+        while let Some(id) = stack.pop() {
+            if nodes.insert(id) {
+                stack.extend(crate::ir::edit::structural_children(&callee.nodes[id]));
+            }
+        }
+        let mut used_sigs = HashSet::from([callee.sig]);
+        let mut tools = HashSet::new();
+        let mut imports = HashSet::new();
+        for &id in &nodes {
+            match &callee.nodes[id] {
+                Node::Predict(n) => {
+                    used_sigs.insert(n.sig);
+                }
+                Node::AgentLoop(n) => {
+                    used_sigs.insert(n.sig);
+                    tools.extend(n.tools.iter().copied());
+                }
+                Node::Call(n) => {
+                    used_sigs.insert(n.sig);
+                    imports.insert(n.import);
+                }
+                Node::Transform(n) => {
+                    used_sigs.insert(n.sig);
+                }
+                Node::Approve(n) => {
+                    used_sigs.insert(n.sig);
+                }
+                Node::Hole(n) => {
+                    used_sigs.insert(n.sig);
+                }
+                _ => {}
+            }
+        }
+        used_sigs.extend(tools.iter().map(|&t| callee.tools[t].sig));
+        let params: HashSet<ParamId> = callee
+            .params
+            .iter()
+            .filter(|(_, slot)| match slot.owner {
+                ParamOwner::Node(node) => nodes.contains(&node),
+                ParamOwner::Tool(tool) => tools.contains(&tool),
+            })
+            .map(|(id, _)| id)
+            .collect();
+        let models: HashSet<ModelId> = params
+            .iter()
+            .filter_map(|&id| callee.params[id].default.model())
+            .collect();
+
+        let map = SpliceMap {
+            callee,
+            prefix,
+            nodes: id_map(callee.nodes.keys(), &nodes, self.nodes.len()),
+            sigs: id_map(callee.sigs.keys(), &used_sigs, sigs.len()),
+            params: id_map(callee.params.keys(), &params, self.params.len()),
+            models: id_map(callee.models.keys(), &models, self.models.len()),
+            tools: id_map(callee.tools.keys(), &tools, self.tools.len()),
+            imports: id_map(callee.imports.keys(), &imports, self.imports.len()),
+        };
+
+        for (id, def) in callee.models.iter() {
+            if map.models.contains_key(&id) {
+                self.models.push(ModelDef {
+                    name: format!("{prefix}.{}", def.name).into(),
+                    config: def.config.clone(),
+                });
+            }
+        }
+        for (id, def) in callee.sigs.iter() {
+            if map.sigs.contains_key(&id) {
+                sigs.push(def.clone());
+            }
+        }
+        for (id, tool) in callee.tools.iter() {
+            if map.tools.contains_key(&id) {
+                let name = self.prefixed(&map, tool.name);
+                self.tools.push(ToolDef {
+                    name,
+                    desc: map.params[&tool.desc],
+                    sig: map.sigs[&tool.sig],
+                    caps: tool.caps.clone(),
+                    kind: match tool.kind {
+                        ToolKind::Host => ToolKind::Host,
+                        ToolKind::Sandboxed { code } => ToolKind::Sandboxed {
+                            code: map.params[&code],
+                        },
+                    },
+                });
+            }
+        }
+        for (id, def) in callee.imports.iter() {
+            if map.imports.contains_key(&id) {
+                self.imports.push(ImportDef {
+                    alias: format!("{prefix}.{}", def.alias).into(),
+                    ..def.clone()
+                });
+            }
+        }
+        for (id, slot) in callee.params.iter() {
+            if !map.params.contains_key(&id) {
+                continue;
+            }
+            let path = match slot.path.strip_prefix("tool.") {
+                Some(rest) => format!("tool.{prefix}.{rest}"),
+                None => format!("{prefix}.{}", slot.path),
+            };
+            let owner = match slot.owner {
+                ParamOwner::Node(node) => ParamOwner::Node(map.nodes[&node]),
+                ParamOwner::Tool(tool) => ParamOwner::Tool(map.tools[&tool]),
+            };
+            let default = match &slot.default {
+                ParamValue::ToolSet { tools } => ParamValue::ToolSet {
+                    tools: tools.iter().map(|t| map.tools[t]).collect(),
+                },
+                other => other
+                    .map_model(|model| Some(map.models[&model]))
+                    .expect("referenced models are spliced"),
+            };
+            self.params.push(ParamSlot {
+                path: path.into(),
+                owner,
+                kind: slot.kind,
+                default,
+            });
+        }
+        for (id, node) in callee.nodes.iter() {
+            if map.nodes.contains_key(&id) {
+                let node = self.splice_node(&map, node);
+                self.nodes.push(node);
+            }
+        }
+        (map.nodes[&callee.root], map.sigs[&callee.sig])
+    }
+
+    fn splice_node(&mut self, map: &SpliceMap<'_>, node: &Node) -> Node {
+        let mut node = node.clone();
+        match &mut node {
+            Node::Predict(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.sig = map.sigs[&n.sig];
+                n.instruction = map.params[&n.instruction];
+                n.demos = map.params[&n.demos];
+                n.model = map.params[&n.model];
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::AgentLoop(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.sig = map.sigs[&n.sig];
+                n.instruction = map.params[&n.instruction];
+                n.demos = map.params[&n.demos];
+                n.model = map.params[&n.model];
+                n.tool_set = map.params[&n.tool_set];
+                n.context_policy = map.params[&n.context_policy];
+                n.tools.iter_mut().for_each(|t| *t = map.tools[t]);
+                n.stop.stop_tools.iter_mut().for_each(|t| *t = map.tools[t]);
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::Call(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.import = map.imports[&n.import];
+                n.sig = map.sigs[&n.sig];
+                n.body = map.nodes[&n.body];
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::Transform(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.sig = map.sigs[&n.sig];
+                for (field, _) in n.exprs.iter_mut() {
+                    *field = self.resym(map, *field);
+                }
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::Approve(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.sig = map.sigs[&n.sig];
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::Hole(n) => {
+                n.name = self.prefixed(map, n.name);
+                n.sig = map.sigs[&n.sig];
+                if let HoleImpl::Sandboxed { code } = &mut n.imp {
+                    *code = map.params[code];
+                }
+                self.splice_binds(map, &mut n.binding);
+            }
+            Node::Seq(n) => {
+                n.body.iter_mut().for_each(|c| *c = map.nodes[c]);
+                self.splice_binds(map, &mut n.out);
+            }
+            Node::ForkJoin(n) => {
+                n.branches.iter_mut().for_each(|c| *c = map.nodes[c]);
+                self.splice_binds(map, &mut n.join);
+            }
+            Node::Route(n) => {
+                self.splice_port(map, &mut n.on);
+                for (variant, arm) in n.arms.iter_mut() {
+                    *variant = self.resym(map, *variant);
+                    *arm = map.nodes[arm];
+                }
+                n.when.iter_mut().for_each(|(_, arm)| *arm = map.nodes[arm]);
+                if let Some(default) = &mut n.default {
+                    *default = map.nodes[default];
+                }
+            }
+            Node::Retry(n) => n.child = map.nodes[&n.child],
+            Node::Refine(n) => {
+                n.child = map.nodes[&n.child];
+                n.judge = map.nodes[&n.judge];
+                n.feedback_field = self.resym(map, n.feedback_field);
+            }
+            Node::Loop(n) => {
+                n.body = map.nodes[&n.body];
+                if let Some(port) = &mut n.while_ {
+                    self.splice_port(map, port);
+                }
+                self.splice_binds(map, &mut n.carry);
+                self.splice_binds(map, &mut n.out);
+            }
+            Node::Map(n) => {
+                self.splice_port(map, &mut n.over);
+                n.item = self.resym(map, n.item);
+                n.body = map.nodes[&n.body];
+                self.splice_binds(map, &mut n.collect);
+            }
+        }
+        node
+    }
+
+    /// A callee string re-interned here unchanged (field names, variants).
+    fn resym(&mut self, map: &SpliceMap<'_>, sym: Sym) -> Sym {
+        self.syms.intern(map.callee.syms.get(sym))
+    }
+
+    /// A callee leaf or tool name, namespaced under the call.
+    fn prefixed(&mut self, map: &SpliceMap<'_>, sym: Sym) -> Sym {
+        let name = format!("{}.{}", map.prefix, map.callee.syms.get(sym));
+        self.syms.intern(&name)
+    }
+
+    fn splice_binds(&mut self, map: &SpliceMap<'_>, binds: &mut [Binding]) {
+        for b in binds {
+            b.dst = self.resym(map, b.dst);
+            self.splice_port(map, &mut b.src);
+        }
+    }
+
+    fn splice_port(&mut self, map: &SpliceMap<'_>, port: &mut PortRef) {
+        match port {
+            PortRef::Input(field) | PortRef::Carried(field) => *field = self.resym(map, *field),
+            PortRef::Out { node, field } => {
+                *node = map.nodes[node];
+                *field = self.resym(map, *field);
+            }
+            PortRef::Lit(_) => {}
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Run checkpoints: a serializable execution-state artifact for crash-safe
+//! resumption of long programs.
+//!
+//! A [`ToolSuspension`](crate::ir::ToolSuspension) or
+//! [`RunSuspension`](crate::ir::RunSuspension) holds its resumption state in
+//! process memory; a [`RunCheckpoint`] is plain data. A checkpointed run
+//! ([`RunOptions::with_checkpoints`](crate::ir::RunOptions::with_checkpoints))
+//! emits one after every completed leaf, and
+//! [`Interpreter::resume_from`](crate::ir::Interpreter::resume_from) continues
+//! it — in this process or another — by re-executing the program from the
+//! root and *serving* every leaf the checkpoint already completed instead of
+//! calling it again.
+//!
+//! Leaves are keyed by (leaf name, hash of the resolved input), so a served
+//! output is exactly the one the leaf produced for the same input. Containers
+//! (`Seq`, `Route`, loops, maps, ...) and `Transform` nodes are deterministic
+//! and simply re-run over the served leaves: loop iterations, carried values,
+//! and route choices rebuild themselves without a separate record.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ir::interp::BudgetMeter;
+use crate::trace::{JsonMap, Trace, TraceSink};
+
+/// Bumped on any incompatible change to the [`RunCheckpoint`] layout.
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// Everything a new process needs to continue a checkpointed run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub version: u32,
+    /// [`ProgramMeta::program_hash`](crate::ir::ProgramMeta) of the program
+    /// that ran; resuming against any other program is refused.
+    pub program_hash: u64,
+    /// [`Overlay::hash`](crate::ir::Overlay::hash) of the run's overlay, if
+    /// any; resuming under a different overlay is refused.
+    pub overlay_hash: Option<u64>,
+    /// The run's program input.
+    pub input: JsonMap,
+    /// Completed leaves in completion order. Repeated evaluations of one leaf
+    /// (loop iterations, map elements, retries) each get an entry.
+    pub leaves: Vec<CompletedLeaf>,
+    /// Run-meter spend when the checkpoint was taken; a resumed run starts
+    /// its meter here.
+    pub meter: MeterReading,
+    /// The capture scope's trace so far, when the process that took the
+    /// checkpoint was capturing. Spans recorded inside a `Map` element join
+    /// it when the map finishes. A resumed run appends its own spans to the
+    /// trace it was resumed with, so the trace covers every process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Trace>,
+}
+
+/// One completed leaf evaluation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompletedLeaf {
+    /// Program-unique leaf name.
+    pub name: String,
+    /// [`leaf_input_hash`] of the leaf's resolved input.
+    pub input_hash: u64,
+    pub output: JsonMap,
+}
+
+/// Run-meter readings at checkpoint time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeterReading {
+    pub lm_calls: u32,
+    pub tokens: u64,
+    /// Wall time spent across every process the run has lived in.
+    pub elapsed_ms: u64,
+}
+
+/// The canonical input hash (sorted keys) a completed leaf is keyed by.
+pub fn leaf_input_hash(input: &JsonMap) -> u64 {
+    use std::hash::Hasher as _;
+    let mut hasher = crate::utils::hash::StableHasher::new();
+    let mut fields: Vec<(&String, &Value)> = input.iter().collect();
+    fields.sort_by_key(|(key, _)| *key);
+    for (key, value) in fields {
+        hasher.write(key.as_bytes());
+        hasher.write(value.to_string().as_bytes());
+    }
+    hasher.finish()
+}
+
+pub(crate) type CheckpointFn = Box<dyn Fn(&RunCheckpoint) + Send + Sync>;
+
+/// The live side of a checkpointed run: the growing checkpoint, the leaves
+/// a resumed run may still serve, and the caller's callback — absent when
+/// the log only backs a [`PartialRun`](crate::ir::PartialRun).
+pub(crate) struct CheckpointLog {
+    state: Mutex<RunCheckpoint>,
+    reuse: Mutex<HashMap<(String, u64), VecDeque<JsonMap>>>,
+    on_checkpoint: Option<CheckpointFn>,
+    /// The run's root trace sink, captured at start so leaves inside map
+    /// lanes still snapshot the whole trace.
+    trace: Option<TraceSink>,
+    /// The trace the run was resumed with, which this process's spans
+    /// extend.
+    prior_trace: Option<Trace>,
+    /// Wall time the run spent before this process picked it up.
+    elapsed_before: u64,
+    started: Instant,
+}
+
+impl CheckpointLog {
+    /// Starts a fresh checkpoint for a run that has completed nothing yet.
+    pub(crate) fn fresh(
+        program_hash: u64,
+        overlay_hash: Option<u64>,
+        input: JsonMap,
+        on_checkpoint: Option<CheckpointFn>,
+    ) -> Self {
+        Self::resumed(
+            RunCheckpoint {
+                version: CHECKPOINT_VERSION,
+                program_hash,
+                overlay_hash,
+                input,
+                leaves: Vec::new(),
+                meter: MeterReading::default(),
+                trace: None,
+            },
+            on_checkpoint,
+        )
+    }
+
+    /// Continues `checkpoint`: its leaves are served once each, in order,
+    /// and carried into every checkpoint this run emits.
+    pub(crate) fn resumed(checkpoint: RunCheckpoint, on_checkpoint: Option<CheckpointFn>) -> Self {
+        let mut reuse: HashMap<(String, u64), VecDeque<JsonMap>> = HashMap::new();
+        for leaf in &checkpoint.leaves {
+            reuse
+                .entry((leaf.name.clone(), leaf.input_hash))
+                .or_default()
+                .push_back(leaf.output.clone());
+        }
+        Self {
+            elapsed_before: checkpoint.meter.elapsed_ms,
+            prior_trace: checkpoint.trace.clone(),
+            state: Mutex::new(checkpoint),
+            reuse: Mutex::new(reuse),
+            on_checkpoint,
+            trace: crate::trace::capture::current_sink(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Spend recorded before this process picked the run up.
+    pub(crate) fn spent(&self) -> MeterReading {
+        self.state.lock().unwrap().meter
+    }
+
+    /// The recorded output for the next evaluation of `name` on this input,
+    /// if the checkpoint completed one.
+    pub(crate) fn reuse(&self, name: &str, input_hash: u64) -> Option<JsonMap> {
+        let mut reuse = self.reuse.lock().unwrap();
+        let key = (name.to_string(), input_hash);
+        let outputs = reuse.get_mut(&key)?;
+        let output = outputs.pop_front();
+        if outputs.is_empty() {
+            reuse.remove(&key);
+        }
+        output
+    }
+
+    /// Appends a completed leaf and hands the caller the new checkpoint.
+    pub(crate) fn record(
+        &self,
+        name: &str,
+        input_hash: u64,
+        output: &JsonMap,
+        meter: &BudgetMeter,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.leaves.push(CompletedLeaf {
+            name: name.to_string(),
+            input_hash,
+            output: output.clone(),
+        });
+        if let Some(on_checkpoint) = &self.on_checkpoint {
+            self.stamp(&mut state, meter);
+            on_checkpoint(&state);
+        }
+    }
+
+    /// The checkpoint as the run ends: every completed leaf, the meter's
+    /// final readings, and the trace so far.
+    pub(crate) fn conclude(&self, meter: &BudgetMeter) -> RunCheckpoint {
+        let mut state = self.state.lock().unwrap();
+        self.stamp(&mut state, meter);
+        state.clone()
+    }
+
+    fn stamp(&self, state: &mut RunCheckpoint, meter: &BudgetMeter) {
+        state.meter = MeterReading {
+            lm_calls: meter.lm_calls(),
+            tokens: meter.tokens(),
+            elapsed_ms: self.elapsed_before + self.started.elapsed().as_millis() as u64,
+        };
+        if let Some(sink) = &self.trace {
+            let current = sink.snapshot();
+            state.trace = Some(match &self.prior_trace {
+                // Resumed under the scope that recorded the prior spans.
+                Some(prior) if prior.meta.trace_id == current.meta.trace_id => current,
+                Some(prior) => prior.followed_by(&current),
+                None => current,
+            });
+        }
+    }
+}
@@ -0,0 +1,863 @@
+//! Static cost and latency bounds: [`Program::cost`], for capacity planning
+//! before a program (or an optimized child) is promoted.
+//!
+//! Every repetition in the IR is bounded — `max_turns`, `max_iters`,
+//! `max_attempts`, `max_rounds` — so the worst case of one run is computable
+//! without running anything. The analysis walks the graph once and reports,
+//! per node and for the whole program, two [`Estimate`]s:
+//!
+//! - **worst** — every retry fails, every refine round and loop iteration
+//!   runs, every agent uses all its turns (capped by its [`NodeBudget`]:
+//!   calls stop at `max_lm_calls`, and once spent tokens reach `max_tokens`
+//!   no further call is reserved), then finalizes if `on_exhausted finalize`;
+//!   every completion fills the model's `max_tokens`; a route takes its most
+//!   expensive arm.
+//! - **expected** — the first attempt and the first refine round succeed,
+//!   `while` loops stop halfway, agents answer after
+//!   [`CostAssumptions::agent_turns`] turns, completions fill
+//!   [`CostAssumptions::output_fill`] of `max_tokens`, and a route's arms are
+//!   equally likely.
+//!
+//! Prompt sizes come from the rendered prompt of each leaf — system message
+//! with the (overlay-resolved) instruction, demos, the live turn with
+//! [`CostAssumptions::input_field_tokens`] per input, and for agents the tool
+//! definitions — at four bytes per token. Agent prompts grow by one
+//! completion plus one tool result per turn, up to the context policy's
+//! history window; its summarizing calls (compaction once the prompt reaches
+//! `at_tokens`, and oversized tool results) count as calls of the
+//! summarizing model, against the agent's budget. Dollar cost needs a [`Pricing`] table; latency is
+//! [`CostAssumptions::call_ms`] per sequential call, plus retry backoff,
+//! with fork branches and `map` elements (up to `max_parallel`) overlapping.
+//!
+//! What the graph cannot bound is flagged rather than guessed silently
+//! ([`CostFlag`]): agents without a budget, `map` list lengths, host holes,
+//! and models missing from the price table.
+//!
+//! [`NodeBudget`]: crate::ir::graph::NodeBudget
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ir::builder::cot_reasoning_field;
+use crate::ir::graph::{
+    AgentLoopNode, BudgetPolicy, HoleImpl, ModelDef, ModelId, Node, NodeId, Program,
+};
+use crate::ir::interp::{
+    COMPACTION_INSTRUCTION, TOOL_RESULT_INSTRUCTION, input_schema_of, render_prompt,
+};
+use crate::ir::params::{ContextPolicy, Overlay, ParamId, ParamValue};
+use crate::ir::sig::SignatureDef;
+use crate::trace::JsonMap;
+
+/// USD per million tokens for one model.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Prices by model: the declared name (`fast`) or the provider model string
+/// (`openai:gpt-4o-mini`), declared name first. As JSON:
+/// `{"openai:gpt-4o-mini": {"input": 0.15, "output": 0.6}, ...}`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pricing(pub BTreeMap<String, ModelPrice>);
+
+impl Pricing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, model: &str, input: f64, output: f64) -> Self {
+        self.0
+            .insert(model.to_string(), ModelPrice { input, output });
+        self
+    }
+
+    /// The price of `model`, by declared name, then provider model string.
+    pub fn get(&self, model: &ModelDef) -> Option<&ModelPrice> {
+        self.0
+            .get(&*model.name)
+            .or_else(|| self.0.get(&model.config.model))
+    }
+}
+
+/// What the graph does not say, assumed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CostAssumptions {
+    /// Elements a `map` runs over; list lengths are not static.
+    pub map_items: u32,
+    /// Turns an agent takes before answering (expected case), capped by its
+    /// `max_turns` and budget.
+    pub agent_turns: u32,
+    /// Fraction of `max_tokens` a completion uses (expected case).
+    pub output_fill: f64,
+    /// Tokens per input value in the live turn.
+    pub input_field_tokens: u64,
+    /// Tokens per tool result fed back to an agent, before the context
+    /// policy's clipping.
+    pub tool_result_tokens: u64,
+    /// Wall time of one LM call, in milliseconds.
+    pub call_ms: u64,
+}
+
+impl Default for CostAssumptions {
+    fn default() -> Self {
+        Self {
+            map_items: 8,
+            agent_turns: 3,
+            output_fill: 0.5,
+            input_field_tokens: 200,
+            tool_result_tokens: 500,
+            call_ms: 2_000,
+        }
+    }
+}
+
+/// Inputs to [`Program::cost_with`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CostOptions<'a> {
+    /// Resolve models, instructions, demos, tool sets, and context policies
+    /// through this overlay.
+    pub overlay: Option<&'a Overlay>,
+    /// Dollar prices; without it every `usd` is 0.
+    pub pricing: Option<&'a Pricing>,
+    pub assumptions: CostAssumptions,
+}
+
+/// Spend of one evaluation (of a node, or of the whole program).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Estimate {
+    pub lm_calls: f64,
+    pub prompt_tokens: f64,
+    pub completion_tokens: f64,
+    pub usd: f64,
+    pub latency_ms: f64,
+}
+
+impl Estimate {
+    pub fn tokens(&self) -> f64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// `self`, then `next`.
+    fn then(self, next: Estimate) -> Estimate {
+        Estimate {
+            lm_calls: self.lm_calls + next.lm_calls,
+            prompt_tokens: self.prompt_tokens + next.prompt_tokens,
+            completion_tokens: self.completion_tokens + next.completion_tokens,
+            usd: self.usd + next.usd,
+            latency_ms: self.latency_ms + next.latency_ms,
+        }
+    }
+
+    /// `self` and `other` at the same time.
+    fn beside(self, other: Estimate) -> Estimate {
+        Estimate {
+            latency_ms: self.latency_ms.max(other.latency_ms),
+            ..self.then(other)
+        }
+    }
+
+    /// The larger of each component.
+    fn max(self, other: Estimate) -> Estimate {
+        Estimate {
+            lm_calls: self.lm_calls.max(other.lm_calls),
+            prompt_tokens: self.prompt_tokens.max(other.prompt_tokens),
+            completion_tokens: self.completion_tokens.max(other.completion_tokens),
+            usd: self.usd.max(other.usd),
+            latency_ms: self.latency_ms.max(other.latency_ms),
+        }
+    }
+
+    fn times(self, k: f64) -> Estimate {
+        Estimate {
+            lm_calls: self.lm_calls * k,
+            prompt_tokens: self.prompt_tokens * k,
+            completion_tokens: self.completion_tokens * k,
+            usd: self.usd * k,
+            latency_ms: self.latency_ms * k,
+        }
+    }
+}
+
+/// Worst-case and expected [`Estimate`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Cost {
+    pub worst: Estimate,
+    pub expected: Estimate,
+}
+
+impl Cost {
+    fn then(self, next: Cost) -> Cost {
+        Cost {
+            worst: self.worst.then(next.worst),
+            expected: self.expected.then(next.expected),
+        }
+    }
+
+    fn beside(self, other: Cost) -> Cost {
+        Cost {
+            worst: self.worst.beside(other.worst),
+            expected: self.expected.beside(other.expected),
+        }
+    }
+}
+
+/// How many times a node is evaluated in one program run.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Runs {
+    pub worst: f64,
+    pub expected: f64,
+}
+
+/// One node's line in a [`CostReport`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NodeCost {
+    /// Leaf name, or container position (`main`, `main.1`, `main.1.body`).
+    pub node: String,
+    /// Kind, signature, and model: `cot Draft @deep`, `retry ×3`.
+    pub what: String,
+    /// A step rather than a container.
+    pub leaf: bool,
+    /// Evaluations per program run.
+    pub runs: Runs,
+    /// One evaluation, children included.
+    pub each: Cost,
+}
+
+impl NodeCost {
+    /// This node's share of one program run: `each` × `runs`.
+    pub fn per_run(&self) -> Cost {
+        Cost {
+            worst: self.each.worst.times(self.runs.worst),
+            expected: self.each.expected.times(self.runs.expected),
+        }
+    }
+}
+
+/// Something the analysis could not bound from the graph alone.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "flag", rename_all = "snake_case")]
+pub enum CostFlag {
+    /// An agent with neither `max_lm_calls` nor `max_tokens`: only
+    /// `max_turns` bounds it.
+    Unbudgeted { node: String },
+    /// A `map` over a list whose length is only known at run time;
+    /// [`CostAssumptions::map_items`] was assumed.
+    MapLength { node: String, assumed: u32 },
+    /// A host hole: whatever it spends happens outside the program.
+    Opaque { node: String },
+    /// No price for this model; its tokens count $0.
+    Unpriced { model: String },
+}
+
+impl fmt::Display for CostFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostFlag::Unbudgeted { node } => write!(
+                f,
+                "{node}: agent without a budget (only max_turns bounds it)"
+            ),
+            CostFlag::MapLength { node, assumed } => {
+                write!(f, "{node}: map length not static (assumed {assumed})")
+            }
+            CostFlag::Opaque { node } => {
+                write!(f, "{node}: extern hole; its spend is not counted")
+            }
+            CostFlag::Unpriced { model } => write!(f, "model {model}: no price; counted as $0"),
+        }
+    }
+}
+
+/// The result of [`Program::cost`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CostReport {
+    pub program: String,
+    /// One program run.
+    pub total: Cost,
+    /// Every node, in program order.
+    pub nodes: Vec<NodeCost>,
+    pub flags: Vec<CostFlag>,
+    pub assumptions: CostAssumptions,
+    /// A price table was given.
+    pub priced: bool,
+}
+
+impl fmt::Display for CostReport {
+    /// A summary of one run, then the steps that call an LM, then flags.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Cost { worst, expected } = self.total;
+        writeln!(f, "program {}: one run, worst / expected", self.program)?;
+        writeln!(
+            f,
+            "  lm calls  {} / {}",
+            num(worst.lm_calls),
+            num(expected.lm_calls)
+        )?;
+        writeln!(
+            f,
+            "  tokens    {} / {}",
+            num(worst.tokens()),
+            num(expected.tokens())
+        )?;
+        if self.priced {
+            writeln!(f, "  cost      ${:.4} / ${:.4}", worst.usd, expected.usd)?;
+        }
+        writeln!(
+            f,
+            "  latency   {:.1} s / {:.1} s",
+            worst.latency_ms / 1000.0,
+            expected.latency_ms / 1000.0
+        )?;
+
+        let calling: Vec<&NodeCost> = self
+            .nodes
+            .iter()
+            .filter(|node| node.leaf && node.each.worst.lm_calls > 0.0)
+            .collect();
+        if !calling.is_empty() {
+            writeln!(f, "\nsteps (per run, worst / expected):")?;
+            let width = calling
+                .iter()
+                .map(|node| node.node.len())
+                .max()
+                .unwrap_or(0);
+            for node in calling {
+                let Cost { worst, expected } = node.per_run();
+                write!(
+                    f,
+                    "  {:<width$}  {}: {} / {} calls, {} / {} tokens",
+                    node.node,
+                    node.what,
+                    num(worst.lm_calls),
+                    num(expected.lm_calls),
+                    num(worst.tokens()),
+                    num(expected.tokens())
+                )?;
+                if self.priced {
+                    write!(f, ", ${:.4} / ${:.4}", worst.usd, expected.usd)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        if !self.flags.is_empty() {
+            writeln!(f, "\nflags:")?;
+            for flag in &self.flags {
+                writeln!(f, "  {flag}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whole numbers plainly, fractions (expected counts) to one place.
+fn num(x: f64) -> String {
+    if x.fract() == 0.0 {
+        format!("{x:.0}")
+    } else {
+        format!("{x:.1}")
+    }
+}
+
+impl Program {
+    /// Worst-case and expected LM calls, tokens, cost, and latency of one
+    /// run, under the default [`CostAssumptions`] and no prices.
+    pub fn cost(&self) -> CostReport {
+        self.cost_with(CostOptions::default())
+    }
+
+    /// [`cost`](Program::cost) with an overlay, prices, and assumptions.
+    pub fn cost_with(&self, options: CostOptions<'_>) -> CostReport {
+        let mut walker = Walker {
+            p: self,
+            options,
+            nodes: Vec::new(),
+            flags: Vec::new(),
+        };
+        let total = walker.node(
+            self.root,
+            "main".to_string(),
+            Runs {
+                worst: 1.0,
+                expected: 1.0,
+            },
+        );
+        CostReport {
+            program: self.meta.name.to_string(),
+            total,
+            nodes: walker.nodes,
+            flags: walker.flags,
+            assumptions: options.assumptions,
+            priced: options.pricing.is_some(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// The walk
+// ---------------------------------------------------------------------------
+
+/// Four bytes per token: the usual rule of thumb for English prose and JSON.
+fn tokens(text: &str) -> f64 {
+    text.len().div_ceil(4) as f64
+}
+
+/// One LM call.
+fn call(price: Option<ModelPrice>, call_ms: u64, prompt: f64, completion: f64) -> Estimate {
+    Estimate {
+        lm_calls: 1.0,
+        prompt_tokens: prompt,
+        completion_tokens: completion,
+        usd: price.map_or(0.0, |price| {
+            (prompt * price.input + completion * price.output) / 1e6
+        }),
+        latency_ms: call_ms as f64,
+    }
+}
+
+struct Walker<'a> {
+    p: &'a Program,
+    options: CostOptions<'a>,
+    nodes: Vec<NodeCost>,
+    flags: Vec<CostFlag>,
+}
+
+impl Walker<'_> {
+    fn resolve(&self, id: ParamId) -> &ParamValue {
+        match self.options.overlay {
+            Some(overlay) => overlay.resolve(self.p, id),
+            None => &self.p.params[id].default,
+        }
+    }
+
+    fn flag(&mut self, flag: CostFlag) {
+        if !self.flags.contains(&flag) {
+            self.flags.push(flag);
+        }
+    }
+
+    /// Records `id` (pre-order) and returns one evaluation's cost.
+    fn node(&mut self, id: NodeId, path: String, runs: Runs) -> Cost {
+        let p = self.p;
+        let at = p
+            .leaf_name(id)
+            .map(str::to_string)
+            .unwrap_or_else(|| path.clone());
+        let slot = self.nodes.len();
+        self.nodes.push(NodeCost {
+            node: at.clone(),
+            what: String::new(),
+            leaf: p.nodes[id].leaf_name().is_some(),
+            runs,
+            each: Cost::default(),
+        });
+
+        let (what, each) = match &p.nodes[id] {
+            Node::Predict(n) => {
+                let def = &p.sigs[n.sig];
+                let kind = if def.outputs.first() == Some(&cot_reasoning_field()) {
+                    "cot"
+                } else {
+                    "predict"
+                };
+                let (model, price, prompt, max) =
+                    self.leaf_call(def, n.instruction, n.demos, n.model, None, 0.0);
+                let call_ms = self.options.assumptions.call_ms;
+                let fill = self.options.assumptions.output_fill;
+                (
+                    format!("{kind} {} @{model}", def.name),
+                    Cost {
+                        worst: call(price, call_ms, prompt, max),
+                        expected: call(price, call_ms, prompt, (max * fill).round()),
+                    },
+                )
+            }
+            Node::AgentLoop(n) => self.agent(&at, n),
+            Node::Transform(n) => (format!("transform {}", p.sigs[n.sig].name), Cost::default()),
+            Node::Approve(n) => (format!("approve {}", p.sigs[n.sig].name), Cost::default()),
+            Node::Hole(n) => {
+                if matches!(n.imp, HoleImpl::Host { .. }) {
+                    self.flag(CostFlag::Opaque { node: at.clone() });
+                }
+                (format!("hole {}", p.sigs[n.sig].name), Cost::default())
+            }
+            Node::Call(n) => {
+                let body = self.node(n.body, format!("{at}.main"), runs);
+                (format!("call {}", p.imports[n.import].alias), body)
+            }
+            Node::Seq(n) => {
+                let mut total = Cost::default();
+                for (i, &child) in n.body.iter().enumerate() {
+                    total = total.then(self.node(child, format!("{path}.{i}"), runs));
+                }
+                ("seq".to_string(), total)
+            }
+            Node::ForkJoin(n) => {
+                let mut total = Cost::default();
+                for (i, &branch) in n.branches.iter().enumerate() {
+                    total = total.beside(self.node(branch, format!("{path}.{i}"), runs));
+                }
+                ("fork".to_string(), total)
+            }
+            Node::Route(n) => {
+                let arms: Vec<(NodeId, String)> = n
+                    .arms
+                    .iter()
+                    .map(|(variant, arm)| (*arm, format!("{path}[{}]", p.syms.get(*variant))))
+                    .chain(
+                        n.when
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (_, arm))| (*arm, format!("{path}[when {i}]"))),
+                    )
+                    .chain(n.default.map(|arm| (arm, format!("{path}[else]"))))
+                    .collect();
+                // Any arm may be the one taken; each is equally likely.
+                let share = 1.0 / arms.len().max(1) as f64;
+                let arm_runs = Runs {
+                    worst: runs.worst,
+                    expected: runs.expected * share,
+                };
+                let mut total = Cost::default();
+                for (arm, arm_path) in arms {
+                    let cost = self.node(arm, arm_path, arm_runs);
+                    total.worst = total.worst.max(cost.worst);
+                    total.expected = total.expected.then(cost.expected.times(share));
+                }
+                ("route".to_string(), total)
+            }
+            Node::Retry(n) => {
+                let attempts = n.max_attempts.get() as f64;
+                let child = self.node(
+                    n.child,
+                    format!("{path}.body"),
+                    Runs {
+                        worst: runs.worst * attempts,
+                        expected: runs.expected,
+                    },
+                );
+                let mut worst = child.worst.times(attempts);
+                worst.latency_ms += (attempts - 1.0) * n.backoff_ms as f64;
+                (
+                    format!("retry ×{}", n.max_attempts),
+                    Cost {
+                        worst,
+                        expected: child.expected,
+                    },
+                )
+            }
+            Node::Refine(n) => {
+                let rounds = n.max_rounds.get() as f64;
+                let round_runs = Runs {
+                    worst: runs.worst * rounds,
+                    expected: runs.expected,
+                };
+                let child = self.node(n.child, format!("{path}.body"), round_runs);
+                let judge = self.node(n.judge, format!("{path}.judge"), round_runs);
+                let round = child.then(judge);
+                (
+                    format!("refine ×{}", n.max_rounds),
+                    Cost {
+                        worst: round.worst.times(rounds),
+                        expected: round.expected,
+                    },
+                )
+            }
+            Node::Loop(n) => {
+                let iters = n.max_iters.get() as f64;
+                // A `while` loop is expected to stop halfway; without one
+                // every iteration runs.
+                let expected_iters = match n.while_ {
+                    Some(_) => (iters / 2.0).ceil(),
+                    None => iters,
+                };
+                let body = self.node(
+                    n.body,
+                    format!("{path}.body"),
+                    Runs {
+                        worst: runs.worst * iters,
+                        expected: runs.expected * expected_iters,
+                    },
+                );
+                (
+                    format!("loop ×{}", n.max_iters),
+                    Cost {
+                        worst: body.worst.times(iters),
+                        expected: body.expected.times(expected_iters),
+                    },
+                )
+            }
+            Node::Map(n) => {
+                self.flag(CostFlag::MapLength {
+                    node: at.clone(),
+                    assumed: self.options.assumptions.map_items,
+                });
+                let items = self.options.assumptions.map_items as f64;
+                let waves = (items / n.max_parallel.get() as f64).ceil();
+                let body = self.node(
+                    n.body,
+                    format!("{path}.body"),
+                    Runs {
+                        worst: runs.worst * items,
+                        expected: runs.expected * items,
+                    },
+                );
+                let spread = |e: Estimate| Estimate {
+                    latency_ms: e.latency_ms * waves,
+                    ..e.times(items)
+                };
+                (
+                    format!("map ∥{}", n.max_parallel),
+                    Cost {
+                        worst: spread(body.worst),
+                        expected: spread(body.expected),
+                    },
+                )
+            }
+        };
+        self.nodes[slot].what = what;
+        self.nodes[slot].each = each;
+        each
+    }
+
+    /// A leaf's model and one call's rendered prompt: `(model name, price,
+    /// prompt tokens, max_tokens)`. `extra` is prompt tokens on top of the
+    /// rendered prompt (tool definitions).
+    fn leaf_call(
+        &mut self,
+        def: &SignatureDef,
+        instruction: ParamId,
+        demos: ParamId,
+        model: ParamId,
+        playbook: Option<&str>,
+        extra: f64,
+    ) -> (String, Option<ModelPrice>, f64, f64) {
+        let prompt = self.prompt_tokens(def, instruction, demos, playbook) + extra;
+        let model = &self.p.models[self.model(model)];
+        let price = self.price(model);
+        (
+            model.name.to_string(),
+            price,
+            prompt,
+            model.config.max_tokens as f64,
+        )
+    }
+
+    fn model(&self, slot: ParamId) -> ModelId {
+        match self.resolve(slot) {
+            ParamValue::ModelRef { model } => *model,
+            other => panic!("model slot resolved to {:?}", other.kind()),
+        }
+    }
+
+    /// The price of `model`, flagged when the table lacks it.
+    fn price(&mut self, model: &ModelDef) -> Option<ModelPrice> {
+        let pricing = self.options.pricing?;
+        let price = pricing.get(model).copied();
+        if price.is_none() {
+            self.flag(CostFlag::Unpriced {
+                model: model.name.to_string(),
+            });
+        }
+        price
+    }
+
+    /// The rendered prompt of one call, with placeholder inputs.
+    fn prompt_tokens(
+        &self,
+        def: &SignatureDef,
+        instruction: ParamId,
+        demos: ParamId,
+        playbook: Option<&str>,
+    ) -> f64 {
+        let instruction = match self.resolve(instruction) {
+            ParamValue::Instruction { text } => text.as_str(),
+            _ => "",
+        };
+        let demos = match self.resolve(demos) {
+            ParamValue::Demos { rows } => rows.as_slice(),
+            _ => &[],
+        };
+        let input: JsonMap = def
+            .inputs
+            .iter()
+            .map(|field| (field.name.to_string(), Value::String(String::new())))
+            .collect();
+        let (prefix, suffix) =
+            render_prompt(def, &self.p.types, instruction, demos, &input, playbook);
+        let rendered: f64 = prefix
+            .iter()
+            .chain(suffix.iter())
+            .map(|message| tokens(&message.content()))
+            .sum();
+        let visible = def.inputs.iter().filter(|field| !field.hidden).count();
+        rendered + (visible as u64 * self.options.assumptions.input_field_tokens) as f64
+    }
+
+    /// An agent loop: turn by turn, each prompt carrying the turns before it
+    /// (within the history window), stopping where the loop's bounds and
+    /// budget would.
+    fn agent(&mut self, at: &str, n: &AgentLoopNode) -> (String, Cost) {
+        let p = self.p;
+        let assumptions = self.options.assumptions;
+        let def = &p.sigs[n.sig];
+        if n.budget.max_lm_calls.is_none() && n.budget.max_tokens.is_none() {
+            self.flag(CostFlag::Unbudgeted {
+                node: at.to_string(),
+            });
+        }
+        let policy = match self.resolve(n.context_policy) {
+            ParamValue::ContextPolicy { policy } => policy.clone(),
+            _ => ContextPolicy::default(),
+        };
+        let tools: Vec<_> = match self.resolve(n.tool_set) {
+            ParamValue::ToolSet { tools } => tools
+                .iter()
+                .copied()
+                .filter(|tool| n.tools.contains(tool))
+                .collect(),
+            _ => n.tools.to_vec(),
+        };
+        let surface: f64 = tools
+            .iter()
+            .map(|&tool| {
+                let tool = &p.tools[tool];
+                let desc = match self.resolve(tool.desc) {
+                    ParamValue::ToolDesc { text } => text.as_str(),
+                    _ => "",
+                };
+                let schema = input_schema_of(&p.sigs[tool.sig], &p.types).to_string();
+                tokens(p.syms.get(tool.name)) + tokens(desc) + tokens(&schema)
+            })
+            .sum();
+        let (model, price, prompt, max) = self.leaf_call(
+            def,
+            n.instruction,
+            n.demos,
+            n.model,
+            policy.playbook.as_deref(),
+            surface,
+        );
+
+        // Summaries are written by the compaction model, else the leaf's.
+        let summarizer = &p.models[policy
+            .compaction
+            .as_ref()
+            .and_then(|compaction| compaction.model)
+            .unwrap_or_else(|| self.model(n.model))];
+        let summary_price = self.price(summarizer);
+        let summary_max = summarizer.config.max_tokens as f64;
+        let raw_result = assumptions.tool_result_tokens as f64;
+        let summarize_results = policy
+            .summarize_tool_results_over
+            .is_some_and(|bytes| raw_result * 4.0 > bytes as f64);
+        let clip = |result: f64| match policy.tool_result_max_bytes {
+            Some(bytes) => result.min(bytes as f64 / 4.0),
+            None => result,
+        };
+        // Each turn adds the model's message and the tool results (two
+        // messages); the window keeps the last `max_history_turns` of them,
+        // and compaction folds all but the last `keep_recent`.
+        let window = policy
+            .max_history_turns
+            .map_or(f64::INFINITY, |messages| (messages as f64 / 2.0).ceil());
+        let compaction = policy.compaction.as_ref().map(|compaction| {
+            (
+                compaction.at_tokens as f64,
+                (compaction.keep_recent as f64 / 2.0).ceil(),
+                tokens(
+                    compaction
+                        .instruction
+                        .as_deref()
+                        .unwrap_or(COMPACTION_INSTRUCTION),
+                ),
+            )
+        });
+        let max_turns = n.stop.max_turns.get();
+        let turns = match n.budget.max_lm_calls {
+            Some(calls) => max_turns.min(calls),
+            None => max_turns,
+        };
+        let call_cap = n.budget.max_lm_calls.map_or(f64::INFINITY, f64::from);
+        let token_cap = n.budget.max_tokens.map_or(f64::INFINITY, |t| t as f64);
+        // `turns` turns with completions filling `fill` of `max_tokens`
+        // (summaries too), and the history the next call would carry.
+        let run = |fill: f64, turns: u32| {
+            let completion = (max * fill).round();
+            let summary = (summary_max * fill).round();
+            let summarize = |prompt: f64| call(summary_price, assumptions.call_ms, prompt, summary);
+            let tool_result = if summarize_results {
+                clip(summary)
+            } else {
+                clip(raw_result)
+            };
+            let growth = completion + tool_result;
+            let mut total = Estimate::default();
+            let mut history: f64 = 0.0;
+            // Tokens of the running summary, once compaction has written one.
+            let mut folded = 0.0;
+            for turn in 0..turns {
+                // The meter refuses a call once the node's calls or tokens
+                // are spent; summaries draw on both.
+                if total.tokens() >= token_cap || total.lm_calls >= call_cap {
+                    break;
+                }
+                let mut carried = folded + history.min(window) * growth;
+                if let Some((at_tokens, keep, instruction)) = compaction
+                    && prompt + carried >= at_tokens
+                    && history > keep
+                    && total.lm_calls + 1.0 < call_cap
+                {
+                    total = total.then(summarize(instruction + carried));
+                    folded = summary;
+                    history = keep;
+                    carried = folded + keep.min(window) * growth;
+                }
+                total = total.then(call(
+                    price,
+                    assumptions.call_ms,
+                    prompt + carried,
+                    completion,
+                ));
+                history += 1.0;
+                // Every turn but the last feeds a tool result back.
+                if summarize_results && turn + 1 < turns && total.lm_calls < call_cap {
+                    total = total.then(summarize(tokens(TOOL_RESULT_INSTRUCTION) + raw_result));
+                }
+            }
+            (total, folded + history.min(window) * growth)
+        };
+
+        let (mut worst, carried) = run(1.0, turns);
+        if let Some(deadline) = n.budget.deadline_ms {
+            worst.latency_ms = worst
+                .latency_ms
+                .min(deadline as f64 + assumptions.call_ms as f64);
+        }
+        if n.budget.on_exhausted == BudgetPolicy::Finalize {
+            // The closing round-trip is reserved against the run, not the
+            // node, so no node bound stops it.
+            worst = worst.then(call(price, assumptions.call_ms, prompt + carried, max));
+        }
+        let (expected, _) = run(
+            assumptions.output_fill,
+            assumptions.agent_turns.clamp(1, turns),
+        );
+
+        let kind = if tools.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<&str> = tools.iter().map(|&t| p.syms.get(p.tools[t].name)).collect();
+            format!(" tools [{}]", names.join(", "))
+        };
+        (
+            format!("agent {} @{model}{kind}", def.name),
+            Cost { worst, expected },
+        )
+    }
+}
@@ -0,0 +1,639 @@
+//! Program diagrams: [`Program::to_dot`] (Graphviz) and
+//! [`Program::to_mermaid`], for design reviews and optimizer reports.
+//!
+//! Containers render as clusters (`seq`, `fork`, `route`, `retry`, `refine`,
+//! `loop`, `map`); leaves as boxes with their kind, signature, model, and —
+//! for agents — tool set. Every [`Binding`] is an edge from the node that
+//! produces the value to the node that reads it, labeled with the field
+//! (`answer → draft` when the names differ); literals are listed on the
+//! reading node instead. A container's exports (`out`, `join`, `collect`) are
+//! a small node of their own inside its cluster, so `name.field` references
+//! to a container have somewhere to start. Route arms hang off a diamond
+//! labeled with the routed port; loop carries are dotted back edges.
+//!
+//! [`GraphNotes`] annotates the leaves: with an overlay, models and tool sets
+//! resolve through it and the slots it sets are listed; with a [`Trace`],
+//! each leaf shows its call count, total latency, tokens, and errors (spans
+//! join leaves by component name, as everywhere else).
+//!
+//! Nodes a `call` splices in from an import are not drawn: the call is one
+//! box, as in the canonical text.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::ir::builder::cot_reasoning_field;
+use crate::ir::graph::{Binding, Node, NodeId, PortRef, Program, Sym};
+use crate::ir::params::{Overlay, ParamId, ParamOwner, ParamValue};
+use crate::trace::Trace;
+use crate::typesys::type_name;
+
+/// Optional annotations for [`Program::to_dot_with`] and
+/// [`Program::to_mermaid_with`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GraphNotes<'a> {
+    /// Resolve models and tool sets through this overlay, and list the
+    /// slots it sets on each leaf.
+    pub overlay: Option<&'a Overlay>,
+    /// Per-leaf call counts, latency, tokens, and errors from this trace.
+    pub trace: Option<&'a Trace>,
+}
+
+impl Program {
+    /// The program as a Graphviz `digraph`.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(GraphNotes::default())
+    }
+
+    /// [`to_dot`](Program::to_dot) with overlay/trace annotations.
+    pub fn to_dot_with(&self, notes: GraphNotes<'_>) -> String {
+        Diagram::of(self, notes).dot(&self.meta.name)
+    }
+
+    /// The program as a Mermaid `flowchart`.
+    pub fn to_mermaid(&self) -> String {
+        self.to_mermaid_with(GraphNotes::default())
+    }
+
+    /// [`to_mermaid`](Program::to_mermaid) with overlay/trace annotations.
+    pub fn to_mermaid_with(&self, notes: GraphNotes<'_>) -> String {
+        Diagram::of(self, notes).mermaid()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// The renderer-neutral diagram
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq)]
+enum Shape {
+    /// A leaf step.
+    Step,
+    /// Program inputs, container exports, a map's item.
+    Port,
+    /// A route's branch point.
+    Branch,
+}
+
+enum Item {
+    Node {
+        id: String,
+        lines: Vec<String>,
+        shape: Shape,
+    },
+    Cluster {
+        id: String,
+        title: String,
+        items: Vec<Item>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum EdgeKind {
+    /// A value flows along a binding.
+    Data,
+    /// Control: a route arm, refine feedback.
+    Control,
+    /// A loop-carried value, from one iteration to the next.
+    Carry,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    label: String,
+    kind: EdgeKind,
+}
+
+struct Diagram {
+    items: Vec<Item>,
+    edges: Vec<Edge>,
+}
+
+/// Per-leaf totals from a trace.
+#[derive(Default)]
+struct LeafStats {
+    calls: u32,
+    duration_us: u64,
+    tokens: u64,
+    errors: u32,
+}
+
+struct Walker<'a> {
+    p: &'a Program,
+    overlay: Option<&'a Overlay>,
+    stats: HashMap<&'a str, LeafStats>,
+    edges: Vec<Edge>,
+    /// Map items in scope, innermost last: `$.<item>` starts at the map's
+    /// item node.
+    items: Vec<(Sym, String)>,
+    /// Enclosing loops' export nodes, innermost last: `^field` starts there.
+    loops: Vec<String>,
+}
+
+fn node_id(id: NodeId) -> String {
+    format!("{id}")
+}
+
+impl Diagram {
+    fn of(p: &Program, notes: GraphNotes<'_>) -> Diagram {
+        let mut stats: HashMap<&str, LeafStats> = HashMap::new();
+        if let Some(trace) = notes.trace {
+            for span in &trace.spans {
+                let entry = stats
+                    .entry(trace.component_name(span.component))
+                    .or_default();
+                entry.calls += 1;
+                entry.duration_us += span.duration_us;
+                entry.tokens += span.usage.total_tokens;
+                entry.errors += u32::from(span.error.is_some());
+            }
+        }
+        let mut walker = Walker {
+            p,
+            overlay: notes.overlay,
+            stats,
+            edges: Vec::new(),
+            items: Vec::new(),
+            loops: Vec::new(),
+        };
+        let sig = &p.sigs[p.sig];
+        let mut inputs = vec!["inputs".to_string()];
+        for field in sig.inputs.iter() {
+            inputs.push(format!(
+                "{}: {}",
+                field.name,
+                type_name(&field.ty, Some(&p.types))
+            ));
+        }
+        let items = vec![
+            Item::Node {
+                id: "inputs".to_string(),
+                lines: inputs,
+                shape: Shape::Port,
+            },
+            walker.node(p.root),
+        ];
+        Diagram {
+            items,
+            edges: walker.edges,
+        }
+    }
+}
+
+impl Walker<'_> {
+    fn sym(&self, sym: Sym) -> &str {
+        self.p.syms.get(sym)
+    }
+
+    fn resolve(&self, id: ParamId) -> &ParamValue {
+        match self.overlay {
+            Some(overlay) => overlay.resolve(self.p, id),
+            None => &self.p.params[id].default,
+        }
+    }
+
+    /// The node that exports `id`'s outputs.
+    fn anchor(&self, id: NodeId) -> String {
+        match &self.p.nodes[id] {
+            Node::Retry(n) => self.anchor(n.child),
+            Node::Refine(n) => self.anchor(n.child),
+            _ => node_id(id),
+        }
+    }
+
+    /// The first node drawn for `id`: where a route arm's edge lands.
+    fn entry(&self, id: NodeId) -> String {
+        match &self.p.nodes[id] {
+            Node::Seq(n) => match n.body.first() {
+                Some(first) => self.entry(*first),
+                None => node_id(id),
+            },
+            Node::ForkJoin(n) => match n.branches.first() {
+                Some(first) => self.entry(*first),
+                None => node_id(id),
+            },
+            Node::Route(_) => format!("{}_on", node_id(id)),
+            Node::Retry(n) => self.entry(n.child),
+            Node::Refine(n) => self.entry(n.child),
+            Node::Loop(n) => self.entry(n.body),
+            Node::Map(_) => format!("{}_item", node_id(id)),
+            _ => node_id(id),
+        }
+    }
+
+    /// Where a port's value comes from, and the field it carries there.
+    fn source(&self, port: &PortRef) -> Option<(String, String)> {
+        match port {
+            PortRef::Input(sym) => {
+                let from = self
+                    .items
+                    .iter()
+                    .rev()
+                    .find(|(item, _)| item == sym)
+                    .map_or_else(|| "inputs".to_string(), |(_, at)| at.clone());
+                Some((from, self.sym(*sym).to_string()))
+            }
+            PortRef::Out { node, field } => {
+                Some((self.anchor(*node), self.sym(*field).to_string()))
+            }
+            PortRef::Carried(sym) => {
+                let from = self.loops.last()?.clone();
+                Some((from, format!("^{}", self.sym(*sym))))
+            }
+            PortRef::Lit(_) => None,
+        }
+    }
+
+    /// Edges for `bindings` into `to`; returns the literal bindings as label
+    /// lines. `carry` marks a loop's `carry` clause: its destinations are the
+    /// next iteration's `^field`s.
+    fn bind(&mut self, to: &str, bindings: &[Binding], carry: bool) -> Vec<String> {
+        let mut literals = Vec::new();
+        for b in bindings {
+            let dst = self.sym(b.dst).to_string();
+            if let PortRef::Lit(value) = &b.src {
+                literals.push(format!("{dst} = {value}"));
+                continue;
+            }
+            let Some((from, field)) = self.source(&b.src) else {
+                continue;
+            };
+            let dst = if carry { format!("^{dst}") } else { dst };
+            let kind = match b.src {
+                PortRef::Carried(_) => EdgeKind::Carry,
+                _ => EdgeKind::Data,
+            };
+            let label = if field == dst || field.strip_prefix('^') == Some(dst.as_str()) {
+                field
+            } else {
+                format!("{field} → {dst}")
+            };
+            self.edge(from, to.to_string(), label, kind);
+        }
+        literals
+    }
+
+    fn edge(&mut self, from: String, to: String, label: String, kind: EdgeKind) {
+        self.edges.push(Edge {
+            from,
+            to,
+            label,
+            kind,
+        });
+    }
+
+    /// A leaf box: name, `kind Sig [@model]`, extra lines, literal inputs,
+    /// and the overlay/trace notes.
+    fn leaf(
+        &mut self,
+        id: NodeId,
+        name: Sym,
+        head: String,
+        extra: Vec<String>,
+        binding: &[Binding],
+    ) -> Item {
+        let at = node_id(id);
+        let mut lines = vec![self.sym(name).to_string(), head];
+        lines.extend(extra);
+        lines.extend(self.bind(&at, binding, false));
+        if let Some(overlay) = self.overlay {
+            let set: Vec<&str> = self
+                .p
+                .params
+                .iter()
+                .filter(|(pid, slot)| {
+                    slot.owner == ParamOwner::Node(id) && overlay.get(*pid).is_some()
+                })
+                .map(|(_, slot)| slot.path.rsplit('.').next().unwrap_or_default())
+                .collect();
+            if !set.is_empty() {
+                lines.push(format!("overlay: {}", set.join(", ")));
+            }
+        }
+        if let Some(stats) = self.stats.get(self.sym(name)) {
+            let mut line = format!(
+                "{} call{}, {} ms, {} tokens",
+                stats.calls,
+                if stats.calls == 1 { "" } else { "s" },
+                stats.duration_us / 1000,
+                stats.tokens
+            );
+            if stats.errors > 0 {
+                let _ = write!(
+                    line,
+                    ", {} error{}",
+                    stats.errors,
+                    if stats.errors == 1 { "" } else { "s" }
+                );
+            }
+            lines.push(line);
+        }
+        Item::Node {
+            id: at,
+            lines,
+            shape: Shape::Step,
+        }
+    }
+
+    fn model(&self, param: ParamId) -> String {
+        match self.resolve(param) {
+            ParamValue::ModelRef { model } => format!(" @{}", self.p.models[*model].name),
+            _ => String::new(),
+        }
+    }
+
+    /// An export node (`out`, `join`, `collect`) fed by `bindings`.
+    fn exports(&mut self, id: NodeId, title: &str, bindings: &[Binding]) -> Item {
+        let at = node_id(id);
+        let mut lines = vec![title.to_string()];
+        lines.extend(self.bind(&at, bindings, false));
+        Item::Node {
+            id: at,
+            lines,
+            shape: Shape::Port,
+        }
+    }
+
+    fn cluster(id: NodeId, title: String, items: Vec<Item>) -> Item {
+        Item::Cluster {
+            id: node_id(id),
+            title,
+            items,
+        }
+    }
+
+    fn port_text(&self, port: &PortRef) -> String {
+        match port {
+            PortRef::Input(sym) => format!("$.{}", self.sym(*sym)),
+            PortRef::Out { node, field } => match self.p.leaf_name(*node) {
+                Some(name) => format!("{name}.{}", self.sym(*field)),
+                None => format!("{node}.{}", self.sym(*field)),
+            },
+            PortRef::Carried(sym) => format!("^{}", self.sym(*sym)),
+            PortRef::Lit(value) => value.to_string(),
+        }
+    }
+
+    fn node(&mut self, id: NodeId) -> Item {
+        let p = self.p;
+        match &p.nodes[id] {
+            Node::Predict(n) => {
+                let sig = &p.sigs[n.sig];
+                let cot = sig.outputs.first() == Some(&cot_reasoning_field());
+                let kind = if cot { "cot" } else { "predict" };
+                let head = format!("{kind} {}{}", sig.name, self.model(n.model));
+                self.leaf(id, n.name, head, Vec::new(), &n.binding)
+            }
+            Node::AgentLoop(n) => {
+                let head = format!("agent {}{}", p.sigs[n.sig].name, self.model(n.model));
+                let tools: Vec<&str> = match self.resolve(n.tool_set) {
+                    ParamValue::ToolSet { tools } => {
+                        tools.iter().map(|t| self.sym(p.tools[*t].name)).collect()
+                    }
+                    _ => n.tools.iter().map(|t| self.sym(p.tools[*t].name)).collect(),
+                };
+                let extra = vec![format!("tools [{}]", tools.join(", "))];
+                self.leaf(id, n.name, head, extra, &n.binding)
+            }
+            Node::Call(n) => {
+                let head = format!("call {}", p.imports[n.import].alias);
+                self.leaf(id, n.name, head, Vec::new(), &n.binding)
+            }
+            Node::Transform(n) => {
+                let head = format!("transform {}", p.sigs[n.sig].name);
+                self.leaf(id, n.name, head, Vec::new(), &n.binding)
+            }
+            Node::Approve(n) => {
+                let head = format!("approve {}", p.sigs[n.sig].name);
+                self.leaf(id, n.name, head, Vec::new(), &n.binding)
+            }
+            Node::Hole(n) => {
+                let head = format!("hole {}", p.sigs[n.sig].name);
+                self.leaf(id, n.name, head, Vec::new(), &n.binding)
+            }
+            Node::Seq(n) => {
+                let mut items: Vec<Item> = n.body.iter().map(|child| self.node(*child)).collect();
+                let title = if id == p.root {
+                    format!("main: {}", p.sigs[p.sig].name)
+                } else {
+                    "seq".to_string()
+                };
+                if !n.out.is_empty() || id == p.root {
+                    let exports = if id == p.root { "outputs" } else { "out" };
+                    items.push(self.exports(id, exports, &n.out));
+                }
+                Self::cluster(id, title, items)
+            }
+            Node::ForkJoin(n) => {
+                let mut items: Vec<Item> =
+                    n.branches.iter().map(|child| self.node(*child)).collect();
+                items.push(self.exports(id, "join", &n.join));
+                Self::cluster(id, "fork".to_string(), items)
+            }
+            Node::Route(n) => {
+                let on = format!("{}_on", node_id(id));
+                if let Some((from, field)) = self.source(&n.on) {
+                    self.edge(from, on.clone(), field, EdgeKind::Data);
+                }
+                let mut items = vec![Item::Node {
+                    id: on.clone(),
+                    lines: vec![format!("on {}", self.port_text(&n.on))],
+                    shape: Shape::Branch,
+                }];
+                let arms = n
+                    .arms
+                    .iter()
+                    .map(|(variant, arm)| (self.sym(*variant).to_string(), *arm))
+                    .chain(n.when.iter().map(|(pred, arm)| (pred.to_string(), *arm)))
+                    .chain(n.default.map(|arm| ("else".to_string(), arm)))
+                    .collect::<Vec<_>>();
+                for (label, arm) in arms {
+                    items.push(self.node(arm));
+                    let entry = self.entry(arm);
+                    self.edge(on.clone(), entry, label, EdgeKind::Control);
+                    let anchor = self.anchor(arm);
+                    self.edge(anchor, node_id(id), String::new(), EdgeKind::Control);
+                }
+                items.push(Item::Node {
+                    id: node_id(id),
+                    lines: vec!["out".to_string()],
+                    shape: Shape::Port,
+                });
+                Self::cluster(id, "route".to_string(), items)
+            }
+            Node::Retry(n) => {
+                let mut title = format!("retry ×{}", n.max_attempts);
+                if n.backoff_ms > 0 {
+                    let _ = write!(title, ", backoff {} ms", n.backoff_ms);
+                }
+                let items = vec![self.node(n.child)];
+                Self::cluster(id, title, items)
+            }
+            Node::Refine(n) => {
+                let title = format!("refine ≥{} ×{}", n.threshold, n.max_rounds);
+                let items = vec![self.node(n.child), self.node(n.judge)];
+                let (from, to) = (self.anchor(n.judge), self.entry(n.child));
+                let label = self.sym(n.feedback_field).to_string();
+                self.edge(from, to, label, EdgeKind::Control);
+                Self::cluster(id, title, items)
+            }
+            Node::Loop(n) => {
+                let mut title = format!("loop ×{}", n.max_iters);
+                if let Some(port) = &n.while_ {
+                    let _ = write!(title, " while {}", self.port_text(port));
+                }
+                self.loops.push(node_id(id));
+                let mut items = vec![self.node(n.body)];
+                self.loops.pop();
+                self.bind(&node_id(id), &n.carry, true);
+                items.push(self.exports(id, "join", &n.out));
+                Self::cluster(id, title, items)
+            }
+            Node::Map(n) => {
+                let item = format!("{}_item", node_id(id));
+                if let Some((from, field)) = self.source(&n.over) {
+                    self.edge(from, item.clone(), field, EdgeKind::Data);
+                }
+                let mut items = vec![Item::Node {
+                    id: item.clone(),
+                    lines: vec![format!("each $.{}", self.sym(n.item))],
+                    shape: Shape::Port,
+                }];
+                self.items.push((n.item, item));
+                items.push(self.node(n.body));
+                self.items.pop();
+                items.push(self.exports(id, "collect", &n.collect));
+                Self::cluster(id, format!("map ∥{}", n.max_parallel), items)
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Renderers
+// ---------------------------------------------------------------------------
+
+fn dot_str(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn mermaid_str(text: &str) -> String {
+    let escaped = text
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>");
+    format!("\"{escaped}\"")
+}
+
+impl Diagram {
+    fn dot(&self, name: &str) -> String {
+        fn items(out: &mut String, items: &[Item], depth: usize) {
+            let pad = "  ".repeat(depth);
+            for item in items {
+                match item {
+                    Item::Node { id, lines, shape } => {
+                        let shape = match shape {
+                            Shape::Step => "box, style=rounded",
+                            Shape::Port => "ellipse",
+                            Shape::Branch => "diamond",
+                        };
+                        let label = dot_str(&lines.join("\n"));
+                        let _ = writeln!(out, "{pad}{id} [label={label}, shape={shape}];");
+                    }
+                    Item::Cluster {
+                        id,
+                        title,
+                        items: inner,
+                    } => {
+                        let _ = writeln!(out, "{pad}subgraph cluster_{id} {{");
+                        let _ = writeln!(out, "{pad}  label={};", dot_str(title));
+                        items(out, inner, depth + 1);
+                        let _ = writeln!(out, "{pad}}}");
+                    }
+                }
+            }
+        }
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph {} {{", dot_str(name));
+        out.push_str("  node [fontname=\"Helvetica\"];\n");
+        out.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
+        items(&mut out, &self.items, 1);
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            if !edge.label.is_empty() {
+                attrs.push(format!("label={}", dot_str(&edge.label)));
+            }
+            match edge.kind {
+                EdgeKind::Data => {}
+                EdgeKind::Control => attrs.push("style=dashed".to_string()),
+                EdgeKind::Carry => {
+                    attrs.push("style=dotted".to_string());
+                    attrs.push("constraint=false".to_string());
+                }
+            }
+            let attrs = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attrs.join(", "))
+            };
+            let _ = writeln!(out, "  {} -> {}{attrs};", edge.from, edge.to);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn mermaid(&self) -> String {
+        fn items(out: &mut String, items: &[Item], depth: usize) {
+            let pad = "  ".repeat(depth);
+            for item in items {
+                match item {
+                    Item::Node { id, lines, shape } => {
+                        let label = mermaid_str(&lines.join("\n"));
+                        let _ = match shape {
+                            Shape::Step => writeln!(out, "{pad}{id}[{label}]"),
+                            Shape::Port => writeln!(out, "{pad}{id}([{label}])"),
+                            Shape::Branch => writeln!(out, "{pad}{id}{{{label}}}"),
+                        };
+                    }
+                    Item::Cluster {
+                        id,
+                        title,
+                        items: inner,
+                    } => {
+                        let _ = writeln!(out, "{pad}subgraph c_{id} [{}]", mermaid_str(title));
+                        items(out, inner, depth + 1);
+                        let _ = writeln!(out, "{pad}end");
+                    }
+                }
+            }
+        }
+        let mut out = String::from("flowchart TD\n");
+        items(&mut out, &self.items, 1);
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Data => "-->",
+                EdgeKind::Control | EdgeKind::Carry => "-.->",
+            };
+            if edge.label.is_empty() {
+                let _ = writeln!(out, "  {} {arrow} {}", edge.from, edge.to);
+            } else {
+                let label = mermaid_str(&edge.label);
+                let _ = writeln!(out, "  {} {arrow}|{label}| {}", edge.from, edge.to);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,698 @@
+//! Semantic diffs: [`Program::diff`] between two programs (a parent and the
+//! child `Program::edited`, `Program::bake`, or an optimizer produced), and
+//! [`Overlay::diff`] between two candidates of one program.
+//!
+//! Programs are compared by what the `.dsrs` text names, never by arena ids:
+//! leaves by name, containers by their position under `main` (`main.2`,
+//! `main.1[Low]`, `main.3.body`), signatures, tools, and models by name,
+//! params by path. Model and tool ids inside param values are shown as names,
+//! so two programs that declare the same models in another order compare
+//! equal. Entries an import spliced in are skipped; a `call` is compared by
+//! its import's pin.
+//!
+//! Both diffs render as text ([`Display`](std::fmt::Display)) for review and
+//! serialize to JSON for tooling. Every entry is a [`Change`]: `from` is
+//! `None` for an addition, `to` is `None` for a removal.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ir::builder::cot_reasoning_field;
+use crate::ir::graph::{
+    Binding, BudgetPolicy, HoleImpl, MapErrorPolicy, Node, NodeId, PortRef, Program, SigId, ToolId,
+    ToolKind,
+};
+use crate::ir::params::{Overlay, ParamValue};
+use crate::ir::sig::{FieldDef, SignatureDef};
+use crate::ir::text::print::{field_text, json_str, memo_opt, model_text};
+
+/// One entry of a diff.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Change<T = String> {
+    /// What changed: a node, `node.field`, a signature, `Sig.field`, a tool,
+    /// a model, a param path.
+    pub key: String,
+    /// `None` when the entry was added.
+    pub from: Option<T>,
+    /// `None` when the entry was removed.
+    pub to: Option<T>,
+}
+
+impl<T> Change<T> {
+    fn new(key: impl Into<String>, from: Option<T>, to: Option<T>) -> Self {
+        Self {
+            key: key.into(),
+            from,
+            to,
+        }
+    }
+}
+
+/// The result of [`Program::diff`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProgramDiff {
+    pub from_hash: u64,
+    pub to_hash: u64,
+    /// The `program` name and the `caps` block.
+    pub program: Vec<Change>,
+    /// Nodes added, removed, or changed in kind, signature, or options. A
+    /// leaf is keyed by name, a container by its position.
+    pub nodes: Vec<Change>,
+    /// Leaves that kept their name but sit at another position.
+    pub moved: Vec<Change>,
+    /// Rewired bindings of nodes on both sides: `node.field` for a leaf
+    /// input, `<container>.<clause>.field` for `out`/`join`/`carry`/`collect`.
+    pub bindings: Vec<Change>,
+    /// Signatures added or removed, instruction and field changes.
+    pub sigs: Vec<Change>,
+    /// Tool table changes: interface, caps, implementation kind.
+    pub tools: Vec<Change>,
+    /// Model table changes.
+    pub models: Vec<Change>,
+    /// Param default changes, by param path, for params on both sides.
+    pub params: Vec<Change<Value>>,
+}
+
+impl ProgramDiff {
+    /// True when the programs are structurally identical (their hashes may
+    /// still differ, e.g. by lineage or an import's pin).
+    pub fn is_empty(&self) -> bool {
+        self.program.is_empty()
+            && self.nodes.is_empty()
+            && self.moved.is_empty()
+            && self.bindings.is_empty()
+            && self.sigs.is_empty()
+            && self.tools.is_empty()
+            && self.models.is_empty()
+            && self.params.is_empty()
+    }
+}
+
+/// The result of [`Overlay::diff`] (or [`OverlayDiff::named`]): param values
+/// set, unset, or changed, by param path.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct OverlayDiff {
+    pub params: Vec<Change<Value>>,
+}
+
+impl OverlayDiff {
+    /// Diffs two overlays in the named (`Overlay::to_named`) form, without a
+    /// program: model and tool ids stay ids.
+    pub fn named(from: &BTreeMap<String, ParamValue>, to: &BTreeMap<String, ParamValue>) -> Self {
+        let json = |value: &ParamValue| serde_json::to_value(value).expect("params serialize");
+        let from: BTreeMap<String, Value> =
+            from.iter().map(|(k, v)| (k.clone(), json(v))).collect();
+        let to: BTreeMap<String, Value> = to.iter().map(|(k, v)| (k.clone(), json(v))).collect();
+        Self {
+            params: map_changes(from, to),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+}
+
+impl Program {
+    /// What changed from `self` to `other`.
+    pub fn diff(&self, other: &Program) -> ProgramDiff {
+        let (a, b) = (Side::new(self), Side::new(other));
+        let mut program = Vec::new();
+        if self.meta.name != other.meta.name {
+            program.push(Change::new(
+                "name",
+                Some(self.meta.name.to_string()),
+                Some(other.meta.name.to_string()),
+            ));
+        }
+        let caps = |p: &Program| format!("{{ {} }}", p.caps.iter().collect::<Vec<_>>().join(" "));
+        if self.caps != other.caps {
+            program.push(Change::new("caps", Some(caps(self)), Some(caps(other))));
+        }
+
+        let mut moved = Vec::new();
+        for (key, node) in &a.nodes {
+            if let Some(theirs) = b.nodes.get(key)
+                && node.path != theirs.path
+            {
+                moved.push(Change::new(
+                    key.clone(),
+                    Some(node.path.clone()),
+                    Some(theirs.path.clone()),
+                ));
+            }
+        }
+        let mut bindings = Vec::new();
+        for (key, node) in &a.nodes {
+            if let Some(theirs) = b.nodes.get(key) {
+                for change in map_changes(node.bindings.clone(), theirs.bindings.clone()) {
+                    bindings.push(Change::new(
+                        format!("{key}.{}", change.key),
+                        change.from,
+                        change.to,
+                    ));
+                }
+            }
+        }
+
+        let mut sigs = Vec::new();
+        for change in map_changes(a.sig_texts(), b.sig_texts()) {
+            let (Some(_), Some(_)) = (&change.from, &change.to) else {
+                sigs.push(change);
+                continue;
+            };
+            let (x, y) = (
+                &self.sigs[a.sigs[&change.key]],
+                &other.sigs[b.sigs[&change.key]],
+            );
+            if x.instruction != y.instruction {
+                sigs.push(Change::new(
+                    format!("{}.instruction", change.key),
+                    Some(json_str(&x.instruction)),
+                    Some(json_str(&y.instruction)),
+                ));
+            }
+            let fields = |fields: &[FieldDef], side: &str| -> BTreeMap<String, String> {
+                fields
+                    .iter()
+                    .map(|f| (f.name.to_string(), field_text(side, f)))
+                    .collect()
+            };
+            let inputs = map_changes(fields(&x.inputs, "in"), fields(&y.inputs, "in"));
+            let outputs = map_changes(fields(&x.outputs, "out"), fields(&y.outputs, "out"));
+            for field in inputs.into_iter().chain(outputs) {
+                sigs.push(Change::new(
+                    format!("{}.{}", change.key, field.key),
+                    field.from,
+                    field.to,
+                ));
+            }
+        }
+
+        let nodes_of = |side: &Side| -> BTreeMap<String, String> {
+            side.nodes
+                .iter()
+                .map(|(key, node)| (key.clone(), node.what.clone()))
+                .collect()
+        };
+        let params = map_changes(a.params.clone(), b.params.clone())
+            .into_iter()
+            .filter(|change| change.from.is_some() && change.to.is_some())
+            .collect();
+        ProgramDiff {
+            from_hash: self.meta.program_hash,
+            to_hash: other.meta.program_hash,
+            program,
+            nodes: map_changes(nodes_of(&a), nodes_of(&b)),
+            moved,
+            bindings,
+            sigs,
+            tools: map_changes(a.tools.clone(), b.tools.clone()),
+            models: map_changes(a.models.clone(), b.models.clone()),
+            params,
+        }
+    }
+}
+
+impl Overlay {
+    /// What changed from `self` to `other`, both overlays of `p`.
+    pub fn diff(&self, other: &Overlay, p: &Program) -> OverlayDiff {
+        let named = |overlay: &Overlay| -> BTreeMap<String, Value> {
+            overlay
+                .entries()
+                .map(|(id, value)| (p.param_path(id).to_string(), param_json(p, value)))
+                .collect()
+        };
+        OverlayDiff {
+            params: map_changes(named(self), named(other)),
+        }
+    }
+}
+
+/// Entries of `from`/`to` that differ, in key order.
+fn map_changes<T: PartialEq>(
+    mut from: BTreeMap<String, T>,
+    to: BTreeMap<String, T>,
+) -> Vec<Change<T>> {
+    let mut changes = Vec::new();
+    for (key, theirs) in to {
+        match from.remove(&key) {
+            Some(ours) if ours == theirs => {}
+            ours => changes.push(Change::new(key, ours, Some(theirs))),
+        }
+    }
+    for (key, ours) in from {
+        changes.push(Change::new(key, Some(ours), None));
+    }
+    changes.sort_by(|x, y| x.key.cmp(&y.key));
+    changes
+}
+
+/// A param value as JSON, with model and tool ids replaced by names.
+fn param_json(p: &Program, value: &ParamValue) -> Value {
+    match value {
+        ParamValue::ModelRef { model } => {
+            serde_json::json!({ "k": "model_ref", "model": &*p.models[*model].name })
+        }
+        ParamValue::ToolSet { tools } => {
+            let names: Vec<&str> = tools.iter().map(|t| p.syms.get(p.tools[*t].name)).collect();
+            serde_json::json!({ "k": "tool_set", "tools": names })
+        }
+        ParamValue::ContextPolicy { policy } => {
+            let mut value = serde_json::to_value(value).expect("params serialize");
+            if let Some(model) = policy.compaction.as_ref().and_then(|c| c.model) {
+                value["policy"]["compaction"]["model"] = (&*p.models[model].name).into();
+            }
+            value
+        }
+        other => serde_json::to_value(other).expect("params serialize"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// One program, keyed by name
+// ---------------------------------------------------------------------------
+
+struct NodeEntry {
+    /// Position under `main`.
+    path: String,
+    /// Kind, signature, and options: `predict Draft`, `loop (max_iters 3)`.
+    what: String,
+    /// Destination → source port text.
+    bindings: BTreeMap<String, String>,
+}
+
+struct Side<'a> {
+    p: &'a Program,
+    /// Leaves by name, containers by path.
+    nodes: BTreeMap<String, NodeEntry>,
+    /// Container paths, for port text.
+    paths: HashMap<NodeId, String>,
+    sigs: BTreeMap<String, SigId>,
+    tools: BTreeMap<String, String>,
+    models: BTreeMap<String, String>,
+    params: BTreeMap<String, Value>,
+}
+
+impl<'a> Side<'a> {
+    fn new(p: &'a Program) -> Self {
+        let spliced = p.spliced();
+        let mut side = Side {
+            p,
+            nodes: BTreeMap::new(),
+            paths: HashMap::new(),
+            sigs: BTreeMap::new(),
+            tools: BTreeMap::new(),
+            models: BTreeMap::new(),
+            params: BTreeMap::new(),
+        };
+        side.name_containers(p.root, "main".to_string());
+        side.walk(p.root);
+
+        // Declared signatures: not a `cot` variant, not only a tool's
+        // interface, not spliced in.
+        let tool_sigs: HashSet<SigId> = p.tools.values().map(|tool| tool.sig).collect();
+        let mut node_sigs: HashSet<SigId> = HashSet::from([p.sig]);
+        for node in p.nodes.values() {
+            match node {
+                Node::Predict(n) => node_sigs.insert(n.sig),
+                Node::AgentLoop(n) => node_sigs.insert(n.sig),
+                Node::Transform(n) => node_sigs.insert(n.sig),
+                Node::Approve(n) => node_sigs.insert(n.sig),
+                Node::Hole(n) => node_sigs.insert(n.sig),
+                _ => false,
+            };
+        }
+        let is_cot = |sig: &SignatureDef| sig.outputs.first() == Some(&cot_reasoning_field());
+        for (id, sig) in p.sigs.iter() {
+            let cot_variant = is_cot(sig)
+                && p.sigs
+                    .values()
+                    .any(|base| base.name == sig.name && !is_cot(base));
+            let tool_only = tool_sigs.contains(&id) && !node_sigs.contains(&id);
+            if cot_variant || tool_only || spliced.sigs.contains(&id) {
+                continue;
+            }
+            side.sigs.entry(sig.name.to_string()).or_insert(id);
+        }
+
+        for (id, tool) in p.tools.iter() {
+            if spliced.tools.contains(&id) {
+                continue;
+            }
+            let sig = &p.sigs[tool.sig];
+            let fields: Vec<String> = sig
+                .inputs
+                .iter()
+                .map(|f| field_text("in", f))
+                .chain(sig.outputs.iter().map(|f| field_text("out", f)))
+                .collect();
+            let caps: Vec<&str> = tool.caps.iter().collect();
+            let kind = match tool.kind {
+                ToolKind::Host => "extern",
+                ToolKind::Sandboxed { .. } => "js",
+            };
+            let what = format!("({}) caps [{}] {kind}", fields.join(", "), caps.join(" "));
+            side.tools.insert(p.syms.get(tool.name).to_string(), what);
+        }
+        for (id, model) in p.models.iter() {
+            if !spliced.models.contains(&id) {
+                side.models
+                    .insert(model.name.to_string(), model_text(&model.config));
+            }
+        }
+        for (id, slot) in p.params.iter() {
+            if !spliced.params.contains(&id) {
+                side.params
+                    .insert(slot.path.to_string(), param_json(p, &slot.default));
+            }
+        }
+        side
+    }
+
+    /// Each declared signature as `"instruction" (in question: string, out
+    /// answer: string)`.
+    fn sig_texts(&self) -> BTreeMap<String, String> {
+        self.sigs
+            .iter()
+            .map(|(name, id)| {
+                let sig = &self.p.sigs[*id];
+                let fields: Vec<String> = sig
+                    .inputs
+                    .iter()
+                    .map(|f| field_text("in", f))
+                    .chain(sig.outputs.iter().map(|f| field_text("out", f)))
+                    .collect();
+                let text = if sig.instruction.is_empty() {
+                    format!("({})", fields.join(", "))
+                } else {
+                    format!("{} ({})", json_str(&sig.instruction), fields.join(", "))
+                };
+                (name.clone(), text)
+            })
+            .collect()
+    }
+
+    /// Assigns every container its position, before any port is printed.
+    fn name_containers(&mut self, id: NodeId, path: String) {
+        let p = self.p;
+        let children: Vec<(NodeId, String)> = match &p.nodes[id] {
+            Node::Seq(n) => enumerated(&n.body, &path),
+            Node::ForkJoin(n) => enumerated(&n.branches, &path),
+            Node::Route(n) => n
+                .arms
+                .iter()
+                .map(|(variant, arm)| (*arm, format!("{path}[{}]", p.syms.get(*variant))))
+                .chain(
+                    n.when
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (_, arm))| (*arm, format!("{path}[when {i}]"))),
+                )
+                .chain(n.default.map(|arm| (arm, format!("{path}[else]"))))
+                .collect(),
+            Node::Retry(n) => vec![(n.child, format!("{path}.body"))],
+            Node::Refine(n) => vec![
+                (n.child, format!("{path}.body")),
+                (n.judge, format!("{path}.judge")),
+            ],
+            Node::Loop(n) => vec![(n.body, format!("{path}.body"))],
+            Node::Map(n) => vec![(n.body, format!("{path}.body"))],
+            _ => Vec::new(),
+        };
+        self.paths.insert(id, path);
+        for (child, path) in children {
+            self.name_containers(child, path);
+        }
+    }
+
+    fn port(&self, port: &PortRef) -> String {
+        let p = self.p;
+        match port {
+            PortRef::Input(sym) => format!("$.{}", p.syms.get(*sym)),
+            PortRef::Carried(sym) => format!("^{}", p.syms.get(*sym)),
+            PortRef::Lit(value) => value.to_string(),
+            PortRef::Out { node, field } => {
+                let node = match p.leaf_name(*node) {
+                    Some(name) => name.to_string(),
+                    None => self
+                        .paths
+                        .get(node)
+                        .cloned()
+                        .unwrap_or_else(|| node.to_string()),
+                };
+                format!("{node}.{}", p.syms.get(*field))
+            }
+        }
+    }
+
+    fn bind(&self, out: &mut BTreeMap<String, String>, clause: &str, bindings: &[Binding]) {
+        for b in bindings {
+            let dst = self.p.syms.get(b.dst);
+            out.insert(format!("{clause}{dst}"), self.port(&b.src));
+        }
+    }
+
+    fn walk(&mut self, id: NodeId) {
+        let p = self.p;
+        let path = self.paths[&id].clone();
+        let mut bindings = BTreeMap::new();
+        let tools = |ids: &[ToolId]| {
+            let names: Vec<&str> = ids.iter().map(|t| p.syms.get(p.tools[*t].name)).collect();
+            format!("[{}]", names.join(" "))
+        };
+        let (what, children): (String, Vec<NodeId>) = match &p.nodes[id] {
+            Node::Predict(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                let sig = &p.sigs[n.sig];
+                let cot = sig.outputs.first() == Some(&cot_reasoning_field());
+                let kind = if cot { "cot" } else { "predict" };
+                (format!("{kind} {}", sig.name), Vec::new())
+            }
+            Node::AgentLoop(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                let mut what = format!("agent {} tools {}", p.sigs[n.sig].name, tools(&n.tools));
+                if !n.stop.stop_tools.is_empty() {
+                    let _ = write!(what, " stop_tools {}", tools(&n.stop.stop_tools));
+                }
+                let _ = write!(what, " max_turns {}", n.stop.max_turns);
+                if !n.stop.until_parse {
+                    what.push_str(" until_parse false");
+                }
+                let budget = &n.budget;
+                let mut opts = Vec::new();
+                if let Some(calls) = budget.max_lm_calls {
+                    opts.push(format!("calls {calls}"));
+                }
+                if let Some(tokens) = budget.max_tokens {
+                    opts.push(format!("tokens {tokens}"));
+                }
+                if let Some(deadline) = budget.deadline_ms {
+                    opts.push(format!("deadline_ms {deadline}"));
+                }
+                if budget.on_exhausted == BudgetPolicy::Finalize {
+                    opts.push("on_exhausted finalize".to_string());
+                }
+                if !opts.is_empty() {
+                    let _ = write!(what, " budget {{ {} }}", opts.join(" "));
+                }
+                (what, Vec::new())
+            }
+            Node::Transform(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                let exprs: Vec<String> = n
+                    .exprs
+                    .iter()
+                    .map(|(field, expr)| format!("{} = {}", p.syms.get(*field), json_str(expr)))
+                    .collect();
+                let what = format!(
+                    "transform {} {{ {} }}",
+                    p.sigs[n.sig].name,
+                    exprs.join(", ")
+                );
+                (what, Vec::new())
+            }
+            Node::Approve(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                (format!("approve {}", p.sigs[n.sig].name), Vec::new())
+            }
+            Node::Call(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                let import = &p.imports[n.import];
+                let what = format!("call {} pin \"{:016x}\"", import.alias, import.hash);
+                (what, Vec::new())
+            }
+            Node::Hole(n) => {
+                self.bind(&mut bindings, "", &n.binding);
+                let caps: Vec<&str> = n.caps.iter().collect();
+                let imp = match n.imp {
+                    HoleImpl::Sandboxed { .. } => "js".to_string(),
+                    HoleImpl::Host { hash } => format!("extern \"{hash:016x}\""),
+                };
+                let what = format!(
+                    "hole {} caps [{}] {imp}",
+                    p.sigs[n.sig].name,
+                    caps.join(" ")
+                );
+                (what, Vec::new())
+            }
+            Node::Seq(n) => {
+                self.bind(&mut bindings, "out.", &n.out);
+                ("seq".to_string(), n.body.to_vec())
+            }
+            Node::ForkJoin(n) => {
+                self.bind(&mut bindings, "join.", &n.join);
+                ("fork".to_string(), n.branches.to_vec())
+            }
+            Node::Route(n) => {
+                let mut children: Vec<NodeId> = n.arms.iter().map(|(_, arm)| *arm).collect();
+                children.extend(n.when.iter().map(|(_, arm)| *arm));
+                children.extend(n.default);
+                let mut what = format!("route {}", self.port(&n.on));
+                for (i, (predicate, _)) in n.when.iter().enumerate() {
+                    let _ = write!(what, " when {i} {}", json_str(predicate));
+                }
+                (what, children)
+            }
+            Node::Retry(n) => {
+                let mut what = format!("retry (attempts {}", n.max_attempts);
+                if n.backoff_ms != 0 {
+                    let _ = write!(what, " backoff_ms {}", n.backoff_ms);
+                }
+                if n.feedback {
+                    what.push_str(" feedback true");
+                }
+                what.push(')');
+                (what, vec![n.child])
+            }
+            Node::Refine(n) => {
+                let what = format!(
+                    "refine (threshold {} max_rounds {} feedback_field {})",
+                    n.threshold,
+                    n.max_rounds,
+                    p.syms.get(n.feedback_field)
+                );
+                (what, vec![n.child, n.judge])
+            }
+            Node::Loop(n) => {
+                self.bind(&mut bindings, "carry.", &n.carry);
+                self.bind(&mut bindings, "join.", &n.out);
+                let mut what = format!("loop (max_iters {})", n.max_iters);
+                if let Some(port) = &n.while_ {
+                    let _ = write!(what, " while {}", self.port(port));
+                }
+                (what, vec![n.body])
+            }
+            Node::Map(n) => {
+                self.bind(&mut bindings, "collect.", &n.collect);
+                let mut what = format!(
+                    "map {} in {} (max_parallel {}",
+                    p.syms.get(n.item),
+                    self.port(&n.over),
+                    n.max_parallel
+                );
+                if n.on_error == MapErrorPolicy::Skip {
+                    what.push_str(" on_error skip");
+                }
+                what.push(')');
+                (what, vec![n.body])
+            }
+        };
+        let what = match p.nodes[id].memo() {
+            Some(memo) => format!("{what} {}", memo_opt(&memo)),
+            None => what,
+        };
+        let key = match p.leaf_name(id) {
+            Some(name) => name.to_string(),
+            None => path.clone(),
+        };
+        self.nodes.insert(
+            key,
+            NodeEntry {
+                path,
+                what,
+                bindings,
+            },
+        );
+        for child in children {
+            self.walk(child);
+        }
+    }
+}
+
+fn enumerated(ids: &[NodeId], path: &str) -> Vec<(NodeId, String)> {
+    ids.iter()
+        .enumerate()
+        .map(|(i, id)| (*id, format!("{path}.{i}")))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Text rendering
+// ---------------------------------------------------------------------------
+
+/// A param value for reading: the payload without the `k` tag when there is
+/// exactly one (`"text"`, a model name, a tool list), else the compact JSON.
+fn value_text(value: &Value) -> String {
+    if let Value::Object(map) = value
+        && map.len() == 2
+        && map.contains_key("k")
+        && let Some((_, payload)) = map.iter().find(|(key, _)| *key != "k")
+    {
+        return payload.to_string();
+    }
+    value.to_string()
+}
+
+fn section<T>(
+    f: &mut fmt::Formatter<'_>,
+    title: &str,
+    changes: &[Change<T>],
+    text: impl Fn(&T) -> String,
+) -> fmt::Result {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{title}:")?;
+    for change in changes {
+        match (&change.from, &change.to) {
+            (None, Some(to)) => writeln!(f, "  + {}: {}", change.key, text(to))?,
+            (Some(from), None) => writeln!(f, "  - {}: {}", change.key, text(from))?,
+            (Some(from), Some(to)) => {
+                writeln!(f, "  ~ {}: {} → {}", change.key, text(from), text(to))?
+            }
+            (None, None) => writeln!(f, "  ~ {}", change.key)?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for ProgramDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "program {:016x} → {:016x}", self.from_hash, self.to_hash)?;
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        let text = |s: &String| s.clone();
+        section(f, "program", &self.program, text)?;
+        section(f, "nodes", &self.nodes, text)?;
+        section(f, "moved", &self.moved, text)?;
+        section(f, "bindings", &self.bindings, text)?;
+        section(f, "sigs", &self.sigs, text)?;
+        section(f, "tools", &self.tools, text)?;
+        section(f, "models", &self.models, text)?;
+        section(f, "params", &self.params, value_text)
+    }
+}
+
+impl fmt::Display for OverlayDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        section(f, "params", &self.params, value_text)
+    }
+}
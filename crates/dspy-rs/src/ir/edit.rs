@@ -185,6 +185,8 @@ pub enum ApplyError {
     NotInSeq { node: NodeId },
     #[error("{node} has no parent to rewire (detached by an earlier edit?)")]
     Unparented { node: NodeId },
+    #[error("{node} belongs to an imported program; edit it there and re-pin the import")]
+    Imported { node: NodeId },
 }
 
 // ---------------------------------------------------------------------------
@@ -196,7 +198,8 @@ impl Program {
     /// validated result. `self` is never mutated. The child gets a **new**
     /// content hash and `lineage.parent` set to `self`'s hash (like
     /// [`Program::bake`]); overlays minted against `self` must be re-minted
-    /// (see [`migrate_overlay`]).
+    /// (see [`migrate_overlay`]). Comments stay on the steps that survive;
+    /// a `WrapRetry` wrapper inherits its child's.
     pub fn edited(&self, edits: &[Edit]) -> Result<Program, EditError> {
         let mut work = self.clone();
         for (index, edit) in edits.iter().enumerate() {
@@ -245,11 +248,15 @@ impl Program {
     /// `Refine` judge (judges must stay bare leaves), `Remove` for `Seq`
     /// steps. Purely structural — data-flow legality (e.g. whether a removal
     /// orphans a downstream binding) is still `validate()`'s call. A stale id
-    /// yields an empty menu.
+    /// yields an empty menu, and so does a node an import spliced in.
     pub fn legal_edits(&self, at: NodeId) -> Vec<EditKind> {
         let Some(node) = self.nodes.get(at) else {
             return Vec::new();
         };
+        let spliced = self.spliced();
+        if spliced.nodes.contains(&at) {
+            return Vec::new();
+        }
         let mut out = Vec::new();
         match node {
             Node::Predict(_) => {
@@ -263,6 +270,9 @@ impl Program {
                 out.push(EditKind::SwapToPredict);
                 out.push(EditKind::SetStop);
                 for (tool, _) in self.tools.iter() {
+                    if spliced.tools.contains(&tool) {
+                        continue;
+                    }
                     if n.tools.contains(&tool) {
                         out.push(EditKind::RemoveTool { tool });
                     } else {
@@ -340,16 +350,17 @@ pub fn migrate_overlay(parent: &Program, overlay: &Overlay, child: &Program) ->
             continue;
         }
         let value = match value {
-            // Model refs are ordinals into `models`; re-mint by name.
-            ParamValue::ModelRef { model } => {
-                let Some(def) = parent.models.get(*model) else {
-                    continue;
-                };
-                let Some((child_model, _)) = child.models.iter().find(|(_, m)| m.name == def.name)
-                else {
+            // Model refs (and compaction models) are ordinals into
+            // `models`; re-mint by name.
+            ParamValue::ModelRef { .. } | ParamValue::ContextPolicy { .. } => {
+                let Some(value) = value.map_model(|model| {
+                    let def = parent.models.get(model)?;
+                    let (child_model, _) = child.models.iter().find(|(_, m)| m.name == def.name)?;
+                    Some(child_model)
+                }) else {
                     continue;
                 };
-                ParamValue::ModelRef { model: child_model }
+                value
             }
             // Tool sets carry ordinals into `tools`; re-mint each by name
             // and keep the intersection with what the child's agent still
@@ -418,6 +429,8 @@ fn owner_sig(p: &Program, owner: ParamOwner) -> Option<SigId> {
         ParamOwner::Node(id) => match p.nodes.get(id)? {
             Node::Predict(n) => Some(n.sig),
             Node::AgentLoop(n) => Some(n.sig),
+            Node::Transform(n) => Some(n.sig),
+            Node::Approve(n) => Some(n.sig),
             Node::Hole(n) => Some(n.sig),
             _ => None,
         },
@@ -430,6 +443,26 @@ fn owner_sig(p: &Program, owner: ParamOwner) -> Option<SigId> {
 // ---------------------------------------------------------------------------
 
 fn apply(work: &mut Program, edit: &Edit) -> Result<(), ApplyError> {
+    // Imported sub-graphs print as their import's pin: an edit inside one
+    // would never reach the canonical text.
+    let spliced = work.spliced();
+    let target = match edit {
+        Edit::AugmentSig { leaf, .. }
+        | Edit::SwapLeaf { leaf, .. }
+        | Edit::SetInstructionDefault { leaf, .. } => *leaf,
+        Edit::WrapRetry { node, .. } | Edit::Remove { node } => *node,
+        Edit::AddTool { agent, .. }
+        | Edit::RemoveTool { agent, .. }
+        | Edit::SetStop { agent, .. } => *agent,
+    };
+    if spliced.nodes.contains(&target) {
+        return Err(ApplyError::Imported { node: target });
+    }
+    if let Edit::AddTool { tool, .. } = edit
+        && spliced.tools.contains(tool)
+    {
+        return Err(ApplyError::UnknownTool { tool: *tool });
+    }
     match edit {
         Edit::AugmentSig { leaf, prepend } => augment_sig(work, *leaf, prepend),
         Edit::SwapLeaf { leaf, to } => swap_leaf(work, *leaf, to),
@@ -461,7 +494,7 @@ fn agent_mut(work: &mut Program, id: NodeId) -> Result<&mut AgentLoopNode, Apply
             return Err(ApplyError::WrongKind {
                 node: id,
                 expected: "an `agent` leaf",
-                got: kind_label(other),
+                got: other.keyword(),
             });
         }
     }
@@ -471,20 +504,6 @@ fn agent_mut(work: &mut Program, id: NodeId) -> Result<&mut AgentLoopNode, Apply
     }
 }
 
-fn kind_label(node: &Node) -> &'static str {
-    match node {
-        Node::Predict(_) => "predict",
-        Node::AgentLoop(_) => "agent",
-        Node::Hole(_) => "hole",
-        Node::Seq(_) => "seq",
-        Node::ForkJoin(_) => "fork",
-        Node::Route(_) => "route",
-        Node::Retry(_) => "retry",
-        Node::Refine(_) => "refine",
-        Node::Loop(_) => "loop",
-    }
-}
-
 fn augment_sig(work: &mut Program, leaf: NodeId, prepend: &FieldDef) -> Result<(), ApplyError> {
     let (sig_id, is_predict) = match node_checked(work, leaf)? {
         Node::Predict(n) => (n.sig, true),
@@ -493,7 +512,7 @@ fn augment_sig(work: &mut Program, leaf: NodeId, prepend: &FieldDef) -> Result<(
             return Err(ApplyError::WrongKind {
                 node: leaf,
                 expected: "a `predict` or `agent` leaf",
-                got: kind_label(other),
+                got: other.keyword(),
             });
         }
     };
@@ -604,6 +623,7 @@ fn swap_leaf(work: &mut Program, leaf: NodeId, to: &SwapTarget) -> Result<(), Ap
                 stop: stop.clone(),
                 budget: budget.clone(),
                 binding: n.binding,
+                memo: n.memo,
             });
             Ok(())
         }
@@ -617,18 +637,19 @@ fn swap_leaf(work: &mut Program, leaf: NodeId, to: &SwapTarget) -> Result<(), Ap
                 demos: n.demos,
                 model: n.model,
                 binding: n.binding,
+                memo: n.memo,
             });
             Ok(())
         }
         (other, SwapTarget::Agent { .. }) => Err(ApplyError::WrongKind {
             node: leaf,
             expected: "a `predict` leaf",
-            got: kind_label(&other),
+            got: other.keyword(),
         }),
         (other, SwapTarget::Predict) => Err(ApplyError::WrongKind {
             node: leaf,
             expected: "an `agent` leaf",
-            got: kind_label(&other),
+            got: other.keyword(),
         }),
     }
 }
@@ -676,6 +697,10 @@ fn wrap_retry(
             redirect_out_ports(n, node, retry);
         }
     }
+    // The wrapper takes over the step position, and the step's comments.
+    if let Some(trivia) = work.meta.comments.nodes.remove(&node) {
+        work.meta.comments.nodes.insert(retry, trivia);
+    }
     Ok(())
 }
 
@@ -754,7 +779,7 @@ fn set_instruction_default(work: &mut Program, leaf: NodeId, text: &str) -> Resu
             return Err(ApplyError::WrongKind {
                 node: leaf,
                 expected: "a `predict` or `agent` leaf",
-                got: kind_label(other),
+                got: other.keyword(),
             });
         }
     };
@@ -773,20 +798,27 @@ fn tool_checked(work: &Program, tool: ToolId) -> Result<&crate::ir::graph::ToolD
 // ---------------------------------------------------------------------------
 
 /// The structural children of a node — the same set `validate()` walks.
-fn structural_children(node: &Node) -> Vec<NodeId> {
+pub(crate) fn structural_children(node: &Node) -> Vec<NodeId> {
     match node {
-        Node::Predict(_) | Node::AgentLoop(_) | Node::Hole(_) => Vec::new(),
+        Node::Predict(_)
+        | Node::AgentLoop(_)
+        | Node::Transform(_)
+        | Node::Approve(_)
+        | Node::Hole(_) => Vec::new(),
         Node::Seq(n) => n.body.to_vec(),
         Node::ForkJoin(n) => n.branches.to_vec(),
         Node::Route(n) => n
             .arms
             .iter()
             .map(|(_, arm)| *arm)
+            .chain(n.when.iter().map(|(_, arm)| *arm))
             .chain(n.default)
             .collect(),
         Node::Retry(n) => vec![n.child],
         Node::Refine(n) => vec![n.child, n.judge],
         Node::Loop(n) => vec![n.body],
+        Node::Map(n) => vec![n.body],
+        Node::Call(n) => vec![n.body],
     }
 }
 
@@ -803,11 +835,16 @@ fn replace_child(node: &mut Node, from: NodeId, to: NodeId) -> bool {
         false
     };
     match node {
-        Node::Predict(_) | Node::AgentLoop(_) | Node::Hole(_) => false,
+        Node::Predict(_)
+        | Node::AgentLoop(_)
+        | Node::Transform(_)
+        | Node::Approve(_)
+        | Node::Hole(_) => false,
         Node::Seq(n) => slot_in(&mut n.body),
         Node::ForkJoin(n) => slot_in(&mut n.branches),
         Node::Route(n) => {
-            for (_, arm) in n.arms.iter_mut() {
+            let arms = n.arms.iter_mut().map(|(_, arm)| arm);
+            for arm in arms.chain(n.when.iter_mut().map(|(_, arm)| arm)) {
                 if *arm == from {
                     *arm = to;
                     return true;
@@ -846,6 +883,22 @@ fn replace_child(node: &mut Node, from: NodeId, to: NodeId) -> bool {
                 false
             }
         }
+        Node::Map(n) => {
+            if n.body == from {
+                n.body = to;
+                true
+            } else {
+                false
+            }
+        }
+        Node::Call(n) => {
+            if n.body == from {
+                n.body = to;
+                true
+            } else {
+                false
+            }
+        }
     }
 }
 
@@ -864,6 +917,8 @@ fn for_each_port(node: &mut Node, mut f: impl FnMut(&mut PortRef)) {
     match node {
         Node::Predict(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
         Node::AgentLoop(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
+        Node::Transform(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
+        Node::Approve(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
         Node::Hole(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
         Node::Seq(n) => n.out.iter_mut().for_each(|b| f(&mut b.src)),
         Node::ForkJoin(n) => n.join.iter_mut().for_each(|b| f(&mut b.src)),
@@ -876,6 +931,11 @@ fn for_each_port(node: &mut Node, mut f: impl FnMut(&mut PortRef)) {
             n.carry.iter_mut().for_each(|b| f(&mut b.src));
             n.out.iter_mut().for_each(|b| f(&mut b.src));
         }
+        Node::Map(n) => {
+            f(&mut n.over);
+            n.collect.iter_mut().for_each(|b| f(&mut b.src));
+        }
+        Node::Call(n) => n.binding.iter_mut().for_each(|b| f(&mut b.src)),
     }
 }
 
@@ -915,16 +975,26 @@ fn gc_nodes(work: &mut Program, reachable: &HashSet<NodeId>) -> HashMap<NodeId,
     }
     work.nodes = nodes;
     work.root = map[&work.root];
+    let comments = std::mem::take(&mut work.meta.comments.nodes);
+    work.meta.comments.nodes = comments
+        .into_iter()
+        .filter_map(|(id, trivia)| Some((*map.get(&id)?, trivia)))
+        .collect();
     map
 }
 
 fn remap_children(node: &mut Node, map: &HashMap<NodeId, NodeId>) {
     match node {
-        Node::Predict(_) | Node::AgentLoop(_) | Node::Hole(_) => {}
+        Node::Predict(_)
+        | Node::AgentLoop(_)
+        | Node::Transform(_)
+        | Node::Approve(_)
+        | Node::Hole(_) => {}
         Node::Seq(n) => n.body.iter_mut().for_each(|c| *c = map[c]),
         Node::ForkJoin(n) => n.branches.iter_mut().for_each(|c| *c = map[c]),
         Node::Route(n) => {
             n.arms.iter_mut().for_each(|(_, arm)| *arm = map[arm]);
+            n.when.iter_mut().for_each(|(_, arm)| *arm = map[arm]);
             if let Some(default) = &mut n.default {
                 *default = map[default];
             }
@@ -935,6 +1005,8 @@ fn remap_children(node: &mut Node, map: &HashMap<NodeId, NodeId>) {
             n.judge = map[&n.judge];
         }
         Node::Loop(n) => n.body = map[&n.body],
+        Node::Map(n) => n.body = map[&n.body],
+        Node::Call(n) => n.body = map[&n.body],
     }
 }
 
@@ -955,9 +1027,18 @@ fn referenced_sigs(p: &Program) -> HashSet<SigId> {
             Node::AgentLoop(n) => {
                 set.insert(n.sig);
             }
+            Node::Transform(n) => {
+                set.insert(n.sig);
+            }
+            Node::Approve(n) => {
+                set.insert(n.sig);
+            }
             Node::Hole(n) => {
                 set.insert(n.sig);
             }
+            Node::Call(n) => {
+                set.insert(n.sig);
+            }
             _ => {}
         }
     }
@@ -1016,7 +1097,10 @@ fn gc_sigs(work: &mut Program, parent: &Program) {
         match node {
             Node::Predict(n) => n.sig = map[&n.sig],
             Node::AgentLoop(n) => n.sig = map[&n.sig],
+            Node::Transform(n) => n.sig = map[&n.sig],
+            Node::Approve(n) => n.sig = map[&n.sig],
             Node::Hole(n) => n.sig = map[&n.sig],
+            Node::Call(n) => n.sig = map[&n.sig],
             _ => {}
         }
     }
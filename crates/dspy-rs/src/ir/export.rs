@@ -0,0 +1,679 @@
+//! Module → program export: recovers a `.dsrs` [`Program`] from a typed
+//! [`Module`] by watching it run.
+//!
+//! A hand-written `forward` is opaque Rust, so the exporter observes it
+//! instead of parsing it. Each sample input runs under
+//! [`capture`](crate::trace::capture); the root spans give the leaf sequence
+//! (component = the name [`Predictors`] declared), and field-level dataflow is
+//! recovered by value: a leaf input that equals a program input or an earlier
+//! node's output on *every* sample is bound to that port. Whatever no port
+//! explains was computed by Rust the trace cannot see, and becomes an extern
+//! [`Hole`](crate::ir::Node::Hole) in front of the node that reads it — so
+//! the exported program is honest about the code it does not carry. Loading
+//! it requires a host binding per such hole, exactly as for `#[module]`.
+//!
+//! The inference is only as good as the samples: two fields that happen to
+//! agree on every sample are indistinguishable, so feed inputs that vary.
+//! Control flow is not recovered — every sample must visit the same leaves in
+//! the same order, each at most once, or the export is refused.
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::{Module, PredictorInfo, Predictors};
+use crate::ir::builder::{self, BuildError, NodeSpec, Port, ProgramBuilder};
+use crate::ir::graph::{Program, SigId};
+use crate::ir::module_build::unbound_model_config;
+use crate::ir::params::DemoRow;
+use crate::ir::sig::{SigError, SignatureDef};
+use crate::trace::{JsonMap, SpanEvent, capture};
+use crate::typesys::{FieldType, OutputSchema, Schema};
+use crate::utils::hash::stable_hash_debug;
+
+/// Why a module could not be exported as a program.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("export needs at least one sample input")]
+    NoSamples,
+    #[error("sample {sample}: {what} does not serialize to a JSON object")]
+    NotAnObject { sample: usize, what: &'static str },
+    #[error("sample {sample}: the module failed: {message}")]
+    Run { sample: usize, message: String },
+    #[error(
+        "sample {sample}: a span was recorded for `{component}`, which is not \
+         among the module's declared predictors"
+    )]
+    UnknownLeaf { sample: usize, component: String },
+    #[error(
+        "sample {sample}: leaf `{leaf}` ran more than once — loops and retries \
+         are not recovered from traces"
+    )]
+    RepeatedLeaf { sample: usize, leaf: String },
+    #[error(
+        "sample {sample}: leaf `{leaf}` ran tools — tool-using leaves are not \
+         exported"
+    )]
+    ToolLeaf { sample: usize, leaf: String },
+    #[error(
+        "sample {sample} ran leaves [{got}], but sample 0 ran [{expected}] — \
+         data-dependent control flow is not recovered from traces"
+    )]
+    Divergent {
+        sample: usize,
+        expected: String,
+        got: String,
+    },
+    #[error("leaf name `{name}` is not a valid .dsrs identifier")]
+    LeafName { name: String },
+    #[error("leaf `{leaf}` exposes no signature definition to rebuild it from")]
+    NoSignature { leaf: String },
+    #[error("field `{field}` has no inferable type (no declaration and a non-scalar value)")]
+    FieldType { field: String },
+    #[error("signature `{name}` is declared twice with different shapes")]
+    SigConflict { name: String },
+    #[error(transparent)]
+    Sig(#[from] SigError),
+    #[error(transparent)]
+    Build(#[from] BuildError),
+}
+
+/// Exports `module` as a `.dsrs` program named `name`, inferring its leaf
+/// graph and field-level dataflow from traced runs over `samples`.
+///
+/// Runs the naming pass first, so leaves record (and the program declares)
+/// the names [`Predictors`] gives them. The samples run for real against
+/// the module's configured LM; use a replay scope or a test client to keep
+/// the export offline. Each leaf keeps its signature, instruction override,
+/// and demos; all leaves share one unbound `default` model ref.
+pub async fn export_module<M>(
+    module: &mut M,
+    name: &str,
+    samples: impl IntoIterator<Item = M::Input>,
+) -> Result<Program, ExportError>
+where
+    M: Module + Predictors,
+{
+    for (leaf, info) in module.predictors_mut() {
+        info.set_trace_name(&leaf);
+    }
+    let module: &M = module;
+
+    // ---- observe -------------------------------------------------------
+    let mut runs: Vec<Observed> = Vec::new();
+    for (sample, input) in samples.into_iter().enumerate() {
+        let input_map = json_object(&input, sample, "the sample input")?;
+        let (result, trace) = capture(|| module.call(input)).await;
+        let predicted = result.map_err(|err| ExportError::Run {
+            sample,
+            message: err.to_string(),
+        })?;
+        let output = json_object(&*predicted, sample, "the module output")?;
+
+        let declared = module.predictors();
+        let mut leaves: IndexMap<String, (JsonMap, JsonMap)> = IndexMap::new();
+        for span in trace.spans.iter().filter(|span| span.parent.is_none()) {
+            let component = trace.components[span.component.0 as usize].clone();
+            if !declared.iter().any(|(leaf, _)| *leaf == component) {
+                return Err(ExportError::UnknownLeaf { sample, component });
+            }
+            if let Some(error) = &span.error {
+                return Err(ExportError::Run {
+                    sample,
+                    message: format!("leaf `{component}`: {}", error.message),
+                });
+            }
+            if span
+                .events
+                .iter()
+                .any(|event| matches!(event, SpanEvent::ToolRun { .. }))
+            {
+                return Err(ExportError::ToolLeaf {
+                    sample,
+                    leaf: component,
+                });
+            }
+            if leaves.contains_key(&component) {
+                return Err(ExportError::RepeatedLeaf {
+                    sample,
+                    leaf: component,
+                });
+            }
+            let io = (
+                span.input.clone().unwrap_or_default(),
+                span.output.clone().unwrap_or_default(),
+            );
+            leaves.insert(component, io);
+        }
+
+        if let Some(first) = runs.first()
+            && !first.leaves.keys().eq(leaves.keys())
+        {
+            return Err(ExportError::Divergent {
+                sample,
+                expected: join(first.leaves.keys()),
+                got: join(leaves.keys()),
+            });
+        }
+        runs.push(Observed {
+            input: input_map,
+            leaves,
+            output,
+        });
+    }
+    let Some(first) = runs.first() else {
+        return Err(ExportError::NoSamples);
+    };
+
+    // ---- declared shapes -----------------------------------------------
+    let declared = module.predictors();
+    let leaves: Vec<Leaf<'_>> = first
+        .leaves
+        .keys()
+        .map(|component| {
+            let info = declared
+                .iter()
+                .find(|(leaf, _)| leaf == component)
+                .map(|(_, info)| *info)
+                .expect("observed leaves were checked against the declared ones");
+            if !is_ident(component) {
+                return Err(ExportError::LeafName {
+                    name: component.clone(),
+                });
+            }
+            let def = info
+                .signature_def()
+                .ok_or_else(|| ExportError::NoSignature {
+                    leaf: component.clone(),
+                })?;
+            Ok(Leaf {
+                name: component.clone(),
+                info,
+                def,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    let input_schema = <M::Input as Schema>::output_schema();
+    let output_schema = <M::Output as Schema>::output_schema();
+    let input_fields = field_names(&input_schema, runs.iter().map(|run| &run.input));
+    let output_fields = field_names(&output_schema, runs.iter().map(|run| &run.output));
+
+    // ---- infer dataflow ------------------------------------------------
+    // Nodes in program order: each leaf, preceded by a hole when some of its
+    // inputs are untraced; a trailing hole for untraced Main outputs.
+    let mut taken: Vec<String> = leaves.iter().map(|leaf| leaf.name.clone()).collect();
+    taken.extend(input_fields.iter().cloned());
+    let mut nodes: Vec<Inferred> = Vec::new();
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let wanted: Vec<(String, FieldType)> = leaf
+            .def
+            .inputs
+            .iter()
+            .map(|field| (field.name.to_string(), field.ty.clone()))
+            .collect();
+        let values = |sample: usize, field: &str| -> Value {
+            runs[sample].leaves[index]
+                .0
+                .get(field)
+                .cloned()
+                .unwrap_or(Value::Null)
+        };
+        let (binds, hole) = trace_fields(
+            &format!("{}_prep", leaf.name),
+            Some(index),
+            &mut taken,
+            &runs,
+            &input_fields,
+            &nodes,
+            &wanted,
+            values,
+        );
+        if let Some(hole) = hole {
+            nodes.push(hole);
+        }
+        nodes.push(Inferred {
+            name: leaf.name.clone(),
+            kind: InferredKind::Leaf(index),
+            binds,
+            outputs: leaf
+                .def
+                .outputs
+                .iter()
+                .map(|field| (field.name.to_string(), field.ty.clone()))
+                .collect(),
+        });
+    }
+
+    let output_types = output_fields
+        .iter()
+        .map(|field| {
+            let ty = declared_field_type(&output_schema, field)
+                .or_else(|| value_type(runs.iter().map(|run| run.output.get(field))))
+                .ok_or_else(|| ExportError::FieldType {
+                    field: field.clone(),
+                })?;
+            Ok((field.clone(), ty))
+        })
+        .collect::<Result<Vec<_>, ExportError>>()?;
+    let (outs, finish) = trace_fields(
+        "finish",
+        None,
+        &mut taken,
+        &runs,
+        &input_fields,
+        &nodes,
+        &output_types,
+        |sample, field| {
+            runs[sample]
+                .output
+                .get(field)
+                .cloned()
+                .unwrap_or(Value::Null)
+        },
+    );
+    if let Some(hole) = finish {
+        nodes.push(hole);
+    }
+
+    // ---- types ---------------------------------------------------------
+    // Program inputs: the declared input type, else the first leaf field they
+    // feed, else the sampled value's scalar type.
+    let mut input_types: Vec<(String, FieldType)> = Vec::new();
+    for field in &input_fields {
+        let fed = nodes.iter().find_map(|node| {
+            let InferredKind::Leaf(index) = node.kind else {
+                return None;
+            };
+            let (to, _) = node
+                .binds
+                .iter()
+                .find(|(_, from)| matches!(from, Source::Input(name) if name == field))?;
+            leaves[index]
+                .def
+                .inputs
+                .iter()
+                .find(|f| &*f.name == to)
+                .map(|f| f.ty.clone())
+        });
+        let ty = declared_field_type(&input_schema, field)
+            .or(fed)
+            .or_else(|| value_type(runs.iter().map(|run| run.input.get(field))))
+            .ok_or_else(|| ExportError::FieldType {
+                field: field.clone(),
+            })?;
+        input_types.push((field.clone(), ty));
+    }
+    let source_type = |source: &Source| -> FieldType {
+        match source {
+            Source::Input(field) => input_types
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, ty)| ty.clone())
+                .expect("input sources name program inputs"),
+            Source::Out { node, field } => nodes[*node]
+                .outputs
+                .iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, ty)| ty.clone())
+                .expect("output sources name node outputs"),
+        }
+    };
+
+    // ---- build ---------------------------------------------------------
+    let mut b = ProgramBuilder::new(name);
+    let model = b.model("default", unbound_model_config("default"));
+    b.add_types(&input_schema.types);
+    b.add_types(&output_schema.types);
+
+    let mut sig_ids: IndexMap<String, (SigId, SignatureDef)> = IndexMap::new();
+    let mut register = |b: &mut ProgramBuilder, def: SignatureDef| -> Result<SigId, ExportError> {
+        if let Some((id, existing)) = sig_ids.get(&*def.name) {
+            if *existing == def {
+                return Ok(*id);
+            }
+            return Err(ExportError::SigConflict {
+                name: def.name.to_string(),
+            });
+        }
+        let name = def.name.to_string();
+        let id = b.sig(def.clone());
+        sig_ids.insert(name, (id, def));
+        Ok(id)
+    };
+    let as_port = |source: &Source| -> Port {
+        match source {
+            Source::Input(field) => builder::input(field),
+            Source::Out { node, field } => builder::out(nodes[*node].name.as_str(), field),
+        }
+    };
+
+    let mut specs: Vec<NodeSpec> = Vec::new();
+    for node in &nodes {
+        let ns = match &node.kind {
+            InferredKind::Leaf(index) => {
+                let leaf = &leaves[*index];
+                b.add_types(&leaf.info.signature_types());
+                let sid = register(&mut b, leaf.def.clone())?;
+                let mut ns = builder::predict(&leaf.name, sid).model(model);
+                let instruction = leaf.info.instruction();
+                if instruction != leaf.info.default_instruction() {
+                    ns = ns.instruction(&instruction);
+                }
+                let demos = leaf.info.demos_as_json();
+                if !demos.is_empty() {
+                    ns = ns.demos(
+                        demos
+                            .into_iter()
+                            .map(|row| split_demo(leaf.def, row))
+                            .collect(),
+                    );
+                }
+                for (field, source) in &node.binds {
+                    ns = ns.bind(field, as_port(source));
+                }
+                ns
+            }
+            InferredKind::Hole { reads, .. } => {
+                let mut sb = SignatureDef::build(&format!("{}_hole", node.name));
+                for (field, source) in reads {
+                    sb = sb.input(field, source_type(source));
+                }
+                for (field, ty) in &node.outputs {
+                    sb = sb.output(field, ty.clone());
+                }
+                let def = sb.finish()?;
+                let hash = stable_hash_debug(&(name, &def));
+                let sid = register(&mut b, def)?;
+                let mut ns = builder::extern_hole(&node.name, sid, hash, &[]);
+                for (field, source) in reads {
+                    ns = ns.bind(field, as_port(source));
+                }
+                ns
+            }
+        };
+        specs.push(ns);
+    }
+
+    let mut mb = SignatureDef::build("Main");
+    for (field, ty) in &input_types {
+        mb = mb.input(field, ty.clone());
+    }
+    for (field, source) in &outs {
+        mb = mb.output(field, source_type(source));
+    }
+    let main_sid = register(&mut b, mb.finish()?)?;
+    let mut root = builder::seq(specs);
+    for (field, source) in &outs {
+        root = root.out(field, as_port(source));
+    }
+    Ok(b.main(main_sid, root)?)
+}
+
+/// One traced sample: program input, per-leaf `(input, output)` in run
+/// order, and the module's output.
+struct Observed {
+    input: JsonMap,
+    leaves: IndexMap<String, (JsonMap, JsonMap)>,
+    output: JsonMap,
+}
+
+struct Leaf<'a> {
+    name: String,
+    info: &'a dyn PredictorInfo,
+    def: &'static SignatureDef,
+}
+
+/// Where a traced value comes from.
+#[derive(Clone, Debug)]
+enum Source {
+    Input(String),
+    Out { node: usize, field: String },
+}
+
+struct Inferred {
+    name: String,
+    kind: InferredKind,
+    /// Bound field → source (leaves only; holes carry theirs in `reads`).
+    binds: Vec<(String, Source)>,
+    /// Output fields and their types.
+    outputs: Vec<(String, FieldType)>,
+}
+
+enum InferredKind {
+    /// Index into the observed leaf sequence.
+    Leaf(usize),
+    /// An extern hole reading every upstream port.
+    Hole {
+        reads: Vec<(String, Source)>,
+        /// The leaf whose untraced inputs it produces; `None` for the
+        /// trailing hole in front of Main's outputs.
+        feeds: Option<usize>,
+    },
+}
+
+/// Traces each of `wanted` back to a port whose value agrees on every sample
+/// — the nearest upstream node first, then the program input — and gathers
+/// the rest into one extern hole named (uniquely) after `hole_name`. The
+/// hole's outputs are the untraced fields themselves; the returned binds
+/// point those fields at it.
+#[allow(clippy::too_many_arguments)]
+fn trace_fields(
+    hole_name: &str,
+    feeds: Option<usize>,
+    taken: &mut Vec<String>,
+    runs: &[Observed],
+    input_fields: &[String],
+    nodes: &[Inferred],
+    wanted: &[(String, FieldType)],
+    value: impl Fn(usize, &str) -> Value,
+) -> (Vec<(String, Source)>, Option<Inferred>) {
+    let node_value = |sample: usize, node: &Inferred, field: &str| -> Value {
+        let side = match node.kind {
+            InferredKind::Leaf(index) => &runs[sample].leaves[index].1,
+            // A hole's outputs are the untraced inputs of the leaf it feeds.
+            InferredKind::Hole {
+                feeds: Some(index), ..
+            } => &runs[sample].leaves[index].0,
+            InferredKind::Hole { feeds: None, .. } => return Value::Null,
+        };
+        side.get(field).cloned().unwrap_or(Value::Null)
+    };
+
+    let mut binds = Vec::new();
+    let mut untraced = Vec::new();
+    for (field, ty) in wanted {
+        // Nulls on every sample match any absent port: that is no evidence of
+        // where the field comes from, so it goes to the hole.
+        let seen = (0..runs.len()).any(|sample| !value(sample, field).is_null());
+        let agrees = |source_value: &dyn Fn(usize) -> Value| {
+            seen && (0..runs.len()).all(|sample| source_value(sample) == value(sample, field))
+        };
+        let mut found = None;
+        'nodes: for (index, node) in nodes.iter().enumerate().rev() {
+            // Same-named output first, then declaration order.
+            let mut candidates: Vec<&String> = node.outputs.iter().map(|(name, _)| name).collect();
+            candidates.sort_by_key(|name| *name != field);
+            for candidate in candidates {
+                if agrees(&|sample| node_value(sample, node, candidate)) {
+                    found = Some(Source::Out {
+                        node: index,
+                        field: candidate.clone(),
+                    });
+                    break 'nodes;
+                }
+            }
+        }
+        if found.is_none() {
+            let mut candidates: Vec<&String> = input_fields.iter().collect();
+            candidates.sort_by_key(|name| *name != field);
+            found = candidates
+                .into_iter()
+                .find(|candidate| {
+                    agrees(&|sample| {
+                        runs[sample]
+                            .input
+                            .get(candidate.as_str())
+                            .cloned()
+                            .unwrap_or(Value::Null)
+                    })
+                })
+                .map(|candidate| Source::Input(candidate.clone()));
+        }
+        match found {
+            Some(source) => binds.push((field.clone(), source)),
+            None => untraced.push((field.clone(), ty.clone())),
+        }
+    }
+    if untraced.is_empty() {
+        return (binds, None);
+    }
+
+    let name = unique_name(hole_name, taken);
+    let hole_index = nodes.len();
+    let mut reads: Vec<(String, Source)> = input_fields
+        .iter()
+        .map(|field| (field.clone(), Source::Input(field.clone())))
+        .collect();
+    for (index, node) in nodes.iter().enumerate() {
+        for (field, _) in &node.outputs {
+            reads.push((
+                format!("{}_{field}", node.name),
+                Source::Out {
+                    node: index,
+                    field: field.clone(),
+                },
+            ));
+        }
+    }
+    for (field, _) in &untraced {
+        binds.push((
+            field.clone(),
+            Source::Out {
+                node: hole_index,
+                field: field.clone(),
+            },
+        ));
+    }
+    let hole = Inferred {
+        name,
+        kind: InferredKind::Hole { reads, feeds },
+        binds: Vec::new(),
+        outputs: untraced,
+    };
+    (binds, Some(hole))
+}
+
+/// Splits a flat demo row into the leaf's input and output sides.
+fn split_demo(def: &SignatureDef, mut row: JsonMap) -> DemoRow {
+    let mut input = JsonMap::new();
+    for field in def.inputs.iter() {
+        if let Some(value) = row.remove(&*field.name) {
+            input.insert(field.name.to_string(), value);
+        }
+    }
+    DemoRow { input, output: row }
+}
+
+/// Field names of a module input/output type: the declared class fields
+/// when the type is a class, else the keys the samples serialized.
+fn field_names<'a>(
+    schema: &OutputSchema,
+    samples: impl Iterator<Item = &'a JsonMap>,
+) -> Vec<String> {
+    if let FieldType::Class(token) = &schema.target
+        && let Some(class) = schema.types.classes.get(token)
+    {
+        return class
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect();
+    }
+    let mut names: Vec<String> = Vec::new();
+    for sample in samples {
+        for key in sample.keys() {
+            if !names.contains(key) {
+                names.push(key.clone());
+            }
+        }
+    }
+    names
+}
+
+fn declared_field_type(schema: &OutputSchema, field: &str) -> Option<FieldType> {
+    let FieldType::Class(token) = &schema.target else {
+        return None;
+    };
+    let class = schema.types.classes.get(token)?;
+    class
+        .fields
+        .iter()
+        .find(|f| f.name == field)
+        .map(|f| f.field_type.clone())
+}
+
+/// The scalar type every sampled value agrees on; `None` for anything else.
+fn value_type<'a>(values: impl Iterator<Item = Option<&'a Value>>) -> Option<FieldType> {
+    let mut found: Option<FieldType> = None;
+    let mut optional = false;
+    for value in values {
+        let ty = match value {
+            None | Some(Value::Null) => {
+                optional = true;
+                continue;
+            }
+            Some(Value::String(_)) => FieldType::String,
+            Some(Value::Bool(_)) => FieldType::Bool,
+            Some(Value::Number(n)) if n.is_i64() || n.is_u64() => FieldType::Int,
+            Some(Value::Number(_)) => FieldType::Float,
+            Some(_) => return None,
+        };
+        match &found {
+            None => found = Some(ty),
+            Some(FieldType::Int) if ty == FieldType::Float => found = Some(ty),
+            Some(FieldType::Float) if ty == FieldType::Int => {}
+            Some(existing) if *existing != ty => return None,
+            Some(_) => {}
+        }
+    }
+    let ty = found?;
+    Some(if optional {
+        FieldType::optional(ty)
+    } else {
+        ty
+    })
+}
+
+fn json_object<T: Serialize + ?Sized>(
+    value: &T,
+    sample: usize,
+    what: &'static str,
+) -> Result<JsonMap, ExportError> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err(ExportError::NotAnObject { sample, what }),
+    }
+}
+
+fn unique_name(base: &str, taken: &mut Vec<String>) -> String {
+    let mut name = base.to_string();
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("{base}{n}");
+        n += 1;
+    }
+    taken.push(name.clone());
+    name
+}
+
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn join<'a>(names: impl Iterator<Item = &'a String>) -> String {
+    names.map(String::as_str).collect::<Vec<_>>().join(", ")
+}
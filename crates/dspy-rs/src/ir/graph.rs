@@ -5,7 +5,8 @@
 //! Nodes form a tree (single parent, single use); fan-in happens through field
 //! references, never shared nodes. Leaf nodes (`Predict`, `AgentLoop`, `Hole`)
 //! carry a mandatory, program-unique name — the trace component name and the
-//! `ParamPath` prefix. Containers are anonymous.
+//! `ParamPath` prefix. `Call` nodes are named the same way — the name is the
+//! namespace of the embedded program. Containers are anonymous.
 //!
 //! # Serialization
 //!
@@ -16,7 +17,7 @@
 //! recomputed, and [`Program::validate`] runs — a file that does not validate
 //! does not load.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use cranelift_entity::{PrimaryMap, entity_impl};
 use serde::{Deserialize, Serialize};
@@ -59,6 +60,11 @@ entity_impl!(ModelId, "m");
 pub struct ToolId(u32);
 entity_impl!(ToolId, "t");
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ImportId(u32);
+entity_impl!(ImportId, "imp");
+
 // ---------------------------------------------------------------------------
 // Interner
 // ---------------------------------------------------------------------------
@@ -251,6 +257,10 @@ pub enum Node {
     Retry(RetryNode),
     Refine(RefineNode),
     Loop(LoopNode),
+    Map(MapNode),
+    Call(CallNode),
+    Transform(TransformNode),
+    Approve(ApproveNode),
     Hole(HoleNode),
 }
 
@@ -267,6 +277,8 @@ pub struct PredictNode {
     /// `ParamKind::ModelRef`.
     pub model: ParamId,
     pub binding: Box<[Binding]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<MemoPolicy>,
 }
 
 /// The LLM+tool loop as the first-class unit.
@@ -289,6 +301,8 @@ pub struct AgentLoopNode {
     pub stop: StopSpec,
     pub budget: NodeBudget,
     pub binding: Box<[Binding]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<MemoPolicy>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -331,6 +345,18 @@ pub enum BudgetPolicy {
     Finalize,
 }
 
+/// Leaf memoization (`cache { ttl_s 3600 }`): the interpreter serves a
+/// leaf from an earlier evaluation with the same resolved inputs and the
+/// same resolved parameter values, instead of re-running it. Memos live on
+/// the loaded interpreter, shared by its runs and overlays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoPolicy {
+    /// Entries older than this many seconds are re-evaluated. `None` keeps
+    /// them for the interpreter's lifetime.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_s: Option<u64>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SeqNode {
     pub body: Box<[NodeId]>,
@@ -347,12 +373,18 @@ pub struct ForkJoinNode {
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RouteNode {
-    /// Must resolve to an Enum-typed (or Literal-union) field.
+    /// Must resolve to an Enum-typed (or Literal-union) field when routing on
+    /// variants; predicate arms accept any type.
     pub on: PortRef,
     /// (variant name, arm). Arms must export identical field name/type sets;
     /// that set is the RouteNode's output interface.
     pub arms: Box<[(Sym, NodeId)]>,
-    /// Required unless arms cover the enum.
+    /// (predicate, arm), tried in order: `#[check]`-style expressions with
+    /// the `on` value bound as `this`; the first that holds wins. A route has
+    /// variant arms or predicate arms, never both.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub when: Box<[(Box<str>, NodeId)]>,
+    /// Required unless arms cover the enum; always required with predicates.
     pub default: Option<NodeId>,
 }
 
@@ -391,6 +423,79 @@ pub struct LoopNode {
     pub out: Box<[Binding]>,
 }
 
+/// Runs `body` once per element of a list port, at most `max_parallel`
+/// elements at a time. Each element's body sees the enclosing `$` scope plus
+/// `$.<item>`; `collect` ports resolve in that element's final scope and the
+/// map exports one `List<T>` per collected field, in element order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MapNode {
+    /// Must resolve to a `List<T>`-typed port; `$.<item>` is `T`.
+    pub over: PortRef,
+    pub item: Sym,
+    pub body: NodeId,
+    pub max_parallel: std::num::NonZeroU32,
+    #[serde(default)]
+    pub on_error: MapErrorPolicy,
+    pub collect: Box<[Binding]>,
+}
+
+/// What a [`MapNode`] does when one element's body fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapErrorPolicy {
+    /// The element's error fails the map; in-flight elements are dropped.
+    #[default]
+    Fail,
+    /// Elements failing with a retryable error (`Lm`, `Parse`, `Tool`,
+    /// `Hole`) are left out of every collected list, which stay aligned.
+    /// Anything else — budget exhaustion included — still fails the map.
+    Skip,
+}
+
+/// Another program embedded as a typed sub-graph (`name = call alias (...)`).
+/// The callee's nodes, signatures, models, tools, and params are spliced into
+/// this program's arenas with every name prefixed `<name>.` — its genes are
+/// addressable as `"<name>.<leaf>.instruction"` / `"tool.<name>.<tool>.desc"`,
+/// and its extern holes bind as `"<name>.<leaf>"`. The body sees only the
+/// bound `sig` inputs as its `$` scope and exports `sig`'s outputs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CallNode {
+    pub name: Sym,
+    pub import: ImportId,
+    /// The callee's program signature.
+    pub sig: SigId,
+    pub binding: Box<[Binding]>,
+    /// The callee's root `Seq`.
+    pub body: NodeId,
+}
+
+/// Pure data reshaping: every output of `sig` is a minijinja expression over
+/// the signature inputs, bound like any leaf's. No LM call, no sandbox, no
+/// params — a transform is deterministic and records no trace span.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransformNode {
+    pub name: Sym,
+    pub sig: SigId,
+    pub binding: Box<[Binding]>,
+    /// (output field, expression), one per signature output.
+    pub exprs: Box<[(Sym, Box<str>)]>,
+}
+
+/// A human sign-off point. The bound signature inputs are the pending
+/// payload; a suspendable run ([`Interpreter::run_suspendable`]) stops here
+/// until the caller approves, rejects, or edits it. Every signature output
+/// passes through the same-named input (or the caller's edit), and the node
+/// additionally exports `decision` (`"approved" | "edited" | "rejected"`,
+/// routable) and `reason: string?`.
+///
+/// [`Interpreter::run_suspendable`]: crate::ir::Interpreter::run_suspendable
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApproveNode {
+    pub name: Sym,
+    pub sig: SigId,
+    pub binding: Box<[Binding]>,
+}
+
 /// LACUNA-style typed hole: opaque-but-typed code. The optimizer sees a
 /// signature (and, when sandboxed, a Code gene); the type system sees a
 /// normal node.
@@ -402,6 +507,8 @@ pub struct HoleNode {
     /// ⊆ `program.caps`.
     pub caps: CapSet,
     pub binding: Box<[Binding]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<MemoPolicy>,
 }
 
 /// How a hole is implemented (RFC 0003 §4.1) — mirrors [`ToolKind`].
@@ -424,10 +531,42 @@ impl Node {
         match self {
             Node::Predict(n) => Some(n.name),
             Node::AgentLoop(n) => Some(n.name),
+            Node::Call(n) => Some(n.name),
+            Node::Transform(n) => Some(n.name),
+            Node::Approve(n) => Some(n.name),
             Node::Hole(n) => Some(n.name),
             _ => None,
         }
     }
+
+    /// The leaf's memo policy, if it has one (`predict`, `agent`, `hole`).
+    pub fn memo(&self) -> Option<MemoPolicy> {
+        match self {
+            Node::Predict(n) => n.memo,
+            Node::AgentLoop(n) => n.memo,
+            Node::Hole(n) => n.memo,
+            _ => None,
+        }
+    }
+
+    /// The `.dsrs` keyword of this node's kind (`predict`, `agent`, `seq`, …).
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Node::Predict(_) => "predict",
+            Node::AgentLoop(_) => "agent",
+            Node::Transform(_) => "transform",
+            Node::Approve(_) => "approve",
+            Node::Hole(_) => "hole",
+            Node::Seq(_) => "seq",
+            Node::ForkJoin(_) => "fork",
+            Node::Route(_) => "route",
+            Node::Retry(_) => "retry",
+            Node::Refine(_) => "refine",
+            Node::Loop(_) => "loop",
+            Node::Map(_) => "map",
+            Node::Call(_) => "call",
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -443,6 +582,52 @@ pub struct ProgramMeta {
     /// the lineage block. Overlays, traces, and state artifacts reference it.
     pub program_hash: u64,
     pub lineage: Option<Lineage>,
+    /// `//` comments carried over from `.dsrs` source. Printed by
+    /// [`Program::to_dsrs`], never part of the hash preimage.
+    #[serde(default, skip_serializing_if = "Comments::is_empty")]
+    pub comments: Comments,
+}
+
+/// Source comments, attached to what they sit next to so they survive
+/// `fmt`, [`Program::bake`] and [`Program::edited`]. Comments inside a
+/// declaration's body, or inside a step that has no steps of its own, are
+/// reprinted above it; comments after a declaration's closing token on the
+/// same line stay there.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comments {
+    /// Comment lines above `dsrs 1` (and through the `program` line).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header: Vec<Box<str>>,
+    /// Top-level declarations, keyed `"<keyword> <name>"` (`"sig Main"`,
+    /// `"model fast"`, `"import retrieve"`) or by keyword alone (`"caps"`,
+    /// `"lineage"`).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub decls: HashMap<Box<str>, Trivia>,
+    /// `seq`/`fork` steps, and the root for the comments above `main`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub nodes: HashMap<NodeId, Trivia>,
+    /// Comment lines after `main`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub footer: Vec<Box<str>>,
+}
+
+impl Comments {
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.decls.is_empty()
+            && self.nodes.is_empty()
+            && self.footer.is_empty()
+    }
+}
+
+/// The comments attached to one declaration or step. Texts are stored
+/// without the leading `//`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trivia {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leading: Vec<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trailing: Option<Box<str>>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -463,6 +648,29 @@ pub struct Lineage {
     pub overlay: Option<Box<str>>,
 }
 
+/// An imported `.dsrs` file (`import "retrieve.dsrs" as retrieve`).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImportDef {
+    pub alias: Box<str>,
+    /// As written, relative to the importing file.
+    pub path: Box<str>,
+    /// The callee's `program_hash` when it was spliced in. The canonical text
+    /// prints it as the import's `pin`, so it is part of this program's hash
+    /// preimage: editing the imported file changes the importer's hash.
+    pub hash: u64,
+}
+
+/// The arena entries owned by `call` nodes ([`Program::spliced`]).
+#[derive(Debug, Default)]
+pub(crate) struct Spliced {
+    pub nodes: HashSet<NodeId>,
+    pub sigs: HashSet<SigId>,
+    pub params: HashSet<ParamId>,
+    pub models: HashSet<ModelId>,
+    pub tools: HashSet<ToolId>,
+    pub imports: HashSet<ImportId>,
+}
+
 /// A loaded IR program: arenas + interner + capability ceiling.
 ///
 /// Owns its signature arena and type table outright — nothing constructed at
@@ -475,6 +683,7 @@ pub struct Program {
     pub params: PrimaryMap<ParamId, ParamSlot>,
     pub models: PrimaryMap<ModelId, ModelDef>,
     pub tools: PrimaryMap<ToolId, ToolDef>,
+    pub imports: PrimaryMap<ImportId, ImportDef>,
     pub types: TypeTable,
     pub syms: Interner,
     /// The program's capability ceiling.
@@ -521,6 +730,64 @@ impl Program {
         self.nodes[id].leaf_name().map(|sym| self.syms.get(sym))
     }
 
+    /// Everything `call` nodes spliced in: the entries an import owns. The
+    /// canonical printer skips them (the import's `pin` stands for them), and
+    /// bake/edit refuse to touch them.
+    pub(crate) fn spliced(&self) -> Spliced {
+        let mut out = Spliced::default();
+        let mut stack: Vec<NodeId> = Vec::new();
+        for (_, node) in self.nodes.iter() {
+            if let Node::Call(n) = node {
+                out.sigs.insert(n.sig);
+                stack.push(n.body);
+            }
+        }
+        while let Some(id) = stack.pop() {
+            if !out.nodes.insert(id) {
+                continue;
+            }
+            let node = &self.nodes[id];
+            stack.extend(crate::ir::edit::structural_children(node));
+            match node {
+                Node::Predict(n) => {
+                    out.sigs.insert(n.sig);
+                }
+                Node::AgentLoop(n) => {
+                    out.sigs.insert(n.sig);
+                    out.tools.extend(n.tools.iter().copied());
+                }
+                Node::Call(n) => {
+                    out.imports.insert(n.import);
+                }
+                Node::Transform(n) => {
+                    out.sigs.insert(n.sig);
+                }
+                Node::Approve(n) => {
+                    out.sigs.insert(n.sig);
+                }
+                Node::Hole(n) => {
+                    out.sigs.insert(n.sig);
+                }
+                _ => {}
+            }
+        }
+        out.sigs
+            .extend(out.tools.iter().map(|&tool| self.tools[tool].sig));
+        for (id, slot) in self.params.iter() {
+            let owned = match slot.owner {
+                crate::ir::params::ParamOwner::Node(node) => out.nodes.contains(&node),
+                crate::ir::params::ParamOwner::Tool(tool) => out.tools.contains(&tool),
+            };
+            if owned {
+                out.params.insert(id);
+                if let Some(model) = slot.default.model() {
+                    out.models.insert(model);
+                }
+            }
+        }
+        out
+    }
+
     /// Rebuilds the `ParamPath` index (deserialization / builder finish).
     pub(crate) fn rebuild_param_index(&mut self) -> Result<(), ValidateError> {
         self.param_index.clear();
@@ -562,6 +829,7 @@ impl Program {
     /// `self` is untouched; the returned program is a first-class artifact:
     /// it validates, serializes, and runs identically to `self` + `overlay`
     /// (overlay read-through and baked defaults render the same prompts).
+    /// Source comments carry over.
     ///
     /// Lineage: `note` supplies the caller's provenance (optimizer name,
     /// trainset id, budget spent, date); [`Lineage::parent`] is overwritten
@@ -571,6 +839,9 @@ impl Program {
     /// Because the hash changes, overlays minted against `self` do **not**
     /// apply to the baked program (the [`Overlay::base`] check) — candidates
     /// are re-minted against the new skeleton by design.
+    ///
+    /// Params spliced in by a `call` are refused ([`BakeError::Imported`]):
+    /// they belong to the imported file.
     pub fn bake(
         &self,
         overlay: &crate::ir::params::Overlay,
@@ -586,8 +857,14 @@ impl Program {
             .into());
         }
 
+        let spliced = self.spliced();
         let mut baked = self.clone();
         for (id, value) in overlay.entries() {
+            if spliced.params.contains(&id) {
+                return Err(BakeError::Imported {
+                    path: self.params[id].path.to_string(),
+                });
+            }
             let slot = &mut baked.params[id];
             if slot.kind != value.kind() {
                 return Err(OverlayError::KindMismatch {
@@ -622,6 +899,11 @@ pub enum BakeError {
     /// graph rules reject.
     #[error("baked program failed validation: {0}")]
     Invalid(#[from] ValidateError),
+    /// The overlay sets a param an import owns. Imported params print as the
+    /// import's `pin`, not as values, so baking one would not reach the
+    /// canonical text — optimize the imported file and re-pin instead.
+    #[error("`{path}` belongs to an imported program; bake it there and re-pin the import")]
+    Imported { path: String },
 }
 
 // ---------------------------------------------------------------------------
@@ -640,6 +922,8 @@ pub(crate) struct ProgramData {
     pub params: PrimaryMap<ParamId, ParamSlot>,
     pub models: PrimaryMap<ModelId, ModelDef>,
     pub tools: PrimaryMap<ToolId, ToolDef>,
+    #[serde(default, skip_serializing_if = "PrimaryMap::is_empty")]
+    pub imports: PrimaryMap<ImportId, ImportDef>,
     pub types: TypeTable,
     pub syms: Vec<Box<str>>,
     pub caps: CapSet,
@@ -656,6 +940,7 @@ impl ProgramData {
             params: p.params.clone(),
             models: p.models.clone(),
             tools: p.tools.clone(),
+            imports: p.imports.clone(),
             types: p.types.clone(),
             syms: p.syms.as_slice(),
             caps: p.caps.clone(),
@@ -696,6 +981,7 @@ impl TryFrom<ProgramData> for Program {
             params: data.params,
             models: data.models,
             tools: data.tools,
+            imports: data.imports,
             types: data.types,
             syms,
             caps: data.caps,
@@ -0,0 +1,626 @@
+//! Guardrails: host policies around leaves and runs.
+//!
+//! A [`Guardrail`] is bound on the [`RuntimeEnv`](crate::ir::RuntimeEnv), not
+//! written into the program: it is the host's compliance layer, so the same
+//! artifact (same program hash) runs under whatever policies each deployment
+//! binds. Each guardrail runs at one [`GuardStage`]:
+//!
+//! - [`Prompt`](GuardStage::Prompt) — the rendered chat of a `predict`,
+//!   `cot`, or `agent` leaf, before every LM call. An agent's later turns
+//!   carry its tool results, so injection heuristics see them. Prompt
+//!   policies run before the leaf's span opens: the trace, the replay key,
+//!   and the provider all see the guarded chat.
+//! - [`Output`](GuardStage::Output) — a leaf's parsed output (`predict`,
+//!   `cot`, `agent`, `hole`), before anything downstream reads it.
+//! - [`RunInput`](GuardStage::RunInput) / [`RunOutput`](GuardStage::RunOutput)
+//!   — the run's input, after the signature check, and its final output.
+//!
+//! A policy answers with a [`Verdict`]: allow, flag (record and continue),
+//! rewrite (replace what it saw and continue), or block (fail the run with
+//! [`RunError::Guardrail`]). Policies run in binding order, each seeing the
+//! previous one's rewrites, and every rewrite must still fit the signature
+//! of what it guards, or the run fails as if the policy blocked. Every
+//! decision other than allow is recorded on the leaf's span as
+//! [`SpanEvent::Guardrail`] and pushed to observers as
+//! [`RunEvent::Guardrail`]. Blocks are not retryable: `retry` and `refine`
+//! pass them through.
+//!
+//! Policies are Rust closures ([`Guardrail::prompt`], [`Guardrail::value`]),
+//! regex helpers ([`Guardrail::redact`], [`Guardrail::blocklist`]), or
+//! JavaScript validators run in the `dsrs_tools` sandbox
+//! ([`Guardrail::js`]). A validator that throws or answers with something
+//! unrecognizable blocks: guardrails fail closed.
+//!
+//! Leaves served without running — replay, memo, checkpoint reuse — return
+//! what was stored and are not checked again.
+
+use std::fmt;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::ir::interp::{LoadError, RunError};
+use crate::ir::observe::{RunEvent, RunObserver};
+use crate::ir::params::code_hash;
+use crate::trace::{JsonMap, SpanEvent};
+use crate::{Chat, ContentBlock};
+
+/// Where a guardrail runs (see the [module docs](self)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardStage {
+    RunInput,
+    Prompt,
+    Output,
+    RunOutput,
+}
+
+impl GuardStage {
+    /// The wire name (`run_input`, `prompt`, `output`, `run_output`), as
+    /// JavaScript validators receive it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RunInput => "run_input",
+            Self::Prompt => "prompt",
+            Self::Output => "output",
+            Self::RunOutput => "run_output",
+        }
+    }
+}
+
+/// The stages that guard maps rather than chats: every [`GuardStage`] but
+/// [`Prompt`](GuardStage::Prompt).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueStage {
+    RunInput,
+    Output,
+    RunOutput,
+}
+
+impl From<ValueStage> for GuardStage {
+    fn from(stage: ValueStage) -> Self {
+        match stage {
+            ValueStage::RunInput => Self::RunInput,
+            ValueStage::Output => Self::Output,
+            ValueStage::RunOutput => Self::RunOutput,
+        }
+    }
+}
+
+impl GuardStage {
+    /// The [`ValueStage`] this is; `None` for [`Prompt`](Self::Prompt).
+    fn value_stage(self) -> Option<ValueStage> {
+        match self {
+            Self::RunInput => Some(ValueStage::RunInput),
+            Self::Prompt => None,
+            Self::Output => Some(ValueStage::Output),
+            Self::RunOutput => Some(ValueStage::RunOutput),
+        }
+    }
+}
+
+impl fmt::Display for GuardStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::RunInput => "run input",
+            Self::Prompt => "prompt",
+            Self::Output => "output",
+            Self::RunOutput => "run output",
+        })
+    }
+}
+
+/// A recorded guardrail decision. Allowing records nothing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardAction {
+    Flag,
+    Rewrite,
+    Block,
+}
+
+/// A policy's answer about one chat (`T = Chat`) or map (`T = JsonMap`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict<T> {
+    Allow,
+    /// Record `reason` and continue unchanged.
+    Flag(String),
+    /// Continue with `value` in place of what the policy saw. The rewrite
+    /// must still fit the guarded signature, or the run fails.
+    Rewrite {
+        value: T,
+        reason: String,
+    },
+    /// Fail the run with [`RunError::Guardrail`].
+    Block(String),
+}
+
+/// A prompt policy: the leaf name and the chat about to be sent.
+pub type PromptCheck = Arc<dyn Fn(&str, &Chat) -> Verdict<Chat> + Send + Sync>;
+
+/// A value policy: the leaf name (`$` for the run) and the map it guards.
+pub type ValueCheck = Arc<dyn Fn(&str, &JsonMap) -> Verdict<JsonMap> + Send + Sync>;
+
+#[derive(Clone)]
+enum Check {
+    Prompt(PromptCheck),
+    Value(ValueCheck),
+    /// Sandboxed JavaScript, registered at load.
+    Js {
+        source: String,
+        hash: u64,
+    },
+}
+
+/// One named policy at one stage, optionally limited to some leaves.
+#[derive(Clone)]
+pub struct Guardrail {
+    name: String,
+    stage: GuardStage,
+    leaves: Option<Vec<String>>,
+    check: Check,
+}
+
+impl fmt::Debug for Guardrail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Guardrail")
+            .field("name", &self.name)
+            .field("stage", &self.stage)
+            .field("leaves", &self.leaves)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Guardrail {
+    /// A [`Prompt`](GuardStage::Prompt) policy over each chat before it is
+    /// sent.
+    pub fn prompt<F>(name: &str, check: F) -> Self
+    where
+        F: Fn(&str, &Chat) -> Verdict<Chat> + Send + Sync + 'static,
+    {
+        Self::new(name, GuardStage::Prompt, Check::Prompt(Arc::new(check)))
+    }
+
+    /// A policy over the maps of a value stage (run input, leaf output, run
+    /// output).
+    pub fn value<F>(name: &str, stage: ValueStage, check: F) -> Self
+    where
+        F: Fn(&str, &JsonMap) -> Verdict<JsonMap> + Send + Sync + 'static,
+    {
+        Self::new(name, stage.into(), Check::Value(Arc::new(check)))
+    }
+
+    /// A JavaScript validator for a value stage, run in the sandbox. The
+    /// source is a function expression (the `dsrs_tools` source contract)
+    /// called with `{ at, stage, value }`; it answers `null` or `{}` to
+    /// allow, `{ flag: "why" }`, `{ block: "why" }`, or
+    /// `{ rewrite: { ...fields }, reason: "why" }`.
+    pub fn js(name: &str, stage: ValueStage, source: &str) -> Self {
+        Self::new(
+            name,
+            stage.into(),
+            Check::Js {
+                source: source.to_string(),
+                hash: code_hash(source),
+            },
+        )
+    }
+
+    /// Replaces every match of `pattern` with `replacement` (`$1` expands
+    /// capture groups): in message text and tool results at the prompt
+    /// stage, in every string of the map at the value stages.
+    pub fn redact(
+        name: &str,
+        stage: GuardStage,
+        pattern: &str,
+        replacement: &str,
+    ) -> Result<Self, regex::Error> {
+        let regex = Regex::new(pattern)?;
+        let replacement = replacement.to_string();
+        let replace = move |text: &str| -> Option<String> {
+            regex
+                .is_match(text)
+                .then(|| regex.replace_all(text, replacement.as_str()).into_owned())
+        };
+        let reason = format!("redacted matches of `{pattern}`");
+        Ok(match stage.value_stage() {
+            None => Self::prompt(name, move |_, chat| match map_chat_text(chat, &replace) {
+                Some(value) => Verdict::Rewrite {
+                    value,
+                    reason: reason.clone(),
+                },
+                None => Verdict::Allow,
+            }),
+            Some(stage) => Self::value(name, stage, move |_, map| {
+                let mut value = Value::Object(map.clone());
+                if !map_strings(&mut value, &replace) {
+                    return Verdict::Allow;
+                }
+                let Value::Object(value) = value else {
+                    unreachable!("rewriting strings keeps the object")
+                };
+                Verdict::Rewrite {
+                    value,
+                    reason: reason.clone(),
+                }
+            }),
+        })
+    }
+
+    /// Blocks when any of `patterns` matches: a message's text at the prompt
+    /// stage, any string in the map at the value stages.
+    pub fn blocklist(
+        name: &str,
+        stage: GuardStage,
+        patterns: &[&str],
+    ) -> Result<Self, regex::Error> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        let find = move |text: &str| {
+            regexes
+                .iter()
+                .find(|regex| regex.is_match(text))
+                .map(|regex| regex.as_str().to_string())
+        };
+        Ok(match stage.value_stage() {
+            None => Self::prompt(name, move |_, chat| {
+                chat.messages
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, message)| {
+                        find(&message.content()).map(|pattern| {
+                            Verdict::Block(format!("message {i} matches `{pattern}`"))
+                        })
+                    })
+                    .unwrap_or(Verdict::Allow)
+            }),
+            Some(stage) => Self::value(name, stage, move |_, map| {
+                map.iter()
+                    .find_map(|(field, value)| {
+                        find_string(value, &find)
+                            .map(|pattern| Verdict::Block(format!("`{field}` matches `{pattern}`")))
+                    })
+                    .unwrap_or(Verdict::Allow)
+            }),
+        })
+    }
+
+    /// Limits a prompt or output policy to the named leaves. Run stages
+    /// ignore it.
+    pub fn only<I, S>(mut self, leaves: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.leaves = Some(leaves.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stage(&self) -> GuardStage {
+        self.stage
+    }
+
+    fn new(name: &str, stage: GuardStage, check: Check) -> Self {
+        Self {
+            name: name.to_string(),
+            stage,
+            leaves: None,
+            check,
+        }
+    }
+
+    fn applies(&self, stage: GuardStage, at: &str) -> bool {
+        self.stage == stage
+            && match (&self.leaves, stage) {
+                (Some(leaves), GuardStage::Prompt | GuardStage::Output) => {
+                    leaves.iter().any(|leaf| leaf == at)
+                }
+                _ => true,
+            }
+    }
+}
+
+/// A loaded interpreter's guardrails, with their JavaScript validators
+/// registered in the sandbox.
+#[derive(Default)]
+pub(crate) struct Guardrails {
+    guards: Vec<Guardrail>,
+    /// The registered sandbox tool of each [`Check::Js`] guard, by position.
+    tools: Vec<Option<String>>,
+    sandbox: Option<Arc<dyn dsrs_tools::Executor>>,
+}
+
+impl Guardrails {
+    pub(crate) async fn load(
+        guards: Vec<Guardrail>,
+        sandbox: Option<&Arc<dyn dsrs_tools::Executor>>,
+    ) -> Result<Self, LoadError> {
+        let mut tools = Vec::with_capacity(guards.len());
+        for guard in &guards {
+            let Check::Js { source, hash } = &guard.check else {
+                tools.push(None);
+                continue;
+            };
+            let executor = sandbox.ok_or_else(|| LoadError::GuardrailSandboxMissing {
+                name: guard.name.clone(),
+            })?;
+            let name = register_validator(executor.as_ref(), &guard.name, source, *hash)
+                .await
+                .map_err(|source| LoadError::Register {
+                    at: guard.name.clone(),
+                    source,
+                })?;
+            tools.push(Some(name));
+        }
+        Ok(Self {
+            guards,
+            tools,
+            sandbox: sandbox.cloned(),
+        })
+    }
+
+    /// Runs the prompt policies for leaf `at` over `chat`, rewriting it in
+    /// place. On a block, `chat` keeps the rewrites made before it.
+    pub(crate) fn check_prompt(
+        &self,
+        at: &str,
+        chat: &mut Chat,
+        notes: &mut Vec<SpanEvent>,
+        observer: Option<&Arc<dyn RunObserver>>,
+    ) -> Result<(), RunError> {
+        for guard in &self.guards {
+            if !guard.applies(GuardStage::Prompt, at) {
+                continue;
+            }
+            let Check::Prompt(check) = &guard.check else {
+                unreachable!("prompt guardrails carry prompt checks")
+            };
+            let verdict = check(at, chat);
+            if let Some(value) =
+                self.decide(guard, GuardStage::Prompt, at, verdict, notes, observer)?
+            {
+                *chat = value;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the policies of value stage `stage` for `at` over `value` and
+    /// returns it, rewritten. Every rewrite passes through `conform`, the
+    /// signature check of what the stage guards; a rewrite that no longer
+    /// fits fails the run as a block by the guard that made it.
+    pub(crate) async fn check_value(
+        &self,
+        stage: GuardStage,
+        at: &str,
+        mut value: JsonMap,
+        conform: &(dyn Fn(JsonMap) -> Result<JsonMap, RunError> + Sync),
+        notes: &mut Vec<SpanEvent>,
+        observer: Option<&Arc<dyn RunObserver>>,
+    ) -> Result<JsonMap, RunError> {
+        for (guard, tool) in self.guards.iter().zip(&self.tools) {
+            if !guard.applies(stage, at) {
+                continue;
+            }
+            let verdict = match (&guard.check, tool) {
+                (Check::Value(check), _) => check(at, &value),
+                (Check::Js { .. }, Some(tool)) => self.run_validator(tool, stage, at, &value).await,
+                _ => unreachable!("value guardrails carry value checks"),
+            };
+            if let Some(rewritten) = self.decide(guard, stage, at, verdict, notes, observer)? {
+                value = conform(rewritten).map_err(|err| RunError::Guardrail {
+                    at: at.into(),
+                    guard: guard.name.as_str().into(),
+                    stage,
+                    reason: format!("rewrite does not fit the signature: {err}"),
+                })?;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Records a verdict; `Some` carries a rewrite, `Err` a block.
+    fn decide<T>(
+        &self,
+        guard: &Guardrail,
+        stage: GuardStage,
+        at: &str,
+        verdict: Verdict<T>,
+        notes: &mut Vec<SpanEvent>,
+        observer: Option<&Arc<dyn RunObserver>>,
+    ) -> Result<Option<T>, RunError> {
+        let (action, reason, rewritten) = match verdict {
+            Verdict::Allow => return Ok(None),
+            Verdict::Flag(reason) => (GuardAction::Flag, reason, None),
+            Verdict::Rewrite { value, reason } => (GuardAction::Rewrite, reason, Some(value)),
+            Verdict::Block(reason) => (GuardAction::Block, reason, None),
+        };
+        notes.push(SpanEvent::Guardrail {
+            guard: guard.name.clone(),
+            stage,
+            action,
+            reason: reason.clone(),
+        });
+        if let Some(observer) = observer {
+            observer.on_event(&RunEvent::Guardrail {
+                node: at.to_string(),
+                guard: guard.name.clone(),
+                stage,
+                action,
+                reason: reason.clone(),
+            });
+        }
+        if action == GuardAction::Block {
+            return Err(RunError::Guardrail {
+                at: at.into(),
+                guard: guard.name.as_str().into(),
+                stage,
+                reason,
+            });
+        }
+        Ok(rewritten)
+    }
+
+    async fn run_validator(
+        &self,
+        tool: &str,
+        stage: GuardStage,
+        at: &str,
+        value: &JsonMap,
+    ) -> Verdict<JsonMap> {
+        let sandbox = self
+            .sandbox
+            .as_ref()
+            .expect("JavaScript guardrails load only with a sandbox");
+        let args = json!({ "at": at, "stage": stage.as_str(), "value": value });
+        match sandbox
+            .execute(dsrs_tools::ToolInvocation::new(tool.to_string(), args))
+            .await
+        {
+            Ok(answer) => js_verdict(answer),
+            Err(err) => Verdict::Block(format!("validator failed: {err}")),
+        }
+    }
+}
+
+/// Reads a JavaScript validator's answer; anything unrecognized blocks.
+fn js_verdict(answer: Value) -> Verdict<JsonMap> {
+    let Value::Object(mut answer) = answer else {
+        return match answer {
+            Value::Null | Value::Bool(true) => Verdict::Allow,
+            other => Verdict::Block(format!("validator answered `{other}`")),
+        };
+    };
+    let reason = |answer: &mut JsonMap, key: &str| match answer.remove(key) {
+        Some(Value::String(reason)) => reason,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    if answer.contains_key("block") {
+        Verdict::Block(reason(&mut answer, "block"))
+    } else if let Some(rewrite) = answer.remove("rewrite") {
+        match rewrite {
+            Value::Object(value) => Verdict::Rewrite {
+                value,
+                reason: reason(&mut answer, "reason"),
+            },
+            other => Verdict::Block(format!("validator rewrote to a non-object `{other}`")),
+        }
+    } else if answer.contains_key("flag") {
+        Verdict::Flag(reason(&mut answer, "flag"))
+    } else if answer.is_empty() {
+        Verdict::Allow
+    } else {
+        Verdict::Block(format!("validator answered `{}`", Value::Object(answer)))
+    }
+}
+
+async fn register_validator(
+    sandbox: &dyn dsrs_tools::Executor,
+    guard: &str,
+    source: &str,
+    hash: u64,
+) -> Result<String, dsrs_tools::RegisterError> {
+    let sanitized: String = guard
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(40)
+        .collect();
+    let name = format!("guard-{sanitized}-{hash:016x}");
+    if sandbox.tool(&name).is_some() {
+        return Ok(name);
+    }
+    let params = json!({
+        "type": "object",
+        "properties": {
+            "at": { "type": "string" },
+            "stage": { "type": "string" },
+            "value": { "type": "object" },
+        },
+        "required": ["at", "stage", "value"],
+    });
+    sandbox
+        .register(dsrs_tools::ToolSource::new(
+            name.clone(),
+            format!("dsrs guardrail `{guard}`"),
+            params,
+            source,
+        ))
+        .await?;
+    Ok(name)
+}
+
+/// `chat` with `replace` applied to its message text and tool results, or
+/// `None` when nothing changed.
+fn map_chat_text(chat: &Chat, replace: &impl Fn(&str) -> Option<String>) -> Option<Chat> {
+    let mut chat = chat.clone();
+    let mut changed = false;
+    for message in &mut chat.messages {
+        for block in &mut message.content {
+            match block {
+                ContentBlock::Text { text } => {
+                    if let Some(new) = replace(text) {
+                        *text = new;
+                        changed = true;
+                    }
+                }
+                ContentBlock::ToolResult { tool_result } => {
+                    for item in tool_result.content.iter_mut() {
+                        if let rig::message::ToolResultContent::Text(text) = item
+                            && let Some(new) = replace(&text.text)
+                        {
+                            text.text = new;
+                            changed = true;
+                        }
+                    }
+                }
+                ContentBlock::ToolCall { .. } | ContentBlock::Reasoning { .. } => {}
+            }
+        }
+    }
+    changed.then_some(chat)
+}
+
+/// Applies `replace` to every string in `value`; whether any changed.
+fn map_strings(value: &mut Value, replace: &impl Fn(&str) -> Option<String>) -> bool {
+    match value {
+        Value::String(text) => match replace(text) {
+            Some(new) => {
+                *text = new;
+                true
+            }
+            None => false,
+        },
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |changed, item| map_strings(item, replace) | changed),
+        Value::Object(map) => map
+            .values_mut()
+            .fold(false, |changed, item| map_strings(item, replace) | changed),
+        _ => false,
+    }
+}
+
+/// The first `find` hit among the strings in `value`.
+fn find_string(value: &Value, find: &impl Fn(&str) -> Option<String>) -> Option<String> {
+    match value {
+        Value::String(text) => find(text),
+        Value::Array(items) => items.iter().find_map(|item| find_string(item, find)),
+        Value::Object(map) => map.values().find_map(|item| find_string(item, find)),
+        _ => None,
+    }
+}
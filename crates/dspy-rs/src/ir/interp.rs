@@ -13,29 +13,73 @@
 //!   ambient capture scope, `component` = the program-unique leaf name. An
 //!   `AgentLoop` is one span with N `Exchange`/`ToolRun` events; a `Hole` is
 //!   one span whose model is the reserved `sandbox:quickjs` config.
+//! - **Approvals**: an `Approve` node suspends a
+//!   [`run_suspendable`](Interpreter::run_suspendable) run and hands the
+//!   caller its payload; [`resume_run`](Interpreter::resume_run) continues it
+//!   with the caller's [`ApprovalDecision`]. A
+//!   [`run_with`](Interpreter::run_with) run asks its
+//!   [`RunOptions`] approver instead.
+//! - **Calls**: a `Call` node evaluates its spliced body in a fresh `$`
+//!   frame of its bound inputs; its leaves are ordinary leaves named
+//!   `<call>.<leaf>`, so spans, overlays, and host holes address them by
+//!   their prefixed names.
+//! - **Run options**: [`run_with`](Interpreter::run_with) takes a
+//!   [`RunOptions`] combining the hooks below, and
+//!   [`resume_from`](Interpreter::resume_from) takes the same options.
+//! - **Checkpoints**: a run with a checkpoint callback emits a serializable
+//!   [`RunCheckpoint`] after every completed leaf;
+//!   [`resume_from`](Interpreter::resume_from) continues it in any process,
+//!   serving completed leaves instead of re-running them.
+//! - **Observers**: an observed run pushes a [`RunEvent`] to its
+//!   [`RunObserver`] at every node start and finish, LM exchange, tool run,
+//!   retry, route decision, and meter charge.
+//! - **Memos**: a leaf with a `cache` policy
+//!   ([`MemoPolicy`](crate::ir::MemoPolicy)) is served from an earlier
+//!   evaluation with the same resolved input and parameter values, held on
+//!   the interpreter across runs; its span is still recorded (see
+//!   [`memo`](crate::ir::memo)).
+//! - **Cancellation**: a run with a [`CancellationToken`] stops when it
+//!   fires. Like any [`run_with`](Interpreter::run_with) run that stops
+//!   early, it returns a [`PartialRun`] — the completed leaves, spend, and
+//!   trace so far — instead of nothing.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
-use std::time::Instant;
+use std::task::Poll;
+use std::time::{Duration, Instant};
 
 use cranelift_entity::SecondaryMap;
+use futures::StreamExt;
+use futures::channel::{mpsc, oneshot};
 use futures::future::BoxFuture;
 use indexmap::IndexMap;
 use serde_json::{Value, json};
+pub use tokio_util::sync::CancellationToken;
 
 use crate::adapter::chat::ChatAdapter;
 use crate::core::FieldMeta;
+use crate::ir::checkpoint::{
+    CHECKPOINT_VERSION, CheckpointFn, CheckpointLog, MeterReading, RunCheckpoint, leaf_input_hash,
+};
 use crate::ir::graph::{
-    AgentLoopNode, Binding, BudgetPolicy, CapSet, HoleImpl, HoleNode, ModelId, Node, NodeId,
-    PortRef, PredictNode, Program, ToolId, ToolKind,
+    AgentLoopNode, ApproveNode, Binding, BudgetPolicy, CapSet, HoleImpl, HoleNode, MapErrorPolicy,
+    MapNode, MemoPolicy, ModelId, Node, NodeId, PortRef, PredictNode, Program, ToolId, ToolKind,
+    TransformNode,
 };
+use crate::ir::guardrail::{GuardStage, Guardrail, Guardrails};
+use crate::ir::memo::{MemoEntry, MemoStore, memo_key};
+use crate::ir::observe::{RunEvent, RunObserver};
 use crate::ir::params::{ContextPolicy, DemoRow, Overlay, ParamId, ParamValue};
 use crate::ir::sig::SignatureDef;
 use crate::ir::validate::{ValidateError, json_matches_type};
+use crate::ir::window::OverflowPolicy;
 use crate::trace::{JsonMap, SpanEvent, SpanOutcome, SpanRequest, begin_span};
 use crate::typesys::{FieldType, TypeTable};
-use crate::{Chat, LM, LMConfig, LmError, LmUsage, Message, Role, ToolLoopMode, ToolSet};
+use crate::{
+    Chat, LM, LMConfig, LmError, LmMiddleware, LmUsage, Message, Role, Tokenizer, ToolLoopMode,
+    ToolSet, builtin_tokenizer, count_messages, default_tokenizer,
+};
 
 // ---------------------------------------------------------------------------
 // Budgets
@@ -69,6 +113,9 @@ pub struct BudgetMeter {
     limits: Budget,
     lm_calls: AtomicU32,
     tokens: AtomicU64,
+    /// Wall time spent before this process (a resumed checkpoint), which
+    /// brings the deadline forward.
+    elapsed_before_ms: AtomicU64,
     parent: Option<Arc<BudgetMeter>>,
 }
 
@@ -78,6 +125,7 @@ impl BudgetMeter {
             limits,
             lm_calls: AtomicU32::new(0),
             tokens: AtomicU64::new(0),
+            elapsed_before_ms: AtomicU64::new(0),
             parent: None,
         }
     }
@@ -91,8 +139,9 @@ impl BudgetMeter {
     }
 
     fn check_soft(&self) -> Result<(), Exhausted> {
+        let elapsed_before = Duration::from_millis(self.elapsed_before_ms.load(Ordering::Relaxed));
         if let Some(deadline) = self.limits.deadline
-            && Instant::now() >= deadline
+            && Instant::now() + elapsed_before >= deadline
         {
             return Err(Exhausted);
         }
@@ -133,6 +182,16 @@ impl BudgetMeter {
         }
     }
 
+    /// Starts the meter at spend recorded elsewhere (a resumed checkpoint):
+    /// its calls and tokens count against the limits, and its wall time
+    /// against the deadline.
+    fn precharge(&self, spent: MeterReading) {
+        self.lm_calls.fetch_add(spent.lm_calls, Ordering::Relaxed);
+        self.tokens.fetch_add(spent.tokens, Ordering::Relaxed);
+        self.elapsed_before_ms
+            .fetch_add(spent.elapsed_ms, Ordering::Relaxed);
+    }
+
     pub fn lm_calls(&self) -> u32 {
         self.lm_calls.load(Ordering::Relaxed)
     }
@@ -163,8 +222,9 @@ impl BudgetMeter {
 ///   (no parse metadata exists for those).
 /// - Only *successful* evaluations report — a failed attempt inside `Retry`
 ///   propagates its error and leaves no outcome; the succeeding attempt
-///   reports one. A leaf re-evaluated by `Refine`/`Loop` reports once per
-///   evaluation.
+///   reports one. A leaf re-evaluated by `Refine`/`Loop`/`Map` reports once
+///   per evaluation (`Map`: in element order; skipped elements report
+///   nothing).
 /// - A replay-served leaf reports raw text and usage from the recorded span
 ///   with **empty** `field_meta`, matching the static lane ("served
 ///   predictions carry no per-field parse metadata").
@@ -204,6 +264,103 @@ pub struct RunOutput {
     pub leaves: Vec<LeafOutcome>,
 }
 
+/// Decides a pending `Approve` node from its leaf name and payload.
+type Approver = Arc<dyn Fn(&str, &JsonMap) -> BoxFuture<'static, ApprovalDecision> + Send + Sync>;
+
+/// How [`Interpreter::run_with`] and [`Interpreter::resume_from`] drive a
+/// run beyond its input, overlay, and budget. Every option is off by
+/// default and they combine freely: a gateway can watch a run, persist its
+/// checkpoints, and cancel it after 30 seconds, all at once.
+#[derive(Default)]
+pub struct RunOptions {
+    observer: Option<Arc<dyn RunObserver>>,
+    on_checkpoint: Option<CheckpointFn>,
+    cancel: Option<CancellationToken>,
+    approver: Option<Approver>,
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports each step to `observer` as it happens: node start and finish,
+    /// LM exchanges, tool runs, retries, route decisions, and the run meter
+    /// after every charge (see [`RunEvent`]). Events arrive inline on the
+    /// run's task, in execution order; an observer that needs to do real
+    /// work should forward them to a channel.
+    pub fn with_observer(mut self, observer: impl RunObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Hands `on_checkpoint` a fresh [`RunCheckpoint`] after every completed
+    /// leaf (`Predict`, `AgentLoop`, `Hole`). Persist the latest one; if the
+    /// process dies, [`Interpreter::resume_from`] continues the run
+    /// elsewhere. The callback runs inline on the run's task, so keep it to
+    /// a quick write (or a channel send).
+    pub fn with_checkpoints(
+        mut self,
+        on_checkpoint: impl Fn(&RunCheckpoint) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_checkpoint = Some(Box::new(on_checkpoint));
+        self
+    }
+
+    /// Stops the run when `cancel` fires. The run races the token: when it
+    /// fires, the run future is dropped wherever it is waiting, aborting
+    /// in-flight provider requests and tool runs (their spans close as
+    /// `Cancelled`), and the [`PartialRun`] carries
+    /// [`RunError::Cancelled`].
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Lets `Approve` nodes ask `approver`, with the node's leaf name and
+    /// payload, instead of failing the run. The run pauses while it decides,
+    /// as a [`RunSuspension`] does, and its decision continues the run as in
+    /// [`Interpreter::resume_run`]. Without an approver, `Approve` nodes are
+    /// refused as in [`Interpreter::run`].
+    pub fn with_approver<F, Fut>(mut self, approver: F) -> Self
+    where
+        F: Fn(&str, &JsonMap) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ApprovalDecision> + Send + 'static,
+    {
+        self.approver = Some(Arc::new(
+            move |at: &str, payload: &JsonMap| -> BoxFuture<'static, ApprovalDecision> {
+                Box::pin(approver(at, payload))
+            },
+        ));
+        self
+    }
+}
+
+/// What a [`run_with`](Interpreter::run_with) run got done before it was
+/// cancelled or failed.
+#[derive(Debug, thiserror::Error)]
+#[error("run did not finish (completed leaves: {})", .checkpoint.leaves.len())]
+pub struct PartialRun {
+    /// Why the run stopped — [`RunError::Cancelled`] when the token fired.
+    #[source]
+    pub error: RunError,
+    /// Every completed leaf output, the spend, and the trace so far.
+    /// Resumable with [`Interpreter::resume_from`].
+    pub checkpoint: RunCheckpoint,
+}
+
+impl PartialRun {
+    /// The output of the last completed evaluation of leaf `name`.
+    pub fn leaf_output(&self, name: &str) -> Option<&JsonMap> {
+        self.checkpoint
+            .leaves
+            .iter()
+            .rev()
+            .find(|leaf| leaf.name == name)
+            .map(|leaf| &leaf.output)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Conversation surface (RFC 0004 §1–2)
 // ---------------------------------------------------------------------------
@@ -275,6 +432,118 @@ struct SuspendState {
     suspended_at: Instant,
 }
 
+// ---------------------------------------------------------------------------
+// Approval surface
+// ---------------------------------------------------------------------------
+
+/// The caller's answer to a pending [`Approve`](Node::Approve) node, fed
+/// back through [`Interpreter::resume_run`]. Every decision continues the
+/// run; downstream nodes route on the node's `decision` output.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ApprovalDecision {
+    /// Pass the payload through unchanged (`decision = "approved"`).
+    Approve,
+    /// Pass the payload through with these output fields replaced
+    /// (`decision = "edited"`). Each key must name a signature output, and
+    /// each value must match its type.
+    Edit(JsonMap),
+    /// Pass the payload through with `decision = "rejected"` and `reason`
+    /// set.
+    Reject { reason: String },
+}
+
+/// One step of a suspendable run, returned by
+/// [`Interpreter::run_suspendable`] and [`Interpreter::resume_run`].
+#[derive(Debug)]
+pub enum RunTurn<'a> {
+    /// The run finished; same output as
+    /// [`run_collecting`](Interpreter::run_collecting).
+    Complete(RunOutput),
+    /// An `Approve` node is waiting on the caller. Decide, then feed the
+    /// decision through [`Interpreter::resume_run`].
+    Suspended(RunSuspension<'a>),
+}
+
+/// A run stopped at an `Approve` node.
+///
+/// The suspension owns the in-flight run: its open spans, its budget meter,
+/// and every node still running. Nothing is polled while it waits, so
+/// concurrent branches pause with it — but budget deadlines keep running and
+/// are enforced when the run resumes. Dropping a suspension without resuming
+/// cancels the run, and every open span (the approval's included) closes as
+/// `Cancelled` — the same contract as [`ToolSuspension`].
+pub struct RunSuspension<'a> {
+    pending: PendingApproval,
+    driver: RunDriver<'a>,
+}
+
+impl RunSuspension<'_> {
+    /// The leaf name of the waiting `Approve` node.
+    pub fn at(&self) -> &str {
+        &self.pending.at
+    }
+
+    /// The pending payload: the node's bound signature inputs.
+    pub fn payload(&self) -> &JsonMap {
+        &self.pending.payload
+    }
+}
+
+impl std::fmt::Debug for RunSuspension<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunSuspension")
+            .field("at", &self.pending.at)
+            .field("payload", &self.pending.payload)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An `Approve` node's request to the caller, queued by the evaluating node.
+struct PendingApproval {
+    at: String,
+    payload: JsonMap,
+    reply: oneshot::Sender<ApprovalDecision>,
+}
+
+/// The private half of a [`RunSuspension`]: the run future and the queue its
+/// `Approve` nodes ask through. Concurrent approvals (fork branches, map
+/// elements) queue up and surface one suspension at a time.
+struct RunDriver<'a> {
+    run: BoxFuture<'a, Result<RunOutput, RunError>>,
+    approvals: mpsc::UnboundedReceiver<PendingApproval>,
+}
+
+impl<'a> RunDriver<'a> {
+    /// Polls the run until it finishes or an `Approve` node asks.
+    async fn advance(mut self) -> Result<RunTurn<'a>, RunError> {
+        enum Step {
+            Done(Result<RunOutput, RunError>),
+            Ask(PendingApproval),
+        }
+        let step = futures::future::poll_fn(|task| {
+            if let Poll::Ready(result) = self.run.as_mut().poll(task) {
+                return Poll::Ready(Step::Done(result));
+            }
+            while let Poll::Ready(Some(pending)) = self.approvals.poll_next_unpin(task) {
+                // The asking node was dropped since (a failed fork sibling):
+                // nobody is left to answer.
+                if !pending.reply.is_canceled() {
+                    return Poll::Ready(Step::Ask(pending));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+        match step {
+            Step::Done(result) => result.map(RunTurn::Complete),
+            Step::Ask(pending) => Ok(RunTurn::Suspended(RunSuspension {
+                pending,
+                driver: self,
+            })),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Errors
 // ---------------------------------------------------------------------------
@@ -293,6 +562,10 @@ pub enum LoadError {
     HostHoleUnbound { name: String },
     #[error("program contains sandboxed code but the environment has no sandbox executor")]
     SandboxMissing,
+    #[error("guardrail `{name}` is JavaScript but the environment has no sandbox executor")]
+    GuardrailSandboxMissing { name: String },
+    #[error("model `{model}` names tokenizer `{name}`, which is neither bound nor builtin")]
+    UnknownTokenizer { model: String, name: String },
     #[error("sandboxed code at `{at}` failed to register")]
     Register {
         at: String,
@@ -344,12 +617,45 @@ pub enum RunError {
     /// Additive to RFC 0002: overlay minted against a different program.
     #[error("overlay minted against program {expected:016x}, run against {got:016x}")]
     Overlay { expected: u64, got: u64 },
+    /// A checkpoint was resumed against a different program or overlay, or
+    /// was written by an incompatible checkpoint version.
+    #[error("checkpoint cannot resume this run: {message}")]
+    Checkpoint { message: String },
     /// Additive to RFC 0002: run input rejected against the program signature.
     #[error("invalid input at `{at}`: {message}")]
     Input { at: Box<str>, message: String },
     /// Additive to RFC 0002: interpreter invariant violation.
     #[error("internal interpreter error at `{at}`: {message}")]
     Internal { at: Box<str>, message: String },
+    /// A transform expression failed to evaluate or produced a value outside
+    /// its output type. Deterministic, so never retryable.
+    #[error("transform `{at}` failed computing `{field}`: {message}")]
+    Transform {
+        at: Box<str>,
+        field: Box<str>,
+        message: String,
+    },
+    /// A guardrail bound on the runtime environment blocked the run input,
+    /// a leaf's prompt or output, or the run output (`at` is `$` for the
+    /// run). Policy, so never retryable.
+    #[error("guardrail `{guard}` blocked the {stage} at `{at}`: {reason}")]
+    Guardrail {
+        at: Box<str>,
+        guard: Box<str>,
+        stage: GuardStage,
+        reason: String,
+    },
+    /// A leaf's prompt counted `prompt_tokens` against the `limit` left in
+    /// `model`'s context window after its `max_tokens`, and the runtime's
+    /// [`OverflowPolicy`] could not trim it to fit. Deterministic, so never
+    /// retryable.
+    #[error("prompt at `{at}` is {prompt_tokens} tokens; `{model}` leaves {limit} for it")]
+    ContextWindow {
+        at: Box<str>,
+        model: String,
+        prompt_tokens: u32,
+        limit: u32,
+    },
     /// RFC 0003 M-1: a strict replay scope refused this call.
     #[error("replay refused at `{at}`")]
     Replay {
@@ -406,6 +712,42 @@ pub struct RuntimeEnv {
     /// collapses a whole loop's tool surface; and leaving the closed enum
     /// untouched keeps the `.dsrs` text format stable.
     pub code_mode: Option<dsrs_tools::SandboxConfig>,
+    /// Dry run: when set, no provider is ever called. Every `Predict` and
+    /// `AgentLoop` LM call is answered with outputs [`synthesize`]d from the
+    /// leaf's signature and the program's type table — type-valid, honoring
+    /// enums, optionals, and constraints — seeded from this value, the leaf,
+    /// and what it was asked, so the same input and seed replay the same run.
+    /// Agents answer through their first stop tool when they have one (else
+    /// with a parseable text turn) and never call their other tools.
+    ///
+    /// Models not bound in [`models`](Self::models) load without a client,
+    /// host tools need no binding, and unbound extern holes answer with
+    /// synthesized outputs too, so a program's routes, loops, maps, and
+    /// holes run end to end offline. Sandboxed code still runs, and still
+    /// requires the sandbox. Budgets meter calls as usual; usage is zero.
+    ///
+    /// [`synthesize`]: crate::ir::synthesize
+    pub dry_run: Option<u64>,
+    /// Input and output policies applied, in order, around every leaf and
+    /// run (see [`guardrail`](crate::ir::guardrail)).
+    pub guardrails: Vec<Guardrail>,
+    /// LM middleware wrapped around every model at load — bound or
+    /// constructed — outside the model's own chain (see
+    /// [`LmMiddleware`]). Dry runs never reach it.
+    pub lm_middleware: Vec<Arc<dyn LmMiddleware>>,
+    /// Tokenizers by name, for model configs whose
+    /// [`tokenizer`](crate::LMConfig::tokenizer) is not a builtin (see
+    /// [`window`](crate::ir::window)). A binding shadows a builtin of the
+    /// same name.
+    pub tokenizers: HashMap<String, Arc<dyn Tokenizer>>,
+    /// What a leaf does when its prompt would overflow its model's context
+    /// window. Fails by default.
+    pub overflow: OverflowPolicy,
+    /// How many leaf evaluations the memo keeps before it evicts the least
+    /// recently used one; `None` keeps [`DEFAULT_MEMO_CAPACITY`].
+    ///
+    /// [`DEFAULT_MEMO_CAPACITY`]: crate::ir::DEFAULT_MEMO_CAPACITY
+    pub memo_capacity: Option<usize>,
 }
 
 impl RuntimeEnv {
@@ -451,6 +793,47 @@ impl RuntimeEnv {
         self.code_mode = Some(config);
         self
     }
+
+    /// Enables a dry run seeded by `seed` (see [`dry_run`](Self::dry_run)):
+    /// every LM call is answered with synthetic, type-valid outputs.
+    pub fn with_dry_run(mut self, seed: u64) -> Self {
+        self.dry_run = Some(seed);
+        self
+    }
+
+    /// Appends a guardrail; guardrails at the same stage run in the order
+    /// they were added.
+    pub fn with_guardrail(mut self, guardrail: Guardrail) -> Self {
+        self.guardrails.push(guardrail);
+        self
+    }
+
+    /// Appends an LM middleware applied to every model (see
+    /// [`lm_middleware`](Self::lm_middleware)).
+    pub fn with_lm_middleware(mut self, middleware: impl LmMiddleware + 'static) -> Self {
+        self.lm_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Binds a tokenizer that model configs can name (see
+    /// [`tokenizers`](Self::tokenizers)).
+    pub fn bind_tokenizer(mut self, name: &str, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizers.insert(name.to_string(), tokenizer);
+        self
+    }
+
+    /// Sets the [`overflow`](Self::overflow) policy.
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Bounds the memo at `capacity` entries (see
+    /// [`memo_capacity`](Self::memo_capacity)).
+    pub fn with_memo_capacity(mut self, capacity: usize) -> Self {
+        self.memo_capacity = Some(capacity);
+        self
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -468,6 +851,18 @@ enum ToolExec {
 pub type HostHoleFn =
     Arc<dyn Fn(JsonMap) -> BoxFuture<'static, Result<Value, String>> + Send + Sync>;
 
+/// The dry-run stand-in for an unbound extern hole: synthetic outputs for
+/// its signature, seeded by the hole's name and input.
+fn synthetic_hole(name: &str, def: &SignatureDef, types: &TypeTable, seed: u64) -> HostHoleFn {
+    let (name, def, types) = (name.to_string(), def.clone(), types.clone());
+    Arc::new(move |input| {
+        let asked = Value::Object(input).to_string();
+        let seed = crate::ir::synth::call_seed(seed, &name, &asked);
+        let output = crate::ir::synth::synthesize(&def, &types, seed);
+        Box::pin(futures::future::ready(Ok(Value::Object(output))))
+    })
+}
+
 /// A loaded, executable program: validated graph + bound models/tools +
 /// registered sandbox code. Cheap to share; run state never lives here.
 pub struct Interpreter {
@@ -482,6 +877,16 @@ pub struct Interpreter {
     registered: tokio::sync::Mutex<HashMap<u64, String>>,
     /// Code Mode sandbox config (see [`RuntimeEnv::code_mode`]).
     code_mode: Option<dsrs_tools::SandboxConfig>,
+    /// Dry-run seed (see [`RuntimeEnv::dry_run`]).
+    dry_run: Option<u64>,
+    /// Outputs of leaves with a `cache` policy, shared by every run.
+    memo: MemoStore,
+    /// Host policies around leaves and runs (see [`RuntimeEnv::guardrails`]).
+    guardrails: Guardrails,
+    /// Bound tokenizers (see [`RuntimeEnv::tokenizers`]).
+    tokenizers: HashMap<String, Arc<dyn Tokenizer>>,
+    /// See [`RuntimeEnv::overflow`].
+    overflow: OverflowPolicy,
 }
 
 impl std::fmt::Debug for Interpreter {
@@ -498,10 +903,15 @@ impl Interpreter {
     /// 1. [`Program::validate`] (§2.3 rules)
     /// 2. `program.caps ⊆ env.grants` (WIT rule: no ambient authority)
     /// 3. every model bindable (pre-bound by name, else client-constructible)
-    /// 4. every `ToolKind::Host` name bound
+    /// 4. every `ToolKind::Host` name and extern hole bound
     /// 5. every sandboxed tool and hole registered through the dsrs-tools
     ///    lifecycle (parse → compile → register). A hole that doesn't compile
     ///    fails the LOAD, not the call.
+    /// 6. every tokenizer named by a model config bound or builtin
+    ///
+    /// A [dry run](RuntimeEnv::dry_run) waives 3 and 4: unbound models load
+    /// without a client, unbound tools stay unbound (dry agents never call
+    /// them), and unbound extern holes answer with synthetic outputs.
     pub async fn load(program: Program, env: RuntimeEnv) -> Result<Self, LoadError> {
         program.validate()?;
 
@@ -515,6 +925,7 @@ impl Interpreter {
         for (id, def) in program.models.iter() {
             let lm = match env.models.get(&*def.name) {
                 Some(lm) => Arc::clone(lm),
+                None if env.dry_run.is_some() => Arc::new(LM::detached(def.config.clone())),
                 None => Arc::new(LM::from_config(def.config.clone()).await.map_err(|err| {
                     LoadError::Model {
                         name: def.name.to_string(),
@@ -522,7 +933,23 @@ impl Interpreter {
                     }
                 })?),
             };
-            models[id] = Some(lm);
+            models[id] = Some(if env.lm_middleware.is_empty() {
+                lm
+            } else {
+                Arc::new(lm.wrapped_in(&env.lm_middleware))
+            });
+        }
+        for (id, def) in program.models.iter() {
+            let config = &models[id].as_ref().expect("bound above").config;
+            if let Some(name) = &config.tokenizer
+                && !env.tokenizers.contains_key(name)
+                && builtin_tokenizer(name).is_none()
+            {
+                return Err(LoadError::UnknownTokenizer {
+                    model: def.name.to_string(),
+                    name: name.clone(),
+                });
+            }
         }
 
         let mut tool_exec: SecondaryMap<ToolId, Option<ToolExec>> = SecondaryMap::new();
@@ -530,14 +957,11 @@ impl Interpreter {
         for (id, tool) in program.tools.iter() {
             let name = program.syms.get(tool.name).to_string();
             match tool.kind {
-                ToolKind::Host => {
-                    let bound = env
-                        .host_tools
-                        .get(&name)
-                        .cloned()
-                        .ok_or(LoadError::HostToolUnbound { name: name.clone() })?;
-                    tool_exec[id] = Some(ToolExec::Host(bound));
-                }
+                ToolKind::Host => match env.host_tools.get(&name) {
+                    Some(bound) => tool_exec[id] = Some(ToolExec::Host(Arc::clone(bound))),
+                    None if env.dry_run.is_some() => {}
+                    None => return Err(LoadError::HostToolUnbound { name }),
+                },
                 ToolKind::Sandboxed { code } => {
                     sandboxed.push((name, code, tool.sig));
                     tool_exec[id] = Some(ToolExec::Sandboxed);
@@ -551,11 +975,13 @@ impl Interpreter {
                 match hole.imp {
                     HoleImpl::Sandboxed { code } => sandboxed.push((name, code, hole.sig)),
                     HoleImpl::Host { .. } => {
-                        let bound = env
-                            .host_holes
-                            .get(&name)
-                            .cloned()
-                            .ok_or(LoadError::HostHoleUnbound { name: name.clone() })?;
+                        let bound = match (env.host_holes.get(&name), env.dry_run) {
+                            (Some(bound), _) => Arc::clone(bound),
+                            (None, Some(seed)) => {
+                                synthetic_hole(&name, &program.sigs[hole.sig], &program.types, seed)
+                            }
+                            (None, None) => return Err(LoadError::HostHoleUnbound { name }),
+                        };
                         host_holes.insert(name, bound);
                     }
                 }
@@ -575,7 +1001,7 @@ impl Interpreter {
                         continue;
                     }
                     let name = program.syms.get(program.tools[tool_id].name).to_string();
-                    let js_name = dsrs_tools::js_identifier(&name);
+                    let js_name = dsrs_tools::js_identifier(lm_tool_name(&name));
                     if let Some(previous) = seen.insert(js_name.clone(), name.clone()) {
                         return Err(LoadError::Register {
                             at: program.syms.get(agent.name).to_string(),
@@ -615,6 +1041,7 @@ impl Interpreter {
                 registered.insert(*hash, name);
             }
         }
+        let guardrails = Guardrails::load(env.guardrails, sandbox.as_ref()).await?;
 
         Ok(Self {
             program: Arc::new(program),
@@ -624,6 +1051,13 @@ impl Interpreter {
             sandbox,
             registered: tokio::sync::Mutex::new(registered),
             code_mode: env.code_mode,
+            dry_run: env.dry_run,
+            memo: env
+                .memo_capacity
+                .map_or_else(MemoStore::default, MemoStore::new),
+            guardrails,
+            tokenizers: env.tokenizers,
+            overflow: env.overflow,
         })
     }
 
@@ -631,6 +1065,18 @@ impl Interpreter {
         &self.program
     }
 
+    /// How many leaf evaluations the memo holds (leaves with a `cache`
+    /// policy; see [`MemoPolicy`]).
+    pub fn memo_len(&self) -> usize {
+        self.memo.len()
+    }
+
+    /// Drops every memoized leaf output; the next evaluation of each runs
+    /// live.
+    pub fn clear_memo(&self) {
+        self.memo.clear()
+    }
+
     /// Evaluates the program on `input`, reading parameters through `overlay`
     /// (never mutating the program), metering spend against `budget`.
     pub async fn run(
@@ -639,7 +1085,7 @@ impl Interpreter {
         overlay: Option<Arc<Overlay>>,
         budget: Budget,
     ) -> Result<JsonMap, RunError> {
-        self.run_inner(input, overlay, budget, false)
+        self.run_inner(input, overlay, budget, RunHooks::default())
             .await
             .map(|out| out.output)
     }
@@ -654,7 +1100,237 @@ impl Interpreter {
         overlay: Option<Arc<Overlay>>,
         budget: Budget,
     ) -> Result<RunOutput, RunError> {
-        self.run_inner(input, overlay, budget, true).await
+        let hooks = RunHooks {
+            collect: true,
+            ..RunHooks::default()
+        };
+        self.run_inner(input, overlay, budget, hooks).await
+    }
+
+    /// [`run_collecting`](Self::run_collecting) for programs with
+    /// [`Approve`](Node::Approve) nodes: when one is reached, the run
+    /// *suspends* and you get its payload plus a resumption token
+    /// ([`RunSuspension`]); continue with
+    /// [`resume_run`](Self::resume_run). [`run`](Self::run) refuses approve
+    /// nodes with [`RunError::Input`] — there is nobody to ask.
+    ///
+    /// Spans, meters, and deadlines behave as in a run that never stopped:
+    /// each approval records one span (model `human:approval`) that stays
+    /// open while the caller decides, and the run meter keeps the clock. A
+    /// replay scope never suspends — recorded decisions are served. Keep the
+    /// whole drive (this call and every resume) inside one capture or replay
+    /// scope.
+    pub async fn run_suspendable(
+        &self,
+        input: JsonMap,
+        overlay: Option<Arc<Overlay>>,
+        budget: Budget,
+    ) -> Result<RunTurn<'_>, RunError> {
+        let (requests, approvals) = mpsc::unbounded();
+        let hooks = RunHooks {
+            collect: true,
+            approvals: Some(requests),
+            ..RunHooks::default()
+        };
+        RunDriver {
+            run: Box::pin(self.run_inner(input, overlay, budget, hooks)),
+            approvals,
+        }
+        .advance()
+        .await
+    }
+
+    /// Continues a suspended run with the caller's decision on
+    /// [`RunSuspension::payload`], until it finishes or the next approval.
+    ///
+    /// The decision becomes the `Approve` node's output — pass-through
+    /// fields (edited or not), `decision`, and `reason` — and flows into
+    /// downstream bindings like any node output. An edit naming an unknown
+    /// field or carrying a mistyped value fails the run with
+    /// [`RunError::Input`].
+    pub async fn resume_run<'a>(
+        &'a self,
+        suspension: RunSuspension<'a>,
+        decision: ApprovalDecision,
+    ) -> Result<RunTurn<'a>, RunError> {
+        let RunSuspension { pending, driver } = suspension;
+        // A send failure means the node was cancelled while waiting; the run
+        // reports why when it is polled.
+        let _ = pending.reply.send(decision);
+        driver.advance().await
+    }
+
+    /// [`run_collecting`](Self::run_collecting) driven by `options`: an
+    /// observer, a checkpoint callback, a cancellation token, and an
+    /// approver, in any combination (see [`RunOptions`]).
+    ///
+    /// A run that stops early — cancelled, out of budget, or failed any other
+    /// way — hands back a [`PartialRun`] instead of nothing: the error plus a
+    /// [`RunCheckpoint`] of every leaf that completed, the spend so far, and
+    /// the trace so far. The trace is recorded in the caller's capture scope,
+    /// or in one of the run's own when there is none. The checkpoint resumes
+    /// with [`resume_from`](Self::resume_from).
+    pub async fn run_with(
+        &self,
+        input: JsonMap,
+        overlay: Option<Arc<Overlay>>,
+        budget: Budget,
+        options: RunOptions,
+    ) -> Result<RunOutput, Box<PartialRun>> {
+        let program_hash = self.program.meta.program_hash;
+        let overlay_hash = overlay.as_ref().map(|overlay| overlay.hash());
+        let start = input.clone();
+        self.drive(input, overlay, budget, options, move |on_checkpoint| {
+            CheckpointLog::fresh(program_hash, overlay_hash, start, on_checkpoint)
+        })
+        .await
+    }
+
+    /// Continues a [`run_with`](Self::run_with) run from `checkpoint`, under
+    /// the same kind of `options`.
+    ///
+    /// The run re-executes from the root on the checkpoint's input. Each
+    /// leaf the checkpoint completed is served from it (matched on leaf name
+    /// and resolved input), with no LM call, span, or [`LeafOutcome`]; an
+    /// observer sees one [`RunEvent::CheckpointServed`] for it instead of a
+    /// start and finish. The first leaf it did not complete runs live.
+    /// `overlay` must be the one the checkpoint was taken under, and the
+    /// program the same program — otherwise [`RunError::Checkpoint`], with
+    /// the checkpoint handed back unchanged. The run meter starts at the
+    /// checkpoint's recorded spend; `budget` applies to the whole run's
+    /// spend, not just this process's share. The checkpoint's `elapsed_ms`
+    /// brings `budget.deadline` forward by as much, so `now + 10min` caps the
+    /// whole run, across processes, at ten minutes.
+    pub async fn resume_from(
+        &self,
+        checkpoint: RunCheckpoint,
+        overlay: Option<Arc<Overlay>>,
+        budget: Budget,
+        options: RunOptions,
+    ) -> Result<RunOutput, Box<PartialRun>> {
+        if let Err(error) = self.check_checkpoint(&checkpoint, overlay.as_ref()) {
+            return Err(Box::new(PartialRun { error, checkpoint }));
+        }
+        let input = checkpoint.input.clone();
+        self.drive(input, overlay, budget, options, move |on_checkpoint| {
+            CheckpointLog::resumed(checkpoint, on_checkpoint)
+        })
+        .await
+    }
+
+    fn check_checkpoint(
+        &self,
+        checkpoint: &RunCheckpoint,
+        overlay: Option<&Arc<Overlay>>,
+    ) -> Result<(), RunError> {
+        let mismatch = |message: String| Err(RunError::Checkpoint { message });
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return mismatch(format!(
+                "written by checkpoint version {}, this build reads {CHECKPOINT_VERSION}",
+                checkpoint.version
+            ));
+        }
+        if checkpoint.program_hash != self.program.meta.program_hash {
+            return mismatch(format!(
+                "taken on program {:016x}, resumed on {:016x}",
+                checkpoint.program_hash, self.program.meta.program_hash
+            ));
+        }
+        let overlay_hash = overlay.map(|overlay| overlay.hash());
+        if checkpoint.overlay_hash != overlay_hash {
+            let show = |hash: Option<u64>| {
+                hash.map_or("no overlay".to_string(), |h| format!("overlay {h:016x}"))
+            };
+            return mismatch(format!(
+                "taken under {}, resumed under {}",
+                show(checkpoint.overlay_hash),
+                show(overlay_hash)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Runs [`run_with`](Self::run_with) and
+    /// [`resume_from`](Self::resume_from) inside a capture scope, so the
+    /// checkpoint log can snapshot the trace.
+    async fn drive(
+        &self,
+        input: JsonMap,
+        overlay: Option<Arc<Overlay>>,
+        budget: Budget,
+        options: RunOptions,
+        log: impl FnOnce(Option<CheckpointFn>) -> CheckpointLog,
+    ) -> Result<RunOutput, Box<PartialRun>> {
+        if crate::trace::is_capturing() {
+            self.drive_captured(input, overlay, budget, options, log)
+                .await
+        } else {
+            crate::trace::capture(|| self.drive_captured(input, overlay, budget, options, log))
+                .await
+                .0
+        }
+    }
+
+    async fn drive_captured(
+        &self,
+        input: JsonMap,
+        overlay: Option<Arc<Overlay>>,
+        budget: Budget,
+        options: RunOptions,
+        log: impl FnOnce(Option<CheckpointFn>) -> CheckpointLog,
+    ) -> Result<RunOutput, Box<PartialRun>> {
+        let RunOptions {
+            observer,
+            on_checkpoint,
+            cancel,
+            approver,
+        } = options;
+        let log = Arc::new(log(on_checkpoint));
+        let meter = Arc::new(BudgetMeter::new(budget));
+        let (requests, approvals) = mpsc::unbounded();
+        let hooks = RunHooks {
+            collect: true,
+            approvals: approver.as_ref().map(|_| requests),
+            checkpoints: Some(Arc::clone(&log)),
+            observer,
+            meter: Some(Arc::clone(&meter)),
+        };
+        let run = async {
+            let driver = RunDriver {
+                run: Box::pin(self.run_inner(input, overlay, budget, hooks)),
+                approvals,
+            };
+            let mut turn = driver.advance().await?;
+            loop {
+                match turn {
+                    RunTurn::Complete(output) => return Ok(output),
+                    RunTurn::Suspended(suspension) => {
+                        let approver = approver
+                            .as_ref()
+                            .expect("only runs with an approver queue approvals");
+                        let decision = approver(suspension.at(), suspension.payload()).await;
+                        turn = self.resume_run(suspension, decision).await?;
+                    }
+                }
+            }
+        };
+        let cancelled = async {
+            match &cancel {
+                Some(cancel) => cancel.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            biased;
+            _ = cancelled => Err(RunError::Cancelled),
+            result = run => result,
+        };
+        result.map_err(|error| {
+            Box::new(PartialRun {
+                error,
+                checkpoint: log.conclude(&meter),
+            })
+        })
     }
 
     /// Conversation-in/conversation-out evaluation (RFC 0004 §1): one turn
@@ -680,7 +1356,10 @@ impl Interpreter {
     ///   formatted input is appended as the next user message.
     ///
     /// Tools on an agent leaf dispatch through their bound executors, exactly
-    /// like [`run`](Self::run). For the suspend-on-tools variant see
+    /// like [`run`](Self::run). An agent's returned chat also carries where
+    /// it stands against its context policy, so the next turn keeps a held
+    /// summary and pinned turns in place; a chat rebuilt from its messages
+    /// keeps only the summary. For the suspend-on-tools variant see
     /// [`run_conversation_caller_managed`](Self::run_conversation_caller_managed).
     ///
     /// Only single-leaf programs (what `Predict<S>` compiles to) have a
@@ -784,12 +1463,30 @@ impl Interpreter {
         let lm = self.p_model(&at, &cx, n.model)?;
         let policy = self.p_context(&cx, n.context_policy);
 
+        let surface = self.build_agent_surface(&at, n, &cx, false).await?;
+        let lc = AgentLoopCx {
+            at: &at,
+            n,
+            def,
+            lm: &lm,
+            toolset: &surface.toolset,
+            by_name: &surface.by_name,
+            sandbox_code: &surface.sandbox_code,
+            stop_names: &surface.stop_names,
+            prefix_len,
+            meter: &meter,
+            run_meter: &run_meter,
+            policy: &policy,
+            code_mode: None,
+            observer: None,
+        };
+
         // Feed the caller's results back: same event, record, and
         // conversation shape as dispatched executions.
         let duration_us = suspended_at.elapsed().as_micros() as u64;
         let mut blocks = Vec::with_capacity(calls.len());
         for (call, result) in calls.iter().zip(results) {
-            let result = clip_tool_result(result, &policy);
+            let result = self.fit_tool_result(&lc, call, result, &mut run).await;
             run.events.push(SpanEvent::ToolRun {
                 id: call.id.clone(),
                 name: call.function.name.clone(),
@@ -804,22 +1501,6 @@ impl Interpreter {
         }
         chat.push_message(Message::with_content(Role::User, blocks));
 
-        let surface = self.build_agent_surface(&at, n, &cx, false).await?;
-        let lc = AgentLoopCx {
-            at: &at,
-            n,
-            def,
-            lm: &lm,
-            toolset: &surface.toolset,
-            by_name: &surface.by_name,
-            sandbox_code: &surface.sandbox_code,
-            stop_names: &surface.stop_names,
-            prefix_len,
-            meter: &meter,
-            run_meter: &run_meter,
-            policy: &policy,
-            code_mode: None,
-        };
         let outcome = self.agent_loop(&lc, chat, &mut run, next_turn, true).await;
         self.conclude_agent_turn(
             &at,
@@ -883,40 +1564,64 @@ impl Interpreter {
             .to_string();
         let def = self.leaf_sig(node);
         let cx = self.conversation_cx(overlay.clone(), Arc::new(BudgetMeter::new(budget)));
+        let lm = match &p.nodes[node] {
+            Node::Predict(n) => self.p_model(&at, &cx, n.model)?,
+            Node::AgentLoop(n) => self.p_model(&at, &cx, n.model)?,
+            _ => unreachable!("conversation_leaf returns only leaves"),
+        };
+
+        // An agent sends its tool definitions with every prompt: build them
+        // first, so fitting counts them as `eval_agent` does.
+        let surface = match &p.nodes[node] {
+            Node::AgentLoop(n) => Some(
+                self.build_agent_surface(&at, n, &cx, !suspend_on_tools)
+                    .await?,
+            ),
+            _ => None,
+        };
+        let tools = surface
+            .as_ref()
+            .map_or(&[][..], |surface| surface.toolset.definitions());
 
         // This turn's messages, plus the rendered prefix when we own it (an
         // opening turn interns system+demos; a caller-owned continuation has
         // no known prefix split — the span records the full chat as suffix).
-        let (prefix, suffix) = if chat.is_empty() {
+        // Both are fitted to the model's context window; only an opening
+        // has demos and inputs to trim.
+        let mut notes = Vec::new();
+        let carried = chat.window.take();
+        let (prefix, suffix, fit) = if chat.is_empty() {
             let input_map = input.as_ref().ok_or_else(|| RunError::Input {
                 at: at.clone().into(),
                 message: "an empty conversation needs an opening `input`".to_string(),
             })?;
             self.validate_input(&at, def, input_map)?;
-            self.render_leaf_opening(node, input_map, &cx)?
+            self.fit_leaf_opening(node, &at, &lm, tools, input_map, &cx, &mut notes)
         } else {
             if let Some(input_map) = input.as_ref() {
                 self.validate_input(&at, def, input_map)?;
                 chat.push_message(Message::user(ChatAdapter.format_input_def(def, input_map)));
             }
-            (Vec::new(), chat.messages)
+            let messages = chat.messages;
+            self.fit_prompt(&at, &lm, tools, &[], &JsonMap::new(), &mut notes, |_, _| {
+                (Vec::new(), messages.clone())
+            })
         };
-        let prefix_len = if prefix.is_empty() {
+        let (prefix_len, window) = if prefix.is_empty() {
             // Continuation: shield the leading system prompt from history
-            // truncation, the only thing `prefix_len` is used for downstream.
-            suffix
+            // truncation, and pick up where the last turn left the context
+            // window — the chat carries it, or at least its held summary.
+            let system = suffix
                 .iter()
                 .take_while(|message| message.role == Role::System)
-                .count()
+                .count();
+            ContextWindow::resume(carried, &suffix, system)
         } else {
-            prefix.len()
+            (prefix.len(), ContextWindow::default())
         };
 
-        let lm = match &p.nodes[node] {
-            Node::Predict(n) => self.p_model(&at, &cx, n.model)?,
-            Node::AgentLoop(n) => self.p_model(&at, &cx, n.model)?,
-            _ => unreachable!("conversation_leaf returns only leaves"),
-        };
+        let (prefix, suffix, verdict) = self.guard_prompt(&at, prefix, suffix, &mut notes, None);
+        let verdict = fit.and(verdict);
 
         let guard = begin_span(SpanRequest {
             component: &at,
@@ -926,6 +1631,15 @@ impl Interpreter {
             model: &lm.config,
             request_hash: None,
         });
+        if let Err(err) = verdict {
+            return Err(guardrail_blocked(
+                guard,
+                err,
+                notes,
+                None,
+                LmUsage::default(),
+            ));
+        }
 
         let mut messages = prefix;
         messages.extend(suffix);
@@ -944,7 +1658,7 @@ impl Interpreter {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Lm,
                         err.to_string(),
-                        Vec::new(),
+                        notes,
                         None,
                         LmUsage::default(),
                     ));
@@ -959,14 +1673,12 @@ impl Interpreter {
 
         match &p.nodes[node] {
             Node::Predict(_) => {
-                self.predict_conversation_turn(&at, def, &lm, &cx, messages, guard)
+                self.predict_conversation_turn(&at, def, &lm, &cx, messages, notes, guard)
                     .await
             }
             Node::AgentLoop(n) => {
                 let policy = self.p_context(&cx, n.context_policy);
-                let surface = self
-                    .build_agent_surface(&at, n, &cx, !suspend_on_tools)
-                    .await?;
+                let surface = surface.expect("agent leaves build their surface");
                 let meter = Arc::new(BudgetMeter::child(&cx.meter, node_budget(&n.budget)));
                 let lc = AgentLoopCx {
                     at: &at,
@@ -982,8 +1694,13 @@ impl Interpreter {
                     run_meter: &cx.meter,
                     policy: &policy,
                     code_mode: surface.code_mode.as_ref(),
+                    observer: None,
+                };
+                let mut run = AgentRun {
+                    events: notes,
+                    window,
+                    ..AgentRun::default()
                 };
-                let mut run = AgentRun::default();
                 let outcome = self
                     .agent_loop(&lc, Chat::new(messages), &mut run, 0, suspend_on_tools)
                     .await;
@@ -1016,6 +1733,7 @@ impl Interpreter {
         lm: &Arc<LM>,
         cx: &Cx,
         messages: Vec<Message>,
+        notes: Vec<SpanEvent>,
         guard: Option<crate::trace::SpanGuard>,
     ) -> Result<ConversationTurn, RunError> {
         if cx.meter.try_reserve_call().is_err() {
@@ -1023,7 +1741,7 @@ impl Interpreter {
                 guard.finish(span_error(
                     crate::trace::SpanErrorKind::Lm,
                     "budget exhausted".to_string(),
-                    Vec::new(),
+                    notes,
                     None,
                     LmUsage::default(),
                 ));
@@ -1031,14 +1749,19 @@ impl Interpreter {
             return Err(RunError::Budget { at: at.into() });
         }
 
-        let response = match lm.call(Chat::new(messages), Vec::new()).await {
+        let chat = Chat::new(messages);
+        let response = match self.dry_response(&at, def, &chat, None) {
+            Some(response) => Ok(response),
+            None => lm.call(chat, Vec::new()).await,
+        };
+        let response = match response {
             Ok(response) => response,
             Err(err) => {
                 if let Some(guard) = guard {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Lm,
                         err.to_string(),
-                        Vec::new(),
+                        notes,
                         None,
                         LmUsage::default(),
                     ));
@@ -1056,8 +1779,33 @@ impl Interpreter {
         cx.meter.record_usage(&response.usage);
 
         let raw = response.output.content();
+        let mut events = notes;
+        events.extend(response.events);
         match ChatAdapter.parse_output_def(def, &self.program.types, &response.output) {
             Ok((output, metas)) => {
+                let output = match self
+                    .guardrails
+                    .check_value(
+                        GuardStage::Output,
+                        at,
+                        output,
+                        &self.conform_output(at, def),
+                        &mut events,
+                        None,
+                    )
+                    .await
+                {
+                    Ok(output) => output,
+                    Err(err) => {
+                        return Err(guardrail_blocked(
+                            guard,
+                            err,
+                            events,
+                            Some(raw),
+                            response.usage,
+                        ));
+                    }
+                };
                 let leaf = LeafOutcome {
                     name: at.to_string(),
                     raw_response: raw.clone(),
@@ -1071,7 +1819,7 @@ impl Interpreter {
                 };
                 if let Some(guard) = guard {
                     guard.finish(SpanOutcome {
-                        events: response.events,
+                        events,
                         raw_output: Some(raw),
                         output: Some(output.clone()),
                         usage: response.usage,
@@ -1091,7 +1839,7 @@ impl Interpreter {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Parse,
                         err.to_string(),
-                        response.events,
+                        events,
                         Some(raw.clone()),
                         response.usage,
                     ));
@@ -1191,8 +1939,12 @@ impl Interpreter {
                 output,
                 raw,
                 field_meta,
-                chat,
+                mut chat,
             }) => {
+                chat.window = Some(HeldWindow {
+                    prefix_len: ctx.prefix_len,
+                    window: run.window,
+                });
                 let leaf = LeafOutcome {
                     name: at.to_string(),
                     raw_response: raw.clone(),
@@ -1245,6 +1997,10 @@ impl Interpreter {
                     let kind = match &err {
                         RunError::Parse { .. } => crate::trace::SpanErrorKind::Parse,
                         RunError::Tool { .. } => crate::trace::SpanErrorKind::Tool,
+                        RunError::Guardrail { .. } => crate::trace::SpanErrorKind::Guardrail,
+                        RunError::ContextWindow { .. } => {
+                            crate::trace::SpanErrorKind::ContextWindow
+                        }
                         _ => crate::trace::SpanErrorKind::Lm,
                     };
                     guard.finish(span_error(
@@ -1324,6 +2080,48 @@ impl Interpreter {
         })
     }
 
+    /// [`render_leaf_opening`](Self::render_leaf_opening), fitted to `lm`'s
+    /// context window next to an agent's `tools` (see
+    /// [`fit_prompt`](Self::fit_prompt)).
+    #[allow(clippy::too_many_arguments)]
+    fn fit_leaf_opening(
+        &self,
+        node: NodeId,
+        at: &str,
+        lm: &LM,
+        tools: &[rig::completion::ToolDefinition],
+        input: &JsonMap,
+        cx: &Cx,
+        notes: &mut Vec<SpanEvent>,
+    ) -> (Vec<Message>, Vec<Message>, Result<(), RunError>) {
+        let p = &*self.program;
+        let (sig, instruction, demos, playbook) = match &p.nodes[node] {
+            Node::Predict(n) => (
+                n.sig,
+                self.p_text(cx, n.instruction),
+                self.p_demos(cx, n.demos),
+                None,
+            ),
+            Node::AgentLoop(n) => (
+                n.sig,
+                self.p_text(cx, n.instruction),
+                self.p_demos(cx, n.demos),
+                self.p_context(cx, n.context_policy).playbook,
+            ),
+            _ => unreachable!("conversation_leaf returns only leaves"),
+        };
+        self.fit_prompt(at, lm, tools, &demos, input, notes, |demos, input| {
+            render_prompt(
+                &p.sigs[sig],
+                &p.types,
+                &instruction,
+                demos,
+                input,
+                playbook.as_deref(),
+            )
+        })
+    }
+
     /// A conversation's run state: overlay + run meter, no frames, no
     /// collection — conversation turns build their [`LeafOutcome`] directly.
     fn conversation_cx(&self, overlay: Option<Arc<Overlay>>, meter: Arc<BudgetMeter>) -> Cx {
@@ -1335,6 +2133,9 @@ impl Interpreter {
             feedback: None,
             refine_feedback: None,
             leaves: None,
+            approvals: None,
+            checkpoints: None,
+            observer: None,
         }
     }
 
@@ -1352,6 +2153,16 @@ impl Interpreter {
     }
 
     /// Validates an input map against a signature's declared input fields.
+    /// The output check of leaf `at` (or `$`): what a guardrail rewrite of
+    /// its output must still pass, coerced as a hole's output is.
+    fn conform_output<'a>(
+        &'a self,
+        at: &'a str,
+        def: &'a SignatureDef,
+    ) -> impl Fn(JsonMap) -> Result<JsonMap, RunError> + Sync + 'a {
+        move |output| coerce_outputs(at, def, &self.program.types, &Value::Object(output))
+    }
+
     fn validate_input(
         &self,
         at: &str,
@@ -1388,23 +2199,58 @@ impl Interpreter {
         input: JsonMap,
         overlay: Option<Arc<Overlay>>,
         budget: Budget,
-        collect: bool,
+        hooks: RunHooks,
     ) -> Result<RunOutput, RunError> {
         self.check_overlay(overlay.as_ref())?;
 
-        // Input surface check against the program's external signature.
-        self.validate_input("$", &self.program.sigs[self.program.sig], &input)?;
+        // Input surface check against the program's external signature,
+        // before the guardrails see it and again after each rewrite.
+        let sig = &self.program.sigs[self.program.sig];
+        self.validate_input("$", sig, &input)?;
+        let conform_input = |input: JsonMap| self.validate_input("$", sig, &input).map(|()| input);
+        // Run-level guardrail decisions have no span; observers see them.
+        let input = self
+            .guardrails
+            .check_value(
+                GuardStage::RunInput,
+                "$",
+                input,
+                &conform_input,
+                &mut Vec::new(),
+                hooks.observer.as_ref(),
+            )
+            .await?;
 
+        let meter = hooks
+            .meter
+            .unwrap_or_else(|| Arc::new(BudgetMeter::new(budget)));
+        if let Some(log) = &hooks.checkpoints {
+            meter.precharge(log.spent());
+        }
         let mut cx = Cx {
             overlay,
-            meter: Arc::new(BudgetMeter::new(budget)),
+            meter,
             frames: SecondaryMap::new(),
             inputs: vec![input],
             feedback: None,
             refine_feedback: None,
-            leaves: collect.then(Vec::new),
+            leaves: hooks.collect.then(Vec::new),
+            approvals: hooks.approvals,
+            checkpoints: hooks.checkpoints,
+            observer: hooks.observer,
         };
         let output = self.eval(self.program.root, &mut cx).await?;
+        let output = self
+            .guardrails
+            .check_value(
+                GuardStage::RunOutput,
+                "$",
+                output,
+                &self.conform_output("$", sig),
+                &mut Vec::new(),
+                cx.observer.as_ref(),
+            )
+            .await?;
         Ok(RunOutput {
             output,
             leaves: cx.leaves.unwrap_or_default(),
@@ -1415,153 +2261,245 @@ impl Interpreter {
 
     fn eval<'a>(&'a self, id: NodeId, cx: &'a mut Cx) -> BoxFuture<'a, Result<JsonMap, RunError>> {
         Box::pin(async move {
-            let out = match &self.program.nodes[id] {
-                Node::Predict(n) => self.eval_predict(id, n, cx).await?,
-                Node::AgentLoop(n) => self.eval_agent(id, n, cx).await?,
-                Node::Hole(n) => self.eval_hole(id, n, cx).await?,
-                Node::Seq(n) => {
-                    for &child in n.body.iter() {
-                        self.eval(child, cx).await?;
-                    }
-                    self.resolve_exports(&n.out, cx)?
+            // Checkpointed runs: serve a leaf the checkpoint already
+            // completed, record every other one once it completes.
+            let checkpoint = match &cx.checkpoints {
+                Some(log) => self
+                    .checkpoint_key(id, cx)?
+                    .map(|key| (Arc::clone(log), key)),
+                None => None,
+            };
+            if let Some((log, (at, hash))) = &checkpoint
+                && let Some(out) = log.reuse(at, *hash)
+            {
+                cx.emit(|| RunEvent::CheckpointServed {
+                    node: self.program.node_display(id),
+                    kind: self.program.nodes[id].keyword(),
+                });
+                cx.feedback = None;
+                cx.frames[id] = Some(out.clone());
+                return Ok(out);
+            }
+            let kind = self.program.nodes[id].keyword();
+            cx.emit(|| RunEvent::NodeStart {
+                node: self.program.node_display(id),
+                kind,
+            });
+            let started = Instant::now();
+            let out = self.dispatch(id, cx).await;
+            cx.emit(|| RunEvent::NodeFinish {
+                node: self.program.node_display(id),
+                kind,
+                duration_us: started.elapsed().as_micros() as u64,
+                error: out.as_ref().err().map(ToString::to_string),
+            });
+            let out = out?;
+            if let Some((log, (at, hash))) = checkpoint {
+                log.record(&at, hash, &out, &cx.meter);
+            }
+            cx.frames[id] = Some(out.clone());
+            Ok(out)
+        })
+    }
+
+    /// One node's evaluation proper, by kind; [`eval`](Self::eval) wraps it
+    /// with checkpoints, observer events, and the output frame.
+    async fn dispatch(&self, id: NodeId, cx: &mut Cx) -> Result<JsonMap, RunError> {
+        Ok(match &self.program.nodes[id] {
+            Node::Predict(n) => self.eval_predict(id, n, cx).await?,
+            Node::AgentLoop(n) => self.eval_agent(id, n, cx).await?,
+            Node::Transform(n) => self.eval_transform(id, n, cx)?,
+            Node::Approve(n) => self.eval_approve(id, n, cx).await?,
+            Node::Hole(n) => self.eval_hole(id, n, cx).await?,
+            Node::Seq(n) => {
+                for &child in n.body.iter() {
+                    self.eval(child, cx).await?;
                 }
-                Node::ForkJoin(n) => {
-                    let branch_cxs: Vec<(NodeId, Cx)> = n
-                        .branches
-                        .iter()
-                        .map(|&branch| (branch, cx.branch()))
-                        .collect();
-                    let futures =
-                        branch_cxs
-                            .into_iter()
-                            .map(|(branch, mut branch_cx)| async move {
-                                self.eval(branch, &mut branch_cx).await?;
-                                Ok::<_, RunError>((branch_cx.frames, branch_cx.leaves))
-                            });
-                    // First error aborts siblings: try_join_all drops the
-                    // remaining futures, whose open spans record Cancelled
-                    // via guard-drop (RFC 0001 §3.1).
-                    let all_frames = futures::future::try_join_all(futures).await?;
-                    for (frames, leaves) in all_frames {
-                        for (node, out) in frames.iter() {
-                            if let Some(out) = out
-                                && cx.frames[node].is_none()
-                            {
-                                cx.frames[node] = Some(out.clone());
-                            }
-                        }
-                        // Branch outcomes merge in declared branch order.
-                        if let (Some(collected), Some(branch_leaves)) = (cx.leaves.as_mut(), leaves)
+                self.resolve_exports(&n.out, cx)?
+            }
+            Node::ForkJoin(n) => {
+                let branch_cxs: Vec<(NodeId, Cx)> = n
+                    .branches
+                    .iter()
+                    .map(|&branch| (branch, cx.branch()))
+                    .collect();
+                let futures = branch_cxs
+                    .into_iter()
+                    .map(|(branch, mut branch_cx)| async move {
+                        self.eval(branch, &mut branch_cx).await?;
+                        Ok::<_, RunError>((branch_cx.frames, branch_cx.leaves))
+                    });
+                // First error aborts siblings: try_join_all drops the
+                // remaining futures, whose open spans record Cancelled
+                // via guard-drop (RFC 0001 §3.1).
+                let all_frames = futures::future::try_join_all(futures).await?;
+                for (frames, leaves) in all_frames {
+                    for (node, out) in frames.iter() {
+                        if let Some(out) = out
+                            && cx.frames[node].is_none()
                         {
-                            collected.extend(branch_leaves);
+                            cx.frames[node] = Some(out.clone());
                         }
                     }
-                    self.resolve_exports(&n.join, cx)?
+                    // Branch outcomes merge in declared branch order.
+                    if let (Some(collected), Some(branch_leaves)) = (cx.leaves.as_mut(), leaves) {
+                        collected.extend(branch_leaves);
+                    }
                 }
-                Node::Route(n) => {
-                    let at = self.program.node_display(id);
-                    let value = self.resolve_port(&at, &n.on, cx)?;
-                    let discriminant = match value.as_str() {
-                        Some(s) => s.to_string(),
-                        None => value.to_string(),
-                    };
-                    let arm = n
-                        .arms
+                self.resolve_exports(&n.join, cx)?
+            }
+            Node::Route(n) => {
+                let at = self.program.node_display(id);
+                let value = self.resolve_port(&at, &n.on, cx)?;
+                let discriminant = match value.as_str() {
+                    Some(s) => s.to_string(),
+                    None => value.to_string(),
+                };
+                let arm = if n.when.is_empty() {
+                    n.arms
                         .iter()
                         .find(|(variant, _)| {
                             self.program.syms.get(*variant) == discriminant.as_str()
                         })
                         .map(|(_, arm)| *arm)
-                        .or(n.default);
-                    match arm {
-                        Some(arm) => self.eval(arm, cx).await?,
-                        None => {
-                            return Err(RunError::Route {
-                                at: at.into(),
-                                value: discriminant,
-                            });
-                        }
+                } else {
+                    // Same semantics as `#[check]`: a predicate that fails
+                    // to evaluate does not hold.
+                    n.when
+                        .iter()
+                        .find(|(predicate, _)| {
+                            crate::typesys::evaluate_expression(predicate, &value)
+                        })
+                        .map(|(_, arm)| *arm)
+                }
+                .or(n.default);
+                cx.emit(|| RunEvent::Route {
+                    node: at.clone(),
+                    value: discriminant.clone(),
+                    arm: arm.map(|arm| self.program.node_display(arm)),
+                });
+                match arm {
+                    Some(arm) => self.eval(arm, cx).await?,
+                    None => {
+                        return Err(RunError::Route {
+                            at: at.into(),
+                            value: discriminant,
+                        });
                     }
                 }
-                Node::Retry(n) => {
-                    let mut attempt = 0u32;
-                    loop {
-                        match self.eval(n.child, cx).await {
-                            Ok(out) => break out,
-                            Err(err) if err.retryable() && attempt + 1 < n.max_attempts.get() => {
-                                attempt += 1;
-                                if n.feedback && matches!(err, RunError::Parse { .. }) {
-                                    cx.feedback = Some(format!(
-                                        "Your previous response could not be parsed: {err}. \
+            }
+            Node::Retry(n) => {
+                let mut attempt = 0u32;
+                loop {
+                    match self.eval(n.child, cx).await {
+                        Ok(out) => break out,
+                        Err(err) if err.retryable() && attempt + 1 < n.max_attempts.get() => {
+                            attempt += 1;
+                            cx.emit(|| RunEvent::Retry {
+                                node: self.program.node_display(id),
+                                attempt: attempt + 1,
+                                error: err.to_string(),
+                            });
+                            if n.feedback && matches!(err, RunError::Parse { .. }) {
+                                cx.feedback = Some(format!(
+                                    "Your previous response could not be parsed: {err}. \
                                          Respond again, following the required \
                                          `[[ ## field ## ]]` output format exactly."
-                                    ));
-                                }
-                                if n.backoff_ms > 0 {
-                                    tokio::time::sleep(std::time::Duration::from_millis(
-                                        n.backoff_ms as u64,
-                                    ))
-                                    .await;
-                                }
+                                ));
+                            }
+                            if n.backoff_ms > 0 {
+                                tokio::time::sleep(std::time::Duration::from_millis(
+                                    n.backoff_ms as u64,
+                                ))
+                                .await;
                             }
-                            Err(err) => return Err(err),
                         }
+                        Err(err) => return Err(err),
                     }
                 }
-                Node::Refine(n) => {
-                    let saved = cx.refine_feedback.take();
-                    let result = self.eval_refine(n, cx).await;
-                    cx.refine_feedback = saved;
-                    result?
-                }
-                Node::Loop(n) => {
-                    let enclosing = cx.inputs.last().cloned().unwrap_or_default();
-                    let mut carry = JsonMap::new();
-                    let at = self.program.node_display(id);
-                    let mut result = None;
-                    for iter in 0..n.max_iters.get() {
+            }
+            Node::Refine(n) => {
+                let saved = cx.refine_feedback.take();
+                let result = self.eval_refine(n, cx).await;
+                cx.refine_feedback = saved;
+                result?
+            }
+            Node::Loop(n) => {
+                let enclosing = cx.inputs.last().cloned().unwrap_or_default();
+                let mut carry = JsonMap::new();
+                let at = self.program.node_display(id);
+                let mut result = None;
+                for iter in 0..n.max_iters.get() {
+                    let mut frame = enclosing.clone();
+                    frame.extend(carry.clone());
+                    cx.inputs.push(frame);
+                    let body = self.eval(n.body, cx).await;
+                    if body.is_err() {
+                        cx.inputs.pop();
+                    }
+                    body?;
+                    // Rebind carried values from this iteration.
+                    let mut next = JsonMap::new();
+                    for b in n.carry.iter() {
+                        let name = self.program.syms.get(b.dst).to_string();
+                        next.insert(name, self.resolve_port(&at, &b.src, cx)?);
+                    }
+                    let continue_ = match &n.while_ {
+                        Some(port) => self.resolve_port(&at, port, cx)?.as_bool().unwrap_or(false),
+                        None => true,
+                    };
+                    cx.inputs.pop();
+                    carry = next;
+                    if !continue_ || iter + 1 == n.max_iters.get() {
+                        // Exports resolve in the final iteration's scope
+                        // (body outputs + final carry visible).
                         let mut frame = enclosing.clone();
                         frame.extend(carry.clone());
                         cx.inputs.push(frame);
-                        let body = self.eval(n.body, cx).await;
-                        if body.is_err() {
-                            cx.inputs.pop();
-                        }
-                        body?;
-                        // Rebind carried values from this iteration.
-                        let mut next = JsonMap::new();
-                        for b in n.carry.iter() {
-                            let name = self.program.syms.get(b.dst).to_string();
-                            next.insert(name, self.resolve_port(&at, &b.src, cx)?);
-                        }
-                        let continue_ = match &n.while_ {
-                            Some(port) => {
-                                self.resolve_port(&at, port, cx)?.as_bool().unwrap_or(false)
-                            }
-                            None => true,
-                        };
+                        let out = self.resolve_exports(&n.out, cx);
                         cx.inputs.pop();
-                        carry = next;
-                        if !continue_ || iter + 1 == n.max_iters.get() {
-                            // Exports resolve in the final iteration's scope
-                            // (body outputs + final carry visible).
-                            let mut frame = enclosing.clone();
-                            frame.extend(carry.clone());
-                            cx.inputs.push(frame);
-                            let out = self.resolve_exports(&n.out, cx);
-                            cx.inputs.pop();
-                            result = Some(out?);
-                            break;
-                        }
+                        result = Some(out?);
+                        break;
                     }
-                    result.expect("bounded loops always take the exit branch")
                 }
-            };
-            cx.frames[id] = Some(out.clone());
-            Ok(out)
+                result.expect("bounded loops always take the exit branch")
+            }
+            Node::Map(n) => self.eval_map(id, n, cx).await?,
+            Node::Call(n) => {
+                // The callee's `$` frame is exactly its bound inputs.
+                let at = self.program.syms.get(n.name);
+                let input = self.resolve_bindings(at, Some(id), &n.binding, cx)?;
+                cx.inputs.push(input);
+                let body = self.eval(n.body, cx).await;
+                cx.inputs.pop();
+                let mut body = body?;
+                self.program.sigs[n.sig]
+                    .outputs
+                    .iter()
+                    .map(|field| {
+                        let value = body.remove(&*field.name).unwrap_or(Value::Null);
+                        (field.name.to_string(), value)
+                    })
+                    .collect()
+            }
         })
     }
 
+    /// The checkpoint key — leaf name and resolved-input hash — of a leaf
+    /// that calls out (`Predict`, `AgentLoop`, `Hole`); `None` for
+    /// containers and transforms, which re-run deterministically.
+    fn checkpoint_key(&self, id: NodeId, cx: &Cx) -> Result<Option<(String, u64)>, RunError> {
+        let (name, binding) = match &self.program.nodes[id] {
+            Node::Predict(n) => (n.name, &n.binding),
+            Node::AgentLoop(n) => (n.name, &n.binding),
+            Node::Hole(n) => (n.name, &n.binding),
+            _ => return Ok(None),
+        };
+        let at = self.program.syms.get(name);
+        let input = self.resolve_bindings(at, Some(id), binding, cx)?;
+        Ok(Some((at.to_string(), leaf_input_hash(&input))))
+    }
+
     async fn eval_refine(
         &self,
         n: &crate::ir::graph::RefineNode,
@@ -1584,6 +2522,104 @@ impl Interpreter {
         unreachable!("refine rounds are bounded and return inside the loop")
     }
 
+    /// One body evaluation per element, at most `max_parallel` in flight.
+    /// Elements share the run's budget meter; each records its spans in a
+    /// capture lane merged in element order, so every leaf's trace `seq` is
+    /// its element index order whatever the completion order. Under replay
+    /// elements run one at a time — replay cursors serve in call order.
+    async fn eval_map(&self, id: NodeId, n: &MapNode, cx: &mut Cx) -> Result<JsonMap, RunError> {
+        let at = self.program.node_display(id);
+        let items = match self.resolve_port(&at, &n.over, cx)? {
+            Value::Array(items) => items,
+            other => {
+                return Err(RunError::Internal {
+                    at: at.into(),
+                    message: format!("map over a non-list value: {other}"),
+                });
+            }
+        };
+        let item = self.program.syms.get(n.item);
+        let enclosing = cx.inputs.last().cloned().unwrap_or_default();
+        let width = if crate::trace::is_replaying() {
+            1
+        } else {
+            n.max_parallel.get() as usize
+        };
+
+        let element_cxs: Vec<Cx> = items
+            .into_iter()
+            .map(|value| {
+                let mut element_cx = cx.branch();
+                let mut frame = enclosing.clone();
+                frame.insert(item.to_string(), value);
+                element_cx.inputs.push(frame);
+                element_cx
+            })
+            .collect();
+        let lanes: Vec<_> = element_cxs
+            .iter()
+            .map(|_| crate::trace::capture::open_lane())
+            .collect();
+        let at = at.as_str();
+        let elements =
+            element_cxs
+                .into_iter()
+                .zip(&lanes)
+                .map(|(mut element_cx, lane)| async move {
+                    let row = crate::trace::capture::in_lane(lane.as_ref(), async {
+                        self.eval(n.body, &mut element_cx).await?;
+                        self.resolve_bindings(at, None, &n.collect, &element_cx)
+                    })
+                    .await;
+                    row.map(|row| (row, element_cx.leaves))
+                });
+        let mut elements = futures::stream::iter(elements).buffered(width);
+
+        let mut rows = Vec::new();
+        let mut merged = 0;
+        let mut failed = None;
+        while let Some(result) = elements.next().await {
+            if let Some(lane) = &lanes[merged] {
+                crate::trace::capture::merge_lane(lane);
+            }
+            merged += 1;
+            match result {
+                Ok((row, leaves)) => {
+                    if let (Some(collected), Some(leaves)) = (cx.leaves.as_mut(), leaves) {
+                        collected.extend(leaves);
+                    }
+                    rows.push(row);
+                }
+                Err(err) if n.on_error == MapErrorPolicy::Skip && err.retryable() => {}
+                Err(err) => {
+                    failed = Some(err);
+                    break;
+                }
+            }
+        }
+        if let Some(err) = failed {
+            // Dropping the stream cancels the elements still running, whose
+            // open spans close `Cancelled` in their lanes. Like a fork's
+            // cancelled siblings they stay in the trace, in element order.
+            drop(elements);
+            for lane in lanes[merged..].iter().flatten() {
+                crate::trace::capture::merge_lane(lane);
+            }
+            return Err(err);
+        }
+
+        let mut out = JsonMap::new();
+        for b in n.collect.iter() {
+            let name = self.program.syms.get(b.dst);
+            let column = rows
+                .iter_mut()
+                .map(|row| row.remove(name).unwrap_or(Value::Null))
+                .collect();
+            out.insert(name.to_string(), Value::Array(column));
+        }
+        Ok(out)
+    }
+
     // -- leaves ---------------------------------------------------------------
 
     async fn eval_predict(
@@ -1596,14 +2632,30 @@ impl Interpreter {
         let at = p.syms.get(n.name).to_string();
         let def = &p.sigs[n.sig];
         let input = self.resolve_bindings(&at, Some(id), &n.binding, cx)?;
+        let memo = self.memo_key(
+            n.memo,
+            &at,
+            &input,
+            &[n.instruction, n.demos, n.model],
+            cx.feedback.as_deref(),
+            cx,
+        );
 
         let instruction = self.p_text(cx, n.instruction);
         let demos = self.p_demos(cx, n.demos);
-        let (prefix, mut suffix) = render_prompt(def, &p.types, &instruction, &demos, &input, None);
-        if let Some(feedback) = cx.feedback.take() {
-            suffix.push(Message::user(feedback));
-        }
         let lm = self.p_model(&at, cx, n.model)?;
+        let feedback = cx.feedback.take();
+        let mut notes = Vec::new();
+        let (prefix, suffix, fit) =
+            self.fit_prompt(&at, &lm, &[], &demos, &input, &mut notes, |demos, input| {
+                let (prefix, mut suffix) =
+                    render_prompt(def, &p.types, &instruction, demos, input, None);
+                suffix.extend(feedback.clone().map(Message::user));
+                (prefix, suffix)
+            });
+        let (prefix, suffix, verdict) =
+            self.guard_prompt(&at, prefix, suffix, &mut notes, cx.observer.as_ref());
+        let verdict = fit.and(verdict);
 
         let guard = begin_span(SpanRequest {
             component: &at,
@@ -1613,6 +2665,15 @@ impl Interpreter {
             model: &lm.config,
             request_hash: None,
         });
+        if let Err(err) = verdict {
+            return Err(guardrail_blocked(
+                guard,
+                err,
+                notes,
+                None,
+                LmUsage::default(),
+            ));
+        }
 
         let mut messages = prefix;
         messages.extend(suffix);
@@ -1659,7 +2720,7 @@ impl Interpreter {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Lm,
                         err.to_string(),
-                        Vec::new(),
+                        notes,
                         None,
                         LmUsage::default(),
                     ));
@@ -1672,29 +2733,57 @@ impl Interpreter {
             Some(crate::trace::replay::ReplayDirective::Live) | None => {}
         }
 
+        if let Some((policy, key)) = memo
+            && let Some((entry, age)) = self.memo.get(key, policy)
+        {
+            if let Some(leaves) = cx.leaves.as_mut() {
+                leaves.push(LeafOutcome {
+                    name: at.clone(),
+                    raw_response: entry.raw_output.clone().unwrap_or_default(),
+                    field_meta: IndexMap::new(),
+                    usage: LmUsage::default(),
+                    model_config_hash: crate::trace::ModelEntry::from_config(&lm.config)
+                        .config_hash,
+                    span_id: guard.as_ref().map(|guard| guard.id()),
+                    tool_calls: Vec::new(),
+                    tool_executions: Vec::new(),
+                });
+            }
+            if let Some(guard) = guard {
+                guard.finish(memo_outcome(&entry, age, notes));
+            }
+            return Ok(entry.output);
+        }
+
         if cx.meter.try_reserve_call().is_err() {
             if let Some(guard) = guard {
                 guard.finish(span_error(
                     crate::trace::SpanErrorKind::Lm,
                     "budget exhausted".to_string(),
-                    Vec::new(),
+                    notes,
                     None,
                     LmUsage::default(),
                 ));
             }
+            cx.emit(|| budget_event(&at, &cx.meter, true));
             return Err(RunError::Budget {
                 at: at.clone().into(),
             });
         }
 
-        let response = match lm.call(Chat::new(messages), Vec::new()).await {
+        let chat = Chat::new(messages);
+        let response = match self.dry_response(&at, def, &chat, None) {
+            Some(response) => Ok(response),
+            None => lm.call(chat, Vec::new()).await,
+        };
+        let response = match response {
             Ok(response) => response,
             Err(err) => {
                 if let Some(guard) = guard {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Lm,
                         err.to_string(),
-                        Vec::new(),
+                        notes,
                         None,
                         LmUsage::default(),
                     ));
@@ -1710,10 +2799,37 @@ impl Interpreter {
             }
         };
         cx.meter.record_usage(&response.usage);
+        cx.emit(|| exchange_event(&at, &lm, &response));
+        cx.emit(|| budget_event(&at, &cx.meter, false));
 
         let raw = response.output.content();
+        let mut events = notes;
+        events.extend(response.events);
         match ChatAdapter.parse_output_def(def, &p.types, &response.output) {
             Ok((output, metas)) => {
+                let output = match self
+                    .guardrails
+                    .check_value(
+                        GuardStage::Output,
+                        &at,
+                        output,
+                        &self.conform_output(&at, def),
+                        &mut events,
+                        cx.observer.as_ref(),
+                    )
+                    .await
+                {
+                    Ok(output) => output,
+                    Err(err) => {
+                        return Err(guardrail_blocked(
+                            guard,
+                            err,
+                            events,
+                            Some(raw),
+                            response.usage,
+                        ));
+                    }
+                };
                 if let Some(leaves) = cx.leaves.as_mut() {
                     leaves.push(LeafOutcome {
                         name: at.clone(),
@@ -1727,9 +2843,12 @@ impl Interpreter {
                         tool_executions: Vec::new(),
                     });
                 }
+                if let Some((policy, key)) = memo {
+                    self.memo.put(key, policy, &output, Some(raw.clone()));
+                }
                 if let Some(guard) = guard {
                     guard.finish(SpanOutcome {
-                        events: response.events,
+                        events,
                         raw_output: Some(raw),
                         output: Some(output.clone()),
                         usage: response.usage,
@@ -1743,7 +2862,7 @@ impl Interpreter {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Parse,
                         err.to_string(),
-                        response.events,
+                        events,
                         Some(raw.clone()),
                         response.usage,
                     ));
@@ -1758,6 +2877,204 @@ impl Interpreter {
         }
     }
 
+    /// Evaluates a transform's expressions over its bound inputs, in
+    /// signature output order. Pure and free: no span, no budget, no replay
+    /// interception — a replayed run recomputes the same values.
+    fn eval_transform(&self, id: NodeId, n: &TransformNode, cx: &Cx) -> Result<JsonMap, RunError> {
+        let p = &*self.program;
+        let at = p.syms.get(n.name);
+        let input = self.resolve_bindings(at, Some(id), &n.binding, cx)?;
+        let mut output = JsonMap::new();
+        for field in p.sigs[n.sig].outputs.iter() {
+            let fail = |message: String| RunError::Transform {
+                at: at.into(),
+                field: field.name.clone(),
+                message,
+            };
+            let (_, expr) = n
+                .exprs
+                .iter()
+                .find(|(dst, _)| p.syms.get(*dst) == &*field.name)
+                .ok_or_else(|| fail("no expression (validate should have refused)".into()))?;
+            let value = crate::ir::transform::evaluate(expr, &input).map_err(fail)?;
+            if !json_matches_type(&value, &field.ty, &p.types) {
+                return Err(fail(format!(
+                    "`{value}` does not match the declared type {}",
+                    crate::ir::validate::type_label(&field.ty)
+                )));
+            }
+            output.insert(field.name.to_string(), value);
+        }
+        Ok(output)
+    }
+
+    /// Asks the caller through the run's approval queue and applies the
+    /// decision. One span per evaluation, keyed on the payload, so replay
+    /// serves the recorded decision instead of asking again.
+    async fn eval_approve(
+        &self,
+        id: NodeId,
+        n: &ApproveNode,
+        cx: &mut Cx,
+    ) -> Result<JsonMap, RunError> {
+        let p = &*self.program;
+        let at = p.syms.get(n.name).to_string();
+        let def = &p.sigs[n.sig];
+        let payload = self.resolve_bindings(&at, Some(id), &n.binding, cx)?;
+        if cx.meter.check_soft().is_err() {
+            return Err(RunError::Budget { at: at.into() });
+        }
+        let Some(requests) = cx.approvals.clone() else {
+            return Err(RunError::Input {
+                at: at.into(),
+                message: "approve nodes need a caller to ask: run with \
+                          `Interpreter::run_suspendable`, or give `run_with` an approver"
+                    .to_string(),
+            });
+        };
+
+        let request_hash = approval_request_hash(&payload);
+        let pseudo_model = LMConfig {
+            model: "human:approval".to_string(),
+            ..LMConfig::default()
+        };
+        let guard = begin_span(SpanRequest {
+            component: &at,
+            prefix: None,
+            suffix: &[],
+            input: Some(payload.clone()),
+            model: &pseudo_model,
+            request_hash: Some(request_hash),
+        });
+        let fail = |guard: Option<crate::trace::SpanGuard>,
+                    kind: crate::trace::SpanErrorKind,
+                    err: RunError| {
+            if let Some(guard) = guard {
+                guard.finish(span_error(
+                    kind,
+                    err.to_string(),
+                    Vec::new(),
+                    None,
+                    LmUsage::default(),
+                ));
+            }
+            err
+        };
+
+        match crate::trace::replay::intercept_hashed(&at, request_hash) {
+            Some(crate::trace::replay::ReplayDirective::Serve(span)) => {
+                let output = span
+                    .output
+                    .clone()
+                    .expect("replay serves only spans with parsed output");
+                if let Some(guard) = guard {
+                    guard.finish(SpanOutcome {
+                        events: span.events.clone(),
+                        raw_output: span.raw_output.clone(),
+                        output: Some(output.clone()),
+                        usage: span.usage,
+                        error: None,
+                    });
+                }
+                return Ok(output);
+            }
+            Some(crate::trace::replay::ReplayDirective::Refuse(err)) => {
+                return Err(fail(
+                    guard,
+                    crate::trace::SpanErrorKind::Lm,
+                    RunError::Replay {
+                        at: at.into(),
+                        source: err,
+                    },
+                ));
+            }
+            Some(crate::trace::replay::ReplayDirective::Live) | None => {}
+        }
+
+        let (reply, decision) = oneshot::channel();
+        let asked = requests.unbounded_send(PendingApproval {
+            at: at.clone(),
+            payload: payload.clone(),
+            reply,
+        });
+        // The queue closes only when the driver is gone, and the driver owns
+        // this future — so an unanswered request means the run is ending.
+        let decision = match asked {
+            Ok(()) => decision.await.ok(),
+            Err(_) => None,
+        };
+        let Some(decision) = decision else {
+            return Err(fail(
+                guard,
+                crate::trace::SpanErrorKind::Cancelled,
+                RunError::Cancelled,
+            ));
+        };
+        // The clock ran while the caller decided.
+        if cx.meter.check_soft().is_err() {
+            return Err(fail(
+                guard,
+                crate::trace::SpanErrorKind::Lm,
+                RunError::Budget { at: at.into() },
+            ));
+        }
+
+        let mut output: JsonMap = def
+            .outputs
+            .iter()
+            .filter_map(|f| Some((f.name.to_string(), payload.get(&*f.name)?.clone())))
+            .collect();
+        let (label, reason) = match &decision {
+            ApprovalDecision::Approve => ("approved", Value::Null),
+            ApprovalDecision::Reject { reason } => ("rejected", Value::String(reason.clone())),
+            ApprovalDecision::Edit(edits) => {
+                for (field, value) in edits {
+                    let Some(out) = def.outputs.iter().find(|f| &*f.name == field) else {
+                        return Err(fail(
+                            guard,
+                            crate::trace::SpanErrorKind::Parse,
+                            RunError::Input {
+                                at: at.into(),
+                                message: format!("edit names `{field}`, which is not an output"),
+                            },
+                        ));
+                    };
+                    if !json_matches_type(value, &out.ty, &p.types) {
+                        return Err(fail(
+                            guard,
+                            crate::trace::SpanErrorKind::Parse,
+                            RunError::Input {
+                                at: at.into(),
+                                message: format!(
+                                    "edited `{field}` does not match its declared type"
+                                ),
+                            },
+                        ));
+                    }
+                    output.insert(field.clone(), value.clone());
+                }
+                ("edited", Value::Null)
+            }
+        };
+        output.insert("decision".to_string(), json!(label));
+        output.insert("reason".to_string(), reason);
+
+        if let Some(guard) = guard {
+            let raw = match &decision {
+                ApprovalDecision::Edit(edits) => json!({ "decision": label, "edits": edits }),
+                _ => json!({ "decision": label, "reason": output["reason"] }),
+            };
+            guard.finish(SpanOutcome {
+                events: Vec::new(),
+                raw_output: Some(raw.to_string()),
+                output: Some(output.clone()),
+                usage: LmUsage::default(),
+                error: None,
+            });
+        }
+        Ok(output)
+    }
+
     async fn eval_hole(&self, id: NodeId, n: &HoleNode, cx: &mut Cx) -> Result<JsonMap, RunError> {
         let p = &*self.program;
         let at = p.syms.get(n.name).to_string();
@@ -1782,6 +3099,11 @@ impl Interpreter {
             HoleImpl::Host { hash } => ResolvedHoleImpl::Host { hash: *hash },
         };
         let request_hash = hole_request_hash(&imp, &input, &n.caps);
+        let code: &[ParamId] = match &n.imp {
+            HoleImpl::Sandboxed { code } => std::slice::from_ref(code),
+            HoleImpl::Host { .. } => &[],
+        };
+        let memo = self.memo_key(n.memo, &at, &input, code, None, cx);
 
         let pseudo_model = LMConfig {
             model: match &imp {
@@ -1837,6 +3159,15 @@ impl Interpreter {
             Some(crate::trace::replay::ReplayDirective::Live) | None => {}
         }
 
+        if let Some((policy, key)) = memo
+            && let Some((entry, age)) = self.memo.get(key, policy)
+        {
+            if let Some(guard) = guard {
+                guard.finish(memo_outcome(&entry, age, Vec::new()));
+            }
+            return Ok(entry.output);
+        }
+
         let started = Instant::now();
         let result = self.execute_hole_impl(&at, &imp, &input, def).await;
         let duration_us = started.elapsed().as_micros() as u64;
@@ -1854,9 +3185,36 @@ impl Interpreter {
                 };
                 match coerce_outputs(&at, def, &p.types, &value) {
                     Ok(output) => {
+                        let mut events = vec![event];
+                        let output = match self
+                            .guardrails
+                            .check_value(
+                                GuardStage::Output,
+                                &at,
+                                output,
+                                &self.conform_output(&at, def),
+                                &mut events,
+                                cx.observer.as_ref(),
+                            )
+                            .await
+                        {
+                            Ok(output) => output,
+                            Err(err) => {
+                                return Err(guardrail_blocked(
+                                    guard,
+                                    err,
+                                    events,
+                                    Some(raw),
+                                    LmUsage::default(),
+                                ));
+                            }
+                        };
+                        if let Some((policy, key)) = memo {
+                            self.memo.put(key, policy, &output, Some(raw.clone()));
+                        }
                         if let Some(guard) = guard {
                             guard.finish(SpanOutcome {
-                                events: vec![event],
+                                events,
                                 raw_output: Some(raw),
                                 output: Some(output.clone()),
                                 usage: LmUsage::default(),
@@ -1954,26 +3312,51 @@ impl Interpreter {
         let at = p.syms.get(n.name).to_string();
         let def = &p.sigs[n.sig];
         let input = self.resolve_bindings(&at, Some(id), &n.binding, cx)?;
+        let reads: Vec<ParamId> = [
+            n.instruction,
+            n.demos,
+            n.model,
+            n.tool_set,
+            n.context_policy,
+        ]
+        .into_iter()
+        .chain(n.tools.iter().map(|tool| p.tools[*tool].desc))
+        .collect();
+        let memo = self.memo_key(n.memo, &at, &input, &reads, cx.feedback.as_deref(), cx);
 
         let instruction = self.p_text(cx, n.instruction);
         let demos = self.p_demos(cx, n.demos);
         let policy = self.p_context(cx, n.context_policy);
-        let (prefix, mut suffix) = render_prompt(
-            def,
-            &p.types,
-            &instruction,
-            &demos,
-            &input,
-            policy.playbook.as_deref(),
-        );
-        if let Some(feedback) = cx.feedback.take() {
-            suffix.push(Message::user(feedback));
-        }
         let lm = self.p_model(&at, cx, n.model)?;
+        let feedback = cx.feedback.take();
 
         let surface = self.build_agent_surface(&at, n, cx, true).await?;
 
         let meter = Arc::new(BudgetMeter::child(&cx.meter, node_budget(&n.budget)));
+        let mut notes = Vec::new();
+        let (prefix, suffix, fit) = self.fit_prompt(
+            &at,
+            &lm,
+            surface.toolset.definitions(),
+            &demos,
+            &input,
+            &mut notes,
+            |demos, input| {
+                let (prefix, mut suffix) = render_prompt(
+                    def,
+                    &p.types,
+                    &instruction,
+                    demos,
+                    input,
+                    policy.playbook.as_deref(),
+                );
+                suffix.extend(feedback.clone().map(Message::user));
+                (prefix, suffix)
+            },
+        );
+        let (prefix, suffix, verdict) =
+            self.guard_prompt(&at, prefix, suffix, &mut notes, cx.observer.as_ref());
+        let verdict = fit.and(verdict);
 
         let guard = begin_span(SpanRequest {
             component: &at,
@@ -1983,6 +3366,15 @@ impl Interpreter {
             model: &lm.config,
             request_hash: None,
         });
+        if let Err(err) = verdict {
+            return Err(guardrail_blocked(
+                guard,
+                err,
+                notes,
+                None,
+                LmUsage::default(),
+            ));
+        }
 
         let prefix_len = prefix.len();
         let mut messages = prefix;
@@ -2042,7 +3434,7 @@ impl Interpreter {
                     guard.finish(span_error(
                         crate::trace::SpanErrorKind::Lm,
                         err.to_string(),
-                        Vec::new(),
+                        notes,
                         None,
                         LmUsage::default(),
                     ));
@@ -2055,6 +3447,28 @@ impl Interpreter {
             Some(crate::trace::replay::ReplayDirective::Live) | None => {}
         }
 
+        if let Some((policy, key)) = memo
+            && let Some((entry, age)) = self.memo.get(key, policy)
+        {
+            if let Some(leaves) = cx.leaves.as_mut() {
+                leaves.push(LeafOutcome {
+                    name: at.clone(),
+                    raw_response: entry.raw_output.clone().unwrap_or_default(),
+                    field_meta: IndexMap::new(),
+                    usage: LmUsage::default(),
+                    model_config_hash: crate::trace::ModelEntry::from_config(&lm.config)
+                        .config_hash,
+                    span_id: guard.as_ref().map(|guard| guard.id()),
+                    tool_calls: Vec::new(),
+                    tool_executions: Vec::new(),
+                });
+            }
+            if let Some(guard) = guard {
+                guard.finish(memo_outcome(&entry, age, notes));
+            }
+            return Ok(entry.output);
+        }
+
         let loop_cx = AgentLoopCx {
             at: &at,
             n,
@@ -2069,8 +3483,12 @@ impl Interpreter {
             run_meter: &cx.meter,
             policy: &policy,
             code_mode: surface.code_mode.as_ref(),
+            observer: cx.observer.as_ref(),
+        };
+        let mut run = AgentRun {
+            events: notes,
+            ..AgentRun::default()
         };
-        let mut run = AgentRun::default();
         let outcome = match self.agent_loop(&loop_cx, chat, &mut run, 0, false).await {
             Ok(LoopOutcome::Done {
                 output,
@@ -2099,6 +3517,9 @@ impl Interpreter {
                         tool_executions: run.tool_executions,
                     });
                 }
+                if let Some((policy, key)) = memo {
+                    self.memo.put(key, policy, &output, Some(raw.clone()));
+                }
                 if let Some(guard) = guard {
                     guard.finish(SpanOutcome {
                         events: run.events,
@@ -2115,6 +3536,10 @@ impl Interpreter {
                     let kind = match &err {
                         RunError::Parse { .. } => crate::trace::SpanErrorKind::Parse,
                         RunError::Tool { .. } => crate::trace::SpanErrorKind::Tool,
+                        RunError::Guardrail { .. } => crate::trace::SpanErrorKind::Guardrail,
+                        RunError::ContextWindow { .. } => {
+                            crate::trace::SpanErrorKind::ContextWindow
+                        }
                         _ => crate::trace::SpanErrorKind::Lm,
                     };
                     guard.finish(span_error(
@@ -2136,8 +3561,49 @@ impl Interpreter {
     /// leaving execution to the caller
     /// ([`resume_conversation`](Self::resume_conversation) re-enters here at
     /// `next_turn`). Stop-tool and budget semantics are identical in both
-    /// modes.
+    /// modes. An accepted output passes the output guardrails.
     async fn agent_loop(
+        &self,
+        lc: &AgentLoopCx<'_>,
+        chat: Chat,
+        run: &mut AgentRun,
+        start_turn: u32,
+        suspend_on_tools: bool,
+    ) -> Result<LoopOutcome, RunError> {
+        match self
+            .agent_turns(lc, chat, run, start_turn, suspend_on_tools)
+            .await?
+        {
+            LoopOutcome::Done {
+                output,
+                raw,
+                field_meta,
+                chat,
+            } => Ok(LoopOutcome::Done {
+                output: self
+                    .guardrails
+                    .check_value(
+                        GuardStage::Output,
+                        lc.at,
+                        output,
+                        &self.conform_output(lc.at, lc.def),
+                        &mut run.events,
+                        lc.observer,
+                    )
+                    .await?,
+                raw,
+                field_meta,
+                chat,
+            }),
+            suspended => Ok(suspended),
+        }
+    }
+
+    /// The turns of [`agent_loop`](Self::agent_loop). Every turn after the
+    /// first passes the prompt guardrails (the caller guards the opening
+    /// prompt before the span opens), so tool results are checked before the
+    /// model reads them.
+    async fn agent_turns(
         &self,
         lc: &AgentLoopCx<'_>,
         mut chat: Chat,
@@ -2149,15 +3615,30 @@ impl Interpreter {
 
         for turn in start_turn..lc.n.stop.max_turns.get() {
             if lc.meter.try_reserve_call().is_err() {
+                emit(lc.observer, || budget_event(lc.at, lc.run_meter, true));
                 return match lc.n.budget.on_exhausted {
                     BudgetPolicy::Fail => Err(RunError::Budget { at: lc.at.into() }),
                     BudgetPolicy::Finalize => self.finalize(lc, chat, run).await,
                 };
             }
 
-            chat = truncate_history(chat, lc.prefix_len, lc.policy);
-            let response = lm_call_toolset(lc.lm, chat, lc.toolset, lc.at).await?;
+            self.compact_history(lc, &mut chat, run).await;
+            chat = truncate_history(chat, lc.prefix_len, lc.policy, &mut run.window);
+            if turn > 0 {
+                self.guardrails
+                    .check_prompt(lc.at, &mut chat, &mut run.events, lc.observer)?;
+            }
+            self.check_window(lc.at, lc.lm, &chat, lc.toolset.definitions())?;
+            // Dry agents stop through a stop tool when they have one: without
+            // `until_parse`, a text turn would not end the loop.
+            let stop_tool = lc.stop_names.first().map(String::as_str);
+            let response = match self.dry_response(lc.at, lc.def, &chat, stop_tool) {
+                Some(response) => response,
+                None => lm_call_toolset(lc.lm, chat, lc.toolset, lc.at).await?,
+            };
             lc.meter.record_usage(&response.usage);
+            emit(lc.observer, || exchange_event(lc.at, lc.lm, &response));
+            emit(lc.observer, || budget_event(lc.at, lc.run_meter, false));
             run.usage = run.usage + response.usage;
             run.events.extend(response.events.clone());
             chat = response.chat;
@@ -2191,12 +3672,22 @@ impl Interpreter {
                 for call in &response.tool_calls {
                     let started = Instant::now();
                     let (result, error) = self.execute_agent_tool(lc, call).await;
+                    let duration_us = started.elapsed().as_micros() as u64;
+                    let result = self.fit_tool_result(lc, call, result, run).await;
+                    emit(lc.observer, || RunEvent::ToolRun {
+                        node: lc.at.to_string(),
+                        tool: call.function.name.clone(),
+                        args: call.function.arguments.clone(),
+                        result: result.clone(),
+                        duration_us,
+                        error: error.clone(),
+                    });
                     run.events.push(SpanEvent::ToolRun {
                         id: call.id.clone(),
                         name: call.function.name.clone(),
                         args: call.function.arguments.clone(),
                         result: result.clone(),
-                        duration_us: started.elapsed().as_micros() as u64,
+                        duration_us,
                         error,
                     });
                     run.tool_calls.push(call.clone());
@@ -2253,26 +3744,31 @@ impl Interpreter {
         mut chat: Chat,
         run: &mut AgentRun,
     ) -> Result<LoopOutcome, RunError> {
-        lc.run_meter
-            .try_reserve_call()
-            .map_err(|_| RunError::Budget { at: lc.at.into() })?;
+        if lc.run_meter.try_reserve_call().is_err() {
+            emit(lc.observer, || budget_event(lc.at, lc.run_meter, true));
+            return Err(RunError::Budget { at: lc.at.into() });
+        }
         chat.push_message(Message::user(
             "Budget exhausted — wrap up now. Produce the final output fields in the required \
              `[[ ## field ## ]]` format, without calling any tools.",
         ));
-        let response = lc
-            .lm
-            .call(chat, Vec::new())
-            .await
-            .map_err(|err| RunError::Lm {
-                at: lc.at.into(),
-                source: LmError::Provider {
-                    provider: lc.lm.config.model.clone(),
-                    message: err.to_string(),
-                    source: None,
-                },
-            })?;
+        self.guardrails
+            .check_prompt(lc.at, &mut chat, &mut run.events, lc.observer)?;
+        let response = match self.dry_response(lc.at, lc.def, &chat, None) {
+            Some(response) => Ok(response),
+            None => lc.lm.call(chat, Vec::new()).await,
+        }
+        .map_err(|err| RunError::Lm {
+            at: lc.at.into(),
+            source: LmError::Provider {
+                provider: lc.lm.config.model.clone(),
+                message: err.to_string(),
+                source: None,
+            },
+        })?;
         lc.run_meter.record_usage(&response.usage);
+        emit(lc.observer, || exchange_event(lc.at, lc.lm, &response));
+        emit(lc.observer, || budget_event(lc.at, lc.run_meter, false));
         run.usage = run.usage + response.usage;
         run.events.extend(response.events.clone());
         let raw = response.output.content();
@@ -2292,8 +3788,237 @@ impl Interpreter {
         })
     }
 
+    /// The dry-run answer to one LM call (`None` when not dry running):
+    /// synthetic outputs for `def`, seeded by the leaf and the chat's last
+    /// message, as `[[ ## field ## ]]` text — or, given a stop tool, as that
+    /// tool's call. Usage is zero; the event stream records the exchange.
+    fn dry_response(
+        &self,
+        at: &str,
+        def: &SignatureDef,
+        chat: &Chat,
+        stop_tool: Option<&str>,
+    ) -> Option<crate::core::lm::LMResponse> {
+        let seed = self.dry_run?;
+        let asked = chat
+            .messages
+            .last()
+            .map(Message::content)
+            .unwrap_or_default();
+        let seed = crate::ir::synth::call_seed(seed, at, &asked);
+        let output = crate::ir::synth::synthesize(def, &self.program.types, seed);
+        let (message, tool_calls) = match stop_tool {
+            Some(name) => {
+                let call = rig::message::ToolCall::new(
+                    format!("dry_{seed:016x}"),
+                    rig::message::ToolFunction {
+                        name: name.to_string(),
+                        arguments: Value::Object(output),
+                    },
+                );
+                (Message::tool_call(call.clone()), vec![call])
+            }
+            None => (
+                Message::assistant(ChatAdapter.format_output_def(def, &output)),
+                Vec::new(),
+            ),
+        };
+        let mut chat = chat.clone();
+        chat.push_message(message.clone());
+        Some(crate::core::lm::LMResponse {
+            output: message.clone(),
+            usage: LmUsage::default(),
+            chat,
+            tool_calls,
+            tool_executions: Vec::new(),
+            events: vec![SpanEvent::Exchange {
+                message,
+                usage: LmUsage::default(),
+            }],
+        })
+    }
+
+    /// `ContextPolicy.compaction`: once the conversation, counted with the
+    /// model's tokenizer, reaches `at_tokens`, folds every live message but the last
+    /// `keep_recent` (and the pinned ones) into the running summary held
+    /// right after the prefix. The summarizing call is reserved against the
+    /// node meter; with none to spare the conversation goes on uncompacted
+    /// and `max_history_turns` (if any) drops instead. A failed call drops
+    /// the folded messages unsummarized and is recorded as
+    /// [`SpanEvent::SummaryFailed`].
+    async fn compact_history(&self, lc: &AgentLoopCx<'_>, chat: &mut Chat, run: &mut AgentRun) {
+        let Some(compaction) = &lc.policy.compaction else {
+            return;
+        };
+        // Counted as the window check counts it, so compaction triggers
+        // before the model's window is what stops the agent.
+        let tokenizer = self.tokenizer(&lc.lm.config);
+        let size = tool_tokens(tokenizer, lc.toolset.definitions())
+            + count_messages(tokenizer, &chat.messages);
+        let live_start = lc.prefix_len + run.window.held;
+        let live = chat.messages.len().saturating_sub(live_start);
+        let keep = compaction.keep_recent as usize;
+        if size < compaction.at_tokens as usize || live <= keep {
+            return;
+        }
+        // Never open the live tail on tool results whose call is folded away.
+        let mut count = live - keep;
+        while count > 0
+            && chat
+                .messages
+                .get(live_start + count)
+                .is_some_and(Message::has_tool_results)
+        {
+            count -= 1;
+        }
+        let pins = &lc.policy.pinned_turns;
+        let foldable = (0..count)
+            .filter(|&i| {
+                !is_pinned(
+                    run.window.first_live + i,
+                    &chat.messages[live_start + i],
+                    pins,
+                )
+            })
+            .count();
+        if foldable == 0 || lc.meter.try_reserve_call().is_err() {
+            return;
+        }
+
+        let held = run.window.held;
+        let folded = run.window.evict(chat, lc.prefix_len, count, pins);
+        let pinned = (run.window.held - held) as u32;
+        let mut transcript = match &run.window.summary {
+            Some(summary) => format!("Earlier summary:\n{summary}\n\n"),
+            None => String::new(),
+        };
+        for message in &folded {
+            transcript.push_str(&format!(
+                "{}: {}\n",
+                message.role.as_str(),
+                message.content()
+            ));
+        }
+        let instruction = compaction
+            .instruction
+            .as_deref()
+            .unwrap_or(COMPACTION_INSTRUCTION);
+        let (summary, usage, model) = match self.summarize(lc, instruction, &transcript).await {
+            Ok(summarized) => summarized,
+            Err((model, error)) => {
+                run.events.push(SpanEvent::SummaryFailed {
+                    model,
+                    id: None,
+                    error,
+                });
+                return;
+            }
+        };
+
+        let message = Message::user(format!("{SUMMARY_HEADER}\n{summary}"));
+        if run.window.summary.is_some() {
+            chat.messages[lc.prefix_len] = message;
+        } else {
+            chat.messages.insert(lc.prefix_len, message);
+            run.window.held += 1;
+        }
+        run.window.summary = Some(summary.clone());
+        run.events.push(SpanEvent::HistoryCompacted {
+            model,
+            summarized: folded.len() as u32,
+            pinned,
+            summary,
+            usage,
+        });
+        run.usage = run.usage + usage;
+    }
+
+    /// Applies the context policy to one tool result: summarized when longer
+    /// than `summarize_tool_results_over` (and the node meter has a call to
+    /// spare), then clipped to `tool_result_max_bytes`. A failed summary
+    /// leaves the result to the clip.
+    async fn fit_tool_result(
+        &self,
+        lc: &AgentLoopCx<'_>,
+        call: &rig::message::ToolCall,
+        text: String,
+        run: &mut AgentRun,
+    ) -> String {
+        let text = match lc.policy.summarize_tool_results_over {
+            Some(max) if text.len() > max as usize && lc.meter.try_reserve_call().is_ok() => {
+                let request = format!(
+                    "Tool `{}` called with {}:\n\n{text}",
+                    call.function.name, call.function.arguments
+                );
+                match self.summarize(lc, TOOL_RESULT_INSTRUCTION, &request).await {
+                    Ok((summary, usage, model)) => {
+                        run.events.push(SpanEvent::ToolResultSummarized {
+                            id: call.id.clone(),
+                            model,
+                            bytes: text.len() as u64,
+                            usage,
+                        });
+                        run.usage = run.usage + usage;
+                        summary
+                    }
+                    Err((model, error)) => {
+                        run.events.push(SpanEvent::SummaryFailed {
+                            model,
+                            id: Some(call.id.clone()),
+                            error,
+                        });
+                        text
+                    }
+                }
+            }
+            _ => text,
+        };
+        clip_tool_result(text, lc.policy)
+    }
+
+    /// One summarizing call for the context policy — on the compaction model
+    /// when one is declared, else the leaf's own — metered like a turn (the
+    /// caller reserves it). Returns the summary, its usage, and the model
+    /// name, or the model name and why the call failed. Dry runs answer with
+    /// a placeholder and make no call.
+    async fn summarize(
+        &self,
+        lc: &AgentLoopCx<'_>,
+        instruction: &str,
+        text: &str,
+    ) -> Result<(String, LmUsage, String), (String, String)> {
+        let lm = match lc.policy.compaction.as_ref().and_then(|c| c.model) {
+            Some(model) => match self.bound_model(lc.at, model) {
+                Ok(lm) => lm,
+                Err(err) => {
+                    return Err((
+                        self.program.models[model].config.model.clone(),
+                        err.to_string(),
+                    ));
+                }
+            },
+            None => Arc::clone(lc.lm),
+        };
+        let model = lm.config.model.clone();
+        if self.dry_run.is_some() {
+            let summary = format!("[summary of {} bytes]", text.len());
+            return Ok((summary, LmUsage::default(), model));
+        }
+        let chat = Chat::new(vec![Message::system(instruction), Message::user(text)]);
+        let response = match lm.call(chat, Vec::new()).await {
+            Ok(response) => response,
+            Err(err) => return Err((model, err.to_string())),
+        };
+        lc.meter.record_usage(&response.usage);
+        emit(lc.observer, || exchange_event(lc.at, &lm, &response));
+        emit(lc.observer, || budget_event(lc.at, lc.run_meter, false));
+        Ok((response.output.content(), response.usage, model))
+    }
+
     /// Executes one agent tool call. Failures are conversational: the error
     /// text goes back to the model (LATM-style repair), never up the tree.
+    /// The result comes back as the tool produced it; callers pass it
+    /// through [`fit_tool_result`](Self::fit_tool_result).
     async fn execute_agent_tool(
         &self,
         lc: &AgentLoopCx<'_>,
@@ -2309,7 +4034,7 @@ impl Interpreter {
             Ok(text) => (text, None),
             Err(message) => (message.clone(), Some(message)),
         };
-        (clip_tool_result(text, lc.policy), error)
+        (text, error)
     }
 
     /// Routes one tool call to its bound executor (host `ToolDyn` or the
@@ -2394,7 +4119,7 @@ impl Interpreter {
         let mut sandbox_code: HashMap<ToolId, (String, u64)> = HashMap::new();
         for &tool_id in tools.iter() {
             let tool = &p.tools[tool_id];
-            let name = p.syms.get(tool.name).to_string();
+            let name = lm_tool_name(p.syms.get(tool.name)).to_string();
             definitions.push(rig::completion::ToolDefinition {
                 name: name.clone(),
                 description: self.p_text(cx, tool.desc),
@@ -2411,7 +4136,7 @@ impl Interpreter {
             .stop
             .stop_tools
             .iter()
-            .map(|&t| p.syms.get(p.tools[t].name).to_string())
+            .map(|&t| lm_tool_name(p.syms.get(p.tools[t].name)).to_string())
             .collect();
         // Code Mode: collapse the non-stop tool surface into one `run_js`
         // definition (stop tools stay individual — the loop must see their
@@ -2507,6 +4232,8 @@ impl Interpreter {
                         },
                     )
                 }
+                // Dry runs load unbound host tools; dry agents never call them.
+                None if self.dry_run.is_some() => continue,
                 None => {
                     return Err(internal(format!(
                         "tool `{}` is not bound (load should have refused)",
@@ -2538,6 +4265,118 @@ impl Interpreter {
 
     // -- param + port resolution ----------------------------------------------
 
+    /// Renders a leaf's prompt from `demos` and `input` to fit `lm`'s context
+    /// window under the runtime's overflow policy (see
+    /// [`window`](crate::ir::window)), recording the count in `notes`.
+    /// Models with neither a window nor a tokenizer render as-is. A prompt
+    /// that still overflows comes back with a [`RunError::ContextWindow`]
+    /// verdict, trimmed as far as the policy went.
+    #[allow(clippy::too_many_arguments)]
+    fn fit_prompt(
+        &self,
+        at: &str,
+        lm: &LM,
+        tools: &[rig::completion::ToolDefinition],
+        demos: &[DemoRow],
+        input: &JsonMap,
+        notes: &mut Vec<SpanEvent>,
+        render: impl Fn(&[DemoRow], &JsonMap) -> (Vec<Message>, Vec<Message>),
+    ) -> (Vec<Message>, Vec<Message>, Result<(), RunError>) {
+        let config = &lm.config;
+        if config.context_window.is_none() && config.tokenizer.is_none() {
+            let (prefix, suffix) = render(demos, input);
+            return (prefix, suffix, Ok(()));
+        }
+        let tokenizer = self.tokenizer(config);
+        let fitted = crate::ir::window::fit_prompt(
+            tokenizer,
+            prompt_limit(config).map(|limit| limit as usize),
+            tool_tokens(tokenizer, tools),
+            self.overflow,
+            demos,
+            input,
+            render,
+        );
+        notes.push(SpanEvent::PromptTokens {
+            tokenizer: tokenizer.name().to_string(),
+            prompt_tokens: saturating_u32(fitted.prompt_tokens),
+            limit: prompt_limit(config),
+            demos_dropped: saturating_u32(fitted.demos_dropped),
+            truncated: fitted.truncated,
+        });
+        let verdict = window_verdict(at, config, fitted.prompt_tokens);
+        (fitted.prefix, fitted.suffix, verdict)
+    }
+
+    /// Fails an agent turn whose chat, tool definitions included, no longer
+    /// fits `lm`'s context window. Later turns are never trimmed.
+    fn check_window(
+        &self,
+        at: &str,
+        lm: &LM,
+        chat: &Chat,
+        tools: &[rig::completion::ToolDefinition],
+    ) -> Result<(), RunError> {
+        if lm.config.context_window.is_none() {
+            return Ok(());
+        }
+        let tokenizer = self.tokenizer(&lm.config);
+        let tokens = tool_tokens(tokenizer, tools) + count_messages(tokenizer, &chat.messages);
+        window_verdict(at, &lm.config, tokens)
+    }
+
+    /// The tokenizer `config` counts with: the one it names, bound or
+    /// builtin (checked at load), else its model family's default.
+    fn tokenizer(&self, config: &LMConfig) -> &dyn Tokenizer {
+        config
+            .tokenizer
+            .as_deref()
+            .and_then(|name| {
+                self.tokenizers
+                    .get(name)
+                    .map(|tokenizer| &**tokenizer)
+                    .or_else(|| builtin_tokenizer(name))
+            })
+            .unwrap_or_else(|| default_tokenizer(&config.model))
+    }
+
+    /// Runs the prompt guardrails over a leaf's rendered prompt, before its
+    /// span opens: the span, the replay key, and the provider all see the
+    /// guarded chat. A block comes back with the prompt as rewritten so far.
+    fn guard_prompt(
+        &self,
+        at: &str,
+        prefix: Vec<Message>,
+        suffix: Vec<Message>,
+        notes: &mut Vec<SpanEvent>,
+        observer: Option<&Arc<dyn RunObserver>>,
+    ) -> (Vec<Message>, Vec<Message>, Result<(), RunError>) {
+        let split = prefix.len();
+        let mut chat = Chat::new(prefix);
+        chat.messages.extend(suffix);
+        let verdict = self.guardrails.check_prompt(at, &mut chat, notes, observer);
+        let mut prefix = chat.messages;
+        let suffix = prefix.split_off(split.min(prefix.len()));
+        (prefix, suffix, verdict)
+    }
+
+    /// The memo policy and key of a leaf evaluation, when the leaf has a
+    /// `cache` policy: its input plus the resolved values of the `params`
+    /// it reads (see [`memo_key`]).
+    fn memo_key(
+        &self,
+        policy: Option<MemoPolicy>,
+        at: &str,
+        input: &JsonMap,
+        params: &[ParamId],
+        feedback: Option<&str>,
+        cx: &Cx,
+    ) -> Option<(MemoPolicy, u64)> {
+        let policy = policy?;
+        let values: Vec<&ParamValue> = params.iter().map(|id| self.p_value(cx, *id)).collect();
+        Some((policy, memo_key(at, input, &values, feedback)))
+    }
+
     fn p_value<'a>(&'a self, cx: &'a Cx, id: ParamId) -> &'a ParamValue {
         match &cx.overlay {
             Some(overlay) => overlay.resolve(&self.program, id),
@@ -2591,6 +4430,10 @@ impl Interpreter {
                 });
             }
         };
+        self.bound_model(at, model)
+    }
+
+    fn bound_model(&self, at: &str, model: ModelId) -> Result<Arc<LM>, RunError> {
         self.models[model]
             .as_ref()
             .cloned()
@@ -2697,6 +4540,96 @@ struct AgentRun {
     usage: LmUsage,
     tool_calls: Vec<rig::message::ToolCall>,
     tool_executions: Vec<String>,
+    window: ContextWindow,
+}
+
+/// Where an agent conversation stands against its [`ContextPolicy`]. After
+/// the rendered prefix come `held` messages — the running summary (if any),
+/// then evicted pinned messages in order — and then the live tail, which
+/// starts at conversation message `first_live`. Pins keep their numbering
+/// however much truncation and compaction have dropped.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ContextWindow {
+    held: usize,
+    first_live: usize,
+    summary: Option<String>,
+}
+
+impl ContextWindow {
+    /// Takes the oldest `count` live messages out of the live tail: pinned
+    /// ones move to the end of the held region, the rest are returned.
+    fn evict(
+        &mut self,
+        chat: &mut Chat,
+        prefix_len: usize,
+        count: usize,
+        pins: &[u32],
+    ) -> Vec<Message> {
+        let mut evicted = chat.messages.split_off(prefix_len + self.held);
+        let live = evicted.split_off(count);
+        let mut dropped = Vec::new();
+        for (offset, message) in evicted.into_iter().enumerate() {
+            if is_pinned(self.first_live + offset, &message, pins) {
+                chat.messages.push(message);
+                self.held += 1;
+            } else {
+                dropped.push(message);
+            }
+        }
+        chat.messages.extend(live);
+        self.first_live += count;
+        dropped
+    }
+
+    /// The window a continuation turn starts from. A window carried on the
+    /// chat is kept while its held region is still in place; otherwise — a
+    /// chat the caller rebuilt — a summary at `prefix_len` is taken as held,
+    /// so it is replaced rather than summarized again or truncated away.
+    /// Pins then count from the first message after it.
+    fn resume(
+        carried: Option<HeldWindow>,
+        messages: &[Message],
+        prefix_len: usize,
+    ) -> (usize, ContextWindow) {
+        if let Some(HeldWindow { prefix_len, window }) = carried
+            && messages.len() >= prefix_len + window.held
+            && window.summary.is_some() == held_summary(messages.get(prefix_len)).is_some()
+        {
+            return (prefix_len, window);
+        }
+        let summary = held_summary(messages.get(prefix_len));
+        let window = ContextWindow {
+            held: usize::from(summary.is_some()),
+            first_live: 0,
+            summary,
+        };
+        (prefix_len, window)
+    }
+}
+
+/// An agent conversation's [`ContextWindow`] as a turn left it, carried on
+/// the returned [`Chat`] for the next turn to pick up.
+#[derive(Clone, Debug)]
+pub(crate) struct HeldWindow {
+    prefix_len: usize,
+    window: ContextWindow,
+}
+
+/// The running summary a held summary message carries, if `message` is one.
+fn held_summary(message: Option<&Message>) -> Option<String> {
+    let message = message.filter(|message| message.role == Role::User)?;
+    let text = message.text_content();
+    let summary = text.strip_prefix(SUMMARY_HEADER)?.strip_prefix('\n')?;
+    Some(summary.to_string())
+}
+
+/// `ContextPolicy.pinned_turns`, widened so a provider never sees a tool
+/// call without its results or results without their call.
+fn is_pinned(turn: usize, message: &Message, pins: &[u32]) -> bool {
+    let pinned = |turn: usize| u32::try_from(turn).is_ok_and(|turn| pins.contains(&turn));
+    pinned(turn)
+        || (message.has_tool_results() && turn > 0 && pinned(turn - 1))
+        || (message.has_tool_calls() && pinned(turn + 1))
 }
 
 /// How one `agent_loop` invocation ended (short of an error): the accepted
@@ -2718,6 +4651,14 @@ enum LoopOutcome {
     },
 }
 
+/// The name an agent's model sees for a tool: an imported tool's
+/// `<call>.` prefix is dropped, since providers refuse dots in tool names.
+/// One agent's tools all come from the same file, so the bare names stay
+/// unique.
+fn lm_tool_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
 /// One agent loop's resolved tool surface (see
 /// [`Interpreter::build_agent_surface`]).
 struct AgentSurface {
@@ -2754,6 +4695,8 @@ struct AgentLoopCx<'a> {
     run_meter: &'a Arc<BudgetMeter>,
     policy: &'a ContextPolicy,
     code_mode: Option<&'a CodeModeSurface>,
+    /// The run's observer; `None` for caller-driven conversations.
+    observer: Option<&'a Arc<dyn RunObserver>>,
 }
 
 /// One agent loop's Code Mode surface: the wrapped tool capabilities and the
@@ -2781,6 +4724,31 @@ async fn execute_code_mode_script(
         .map_err(|err| err.to_llm_json())
 }
 
+/// Delivers an event to `observer`, building it only when there is one.
+fn emit(observer: Option<&Arc<dyn RunObserver>>, event: impl FnOnce() -> RunEvent) {
+    if let Some(observer) = observer {
+        observer.on_event(&event());
+    }
+}
+
+fn exchange_event(at: &str, lm: &LM, response: &crate::core::lm::LMResponse) -> RunEvent {
+    RunEvent::LmExchange {
+        node: at.to_string(),
+        model: lm.config.model.clone(),
+        output: response.output.content(),
+        usage: response.usage,
+    }
+}
+
+fn budget_event(at: &str, meter: &BudgetMeter, exhausted: bool) -> RunEvent {
+    RunEvent::Budget {
+        node: at.to_string(),
+        lm_calls: meter.lm_calls(),
+        tokens: meter.tokens(),
+        exhausted,
+    }
+}
+
 async fn lm_call_toolset(
     lm: &Arc<LM>,
     chat: Chat,
@@ -2806,7 +4774,7 @@ async fn lm_call_toolset(
 /// Renders the (prefix, suffix) message split for a leaf call: prefix =
 /// system + demo turns, suffix = the live user turn. Instruction and demos
 /// arrive overlay-resolved.
-fn render_prompt(
+pub(crate) fn render_prompt(
     def: &SignatureDef,
     types: &TypeTable,
     instruction: &str,
@@ -2832,6 +4800,78 @@ fn render_prompt(
     (prefix, suffix)
 }
 
+/// The span of a memo-served leaf: the stored output, no usage. `notes` are
+/// the prompt guardrail decisions made before the memo was consulted.
+fn memo_outcome(
+    entry: &MemoEntry,
+    age: std::time::Duration,
+    mut notes: Vec<SpanEvent>,
+) -> SpanOutcome {
+    notes.push(SpanEvent::Memo {
+        age_ms: age.as_millis() as u64,
+    });
+    SpanOutcome {
+        events: notes,
+        raw_output: entry.raw_output.clone(),
+        output: Some(entry.output.clone()),
+        usage: LmUsage::default(),
+        error: None,
+    }
+}
+
+/// Closes a leaf's span on a guardrail block or a context-window overflow
+/// and hands the error back.
+fn guardrail_blocked(
+    guard: Option<crate::trace::SpanGuard>,
+    err: RunError,
+    events: Vec<SpanEvent>,
+    raw_output: Option<String>,
+    usage: LmUsage,
+) -> RunError {
+    if let Some(guard) = guard {
+        let kind = match &err {
+            RunError::ContextWindow { .. } => crate::trace::SpanErrorKind::ContextWindow,
+            _ => crate::trace::SpanErrorKind::Guardrail,
+        };
+        guard.finish(span_error(kind, err.to_string(), events, raw_output, usage));
+    }
+    err
+}
+
+/// The tokens `config` leaves for a prompt: its context window less the
+/// `max_tokens` reserved for the reply. `None` without a window.
+fn prompt_limit(config: &LMConfig) -> Option<u32> {
+    config
+        .context_window
+        .map(|window| window.saturating_sub(config.max_tokens))
+}
+
+/// [`RunError::ContextWindow`] when `prompt_tokens` is over what `config`
+/// leaves for a prompt.
+fn window_verdict(at: &str, config: &LMConfig, prompt_tokens: usize) -> Result<(), RunError> {
+    match prompt_limit(config) {
+        Some(limit) if prompt_tokens > limit as usize => Err(RunError::ContextWindow {
+            at: at.into(),
+            model: config.model.clone(),
+            prompt_tokens: saturating_u32(prompt_tokens),
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Tokens an agent's tool definitions cost, counted as the JSON sent.
+fn tool_tokens(tokenizer: &dyn Tokenizer, tools: &[rig::completion::ToolDefinition]) -> usize {
+    if tools.is_empty() {
+        return 0;
+    }
+    tokenizer.count(&serde_json::to_string(tools).unwrap_or_default())
+}
+
+fn saturating_u32(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
 fn span_error(
     kind: crate::trace::SpanErrorKind,
     message: String,
@@ -2965,25 +5005,44 @@ fn node_budget(budget: &crate::ir::graph::NodeBudget) -> Budget {
     }
 }
 
-/// `ContextPolicy.max_history_turns`: keep the rendered prefix plus the
-/// trailing N conversation messages.
-fn truncate_history(chat: Chat, prefix_len: usize, policy: &ContextPolicy) -> Chat {
+/// `ContextPolicy.max_history_turns`: keep the rendered prefix, the held
+/// summary and pinned messages, plus the trailing N live messages.
+fn truncate_history(
+    mut chat: Chat,
+    prefix_len: usize,
+    policy: &ContextPolicy,
+    window: &mut ContextWindow,
+) -> Chat {
     let Some(max_turns) = policy.max_history_turns else {
         return chat;
     };
     let max_turns = max_turns as usize;
-    let tail = chat.messages.len().saturating_sub(prefix_len);
-    if tail <= max_turns {
-        return chat;
+    let live = chat.messages.len().saturating_sub(prefix_len + window.held);
+    if live > max_turns {
+        window.evict(
+            &mut chat,
+            prefix_len,
+            live - max_turns,
+            &policy.pinned_turns,
+        );
     }
-    let mut messages = chat.messages;
-    let mut kept: Vec<Message> = messages.drain(..prefix_len).collect();
-    // `messages` is now the conversation tail; keep its last `max_turns`.
-    let skip = messages.len() - max_turns;
-    kept.extend(messages.into_iter().skip(skip));
-    Chat::new(kept)
+    chat
 }
 
+/// Built-in instruction for [`Compaction`](crate::ir::Compaction) summaries.
+pub(crate) const COMPACTION_INSTRUCTION: &str = "You compress an agent's working conversation. Summarize \
+    the transcript below, and the earlier summary if one is given, into a concise record of \
+    the task, the facts and tool results established so far, the decisions made, and what \
+    remains open. Keep names, numbers, and identifiers exact. Reply with the summary only.";
+
+/// Built-in instruction for `ContextPolicy.summarize_tool_results_over`.
+pub(crate) const TOOL_RESULT_INSTRUCTION: &str = "You condense tool output for an agent. Summarize the \
+    tool result below, keeping every fact, name, number, and identifier the agent may need to \
+    act on it. Reply with the summary only.";
+
+/// Heads the held summary message.
+const SUMMARY_HEADER: &str = "Summary of the earlier conversation:";
+
 /// Applies `ContextPolicy.tool_result_max_bytes` to one tool result — the
 /// same clip whether the tool was dispatched or the result came back through
 /// [`Interpreter::resume_conversation`].
@@ -3053,6 +5112,45 @@ fn hole_request_hash(imp: &ResolvedHoleImpl, input: &JsonMap, caps: &CapSet) ->
     hasher.finish()
 }
 
+/// The approval replay preimage: the canonical payload (keys sorted at every
+/// depth). Like holes, approvals render no prompt, so the payload is what
+/// makes each one addressable by replay.
+fn approval_request_hash(payload: &JsonMap) -> u64 {
+    use std::hash::Hasher as _;
+    let mut hasher = crate::utils::hash::StableHasher::new();
+    hasher.write(b"approve");
+    let mut fields: Vec<(&String, &Value)> = payload.iter().collect();
+    fields.sort_by_key(|(key, _)| *key);
+    for (key, value) in fields {
+        hasher.write(key.as_bytes());
+        hasher.write(canonical_json(value).as_bytes());
+    }
+    hasher.finish()
+}
+
+/// Compact JSON with object keys sorted recursively, so maps built in a
+/// different insertion order (`preserve_order` keeps it) print the same.
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<(&String, &Value)> = map.iter().collect();
+            fields.sort_by_key(|(key, _)| *key);
+            let fields: Vec<String> = fields
+                .into_iter()
+                .map(|(key, value)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(value))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        scalar => scalar.to_string(),
+    }
+}
+
 /// Registers a code gene in the sandbox under a content-addressed name
 /// (`<base>-<hash hex>`), deduplicating against an already-registered tool.
 async fn register_code(
@@ -3112,6 +5210,27 @@ struct Cx {
     /// ([`Interpreter::run_collecting`]); successful `Predict` leaves push
     /// here in execution order. `None` = plain `run`, zero collection cost.
     leaves: Option<Vec<LeafOutcome>>,
+    /// The approval queue of a [`Interpreter::run_suspendable`] run, or of
+    /// a [`Interpreter::run_with`] run with an approver; `None` = nobody to
+    /// ask, and `Approve` nodes fail the run.
+    approvals: Option<mpsc::UnboundedSender<PendingApproval>>,
+    /// The checkpoint log of a [`Interpreter::run_with`] run.
+    checkpoints: Option<Arc<CheckpointLog>>,
+    /// The observer of a [`Interpreter::run_with`] run.
+    observer: Option<Arc<dyn RunObserver>>,
+}
+
+/// What distinguishes the public run entry points, threaded into
+/// `run_inner`.
+#[derive(Default)]
+struct RunHooks {
+    collect: bool,
+    approvals: Option<mpsc::UnboundedSender<PendingApproval>>,
+    checkpoints: Option<Arc<CheckpointLog>>,
+    observer: Option<Arc<dyn RunObserver>>,
+    /// A meter the caller keeps a handle on, to read spend after the run
+    /// future is dropped.
+    meter: Option<Arc<BudgetMeter>>,
 }
 
 impl Cx {
@@ -3127,6 +5246,15 @@ impl Cx {
             feedback: None,
             refine_feedback: None,
             leaves: self.leaves.as_ref().map(|_| Vec::new()),
+            approvals: self.approvals.clone(),
+            checkpoints: self.checkpoints.clone(),
+            observer: self.observer.clone(),
         }
     }
+
+    /// Delivers an event to the run's observer; the event is only built
+    /// when someone is listening.
+    fn emit(&self, event: impl FnOnce() -> RunEvent) {
+        emit(self.observer.as_ref(), event);
+    }
 }
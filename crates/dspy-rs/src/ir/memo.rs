@@ -0,0 +1,190 @@
+//! The node memo behind `cache { ttl_s ... }` ([`MemoPolicy`]).
+//!
+//! The LM response cache keys on the rendered prompt, so a deterministic
+//! leaf — a pure `js` hole, an extern retrieval step — re-runs on every
+//! run, and an optimizer scoring candidates that differ only downstream
+//! re-runs every unchanged upstream leaf. A memoized leaf instead keys on
+//! what decides its output: its name, its resolved input, and the resolved
+//! value of every parameter it reads (instruction, demos, model, tool set,
+//! tool descriptions, context policy, code). Overlays that leave those
+//! alone share the leaf's entries; one that changes any of them misses.
+//!
+//! Entries live on the loaded [`Interpreter`](crate::ir::Interpreter),
+//! shared by all of its runs and overlays, and only successful evaluations
+//! are stored. The store is bounded (see
+//! [`RuntimeEnv::memo_capacity`](crate::ir::RuntimeEnv::memo_capacity)):
+//! every store first drops the entries whose TTL ran out, and at capacity
+//! the least recently used entry goes. A served leaf makes no call and
+//! spends no budget, but still records its span, marked with
+//! [`SpanEvent::Memo`](crate::trace::SpanEvent). Replay is consulted first,
+//! so a replayed run stays aligned with its recording.
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hasher as _;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::ir::checkpoint::leaf_input_hash;
+use crate::ir::graph::MemoPolicy;
+use crate::ir::params::ParamValue;
+use crate::trace::JsonMap;
+
+/// How many leaf evaluations an interpreter's memo keeps unless its
+/// [`RuntimeEnv`](crate::ir::RuntimeEnv) sets another bound.
+pub const DEFAULT_MEMO_CAPACITY: usize = 4096;
+
+/// One stored leaf evaluation.
+#[derive(Clone, Debug)]
+pub(crate) struct MemoEntry {
+    pub(crate) output: JsonMap,
+    /// The raw text the leaf produced (LM reply or hole result), for the
+    /// served span and [`LeafOutcome`](crate::ir::LeafOutcome).
+    pub(crate) raw_output: Option<String>,
+    stored: Instant,
+    /// When the entry outlives its leaf's TTL, if it has one.
+    expires: Option<Instant>,
+    /// The store's use tick when the entry was last stored or served.
+    used: u64,
+}
+
+/// The memo's entries with two orderings over them: by last use, for
+/// evicting the least recently used entry at capacity, and by expiry, for
+/// sweeping entries whose TTL ran out without another lookup.
+#[derive(Debug, Default)]
+struct Slots {
+    entries: HashMap<u64, MemoEntry>,
+    recency: BTreeSet<(u64, u64)>,
+    deadlines: BTreeSet<(Instant, u64)>,
+    tick: u64,
+}
+
+impl Slots {
+    fn remove(&mut self, key: u64) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.recency.remove(&(entry.used, key));
+            if let Some(expires) = entry.expires {
+                self.deadlines.remove(&(expires, key));
+            }
+        }
+    }
+
+    fn sweep(&mut self, now: Instant) {
+        while let Some(&(expires, key)) = self.deadlines.first()
+            && expires <= now
+        {
+            self.remove(key);
+        }
+    }
+}
+
+/// A bounded, least-recently-used store of leaf evaluations.
+#[derive(Debug)]
+pub(crate) struct MemoStore {
+    slots: Mutex<Slots>,
+    capacity: usize,
+}
+
+impl Default for MemoStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMO_CAPACITY)
+    }
+}
+
+impl MemoStore {
+    /// A store holding at most `capacity` entries (at least one).
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            slots: Mutex::default(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// The entry for `key` and its age, unless it outlived `policy`'s TTL
+    /// (then it is dropped). A hit counts as a use.
+    pub(crate) fn get(&self, key: u64, policy: MemoPolicy) -> Option<(MemoEntry, Duration)> {
+        let mut slots = self.slots.lock().unwrap();
+        let age = slots.entries.get(&key)?.stored.elapsed();
+        if policy
+            .ttl_s
+            .is_some_and(|ttl| age >= Duration::from_secs(ttl))
+        {
+            slots.remove(key);
+            return None;
+        }
+        slots.tick += 1;
+        let tick = slots.tick;
+        let entry = slots.entries.get_mut(&key)?;
+        let last = std::mem::replace(&mut entry.used, tick);
+        let entry = entry.clone();
+        slots.recency.remove(&(last, key));
+        slots.recency.insert((tick, key));
+        Some((entry, age))
+    }
+
+    /// Stores `output` under `key`, first dropping every expired entry and
+    /// then, at capacity, the least recently used one.
+    pub(crate) fn put(
+        &self,
+        key: u64,
+        policy: MemoPolicy,
+        output: &JsonMap,
+        raw_output: Option<String>,
+    ) {
+        let mut slots = self.slots.lock().unwrap();
+        let now = Instant::now();
+        slots.sweep(now);
+        slots.remove(key);
+        while slots.entries.len() >= self.capacity {
+            let Some(&(_, oldest)) = slots.recency.first() else {
+                break;
+            };
+            slots.remove(oldest);
+        }
+        slots.tick += 1;
+        let used = slots.tick;
+        let expires = policy.ttl_s.map(|ttl| now + Duration::from_secs(ttl));
+        if let Some(expires) = expires {
+            slots.deadlines.insert((expires, key));
+        }
+        slots.recency.insert((used, key));
+        slots.entries.insert(
+            key,
+            MemoEntry {
+                output: output.clone(),
+                raw_output,
+                stored: now,
+                expires,
+                used,
+            },
+        );
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.slots.lock().unwrap() = Slots::default();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.slots.lock().unwrap().entries.len()
+    }
+}
+
+/// The memo key of leaf `at`: its canonical input hash, the canonical hash
+/// of each resolved parameter it reads (in the leaf's fixed order), and any
+/// pending retry feedback, which changes the prompt.
+pub(crate) fn memo_key(
+    at: &str,
+    input: &JsonMap,
+    params: &[&ParamValue],
+    feedback: Option<&str>,
+) -> u64 {
+    let mut hasher = crate::utils::hash::StableHasher::new();
+    hasher.write(at.as_bytes());
+    hasher.write_u64(leaf_input_hash(input));
+    for value in params {
+        hasher.write_u64(crate::optimizer::engine::canonical_hash(value));
+    }
+    if let Some(feedback) = feedback {
+        hasher.write(feedback.as_bytes());
+    }
+    hasher.finish()
+}
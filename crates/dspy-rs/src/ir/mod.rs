@@ -20,6 +20,31 @@
 //!   lineage) is the [`Program::compute_hash`] preimage. A canonical JSON
 //!   projection ([`serde::Serialize`]) remains for embedding programs in
 //!   JSON documents; both forms agree on the hash.
+//! - **Module export** — [`export_module`] recovers a program from a typed
+//!   [`Module`](crate::Module) by tracing it over sample inputs; Rust the
+//!   trace cannot see becomes extern holes.
+//! - **Diagrams** — [`Program::to_dot`]/[`Program::to_mermaid`] draw the
+//!   graph for review, optionally annotated from an overlay and a trace
+//!   ([`GraphNotes`]).
+//! - **Diffs** — [`Program::diff`] and [`Overlay::diff`] report what changed
+//!   between a parent and a child program, or two candidates, by name and
+//!   param path rather than by text line.
+//! - **Cost bounds** — [`Program::cost`] computes worst-case and expected LM
+//!   calls, tokens, dollars, and latency per step and per run from the
+//!   graph's bounds and budgets, flagging what only runtime can tell.
+//! - **Memos** — a leaf's [`MemoPolicy`] (`cache { ttl_s 3600 }`) lets the
+//!   interpreter serve it from an earlier evaluation on the same resolved
+//!   inputs and parameter values ([`memo`]).
+//! - **Guardrails** — [`Guardrail`] policies bound on the [`RuntimeEnv`]
+//!   check, rewrite, or block run inputs, leaf prompts, leaf outputs, and run
+//!   outputs, recording each decision on the leaf's span ([`guardrail`]).
+//! - **Context windows** — leaf prompts are counted with the model's
+//!   [`Tokenizer`](crate::Tokenizer) against its `context_window`, and an
+//!   [`OverflowPolicy`] trims demos, truncates inputs, or fails the leaf
+//!   before anything is sent ([`window`]).
+//! - **Dry runs** — [`RuntimeEnv::dry_run`] answers every LM leaf with
+//!   [`synthesize`]d, type-valid outputs, so a program runs end to end
+//!   offline.
 
 pub mod sig;
 
@@ -30,40 +55,71 @@ pub use sig::{
 
 pub mod bridge;
 pub mod builder;
+pub mod checkpoint;
+pub mod cost;
+pub mod diagram;
+pub mod diff;
 pub mod edit;
+pub mod export;
 pub mod graph;
+pub mod guardrail;
 pub mod interp;
+pub mod memo;
 pub mod module_build;
+pub mod observe;
 pub mod params;
 pub mod step;
+pub mod synth;
 pub mod text;
+pub(crate) mod transform;
 pub mod validate;
+pub mod window;
 
 pub use bridge::{current_overlay, with_ambient_overlay, with_overlay};
 pub use builder::{
-    AsNodeName, BuildError, NodeSpec, Port, ProgramBuilder, agent, carried, cot, extern_hole, fork,
-    hole, input, lit, loop_, out, predict, refine, retry, route, seq,
+    AsNodeName, BuildError, NodeSpec, Port, ProgramBuilder, agent, approve, call, carried, cot,
+    extern_hole, fork, hole, input, lit, loop_, map, out, predict, refine, retry, route, seq,
+    transform,
 };
+pub use checkpoint::{
+    CHECKPOINT_VERSION, CompletedLeaf, MeterReading, RunCheckpoint, leaf_input_hash,
+};
+pub use cost::{
+    Cost, CostAssumptions, CostFlag, CostOptions, CostReport, Estimate, ModelPrice, NodeCost,
+    Pricing, Runs,
+};
+pub use diagram::GraphNotes;
+pub use diff::{Change, OverlayDiff, ProgramDiff};
 pub use edit::{ApplyError, Edit, EditError, EditKind, SwapTarget, migrate_overlay};
+pub use export::{ExportError, export_module};
 pub use graph::{
-    AgentLoopNode, BakeError, Binding, BudgetPolicy, CapSet, ForkJoinNode, HoleImpl, HoleNode,
-    Interner, Lineage, LoopNode, ModelDef, ModelId, Node, NodeBudget, NodeId, PortRef, PredictNode,
-    Program, ProgramMeta, RefineNode, RetryNode, RouteNode, SeqNode, SigId, StopSpec, Sym, ToolDef,
-    ToolId, ToolKind,
+    AgentLoopNode, ApproveNode, BakeError, Binding, BudgetPolicy, CallNode, CapSet, Comments,
+    ForkJoinNode, HoleImpl, HoleNode, ImportDef, ImportId, Interner, Lineage, LoopNode,
+    MapErrorPolicy, MapNode, MemoPolicy, ModelDef, ModelId, Node, NodeBudget, NodeId, PortRef,
+    PredictNode, Program, ProgramMeta, RefineNode, RetryNode, RouteNode, SeqNode, SigId, StopSpec,
+    Sym, ToolDef, ToolId, ToolKind, TransformNode, Trivia,
+};
+pub use guardrail::{
+    GuardAction, GuardStage, Guardrail, PromptCheck, ValueCheck, ValueStage, Verdict,
 };
 pub use interp::{
-    Budget, BudgetMeter, ConversationTurn, Exhausted, HostHoleFn, Interpreter, LeafOutcome,
-    LoadError, RunError, RunOutput, RuntimeEnv, ToolSuspension, input_schema_of,
+    ApprovalDecision, Budget, BudgetMeter, CancellationToken, ConversationTurn, Exhausted,
+    HostHoleFn, Interpreter, LeafOutcome, LoadError, PartialRun, RunError, RunOptions, RunOutput,
+    RunSuspension, RunTurn, RuntimeEnv, ToolSuspension, input_schema_of,
 };
+pub use memo::DEFAULT_MEMO_CAPACITY;
 pub use module_build::{
     ModuleBuildError, ModuleSpec, ModuleStep, ModuleStepKind, PortSpec, build_module_program,
     default_lm, unbound_model_config,
 };
+pub use observe::{RunEvent, RunObserver};
 pub use params::{
-    CodeK, CodeLang, ContextK, ContextPolicy, DemoRow, Demos, Instruction, KindTag, ModelRefK,
-    Overlay, OverlayError, ParamId, ParamKind, ParamOwner, ParamSlot, ParamValue, Slot, ToolDesc,
-    ToolSetK, code_hash,
+    CodeK, CodeLang, Compaction, ContextK, ContextPolicy, DemoRow, Demos, Instruction, KindTag,
+    ModelRefK, Overlay, OverlayError, ParamId, ParamKind, ParamOwner, ParamSlot, ParamValue, Slot,
+    ToolDesc, ToolSetK, code_hash,
 };
 pub use step::{AgentStepOpts, HoleReport, StepDef, StepKind, ToolStepDef};
-pub use text::{DsrsFileError, ParseError};
-pub use validate::ValidateError;
+pub use synth::synthesize;
+pub use text::{DsrsFileError, ParseError, SourceMap, Span};
+pub use validate::{PortScope, ValidateError};
+pub use window::{OverflowPolicy, TRUNCATION_MARKER};
@@ -0,0 +1,118 @@
+//! Run observers: what a run is doing, pushed while it happens.
+//!
+//! A trace ([`capture`](crate::trace::capture)) is read after the run ends,
+//! and [`LeafOutcome`](crate::ir::LeafOutcome)s arrive with the output. An
+//! observed run
+//! ([`RunOptions::with_observer`](crate::ir::RunOptions::with_observer), for
+//! [`Interpreter::run_with`](crate::ir::Interpreter::run_with) and
+//! [`Interpreter::resume_from`](crate::ir::Interpreter::resume_from)) calls
+//! a [`RunObserver`] with a [`RunEvent`] at each step instead: node start
+//! and finish, every LM exchange and tool run, retries, route and guardrail
+//! decisions, and the run meter after every charge — enough for progress
+//! UIs, custom logging, and live cost meters.
+//!
+//! Events are delivered inline on the run's task, in execution order (`fork`
+//! branches and `map` elements interleave as they make progress). Keep the
+//! observer quick; forward to a channel for anything slow:
+//!
+//! ```ignore
+//! let (tx, mut rx) = futures::channel::mpsc::unbounded();
+//! let options = RunOptions::new().with_observer(move |event: &RunEvent| {
+//!     let _ = tx.unbounded_send(event.clone());
+//! });
+//! let run = interp.run_with(input, None, budget, options);
+//! ```
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::LmUsage;
+use crate::ir::guardrail::{GuardAction, GuardStage};
+
+/// One step of an observed run. `node` is the leaf name for leaves and the
+/// node id (`n3`) for containers — the same `at` [`RunError`]s carry.
+///
+/// [`RunError`]: crate::ir::RunError
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    /// A node began evaluating. `kind` is its `.dsrs` keyword (`predict`,
+    /// `agent`, `route`, ...).
+    NodeStart { node: String, kind: &'static str },
+    /// A leaf a resumed run served from its checkpoint, with no LM call; it
+    /// has no start or finish.
+    CheckpointServed { node: String, kind: &'static str },
+    /// A node finished; `error` is set when it failed (a failure inside
+    /// `retry` is followed by a [`Retry`](Self::Retry) event).
+    NodeFinish {
+        node: String,
+        kind: &'static str,
+        duration_us: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// One LM round-trip of a `predict` or `agent` leaf: the model's reply
+    /// text and the call's usage.
+    LmExchange {
+        node: String,
+        model: String,
+        output: String,
+        usage: LmUsage,
+    },
+    /// One tool an agent ran, with the result text fed back to the model.
+    ToolRun {
+        node: String,
+        tool: String,
+        args: Value,
+        result: String,
+        duration_us: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A `retry` node caught a retryable failure and starts attempt
+    /// `attempt` (1-based: the first retry is attempt 2).
+    Retry {
+        node: String,
+        attempt: u32,
+        error: String,
+    },
+    /// A `route` node chose `arm` (its node) for `value`; `None` when no
+    /// arm matched and the run fails.
+    Route {
+        node: String,
+        value: String,
+        arm: Option<String>,
+    },
+    /// A guardrail flagged, rewrote, or blocked at `node` (`$` for the run
+    /// input and output).
+    Guardrail {
+        node: String,
+        guard: String,
+        stage: GuardStage,
+        action: GuardAction,
+        reason: String,
+    },
+    /// The run meter after `node` charged it: cumulative LM calls and
+    /// tokens. `exhausted` is set when the charge was refused.
+    Budget {
+        node: String,
+        lm_calls: u32,
+        tokens: u64,
+        exhausted: bool,
+    },
+}
+
+/// Receives a run's [`RunEvent`]s as they happen. Implemented for every
+/// `Fn(&RunEvent) + Send + Sync`.
+pub trait RunObserver: Send + Sync {
+    fn on_event(&self, event: &RunEvent);
+}
+
+impl<F> RunObserver for F
+where
+    F: Fn(&RunEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &RunEvent) {
+        self(event)
+    }
+}
@@ -122,6 +122,36 @@ impl ParamValue {
             Self::Code { .. } => ParamKind::Code,
         }
     }
+
+    /// The model this value refers to: a `ModelRef`'s target, or a context
+    /// policy's compaction model.
+    pub fn model(&self) -> Option<ModelId> {
+        match self {
+            Self::ModelRef { model } => Some(*model),
+            Self::ContextPolicy { policy } => policy.compaction.as_ref().and_then(|c| c.model),
+            _ => None,
+        }
+    }
+
+    /// This value with its [`model`](Self::model) re-minted through `f`
+    /// (splicing, migration). Values without a model come back unchanged;
+    /// `None` when `f` finds no counterpart.
+    pub(crate) fn map_model(&self, f: impl FnOnce(ModelId) -> Option<ModelId>) -> Option<Self> {
+        let Some(model) = self.model() else {
+            return Some(self.clone());
+        };
+        let model = f(model)?;
+        Some(match self {
+            Self::ContextPolicy { policy } => {
+                let mut policy = policy.clone();
+                if let Some(compaction) = &mut policy.compaction {
+                    compaction.model = Some(model);
+                }
+                Self::ContextPolicy { policy }
+            }
+            _ => Self::ModelRef { model },
+        })
+    }
 }
 
 /// Stable content hash of a code gene's source.
@@ -155,6 +185,56 @@ pub struct ContextPolicy {
     /// Free-text playbook injected after the instruction (ACE/Dynamic
     /// Cheatsheet pattern) — reflective optimizers write here.
     pub playbook: Option<String>,
+    /// Summarize older conversation messages once the conversation grows
+    /// past a size, instead of only dropping them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<Compaction>,
+    /// Tool results longer than this many bytes are summarized by the
+    /// compaction model before the agent reads them; `tool_result_max_bytes`
+    /// still clips the summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summarize_tool_results_over: Option<u32>,
+    /// Conversation messages (0 is the rendered input turn) that truncation
+    /// and compaction keep verbatim. A pinned tool call keeps its results,
+    /// and pinned results keep their call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_turns: Vec<u32>,
+}
+
+/// [`ContextPolicy::compaction`]: when the conversation reaches `at_tokens`
+/// (counted with the model's tokenizer, tool definitions included), every
+/// message but the last `keep_recent` and the pinned ones is folded into one
+/// running summary.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Compaction {
+    pub at_tokens: u32,
+    #[serde(default = "Compaction::default_keep_recent")]
+    pub keep_recent: u32,
+    /// The declared model that writes summaries; `None` uses the leaf's own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelId>,
+    /// Replaces the built-in summarizer instruction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+}
+
+impl Compaction {
+    pub const DEFAULT_KEEP_RECENT: u32 = 4;
+
+    /// Compaction at `at_tokens`, keeping the default recent window and
+    /// summarizing with the leaf's own model.
+    pub fn at_tokens(at_tokens: u32) -> Self {
+        Self {
+            at_tokens,
+            keep_recent: Self::DEFAULT_KEEP_RECENT,
+            model: None,
+            instruction: None,
+        }
+    }
+
+    fn default_keep_recent() -> u32 {
+        Self::DEFAULT_KEEP_RECENT
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -56,6 +56,29 @@ pub struct FieldDef {
     pub constraints: Box<[ConstraintDef]>,
     #[serde(default)]
     pub render: RenderSpec,
+    /// Input only: the value stays in the input map (metrics, tools, holes,
+    /// and other fields' templates read it) but is never shown to the LM.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Text opening the field's section in prompts and demos, ahead of the
+    /// value; stripped from a parsed output section that echoes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<Box<str>>,
+    /// Output only: a rendering hint shown in the response template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<Box<str>>,
+    /// Output only: example values shown in the response template.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub examples: Box<[Box<str>]>,
+    /// Position in the prompt among the fields on its side. Fields sort
+    /// stably by it, unset counting as 0, so a lone `order -1` moves a field
+    /// first without renumbering the rest. Parsing is by marker, not position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+    /// Heading printed above the field in the field list and the input
+    /// message, once per run of consecutive fields sharing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<Box<str>>,
 }
 
 impl FieldDef {
@@ -68,6 +91,12 @@ impl FieldDef {
             docs: None,
             constraints: Box::new([]),
             render: RenderSpec::Default,
+            hidden: false,
+            prefix: None,
+            hint: None,
+            examples: Box::new([]),
+            order: None,
+            section: None,
         }
     }
 
@@ -96,6 +125,44 @@ impl FieldDef {
         self.render = render;
         self
     }
+
+    /// Hides an input field from the LM (`#[input(hidden)]`).
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Sets the text that opens the field's section (`#[prefix("...")]`).
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets an output field's rendering hint (`#[hint("...")]`).
+    pub fn with_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Appends an output field example value (`#[example("...")]`).
+    pub fn with_example(mut self, example: &str) -> Self {
+        let mut examples = self.examples.into_vec();
+        examples.push(example.into());
+        self.examples = examples.into_boxed_slice();
+        self
+    }
+
+    /// Sets the field's prompt position (`#[order(N)]`).
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Sets the heading the field renders under (`#[section("...")]`).
+    pub fn with_section(mut self, section: &str) -> Self {
+        self.section = Some(section.into());
+        self
+    }
 }
 
 /// Owned runtime form of [`ConstraintSpec`](crate::ConstraintSpec) (which stays
@@ -172,6 +239,12 @@ pub enum SigError {
         expr: String,
         message: String,
     },
+    #[error("`{what}` is only supported on {side} fields (field `{field}`)")]
+    MisplacedPresentation {
+        field: String,
+        what: &'static str,
+        side: &'static str,
+    },
     #[error(
         "map keys must be String in Signature fields (field `{field}`); hint: use HashMap<String, V> or BTreeMap<String, V>"
     )]
@@ -334,6 +407,19 @@ fn validate_side(side: &'static str, fields: &[FieldDef]) -> Result<(), SigError
                 current: field.name.to_string(),
             });
         }
+        let misplaced = match side {
+            "output" if field.hidden => Some(("hidden", "input")),
+            "input" if field.hint.is_some() => Some(("hint", "output")),
+            "input" if !field.examples.is_empty() => Some(("example", "output")),
+            _ => None,
+        };
+        if let Some((what, side)) = misplaced {
+            return Err(SigError::MisplacedPresentation {
+                field: field.name.to_string(),
+                what,
+                side,
+            });
+        }
         validate_field(field)?;
     }
     Ok(())
@@ -478,6 +564,17 @@ fn field_def_of(field: &FieldSchema) -> FieldDef {
             InputRenderSpec::Format(value) => RenderSpec::Format(value.into()),
             InputRenderSpec::Jinja(template) => RenderSpec::Jinja(template.into()),
         },
+        hidden: field.presentation.hidden,
+        prefix: field.presentation.prefix.map(Into::into),
+        hint: field.presentation.hint.map(Into::into),
+        examples: field
+            .presentation
+            .examples
+            .iter()
+            .map(|example| (*example).into())
+            .collect(),
+        order: field.presentation.order,
+        section: field.presentation.section.map(Into::into),
     }
 }
 
@@ -0,0 +1,165 @@
+//! Synthetic outputs for dry runs ([`RuntimeEnv::dry_run`]): a type-valid
+//! value for every output field of a signature, generated from its
+//! [`FieldType`] and the program's [`TypeTable`], deterministic from a seed.
+//!
+//! Enums pick one of their variants, literals and unions one of their
+//! alternatives; lists hold one to three elements (so `map` bodies run);
+//! optionals are `null` a quarter of the time; classes fill every field.
+//! Field and class constraints (`check`/`assert` expressions) are honored
+//! by regenerating until every expression holds, up to a fixed number of
+//! attempts — after which the last candidate stands and the constraint
+//! surfaces exactly as it would for a model that broke it.
+//!
+//! Values are canonical (class fields and enum variants by their Rust name),
+//! which is also what coercion produces from an LM response.
+//!
+//! [`RuntimeEnv::dry_run`]: crate::ir::RuntimeEnv::dry_run
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Map, Value, json};
+
+use crate::ir::sig::SignatureDef;
+use crate::trace::JsonMap;
+use crate::typesys::{FieldType, TypeTable, evaluate_expression};
+
+/// Candidates tried per constrained value before the last one stands.
+const ATTEMPTS: usize = 16;
+
+/// Past this nesting depth lists are empty and optionals `null`, so
+/// recursive classes terminate.
+const MAX_DEPTH: usize = 6;
+
+const WORDS: &[&str] = &[
+    "amber", "basalt", "cedar", "delta", "ember", "fjord", "granite", "harbor", "indigo",
+    "juniper", "kestrel", "lagoon", "meadow", "nimbus", "orchid", "quartz",
+];
+
+/// One output map for `def`: every output field, type-valid against
+/// `types`, the same for the same `seed`.
+pub fn synthesize(def: &SignatureDef, types: &TypeTable, seed: u64) -> JsonMap {
+    let mut rng = StdRng::seed_from_u64(seed);
+    def.outputs
+        .iter()
+        .map(|field| {
+            let checks: Vec<&str> = field.constraints.iter().map(|c| &*c.expr).collect();
+            let value = satisfying(&field.ty, types, &checks, &mut rng, 0);
+            (field.name.to_string(), value)
+        })
+        .collect()
+}
+
+/// The seed of one dry-run call: the run's seed, the leaf, and what the
+/// leaf was asked (its rendered prompt or input), so a loop iteration with
+/// new inputs gets new outputs and a replayed run gets the same ones.
+pub(crate) fn call_seed(seed: u64, at: &str, asked: &str) -> u64 {
+    use std::hash::Hasher as _;
+    let mut hasher = crate::utils::hash::StableHasher::new();
+    hasher.write_u64(seed);
+    hasher.write(at.as_bytes());
+    hasher.write(asked.as_bytes());
+    hasher.finish()
+}
+
+fn satisfying(
+    ty: &FieldType,
+    types: &TypeTable,
+    checks: &[&str],
+    rng: &mut StdRng,
+    depth: usize,
+) -> Value {
+    let mut value = generate(ty, types, rng, depth);
+    for _ in 1..ATTEMPTS {
+        if checks.iter().all(|expr| evaluate_expression(expr, &value)) {
+            break;
+        }
+        value = generate(ty, types, rng, depth);
+    }
+    value
+}
+
+fn generate(ty: &FieldType, types: &TypeTable, rng: &mut StdRng, depth: usize) -> Value {
+    let deep = depth >= MAX_DEPTH;
+    match ty {
+        FieldType::String => {
+            let n = rng.gen_range(1..=3);
+            let words: Vec<&str> = (0..n)
+                .map(|_| WORDS[rng.gen_range(0..WORDS.len())])
+                .collect();
+            Value::String(words.join(" "))
+        }
+        FieldType::Int => json!(rng.gen_range(0..=100)),
+        FieldType::Float => json!(rng.gen_range(0..=100) as f64 / 100.0),
+        FieldType::Bool => Value::Bool(rng.gen_bool(0.5)),
+        FieldType::Literal(text) => Value::String(text.clone()),
+        FieldType::List(inner) => {
+            let n = if deep { 0 } else { rng.gen_range(1..=3) };
+            Value::Array(
+                (0..n)
+                    .map(|_| generate(inner, types, rng, depth + 1))
+                    .collect(),
+            )
+        }
+        FieldType::Optional(inner) => {
+            if deep || rng.gen_ratio(1, 4) {
+                Value::Null
+            } else {
+                generate(inner, types, rng, depth + 1)
+            }
+        }
+        FieldType::Map(_, value) => {
+            let n = if deep { 0 } else { rng.gen_range(1..=2) };
+            let entries: Map<String, Value> = (0..n)
+                .map(|i| {
+                    let key = format!("{}{i}", WORDS[rng.gen_range(0..WORDS.len())]);
+                    (key, generate(value, types, rng, depth + 1))
+                })
+                .collect();
+            Value::Object(entries)
+        }
+        FieldType::Class(name) => {
+            let Some(class) = types.classes.get(name) else {
+                return Value::Null;
+            };
+            let checks: Vec<&str> = class
+                .constraints
+                .iter()
+                .map(|c| c.expression.as_str())
+                .collect();
+            let mut object = Value::Null;
+            for _ in 0..ATTEMPTS {
+                object = Value::Object(
+                    class
+                        .fields
+                        .iter()
+                        .map(|field| {
+                            let checks: Vec<&str> = field
+                                .constraints
+                                .iter()
+                                .map(|c| c.expression.as_str())
+                                .collect();
+                            let value =
+                                satisfying(&field.field_type, types, &checks, rng, depth + 1);
+                            (field.name.clone(), value)
+                        })
+                        .collect(),
+                );
+                if checks.iter().all(|expr| evaluate_expression(expr, &object)) {
+                    break;
+                }
+            }
+            object
+        }
+        FieldType::Enum(name) => match types.enums.get(name) {
+            Some(enm) if !enm.values.is_empty() => {
+                Value::String(enm.values[rng.gen_range(0..enm.values.len())].name.clone())
+            }
+            _ => Value::Null,
+        },
+        FieldType::Union(alternatives) if !alternatives.is_empty() => {
+            let pick = &alternatives[rng.gen_range(0..alternatives.len())];
+            generate(pick, types, rng, depth)
+        }
+        FieldType::Union(_) => Value::Null,
+    }
+}
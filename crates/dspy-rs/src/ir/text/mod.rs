@@ -11,10 +11,17 @@
 //!   canonical form of `t`). Ordering rules are documented in [`print`].
 //! - [`Program::load_dsrs`] / [`Program::save_dsrs`] — file convenience;
 //!   `.dsrs` artifacts are UTF-8 text only, non-text files are rejected.
+//! - [`Program::from_dsrs_in`] — parse text whose `import "<path>"`
+//!   declarations resolve against a directory (`load_dsrs` uses the file's
+//!   own). Plain `from_dsrs` refuses imports: it has nowhere to look.
+//! - [`Program::check_dsrs`] — parse reporting *every* validation error with
+//!   its position, plus a [`SourceMap`] from nodes, bindings, and signatures
+//!   back to the text. What `dsrs check` and editor tooling use.
 
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::ir::graph::Program;
+use crate::ir::graph::{NodeId, Program, SigId};
 
 pub(crate) mod parse;
 pub(crate) mod print;
@@ -26,6 +33,43 @@ pub(crate) mod print;
 /// structural grammar (also used by `include_program!` at macro expansion).
 pub use dsrs_syntax::ParseError;
 
+/// A 1-based source position, as carried by [`ParseError`].
+pub use dsrs_syntax::lex::Span;
+
+/// Where the parts of a parsed program came from in its `.dsrs` text. Nodes a
+/// `call` splices in from an import, and signatures or types declared
+/// elsewhere, have no entry.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    pub(crate) nodes: HashMap<NodeId, Span>,
+    pub(crate) bindings: HashMap<(NodeId, String), Span>,
+    pub(crate) sigs: HashMap<SigId, Span>,
+    pub(crate) types: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    /// The node's keyword (containers) or step name (leaves).
+    pub fn node(&self, id: NodeId) -> Option<Span> {
+        self.nodes.get(&id).copied()
+    }
+
+    /// The destination field of one of the node's bindings
+    /// (args, `out`, `join`, `carry`, `collect`).
+    pub fn binding(&self, id: NodeId, field: &str) -> Option<Span> {
+        self.bindings.get(&(id, field.to_string())).copied()
+    }
+
+    /// The signature's (or tool's) declaration.
+    pub fn sig(&self, id: SigId) -> Option<Span> {
+        self.sigs.get(&id).copied()
+    }
+
+    /// The declaration of a class or enum, by type token.
+    pub fn type_decl(&self, token: &str) -> Option<Span> {
+        self.types.get(token).copied()
+    }
+}
+
 /// Failure loading or saving a `.dsrs` artifact file.
 #[derive(Debug, thiserror::Error)]
 pub enum DsrsFileError {
@@ -53,8 +97,30 @@ impl Program {
         parse::parse_program(src)
     }
 
+    /// [`from_dsrs`](Program::from_dsrs) with `import` paths resolved
+    /// relative to `dir`. Errors inside an imported file are reported at its
+    /// `import` declaration.
+    pub fn from_dsrs_in(src: &str, dir: impl AsRef<Path>) -> Result<Program, ParseError> {
+        parse::parse_program_in(src, dir.as_ref(), None)
+    }
+
+    /// Parses like [`from_dsrs`](Program::from_dsrs), but reports every
+    /// capability and validation error (sorted by position) rather than the
+    /// first, and returns the program's [`SourceMap`]. Syntax errors still
+    /// stop the parse. `file` is the path `src` was read from: imports
+    /// resolve relative to it, and without one they are refused.
+    pub fn check_dsrs(
+        src: &str,
+        file: Option<&Path>,
+    ) -> Result<(Program, SourceMap), Vec<ParseError>> {
+        let dir = file.map(|path| path.parent().unwrap_or(Path::new(".")));
+        let origin = file.and_then(|path| path.canonicalize().ok());
+        parse::parse_program_mapped(src, dir, origin)
+    }
+
     /// Reads and parses a `.dsrs` text artifact. Non-UTF-8 (binary) files are
     /// rejected — the text format is the only wire form of a program.
+    /// Imports resolve relative to the file's directory.
     pub fn load_dsrs(path: impl AsRef<Path>) -> Result<Program, DsrsFileError> {
         let path = path.as_ref();
         let display = path.display().to_string();
@@ -65,9 +131,12 @@ impl Program {
         let text = String::from_utf8(bytes).map_err(|_| DsrsFileError::NotText {
             path: display.clone(),
         })?;
-        Self::from_dsrs(&text).map_err(|source| DsrsFileError::Parse {
-            path: display,
-            source,
+        let dir = path.parent().unwrap_or(Path::new("."));
+        parse::parse_program_in(&text, dir, path.canonicalize().ok()).map_err(|source| {
+            DsrsFileError::Parse {
+                path: display,
+                source,
+            }
         })
     }
 
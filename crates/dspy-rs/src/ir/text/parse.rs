@@ -9,36 +9,123 @@
 //! [`Program`] by construction (RFC 0002 §4.4). A parallel *shadow tree*
 //! records source spans so that post-lowering validation errors
 //! ([`ValidateError`]) can still be reported with a position.
+//!
+//! `//` comments are lexed as trivia and attached to the top-level
+//! declaration or `seq`/`fork` step they sit next to ([`Comments`]), so the
+//! printer can put them back.
 
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use crate::LMConfig;
 use crate::ir::builder::{self, BuildError, NodeSpec, Port, ProgramBuilder};
-use crate::ir::graph::{ModelId, NodeBudget, Program, SigId, ToolId};
-use crate::ir::params::{ContextPolicy, DemoRow};
+use crate::ir::graph::{
+    Comments, ImportId, MapErrorPolicy, MemoPolicy, ModelId, NodeBudget, Program, SigId, ToolId,
+    Trivia,
+};
+use crate::ir::params::{Compaction, ContextPolicy, DemoRow};
 use crate::ir::sig::{ConstraintDef, FieldDef, RenderSpec, SignatureDef};
 use crate::ir::validate::ValidateError;
 use crate::typesys::{ClassDef, EnumDef, EnumValueDef, FieldType, TypeTable};
 
-use super::ParseError;
-use dsrs_syntax::lex::{Lexed, Lexer, Span, Tok};
+use super::{ParseError, SourceMap};
+use cranelift_entity::EntityRef;
+use dsrs_syntax::lex::{Comment, Lexed, Lexer, Span, Tok};
 
 /// Words that cannot be used as node/sig/tool/model/class/enum names.
 const RESERVED: &[&str] = &[
-    "dsrs", "program", "caps", "model", "sig", "class", "enum", "tool", "lineage", "main", "in",
-    "out", "predict", "cot", "agent", "hole", "seq", "fork", "join", "route", "retry", "refine",
-    "loop", "else", "js", "demos", "string", "int", "float", "bool", "map", "true", "false",
-    "null", "while", "carry",
+    "dsrs",
+    "program",
+    "caps",
+    "model",
+    "sig",
+    "class",
+    "enum",
+    "tool",
+    "lineage",
+    "main",
+    "in",
+    "out",
+    "predict",
+    "cot",
+    "agent",
+    "hole",
+    "seq",
+    "fork",
+    "join",
+    "route",
+    "retry",
+    "refine",
+    "loop",
+    "else",
+    "js",
+    "demos",
+    "string",
+    "int",
+    "float",
+    "bool",
+    "map",
+    "true",
+    "false",
+    "null",
+    "while",
+    "carry",
+    "collect",
+    "transform",
+    "approve",
+    "import",
+    "call",
 ];
 
 const EXPR_KEYWORDS: &[&str] = &[
-    "predict", "cot", "agent", "hole", "seq", "fork", "route", "retry", "refine", "loop",
+    "predict",
+    "cot",
+    "agent",
+    "hole",
+    "transform",
+    "approve",
+    "seq",
+    "fork",
+    "route",
+    "retry",
+    "refine",
+    "loop",
+    "map",
+    "call",
 ];
 
 pub(crate) fn parse_program(src: &str) -> Result<Program, ParseError> {
     Parser::new(src)?.file()
 }
 
+/// Parses with every validation error reported (sorted by position) and the
+/// program's [`SourceMap`]. `dir`/`origin` as for [`parse_program_in`];
+/// without a `dir`, imports are refused.
+pub(crate) fn parse_program_mapped(
+    src: &str,
+    dir: Option<&Path>,
+    origin: Option<PathBuf>,
+) -> Result<(Program, SourceMap), Vec<ParseError>> {
+    let mut parser = Parser::new(src).map_err(|err| vec![err])?;
+    parser.dir = dir.map(Path::to_path_buf);
+    parser.import_stack.extend(origin);
+    parser.file_all()
+}
+
+/// Parses a program whose `import` paths resolve against `dir`. `origin` is
+/// the canonical path of the file itself when known, so a file importing
+/// itself is caught as a cycle at the first hop.
+pub(crate) fn parse_program_in(
+    src: &str,
+    dir: &Path,
+    origin: Option<PathBuf>,
+) -> Result<Program, ParseError> {
+    let mut parser = Parser::new(src)?;
+    parser.dir = Some(dir.to_path_buf());
+    parser.import_stack.extend(origin);
+    parser.file()
+}
+
 // ---------------------------------------------------------------------------
 // Shadow tree: spans for post-lowering error mapping
 // ---------------------------------------------------------------------------
@@ -55,6 +142,10 @@ struct Shadow {
     /// Binding destinations declared on this node (args/out/join/carry), with
     /// the span of each destination identifier.
     binds: Vec<(String, Span)>,
+    /// Nodes a `call` splices in ahead of itself (the callee's arena).
+    spliced: usize,
+    /// Comments attached to this node as a `seq`/`fork` step.
+    trivia: Trivia,
 }
 
 impl Shadow {
@@ -64,6 +155,8 @@ impl Shadow {
             span,
             children: Vec::new(),
             binds: Vec::new(),
+            spliced: 0,
+            trivia: Trivia::default(),
         }
     }
 
@@ -73,6 +166,8 @@ impl Shadow {
             span,
             children: Vec::new(),
             binds: Vec::new(),
+            spliced: 0,
+            trivia: Trivia::default(),
         }
     }
 }
@@ -85,29 +180,50 @@ struct SpanMaps {
     field: HashMap<(String, String), Span>,
 }
 
-fn build_span_maps(root: &Shadow) -> SpanMaps {
-    fn walk(shadow: &Shadow, counter: &mut usize, maps: &mut SpanMaps) {
-        for child in &shadow.children {
-            walk(child, counter, maps);
-        }
+/// The error-mapping tables plus the id-keyed [`SourceMap`] (signature spans
+/// are filled in by the caller), moving step comments into `comments`. The
+/// post-order counter is the [`NodeId`] the builder assigns.
+///
+/// [`NodeId`]: crate::ir::NodeId
+fn build_span_maps(root: &mut Shadow, comments: &mut Comments) -> (SpanMaps, SourceMap) {
+    fn walk(
+        shadow: &mut Shadow,
+        counter: &mut usize,
+        maps: &mut SpanMaps,
+        source: &mut SourceMap,
+        comments: &mut Comments,
+    ) {
+        for child in &mut shadow.children {
+            walk(child, counter, maps, source, comments);
+        }
+        *counter += shadow.spliced;
         let id = *counter;
         *counter += 1;
         let at = match &shadow.leaf {
             Some(name) => name.clone(),
             None => format!("n{id}"),
         };
+        let node = crate::ir::NodeId::new(id);
         maps.at.insert(at.clone(), shadow.span);
+        source.nodes.insert(node, shadow.span);
+        if shadow.trivia != Trivia::default() {
+            comments
+                .nodes
+                .insert(node, std::mem::take(&mut shadow.trivia));
+        }
         for (field, span) in &shadow.binds {
             maps.field.insert((at.clone(), field.clone()), *span);
+            source.bindings.insert((node, field.clone()), *span);
         }
     }
     let mut maps = SpanMaps {
         at: HashMap::new(),
         field: HashMap::new(),
     };
+    let mut source = SourceMap::default();
     let mut counter = 0usize;
-    walk(root, &mut counter, &mut maps);
-    maps
+    walk(root, &mut counter, &mut maps, &mut source, comments);
+    (maps, source)
 }
 
 // ---------------------------------------------------------------------------
@@ -131,6 +247,15 @@ enum SigItem {
     },
 }
 
+/// A parsed import, registered with the builder after the file's own types
+/// (so a conflicting shared type is reported at the import).
+struct PendingImport {
+    alias: String,
+    path: String,
+    callee: Program,
+    span: Span,
+}
+
 struct Parser<'a> {
     lx: Lexer<'a>,
     cur: Lexed,
@@ -153,6 +278,26 @@ struct Parser<'a> {
     type_spans: HashMap<String, Span>,
     /// First span at which each node name is referenced through a port.
     ref_spans: HashMap<String, Span>,
+
+    /// Directory `import` paths resolve against (`None`: imports refused).
+    dir: Option<PathBuf>,
+    /// Canonical paths of the files being imported above this one.
+    import_stack: Vec<PathBuf>,
+    pending_imports: Vec<PendingImport>,
+    /// Import alias → (id, callee node count).
+    imports: HashMap<String, (ImportId, usize)>,
+    /// Declaration span of each signature this file declares.
+    sig_spans: HashMap<SigId, Span>,
+    /// Declaration span of each class and enum this file declares.
+    type_spans: HashMap<String, Span>,
+
+    /// Comments lexed but not yet attached, in source order.
+    pending_comments: Vec<Comment>,
+    /// Line of the last token consumed (the end line of a raw region).
+    last_line: u32,
+    /// Header, declaration and footer comments (step comments ride on the
+    /// shadow tree until node ids are known).
+    comments: Comments,
 }
 
 impl<'a> Parser<'a> {
@@ -174,6 +319,15 @@ impl<'a> Parser<'a> {
             deferred_caps: Vec::new(),
             type_spans: HashMap::new(),
             ref_spans: HashMap::new(),
+            dir: None,
+            import_stack: Vec::new(),
+            pending_imports: Vec::new(),
+            imports: HashMap::new(),
+            sig_spans: HashMap::new(),
+            type_spans: HashMap::new(),
+            pending_comments: Vec::new(),
+            last_line: 0,
+            comments: Comments::default(),
         })
     }
 
@@ -181,9 +335,41 @@ impl<'a> Parser<'a> {
 
     fn bump(&mut self) -> Result<Lexed, ParseError> {
         let next = self.lx.next_token()?;
+        self.last_line = self.cur.span.line;
         Ok(std::mem::replace(&mut self.cur, next))
     }
 
+    /// Every comment lexed so far and not yet attached: at a declaration or
+    /// step keyword, the comments above it.
+    fn take_comments(&mut self) -> Vec<Comment> {
+        self.pending_comments.extend(self.lx.take_comments());
+        std::mem::take(&mut self.pending_comments)
+    }
+
+    /// The pending comments up to the end of the construct just parsed (its
+    /// body and its last line), leaving those below it pending.
+    fn take_comments_through_last(&mut self) -> Vec<Comment> {
+        let mut taken = self.take_comments();
+        let split = taken.partition_point(|c| c.span.line <= self.last_line);
+        self.pending_comments = taken.split_off(split);
+        taken
+    }
+
+    /// Attaches `leading` and `own` (from [`Parser::take_comments_through_last`])
+    /// to the construct just parsed: a trailing comment on its last line
+    /// stays trailing, everything else goes above it.
+    fn trivia(&self, leading: Vec<Comment>, own: Vec<Comment>) -> Trivia {
+        let mut trivia = Trivia::default();
+        for comment in leading.into_iter().chain(own) {
+            if comment.trailing && comment.span.line == self.last_line {
+                trivia.trailing = Some(comment.text.into());
+            } else {
+                trivia.leading.push(comment.text.into());
+            }
+        }
+        trivia
+    }
+
     fn err(&self, message: impl Into<String>) -> ParseError {
         ParseError::at(self.cur.span, message)
     }
@@ -309,6 +495,7 @@ impl<'a> Parser<'a> {
     fn resync(&mut self, end: usize) -> Result<(), ParseError> {
         let span = self.lx.span_at(end);
         self.lx.seek(end, span);
+        self.last_line = span.line;
         self.cur = self.lx.next_token()?;
         Ok(())
     }
@@ -336,7 +523,61 @@ impl<'a> Parser<'a> {
 
     // -- file ---------------------------------------------------------------
 
-    fn file(mut self) -> Result<Program, ParseError> {
+    fn file(self) -> Result<Program, ParseError> {
+        self.file_all()
+            .map(|(program, _)| program)
+            .map_err(|errors| errors.into_iter().next().expect("failures carry an error"))
+    }
+
+    /// The whole file. Syntax errors stop at the first; capability and
+    /// validation errors are all reported, sorted by position.
+    fn file_all(mut self) -> Result<(Program, SourceMap), Vec<ParseError>> {
+        let (main_sig, root, mut root_shadow, main_span) =
+            self.declarations().map_err(|err| vec![err])?;
+
+        // Capability declarations: every hole/tool cap must be inside the
+        // program ceiling (checked here with positions; `validate()` enforces
+        // the same rule structurally).
+        let mut errors: Vec<ParseError> = self
+            .deferred_caps
+            .iter()
+            .filter(|(cap, _, _)| !self.program_caps.contains(cap))
+            .map(|(cap, span, owner)| {
+                ParseError::at(
+                    *span,
+                    format!(
+                        "{owner} requires capability `{cap}`, which the program does not declare: \
+                         add `{cap}` to the top-level `caps {{ ... }}` block"
+                    ),
+                )
+            })
+            .collect();
+        if errors.is_empty() {
+            let (maps, mut source) = build_span_maps(&mut root_shadow, &mut self.comments);
+            source.sigs = std::mem::take(&mut self.sig_spans);
+            source.types = std::mem::take(&mut self.type_spans);
+            let builder = self.builder.take().expect("builder present");
+            match builder.main_all(main_sig, root) {
+                Ok(mut program) => {
+                    program.meta.lineage = self.lineage.take();
+                    program.meta.comments = std::mem::take(&mut self.comments);
+                    return Ok((program, source));
+                }
+                Err(build) => errors.extend(
+                    build
+                        .into_iter()
+                        .map(|err| self.map_build_error(err, &maps, main_span)),
+                ),
+            }
+        }
+        errors.sort_by_key(|err| (err.line, err.col));
+        errors.dedup();
+        Err(errors)
+    }
+
+    /// Everything up to the end of `main`: the root signature, the lowered
+    /// tree, its shadow, and the `seq` keyword's span.
+    fn declarations(&mut self) -> Result<(SigId, NodeSpec, Shadow, Span), ParseError> {
         self.expect_kw("dsrs", "at the start of the file (`dsrs 1`)")?;
         let (format, format_span) = self.expect_int::<u32>("after `dsrs` (the format major)")?;
         if format != 1 {
@@ -348,11 +589,18 @@ impl<'a> Parser<'a> {
         self.expect_kw("program", "after the `dsrs 1` pragma")?;
         let (program_name, _) = self.expect_name("after `program`")?;
         self.builder = Some(ProgramBuilder::new(&program_name));
+        self.comments.header = self
+            .take_comments_through_last()
+            .into_iter()
+            .map(|c| c.text.into_boxed_str())
+            .collect();
 
         // Top-level declarations until `main`.
         loop {
-            match &self.cur.tok {
+            let leading = self.take_comments();
+            let key = match &self.cur.tok {
                 Tok::Ident(word) => match word.as_str() {
+                    "import" => self.import_decl()?,
                     "caps" => self.caps_decl()?,
                     "model" => self.model_decl()?,
                     "sig" => self.sig_decl()?,
@@ -360,11 +608,14 @@ impl<'a> Parser<'a> {
                     "enum" => self.enum_decl()?,
                     "tool" => self.tool_decl()?,
                     "lineage" => self.lineage_decl()?,
-                    "main" => break,
+                    "main" => {
+                        self.pending_comments = leading;
+                        break;
+                    }
                     other => {
                         return Err(self.err(format!(
-                            "unknown top-level keyword `{other}`: expected one of `caps`, \
-                             `model`, `sig`, `class`, `enum`, `tool`, `lineage`, `main`"
+                            "unknown top-level keyword `{other}`: expected one of `import`, \
+                             `caps`, `model`, `sig`, `class`, `enum`, `tool`, `lineage`, `main`"
                         )));
                     }
                 },
@@ -379,6 +630,13 @@ impl<'a> Parser<'a> {
                         other.describe()
                     )));
                 }
+            };
+            let own = self.take_comments_through_last();
+            let trivia = self.trivia(leading, own);
+            if trivia != Trivia::default() {
+                let entry = self.comments.decls.entry(key.into()).or_default();
+                entry.leading.extend(trivia.leading);
+                entry.trailing = trivia.trailing.or(entry.trailing.take());
             }
         }
 
@@ -407,7 +665,10 @@ impl<'a> Parser<'a> {
             )));
         }
         let main_span = self.cur.span;
-        let (root, root_shadow) = self.expr(None, main_span)?;
+        let leading = self.take_comments();
+        let (root, mut root_shadow) = self.expr(None, main_span)?;
+        let own = self.take_comments_through_last();
+        root_shadow.trivia = self.trivia(leading, own);
 
         if self.cur.tok != Tok::Eof {
             return Err(self.err(format!(
@@ -415,34 +676,89 @@ impl<'a> Parser<'a> {
                 self.cur.tok.describe()
             )));
         }
+        self.comments.footer = self
+            .take_comments()
+            .into_iter()
+            .map(|c| c.text.into_boxed_str())
+            .collect();
+        Ok((main_sig, root, root_shadow, main_span))
+    }
 
-        // Capability declarations: every hole/tool cap must be inside the
-        // program ceiling (checked here with positions; `validate()` enforces
-        // the same rule structurally).
-        for (cap, span, owner) in &self.deferred_caps {
-            if !self.program_caps.contains(cap) {
-                return Err(ParseError::at(
-                    *span,
-                    format!(
-                        "{owner} requires capability `{cap}`, which the program does not declare: \
-                         add `{cap}` to the top-level `caps {{ ... }}` block"
-                    ),
-                ));
-            }
+    // -- top-level declarations --------------------------------------------
+
+    /// `import "<path>" as <alias> [pin "<hash>"]`: parses the imported file
+    /// (relative to this one) now, so its errors surface at the import.
+    fn import_decl(&mut self) -> Result<String, ParseError> {
+        self.bump()?; // import
+        let (path, span) = self.expect_str("after `import` (the imported file's path)")?;
+        self.expect_kw("as", "after the import path")?;
+        let (alias, alias_span) = self.expect_name("after `as` (the import alias)")?;
+        let key = format!("import {alias}");
+        if self.pending_imports.iter().any(|i| i.alias == alias) {
+            return Err(ParseError::at(
+                alias_span,
+                format!("duplicate import alias `{alias}`"),
+            ));
         }
+        let pin = if self.eat_kw("pin")? {
+            Some(self.expect_str("after `pin` (the imported program's hash)")?)
+        } else {
+            None
+        };
 
-        let maps = build_span_maps(&root_shadow);
-        let builder = self.builder.take().expect("builder present");
-        let mut program = builder
-            .main(main_sig, root)
-            .map_err(|err| self.map_build_error(err, &maps, main_span))?;
-        program.meta.lineage = self.lineage.take();
-        Ok(program)
+        let Some(dir) = &self.dir else {
+            return Err(ParseError::at(
+                span,
+                "imports resolve relative to the importing file: load this program with \
+                 `Program::load_dsrs` or `Program::from_dsrs_in`",
+            ));
+        };
+        let file = dir
+            .join(&path)
+            .canonicalize()
+            .map_err(|err| ParseError::at(span, format!("cannot read imported `{path}`: {err}")))?;
+        if self.import_stack.contains(&file) {
+            return Err(ParseError::at(
+                span,
+                format!("import cycle: `{path}` is already being imported"),
+            ));
+        }
+        let text = std::fs::read_to_string(&file)
+            .map_err(|err| ParseError::at(span, format!("cannot read imported `{path}`: {err}")))?;
+        let mut nested = Parser::new(&text).map_err(|err| imported_error(&path, span, err))?;
+        nested.dir = file.parent().map(Path::to_path_buf);
+        nested.import_stack = self.import_stack.clone();
+        nested.import_stack.push(file);
+        let callee = nested
+            .file()
+            .map_err(|err| imported_error(&path, span, err))?;
+
+        let hash = format!("{:016x}", callee.meta.program_hash);
+        if let Some((pin, pin_span)) = pin
+            && pin != hash
+        {
+            return Err(ParseError::at(
+                pin_span,
+                format!(
+                    "import `{alias}` is pinned to {pin}, but `{path}` now hashes to {hash}: \
+                     review the change to the imported file, then update the pin"
+                ),
+            ));
+        }
+        for cap in callee.caps.0.iter() {
+            self.deferred_caps
+                .push((cap.to_string(), span, format!("import `{alias}`")));
+        }
+        self.pending_imports.push(PendingImport {
+            alias,
+            path,
+            callee,
+            span,
+        });
+        Ok(key)
     }
 
-    // -- top-level declarations --------------------------------------------
-
-    fn caps_decl(&mut self) -> Result<(), ParseError> {
+    fn caps_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // caps
         self.expect_tok(Tok::LBrace, "after `caps`")?;
         while self.cur.tok != Tok::RBrace {
@@ -451,7 +767,7 @@ impl<'a> Parser<'a> {
             self.builder.as_mut().expect("builder").cap(&cap);
         }
         self.bump()?; // }
-        Ok(())
+        Ok("caps".to_string())
     }
 
     /// One capability name: `IDENT (":" IDENT)*`.
@@ -466,9 +782,10 @@ impl<'a> Parser<'a> {
         Ok((cap, span))
     }
 
-    fn model_decl(&mut self) -> Result<(), ParseError> {
+    fn model_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // model
         let (name, span) = self.expect_name("after `model`")?;
+        let key = format!("model {name}");
         if self.models.contains_key(&name) {
             return Err(ParseError::at(
                 span,
@@ -512,13 +829,25 @@ impl<'a> Parser<'a> {
                             self.expect_int("after `retry_base_delay_ms`")?.0
                     }
                     "cache" => config.cache = self.expect_bool("after `cache`")?,
+                    "context_window" => {
+                        let (tokens, span) = self.expect_int("after `context_window`")?;
+                        if tokens == 0 {
+                            return Err(ParseError::at(
+                                span,
+                                "`context_window` must be at least 1",
+                            ));
+                        }
+                        config.context_window = Some(tokens);
+                    }
+                    "tokenizer" => config.tokenizer = Some(self.expect_str("after `tokenizer`")?.0),
                     other => {
                         return Err(ParseError::at(
                             key_span,
                             format!(
                                 "unknown model option `{other}`: expected `base_url`, \
                                  `temperature`, `max_tokens`, `max_tool_iterations`, \
-                                 `max_retries`, `retry_base_delay_ms`, or `cache`"
+                                 `max_retries`, `retry_base_delay_ms`, `cache`, \
+                                 `context_window`, or `tokenizer`"
                             ),
                         ));
                     }
@@ -528,12 +857,13 @@ impl<'a> Parser<'a> {
         }
         let id = self.builder.as_mut().expect("builder").model(&name, config);
         self.models.insert(name, id);
-        Ok(())
+        Ok(key)
     }
 
-    fn sig_decl(&mut self) -> Result<(), ParseError> {
+    fn sig_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // sig
         let (name, span) = self.expect_name("after `sig`")?;
+        let key = format!("sig {name}");
         if self.sig_items.iter().any(|item| match item {
             SigItem::Sig { def, .. } => *def.name == name,
             _ => false,
@@ -567,7 +897,7 @@ impl<'a> Parser<'a> {
             .finish()
             .map_err(|e| ParseError::at(span, format!("invalid sig `{name}`: {e}")))?;
         self.sig_items.push(SigItem::Sig { def, span });
-        Ok(())
+        Ok(key)
     }
 
     /// One `in`/`out` field with its metadata. Returns `(field, is_input)`.
@@ -587,8 +917,9 @@ impl<'a> Parser<'a> {
         self.expect_tok(Tok::Colon, "after the field name")?;
         let ty = self.type_expr()?;
         let mut field = FieldDef::new(&name, ty);
-        // Field metadata, any order; canonical print order is docs, alias,
-        // constraints, format/jinja.
+        // Field metadata, any order; canonical print order is alias, docs,
+        // constraints, format/jinja, prefix, hint, examples, hidden, order,
+        // section.
         loop {
             match &self.cur.tok {
                 Tok::Str(docs) => {
@@ -627,6 +958,35 @@ impl<'a> Parser<'a> {
                     let (template, _) = self.expect_str("after `jinja`")?;
                     field = field.with_render(RenderSpec::Jinja(template.into()));
                 }
+                Tok::Ident(word) if word == "prefix" => {
+                    self.bump()?;
+                    let (prefix, _) = self.expect_str("after `prefix`")?;
+                    field = field.with_prefix(&prefix);
+                }
+                Tok::Ident(word) if word == "hint" => {
+                    self.bump()?;
+                    let (hint, _) = self.expect_str("after `hint`")?;
+                    field = field.with_hint(&hint);
+                }
+                Tok::Ident(word) if word == "example" => {
+                    self.bump()?;
+                    let (example, _) = self.expect_str("after `example`")?;
+                    field = field.with_example(&example);
+                }
+                Tok::Ident(word) if word == "hidden" => {
+                    self.bump()?;
+                    field = field.hidden();
+                }
+                Tok::Ident(word) if word == "order" => {
+                    self.bump()?;
+                    let (order, _) = self.expect_int("after `order`")?;
+                    field = field.with_order(order);
+                }
+                Tok::Ident(word) if word == "section" => {
+                    self.bump()?;
+                    let (section, _) = self.expect_str("after `section`")?;
+                    field = field.with_section(&section);
+                }
                 _ => break,
             }
         }
@@ -649,15 +1009,17 @@ impl<'a> Parser<'a> {
         Ok((expr, label))
     }
 
-    fn class_decl(&mut self) -> Result<(), ParseError> {
+    fn class_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // class
         let (token, span) = self.qualified_name("after `class`")?;
+        let key = format!("class {token}");
         if self.types.classes.contains_key(&token) {
             return Err(ParseError::at(
                 span,
                 format!("duplicate class name `{token}`"),
             ));
         }
+        self.type_spans.insert(token.clone(), span);
         let rendered = if self.eat_kw("alias")? {
             self.expect_str("after `alias`")?.0
         } else {
@@ -732,18 +1094,20 @@ impl<'a> Parser<'a> {
                 constraints: Vec::new(),
             },
         );
-        Ok(())
+        Ok(key)
     }
 
-    fn enum_decl(&mut self) -> Result<(), ParseError> {
+    fn enum_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // enum
         let (token, span) = self.qualified_name("after `enum`")?;
+        let key = format!("enum {token}");
         if self.types.enums.contains_key(&token) {
             return Err(ParseError::at(
                 span,
                 format!("duplicate enum name `{token}`"),
             ));
         }
+        self.type_spans.insert(token.clone(), span);
         let rendered = if self.eat_kw("alias")? {
             self.expect_str("after `alias`")?.0
         } else {
@@ -761,11 +1125,21 @@ impl<'a> Parser<'a> {
         let mut values = Vec::new();
         while self.cur.tok != Tok::RBrace {
             let (name, _) = self.expect_name("as an enum value")?;
-            let rendered_name = if self.eat_kw("alias")? {
-                self.expect_str("after `alias`")?.0
-            } else {
-                name.clone()
-            };
+            // `alias "x"` renames the value for the LM; `alias ["x" "y"]` adds
+            // spellings coercion also accepts.
+            let mut rendered_name = name.clone();
+            let mut aliases = Vec::new();
+            while self.eat_kw("alias")? {
+                if self.cur.tok == Tok::LBracket {
+                    self.bump()?;
+                    while self.cur.tok != Tok::RBracket {
+                        aliases.push(self.expect_str("inside `alias [ ... ]`")?.0);
+                    }
+                    self.bump()?; // ]
+                } else {
+                    rendered_name = self.expect_str("after `alias`")?.0;
+                }
+            }
             let value_docs = match &self.cur.tok {
                 Tok::Str(docs) => {
                     let docs = docs.clone();
@@ -778,6 +1152,7 @@ impl<'a> Parser<'a> {
                 name,
                 rendered_name,
                 docs: value_docs,
+                aliases,
             });
         }
         self.bump()?; // }
@@ -793,12 +1168,13 @@ impl<'a> Parser<'a> {
                 values,
             },
         );
-        Ok(())
+        Ok(key)
     }
 
-    fn tool_decl(&mut self) -> Result<(), ParseError> {
+    fn tool_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // tool
         let (name, span) = self.expect_name("after `tool`")?;
+        let key = format!("tool {name}");
         if self.sig_items.iter().any(|item| match item {
             SigItem::Tool { name: existing, .. } => *existing == name,
             _ => false,
@@ -849,10 +1225,10 @@ impl<'a> Parser<'a> {
             js,
             span,
         });
-        Ok(())
+        Ok(key)
     }
 
-    fn lineage_decl(&mut self) -> Result<(), ParseError> {
+    fn lineage_decl(&mut self) -> Result<String, ParseError> {
         self.bump()?; // lineage
         self.expect_tok(Tok::LBrace, "after `lineage`")?;
         let mut optimizer = String::new();
@@ -891,15 +1267,24 @@ impl<'a> Parser<'a> {
             overlay,
             date: date.into(),
         });
-        Ok(())
+        Ok("lineage".to_string())
     }
 
     /// Resolves class↔enum tokens and registers signatures/tools with the
     /// builder in declaration order.
     fn register_sig_items(&mut self) -> Result<(), ParseError> {
-        let enums: HashSet<String> = self.types.enums.keys().cloned().collect();
+        let mut enums: HashSet<String> = self.types.enums.keys().cloned().collect();
         let builder = self.builder.as_mut().expect("builder");
         builder.add_types(&self.types);
+        // Imported class/enum definitions are usable here without redeclaring.
+        for import in std::mem::take(&mut self.pending_imports) {
+            enums.extend(import.callee.types.enums.keys().cloned());
+            let nodes = import.callee.nodes.len();
+            let id = builder
+                .import(&import.alias, &import.path, import.callee)
+                .map_err(|err| ParseError::at(import.span, err.to_string()))?;
+            self.imports.insert(import.alias, (id, nodes));
+        }
         for item in std::mem::take(&mut self.sig_items) {
             match item {
                 SigItem::Sig { mut def, span } => {
@@ -907,7 +1292,7 @@ impl<'a> Parser<'a> {
                     let name = def.name.to_string();
                     let id = builder.sig(def);
                     self.sigs.insert(name, id);
-                    let _ = span;
+                    self.sig_spans.insert(id, span);
                 }
                 SigItem::Tool {
                     name,
@@ -919,13 +1304,13 @@ impl<'a> Parser<'a> {
                 } => {
                     fixup_sig(&mut def, &enums);
                     let sig_id = builder.sig(def);
+                    self.sig_spans.insert(sig_id, span);
                     let cap_strs: Vec<&str> = caps.iter().map(|(c, _)| c.as_str()).collect();
                     let id = match js {
                         None => builder.host_tool(&name, &desc, sig_id, &cap_strs),
                         Some(js) => builder.sandboxed_tool(&name, &desc, sig_id, &cap_strs, &js),
                     };
                     self.tools.insert(name, id);
-                    let _ = span;
                 }
             }
         }
@@ -1054,12 +1439,16 @@ impl<'a> Parser<'a> {
         match keyword.as_str() {
             "predict" | "cot" | "agent" => self.lm_leaf(&keyword, name),
             "hole" => self.hole(name),
+            "transform" => self.transform(name),
+            "approve" => self.approve(name),
+            "call" => self.call(name),
             "seq" => self.seq(name, kw_span),
             "fork" => self.fork(name, kw_span),
             "route" => self.route(name, kw_span),
             "retry" => self.retry(name, kw_span),
             "refine" => self.refine(name, kw_span),
             "loop" => self.loop_(name, kw_span),
+            "map" => self.map(name, kw_span),
             other => Err(self.err(format!(
                 "unknown expression keyword `{other}`: expected one of {}",
                 EXPR_KEYWORDS.join(", ")
@@ -1090,6 +1479,16 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// A [`Parser::target`] in a `seq`/`fork` body, carrying the comments
+    /// above it and inside it (those not claimed by nested steps).
+    fn step(&mut self) -> Result<(NodeSpec, Shadow), ParseError> {
+        let leading = self.take_comments();
+        let (spec, mut shadow) = self.target()?;
+        let own = self.take_comments_through_last();
+        shadow.trivia = self.trivia(leading, own);
+        Ok((spec, shadow))
+    }
+
     fn register_name(&mut self, name: &str, span: Span) -> Result<(), ParseError> {
         if let Some(_previous) = self.node_names.get(name) {
             return Err(ParseError::at(
@@ -1170,12 +1569,13 @@ impl<'a> Parser<'a> {
                         spec = spec.instruction(&text);
                     }
                     "demos" => spec = spec.demos(self.demos_value()?),
+                    "cache" => spec = spec.memo(self.memo_policy()?),
                     other => {
                         return Err(ParseError::at(
                             key_span,
                             format!(
                                 "unknown option `{other}` in a `{keyword}` block: expected \
-                                 `instruction` or `demos`"
+                                 `instruction`, `demos`, or `cache`"
                             ),
                         ));
                     }
@@ -1280,13 +1680,30 @@ impl<'a> Parser<'a> {
                             "playbook" => {
                                 policy.playbook = Some(self.expect_str("after `playbook`")?.0)
                             }
+                            "compact" => policy.compaction = Some(self.compaction()?),
+                            "summarize_tool_results_over" => {
+                                policy.summarize_tool_results_over =
+                                    Some(self.expect_int("after `summarize_tool_results_over`")?.0)
+                            }
+                            "pin" => {
+                                self.expect_tok(Tok::LBracket, "after `pin`")?;
+                                while self.cur.tok != Tok::RBracket {
+                                    let (turn, _) = self.expect_int("as a pinned turn")?;
+                                    if !policy.pinned_turns.contains(&turn) {
+                                        policy.pinned_turns.push(turn);
+                                    }
+                                }
+                                self.bump()?; // ]
+                                policy.pinned_turns.sort_unstable();
+                            }
                             other => {
                                 return Err(ParseError::at(
                                     cspan,
                                     format!(
                                         "unknown context key `{other}`: expected \
-                                         `max_history_turns`, `tool_result_max_bytes`, or \
-                                         `playbook`"
+                                         `max_history_turns`, `tool_result_max_bytes`, \
+                                         `playbook`, `compact`, `summarize_tool_results_over`, \
+                                         or `pin`"
                                     ),
                                 ));
                             }
@@ -1300,13 +1717,14 @@ impl<'a> Parser<'a> {
                     spec = spec.instruction(&text);
                 }
                 "demos" => spec = spec.demos(self.demos_value()?),
+                "cache" => spec = spec.memo(self.memo_policy()?),
                 other => {
                     return Err(ParseError::at(
                         key_span,
                         format!(
                             "unknown agent option `{other}`: expected `tools`, `tool_set`, \
                              `stop_tools`, `max_turns`, `until_parse`, `budget`, `context`, \
-                             `instruction`, or `demos`"
+                             `cache`, `instruction`, or `demos`"
                         ),
                     ));
                 }
@@ -1316,6 +1734,82 @@ impl<'a> Parser<'a> {
         Ok(spec)
     }
 
+    /// `{ ttl_s <n> }` after `cache` (the block may be empty: no expiry).
+    fn memo_policy(&mut self) -> Result<MemoPolicy, ParseError> {
+        let mut policy = MemoPolicy::default();
+        self.expect_tok(Tok::LBrace, "after `cache` (it may be empty: `cache { }`)")?;
+        while self.cur.tok != Tok::RBrace {
+            let (key, key_span) = self.expect_ident("as a cache key")?;
+            match key.as_str() {
+                "ttl_s" => {
+                    let (ttl, span) = self.expect_int::<u64>("after `ttl_s`")?;
+                    if ttl == 0 {
+                        return Err(ParseError::at(span, "`ttl_s` must be at least 1"));
+                    }
+                    policy.ttl_s = Some(ttl);
+                }
+                other => {
+                    return Err(ParseError::at(
+                        key_span,
+                        format!("unknown cache key `{other}`: expected `ttl_s`"),
+                    ));
+                }
+            }
+        }
+        self.bump()?; // }
+        Ok(policy)
+    }
+
+    /// `compact { at_tokens N keep_recent N model @m instruction "..." }`
+    /// inside a `context` block; `at_tokens` is required.
+    fn compaction(&mut self) -> Result<Compaction, ParseError> {
+        let open = self.cur.span;
+        self.expect_tok(Tok::LBrace, "after `compact`")?;
+        let mut at_tokens = None;
+        let mut compaction = Compaction::at_tokens(0);
+        while self.cur.tok != Tok::RBrace {
+            let (key, key_span) = self.expect_ident("as a compact key")?;
+            match key.as_str() {
+                "at_tokens" => {
+                    let (tokens, span) = self.expect_int::<u32>("after `at_tokens`")?;
+                    if tokens == 0 {
+                        return Err(ParseError::at(span, "`at_tokens` must be at least 1"));
+                    }
+                    at_tokens = Some(tokens);
+                }
+                "keep_recent" => {
+                    let (keep, span) = self.expect_int::<u32>("after `keep_recent`")?;
+                    if keep == 0 {
+                        return Err(ParseError::at(span, "`keep_recent` must be at least 1"));
+                    }
+                    compaction.keep_recent = keep;
+                }
+                "model" => {
+                    compaction.model = Some(
+                        self.modelref()?
+                            .ok_or_else(|| self.err("expected `@model` after `model`"))?,
+                    );
+                }
+                "instruction" => {
+                    compaction.instruction = Some(self.expect_str("after `instruction`")?.0)
+                }
+                other => {
+                    return Err(ParseError::at(
+                        key_span,
+                        format!(
+                            "unknown compact key `{other}`: expected `at_tokens`, \
+                             `keep_recent`, `model`, or `instruction`"
+                        ),
+                    ));
+                }
+            }
+        }
+        self.bump()?; // }
+        compaction.at_tokens =
+            at_tokens.ok_or_else(|| ParseError::at(open, "`compact` requires `at_tokens`"))?;
+        Ok(compaction)
+    }
+
     fn tool_list(&mut self, context: &str) -> Result<Vec<ToolId>, ParseError> {
         self.expect_tok(Tok::LBracket, &format!("after `{context}`"))?;
         let mut ids = Vec::new();
@@ -1388,10 +1882,86 @@ impl<'a> Parser<'a> {
         for (field, port) in binds {
             spec = spec.bind(&field, port);
         }
+        // Optional trailing options block: `{ cache { ... } }`.
+        if self.cur.tok == Tok::LBrace {
+            self.bump()?;
+            while self.cur.tok != Tok::RBrace {
+                let (key, key_span) = self.expect_ident("as a hole option")?;
+                match key.as_str() {
+                    "cache" => spec = spec.memo(self.memo_policy()?),
+                    other => {
+                        return Err(ParseError::at(
+                            key_span,
+                            format!("unknown hole option `{other}`: expected `cache`"),
+                        ));
+                    }
+                }
+            }
+            self.bump()?; // }
+        }
+        Ok((spec, shadow))
+    }
+
+    /// `transform Sig (args) { field = "expr", ... }` — one minijinja
+    /// expression per output field, comma-separated like a bindmap.
+    fn transform(
+        &mut self,
+        name: Option<(String, Span)>,
+    ) -> Result<(NodeSpec, Shadow), ParseError> {
+        self.bump()?; // transform
+        let (name, name_span) = self.require_leaf_name(name, "transform")?;
+        let sig = self.resolve_sig()?;
+        let mut shadow = Shadow::leaf(&name, name_span);
+        let mut spec = builder::transform(&name, sig);
+        spec = self.args(spec, &mut shadow)?;
+        self.expect_tok(
+            Tok::LBrace,
+            "after the transform arguments (every transform lists `{ field = \"expr\" }`)",
+        )?;
+        while self.cur.tok != Tok::RBrace {
+            let (field, span) = self.expect_name("as the output field")?;
+            self.expect_tok(Tok::Eq, "after the output field")?;
+            let (expr, _) = self.expect_str("after `=` (a quoted expression)")?;
+            shadow.binds.push((field.clone(), span));
+            spec = spec.expr(&field, &expr);
+            if self.cur.tok == Tok::Comma {
+                self.bump()?;
+            } else {
+                break;
+            }
+        }
+        self.expect_tok(Tok::RBrace, "to close the transform expressions")?;
+        Ok((spec, shadow))
+    }
+
+    /// `approve Sig (args)` — the arguments are the pending payload.
+    fn approve(&mut self, name: Option<(String, Span)>) -> Result<(NodeSpec, Shadow), ParseError> {
+        self.bump()?; // approve
+        let (name, name_span) = self.require_leaf_name(name, "approve")?;
+        let sig = self.resolve_sig()?;
+        let mut shadow = Shadow::leaf(&name, name_span);
+        let spec = self.args(builder::approve(&name, sig), &mut shadow)?;
+        Ok((spec, shadow))
+    }
+
+    /// `name = call <alias> (args)`: embeds an imported program.
+    fn call(&mut self, name: Option<(String, Span)>) -> Result<(NodeSpec, Shadow), ParseError> {
+        self.bump()?; // call
+        let (name, name_span) = self.require_leaf_name(name, "call")?;
+        let (alias, alias_span) = self.expect_ident("after `call` (an import alias)")?;
+        let (import, spliced) = *self.imports.get(&alias).ok_or_else(|| {
+            ParseError::at(
+                alias_span,
+                format!("unknown import `{alias}`: declare it with `import \"<path>\" as {alias}`"),
+            )
+        })?;
+        let mut shadow = Shadow::leaf(&name, name_span);
+        shadow.spliced = spliced;
+        let spec = self.args(builder::call(&name, import), &mut shadow)?;
         Ok((spec, shadow))
     }
 
-    /// Argument list on a predict/cot/agent leaf.
+    /// Argument list on a predict/cot/agent/transform/approve/call leaf.
     fn args(&mut self, mut spec: NodeSpec, shadow: &mut Shadow) -> Result<NodeSpec, ParseError> {
         if self.cur.tok != Tok::LParen {
             return Ok(spec);
@@ -1524,7 +2094,7 @@ impl<'a> Parser<'a> {
                 self.bump()?;
                 outs.extend(self.bindmap("out")?);
             } else {
-                let (child, child_shadow) = self.target()?;
+                let (child, child_shadow) = self.step()?;
                 children.push(child);
                 shadow.children.push(child_shadow);
             }
@@ -1551,7 +2121,7 @@ impl<'a> Parser<'a> {
         let mut shadow = Shadow::container(kw_span);
         let mut branches = Vec::new();
         while self.cur.tok != Tok::RBrace {
-            let (branch, branch_shadow) = self.target()?;
+            let (branch, branch_shadow) = self.step()?;
             branches.push(branch);
             shadow.children.push(branch_shadow);
         }
@@ -1578,6 +2148,7 @@ impl<'a> Parser<'a> {
         self.expect_tok(Tok::LBrace, "after the route discriminant port")?;
         let mut shadow = Shadow::container(kw_span);
         let mut spec = builder::route(on);
+        let mut when_shadows: Vec<Shadow> = Vec::new();
         let mut default_shadow: Option<Shadow> = None;
         while self.cur.tok != Tok::RBrace {
             if self.at_kw("else") {
@@ -1586,6 +2157,21 @@ impl<'a> Parser<'a> {
                 let (target, target_shadow) = self.target()?;
                 spec = spec.default_arm(target);
                 default_shadow = Some(target_shadow);
+            } else if self.at_kw("when") {
+                self.bump()?; // when
+                if self.cur.tok == Tok::Arrow {
+                    // An enum variant that happens to be called `when`.
+                    self.bump()?;
+                    let (target, target_shadow) = self.target()?;
+                    spec = spec.arm("when", target);
+                    shadow.children.push(target_shadow);
+                    continue;
+                }
+                let (predicate, _) = self.expect_str("after `when` (a quoted predicate)")?;
+                self.expect_tok(Tok::Arrow, "after the predicate")?;
+                let (target, target_shadow) = self.target()?;
+                spec = spec.when(&predicate, target);
+                when_shadows.push(target_shadow);
             } else {
                 let (variant, _) = self.expect_name("as a route arm variant")?;
                 self.expect_tok(Tok::Arrow, "after the arm variant")?;
@@ -1595,8 +2181,10 @@ impl<'a> Parser<'a> {
             }
         }
         self.bump()?; // }
+        // Builder lowering order: variant arms, predicate arms, then default,
+        // then the route node.
+        shadow.children.extend(when_shadows);
         if let Some(default) = default_shadow {
-            // Builder lowering order: arms, then default, then the route node.
             shadow.children.push(default);
         }
         if let Some((name, _)) = name {
@@ -1783,7 +2371,7 @@ impl<'a> Parser<'a> {
                 self.bump()?;
                 outs.extend(self.bindmap("join")?);
             } else {
-                let (child, child_shadow) = self.target()?;
+                let (child, child_shadow) = self.step()?;
                 children.push(child);
                 body_shadow.children.push(child_shadow);
             }
@@ -1816,6 +2404,97 @@ impl<'a> Parser<'a> {
         Ok((spec, shadow))
     }
 
+    fn map(
+        &mut self,
+        name: Option<(String, Span)>,
+        kw_span: Span,
+    ) -> Result<(NodeSpec, Shadow), ParseError> {
+        self.bump()?; // map
+        let (item, _) = self.expect_name("as the map item name")?;
+        self.expect_kw("in", "after the map item name")?;
+        let over = self.port()?;
+        let mut max_parallel: u32 = 1;
+        let mut on_error = MapErrorPolicy::Fail;
+        if self.cur.tok == Tok::LParen {
+            self.bump()?;
+            while self.cur.tok != Tok::RParen {
+                let (key, key_span) = self.expect_ident("as a map option")?;
+                match key.as_str() {
+                    "max_parallel" => {
+                        let (value, span) = self.expect_int::<u32>("after `max_parallel`")?;
+                        if value == 0 {
+                            return Err(ParseError::at(span, "`max_parallel` must be at least 1"));
+                        }
+                        max_parallel = value;
+                    }
+                    "on_error" => {
+                        let (policy, span) = self.expect_ident("after `on_error`")?;
+                        on_error = match policy.as_str() {
+                            "fail" => MapErrorPolicy::Fail,
+                            "skip" => MapErrorPolicy::Skip,
+                            other => {
+                                return Err(ParseError::at(
+                                    span,
+                                    format!(
+                                        "unknown on_error policy `{other}`: expected `fail` \
+                                         or `skip`"
+                                    ),
+                                ));
+                            }
+                        };
+                    }
+                    other => {
+                        return Err(ParseError::at(
+                            key_span,
+                            format!(
+                                "unknown map option `{other}`: expected `max_parallel` or \
+                                 `on_error`"
+                            ),
+                        ));
+                    }
+                }
+            }
+            self.bump()?; // )
+        }
+        self.expect_tok(Tok::LBrace, "to open the map body")?;
+
+        let mut body_shadow = Shadow::container(kw_span);
+        let mut children = Vec::new();
+        let mut body_outs: Vec<(String, Port, Span)> = Vec::new();
+        while self.cur.tok != Tok::RBrace {
+            if self.at_kw("out") {
+                self.bump()?;
+                body_outs.extend(self.bindmap("out")?);
+            } else {
+                let (child, child_shadow) = self.step()?;
+                children.push(child);
+                body_shadow.children.push(child_shadow);
+            }
+        }
+        self.bump()?; // }
+        self.expect_kw("collect", "after the map body")?;
+
+        let mut body = builder::seq(children);
+        for (field, port, span) in body_outs {
+            body_shadow.binds.push((field.clone(), span));
+            body = body.out(&field, port);
+        }
+        let mut shadow = Shadow::container(kw_span);
+        shadow.children.push(body_shadow);
+        let mut spec = builder::map(&item, over, body)
+            .max_parallel(max_parallel)
+            .on_error(on_error);
+        for (field, port, span) in self.bindmap("collect")? {
+            shadow.binds.push((field.clone(), span));
+            spec = spec.collect(&field, port);
+        }
+        let spec = match name {
+            Some((name, _)) => spec.named(&name),
+            None => spec,
+        };
+        Ok((spec, shadow))
+    }
+
     // -- error mapping ------------------------------------------------------
 
     /// Maps a post-lowering [`BuildError`] onto a source position using the
@@ -1837,6 +2516,8 @@ impl<'a> Parser<'a> {
                 self.node_names.get(name).copied(),
                 format!("duplicate step name `{name}`"),
             ),
+            BuildError::UnknownImport { at, .. } => (maps.at.get(at).copied(), err.to_string()),
+            BuildError::ImportTypeConflict { .. } => (None, err.to_string()),
             BuildError::Invalid(v) => {
                 let (at, field) = validate_error_handles(v);
                 let span = field
@@ -1869,6 +2550,17 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// An error inside an imported file, reported at the importing `import`.
+fn imported_error(path: &str, span: Span, err: ParseError) -> ParseError {
+    ParseError::at(
+        span,
+        format!(
+            "in imported `{path}` (line {}, column {}): {}",
+            err.line, err.col, err.message
+        ),
+    )
+}
+
 /// `(at, (at, field))` handles a [`ValidateError`] names things by.
 fn validate_error_handles(v: &ValidateError) -> (Option<&str>, Option<(&str, &str)>) {
     use ValidateError as E;
@@ -1882,6 +2574,10 @@ fn validate_error_handles(v: &ValidateError) -> (Option<&str>, Option<(&str, &st
         | E::RouteUnknownVariant { at, .. }
         | E::RouteArmMismatch { at, .. }
         | E::RouteUncovered { at, .. }
+        | E::RouteMixedArms { at }
+        | E::RouteBadPredicate { at, .. }
+        | E::RoutePredicateVariable { at, .. }
+        | E::RouteNoElse { at }
         | E::CapsExceedProgram { at, .. }
         | E::ParamKindMismatch { at, .. }
         | E::ParamOwnerMismatch { at, .. }
@@ -1890,7 +2586,19 @@ fn validate_error_handles(v: &ValidateError) -> (Option<&str>, Option<(&str, &st
         | E::ToolSetDuplicate { at, .. }
         | E::RefineJudgeNotLeaf { at }
         | E::RefineJudgeInterface { at }
-        | E::WhileNotBool { at, .. } => (Some(at), None),
+        | E::WhileNotBool { at, .. }
+        | E::MapNotList { at, .. }
+        | E::MapItemShadows { at, .. }
+        | E::ApproveOutputNotInput { at, .. }
+        | E::ApproveReservedOutput { at, .. }
+        | E::CallBodyNotSeq { at }
+        | E::CallOutputMissing { at, .. } => (Some(at), None),
+        E::TransformUnknownOutput { at, field }
+        | E::TransformDuplicateOutput { at, field }
+        | E::TransformBadExpr { at, field, .. }
+        | E::TransformUnknownVariable { at, field, .. }
+        | E::TransformTypeMismatch { at, field, .. } => (Some(at), Some((at, field))),
+        E::TransformMissingOutput { at, .. } => (Some(at), None),
         E::DuplicateBinding { at, field }
         | E::UnknownBindingDst { at, field }
         | E::UnknownScopeInput { at, field }
@@ -3,8 +3,8 @@
 //! `print` is deterministic and total over valid [`Program`]s;
 //! `parse(print(p))` reconstructs a program with the identical canonical print
 //! and program hash, and `print(parse(t))` is the canonical form of any valid
-//! text `t`. The canonical printed text (with the `lineage` block omitted) is
-//! also the preimage of [`Program::compute_hash`].
+//! text `t`. The canonical printed text (with the `lineage` block and
+//! comments omitted) is also the preimage of [`Program::compute_hash`].
 //!
 //! # Canonical form rules
 //!
@@ -12,7 +12,8 @@
 //!
 //! 1. Header: `dsrs 1`, `program <name>`.
 //! 2. Sections in fixed order, separated by exactly one blank line:
-//!    `caps` (one line, entries sorted — `CapSet` is a `BTreeSet`), `model`
+//!    `import` lines (arena order, one block, each with its `pin`), `caps`
+//!    (one line, entries sorted — `CapSet` is a `BTreeSet`), `model`
 //!    lines (arena order, one block), `class` blocks (sorted by token),
 //!    `enum` blocks (sorted by token), `sig` blocks (arena order), `tool`
 //!    blocks (arena order), `lineage`, `main`. Empty sections are omitted.
@@ -24,7 +25,8 @@
 //!    (it is `#[serde(skip)]` — secrets are structurally absent).
 //! 5. Node option blocks print only non-default entries in fixed order;
 //!    `max_turns` is always printed on `agent` nodes (the bound is
-//!    load-bearing), and `@model` references are always explicit.
+//!    load-bearing), and `@model` references are always explicit. A `hole`
+//!    prints its options block (only `cache`) after its implementation.
 //! 6. Container nodes have no names in the IR; the printer assigns `_0`,
 //!    `_1`, … in order of appearance (skipping any identifier already taken
 //!    by a leaf or tool). A container in a non-step position is named only
@@ -40,6 +42,14 @@
 //! 10. Ordering within a construct always follows arena/stored order
 //!     (bindings, tools, route arms, steps) — parse preserves text order, so
 //!     canonical text round-trips byte-for-byte.
+//! 11. Whatever a `call` spliced in — nodes, signatures, tools, models,
+//!     nested imports, and classes/enums only imported signatures use —
+//!     prints as nothing but `call <alias>`: the import's pin stands for it.
+//! 12. Comments ([`Comments`]) print as `//` lines directly above the
+//!     declaration or step they are attached to, at its indentation; a
+//!     trailing comment follows its construct's last line after one space.
+//!     Header comments precede `dsrs 1`; footer comments follow `main`
+//!     after a blank line.
 
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
@@ -47,10 +57,11 @@ use std::fmt::Write as _;
 use crate::LMConfig;
 use crate::ir::builder::cot_reasoning_field;
 use crate::ir::graph::{
-    AgentLoopNode, Binding, HoleImpl, HoleNode, Node, NodeId, PortRef, PredictNode, Program, SigId,
-    ToolKind,
+    AgentLoopNode, ApproveNode, Binding, CallNode, Comments, HoleImpl, HoleNode, MapErrorPolicy,
+    MemoPolicy, Node, NodeId, PortRef, PredictNode, Program, SigId, Spliced, ToolKind,
+    TransformNode, Trivia,
 };
-use crate::ir::params::{ContextPolicy, ParamId, ParamValue};
+use crate::ir::params::{Compaction, ContextPolicy, ParamId, ParamValue};
 use crate::ir::sig::{ConstraintDef, FieldDef, RenderSpec};
 use crate::typesys::FieldType;
 
@@ -61,10 +72,15 @@ impl Program {
     }
 }
 
-/// Canonical text; `include_lineage = false` yields the hash preimage
-/// (RFC 0002 §2.1: the program hash covers the canonical text minus lineage).
-pub(crate) fn canonical_text(p: &Program, include_lineage: bool) -> String {
-    Printer::new(p).render(include_lineage)
+/// Canonical text; `annotated = false` yields the hash preimage (RFC 0002
+/// §2.1: the program hash covers the canonical text minus lineage — and
+/// minus comments, which are not content).
+pub(crate) fn canonical_text(p: &Program, annotated: bool) -> String {
+    let mut printer = Printer::new(p);
+    if annotated {
+        printer.comments = Some(&p.meta.comments);
+    }
+    printer.render(annotated)
 }
 
 struct Printer<'p> {
@@ -80,6 +96,10 @@ struct Printer<'p> {
     referenced: HashSet<NodeId>,
     /// Predict nodes detected as `cot` sugar → the base signature.
     cot_bases: HashMap<NodeId, SigId>,
+    /// Entries owned by `call` nodes (rule 11).
+    spliced: Spliced,
+    /// Comments to reprint (rule 12); `None` for the hash preimage.
+    comments: Option<&'p Comments>,
 }
 
 impl<'p> Printer<'p> {
@@ -104,6 +124,9 @@ impl<'p> Printer<'p> {
             match node {
                 Node::Predict(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
                 Node::AgentLoop(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
+                Node::Transform(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
+                Node::Approve(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
+                Node::Call(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
                 Node::Hole(n) => n.binding.iter().for_each(|b| visit_port(&b.src)),
                 Node::Seq(n) => n.out.iter().for_each(|b| visit_port(&b.src)),
                 Node::ForkJoin(n) => n.join.iter().for_each(|b| visit_port(&b.src)),
@@ -117,13 +140,19 @@ impl<'p> Printer<'p> {
                     n.carry.iter().for_each(|b| visit_port(&b.src));
                     n.out.iter().for_each(|b| visit_port(&b.src));
                 }
+                Node::Map(n) => {
+                    visit_port(&n.over);
+                    n.collect.iter().for_each(|b| visit_port(&b.src));
+                }
             }
         }
 
+        let spliced = p.spliced();
         let mut cot_bases = HashMap::new();
         for (id, node) in p.nodes.iter() {
             if let Node::Predict(n) = node
-                && let Some(base) = cot_base(p, n)
+                && !spliced.nodes.contains(&id)
+                && let Some(base) = cot_base(p, n, &spliced.sigs)
             {
                 cot_bases.insert(id, base);
             }
@@ -137,6 +166,8 @@ impl<'p> Printer<'p> {
             next_anon: 0,
             referenced,
             cot_bases,
+            spliced,
+            comments: None,
         }
     }
 
@@ -163,36 +194,77 @@ impl<'p> Printer<'p> {
 
     fn render(mut self, include_lineage: bool) -> String {
         let p = self.p;
+        if let Some(comments) = self.comments {
+            for text in &comments.header {
+                let _ = writeln!(self.out, "//{text}");
+            }
+        }
         let _ = writeln!(self.out, "dsrs {}", p.meta.format);
         let _ = writeln!(self.out, "program {}", p.meta.name);
 
+        let imports: Vec<_> = p
+            .imports
+            .iter()
+            .filter(|(id, _)| !self.spliced.imports.contains(id))
+            .collect();
+        if !imports.is_empty() {
+            self.out.push('\n');
+            for (_, import) in imports {
+                let trivia = self.decl_trivia(&format!("import {}", import.alias));
+                self.comment_lines(trivia, 0);
+                let _ = write!(
+                    self.out,
+                    "import {} as {} pin \"{:016x}\"",
+                    json_str(&import.path),
+                    import.alias,
+                    import.hash
+                );
+                self.trailing_comment(trivia);
+                self.out.push('\n');
+            }
+        }
+
         if !p.caps.is_empty() {
             let caps: Vec<&str> = p.caps.iter().collect();
-            let _ = write!(self.out, "\ncaps {{ {} }}\n", caps.join(" "));
+            let trivia = self.decl_trivia("caps");
+            self.out.push('\n');
+            self.comment_lines(trivia, 0);
+            let _ = write!(self.out, "caps {{ {} }}", caps.join(" "));
+            self.trailing_comment(trivia);
+            self.out.push('\n');
         }
 
-        if !p.models.is_empty() {
+        let models: Vec<_> = p
+            .models
+            .iter()
+            .filter(|(id, _)| !self.spliced.models.contains(id))
+            .map(|(_, model)| model)
+            .collect();
+        if !models.is_empty() {
             self.out.push('\n');
-            for model in p.models.values() {
-                let opts = model_opts(&model.config);
-                let _ = write!(
-                    self.out,
-                    "model {} = {}",
-                    model.name,
-                    json_str(&model.config.model)
-                );
-                if !opts.is_empty() {
-                    let _ = write!(self.out, " {{ {} }}", opts.join(" "));
-                }
+            for model in models {
+                let trivia = self.decl_trivia(&format!("model {}", model.name));
+                self.comment_lines(trivia, 0);
+                let config = model_text(&model.config);
+                let _ = write!(self.out, "model {} = {config}", model.name);
+                self.trailing_comment(trivia);
                 self.out.push('\n');
             }
         }
 
-        let mut class_tokens: Vec<&String> = p.types.classes.keys().collect();
+        let imported_only = self.imported_only_types();
+        let mut class_tokens: Vec<&String> = p
+            .types
+            .classes
+            .keys()
+            .filter(|token| !imported_only.contains(token.as_str()))
+            .collect();
         class_tokens.sort();
         for token in class_tokens {
             let class = &p.types.classes[token];
+            let trivia = self.decl_trivia(&format!("class {token}"));
             self.out.push('\n');
+            self.comment_lines(trivia, 0);
             let _ = write!(self.out, "class {token}");
             if class.rendered_name != *token {
                 let _ = write!(self.out, " alias {}", json_str(&class.rendered_name));
@@ -225,14 +297,23 @@ impl<'p> Printer<'p> {
                 }
                 self.out.push('\n');
             }
-            self.out.push_str("}\n");
+            self.out.push('}');
+            self.trailing_comment(trivia);
+            self.out.push('\n');
         }
 
-        let mut enum_tokens: Vec<&String> = p.types.enums.keys().collect();
+        let mut enum_tokens: Vec<&String> = p
+            .types
+            .enums
+            .keys()
+            .filter(|token| !imported_only.contains(token.as_str()))
+            .collect();
         enum_tokens.sort();
         for token in enum_tokens {
             let def = &p.types.enums[token];
+            let trivia = self.decl_trivia(&format!("enum {token}"));
             self.out.push('\n');
+            self.comment_lines(trivia, 0);
             let _ = write!(self.out, "enum {token}");
             if def.rendered_name != *token {
                 let _ = write!(self.out, " alias {}", json_str(&def.rendered_name));
@@ -246,17 +327,25 @@ impl<'p> Printer<'p> {
                 if value.rendered_name != value.name {
                     let _ = write!(self.out, " alias {}", json_str(&value.rendered_name));
                 }
+                if !value.aliases.is_empty() {
+                    let aliases: Vec<String> = value.aliases.iter().map(|a| json_str(a)).collect();
+                    let _ = write!(self.out, " alias [{}]", aliases.join(" "));
+                }
                 if let Some(docs) = &value.docs {
                     let _ = write!(self.out, " {}", json_str(docs));
                 }
                 self.out.push('\n');
             }
-            self.out.push_str("}\n");
+            self.out.push('}');
+            self.trailing_comment(trivia);
+            self.out.push('\n');
         }
 
         for sig_id in self.printed_sigs() {
             let sig = &p.sigs[sig_id];
+            let trivia = self.decl_trivia(&format!("sig {}", sig.name));
             self.out.push('\n');
+            self.comment_lines(trivia, 0);
             let _ = writeln!(self.out, "sig {} {{", sig.name);
             if !sig.instruction.is_empty() {
                 let _ = writeln!(self.out, "  {}", json_str(&sig.instruction));
@@ -267,12 +356,19 @@ impl<'p> Printer<'p> {
             for field in sig.outputs.iter() {
                 self.sig_field("out", field);
             }
-            self.out.push_str("}\n");
+            self.out.push('}');
+            self.trailing_comment(trivia);
+            self.out.push('\n');
         }
 
-        for tool in p.tools.values() {
-            self.out.push('\n');
+        for (id, tool) in p.tools.iter() {
+            if self.spliced.tools.contains(&id) {
+                continue;
+            }
             let name = p.syms.get(tool.name);
+            let trivia = self.decl_trivia(&format!("tool {name}"));
+            self.out.push('\n');
+            self.comment_lines(trivia, 0);
             let desc = match &p.params[tool.desc].default {
                 ParamValue::ToolDesc { text } => text.as_str(),
                 _ => "",
@@ -297,11 +393,14 @@ impl<'p> Printer<'p> {
                 self.out.push_str(" js");
                 self.code_fence(source);
             }
+            self.trailing_comment(trivia);
             self.out.push('\n');
         }
 
         if include_lineage && let Some(lineage) = &p.meta.lineage {
+            let trivia = self.decl_trivia("lineage");
             self.out.push('\n');
+            self.comment_lines(trivia, 0);
             self.out.push_str("lineage {\n");
             let _ = writeln!(self.out, "  optimizer {}", json_str(&lineage.optimizer));
             let _ = writeln!(self.out, "  trainset {}", json_str(&lineage.trainset));
@@ -313,17 +412,52 @@ impl<'p> Printer<'p> {
                 let _ = writeln!(self.out, "  overlay {}", json_str(overlay));
             }
             let _ = writeln!(self.out, "  date {}", json_str(&lineage.date));
-            self.out.push_str("}\n");
+            self.out.push('}');
+            self.trailing_comment(trivia);
+            self.out.push('\n');
         }
 
         self.out.push('\n');
+        let trivia = self.node_trivia(p.root);
+        self.comment_lines(trivia, 0);
         let main_sig_name = p.sigs[p.sig].name.to_string();
         let _ = write!(self.out, "main: {main_sig_name} = ");
         self.expr(p.root, 0);
+        self.trailing_comment(trivia);
         self.out.push('\n');
+        if let Some(comments) = self.comments
+            && !comments.footer.is_empty()
+        {
+            self.out.push('\n');
+            for text in &comments.footer {
+                let _ = writeln!(self.out, "//{text}");
+            }
+        }
         self.out
     }
 
+    fn decl_trivia(&self, key: &str) -> Option<&'p Trivia> {
+        self.comments?.decls.get(key)
+    }
+
+    fn node_trivia(&self, id: NodeId) -> Option<&'p Trivia> {
+        self.comments?.nodes.get(&id)
+    }
+
+    /// Leading comments, one `//` line each at `level` (rule 12).
+    fn comment_lines(&mut self, trivia: Option<&Trivia>, level: usize) {
+        for text in trivia.iter().flat_map(|t| t.leading.iter()) {
+            self.indent(level);
+            let _ = writeln!(self.out, "//{text}");
+        }
+    }
+
+    fn trailing_comment(&mut self, trivia: Option<&Trivia>) {
+        if let Some(text) = trivia.and_then(|t| t.trailing.as_deref()) {
+            let _ = write!(self.out, " //{text}");
+        }
+    }
+
     /// Signature arena entries printed as `sig` declarations (rule 3).
     fn printed_sigs(&self) -> Vec<SigId> {
         let p = self.p;
@@ -348,6 +482,8 @@ impl<'p> Printer<'p> {
                     }
                 }
                 Node::AgentLoop(n) => n.sig,
+                Node::Transform(n) => n.sig,
+                Node::Approve(n) => n.sig,
                 Node::Hole(n) => n.sig,
                 _ => continue,
             };
@@ -355,32 +491,33 @@ impl<'p> Printer<'p> {
         }
         p.sigs
             .keys()
-            .filter(|id| !sugar.contains(id))
+            .filter(|id| !sugar.contains(id) && !self.spliced.sigs.contains(id))
             .filter(|id| !tool_sigs.contains(id) || node_sigs.contains(id))
             .collect()
     }
 
+    /// Class/enum tokens referenced (transitively) by spliced signatures and
+    /// by nothing else — they belong to an import (rule 11).
+    fn imported_only_types(&self) -> HashSet<String> {
+        let p = self.p;
+        let reach = |imported: bool| {
+            let mut tokens = HashSet::new();
+            for (id, sig) in p.sigs.iter() {
+                if self.spliced.sigs.contains(&id) != imported {
+                    continue;
+                }
+                for field in sig.inputs.iter().chain(sig.outputs.iter()) {
+                    type_tokens(&field.ty, p, &mut tokens);
+                }
+            }
+            tokens
+        };
+        let own = reach(false);
+        reach(true).difference(&own).cloned().collect()
+    }
+
     fn sig_field(&mut self, side: &str, field: &FieldDef) {
-        let _ = write!(
-            self.out,
-            "  {side} {}: {}",
-            field.name,
-            type_text(&field.ty)
-        );
-        if field.lm_name != field.name {
-            let _ = write!(self.out, " alias {}", json_str(&field.lm_name));
-        }
-        if let Some(docs) = &field.docs {
-            let _ = write!(self.out, " {}", json_str(docs));
-        }
-        for c in field.constraints.iter() {
-            self.out.push(' ');
-            self.out.push_str(&constraint_def_text(c));
-        }
-        if let Some(meta) = render_text(&field.render) {
-            let _ = write!(self.out, " {meta}");
-        }
-        self.out.push('\n');
+        let _ = writeln!(self.out, "  {}", field_text(side, field));
     }
 
     fn code_fence(&mut self, source: &str) {
@@ -429,6 +566,8 @@ impl<'p> Printer<'p> {
 
     /// Prints a step (`name = expr`) in a seq/fork position.
     fn step(&mut self, id: NodeId, level: usize) {
+        let trivia = self.node_trivia(id);
+        self.comment_lines(trivia, level);
         self.indent(level);
         let name = match self.p.nodes[id].leaf_name() {
             Some(sym) => self.p.syms.get(sym).to_string(),
@@ -436,6 +575,7 @@ impl<'p> Printer<'p> {
         };
         let _ = write!(self.out, "{name} = ");
         self.expr(id, level);
+        self.trailing_comment(trivia);
         self.out.push('\n');
     }
 
@@ -445,6 +585,9 @@ impl<'p> Printer<'p> {
         match &node {
             Node::Predict(n) => self.predict(id, n),
             Node::AgentLoop(n) => self.agent(n, level),
+            Node::Transform(n) => self.transform(n),
+            Node::Approve(n) => self.approve(n),
+            Node::Call(n) => self.call(n),
             Node::Hole(n) => self.hole(n),
             Node::Seq(n) => {
                 self.out.push_str("seq {\n");
@@ -480,6 +623,12 @@ impl<'p> Printer<'p> {
                     self.target(*arm, level + 1);
                     self.out.push('\n');
                 }
+                for (predicate, arm) in n.when.iter() {
+                    self.indent(level + 1);
+                    let _ = write!(self.out, "when {} -> ", json_str(predicate));
+                    self.target(*arm, level + 1);
+                    self.out.push('\n');
+                }
                 if let Some(default) = n.default {
                     self.indent(level + 1);
                     self.out.push_str("else -> ");
@@ -556,6 +705,33 @@ impl<'p> Printer<'p> {
                 self.indent(level);
                 self.out.push('}');
             }
+            Node::Map(n) => {
+                let item = self.p.syms.get(n.item).to_string();
+                let _ = write!(self.out, "map {item} in ");
+                self.port(&n.over);
+                let _ = write!(self.out, " (max_parallel {}", n.max_parallel);
+                if n.on_error == MapErrorPolicy::Skip {
+                    self.out.push_str(" on_error skip");
+                }
+                self.out.push_str(") {\n");
+                let body = self.p.nodes[n.body].clone();
+                if let Node::Seq(seq) = &body {
+                    for &child in seq.body.iter() {
+                        self.step(child, level + 1);
+                    }
+                    if !seq.out.is_empty() {
+                        self.indent(level + 1);
+                        self.out.push_str("out ");
+                        self.bindmap(&seq.out);
+                        self.out.push('\n');
+                    }
+                } else {
+                    self.step(n.body, level + 1);
+                }
+                self.indent(level);
+                self.out.push_str("} collect ");
+                self.bindmap(&n.collect);
+            }
         }
     }
 
@@ -568,7 +744,7 @@ impl<'p> Printer<'p> {
         let _ = write!(self.out, "{keyword} {sig_name}");
         self.modelref(n.model);
         self.args(&n.binding);
-        let mut opts: Vec<String> = Vec::new();
+        let mut opts: Vec<String> = n.memo.iter().map(memo_opt).collect();
         self.instruction_opt(&mut opts, n.instruction, n.sig);
         self.demos_opt(&mut opts, n.demos);
         if !opts.is_empty() {
@@ -645,6 +821,9 @@ impl<'p> Printer<'p> {
                 max_history_turns,
                 tool_result_max_bytes,
                 playbook,
+                compaction,
+                summarize_tool_results_over,
+                pinned_turns,
             } = policy;
             if let Some(turns) = max_history_turns {
                 ctx.push(format!("max_history_turns {turns}"));
@@ -655,12 +834,32 @@ impl<'p> Printer<'p> {
             if let Some(playbook) = playbook {
                 ctx.push(format!("playbook {}", json_str(playbook)));
             }
+            if let Some(compaction) = compaction {
+                let mut opts = vec![format!("at_tokens {}", compaction.at_tokens)];
+                if compaction.keep_recent != Compaction::DEFAULT_KEEP_RECENT {
+                    opts.push(format!("keep_recent {}", compaction.keep_recent));
+                }
+                if let Some(model) = compaction.model {
+                    opts.push(format!("model @{}", self.p.models[model].name));
+                }
+                if let Some(instruction) = &compaction.instruction {
+                    opts.push(format!("instruction {}", json_str(instruction)));
+                }
+                ctx.push(format!("compact {{ {} }}", opts.join(" ")));
+            }
+            if let Some(bytes) = summarize_tool_results_over {
+                ctx.push(format!("summarize_tool_results_over {bytes}"));
+            }
+            if !pinned_turns.is_empty() {
+                let turns: Vec<String> = pinned_turns.iter().map(u32::to_string).collect();
+                ctx.push(format!("pin [{}]", turns.join(" ")));
+            }
             if !ctx.is_empty() {
                 self.indent(level + 1);
                 let _ = writeln!(self.out, "context {{ {} }}", ctx.join(" "));
             }
         }
-        let mut opts: Vec<String> = Vec::new();
+        let mut opts: Vec<String> = n.memo.iter().map(memo_opt).collect();
         self.instruction_opt(&mut opts, n.instruction, n.sig);
         self.demos_opt(&mut opts, n.demos);
         for opt in opts {
@@ -688,6 +887,39 @@ impl<'p> Printer<'p> {
                 let _ = write!(self.out, " caps [{}] extern \"{hash:016x}\"", caps.join(" "));
             }
         }
+        if let Some(memo) = &n.memo {
+            let _ = write!(self.out, " {{ {} }}", memo_opt(memo));
+        }
+    }
+
+    /// Expressions print in signature output order, whatever order they
+    /// were declared in.
+    fn transform(&mut self, n: &TransformNode) {
+        let p = self.p;
+        let def = &p.sigs[n.sig];
+        let _ = write!(self.out, "transform {}", def.name);
+        self.args(&n.binding);
+        let exprs: Vec<String> = def
+            .outputs
+            .iter()
+            .filter_map(|field| {
+                n.exprs
+                    .iter()
+                    .find(|(dst, _)| p.syms.get(*dst) == &*field.name)
+                    .map(|(_, expr)| format!("{} = {}", field.name, json_str(expr)))
+            })
+            .collect();
+        let _ = write!(self.out, " {{ {} }}", exprs.join(", "));
+    }
+
+    fn approve(&mut self, n: &ApproveNode) {
+        let _ = write!(self.out, "approve {}", self.p.sigs[n.sig].name);
+        self.args(&n.binding);
+    }
+
+    fn call(&mut self, n: &CallNode) {
+        let _ = write!(self.out, "call {}", self.p.imports[n.import].alias);
+        self.args(&n.binding);
     }
 
     fn instruction_opt(&mut self, opts: &mut Vec<String>, param: ParamId, sig: SigId) {
@@ -781,7 +1013,7 @@ impl<'p> Printer<'p> {
 
 /// Detects `cot` sugar: the node's signature is `base.augmented_with([reasoning])`
 /// for some *other* arena signature with identical name/instruction/inputs.
-fn cot_base(p: &Program, n: &PredictNode) -> Option<SigId> {
+fn cot_base(p: &Program, n: &PredictNode, spliced: &HashSet<SigId>) -> Option<SigId> {
     let sig = &p.sigs[n.sig];
     let first = sig.outputs.first()?;
     if *first != cot_reasoning_field() {
@@ -789,6 +1021,7 @@ fn cot_base(p: &Program, n: &PredictNode) -> Option<SigId> {
     }
     p.sigs.iter().find_map(|(id, base)| {
         (id != n.sig
+            && !spliced.contains(&id)
             && base.name == sig.name
             && base.instruction == sig.instruction
             && base.inputs == sig.inputs
@@ -797,6 +1030,31 @@ fn cot_base(p: &Program, n: &PredictNode) -> Option<SigId> {
     })
 }
 
+/// Collects the class/enum tokens `ty` reaches, through class fields.
+fn type_tokens(ty: &FieldType, p: &Program, out: &mut HashSet<String>) {
+    match ty {
+        FieldType::Class(token) => {
+            if out.insert(token.clone())
+                && let Some(class) = p.types.classes.get(token)
+            {
+                for field in &class.fields {
+                    type_tokens(&field.field_type, p, out);
+                }
+            }
+        }
+        FieldType::Enum(token) => {
+            out.insert(token.clone());
+        }
+        FieldType::List(inner) | FieldType::Optional(inner) => type_tokens(inner, p, out),
+        FieldType::Map(key, value) => {
+            type_tokens(key, p, out);
+            type_tokens(value, p, out);
+        }
+        FieldType::Union(items) => items.iter().for_each(|item| type_tokens(item, p, out)),
+        _ => {}
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Formatting helpers
 // ---------------------------------------------------------------------------
@@ -807,6 +1065,14 @@ pub(crate) fn json_str(s: &str) -> String {
 }
 
 /// Shortest round-trip float text (`1` for 1.0 parses back exactly).
+/// `cache { ... }`; an empty block means no expiry.
+pub(crate) fn memo_opt(memo: &MemoPolicy) -> String {
+    match memo.ttl_s {
+        Some(ttl) => format!("cache {{ ttl_s {ttl} }}"),
+        None => "cache { }".to_string(),
+    }
+}
+
 fn fmt_f64(v: f64) -> String {
     format!("{v}")
 }
@@ -839,6 +1105,53 @@ pub(crate) fn type_text(ty: &FieldType) -> String {
     }
 }
 
+/// One signature field as declared: `in question: string "docs" hidden`.
+pub(crate) fn field_text(side: &str, field: &FieldDef) -> String {
+    let mut out = format!("{side} {}: {}", field.name, type_text(&field.ty));
+    if field.lm_name != field.name {
+        let _ = write!(out, " alias {}", json_str(&field.lm_name));
+    }
+    if let Some(docs) = &field.docs {
+        let _ = write!(out, " {}", json_str(docs));
+    }
+    for c in field.constraints.iter() {
+        out.push(' ');
+        out.push_str(&constraint_def_text(c));
+    }
+    if let Some(meta) = render_text(&field.render) {
+        let _ = write!(out, " {meta}");
+    }
+    if let Some(prefix) = &field.prefix {
+        let _ = write!(out, " prefix {}", json_str(prefix));
+    }
+    if let Some(hint) = &field.hint {
+        let _ = write!(out, " hint {}", json_str(hint));
+    }
+    for example in field.examples.iter() {
+        let _ = write!(out, " example {}", json_str(example));
+    }
+    if field.hidden {
+        out.push_str(" hidden");
+    }
+    if let Some(order) = field.order {
+        let _ = write!(out, " order {order}");
+    }
+    if let Some(section) = &field.section {
+        let _ = write!(out, " section {}", json_str(section));
+    }
+    out
+}
+
+/// A model declaration's right-hand side: `"openai:gpt-4o-mini" { cache true }`.
+pub(crate) fn model_text(config: &LMConfig) -> String {
+    let opts = model_opts(config);
+    if opts.is_empty() {
+        json_str(&config.model)
+    } else {
+        format!("{} {{ {} }}", json_str(&config.model), opts.join(" "))
+    }
+}
+
 fn constraint_def_text(c: &ConstraintDef) -> String {
     constraint_text(
         c.kind == crate::core::ConstraintKind::Check,
@@ -896,5 +1209,11 @@ fn model_opts(config: &LMConfig) -> Vec<String> {
     if config.cache != default.cache {
         opts.push(format!("cache {}", config.cache));
     }
+    if let Some(tokens) = config.context_window {
+        opts.push(format!("context_window {tokens}"));
+    }
+    if let Some(name) = &config.tokenizer {
+        opts.push(format!("tokenizer {}", json_str(name)));
+    }
     opts
 }
@@ -0,0 +1,229 @@
+//! `Transform` expressions: the minijinja expression language a
+//! [`TransformNode`](crate::ir::TransformNode) computes its outputs with.
+//!
+//! Expressions see exactly the node's signature inputs as variables and run
+//! in a shared environment with strict undefined handling — no templates, no
+//! host functions, no I/O. `validate()` compiles every expression, checks its
+//! free variables against the signature inputs, and statically types the
+//! shapes that have an obvious type (field paths, `~` concatenation, common
+//! filters); everything else is checked against the output type when it runs.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::Value;
+
+use crate::trace::JsonMap;
+use crate::typesys::constraint::JINJA_GLOBALS;
+use crate::typesys::{FieldType, TypeTable};
+
+/// Shared evaluation environment. Strict undefined handling turns a missing
+/// key or out-of-range index into an error instead of a silent `null`.
+static TRANSFORM_ENV: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env
+});
+
+/// Compiles `expr` and returns the variables it reads that are neither
+/// environment globals nor bound in the expression itself.
+pub(crate) fn free_variables(expr: &str) -> Result<Vec<String>, String> {
+    let compiled = TRANSFORM_ENV
+        .compile_expression(expr)
+        .map_err(|err| err.to_string())?;
+    let mut vars: Vec<String> = compiled
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|var| !JINJA_GLOBALS.contains(&var.as_str()))
+        .collect();
+    vars.sort();
+    Ok(vars)
+}
+
+/// Evaluates `expr` with `input` as its variables.
+pub(crate) fn evaluate(expr: &str, input: &JsonMap) -> Result<Value, String> {
+    let compiled = TRANSFORM_ENV
+        .compile_expression(expr)
+        .map_err(|err| err.to_string())?;
+    let value = compiled
+        .eval(minijinja::Value::from_serialize(input))
+        .map_err(|err| err.to_string())?;
+    if value.is_undefined() {
+        return Err("expression evaluated to undefined".to_string());
+    }
+    serde_json::to_value(&value).map_err(|err| err.to_string())
+}
+
+/// The static type of `expr`, when its shape determines one. `inputs` types
+/// the free variables. `None` means "only known at run time", never "wrong".
+pub(crate) fn infer_type(
+    expr: &str,
+    inputs: &dyn Fn(&str) -> Option<FieldType>,
+    types: &TypeTable,
+) -> Option<FieldType> {
+    let tokens = tokenize(expr)?;
+    let top = top_level(&tokens);
+    // Lower-precedence operators than `~`/`|` make the shape opaque.
+    if top.iter().any(|&i| match &tokens[i] {
+        Tok::Ident(word) => matches!(
+            word.as_str(),
+            "if" | "else" | "and" | "or" | "not" | "in" | "is"
+        ),
+        Tok::Punct(c) => matches!(c, '=' | '<' | '>' | '!'),
+        _ => false,
+    }) {
+        return None;
+    }
+    if top.iter().any(|&i| tokens[i] == Tok::Punct('~')) {
+        return Some(FieldType::String);
+    }
+
+    // Only top-level pipes apply filters to the whole expression; nested
+    // ones (inside call arguments or groups) make the shape opaque.
+    if tokens
+        .iter()
+        .enumerate()
+        .any(|(i, tok)| *tok == Tok::Punct('|') && !top.contains(&i))
+    {
+        return None;
+    }
+    let mut segments = tokens.split(|tok| *tok == Tok::Punct('|'));
+    let mut ty = path_type(segments.next()?, inputs, types);
+    for filter in segments {
+        let Some(Tok::Ident(name)) = filter.first() else {
+            return None;
+        };
+        if filter.len() > 1
+            && (filter[1] != Tok::Punct('(') || filter.last() != Some(&Tok::Punct(')')))
+        {
+            return None;
+        }
+        ty = match name.as_str() {
+            "upper" | "lower" | "title" | "capitalize" | "trim" | "string" | "join" | "replace"
+            | "tojson" | "format" => Some(FieldType::String),
+            "length" | "count" | "int" => Some(FieldType::Int),
+            "float" => Some(FieldType::Float),
+            "first" | "last" => match ty {
+                Some(FieldType::List(inner)) => Some(*inner),
+                _ => None,
+            },
+            "sort" | "reverse" | "unique" => ty.filter(|t| matches!(t, FieldType::List(_))),
+            _ => None,
+        };
+    }
+    ty
+}
+
+/// Types a bare field path: `name`, then any of `.field`, `[0]`, `["key"]`.
+fn path_type(
+    tokens: &[Tok],
+    inputs: &dyn Fn(&str) -> Option<FieldType>,
+    types: &TypeTable,
+) -> Option<FieldType> {
+    let (Tok::Ident(root), mut rest) = tokens.split_first()? else {
+        return None;
+    };
+    let mut ty = inputs(root)?;
+    while !rest.is_empty() {
+        let (key, tail) = match rest {
+            [Tok::Punct('.'), Tok::Ident(field), tail @ ..] => (Key::Field(field), tail),
+            [Tok::Punct('['), Tok::Str(field), Tok::Punct(']'), tail @ ..] => {
+                (Key::Field(field), tail)
+            }
+            [Tok::Punct('['), Tok::Int, Tok::Punct(']'), tail @ ..] => (Key::Index, tail),
+            _ => return None,
+        };
+        ty = match (ty, key) {
+            (FieldType::List(inner), Key::Index) => *inner,
+            (FieldType::Map(_, value), Key::Field(_)) => *value,
+            (FieldType::Class(token), Key::Field(field)) => types
+                .classes
+                .get(&token)?
+                .fields
+                .iter()
+                .find(|f| f.name == *field || f.rendered_name == *field)?
+                .field_type
+                .clone(),
+            _ => return None,
+        };
+        rest = tail;
+    }
+    Some(ty)
+}
+
+enum Key<'a> {
+    Field(&'a str),
+    Index,
+}
+
+#[derive(Debug, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Int,
+    Float,
+    Punct(char),
+}
+
+/// A shallow lexer — enough to see an expression's top-level shape. Returns
+/// `None` on anything it does not understand (the compiler has already
+/// accepted the expression; inference just gives up).
+fn tokenize(src: &str) -> Option<Vec<Tok>> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Tok::Ident(word));
+        } else if c.is_ascii_digit() {
+            let mut float = false;
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                float |= c == '.';
+                chars.next();
+            }
+            tokens.push(if float { Tok::Float } else { Tok::Int });
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next()? {
+                    '\\' => text.push(chars.next()?),
+                    q if q == c => break,
+                    other => text.push(other),
+                }
+            }
+            tokens.push(Tok::Str(text));
+        } else {
+            tokens.push(Tok::Punct(c));
+            chars.next();
+        }
+    }
+    Some(tokens)
+}
+
+/// Indices of the tokens outside any `()`/`[]`/`{}` group.
+fn top_level(tokens: &[Tok]) -> HashSet<usize> {
+    let mut depth = 0usize;
+    let mut top = HashSet::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok {
+            Tok::Punct('(' | '[' | '{') => depth += 1,
+            Tok::Punct(')' | ']' | '}') => depth = depth.saturating_sub(1),
+            _ if depth == 0 => {
+                top.insert(i);
+            }
+            _ => {}
+        }
+    }
+    top
+}
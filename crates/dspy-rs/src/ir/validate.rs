@@ -10,8 +10,15 @@
 //!    on the referenced node's interface; bound port types must equal (or
 //!    widen: Int→Float, T→Optional<T>, T→Union containing T) the destination.
 //! 3. Leaf names are unique program-wide. Route arms are type-identical;
-//!    `Route.on` is enum-typed with covered variants or a default. All loops
-//!    are bounded by construction.
+//!    `Route.on` is enum-typed with covered variants or a default, unless the
+//!    route branches on `when` predicates, which compile, read only `this`,
+//!    and always come with an `else`. All loops are bounded by construction.
+//!    `Map.over` is list-typed and its item name does not shadow a scope
+//!    input. `Transform` expressions compile, read only signature inputs,
+//!    and cover each output exactly once. `Approve` outputs pass through
+//!    same-named inputs and never shadow the implicit `decision`/`reason`
+//!    fields. A `Call` body is a `Seq` checked in the callee's own `$` frame
+//!    and exports every callee output.
 //! 4. Node/tool/hole caps ⊆ `program.caps`.
 //! 5. Acyclicity is structural: trees + earlier-sibling references cannot
 //!    cycle. Every node is reachable from the root exactly once.
@@ -85,6 +92,22 @@ pub enum ValidateError {
     RouteArmMismatch { at: String, arm: String },
     #[error("route at {at} does not cover variants {missing:?} and has no default")]
     RouteUncovered { at: String, missing: Vec<String> },
+    #[error("route at {at} mixes variant arms with `when` predicates")]
+    RouteMixedArms { at: String },
+    #[error("route at {at}: predicate `{predicate}` does not compile: {message}")]
+    RouteBadPredicate {
+        at: String,
+        predicate: String,
+        message: String,
+    },
+    #[error("route at {at}: predicate `{predicate}` reads `{var}`; predicates see only `this`")]
+    RoutePredicateVariable {
+        at: String,
+        predicate: String,
+        var: String,
+    },
+    #[error("route at {at} has `when` predicates but no `else` arm")]
+    RouteNoElse { at: String },
     #[error("caps of `{at}` exceed the program ceiling: missing {missing:?}")]
     CapsExceedProgram { at: String, missing: Vec<String> },
     #[error("param `{path}` referenced by `{at}` has kind {got:?}, expected {expected:?}")]
@@ -123,6 +146,49 @@ pub enum ValidateError {
          compatible type (v1 rule: carry gives iteration-0 values from the scope input)"
     )]
     CarryNotScopeInput { at: String, field: String },
+    #[error("map at {at}: `over` port must be list-typed, got {got}")]
+    MapNotList { at: String, got: String },
+    #[error("map at {at}: item name `{field}` shadows an enclosing scope input")]
+    MapItemShadows { at: String, field: String },
+    #[error("transform `{at}`: `{field}` is not an output field of its signature")]
+    TransformUnknownOutput { at: String, field: String },
+    #[error("transform `{at}`: output `{field}` has more than one expression")]
+    TransformDuplicateOutput { at: String, field: String },
+    #[error("transform `{at}`: output `{field}` has no expression")]
+    TransformMissingOutput { at: String, field: String },
+    #[error("transform `{at}`: expression for `{field}` does not compile: {message}")]
+    TransformBadExpr {
+        at: String,
+        field: String,
+        message: String,
+    },
+    #[error(
+        "transform `{at}`: expression for `{field}` reads `{var}`, which is not an input of \
+         its signature"
+    )]
+    TransformUnknownVariable {
+        at: String,
+        field: String,
+        var: String,
+    },
+    #[error("transform `{at}`: expression for `{field}` yields {got}, expected {expected}")]
+    TransformTypeMismatch {
+        at: String,
+        field: String,
+        expected: String,
+        got: String,
+    },
+    #[error(
+        "approve `{at}`: output `{field}` must pass through an input of the same name and a \
+         compatible type"
+    )]
+    ApproveOutputNotInput { at: String, field: String },
+    #[error("approve `{at}`: output `{field}` shadows the implicit `{field}` field")]
+    ApproveReservedOutput { at: String, field: String },
+    #[error("call `{at}`: the callee's root must be a Seq")]
+    CallBodyNotSeq { at: String },
+    #[error("call `{at}`: callee output `{field}` is not exported (or has an incompatible type)")]
+    CallOutputMissing { at: String, field: String },
     #[error(
         "program output `{field}` is not exported by the root seq (or has an incompatible type)"
     )]
@@ -132,11 +198,49 @@ pub enum ValidateError {
 /// Type interface of a node: exported field name → type, in export order.
 pub(crate) type Interface = IndexMap<String, FieldType>;
 
+/// The ports a node may read, as the validator sees them on entering it —
+/// what editor tooling offers when completing a binding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PortScope {
+    /// `$.field`: the scope's inputs.
+    pub inputs: IndexMap<String, FieldType>,
+    /// `node.field`: the visible nodes' interfaces, in scope order.
+    pub nodes: Vec<(NodeId, IndexMap<String, FieldType>)>,
+    /// `^field`: loop-carried values (empty outside a loop body).
+    pub carried: IndexMap<String, FieldType>,
+}
+
 impl Program {
     /// Full structural validation. Run by builder `finish()` and by the
-    /// deserialization load path; hosts may re-run it at will.
+    /// deserialization load path; hosts may re-run it at will. Reports the
+    /// first error; [`validate_all`](Program::validate_all) reports them all.
     pub fn validate(&self) -> Result<(), ValidateError> {
-        Validator::new(self).run()
+        match self.validate_all().into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Every validation error, in discovery order (empty: valid). A step that
+    /// fails still exports its signature outputs to later steps, so one bad
+    /// binding does not hide the errors after it; errors that only follow
+    /// from an earlier one are not reported again.
+    pub fn validate_all(&self) -> Vec<ValidateError> {
+        let mut validator = Validator::new(self);
+        if let Err(err) = validator.run() {
+            validator.errors.push(err);
+        }
+        validator.errors
+    }
+
+    /// The [`PortScope`] of every node the validation walk reached. Steps
+    /// after an invalid one still get a scope; nodes under a failed
+    /// container may not.
+    pub fn port_scopes(&self) -> HashMap<NodeId, PortScope> {
+        let mut validator = Validator::new(self);
+        validator.scopes = Some(HashMap::new());
+        let _ = validator.run();
+        validator.scopes.unwrap_or_default()
     }
 
     /// A human-readable handle for error messages: the leaf name when the
@@ -175,6 +279,13 @@ struct Validator<'p> {
     visited: HashSet<NodeId>,
     leaf_names: HashSet<&'p str>,
     ifaces: HashMap<NodeId, Interface>,
+    /// Errors recorded so far; the walk continues past a failing step.
+    errors: Vec<ValidateError>,
+    /// Failed containers with no interface to stand in for them.
+    poisoned: HashSet<NodeId>,
+    /// Scopes recorded on entry to each node, when asked for
+    /// ([`Program::port_scopes`]).
+    scopes: Option<HashMap<NodeId, PortScope>>,
 }
 
 impl<'p> Validator<'p> {
@@ -184,14 +295,22 @@ impl<'p> Validator<'p> {
             visited: HashSet::new(),
             leaf_names: HashSet::new(),
             ifaces: HashMap::new(),
+            errors: Vec::new(),
+            poisoned: HashSet::new(),
+            scopes: None,
         }
     }
 
-    fn run(mut self) -> Result<(), ValidateError> {
+    /// Walks the program. `Err` only for what stops the walk outright;
+    /// everything else lands in `self.errors`.
+    fn run(&mut self) -> Result<(), ValidateError> {
+        // Out-of-range ids would make the tree walk panic: fail fast.
         self.check_ids()?;
-        self.check_sigs()?;
-        self.check_params()?;
-        self.check_tools()?;
+        for pass in [Self::check_sigs, Self::check_params, Self::check_tools] {
+            if let Err(err) = pass(self) {
+                self.errors.push(err);
+            }
+        }
 
         let Node::Seq(_) = &self.p.nodes[self.p.root] else {
             return Err(ValidateError::RootNotSeq);
@@ -203,21 +322,27 @@ impl<'p> Validator<'p> {
             visible: Vec::new(),
             in_loop: false,
         };
-        let root_iface = self.check_node(self.p.root, &scope)?;
+        let root_iface = match self.check_node(self.p.root, &scope) {
+            Ok(iface) => iface,
+            Err(err) => {
+                self.recover(self.p.root, err);
+                return Ok(());
+            }
+        };
 
         // The root seq's exports are the program's external interface.
         for field in self.p.sigs[self.p.sig].outputs.iter() {
             match root_iface.get(&*field.name) {
                 Some(ty) if compat(ty, &field.ty) => {}
-                _ => {
-                    return Err(ValidateError::ProgramOutputMissing {
-                        field: field.name.to_string(),
-                    });
-                }
+                _ => self.errors.push(ValidateError::ProgramOutputMissing {
+                    field: field.name.to_string(),
+                }),
             }
         }
 
-        if self.visited.len() != self.p.nodes.len() {
+        // A failed step leaves its subtree partly unwalked; only a clean
+        // walk can tell unreachable nodes apart.
+        if self.errors.is_empty() && self.visited.len() != self.p.nodes.len() {
             return Err(ValidateError::UnreachableNodes {
                 count: self.p.nodes.len() - self.visited.len(),
             });
@@ -225,6 +350,41 @@ impl<'p> Validator<'p> {
         Ok(())
     }
 
+    /// Records a failed step and stands in for its interface so the steps
+    /// after it are still checked: a leaf keeps its signature outputs, a
+    /// container is poisoned. An error that only says a poisoned node lacks
+    /// a field is a consequence of one already recorded, and is dropped.
+    fn recover(&mut self, id: NodeId, err: ValidateError) {
+        let consequence = matches!(
+            &err,
+            ValidateError::UnknownOutField { node, .. }
+                if self.poisoned.iter().any(|p| self.p.node_display(*p) == *node)
+        );
+        if !consequence {
+            self.errors.push(err);
+        }
+        if self.ifaces.contains_key(&id) {
+            return;
+        }
+        let stand_in = match &self.p.nodes[id] {
+            Node::Predict(n) => Some(sig_outputs(&self.p.sigs[n.sig])),
+            Node::AgentLoop(n) => Some(sig_outputs(&self.p.sigs[n.sig])),
+            Node::Transform(n) => Some(sig_outputs(&self.p.sigs[n.sig])),
+            Node::Call(n) => Some(sig_outputs(&self.p.sigs[n.sig])),
+            Node::Hole(n) => Some(sig_outputs(&self.p.sigs[n.sig])),
+            Node::Approve(n) => approve_interface("", &self.p.sigs[n.sig]).ok(),
+            _ => None,
+        };
+        match stand_in {
+            Some(iface) => {
+                self.ifaces.insert(id, iface);
+            }
+            None => {
+                self.poisoned.insert(id);
+            }
+        }
+    }
+
     // -- structural pre-passes ------------------------------------------------
 
     /// Every entity reference in every arena is range-checked up front so the
@@ -306,6 +466,28 @@ impl<'p> Validator<'p> {
                     }
                     binds_ok(&at, &n.binding)?;
                 }
+                Node::Call(n) => {
+                    sym_ok(&at, n.name)?;
+                    sig_ok(&at, n.sig)?;
+                    node_ok(&at, n.body)?;
+                    if n.import.index() >= p.imports.len() {
+                        return Err(err(&at, format!("{}", n.import)));
+                    }
+                    binds_ok(&at, &n.binding)?;
+                }
+                Node::Transform(n) => {
+                    sym_ok(&at, n.name)?;
+                    sig_ok(&at, n.sig)?;
+                    for (field, _) in n.exprs.iter() {
+                        sym_ok(&at, *field)?;
+                    }
+                    binds_ok(&at, &n.binding)?;
+                }
+                Node::Approve(n) => {
+                    sym_ok(&at, n.name)?;
+                    sig_ok(&at, n.sig)?;
+                    binds_ok(&at, &n.binding)?;
+                }
                 Node::Hole(n) => {
                     sym_ok(&at, n.name)?;
                     sig_ok(&at, n.sig)?;
@@ -332,6 +514,9 @@ impl<'p> Validator<'p> {
                         sym_ok(&at, *v)?;
                         node_ok(&at, *c)?;
                     }
+                    for (_, c) in n.when.iter() {
+                        node_ok(&at, *c)?;
+                    }
                     if let Some(d) = n.default {
                         node_ok(&at, d)?;
                     }
@@ -350,6 +535,12 @@ impl<'p> Validator<'p> {
                     binds_ok(&at, &n.carry)?;
                     binds_ok(&at, &n.out)?;
                 }
+                Node::Map(n) => {
+                    port_ok(&at, &n.over)?;
+                    sym_ok(&at, n.item)?;
+                    node_ok(&at, n.body)?;
+                    binds_ok(&at, &n.collect)?;
+                }
             }
         }
 
@@ -374,6 +565,13 @@ impl<'p> Validator<'p> {
                         }
                     }
                 }
+                crate::ir::params::ParamValue::ContextPolicy { policy } => {
+                    if let Some(model) = policy.compaction.as_ref().and_then(|c| c.model)
+                        && model.index() >= n_models
+                    {
+                        return Err(err(&at, format!("{model}")));
+                    }
+                }
                 _ => {}
             }
         }
@@ -480,6 +678,24 @@ impl<'p> Validator<'p> {
                 at: self.p.node_display(id),
             });
         }
+        if self.scopes.is_some() {
+            let recorded = PortScope {
+                inputs: scope.inputs.clone(),
+                nodes: scope
+                    .visible
+                    .iter()
+                    .filter_map(|n| self.ifaces.get(n).map(|iface| (*n, iface.clone())))
+                    .collect(),
+                carried: if scope.in_loop {
+                    scope.inputs.clone()
+                } else {
+                    IndexMap::new()
+                },
+            };
+            if let Some(scopes) = &mut self.scopes {
+                scopes.insert(id, recorded);
+            }
+        }
         // Clone the node handle so `self` stays borrowable; nodes are cheap
         // relative to a validation pass and this runs once at load.
         let node = self.p.nodes[id].clone();
@@ -545,6 +761,45 @@ impl<'p> Validator<'p> {
                 self.check_leaf_bindings(&at, n.sig, &n.binding, scope)?;
                 sig_outputs(&self.p.sigs[n.sig])
             }
+            Node::Call(n) => {
+                let at = self.leaf(n.name)?;
+                self.check_leaf_bindings(&at, n.sig, &n.binding, scope)?;
+                let Node::Seq(_) = &self.p.nodes[n.body] else {
+                    return Err(ValidateError::CallBodyNotSeq { at });
+                };
+                // The callee runs in its own frame: `$` is its signature's
+                // inputs, and nothing of the caller's is visible.
+                let inputs = sig_inputs(&self.p.sigs[n.sig]);
+                let body_scope = Scope {
+                    inputs: &inputs,
+                    visible: Vec::new(),
+                    in_loop: false,
+                };
+                let body_iface = self.check_node(n.body, &body_scope)?;
+                for field in self.p.sigs[n.sig].outputs.iter() {
+                    match body_iface.get(&*field.name) {
+                        Some(ty) if compat(ty, &field.ty) => {}
+                        _ => {
+                            return Err(ValidateError::CallOutputMissing {
+                                at,
+                                field: field.name.to_string(),
+                            });
+                        }
+                    }
+                }
+                sig_outputs(&self.p.sigs[n.sig])
+            }
+            Node::Transform(n) => {
+                let at = self.leaf(n.name)?;
+                self.check_leaf_bindings(&at, n.sig, &n.binding, scope)?;
+                self.check_transform_exprs(&at, n.sig, &n.exprs)?;
+                sig_outputs(&self.p.sigs[n.sig])
+            }
+            Node::Approve(n) => {
+                let at = self.leaf(n.name)?;
+                self.check_leaf_bindings(&at, n.sig, &n.binding, scope)?;
+                approve_interface(&at, &self.p.sigs[n.sig])?
+            }
             Node::Hole(n) => {
                 let at = self.leaf(n.name)?;
                 if !n.caps.is_subset(&self.p.caps) {
@@ -562,7 +817,9 @@ impl<'p> Validator<'p> {
             Node::Seq(n) => {
                 let mut inner = scope.child(&[]);
                 for &child in n.body.iter() {
-                    self.check_node(child, &inner)?;
+                    if let Err(err) = self.check_node(child, &inner) {
+                        self.recover(child, err);
+                    }
                     inner.visible.push(child);
                 }
                 self.check_export_bindings(&format!("{id}"), &n.out, &inner)?
@@ -570,11 +827,61 @@ impl<'p> Validator<'p> {
             Node::ForkJoin(n) => {
                 for &branch in n.branches.iter() {
                     // Branches cannot see each other: entry scope for each.
-                    self.check_node(branch, scope)?;
+                    if let Err(err) = self.check_node(branch, scope) {
+                        self.recover(branch, err);
+                    }
                 }
                 let after = scope.child(&n.branches);
                 self.check_export_bindings(&format!("{id}"), &n.join, &after)?
             }
+            Node::Route(n) if !n.when.is_empty() => {
+                let at = format!("{id}");
+                if !n.arms.is_empty() {
+                    return Err(ValidateError::RouteMixedArms { at });
+                }
+                self.port_type(&at, &n.on, scope)?;
+                let Some(default) = n.default else {
+                    return Err(ValidateError::RouteNoElse { at });
+                };
+                let mut first: Option<Interface> = None;
+                for (predicate, arm) in n.when.iter() {
+                    let vars = crate::typesys::constraint::unbound_variables(predicate).map_err(
+                        |message| ValidateError::RouteBadPredicate {
+                            at: at.clone(),
+                            predicate: predicate.to_string(),
+                            message,
+                        },
+                    )?;
+                    if let Some(var) = vars.into_iter().next() {
+                        return Err(ValidateError::RoutePredicateVariable {
+                            at,
+                            predicate: predicate.to_string(),
+                            var,
+                        });
+                    }
+                    let iface = self.check_node(*arm, scope)?;
+                    match &first {
+                        None => first = Some(iface),
+                        Some(expect) => {
+                            if !iface_eq(expect, &iface) {
+                                return Err(ValidateError::RouteArmMismatch {
+                                    at,
+                                    arm: format!("when {predicate:?}"),
+                                });
+                            }
+                        }
+                    }
+                }
+                let iface = first.expect("predicate routes have at least one arm");
+                let d_iface = self.check_node(default, scope)?;
+                if !iface_eq(&iface, &d_iface) {
+                    return Err(ValidateError::RouteArmMismatch {
+                        at,
+                        arm: "else".to_string(),
+                    });
+                }
+                iface
+            }
             Node::Route(n) => {
                 let at = format!("{id}");
                 let on_ty = self.port_type(&at, &n.on, scope)?;
@@ -714,6 +1021,40 @@ impl<'p> Validator<'p> {
                 }
                 self.check_export_bindings(&at, &n.out, &after)?
             }
+            Node::Map(n) => {
+                let at = format!("{id}");
+                let item_ty = match self.port_type(&at, &n.over, scope)? {
+                    FieldType::List(inner) => *inner,
+                    other => {
+                        return Err(ValidateError::MapNotList {
+                            at,
+                            got: type_label(&other),
+                        });
+                    }
+                };
+                let item = self.p.syms.get(n.item).to_string();
+                if scope.inputs.contains_key(&item) {
+                    return Err(ValidateError::MapItemShadows { at, field: item });
+                }
+                let mut inputs = scope.inputs.clone();
+                inputs.insert(item, item_ty);
+                let body_scope = Scope {
+                    inputs: &inputs,
+                    visible: scope.visible.clone(),
+                    in_loop: scope.in_loop,
+                };
+                self.check_node(n.body, &body_scope)?;
+                // `collect` resolves per element, like a loop's `join`: the
+                // body node plus, when the body is a Seq, its steps.
+                let mut after = body_scope.child(&[n.body]);
+                if let Node::Seq(seq) = &self.p.nodes[n.body] {
+                    after.visible.extend(seq.body.iter().copied());
+                }
+                self.check_export_bindings(&at, &n.collect, &after)?
+                    .into_iter()
+                    .map(|(name, ty)| (name, FieldType::List(Box::new(ty))))
+                    .collect::<Interface>()
+            }
         };
         self.ifaces.insert(id, iface.clone());
         Ok(iface)
@@ -766,6 +1107,66 @@ impl<'p> Validator<'p> {
         Ok(())
     }
 
+    /// Transform rule: one compiling expression per signature output, reading
+    /// only signature inputs; shapes with a static type must fit the output.
+    fn check_transform_exprs(
+        &self,
+        at: &str,
+        sig: SigId,
+        exprs: &[(Sym, Box<str>)],
+    ) -> Result<(), ValidateError> {
+        let def = &self.p.sigs[sig];
+        let inputs = sig_inputs(def);
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (field, expr) in exprs {
+            let name = self.p.syms.get(*field);
+            let Some(out) = def.outputs.iter().find(|f| &*f.name == name) else {
+                return Err(ValidateError::TransformUnknownOutput {
+                    at: at.to_string(),
+                    field: name.to_string(),
+                });
+            };
+            if !seen.insert(name) {
+                return Err(ValidateError::TransformDuplicateOutput {
+                    at: at.to_string(),
+                    field: name.to_string(),
+                });
+            }
+            let vars = crate::ir::transform::free_variables(expr).map_err(|message| {
+                ValidateError::TransformBadExpr {
+                    at: at.to_string(),
+                    field: name.to_string(),
+                    message,
+                }
+            })?;
+            if let Some(var) = vars.into_iter().find(|v| !inputs.contains_key(v)) {
+                return Err(ValidateError::TransformUnknownVariable {
+                    at: at.to_string(),
+                    field: name.to_string(),
+                    var,
+                });
+            }
+            let lookup = |var: &str| inputs.get(var).cloned();
+            if let Some(got) = crate::ir::transform::infer_type(expr, &lookup, &self.p.types)
+                && !transform_fits(&got, &out.ty)
+            {
+                return Err(ValidateError::TransformTypeMismatch {
+                    at: at.to_string(),
+                    field: name.to_string(),
+                    expected: type_label(&out.ty),
+                    got: type_label(&got),
+                });
+            }
+        }
+        if let Some(missing) = def.outputs.iter().find(|f| !seen.contains(&*f.name)) {
+            return Err(ValidateError::TransformMissingOutput {
+                at: at.to_string(),
+                field: missing.name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
     /// Container export rule (`out`/`join`): dst names unique; sources
     /// resolve. The export set becomes the container's interface.
     fn check_export_bindings(
@@ -871,12 +1272,11 @@ impl<'p> Validator<'p> {
                     });
                 }
                 let name = self.p.syms.get(*field);
-                let iface = self
-                    .ifaces
+                // Visible nodes are validated before use; a poisoned one has
+                // no interface, and `recover` drops the error below.
+                self.ifaces
                     .get(node)
-                    .expect("visible nodes are validated before use");
-                iface
-                    .get(name)
+                    .and_then(|iface| iface.get(name))
                     .cloned()
                     .ok_or_else(|| ValidateError::UnknownOutField {
                         at: at.to_string(),
@@ -937,6 +1337,44 @@ fn sig_outputs(def: &SignatureDef) -> Interface {
         .collect()
 }
 
+/// The values of an `Approve` node's implicit `decision` field.
+pub(crate) const APPROVE_DECISIONS: [&str; 3] = ["approved", "edited", "rejected"];
+
+/// `Approve` interface: the signature outputs, each passing through a
+/// same-named input, then `decision` and `reason`.
+fn approve_interface(at: &str, def: &SignatureDef) -> Result<Interface, ValidateError> {
+    let mut iface = sig_outputs(def);
+    for (field, ty) in iface.iter() {
+        if field == "decision" || field == "reason" {
+            return Err(ValidateError::ApproveReservedOutput {
+                at: at.to_string(),
+                field: field.clone(),
+            });
+        }
+        if !def
+            .inputs
+            .iter()
+            .any(|input| &*input.name == field && compat(&input.ty, ty))
+        {
+            return Err(ValidateError::ApproveOutputNotInput {
+                at: at.to_string(),
+                field: field.clone(),
+            });
+        }
+    }
+    iface.insert(
+        "decision".to_string(),
+        FieldType::Union(
+            APPROVE_DECISIONS
+                .iter()
+                .map(|d| FieldType::Literal(d.to_string()))
+                .collect(),
+        ),
+    );
+    iface.insert("reason".to_string(), FieldType::optional(FieldType::String));
+    Ok(iface)
+}
+
 fn iface_eq(a: &Interface, b: &Interface) -> bool {
     a.len() == b.len() && a.iter().all(|(name, ty)| b.get(name) == Some(ty))
 }
@@ -956,6 +1394,22 @@ pub(crate) fn compat(src: &FieldType, dst: &FieldType) -> bool {
     }
 }
 
+/// [`compat`] for statically typed transform expressions. A computed string
+/// may still name an enum variant or literal, so those are left to the
+/// run-time output check.
+fn transform_fits(got: &FieldType, out: &FieldType) -> bool {
+    let out = match out {
+        FieldType::Optional(inner) => inner,
+        other => other,
+    };
+    compat(got, out)
+        || (*got == FieldType::String
+            && matches!(
+                out,
+                FieldType::Enum(_) | FieldType::Literal(_) | FieldType::Union(_)
+            ))
+}
+
 fn check_tokens(sig: &str, ty: &FieldType, types: &TypeTable) -> Result<(), ValidateError> {
     match ty {
         FieldType::Class(token) => {
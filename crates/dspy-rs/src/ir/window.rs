@@ -0,0 +1,167 @@
+//! Context windows: fitting a leaf's prompt to its model before sending it.
+//!
+//! A model whose config sets [`context_window`](crate::LMConfig::context_window)
+//! (or names a [`tokenizer`](crate::LMConfig::tokenizer)) has every
+//! `predict`, `cot`, and `agent` prompt counted with its [`Tokenizer`]
+//! before the leaf's span opens. The prompt may use the window less the
+//! model's `max_tokens`, which stay reserved for the reply; an agent's tool
+//! definitions count against it too. The count lands on the span as
+//! [`SpanEvent::PromptTokens`](crate::trace::SpanEvent::PromptTokens).
+//!
+//! A prompt that does not fit is handled by the runtime's
+//! [`OverflowPolicy`]: drop demos, then truncate the longest string inputs,
+//! and fail with [`RunError::ContextWindow`](crate::ir::RunError::ContextWindow)
+//! if it still does not fit. Failing is the default, and is not retryable.
+//! An agent's later turns are counted too but never trimmed: a turn over the
+//! window fails the same way, so pair long loops with a
+//! [`ContextPolicy`](crate::ir::ContextPolicy).
+
+use serde_json::Value;
+
+use crate::core::lm::{Message, Tokenizer, count_messages};
+use crate::ir::DemoRow;
+use crate::trace::JsonMap;
+
+/// Appended to an input the [`OverflowPolicy`] shortened.
+pub const TRUNCATION_MARKER: &str = "… [truncated]";
+
+/// What a leaf does when its prompt would overflow its model's context
+/// window. The default fails; each step enabled here runs in order until
+/// the prompt fits.
+///
+/// ```ignore
+/// let env = RuntimeEnv::new()
+///     .with_overflow_policy(OverflowPolicy::fail().trim_demos().truncate_inputs());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverflowPolicy {
+    /// Drop few-shot demos, last first.
+    pub trim_demos: bool,
+    /// Shorten the longest string inputs, ending each with
+    /// [`TRUNCATION_MARKER`].
+    pub truncate_inputs: bool,
+}
+
+impl OverflowPolicy {
+    /// Never trims: an overflowing prompt fails the leaf.
+    pub fn fail() -> Self {
+        Self::default()
+    }
+
+    pub fn trim_demos(mut self) -> Self {
+        self.trim_demos = true;
+        self
+    }
+
+    pub fn truncate_inputs(mut self) -> Self {
+        self.truncate_inputs = true;
+        self
+    }
+}
+
+/// A prompt rendered to fit, with what it cost and what fitting it took.
+/// `prompt_tokens` may still exceed the limit when the policy ran out of
+/// things to trim.
+pub(crate) struct Fitted {
+    pub prefix: Vec<Message>,
+    pub suffix: Vec<Message>,
+    pub prompt_tokens: usize,
+    pub demos_dropped: usize,
+    pub truncated: Vec<String>,
+}
+
+/// Renders a prompt from `demos` and `input`, then applies `policy` until it
+/// fits in `limit` tokens (no limit: render once and count). `fixed` counts
+/// tokens sent outside the messages, like tool definitions.
+pub(crate) fn fit_prompt(
+    tokenizer: &dyn Tokenizer,
+    limit: Option<usize>,
+    fixed: usize,
+    policy: OverflowPolicy,
+    demos: &[DemoRow],
+    input: &JsonMap,
+    render: impl Fn(&[DemoRow], &JsonMap) -> (Vec<Message>, Vec<Message>),
+) -> Fitted {
+    let count = |prefix: &[Message], suffix: &[Message]| {
+        fixed + count_messages(tokenizer, prefix.iter().chain(suffix))
+    };
+    let (prefix, suffix) = render(demos, input);
+    let mut fitted = Fitted {
+        prompt_tokens: count(&prefix, &suffix),
+        prefix,
+        suffix,
+        demos_dropped: 0,
+        truncated: Vec::new(),
+    };
+    let Some(limit) = limit else {
+        return fitted;
+    };
+
+    let mut kept = demos.len();
+    while policy.trim_demos && fitted.prompt_tokens > limit && kept > 0 {
+        kept -= 1;
+        (fitted.prefix, fitted.suffix) = render(&demos[..kept], input);
+        fitted.prompt_tokens = count(&fitted.prefix, &fitted.suffix);
+    }
+    fitted.demos_dropped = demos.len() - kept;
+
+    if policy.truncate_inputs && fitted.prompt_tokens > limit {
+        let mut input = input.clone();
+        let mut exhausted: Vec<String> = Vec::new();
+        while fitted.prompt_tokens > limit {
+            let Some((field, text, tokens)) = input
+                .iter()
+                .filter(|(field, _)| !exhausted.contains(field))
+                .filter_map(|(field, value)| {
+                    let text = value.as_str()?;
+                    Some((field.clone(), text, tokenizer.count(text)))
+                })
+                .max_by_key(|(_, _, tokens)| *tokens)
+            else {
+                break;
+            };
+            let base = text.strip_suffix(TRUNCATION_MARKER).unwrap_or(text);
+            let keep = tokens
+                .saturating_sub(fitted.prompt_tokens - limit)
+                .saturating_sub(tokenizer.count(TRUNCATION_MARKER));
+            let shortened = format!(
+                "{}{TRUNCATION_MARKER}",
+                truncate_tokens(tokenizer, base, keep)
+            );
+            if shortened.len() >= text.len() {
+                exhausted.push(field);
+                continue;
+            }
+            if keep == 0 {
+                exhausted.push(field.clone());
+            }
+            if !fitted.truncated.contains(&field) {
+                fitted.truncated.push(field.clone());
+            }
+            input.insert(field, Value::String(shortened));
+            (fitted.prefix, fitted.suffix) = render(&demos[..kept], &input);
+            fitted.prompt_tokens = count(&fitted.prefix, &fitted.suffix);
+        }
+    }
+    fitted
+}
+
+/// The longest prefix of `text`, cut on a char boundary, that counts at
+/// most `max` tokens.
+fn truncate_tokens<'a>(tokenizer: &dyn Tokenizer, text: &'a str, max: usize) -> &'a str {
+    if tokenizer.count(text) <= max {
+        return text;
+    }
+    let ends: Vec<usize> = text.char_indices().map(|(at, _)| at).collect();
+    // `ends[0]` is 0, the empty prefix, which always fits.
+    let (mut lo, mut hi) = (0, ends.len());
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if tokenizer.count(&text[..ends[mid]]) <= max {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    &text[..ends.get(lo).copied().unwrap_or(0)]
+}
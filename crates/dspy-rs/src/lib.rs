@@ -134,7 +134,9 @@ pub use dsrs_tools::{Capability, CodeModeTool, RUN_JS_TOOL_NAME, SandboxConfig};
 pub mod typesys;
 pub use dsrs_macros::*;
 pub use facet::{Facet, Shape};
-pub use typesys::{Constraint, ConstraintLevel, FieldType, Flag, OutputSchema, Schema};
+pub use typesys::{
+    AmbiguousMatch, Constraint, ConstraintLevel, FieldType, Flag, MatchRule, OutputSchema, Schema,
+};
 
 /// The curated core surface — the recommended import for DSRs programs.
 ///
@@ -216,6 +218,29 @@ pub mod __macro_support {
     pub use serde;
     pub use serde_json;
     pub use tokio;
+
+    /// `name` is one of `names`, usable in `const` items: backs the
+    /// compile-time field collision check of `#[signature(extends = ...)]`.
+    pub const fn declares(names: &[&str], name: &str) -> bool {
+        let name = name.as_bytes();
+        let mut i = 0;
+        'names: while i < names.len() {
+            let candidate = names[i].as_bytes();
+            i += 1;
+            if candidate.len() != name.len() {
+                continue;
+            }
+            let mut j = 0;
+            while j < name.len() {
+                if candidate[j] != name[j] {
+                    continue 'names;
+                }
+                j += 1;
+            }
+            return true;
+        }
+        false
+    }
 }
 
 
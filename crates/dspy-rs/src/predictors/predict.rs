@@ -898,6 +898,7 @@ fn field_type_from_json_schema(
                             rendered_name: name.clone(),
                             name,
                             docs: None,
+                            aliases: Vec::new(),
                         })
                         .collect(),
                 },
@@ -1216,6 +1217,24 @@ where
         S::schema()
     }
 
+    fn signature_def(&self) -> Option<&'static SignatureDef> {
+        Some(SignatureDef::of::<S>())
+    }
+
+    fn signature_types(&self) -> crate::typesys::TypeTable {
+        // Same merge as the leaf's own program: output-reachable defs from
+        // the cache entry plus the input side's.
+        let mut types = SignatureDef::types_of::<S>().clone();
+        let input = <S::Input as Schema>::output_schema().types;
+        for (name, class) in input.classes {
+            types.classes.entry(name).or_insert(class);
+        }
+        for (name, def) in input.enums {
+            types.enums.entry(name).or_insert(def);
+        }
+        types
+    }
+
     fn instruction(&self) -> String {
         self.instruction_override
             .clone()
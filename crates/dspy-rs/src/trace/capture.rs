@@ -53,6 +53,54 @@ struct SinkInner {
     epoch: Instant,
 }
 
+impl SinkInner {
+    fn intern_component(&mut self, name: &str) -> CompId {
+        match self.comp_index.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = CompId(self.trace.components.len() as u32);
+                self.trace.components.push(name.to_string());
+                self.comp_index.insert(name.to_string(), id);
+                self.seqs.push(0);
+                id
+            }
+        }
+    }
+
+    fn next_seq(&mut self, component: CompId) -> u32 {
+        let seq = self.seqs[component.0 as usize];
+        self.seqs[component.0 as usize] += 1;
+        seq
+    }
+
+    fn intern_prefix(&mut self, messages: &[Message]) -> PrefixId {
+        let hash = stable_hash_debug(&messages);
+        match self.prefix_index.get(&hash) {
+            Some(&id) => id,
+            None => {
+                let id = PrefixId(self.trace.prefixes.len() as u32);
+                self.trace.prefixes.push(PrefixEntry {
+                    messages: messages.to_vec(),
+                });
+                self.prefix_index.insert(hash, id);
+                id
+            }
+        }
+    }
+
+    fn intern_model(&mut self, entry: ModelEntry) -> ModelId {
+        match self.model_index.get(&entry.config_hash) {
+            Some(&id) => id,
+            None => {
+                let id = ModelId(self.trace.models.len() as u32);
+                self.model_index.insert(entry.config_hash, id);
+                self.trace.models.push(entry);
+                id
+            }
+        }
+    }
+}
+
 impl TraceSink {
     fn new(mut meta: TraceMeta) -> Self {
         meta.v = 1;
@@ -84,47 +132,46 @@ impl TraceSink {
         }
     }
 
-    fn begin(&self, req: SpanRequest<'_>) -> SpanGuard {
+    /// Everything recorded so far; spans still open have no outcome yet.
+    pub(crate) fn snapshot(&self) -> Trace {
+        self.0.lock().unwrap().trace.clone()
+    }
+
+    /// A detached sink for one lane of concurrent work, sharing this
+    /// trace's metadata.
+    fn lane(&self) -> TraceSink {
+        let meta = self.0.lock().unwrap().trace.meta.clone();
+        TraceSink::new(meta)
+    }
+
+    /// Appends a finished lane's spans in their recorded order, re-interning
+    /// components, prefixes, and models, and assigning `seq`s from this
+    /// trace's counters. Top-level lane spans parent to the innermost span
+    /// open here.
+    fn absorb(&self, lane: Trace) {
         let mut inner = self.0.lock().unwrap();
+        let base = inner.trace.spans.len() as u32;
+        let open = inner.open.last().copied();
+        for mut span in lane.spans {
+            span.component = inner.intern_component(&lane.components[span.component.0 as usize]);
+            span.seq = inner.next_seq(span.component);
+            span.prefix = span
+                .prefix
+                .map(|p| inner.intern_prefix(&lane.prefixes[p.0 as usize].messages));
+            span.model = inner.intern_model(lane.models[span.model.0 as usize].clone());
+            span.id = SpanId(base + span.id.0);
+            span.parent = span.parent.map(|p| SpanId(base + p.0)).or(open);
+            inner.trace.spans.push(span);
+        }
+    }
 
-        let component = match inner.comp_index.get(req.component) {
-            Some(&id) => id,
-            None => {
-                let id = CompId(inner.trace.components.len() as u32);
-                inner.trace.components.push(req.component.to_string());
-                inner.comp_index.insert(req.component.to_string(), id);
-                inner.seqs.push(0);
-                id
-            }
-        };
-        let seq = inner.seqs[component.0 as usize];
-        inner.seqs[component.0 as usize] += 1;
-
-        let prefix = req.prefix.map(|messages| {
-            let hash = stable_hash_debug(&messages);
-            match inner.prefix_index.get(&hash) {
-                Some(&id) => id,
-                None => {
-                    let id = PrefixId(inner.trace.prefixes.len() as u32);
-                    inner.trace.prefixes.push(PrefixEntry {
-                        messages: messages.to_vec(),
-                    });
-                    inner.prefix_index.insert(hash, id);
-                    id
-                }
-            }
-        });
+    fn begin(&self, req: SpanRequest<'_>) -> SpanGuard {
+        let mut inner = self.0.lock().unwrap();
 
-        let entry = ModelEntry::from_config(req.model);
-        let model = match inner.model_index.get(&entry.config_hash) {
-            Some(&id) => id,
-            None => {
-                let id = ModelId(inner.trace.models.len() as u32);
-                inner.model_index.insert(entry.config_hash, id);
-                inner.trace.models.push(entry);
-                id
-            }
-        };
+        let component = inner.intern_component(req.component);
+        let seq = inner.next_seq(component);
+        let prefix = req.prefix.map(|messages| inner.intern_prefix(messages));
+        let model = inner.intern_model(ModelEntry::from_config(req.model));
 
         let id = SpanId(inner.trace.spans.len() as u32);
         let parent = inner.open.last().copied();
@@ -231,6 +278,37 @@ pub fn is_capturing() -> bool {
     ACTIVE.try_with(|_| ()).is_ok()
 }
 
+/// Opens a capture lane on the active scope, or `None` outside one. Work
+/// run [`in_lane`] records into the lane, a detached trace, instead of the
+/// active one. Concurrent work that runs the same component many times (map
+/// elements) merges its lanes in a fixed order with [`merge_lane`], so
+/// `(component, seq)` does not depend on completion order.
+pub(crate) fn open_lane() -> Option<TraceSink> {
+    ACTIVE.try_with(TraceSink::lane).ok()
+}
+
+/// Runs `fut` recording into `lane` (from [`open_lane`]), or into the active
+/// scope when there is none. The lane outlives `fut`, so a caller that drops
+/// the work mid-flight still merges its spans, closed `Cancelled`.
+pub(crate) async fn in_lane<Fut: Future>(lane: Option<&TraceSink>, fut: Fut) -> Fut::Output {
+    match lane {
+        Some(lane) => ACTIVE.scope(lane.clone(), fut).await,
+        None => fut.await,
+    }
+}
+
+/// The active scope's sink, for readers that outlive the current lane (run
+/// checkpoints snapshot the run's root trace from inside map elements).
+pub(crate) fn current_sink() -> Option<TraceSink> {
+    ACTIVE.try_with(TraceSink::clone).ok()
+}
+
+/// Appends what `lane` recorded to the active scope, assigning its spans the
+/// next `seq`s. No-op when no scope is active.
+pub(crate) fn merge_lane(lane: &TraceSink) {
+    let _ = ACTIVE.try_with(|sink| sink.absorb(lane.snapshot()));
+}
+
 /// Everything recorded eagerly at span open.
 pub struct SpanRequest<'a> {
     /// Component name: `trace_name` / fx slot name / dotted path.
@@ -125,6 +125,60 @@ pub enum SpanEvent {
         /// Tool-level failure that was reported back to the model as text.
         error: Option<String>,
     },
+    /// The leaf was served from the interpreter's node memo (a `cache`
+    /// policy) instead of running: the output is the one stored `age_ms`
+    /// ago, and the span carries no usage.
+    Memo { age_ms: u64 },
+    /// A guardrail flagged, rewrote, or blocked what this leaf sent or
+    /// produced (see [`Guardrail`](crate::ir::Guardrail)).
+    Guardrail {
+        guard: String,
+        stage: crate::ir::GuardStage,
+        action: crate::ir::GuardAction,
+        reason: String,
+    },
+    /// The context policy folded `summarized` older conversation messages
+    /// into a running `summary` written by `model`, keeping `pinned` of them
+    /// verbatim (see [`Compaction`](crate::ir::Compaction)). The summarizing
+    /// call's usage counts toward the span's.
+    HistoryCompacted {
+        model: String,
+        summarized: u32,
+        pinned: u32,
+        summary: String,
+        usage: LmUsage,
+    },
+    /// The context policy had `model` summarize a `bytes`-long result of tool
+    /// call `id` before feeding it back; the following `ToolRun` carries the
+    /// summary.
+    ToolResultSummarized {
+        id: String,
+        model: String,
+        bytes: u64,
+        usage: LmUsage,
+    },
+    /// A summarizing call of the context policy failed with `error` and the
+    /// agent went on without it: compaction dropped the messages it was
+    /// folding, and the result of tool call `id` (when set) was only
+    /// clipped.
+    SummaryFailed {
+        model: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        error: String,
+    },
+    /// The leaf's prompt counted `prompt_tokens` with `tokenizer` against a
+    /// `limit` of its model's context window less `max_tokens` (`None` when
+    /// the model declares no window). The overflow policy dropped
+    /// `demos_dropped` demos and truncated the `truncated` inputs to fit
+    /// (see [`OverflowPolicy`](crate::ir::OverflowPolicy)).
+    PromptTokens {
+        tokenizer: String,
+        prompt_tokens: u32,
+        limit: Option<u32>,
+        demos_dropped: u32,
+        truncated: Vec<String>,
+    },
     /// Unknown tag from a newer writer; preserved as a placeholder on read,
     /// dropped from the canonical JSONL on re-serialize.
     #[doc(hidden)]
@@ -150,6 +204,10 @@ pub enum SpanErrorKind {
     Tool,
     /// Scope ended while the span was open (task cancelled).
     Cancelled,
+    /// A guardrail blocked the call or its output.
+    Guardrail,
+    /// The prompt did not fit the model's context window.
+    ContextWindow,
 }
 
 impl SpanErrorKind {
@@ -159,6 +217,8 @@ impl SpanErrorKind {
             Self::Parse => "parse",
             Self::Tool => "tool",
             Self::Cancelled => "cancelled",
+            Self::Guardrail => "guardrail",
+            Self::ContextWindow => "context_window",
         }
     }
 }
@@ -361,6 +421,72 @@ impl Trace {
             .collect();
     }
 
+    /// This trace with `later`'s spans appended: components, prefixes, and
+    /// models re-interned, span ids shifted past this trace's, and each
+    /// component's `seq` continuing where it left off. Stitches together the
+    /// traces of one run that moved between processes; `meta` stays this
+    /// trace's.
+    pub fn followed_by(&self, later: &Trace) -> Trace {
+        let mut out = self.clone();
+        let base = out.spans.len() as u32;
+        let mut seqs: Vec<u32> = vec![0; out.components.len()];
+        for span in &out.spans {
+            let next = &mut seqs[span.component.0 as usize];
+            *next = (*next).max(span.seq + 1);
+        }
+        let mut prefix_index: std::collections::HashMap<u64, PrefixId> = out
+            .prefixes
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| (stable_hash_debug(&entry.messages), PrefixId(id as u32)))
+            .collect();
+
+        for span in &later.spans {
+            let mut span = span.clone();
+            let name = &later.components[span.component.0 as usize];
+            span.component = out.component_id(name).unwrap_or_else(|| {
+                out.components.push(name.clone());
+                if !out.param_ids.is_empty() {
+                    out.param_ids.push(None);
+                }
+                seqs.push(0);
+                CompId(out.components.len() as u32 - 1)
+            });
+            span.seq = seqs[span.component.0 as usize];
+            seqs[span.component.0 as usize] += 1;
+            span.prefix = span.prefix.map(|p| {
+                let messages = &later.prefixes[p.0 as usize].messages;
+                *prefix_index
+                    .entry(stable_hash_debug(messages))
+                    .or_insert_with(|| {
+                        out.prefixes.push(PrefixEntry {
+                            messages: messages.clone(),
+                        });
+                        PrefixId(out.prefixes.len() as u32 - 1)
+                    })
+            });
+            let model = &later.models[span.model.0 as usize];
+            span.model = match out
+                .models
+                .iter()
+                .position(|entry| entry.config_hash == model.config_hash)
+            {
+                Some(id) => ModelId(id as u32),
+                None => {
+                    out.models.push(model.clone());
+                    ModelId(out.models.len() as u32 - 1)
+                }
+            };
+            span.id = SpanId(base + span.id.0);
+            span.parent = span.parent.map(|p| SpanId(base + p.0));
+            out.spans.push(span);
+        }
+        if later.outcome.is_some() {
+            out.outcome = later.outcome.clone();
+        }
+        out
+    }
+
 }
 
 impl Span {
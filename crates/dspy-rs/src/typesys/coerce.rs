@@ -2,7 +2,9 @@
 //!
 //! Replaces BAML's `jsonish` parser with a focused, dependency-light coercer that handles
 //! the quirks DSRs actually relies on: markdown code fences, bulleted/numbered lists as
-//! arrays, loose bool/number spellings, and JSON objects for nested structs.
+//! arrays, loose bool/number spellings, JSON objects for nested structs, and fuzzy
+//! enum/literal matching (declared aliases, case/punctuation normalization, and a bounded
+//! edit distance that refuses to guess between equally close values).
 
 use anyhow::{Result, anyhow, bail};
 use serde_json::{Map, Value};
@@ -20,6 +22,37 @@ pub enum Flag {
     CoercedFromString,
     /// Extra text around a JSON object/array was ignored.
     ExtraTextIgnored,
+    /// An enum value or literal was matched without being spelled exactly.
+    FuzzyMatch {
+        /// The value the text resolved to (the enum variant's Rust name, or the literal).
+        matched: String,
+        rule: MatchRule,
+    },
+}
+
+/// Which rule resolved a [`Flag::FuzzyMatch`]. Rules are tried in this order; the first
+/// that yields a candidate wins, and more than one candidate is an [`AmbiguousMatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchRule {
+    /// A declared alias, compared case-insensitively.
+    Alias,
+    /// Equal after lowercasing and dropping everything but letters and digits
+    /// (`"Negative."`, `"in-progress"` → `InProgress`).
+    Normalized,
+    /// Within the bounded edit distance (this many edits) of the normalized text.
+    EditDistance(usize),
+}
+
+/// Fuzzy matching found more than one equally good value, so coercion refuses to pick.
+///
+/// Surfaces as the source of a [`ParseError::CoercionFailed`](crate::ParseError); reach it
+/// with [`JsonishError::ambiguous_match`](crate::JsonishError::ambiguous_match).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("`{input}` is ambiguous for `{target}`: could be {}", .candidates.join(" or "))]
+pub struct AmbiguousMatch {
+    pub input: String,
+    pub target: String,
+    pub candidates: Vec<String>,
 }
 
 impl std::fmt::Display for Flag {
@@ -29,6 +62,15 @@ impl std::fmt::Display for Flag {
             Flag::ParsedListFromText => "parsed list from text",
             Flag::CoercedFromString => "coerced from string",
             Flag::ExtraTextIgnored => "extra text ignored",
+            Flag::FuzzyMatch { matched, rule } => {
+                return match rule {
+                    MatchRule::Alias => write!(f, "matched `{matched}` by alias"),
+                    MatchRule::Normalized => write!(f, "matched `{matched}` after normalization"),
+                    MatchRule::EditDistance(distance) => {
+                        write!(f, "matched `{matched}` within edit distance {distance}")
+                    }
+                };
+            }
         };
         f.write_str(s)
     }
@@ -62,12 +104,7 @@ fn coerce_inner(
         FieldType::Float => coerce_float(raw, flags),
         FieldType::Bool => coerce_bool(raw, flags),
         FieldType::Literal(expected) => {
-            let cleaned = strip_quotes(raw.trim());
-            if cleaned == *expected {
-                Ok(Value::String(expected.clone()))
-            } else {
-                bail!("expected literal `{expected}`, got `{cleaned}`")
-            }
+            coerce_literals(raw, std::slice::from_ref(expected), field_type, flags)
         }
         FieldType::Optional(inner) => {
             if is_nullish(raw) {
@@ -79,8 +116,13 @@ fn coerce_inner(
         FieldType::List(inner) => coerce_list(raw, inner, schema, flags),
         FieldType::Map(_, value_type) => coerce_map(raw, value_type, schema, flags),
         FieldType::Class(name) => coerce_class(raw, name, schema, flags),
-        FieldType::Enum(name) => coerce_enum(raw, name, schema),
+        FieldType::Enum(name) => coerce_enum(raw, name, schema, flags),
         FieldType::Union(items) => {
+            // A union of literals is one choice set: matching each arm in turn would let
+            // the first fuzzy hit win where the arms are actually ambiguous.
+            if let Some(literals) = literal_union(items) {
+                return coerce_literals(raw, &literals, field_type, flags);
+            }
             let mut last_err = None;
             for item in items {
                 match coerce_inner(raw, item, schema, flags) {
@@ -264,24 +306,222 @@ fn coerce_class(
     Ok(Value::Object(out))
 }
 
-fn coerce_enum(raw: &str, enum_name: &str, schema: &TypeTable) -> Result<Value> {
+fn coerce_enum(
+    raw: &str,
+    enum_name: &str,
+    schema: &TypeTable,
+    flags: &mut Vec<Flag>,
+) -> Result<Value> {
     let enm = schema
         .enums
         .get(enum_name)
         .ok_or_else(|| anyhow!("unknown enum `{enum_name}`"))?;
     let needle = strip_quotes(raw.trim());
-    let needle_lower = needle.to_ascii_lowercase();
-    for value in &enm.values {
-        if value.rendered_name == needle
-            || value.name == needle
-            || value.rendered_name.to_ascii_lowercase() == needle_lower
-            || value.name.to_ascii_lowercase() == needle_lower
-        {
+    let choices: Vec<Choice<'_>> = enm
+        .values
+        .iter()
+        .map(|value| Choice {
             // serde deserializes unit enum variants from their Rust name.
-            return Ok(Value::String(value.name.clone()));
+            value: &value.name,
+            names: vec![value.rendered_name.as_str(), value.name.as_str()],
+            aliases: &value.aliases,
+        })
+        .collect();
+    match match_choice(&needle, &enm.rendered_name, &choices, flags)? {
+        Some(value) => Ok(Value::String(value.to_string())),
+        None => bail!("`{needle}` is not a valid `{}` variant", enm.rendered_name),
+    }
+}
+
+fn coerce_literals(
+    raw: &str,
+    literals: &[String],
+    field_type: &FieldType,
+    flags: &mut Vec<Flag>,
+) -> Result<Value> {
+    let needle = strip_quotes(raw.trim());
+    let choices: Vec<Choice<'_>> = literals
+        .iter()
+        .map(|literal| Choice {
+            value: literal,
+            names: vec![literal.as_str()],
+            aliases: &[],
+        })
+        .collect();
+    let target = super::render::type_name(field_type, None);
+    match match_choice(&needle, &target, &choices, flags)? {
+        Some(value) => Ok(Value::String(value.to_string())),
+        None if literals.len() == 1 => {
+            bail!("expected literal `{}`, got `{needle}`", literals[0])
+        }
+        None => bail!("`{needle}` is not one of {target}"),
+    }
+}
+
+fn literal_union(items: &[FieldType]) -> Option<Vec<String>> {
+    items
+        .iter()
+        .map(|item| match item {
+            FieldType::Literal(literal) => Some(literal.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// One candidate of a closed choice set (an enum value or a literal).
+struct Choice<'a> {
+    /// What a match coerces to.
+    value: &'a str,
+    /// Canonical spellings, matched exactly and case-insensitively.
+    names: Vec<&'a str>,
+    aliases: &'a [String],
+}
+
+/// Resolves `needle` against a closed choice set. Exact and case-insensitive spellings
+/// win silently (first declared wins, as before); the fuzzy rules record a
+/// [`Flag::FuzzyMatch`] and fail with an [`AmbiguousMatch`] rather than guess.
+fn match_choice<'a>(
+    needle: &str,
+    target: &str,
+    choices: &[Choice<'a>],
+    flags: &mut Vec<Flag>,
+) -> Result<Option<&'a str>> {
+    if let Some(choice) = choices
+        .iter()
+        .find(|choice| choice.names.contains(&needle))
+        .or_else(|| {
+            choices.iter().find(|choice| {
+                choice
+                    .names
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(needle))
+            })
+        })
+    {
+        return Ok(Some(choice.value));
+    }
+
+    let key = normalize_choice(needle);
+    let (hits, rule) = fuzzy_hits(needle, &key, choices);
+    let Some(rule) = rule else {
+        return Ok(None);
+    };
+
+    let candidates: Vec<&'a str> = hits.iter().map(|choice| choice.value).collect();
+    if let [matched] = candidates.as_slice() {
+        flags.push(Flag::FuzzyMatch {
+            matched: matched.to_string(),
+            rule,
+        });
+        return Ok(Some(*matched));
+    }
+    Err(AmbiguousMatch {
+        input: needle.to_string(),
+        target: target.to_string(),
+        candidates: candidates.iter().map(|value| value.to_string()).collect(),
+    }
+    .into())
+}
+
+/// The candidates of the first fuzzy rule that yields any, in declaration order.
+fn fuzzy_hits<'c, 'a>(
+    needle: &str,
+    key: &str,
+    choices: &'c [Choice<'a>],
+) -> (Vec<&'c Choice<'a>>, Option<MatchRule>) {
+    let by_alias: Vec<_> = choices
+        .iter()
+        .filter(|choice| {
+            choice
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(needle))
+        })
+        .collect();
+    if !by_alias.is_empty() {
+        return (by_alias, Some(MatchRule::Alias));
+    }
+    if key.is_empty() {
+        return (Vec::new(), None);
+    }
+
+    let by_normalized: Vec<_> = choices
+        .iter()
+        .filter(|choice| {
+            choice_spellings(choice)
+                .iter()
+                .any(|spelling| spelling == key)
+        })
+        .collect();
+    if !by_normalized.is_empty() {
+        return (by_normalized, Some(MatchRule::Normalized));
+    }
+
+    let mut best: Option<usize> = None;
+    let mut hits = Vec::new();
+    for choice in choices {
+        let Some(distance) = choice_spellings(choice)
+            .iter()
+            .filter_map(|spelling| bounded_edit_distance(key, spelling))
+            .min()
+        else {
+            continue;
+        };
+        match best {
+            Some(current) if distance > current => {}
+            Some(current) if distance == current => hits.push(choice),
+            _ => {
+                best = Some(distance);
+                hits = vec![choice];
+            }
+        }
+    }
+    (hits, best.map(MatchRule::EditDistance))
+}
+
+/// Every normalized spelling of a choice: its names, then its aliases.
+fn choice_spellings(choice: &Choice<'_>) -> Vec<String> {
+    choice
+        .names
+        .iter()
+        .copied()
+        .chain(choice.aliases.iter().map(String::as_str))
+        .map(normalize_choice)
+        .collect()
+}
+
+/// Lowercases and keeps only letters and digits: `"Not sure."` → `"notsure"`.
+fn normalize_choice(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between two normalized spellings, or `None` past the bound:
+/// one edit per four characters of the candidate, at most two, and none under four
+/// characters — short labels like `"pos"`/`"neg"` are too close to guess between.
+fn bounded_edit_distance(key: &str, candidate: &str) -> Option<usize> {
+    let key: Vec<char> = key.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    let bound = (candidate.len() / 4).min(2);
+    if bound == 0 || key.is_empty() || key.len().abs_diff(candidate.len()) > bound {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=candidate.len()).collect();
+    for (i, a) in key.iter().enumerate() {
+        let mut current = vec![i + 1; candidate.len() + 1];
+        for (j, b) in candidate.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
         }
+        if current.iter().min().is_some_and(|min| *min > bound) {
+            return None;
+        }
+        previous = current;
     }
-    bail!("`{needle}` is not a valid `{}` variant", enm.rendered_name)
+    let distance = previous[candidate.len()];
+    (distance <= bound).then_some(distance)
 }
 
 /// Coerces an already-parsed JSON value into the target type. Used for list items and
@@ -349,20 +589,21 @@ fn coerce_json_value(
                 Value::String(s) => s,
                 other => json_scalar_to_string(&other),
             };
-            coerce_enum(&text, name, schema)
+            coerce_enum(&text, name, schema, flags)
         }
         FieldType::Literal(expected) => {
-            let text = match &value {
-                Value::String(s) => s.clone(),
-                other => json_scalar_to_string(other),
-            };
-            if text == *expected {
-                Ok(Value::String(expected.clone()))
-            } else {
-                bail!("expected literal `{expected}`, got `{text}`")
-            }
+            let text = json_scalar_to_string(&value);
+            coerce_literals(&text, std::slice::from_ref(expected), field_type, flags)
         }
         FieldType::Union(items) => {
+            if let Some(literals) = literal_union(items) {
+                return coerce_literals(
+                    &json_scalar_to_string(&value),
+                    &literals,
+                    field_type,
+                    flags,
+                );
+            }
             let mut last_err = None;
             for item in items {
                 match coerce_json_value(value.clone(), item, schema, flags) {
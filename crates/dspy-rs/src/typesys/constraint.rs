@@ -15,6 +15,11 @@ use serde_json::Value;
 /// evaluation is measurable waste on the parse hot path.
 static CONSTRAINT_ENV: LazyLock<Environment<'static>> = LazyLock::new(Environment::new);
 
+/// Global functions a default minijinja `Environment` defines. Constraint,
+/// route-predicate, and transform expressions may call them, so free-variable
+/// checks never report them as unbound.
+pub(crate) const JINJA_GLOBALS: &[&str] = &["range", "dict", "namespace", "debug"];
+
 /// Compiled constraint expressions, keyed by the `&'static str` the signature macro
 /// emitted. `None` marks an expression that failed to compile (cached so a bad
 /// expression doesn't recompile on every parse either).
@@ -57,6 +62,22 @@ fn eval_expression(expression: &str, value: &Value) -> Result<bool, minijinja::E
     Ok(result.is_true())
 }
 
+/// Compiles `expression` and returns the variables it reads besides `this`
+/// and the environment globals. Load-time validation for route predicates,
+/// which would otherwise evaluate a misspelt name to `false` forever.
+pub(crate) fn unbound_variables(expression: &str) -> Result<Vec<String>, String> {
+    let expr = CONSTRAINT_ENV
+        .compile_expression(expression)
+        .map_err(|err| err.to_string())?;
+    let mut vars: Vec<String> = expr
+        .undeclared_variables(false)
+        .into_iter()
+        .filter(|var| var != "this" && !JINJA_GLOBALS.contains(&var.as_str()))
+        .collect();
+    vars.sort();
+    Ok(vars)
+}
+
 /// Evaluates a `'static` constraint expression against `value`, compiling it at
 /// most once per process.
 ///
@@ -12,7 +12,7 @@ pub mod constraint;
 pub mod render;
 pub mod schema;
 
-pub use coerce::{Coerced, Flag, coerce};
+pub use coerce::{AmbiguousMatch, Coerced, Flag, MatchRule, coerce};
 pub use constraint::{Constraint, ConstraintKind, ConstraintLevel, evaluate_expression};
 pub use render::{schema_block, type_name};
 pub use schema::{
@@ -87,6 +87,10 @@ pub struct EnumValueDef {
     pub name: String,
     pub rendered_name: String,
     pub docs: Option<String>,
+    /// Extra spellings coercion accepts for this value (`#[alias("neg")]` on
+    /// a variant, `alias ["neg"]` in `.dsrs`). Never rendered to the LM.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
 }
 
 /// A unit-enum definition reachable from a signature's output type.
@@ -376,6 +380,7 @@ impl SchemaBuilder {
                 name: variant.name.to_string(),
                 rendered_name: variant.effective_name().to_string(),
                 docs: doc_to_description(variant.doc),
+                aliases: variant_aliases(variant),
             })
             .collect();
 
@@ -414,6 +419,18 @@ fn rendered_name_for_shape(shape: &'static Shape) -> String {
     shape.type_identifier.to_string()
 }
 
+/// `#[alias("neg", "bad")]` on a `#[Schema]` enum variant lowers to one
+/// `#[facet(alias = "...")]` per spelling; collect them back in order.
+fn variant_aliases(variant: &facet::Variant) -> Vec<String> {
+    variant
+        .attributes
+        .iter()
+        .filter(|attr| attr.ns.is_none() && attr.key == "alias")
+        .filter_map(|attr| attr.get_as::<&'static str>())
+        .map(|alias| alias.to_string())
+        .collect()
+}
+
 fn doc_to_description(doc: &'static [&'static str]) -> Option<String> {
     if doc.is_empty() {
         return None;
@@ -2,7 +2,7 @@
 //! Predict routes through the IR interpreter.
 
 use dspy_rs::ir::SignatureDef;
-use dspy_rs::{ChatAdapter, Demo, Signature};
+use dspy_rs::{ChatAdapter, Demo, Message, Signature};
 use serde_json::Value;
 
 #[derive(Signature, Clone, Debug)]
@@ -30,6 +30,49 @@ struct EmptyInstructionSig {
     summary: String,
 }
 
+#[derive(Signature, Clone, Debug)]
+/// Suggest troubleshooting steps.
+struct PresentedSig {
+    #[input(hidden, desc = "Internal tenant id")]
+    tenant: String,
+
+    #[input]
+    #[prefix("Customer wrote:")]
+    #[render(jinja = "{{ this }} (plan: {{ input.tenant }})")]
+    message: String,
+
+    #[output]
+    #[prefix("Steps:")]
+    #[hint("answer as a bullet list")]
+    #[example("- restart the router")]
+    steps: String,
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Answer from the notes.
+struct OrderedSig {
+    #[input]
+    #[section("Question")]
+    question: String,
+
+    #[input]
+    #[order(-1)]
+    #[section("Background")]
+    notes: String,
+
+    #[input]
+    #[order(-1)]
+    #[section("Background")]
+    source: String,
+
+    #[output]
+    answer: String,
+
+    #[output]
+    #[order(-1)]
+    confidence: f64,
+}
+
 fn json_map<T: serde::Serialize>(value: &T) -> serde_json::Map<String, Value> {
     match serde_json::to_value(value).expect("serializable") {
         Value::Object(map) => map,
@@ -217,3 +260,116 @@ fn assistant_builder_orders_fields_and_ends_with_completed_marker() {
     assert!(answer_idx < confidence_idx);
     assert!(assistant.trim_end().ends_with("[[ ## completed ## ]]"));
 }
+
+#[test]
+fn hidden_inputs_never_reach_the_prompt_but_feed_templates() {
+    let system = system_prompt::<PresentedSig>(None);
+    assert!(!system.contains("tenant"), "{system}");
+    assert!(!system.contains("Internal tenant id"), "{system}");
+    assert!(system.contains("Suggest troubleshooting steps."));
+
+    let input = PresentedSigInput {
+        tenant: "acme-pro".to_string(),
+        message: "wifi is down".to_string(),
+    };
+    let user = ChatAdapter.format_input_def(SignatureDef::of::<PresentedSig>(), &json_map(&input));
+    assert!(!user.contains("[[ ## tenant ## ]]"), "{user}");
+    assert!(
+        user.contains("[[ ## message ## ]]\nCustomer wrote:\nwifi is down (plan: acme-pro)"),
+        "{user}"
+    );
+}
+
+#[test]
+fn prefixes_hints_and_examples_shape_the_structure_template() {
+    let system = system_prompt::<PresentedSig>(None);
+    assert!(
+        system.contains("[[ ## message ## ]]\nCustomer wrote:\nmessage\n"),
+        "{system}"
+    );
+    assert!(
+        system.contains(
+            "[[ ## steps ## ]]\nSteps:\nOutput field `steps` should be of type: string\n\
+             Formatting: answer as a bullet list\nExample: - restart the router\n"
+        ),
+        "{system}"
+    );
+
+    let def = SignatureDef::of::<PresentedSig>();
+    let output = PresentedSigOutput {
+        steps: "- reboot".to_string(),
+    };
+    let assistant = ChatAdapter.format_output_def(def, &json_map(&output));
+    assert!(
+        assistant.starts_with("[[ ## steps ## ]]\nSteps:\n- reboot"),
+        "{assistant}"
+    );
+
+    // An echoed prefix is dropped from the parsed value.
+    let (parsed, _) = ChatAdapter
+        .parse_output_def(
+            def,
+            SignatureDef::types_of::<PresentedSig>(),
+            &Message::assistant(assistant),
+        )
+        .expect("demo output parses back");
+    assert_eq!(parsed["steps"], "- reboot");
+}
+
+#[test]
+fn order_and_sections_arrange_the_prompt() {
+    let system = system_prompt::<OrderedSig>(None);
+    assert!(
+        system.contains(
+            "Your input fields are:\n--- Background ---\n1. `notes` (string)\n\
+             2. `source` (string)\n--- Question ---\n3. `question` (string)\n"
+        ),
+        "{system}"
+    );
+    assert!(
+        system.contains("Your output fields are:\n1. `confidence` (float)\n2. `answer` (string)"),
+        "{system}"
+    );
+    assert!(
+        system.contains(
+            "starting with the field `[[ ## confidence ## ]]`, then `[[ ## answer ## ]]`"
+        ),
+        "{system}"
+    );
+    // The structure template never carries headings the model could echo.
+    let structure = &system[find_required(&system, "All interactions")..];
+    assert!(!structure.contains("---"), "{structure}");
+
+    let input = OrderedSigInput {
+        question: "Why?".to_string(),
+        notes: "Because.".to_string(),
+        source: "wiki".to_string(),
+    };
+    let user = ChatAdapter.format_input_def(SignatureDef::of::<OrderedSig>(), &json_map(&input));
+    assert!(
+        user.starts_with(
+            "--- Background ---\n\n[[ ## notes ## ]]\nBecause.\n\n[[ ## source ## ]]\nwiki\n\n\
+             --- Question ---\n\n[[ ## question ## ]]\nWhy?\n\n"
+        ),
+        "{user}"
+    );
+
+    let def = SignatureDef::of::<OrderedSig>();
+    let output = OrderedSigOutput {
+        answer: "It is.".to_string(),
+        confidence: 0.5,
+    };
+    let assistant = ChatAdapter.format_output_def(def, &json_map(&output));
+    assert!(
+        find_required(&assistant, "[[ ## confidence ## ]]")
+            < find_required(&assistant, "[[ ## answer ## ]]")
+    );
+    let (parsed, _) = ChatAdapter
+        .parse_output_def(
+            def,
+            SignatureDef::types_of::<OrderedSig>(),
+            &Message::assistant(assistant),
+        )
+        .expect("reordered output parses back");
+    assert_eq!(parsed["answer"], "It is.");
+}
@@ -0,0 +1,199 @@
+//! Fuzzy enum/literal coercion: declared variant aliases, case/punctuation
+//! normalization, bounded edit distance, the `FuzzyMatch` flag naming the rule
+//! that fired, and the ambiguity error when two values are equally close.
+
+use dspy_rs::ir::{Program, SignatureDef};
+use dspy_rs::typesys::coerce;
+use dspy_rs::{ChatAdapter, FieldType, Flag, MatchRule, Message, ParseError, Schema, Signature};
+use serde_json::json;
+
+#[derive(Clone, Debug, PartialEq)]
+#[Schema]
+enum Sentiment {
+    #[alias("pos", "good")]
+    Positive,
+    #[alias("neg", "bad")]
+    Negative,
+    InProgress,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[Schema]
+enum Shelf {
+    Cart,
+    Card,
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Classify the review.
+struct ClassifyReview {
+    #[input]
+    review: String,
+
+    #[output]
+    sentiment: Sentiment,
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Pick a shelf.
+struct PickShelf {
+    #[input]
+    item: String,
+
+    #[output]
+    shelf: Shelf,
+}
+
+fn parse_sentiment(raw: &str) -> (serde_json::Value, Vec<Flag>) {
+    let response = Message::assistant(format!(
+        "[[ ## sentiment ## ]]\n{raw}\n\n[[ ## completed ## ]]"
+    ));
+    let (output, meta) = ChatAdapter
+        .parse_output_def(
+            SignatureDef::of::<ClassifyReview>(),
+            SignatureDef::types_of::<ClassifyReview>(),
+            &response,
+        )
+        .unwrap_or_else(|err| panic!("`{raw}` should coerce: {err}"));
+    (output["sentiment"].clone(), meta["sentiment"].flags.clone())
+}
+
+#[test]
+fn variant_aliases_reach_the_type_table() {
+    let types = SignatureDef::types_of::<ClassifyReview>();
+    let (_, sentiment) = types.enums.iter().next().expect("one enum");
+    assert_eq!(sentiment.values[0].aliases, ["pos", "good"]);
+    assert_eq!(sentiment.values[1].aliases, ["neg", "bad"]);
+    assert!(sentiment.values[2].aliases.is_empty());
+}
+
+#[test]
+fn exact_and_case_insensitive_spellings_match_silently() {
+    assert_eq!(parse_sentiment("Negative"), (json!("Negative"), vec![]));
+    assert_eq!(parse_sentiment("negative"), (json!("Negative"), vec![]));
+}
+
+#[test]
+fn each_fuzzy_rule_records_the_rule_that_fired() {
+    let fuzzy = |rule| {
+        vec![Flag::FuzzyMatch {
+            matched: "Negative".to_string(),
+            rule,
+        }]
+    };
+    assert_eq!(
+        parse_sentiment("NEG"),
+        (json!("Negative"), fuzzy(MatchRule::Alias))
+    );
+    assert_eq!(
+        parse_sentiment("Negative."),
+        (json!("Negative"), fuzzy(MatchRule::Normalized))
+    );
+    assert_eq!(
+        parse_sentiment("negatve"),
+        (json!("Negative"), fuzzy(MatchRule::EditDistance(1)))
+    );
+    assert_eq!(parse_sentiment("in-progress").0, json!("InProgress"));
+
+    let output: ClassifyReviewOutput = serde_json::from_value(json!({
+        "sentiment": parse_sentiment("bad").0,
+    }))
+    .unwrap();
+    assert_eq!(output.sentiment, Sentiment::Negative);
+}
+
+#[test]
+fn unrelated_text_still_fails() {
+    let types = SignatureDef::types_of::<ClassifyReview>();
+    let ty = &SignatureDef::of::<ClassifyReview>().outputs[0].ty;
+    assert!(coerce("neutral", ty, types).is_err());
+    // Short labels get no edit-distance slack.
+    assert!(coerce("ba", ty, types).is_err());
+}
+
+#[test]
+fn equally_close_values_are_an_ambiguity_error() {
+    let response = Message::assistant("[[ ## shelf ## ]]\nCarx\n\n[[ ## completed ## ]]");
+    let err = ChatAdapter
+        .parse_output_def(
+            SignatureDef::of::<PickShelf>(),
+            SignatureDef::types_of::<PickShelf>(),
+            &response,
+        )
+        .expect_err("`Carx` is one edit from both `Cart` and `Card`");
+    let ParseError::Multiple { errors, .. } = err else {
+        panic!("expected the multi-field parse error");
+    };
+    let [ParseError::CoercionFailed { source, .. }] = errors.as_slice() else {
+        panic!("expected one coercion failure, got {errors:?}");
+    };
+    let ambiguous = source.ambiguous_match().expect("ambiguity surfaces typed");
+    assert_eq!(ambiguous.input, "Carx");
+    assert_eq!(ambiguous.candidates, ["Cart", "Card"]);
+}
+
+#[test]
+fn literal_unions_match_as_one_choice_set() {
+    let ty = FieldType::Union(vec![
+        FieldType::Literal("gold".to_string()),
+        FieldType::Literal("basic".to_string()),
+    ]);
+    let table = Default::default();
+    let coerced = coerce("Gold!", &ty, &table).unwrap();
+    assert_eq!(coerced.value, json!("gold"));
+    assert_eq!(
+        coerced.flags,
+        vec![Flag::FuzzyMatch {
+            matched: "gold".to_string(),
+            rule: MatchRule::Normalized,
+        }]
+    );
+    assert!(coerce("silver", &ty, &table).is_err());
+}
+
+#[test]
+fn dsrs_enum_aliases_round_trip_and_drive_coercion() {
+    let src = r#"dsrs 1
+program triage
+
+enum Severity {
+  Low alias ["trivial" "minor"]
+  High alias "urgent" alias ["critical"] "Needs a human today."
+}
+
+sig Main {
+  in ticket: string
+  out severity: Severity
+}
+
+sig Classify {
+  in ticket: string
+  out severity: Severity
+}
+
+main: Main = seq {
+  classifier = predict Classify (ticket = $.ticket)
+  out { severity = classifier.severity }
+}
+"#;
+    let program = Program::from_dsrs(src).expect("aliases parse");
+    let text = program.to_dsrs();
+    assert!(text.contains(r#"Low alias ["trivial" "minor"]"#), "{text}");
+    assert!(
+        text.contains(r#"High alias "urgent" alias ["critical"] "Needs a human today.""#),
+        "{text}"
+    );
+    let reparsed = Program::from_dsrs(&text).unwrap();
+    assert_eq!(reparsed.meta.program_hash, program.meta.program_hash);
+
+    let severity = FieldType::Enum("Severity".to_string());
+    let coerced = coerce("Critical!", &severity, &program.types).unwrap();
+    assert_eq!(coerced.value, json!("High"));
+    assert_eq!(
+        coerced.flags,
+        vec![Flag::FuzzyMatch {
+            matched: "High".to_string(),
+            rule: MatchRule::Normalized,
+        }]
+    );
+}
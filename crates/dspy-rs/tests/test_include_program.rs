@@ -21,3 +21,50 @@ fn embedded_program_agrees_with_the_direct_loader() {
     );
     assert_eq!(embedded.to_dsrs(), loaded.to_dsrs());
 }
+
+// Typed surface: `Input`/`Output`, class structs, and enums generated from the
+// artifact's declarations, run through the typed `run` wrapper.
+dspy_rs::include_program!("tests/fixtures/triage.dsrs");
+
+#[tokio::test]
+async fn typed_run_round_trips_through_the_interpreter() {
+    let env = dspy_rs::ir::RuntimeEnv::new().bind_host_hole(
+        "triager",
+        |input: dspy_rs::trace::JsonMap| async move {
+            // Optional class fields left `None` are omitted, not sent as null.
+            assert!(input["ticket"].get("body").is_none());
+            assert_eq!(input["history"]["refunds"], 2);
+            Ok(serde_json::json!({
+                "priority": "High",
+                "score": 0.75,
+                "route": "billing",
+            }))
+        },
+    );
+    let interp = dspy_rs::ir::Interpreter::load(triage::program().clone(), env)
+        .await
+        .expect("extern hole bound");
+
+    let output = triage::run(
+        &interp,
+        triage::Input {
+            ticket: triage::Ticket {
+                subject: "Charged twice".to_string(),
+                body: None,
+                tags: vec!["billing".to_string()],
+            },
+            history: [("refunds".to_string(), 2)].into(),
+        },
+    )
+    .await
+    .expect("typed run succeeds");
+
+    assert_eq!(
+        output,
+        triage::Output {
+            priority: triage::Priority::High,
+            score: 0.75,
+            route: "billing".to_string(),
+        }
+    );
+}
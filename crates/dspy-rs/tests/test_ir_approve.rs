@@ -0,0 +1,382 @@
+//! `Approve` nodes: `.dsrs` round trip, pass-through validation, and the
+//! suspend/resume surface — a refund agent that needs a human sign-off
+//! before issuing, with approve/reject/edit decisions flowing downstream,
+//! cancelled spans on a dropped suspension, and replay without asking — and
+//! the approver a `run_with` run asks instead.
+
+use std::sync::{Arc, Mutex};
+
+use dspy_rs::ir::{
+    ApprovalDecision, Budget, Interpreter, Program, RunError, RunOptions, RunTurn, RuntimeEnv,
+};
+use dspy_rs::trace::{JsonMap, SpanErrorKind};
+use dspy_rs::{ReplayMode, capture, replay};
+use serde_json::json;
+
+const REFUND: &str = r#"dsrs 1
+program refunds
+
+sig Main {
+  in order: string
+  out status: string
+}
+
+sig Plan {
+  in order: string
+  out amount: float
+  out note: string
+}
+
+sig Refund {
+  in amount: float
+  in note: string
+  out amount: float
+}
+
+sig Issue {
+  in amount: float
+  out status: string
+}
+
+sig Decline {
+  in reason: string?
+  out status: string
+}
+
+main: Main = seq {
+  planner = hole Plan (order = $.order) caps [] extern "00000000000000aa"
+  review = approve Refund (amount = planner.amount, note = planner.note)
+  outcome = route review.decision {
+    rejected -> declined = transform Decline (reason = review.reason) { status = "'declined: ' ~ reason" }
+    else -> issuer = hole Issue (amount = review.amount) caps [] extern "00000000000000bb"
+  }
+  out { status = outcome.status }
+}
+"#;
+
+fn obj(pairs: &[(&str, serde_json::Value)]) -> JsonMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+/// Plans a 42.5 refund and records every amount actually issued.
+async fn refund_agent(issued: Arc<Mutex<Vec<f64>>>) -> Interpreter {
+    let env = RuntimeEnv::new()
+        .bind_host_hole("planner", |_input: JsonMap| async move {
+            Ok(json!({ "amount": 42.5, "note": "damaged in transit" }))
+        })
+        .bind_host_hole("issuer", move |input: JsonMap| {
+            let issued = Arc::clone(&issued);
+            async move {
+                let amount = input["amount"].as_f64().unwrap_or_default();
+                issued.lock().unwrap().push(amount);
+                Ok(json!({ "status": format!("refunded {amount}") }))
+            }
+        });
+    Interpreter::load(Program::from_dsrs(REFUND).unwrap(), env)
+        .await
+        .unwrap()
+}
+
+fn order() -> JsonMap {
+    obj(&[("order", json!("A-17"))])
+}
+
+/// Runs to the sign-off, answers with `decision`, and runs to completion.
+async fn sign_off(interp: &Interpreter, decision: ApprovalDecision) -> Result<JsonMap, RunError> {
+    let RunTurn::Suspended(pending) = interp
+        .run_suspendable(order(), None, Budget::unlimited())
+        .await?
+    else {
+        panic!("the run must stop for sign-off");
+    };
+    match interp.resume_run(pending, decision).await? {
+        RunTurn::Complete(run) => Ok(run.output),
+        RunTurn::Suspended(_) => panic!("one approval only"),
+    }
+}
+
+#[test]
+fn approve_round_trips_through_text() {
+    let program = Program::from_dsrs(REFUND).unwrap();
+    let printed = program.to_dsrs();
+    assert!(
+        printed.contains("review = approve Refund (amount = planner.amount, note = planner.note)"),
+        "{printed}"
+    );
+    let reparsed = Program::from_dsrs(&printed).unwrap();
+    assert_eq!(reparsed.to_dsrs(), printed);
+    assert_eq!(reparsed.meta.program_hash, program.meta.program_hash);
+}
+
+#[test]
+fn outputs_must_pass_through_inputs() {
+    let err = |src: &str| Program::from_dsrs(src).unwrap_err().to_string();
+
+    let invented = REFUND.replace(
+        "  out amount: float\n}\n\nsig Issue",
+        "  out total: float\n}\n\nsig Issue",
+    );
+    assert!(
+        err(&invented).contains("`total` must pass through"),
+        "{}",
+        err(&invented)
+    );
+
+    let reserved = REFUND
+        .replace(
+            "  in note: string\n  out amount: float\n}",
+            "  in reason: string\n  out amount: float\n  out reason: string\n}",
+        )
+        .replace("note = planner.note)", "reason = planner.note)");
+    assert!(
+        err(&reserved).contains("shadows the implicit `reason`"),
+        "{}",
+        err(&reserved)
+    );
+}
+
+#[tokio::test]
+async fn approved_refunds_are_issued_after_sign_off() {
+    let issued = Arc::new(Mutex::new(Vec::new()));
+    let interp = refund_agent(issued.clone()).await;
+
+    let (status, trace) = capture(|| async {
+        let RunTurn::Suspended(pending) = interp
+            .run_suspendable(order(), None, Budget::unlimited())
+            .await
+            .unwrap()
+        else {
+            panic!("the run must stop for sign-off");
+        };
+        assert_eq!(pending.at(), "review");
+        assert_eq!(pending.payload()["amount"], json!(42.5));
+        assert_eq!(pending.payload()["note"], json!("damaged in transit"));
+        // Nothing downstream of the approval has run yet.
+        assert!(issued.lock().unwrap().is_empty());
+
+        match interp
+            .resume_run(pending, ApprovalDecision::Approve)
+            .await
+            .unwrap()
+        {
+            RunTurn::Complete(run) => run.output["status"].clone(),
+            RunTurn::Suspended(_) => panic!("one approval only"),
+        }
+    })
+    .await;
+    assert_eq!(status, json!("refunded 42.5"));
+    assert_eq!(*issued.lock().unwrap(), [42.5]);
+
+    let span = trace.for_component("review").next().unwrap();
+    assert_eq!(trace.model(span).model, "human:approval");
+    assert!(span.error.is_none());
+    assert_eq!(span.output.as_ref().unwrap()["decision"], json!("approved"));
+}
+
+#[tokio::test]
+async fn rejections_and_edits_flow_downstream() {
+    let issued = Arc::new(Mutex::new(Vec::new()));
+    let interp = refund_agent(issued.clone()).await;
+    let decide = |decision| sign_off(&interp, decision);
+
+    let rejected = decide(ApprovalDecision::Reject {
+        reason: "over the limit".to_string(),
+    })
+    .await
+    .unwrap();
+    assert_eq!(rejected["status"], json!("declined: over the limit"));
+    assert!(
+        issued.lock().unwrap().is_empty(),
+        "a rejected refund never issues"
+    );
+
+    let edited = decide(ApprovalDecision::Edit(obj(&[("amount", json!(10.0))])))
+        .await
+        .unwrap();
+    assert_eq!(edited["status"], json!("refunded 10"));
+    assert_eq!(*issued.lock().unwrap(), [10.0]);
+
+    let err = decide(ApprovalDecision::Edit(obj(&[("amount", json!("ten"))])))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, RunError::Input { ref at, .. } if &**at == "review"),
+        "{err}"
+    );
+    let err = decide(ApprovalDecision::Edit(obj(&[("note", json!("x"))])))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("`note`"), "{err}");
+}
+
+#[tokio::test]
+async fn plain_runs_refuse_approve_nodes() {
+    let interp = refund_agent(Arc::default()).await;
+    let err = interp
+        .run(order(), None, Budget::unlimited())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("run_suspendable"), "{err}");
+}
+
+#[tokio::test]
+async fn run_options_answer_approvals_through_an_approver() {
+    let issued = Arc::new(Mutex::new(Vec::new()));
+    let interp = refund_agent(issued.clone()).await;
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&asked);
+    let options = RunOptions::new().with_approver(move |at: &str, payload: &JsonMap| {
+        seen.lock()
+            .unwrap()
+            .push((at.to_string(), payload["amount"].clone()));
+        async { ApprovalDecision::Edit(obj(&[("amount", json!(20.0))])) }
+    });
+    let run = interp
+        .run_with(order(), None, Budget::unlimited(), options)
+        .await
+        .unwrap();
+    assert_eq!(run.output["status"], json!("refunded 20"));
+    assert_eq!(
+        *asked.lock().unwrap(),
+        [("review".to_string(), json!(42.5))]
+    );
+    assert_eq!(*issued.lock().unwrap(), [20.0]);
+
+    // Without an approver the run is refused, with the partial result.
+    let partial = interp
+        .run_with(order(), None, Budget::unlimited(), RunOptions::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(partial.error, RunError::Input { ref at, .. } if &**at == "review"));
+    assert_eq!(
+        partial.leaf_output("planner").unwrap()["amount"],
+        json!(42.5)
+    );
+}
+
+#[tokio::test]
+async fn dropping_a_suspension_cancels_the_run() {
+    let issued = Arc::new(Mutex::new(Vec::new()));
+    let interp = refund_agent(issued.clone()).await;
+
+    let ((), trace) = capture(|| async {
+        let turn = interp
+            .run_suspendable(order(), None, Budget::unlimited())
+            .await
+            .unwrap();
+        assert!(matches!(turn, RunTurn::Suspended(_)));
+        drop(turn);
+    })
+    .await;
+    assert!(issued.lock().unwrap().is_empty());
+    let span = trace.for_component("review").next().unwrap();
+    assert_eq!(
+        span.error.as_ref().map(|e| e.kind),
+        Some(SpanErrorKind::Cancelled)
+    );
+}
+
+#[tokio::test]
+async fn replay_serves_the_recorded_decision() {
+    let issued = Arc::new(Mutex::new(Vec::new()));
+    let interp = refund_agent(issued.clone()).await;
+
+    let (baseline, trace) = capture(|| async {
+        let RunTurn::Suspended(pending) = interp
+            .run_suspendable(order(), None, Budget::unlimited())
+            .await
+            .unwrap()
+        else {
+            panic!("the run must stop for sign-off");
+        };
+        let edit = ApprovalDecision::Edit(obj(&[("amount", json!(5.0))]));
+        match interp.resume_run(pending, edit).await.unwrap() {
+            RunTurn::Complete(run) => run.output,
+            RunTurn::Suspended(_) => panic!("one approval only"),
+        }
+    })
+    .await;
+
+    // A replayed run never stops: the recorded decision is served.
+    let (replayed, report) = replay(&trace, ReplayMode::Strict, || {
+        interp.run_suspendable(order(), None, Budget::unlimited())
+    })
+    .await;
+    let RunTurn::Complete(run) = replayed.unwrap() else {
+        panic!("replay must not suspend");
+    };
+    assert_eq!(run.output, baseline);
+    assert_eq!(report.served, 3, "planner, review, and issuer all served");
+    assert_eq!(*issued.lock().unwrap(), [5.0]);
+}
+
+#[tokio::test]
+async fn replay_matches_payloads_regardless_of_key_order() {
+    let source = r#"dsrs 1
+program tagging
+
+sig Main {
+  in tags: map<string>
+  out tags: map<string>
+}
+
+sig Check {
+  in tags: map<string>
+  out tags: map<string>
+}
+
+main: Main = seq {
+  review = approve Check (tags = $.tags)
+  out { tags = review.tags }
+}
+"#;
+    let interp = Interpreter::load(Program::from_dsrs(source).unwrap(), RuntimeEnv::new())
+        .await
+        .unwrap();
+    let tags = |pairs: &[(&str, serde_json::Value)]| obj(&[("tags", json!(obj(pairs)))]);
+
+    let (_, trace) = capture(|| async {
+        let RunTurn::Suspended(pending) = interp
+            .run_suspendable(
+                tags(&[("a", json!("1")), ("b", json!("2"))]),
+                None,
+                Budget::unlimited(),
+            )
+            .await
+            .unwrap()
+        else {
+            panic!("the run must stop for sign-off");
+        };
+        interp
+            .resume_run(pending, ApprovalDecision::Approve)
+            .await
+            .unwrap()
+    })
+    .await;
+
+    // The same payload with its nested keys inserted the other way round.
+    let (replayed, report) = replay(&trace, ReplayMode::Strict, || {
+        interp.run_suspendable(
+            tags(&[("b", json!("2")), ("a", json!("1"))]),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    assert!(matches!(replayed.unwrap(), RunTurn::Complete(_)));
+    assert_eq!(report.served, 1);
+
+    // A different payload is refused, recorded like a refused LM call.
+    let ((refused, _), refused_trace) = capture(|| {
+        replay(&trace, ReplayMode::Strict, || {
+            interp.run_suspendable(tags(&[("a", json!("3"))]), None, Budget::unlimited())
+        })
+    })
+    .await;
+    assert!(matches!(refused, Err(RunError::Replay { .. })));
+    let span = refused_trace.for_component("review").next().unwrap();
+    assert_eq!(span.error.as_ref().map(|e| e.kind), Some(SpanErrorKind::Lm));
+}
@@ -0,0 +1,185 @@
+//! Cancellable runs: `Interpreter::run_with` stops when its token fires and
+//! returns a `PartialRun` — completed leaf outputs, spend, and the trace so
+//! far — which `resume_from` can finish later. The token combines with the
+//! other run options.
+
+use std::sync::{Arc, Mutex};
+
+use dspy_rs::ir::{
+    Budget, CancellationToken, Interpreter, Program, RunError, RunEvent, RunOptions, RuntimeEnv,
+};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const SLOW: &str = r#"dsrs 1
+program slow
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in text: string
+  out reply: string
+}
+
+sig Summarize {
+  in text: string
+  out summary: string
+}
+
+sig Reply {
+  in text: string
+  out reply: string
+}
+
+main: Main = seq {
+  summarizer = predict Summarize (text = $.text)
+  polisher = hole Reply (text = summarizer.summary) caps [] extern "00000000deadbeef"
+  out { reply = polisher.reply }
+}
+"#;
+
+fn input() -> JsonMap {
+    [("text".to_string(), json!("where is my parcel"))]
+        .into_iter()
+        .collect()
+}
+
+/// Loads SLOW as a dry run whose `polisher` hole either answers at once or,
+/// given a token, fires it and then never returns — the gateway timing out
+/// mid-call.
+async fn interpreter(cancel: Option<CancellationToken>) -> Interpreter {
+    let env =
+        RuntimeEnv::new()
+            .with_dry_run(5)
+            .bind_host_hole("polisher", move |input: JsonMap| {
+                let cancel = cancel.clone();
+                async move {
+                    if let Some(cancel) = cancel {
+                        cancel.cancel();
+                        std::future::pending::<()>().await;
+                    }
+                    Ok(json!({ "reply": input["text"] }))
+                }
+            });
+    Interpreter::load(Program::from_dsrs(SLOW).unwrap(), env)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn uncancelled_runs_finish() {
+    let run = interpreter(None)
+        .await
+        .run_with(
+            input(),
+            None,
+            Budget::unlimited(),
+            RunOptions::new().with_cancel(CancellationToken::new()),
+        )
+        .await
+        .unwrap();
+    assert!(run.output["reply"].is_string());
+    assert_eq!(run.leaves.len(), 1);
+}
+
+#[tokio::test]
+async fn cancelling_mid_call_returns_what_completed() {
+    let cancel = CancellationToken::new();
+    let partial = interpreter(Some(cancel.clone()))
+        .await
+        .run_with(
+            input(),
+            None,
+            Budget::unlimited(),
+            RunOptions::new().with_cancel(cancel),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(partial.error, RunError::Cancelled));
+    let summary = partial.leaf_output("summarizer").cloned().unwrap();
+    assert!(summary["summary"].is_string());
+    assert!(partial.leaf_output("polisher").is_none());
+    assert_eq!(partial.checkpoint.leaves.len(), 1);
+    assert_eq!(partial.checkpoint.meter.lm_calls, 1);
+    assert_eq!(
+        partial.to_string(),
+        "run did not finish (completed leaves: 1)"
+    );
+
+    // No capture scope here: the run records its own trace.
+    let trace = partial.checkpoint.trace.as_ref().expect("the trace so far");
+    assert_eq!(trace.for_component("summarizer").count(), 1);
+
+    // The partial result resumes: the summarizer is served, not re-run.
+    let run = interpreter(None)
+        .await
+        .resume_from(
+            partial.checkpoint,
+            None,
+            Budget::unlimited(),
+            RunOptions::new(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(run.output["reply"], summary["summary"]);
+    assert!(run.leaves.is_empty());
+}
+
+#[tokio::test]
+async fn a_cancelled_token_stops_before_the_first_node() {
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let partial = interpreter(None)
+        .await
+        .run_with(
+            input(),
+            None,
+            Budget::unlimited(),
+            RunOptions::new().with_cancel(cancel),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(partial.error, RunError::Cancelled));
+    assert!(partial.checkpoint.leaves.is_empty());
+    assert_eq!(partial.checkpoint.meter.lm_calls, 0);
+}
+
+#[tokio::test]
+async fn cancelled_runs_can_be_observed_and_checkpointed() {
+    let cancel = CancellationToken::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let checkpoints = Arc::new(Mutex::new(Vec::new()));
+    let (sink, kept) = (Arc::clone(&events), Arc::clone(&checkpoints));
+    let options = RunOptions::new()
+        .with_cancel(cancel.clone())
+        .with_observer(move |event: &RunEvent| sink.lock().unwrap().push(event.clone()))
+        .with_checkpoints(move |checkpoint| kept.lock().unwrap().push(checkpoint.clone()));
+    let partial = interpreter(Some(cancel))
+        .await
+        .run_with(input(), None, Budget::unlimited(), options)
+        .await
+        .unwrap_err();
+    assert!(matches!(partial.error, RunError::Cancelled));
+
+    // The observer saw the summarizer finish and the polisher start, which
+    // never finished.
+    let events = events.lock().unwrap();
+    let finished = |name: &str| {
+        events
+            .iter()
+            .any(|e| matches!(e, RunEvent::NodeFinish { node, .. } if node == name))
+    };
+    assert!(finished("summarizer"));
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, RunEvent::NodeStart { node, .. } if node == "polisher"))
+    );
+    assert!(!finished("polisher"));
+
+    // The one checkpoint emitted is the partial result's leaves.
+    let checkpoints = checkpoints.lock().unwrap();
+    assert_eq!(checkpoints.len(), 1);
+    assert_eq!(checkpoints[0].leaves, partial.checkpoint.leaves);
+}
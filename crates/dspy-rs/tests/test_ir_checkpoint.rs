@@ -0,0 +1,376 @@
+//! Run checkpoints: a checkpoint after every completed leaf, serde round
+//! trip, and resumption in a fresh interpreter that serves completed leaves
+//! (including loop iterations and their carry) instead of re-running them.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use dspy_rs::capture;
+use dspy_rs::ir::{
+    Budget, Interpreter, Overlay, Program, RunCheckpoint, RunError, RunEvent, RunOptions,
+    RuntimeEnv,
+};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const RESEARCH: &str = r#"dsrs 1
+program research
+
+sig Main {
+  in draft: string
+  out report: string
+}
+
+sig Step {
+  in draft: string
+  out draft: string
+  out keep_going: bool
+}
+
+sig Write {
+  in draft: string
+  out report: string
+}
+
+main: Main = seq {
+  research = loop (max_iters 8) {
+    step = hole Step (draft = ^draft) caps [] extern "00000000000000c1"
+    while step.keep_going
+    carry { draft = step.draft }
+    join { draft = step.draft }
+  }
+  writer = hole Write (draft = research.draft) caps [] extern "00000000000000c2"
+  out { report = writer.report }
+}
+"#;
+
+/// Host-side call counters, plus the step call that "crashes" the process.
+#[derive(Default)]
+struct Calls {
+    step: AtomicU32,
+    writer: AtomicU32,
+    crash_at: Option<u32>,
+}
+
+/// Each step appends a `+` until the draft has four; the writer wraps it.
+async fn researcher(calls: Arc<Calls>) -> Interpreter {
+    let steps = Arc::clone(&calls);
+    let env = RuntimeEnv::new()
+        .bind_host_hole("step", move |input: JsonMap| {
+            let calls = Arc::clone(&steps);
+            async move {
+                let n = calls.step.fetch_add(1, Ordering::SeqCst) + 1;
+                if calls.crash_at == Some(n) {
+                    return Err("killed by a deploy".to_string());
+                }
+                let draft = format!("{}+", input["draft"].as_str().unwrap_or_default());
+                let keep_going = draft.len() < "topic++++".len();
+                Ok(json!({ "draft": draft, "keep_going": keep_going }))
+            }
+        })
+        .bind_host_hole("writer", move |input: JsonMap| {
+            let calls = Arc::clone(&calls);
+            async move {
+                calls.writer.fetch_add(1, Ordering::SeqCst);
+                Ok(json!({ "report": format!("report on {}", input["draft"].as_str().unwrap()) }))
+            }
+        });
+    Interpreter::load(Program::from_dsrs(RESEARCH).unwrap(), env)
+        .await
+        .unwrap()
+}
+
+fn topic() -> JsonMap {
+    JsonMap::from_iter([("draft".to_string(), json!("topic"))])
+}
+
+/// Run options keeping every checkpoint the run hands out.
+fn keep(into: &Arc<Mutex<Vec<RunCheckpoint>>>) -> RunOptions {
+    let into = Arc::clone(into);
+    RunOptions::new()
+        .with_checkpoints(move |checkpoint| into.lock().unwrap().push(checkpoint.clone()))
+}
+
+/// Runs until the third step call fails and returns the last checkpoint,
+/// round-tripped through JSON as a persisted one would be.
+async fn crashed_run() -> RunCheckpoint {
+    let calls = Arc::new(Calls {
+        crash_at: Some(3),
+        ..Calls::default()
+    });
+    let interp = researcher(calls).await;
+    let checkpoints = Arc::default();
+    let err = interp
+        .run_with(topic(), None, Budget::unlimited(), keep(&checkpoints))
+        .await
+        .unwrap_err();
+    assert!(matches!(err.error, RunError::Hole { .. }), "{err}");
+    let last = checkpoints.lock().unwrap().last().cloned().unwrap();
+    serde_json::from_str(&serde_json::to_string(&last).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn every_completed_leaf_emits_a_checkpoint() {
+    let interp = researcher(Arc::default()).await;
+    let checkpoints = Arc::default();
+    let (run, _trace) =
+        capture(|| interp.run_with(topic(), None, Budget::unlimited(), keep(&checkpoints))).await;
+    assert_eq!(run.unwrap().output["report"], json!("report on topic++++"));
+
+    let checkpoints = checkpoints.lock().unwrap();
+    let names: Vec<_> = checkpoints
+        .last()
+        .unwrap()
+        .leaves
+        .iter()
+        .map(|leaf| leaf.name.as_str())
+        .collect();
+    assert_eq!(names, ["step", "step", "step", "step", "writer"]);
+    assert_eq!(checkpoints.len(), 5, "one checkpoint per completed leaf");
+    for (i, checkpoint) in checkpoints.iter().enumerate() {
+        assert_eq!(checkpoint.leaves.len(), i + 1);
+        assert_eq!(checkpoint.input, topic());
+        let trace = checkpoint
+            .trace
+            .as_ref()
+            .expect("captured runs snapshot their trace");
+        assert_eq!(trace.spans.len(), i + 1);
+    }
+
+    let last = checkpoints.last().unwrap();
+    let json = serde_json::to_string(last).unwrap();
+    let back: RunCheckpoint = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+}
+
+#[tokio::test]
+async fn resuming_serves_completed_leaves() {
+    let checkpoint = crashed_run().await;
+    assert_eq!(
+        checkpoint.leaves.len(),
+        2,
+        "two steps completed before the crash"
+    );
+
+    // A fresh process: new interpreter, new host bindings.
+    let calls = Arc::new(Calls::default());
+    let interp = researcher(Arc::clone(&calls)).await;
+    let resumed_checkpoints = Arc::default();
+    let (run, trace) = capture(|| {
+        interp.resume_from(
+            checkpoint,
+            None,
+            Budget::unlimited(),
+            keep(&resumed_checkpoints),
+        )
+    })
+    .await;
+    let run = run.unwrap();
+
+    // The carry picked up where the crashed run left off.
+    assert_eq!(run.output["report"], json!("report on topic++++"));
+    assert_eq!(
+        calls.step.load(Ordering::SeqCst),
+        2,
+        "only the last two steps ran"
+    );
+    assert_eq!(calls.writer.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        trace.for_component("step").count(),
+        2,
+        "served leaves record no span"
+    );
+
+    // Later checkpoints carry the served leaves forward.
+    let resumed = resumed_checkpoints.lock().unwrap();
+    assert_eq!(resumed.len(), 3);
+    assert_eq!(resumed.last().unwrap().leaves.len(), 5);
+}
+
+#[tokio::test]
+async fn resumed_checkpoints_keep_the_spans_of_every_process() {
+    let calls = Arc::new(Calls {
+        crash_at: Some(3),
+        ..Calls::default()
+    });
+    let interp = researcher(calls).await;
+    let checkpoints = Arc::default();
+    let _ =
+        capture(|| interp.run_with(topic(), None, Budget::unlimited(), keep(&checkpoints))).await;
+    let crashed = checkpoints.lock().unwrap().last().cloned().unwrap();
+    assert_eq!(crashed.trace.as_ref().unwrap().spans.len(), 2);
+
+    let interp = researcher(Arc::default()).await;
+    let resumed_checkpoints = Arc::default();
+    let (run, _) = capture(|| {
+        interp.resume_from(
+            crashed,
+            None,
+            Budget::unlimited(),
+            keep(&resumed_checkpoints),
+        )
+    })
+    .await;
+    run.unwrap();
+
+    let last = resumed_checkpoints.lock().unwrap().last().cloned().unwrap();
+    let trace = last.trace.expect("captured runs snapshot their trace");
+    let steps: Vec<u32> = trace.for_component("step").map(|span| span.seq).collect();
+    assert_eq!(steps, [0, 1, 2, 3], "two steps from each process");
+    assert_eq!(trace.for_component("writer").count(), 1);
+    let ids: Vec<u32> = trace.spans.iter().map(|span| span.id.0).collect();
+    assert_eq!(ids, [0, 1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn observed_resumes_report_served_leaves() {
+    let checkpoint = crashed_run().await;
+    let interp = researcher(Arc::default()).await;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    interp
+        .resume_from(
+            checkpoint,
+            None,
+            Budget::unlimited(),
+            RunOptions::new()
+                .with_observer(move |event: &RunEvent| sink.lock().unwrap().push(event.clone())),
+        )
+        .await
+        .unwrap();
+
+    let events = events.lock().unwrap();
+    let served = events
+        .iter()
+        .filter(
+            |e| matches!(e, RunEvent::CheckpointServed { node, kind: "hole" } if node == "step"),
+        )
+        .count();
+    let started = events
+        .iter()
+        .filter(|e| matches!(e, RunEvent::NodeStart { kind: "hole", .. }))
+        .count();
+    assert_eq!(served, 2, "{events:?}");
+    assert_eq!(started, 3, "two live steps and the writer");
+}
+
+#[tokio::test]
+async fn resuming_a_finished_run_calls_nothing() {
+    let interp = researcher(Arc::default()).await;
+    let checkpoints = Arc::default();
+    let first = interp
+        .run_with(topic(), None, Budget::unlimited(), keep(&checkpoints))
+        .await
+        .unwrap();
+    let last = checkpoints.lock().unwrap().last().cloned().unwrap();
+
+    let calls = Arc::new(Calls::default());
+    let interp = researcher(Arc::clone(&calls)).await;
+    let again = interp
+        .resume_from(last, None, Budget::unlimited(), RunOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(again.output, first.output);
+    assert_eq!(calls.step.load(Ordering::SeqCst), 0);
+    assert_eq!(calls.writer.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn resuming_refuses_a_different_program_or_overlay() {
+    let checkpoint = crashed_run().await;
+
+    let edited = RESEARCH.replace("max_iters 8", "max_iters 9");
+    let env = RuntimeEnv::new()
+        .bind_host_hole("step", |_input: JsonMap| async move { Ok(json!({})) })
+        .bind_host_hole("writer", |_input: JsonMap| async move { Ok(json!({})) });
+    let other = Interpreter::load(Program::from_dsrs(&edited).unwrap(), env)
+        .await
+        .unwrap();
+    let err = other
+        .resume_from(
+            checkpoint.clone(),
+            None,
+            Budget::unlimited(),
+            RunOptions::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err.error, RunError::Checkpoint { .. }), "{err}");
+    assert!(err.error.to_string().contains("program"), "{err}");
+    // The refused checkpoint comes back as it was.
+    assert_eq!(err.checkpoint.leaves, checkpoint.leaves);
+
+    let interp = researcher(Arc::default()).await;
+    let overlay = Arc::new(Overlay::new(interp.program()));
+    let err = interp
+        .resume_from(
+            checkpoint.clone(),
+            Some(overlay),
+            Budget::unlimited(),
+            RunOptions::new(),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.error.to_string().contains("no overlay"), "{err}");
+
+    let future = RunCheckpoint {
+        version: 99,
+        ..checkpoint
+    };
+    let err = interp
+        .resume_from(future, None, Budget::unlimited(), RunOptions::new())
+        .await
+        .unwrap_err();
+    assert!(err.error.to_string().contains("version 99"), "{err}");
+}
+
+#[tokio::test]
+async fn earlier_wall_time_counts_against_the_deadline() {
+    let source = r#"dsrs 1
+program answer
+
+model m = "openai:gpt-4o-mini"
+
+sig QA {
+  in question: string
+  out answer: string
+}
+
+main: QA = seq {
+  answerer = predict QA (question = $.question)
+  out { answer = answerer.answer }
+}
+"#;
+    let interp = Interpreter::load(
+        Program::from_dsrs(source).unwrap(),
+        RuntimeEnv::new().with_dry_run(3),
+    )
+    .await
+    .unwrap();
+    let input = JsonMap::from_iter([("question".to_string(), json!("why?"))]);
+    let checkpoints = Arc::default();
+    interp
+        .run_with(input, None, Budget::unlimited(), keep(&checkpoints))
+        .await
+        .unwrap();
+    let mut checkpoint = checkpoints.lock().unwrap().last().cloned().unwrap();
+    // Nothing completed yet, but an hour already spent elsewhere.
+    checkpoint.leaves.clear();
+    checkpoint.meter.elapsed_ms = 60 * 60 * 1000;
+
+    let budget = |minutes: u64| Budget {
+        deadline: Some(Instant::now() + Duration::from_secs(minutes * 60)),
+        ..Budget::unlimited()
+    };
+    let err = interp
+        .resume_from(checkpoint.clone(), None, budget(10), RunOptions::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(err.error, RunError::Budget { .. }), "{err}");
+
+    interp
+        .resume_from(checkpoint, None, budget(90), RunOptions::new())
+        .await
+        .unwrap();
+}
@@ -0,0 +1,139 @@
+//! `//` comments as trivia: kept through parse → canonical print (`dsrs fmt`),
+//! the JSON projection, `Program::edited` and `Program::bake`, and never part
+//! of the program hash.
+
+use std::num::NonZeroU32;
+
+use dspy_rs::ir::{self, Edit, Lineage, Overlay, Program};
+
+const ANNOTATED: &str = r#"// Support triage.
+// Owned by the support team.
+dsrs 1
+program annotated
+
+// The model every step uses.
+model m = "openai:gpt-4o-mini" // cheap on purpose
+
+// What callers see.
+sig Main {
+  in  question: string
+  out answer: string
+}
+
+sig Draft {
+  in  question: string
+  out answer: string
+}
+
+// Entry point.
+main: Main = seq {
+  // First pass.
+  drafter = predict Draft @m (question = $.question) // tuned by hand
+  // Scratch work nobody reads.
+  aside = predict Draft @m (question = $.question)
+  polisher = predict Draft @m (question = drafter.answer)
+  out { answer = polisher.answer }
+}
+
+// end of file
+"#;
+
+/// `text` with every comment removed.
+fn uncommented(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .map(|line| line.split(" //").next().unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn comments_survive_the_canonical_printer_and_stay_out_of_the_hash() {
+    let program = Program::from_dsrs(ANNOTATED).unwrap();
+    assert_eq!(program.to_dsrs(), ANNOTATED);
+
+    let plain = Program::from_dsrs(&uncommented(ANNOTATED)).unwrap();
+    assert!(plain.meta.comments.is_empty());
+    assert_eq!(plain.meta.program_hash, program.meta.program_hash);
+
+    let json = serde_json::to_string(&program).unwrap();
+    let loaded: Program = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.to_dsrs(), ANNOTATED);
+}
+
+#[test]
+fn comments_inside_a_construct_move_above_it() {
+    let src = ANNOTATED
+        .replace(
+            "  in  question: string\n  out answer: string\n}\n\nsig Draft",
+            "  in  question: string // asked by the user\n  out answer: string\n}\n\nsig Draft",
+        )
+        .replace(
+            "drafter.answer)",
+            "drafter.answer // the draft, not the question\n  )",
+        );
+    let text = Program::from_dsrs(&src).unwrap().to_dsrs();
+    assert!(
+        text.contains("// What callers see.\n// asked by the user\nsig Main {"),
+        "{text}"
+    );
+    assert!(
+        text.contains(
+            "  // the draft, not the question\n  polisher = predict Draft @m (question = drafter.answer)\n"
+        ),
+        "{text}"
+    );
+    assert_eq!(
+        Program::from_dsrs(&text).unwrap().to_dsrs(),
+        text,
+        "fmt is idempotent"
+    );
+}
+
+#[test]
+fn edits_keep_the_comments_of_surviving_steps() {
+    let program = Program::from_dsrs(ANNOTATED).unwrap();
+    let edited = program
+        .edited(&[
+            Edit::WrapRetry {
+                node: program.leaf_id("drafter").unwrap(),
+                max_attempts: NonZeroU32::new(2).unwrap(),
+                backoff_ms: 0,
+                feedback: false,
+            },
+            Edit::Remove {
+                node: program.leaf_id("aside").unwrap(),
+            },
+        ])
+        .unwrap();
+    let text = edited.to_dsrs();
+    assert!(text.starts_with("// Support triage.\n"), "{text}");
+    assert!(
+        text.contains("  // First pass.\n  _0 = retry (attempts 2) drafter = predict"),
+        "{text}"
+    );
+    assert!(
+        text.contains("(question = $.question) // tuned by hand\n"),
+        "{text}"
+    );
+    assert!(!text.contains("Scratch work"), "{text}");
+    assert!(text.ends_with("\n// end of file\n"), "{text}");
+}
+
+#[test]
+fn bake_keeps_comments() {
+    let program = Program::from_dsrs(ANNOTATED).unwrap();
+    let mut overlay = Overlay::new(&program);
+    let slot = program
+        .slot_of::<ir::Instruction>("drafter.instruction")
+        .unwrap();
+    overlay.set_instruction(slot, "Answer in one line.");
+    let baked = program.bake(&overlay, Lineage::default()).unwrap();
+    assert_eq!(baked.meta.comments, program.meta.comments);
+    let text = baked.to_dsrs();
+    assert!(
+        text.contains("// Entry point.\nmain: Main = seq {"),
+        "{text}"
+    );
+    assert_eq!(Program::from_dsrs(&text).unwrap().to_dsrs(), text);
+}
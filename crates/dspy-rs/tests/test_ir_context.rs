@@ -0,0 +1,475 @@
+//! Context policy on agent leaves: LLM compaction of older turns into a
+//! running summary, summaries of oversized tool results, and pinned turns
+//! that survive both compaction and `max_history_turns` truncation.
+
+use std::sync::Arc;
+
+use dspy_rs::ir::{
+    Budget, Compaction, ContextPolicy, Interpreter, ParamValue, Program, RuntimeEnv,
+};
+use dspy_rs::trace::{JsonMap, Span, SpanEvent, Trace, capture};
+use dspy_rs::{Chat, LM, LMClient, TestCompletionModel};
+use rig::completion::{AssistantContent, ToolDefinition};
+use rig::message::{Text, ToolCall, ToolFunction};
+use serde_json::json;
+
+/// A research agent on `@m` with a `search` host tool and `context` as its
+/// context block; `@s` is there to summarize.
+fn source(context: &str) -> String {
+    format!(
+        r#"dsrs 1
+program research
+
+model m = "openai:gpt-4o-mini"
+model s = "openai:gpt-4o"
+
+sig Main {{
+  in question: string
+  out answer: string
+}}
+
+sig Research {{
+  "Research and answer."
+  in question: string
+  out answer: string
+}}
+
+tool search "Web search" {{
+  in query: string
+  out results: string
+}}
+
+main: Main = seq {{
+  researcher = agent Research @m (question = $.question) {{
+    tools [search]
+    max_turns 6
+    context {{ {context} }}
+  }}
+  out {{ answer = researcher.answer }}
+}}
+"#
+    )
+}
+
+fn program(context: &str) -> Program {
+    Program::from_dsrs(&source(context)).unwrap()
+}
+
+fn policy(program: &Program) -> ContextPolicy {
+    program
+        .params
+        .iter()
+        .find_map(|(_, slot)| match &slot.default {
+            ParamValue::ContextPolicy { policy } => Some(policy.clone()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn fields(answer: &str) -> String {
+    format!("[[ ## answer ## ]]\n{answer}\n\n[[ ## completed ## ]]\n")
+}
+
+fn text(content: impl Into<String>) -> AssistantContent {
+    AssistantContent::Text(Text {
+        text: content.into(),
+    })
+}
+
+fn search(query: &str) -> AssistantContent {
+    AssistantContent::ToolCall(ToolCall::new(
+        format!("tc-{query}"),
+        ToolFunction {
+            name: "search".to_string(),
+            arguments: json!({ "query": query }),
+        },
+    ))
+}
+
+async fn canned_lm(
+    model: &str,
+    responses: Vec<AssistantContent>,
+) -> (Arc<LM>, TestCompletionModel) {
+    let client = TestCompletionModel::new(responses);
+    let lm = temp_env::async_with_vars(
+        [("OPENAI_API_KEY", Some("test"))],
+        LM::builder().model(model.to_string()).build(),
+    )
+    .await
+    .unwrap()
+    .with_client(LMClient::Test(client.clone()))
+    .await
+    .unwrap();
+    (Arc::new(lm), client)
+}
+
+/// Answers every query with a long, query-tagged page.
+#[derive(Clone)]
+struct Search;
+
+#[derive(Debug)]
+struct SearchError;
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "search error")
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl rig::tool::Tool for Search {
+    const NAME: &'static str = "search";
+    type Error = SearchError;
+    type Args = serde_json::Value;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "host-side definition (ignored: the IR declares the interface)"
+                .to_string(),
+            parameters: json!({"type": "object", "additionalProperties": true}),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let query = args["query"].as_str().unwrap_or("?");
+        Ok(format!(
+            "results for {query}: {}",
+            "lorem ipsum ".repeat(30)
+        ))
+    }
+}
+
+async fn run(interp: &Interpreter) -> (JsonMap, Trace) {
+    let input: JsonMap = [("question".to_string(), json!("what is dsrs?"))]
+        .into_iter()
+        .collect();
+    let (result, trace) = capture(|| interp.run(input, None, Budget::unlimited())).await;
+    (result.unwrap(), trace)
+}
+
+fn researcher(trace: &Trace) -> &Span {
+    trace
+        .spans
+        .iter()
+        .find(|span| trace.component_name(span.component) == "researcher")
+        .unwrap()
+}
+
+fn sent(client: &TestCompletionModel) -> String {
+    format!("{:?}", client.last_request().unwrap().chat_history)
+}
+
+#[tokio::test]
+async fn compaction_folds_old_turns_into_a_summary_and_keeps_pins() {
+    let (agent, agent_client) = canned_lm(
+        "openai:gpt-4o-mini",
+        vec![search("alpha"), search("beta"), text(fields("a rust dspy"))],
+    )
+    .await;
+    let (summarizer, summarizer_client) = canned_lm(
+        "openai:gpt-4o",
+        vec![text("Searched alpha: dsrs is a rust port of dspy.")],
+    )
+    .await;
+    let env = RuntimeEnv::new()
+        .bind_model("m", agent)
+        .bind_model("s", summarizer)
+        .bind_host_tool("search", Arc::new(Search));
+    let interp = Interpreter::load(
+        program("compact { at_tokens 50 keep_recent 2 model @s } pin [0]"),
+        env,
+    )
+    .await
+    .unwrap();
+
+    let (output, trace) = run(&interp).await;
+    assert_eq!(output["answer"], "a rust dspy");
+
+    // The summarizer saw the folded turns; the agent's last prompt carries
+    // the summary, the pinned input turn, and the live tail — not the
+    // folded tool result.
+    assert!(sent(&summarizer_client).contains("results for alpha"));
+    let prompt = sent(&agent_client);
+    assert!(
+        prompt.contains("Summary of the earlier conversation"),
+        "{prompt}"
+    );
+    assert!(prompt.contains("dsrs is a rust port of dspy"));
+    assert!(prompt.contains("what is dsrs?"));
+    assert!(prompt.contains("results for beta"));
+    assert!(!prompt.contains("results for alpha"));
+
+    let compactions: Vec<_> = researcher(&trace)
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SpanEvent::HistoryCompacted {
+                model,
+                summarized,
+                pinned,
+                summary,
+                ..
+            } => Some((model.as_str(), *summarized, *pinned, summary.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        compactions,
+        [(
+            "openai:gpt-4o",
+            2,
+            1,
+            "Searched alpha: dsrs is a rust port of dspy."
+        )]
+    );
+}
+
+#[tokio::test]
+async fn conversation_turns_keep_the_held_summary_and_pin_numbering() {
+    let (agent, agent_client) = canned_lm(
+        "openai:gpt-4o-mini",
+        vec![
+            search("alpha"),
+            search("beta"),
+            text(fields("a rust dspy")),
+            text(fields("the dsrs authors")),
+        ],
+    )
+    .await;
+    let (summarizer, summarizer_client) = canned_lm(
+        "openai:gpt-4o",
+        vec![
+            text("Searched alpha: dsrs is a rust port of dspy."),
+            text("dsrs is a rust port of dspy; beta agreed."),
+        ],
+    )
+    .await;
+    let env = RuntimeEnv::new()
+        .bind_model("m", agent)
+        .bind_model("s", summarizer)
+        .bind_host_tool("search", Arc::new(Search));
+    let interp = Interpreter::load(
+        program("compact { at_tokens 50 keep_recent 2 model @s } pin [0]"),
+        env,
+    )
+    .await
+    .unwrap();
+
+    let input: JsonMap = [("question".to_string(), json!("what is dsrs?"))]
+        .into_iter()
+        .collect();
+    let (result, _) = capture(|| {
+        interp.run_conversation(
+            Chat::new(Vec::new()),
+            Some(input),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    let (first, mut chat) = result.unwrap();
+    assert_eq!(first.output["answer"], "a rust dspy");
+
+    chat.push("user", "who wrote it?");
+    let (result, trace) =
+        capture(|| interp.run_conversation(chat, None, None, Budget::unlimited())).await;
+    let (second, chat) = result.unwrap();
+    assert_eq!(second.output["answer"], "the dsrs authors");
+
+    // The second compaction folds on from the first summary: it is replaced,
+    // not summarized as a turn, and pin 0 still names the opening question.
+    let transcript = sent(&summarizer_client);
+    assert!(
+        transcript.contains("Earlier summary:\\nSearched alpha"),
+        "{transcript}"
+    );
+    assert!(!transcript.contains("Summary of the earlier conversation"));
+    assert!(transcript.contains("results for beta"));
+    let prompt = sent(&agent_client);
+    assert_eq!(
+        prompt
+            .matches("Summary of the earlier conversation")
+            .count(),
+        1,
+        "{prompt}"
+    );
+    assert!(prompt.contains("what is dsrs?"));
+    assert!(prompt.contains("beta agreed"));
+    assert!(chat.messages[1].text_content().ends_with("beta agreed."));
+    assert!(chat.messages[2].text_content().contains("what is dsrs?"));
+
+    let compactions: Vec<_> = researcher(&trace)
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            SpanEvent::HistoryCompacted {
+                summarized, pinned, ..
+            } => Some((*summarized, *pinned)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(compactions, [(2, 0)]);
+}
+
+#[tokio::test]
+async fn oversized_tool_results_are_summarized_before_the_agent_reads_them() {
+    // No compaction model declared: the leaf's own model summarizes, so
+    // the summary is the second canned response.
+    let (agent, client) = canned_lm(
+        "openai:gpt-4o-mini",
+        vec![
+            search("alpha"),
+            text("alpha: dsrs is a rust dspy"),
+            text(fields("a rust dspy")),
+        ],
+    )
+    .await;
+    let env = RuntimeEnv::new()
+        .bind_model("m", Arc::clone(&agent))
+        .bind_model("s", agent)
+        .bind_host_tool("search", Arc::new(Search));
+    let interp = Interpreter::load(program("summarize_tool_results_over 100"), env)
+        .await
+        .unwrap();
+
+    let (output, trace) = run(&interp).await;
+    assert_eq!(output["answer"], "a rust dspy");
+    assert!(!sent(&client).contains("lorem ipsum"));
+
+    let events = &researcher(&trace).events;
+    let summarized = events
+        .iter()
+        .position(|event| matches!(event, SpanEvent::ToolResultSummarized { id, bytes, .. } if id == "tc-alpha" && *bytes > 100))
+        .expect("a ToolResultSummarized event");
+    assert!(matches!(
+        &events[summarized + 1],
+        SpanEvent::ToolRun { result, .. } if result == "alpha: dsrs is a rust dspy"
+    ));
+}
+
+#[tokio::test]
+async fn failed_summaries_fall_back_to_truncation() {
+    let (agent, _) = canned_lm(
+        "openai:gpt-4o-mini",
+        vec![search("alpha"), search("beta"), text(fields("a rust dspy"))],
+    )
+    .await;
+    // No canned responses: every summarizing call fails.
+    let (summarizer, _) = canned_lm("openai:gpt-4o", vec![]).await;
+    let env = RuntimeEnv::new()
+        .bind_model("m", agent)
+        .bind_model("s", summarizer)
+        .bind_host_tool("search", Arc::new(Search));
+    let interp = Interpreter::load(
+        program(
+            "compact { at_tokens 50 keep_recent 2 model @s } \
+             summarize_tool_results_over 100 tool_result_max_bytes 60",
+        ),
+        env,
+    )
+    .await
+    .unwrap();
+
+    let (output, trace) = run(&interp).await;
+    assert_eq!(output["answer"], "a rust dspy");
+
+    let events = &researcher(&trace).events;
+    let failed = events
+        .iter()
+        .position(|event| matches!(event, SpanEvent::SummaryFailed { model, id: Some(id), .. } if model == "openai:gpt-4o" && id == "tc-alpha"))
+        .expect("a failed tool result summary");
+    assert!(matches!(
+        &events[failed + 1],
+        SpanEvent::ToolRun { result, .. } if result.ends_with("[truncated]")
+    ));
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, SpanEvent::SummaryFailed { id: None, .. }))
+    );
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, SpanEvent::HistoryCompacted { .. }))
+    );
+}
+
+#[tokio::test]
+async fn pinned_turns_survive_history_truncation() {
+    let (agent, client) = canned_lm(
+        "openai:gpt-4o-mini",
+        vec![search("alpha"), search("beta"), text(fields("a rust dspy"))],
+    )
+    .await;
+    let env = RuntimeEnv::new()
+        .bind_model("m", Arc::clone(&agent))
+        .bind_model("s", agent)
+        .bind_host_tool("search", Arc::new(Search));
+    let interp = Interpreter::load(program("max_history_turns 2 pin [0]"), env)
+        .await
+        .unwrap();
+
+    let (output, _) = run(&interp).await;
+    assert_eq!(output["answer"], "a rust dspy");
+    let prompt = sent(&client);
+    assert!(prompt.contains("what is dsrs?"), "{prompt}");
+    assert!(prompt.contains("results for beta"));
+    assert!(!prompt.contains("results for alpha"));
+}
+
+#[test]
+fn context_keys_round_trip_through_dsrs() {
+    let full = program(
+        r#"compact { at_tokens 8000 keep_recent 6 model @s instruction "Keep ids." } summarize_tool_results_over 4096 pin [2 0]"#,
+    );
+    let printed = full.to_dsrs();
+    assert!(
+        printed.contains(
+            r#"context { compact { at_tokens 8000 keep_recent 6 model @s instruction "Keep ids." } summarize_tool_results_over 4096 pin [0 2] }"#
+        ),
+        "{printed}"
+    );
+    assert_eq!(Program::from_dsrs(&printed).unwrap().to_dsrs(), printed);
+
+    let compaction = policy(&full).compaction.unwrap();
+    assert_eq!(compaction.keep_recent, 6);
+    assert_eq!(&*full.models[compaction.model.unwrap()].name, "s");
+
+    // `keep_recent` defaults, and the default is left out when printing.
+    let defaults = program("compact { at_tokens 100 }");
+    assert_eq!(
+        policy(&defaults).compaction,
+        Some(Compaction::at_tokens(100))
+    );
+    assert!(
+        defaults
+            .to_dsrs()
+            .contains("context { compact { at_tokens 100 } }")
+    );
+}
+
+#[test]
+fn malformed_context_keys_are_parse_errors() {
+    for (context, message) in [
+        ("compact { keep_recent 2 }", "requires `at_tokens`"),
+        ("compact { at_tokens 0 }", "at least 1"),
+        ("compact { at_tokens 10 keep_recent 0 }", "at least 1"),
+        (
+            "compact { at_tokens 10 model @nope }",
+            "unknown model `@nope`",
+        ),
+        (
+            "compact { at_tokens 10 every 3 }",
+            "unknown compact key `every`",
+        ),
+        ("pinned [0]", "`pin`"),
+    ] {
+        let err = Program::from_dsrs(&source(context))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(message), "{context}: {err}");
+    }
+}
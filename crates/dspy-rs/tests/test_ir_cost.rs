@@ -0,0 +1,229 @@
+//! Static cost bounds: `Program::cost` — per-step evaluation counts through
+//! every container kind, agent turns capped by budgets, prices, overlays,
+//! and the flags for what the graph cannot bound.
+
+use dspy_rs::ir::{CostFlag, CostOptions, NodeCost, Overlay, ParamValue, Pricing, Program};
+
+const QA: &str = include_str!("fixtures/qa.dsrs");
+const KITCHEN: &str = include_str!("fixtures/kitchen.dsrs");
+
+fn node<'a>(nodes: &'a [NodeCost], name: &str) -> &'a NodeCost {
+    nodes
+        .iter()
+        .find(|node| node.node == name)
+        .unwrap_or_else(|| panic!("no node `{name}`"))
+}
+
+#[test]
+fn qa_bounds_count_agent_turns_and_finalize() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let report = program.cost();
+
+    // drafter once; researcher's 6 turns fit its 40k tokens, then the
+    // finalize round-trip. Expected: 3 agent turns, no finalize.
+    assert_eq!(report.total.worst.lm_calls, 8.0);
+    assert_eq!(report.total.expected.lm_calls, 4.0);
+    assert!(report.flags.is_empty(), "{:?}", report.flags);
+    assert!(!report.priced);
+    assert_eq!(report.total.worst.usd, 0.0);
+
+    let keys: Vec<&str> = report.nodes.iter().map(|n| n.node.as_str()).collect();
+    assert_eq!(keys, ["main", "drafter", "researcher", "checker"]);
+    let drafter = node(&report.nodes, "drafter");
+    assert_eq!(drafter.what, "cot Draft @deep");
+    assert!(drafter.leaf);
+    // Worst completions fill max_tokens (512 by default); expected half.
+    assert_eq!(drafter.each.worst.completion_tokens, 512.0);
+    assert_eq!(drafter.each.expected.completion_tokens, 256.0);
+    assert!(drafter.each.worst.prompt_tokens > 200.0);
+
+    let researcher = node(&report.nodes, "researcher");
+    assert_eq!(researcher.what, "agent Research @fast tools [search]");
+    // Later turns carry earlier ones: the longer run has longer prompts.
+    let (worst, expected) = (researcher.each.worst, researcher.each.expected);
+    assert!(worst.prompt_tokens / 7.0 > expected.prompt_tokens / 3.0);
+    assert_eq!(researcher.each.worst.latency_ms, 7.0 * 2_000.0);
+    assert_eq!(node(&report.nodes, "checker").each.worst.lm_calls, 0.0);
+
+    let text = report.to_string();
+    assert!(
+        text.starts_with("program qa: one run, worst / expected\n  lm calls  8 / 4\n"),
+        "{text}"
+    );
+    assert!(text.contains("  latency   16.0 s / 8.0 s\n"), "{text}");
+    assert!(text.contains("\nsteps (per run, worst / expected):\n"));
+    assert!(
+        text.contains("  drafter     cot Draft @deep: 1 / 1 calls"),
+        "{text}"
+    );
+    assert!(!text.contains("checker"));
+    assert!(!text.contains("cost "));
+}
+
+#[test]
+fn containers_multiply_their_children() {
+    let program = Program::from_dsrs(KITCHEN).unwrap();
+    let report = program.cost();
+    let runs = |name: &str| {
+        let node = node(&report.nodes, name);
+        (node.runs.worst, node.runs.expected)
+    };
+
+    assert_eq!(runs("classifier"), (1.0, 1.0));
+    // Route: any arm in the worst case, each arm half the time expected.
+    assert_eq!(runs("low"), (1.0, 0.5));
+    assert_eq!(runs("high"), (1.0, 0.5));
+    // Refine: every round worst, the first expected.
+    assert_eq!(runs("drafter"), (2.0, 1.0));
+    assert_eq!(runs("grader"), (2.0, 1.0));
+    // Loop with `while`: every iteration worst, half expected.
+    assert_eq!(runs("improver"), (3.0, 2.0));
+    // Map: the assumed list length.
+    assert_eq!(runs("partial"), (8.0, 8.0));
+    // Retry: every attempt worst, one expected.
+    assert_eq!(runs("auditor"), (2.0, 1.0));
+
+    // The agent's 2000-token budget stops it before its 3 turns are used;
+    // finalize still runs.
+    let high = node(&report.nodes, "high");
+    assert!(high.each.worst.lm_calls < 4.0, "{high:?}");
+    assert!(
+        high.what
+            .starts_with("agent Reply @core tools [fetch, shout]")
+    );
+    let router = node(&report.nodes, "main.1");
+    assert_eq!(router.what, "route");
+    assert_eq!(router.each.worst.lm_calls, high.each.worst.lm_calls);
+    assert_eq!(
+        router.each.expected.lm_calls,
+        (1.0 + high.each.expected.lm_calls) / 2.0
+    );
+
+    // classifier, router, summarizer, refine 2 × 2, loop 3, splitter, map 8,
+    // retry 2.
+    assert_eq!(
+        report.total.worst.lm_calls,
+        1.0 + high.each.worst.lm_calls + 1.0 + 4.0 + 3.0 + 1.0 + 8.0 + 2.0
+    );
+    // Map latency: 8 elements, 4 at a time. Retry latency adds backoff.
+    assert_eq!(node(&report.nodes, "main.5").each.worst.latency_ms, 4_000.0);
+    assert_eq!(node(&report.nodes, "main.8").each.worst.latency_ms, 4_050.0);
+    // Fork latency: the slower branch, not the sum.
+    assert_eq!(node(&report.nodes, "main.2").each.worst.latency_ms, 8_000.0);
+    assert_eq!(node(&report.nodes, "main.2").each.worst.lm_calls, 5.0);
+
+    assert_eq!(
+        report.flags,
+        vec![CostFlag::MapLength {
+            node: "main.5".to_string(),
+            assumed: 8,
+        }]
+    );
+}
+
+#[test]
+fn prices_and_overlays_feed_the_estimate() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let pricing = Pricing::new().with("openai:gpt-4o-mini", 0.15, 0.6);
+    let report = program.cost_with(CostOptions {
+        pricing: Some(&pricing),
+        ..Default::default()
+    });
+    assert!(report.priced);
+    assert_eq!(
+        report.flags,
+        vec![CostFlag::Unpriced {
+            model: "deep".to_string(),
+        }]
+    );
+    let researcher = node(&report.nodes, "researcher");
+    let worst = researcher.each.worst;
+    let usd = (worst.prompt_tokens * 0.15 + worst.completion_tokens * 0.6) / 1e6;
+    assert!((worst.usd - usd).abs() < 1e-12);
+    assert_eq!(report.total.worst.usd, worst.usd);
+    assert!(report.to_string().contains("  cost      $"));
+
+    // Moving the researcher to the unpriced model drops its dollars.
+    let deep = program
+        .models
+        .iter()
+        .find(|(_, m)| &*m.name == "deep")
+        .map(|(id, _)| id)
+        .unwrap();
+    let mut overlay = Overlay::new(&program);
+    overlay
+        .set(
+            &program,
+            program.param_id("researcher.model").unwrap(),
+            ParamValue::ModelRef { model: deep },
+        )
+        .unwrap();
+    let report = program.cost_with(CostOptions {
+        overlay: Some(&overlay),
+        pricing: Some(&pricing),
+        ..Default::default()
+    });
+    let researcher = node(&report.nodes, "researcher");
+    assert_eq!(researcher.what, "agent Research @deep tools [search]");
+    assert_eq!(report.total.worst.usd, 0.0);
+}
+
+#[test]
+fn agents_without_a_budget_are_flagged() {
+    let src = "dsrs 1\nprogram p\n\nmodel m = \"openai:gpt-4o-mini\"\n\n\
+               sig Main {\n  in q: string\n  out a: string\n}\n\n\
+               main: Main = seq {\n  \
+               asker = agent Main (q = $.q) {\n    max_turns 4\n  }\n  \
+               out { a = asker.a }\n}\n";
+    let program = Program::from_dsrs(src).unwrap();
+    let report = program.cost();
+    assert_eq!(
+        report.flags,
+        vec![CostFlag::Unbudgeted {
+            node: "asker".to_string(),
+        }]
+    );
+    // No finalize without `on_exhausted finalize`.
+    assert_eq!(report.total.worst.lm_calls, 4.0);
+    assert_eq!(report.total.expected.lm_calls, 3.0);
+    assert!(
+        report
+            .to_string()
+            .ends_with("flags:\n  asker: agent without a budget (only max_turns bounds it)\n")
+    );
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["flags"][0]["flag"], "unbudgeted");
+    assert_eq!(json["total"]["worst"]["lm_calls"], 4.0);
+}
+
+#[test]
+fn context_policy_summaries_count_against_the_agent() {
+    let cost = |block: &str| {
+        let src = format!(
+            "dsrs 1\nprogram p\n\nmodel m = \"openai:gpt-4o-mini\"\n\n\
+             sig Main {{\n  in q: string\n  out a: string\n}}\n\n\
+             main: Main = seq {{\n  \
+             asker = agent Main (q = $.q) {{\n    max_turns 4\n    {block}\n  }}\n  \
+             out {{ a = asker.a }}\n}}\n"
+        );
+        Program::from_dsrs(&src).unwrap().cost().total
+    };
+
+    // Every turn but the last summarizes its (assumed 2000-byte) result.
+    let summarized = cost("context { summarize_tool_results_over 100 }");
+    assert_eq!(summarized.worst.lm_calls, 4.0 + 3.0);
+    assert_eq!(summarized.expected.lm_calls, 3.0 + 2.0);
+
+    // Summaries draw on the agent's call budget.
+    let capped = cost("context { summarize_tool_results_over 100 }\n    budget { calls 5 }");
+    assert_eq!(capped.worst.lm_calls, 5.0);
+
+    // Compaction adds a call once the carried history reaches `at_tokens`.
+    let plain = cost("");
+    let compacted = cost("context { compact { at_tokens 500 keep_recent 2 } }");
+    assert!(
+        compacted.worst.lm_calls > plain.worst.lm_calls,
+        "{compacted:?}"
+    );
+}
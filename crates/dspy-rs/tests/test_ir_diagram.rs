@@ -0,0 +1,170 @@
+//! Program diagrams: `Program::to_dot` / `to_mermaid` — clusters per
+//! container, leaves with model and tool set, field-labeled dataflow edges,
+//! and overlay/trace annotations via `GraphNotes`.
+
+use dspy_rs::Trace;
+use dspy_rs::ir::{GraphNotes, Overlay, ParamValue, Program};
+use serde_json::json;
+
+const QA: &str = include_str!("fixtures/qa.dsrs");
+const KITCHEN: &str = include_str!("fixtures/kitchen.dsrs");
+
+/// Two spans for `researcher` (one failed) and one for `drafter`.
+fn qa_trace() -> Trace {
+    let span = |id: u32, component: u32, duration_us: u64, tokens: u64, error: bool| {
+        let mut span = json!({
+            "id": id,
+            "component": component,
+            "seq": 0,
+            "suffix": [],
+            "model": 0,
+            "request_hash": 0,
+            "events": [],
+            "usage": { "prompt_tokens": tokens, "completion_tokens": 0, "total_tokens": tokens },
+            "started_at_us": 0,
+            "duration_us": duration_us,
+        });
+        if error {
+            span["error"] = json!({ "kind": "parse", "message": "bad output" });
+        }
+        span
+    };
+    serde_json::from_value(json!({
+        "meta": { "v": 1, "trace_id": "t", "started_at_us": 0 },
+        "components": ["drafter", "researcher"],
+        "models": [],
+        "prefixes": [],
+        "spans": [
+            span(0, 0, 1_200, 100, false),
+            span(1, 1, 2_000, 120, true),
+            span(2, 1, 3_000, 180, false),
+        ],
+        "outcome": null,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn dot_draws_leaves_clusters_and_labeled_edges() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let dot = program.to_dot();
+
+    assert!(dot.starts_with("digraph \"qa\" {\n"), "{dot}");
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("label=\"main: Main\";"), "{dot}");
+    assert!(dot.contains("inputs [label=\"inputs\\nquestion: string\", shape=ellipse];"));
+    assert!(dot.contains("[label=\"drafter\\ncot Draft @deep\", shape=box, style=rounded];"));
+    assert!(dot.contains("\"researcher\\nagent Research @fast\\ntools [search]\""));
+    assert!(dot.contains("\"checker\\nhole CiteCheck\""));
+    assert!(dot.contains("\"outputs\""));
+    // Same field name on both ends: just the field; renamed: both.
+    assert!(dot.contains("inputs -> "));
+    assert!(dot.contains("[label=\"question\"];"));
+    assert!(dot.contains("[label=\"answer → draft\"];"), "{dot}");
+    assert!(dot.contains("[label=\"evidence\"];"));
+    // No annotation without notes.
+    assert!(!dot.contains("overlay:"));
+    assert!(!dot.contains(" calls"));
+}
+
+#[test]
+fn mermaid_uses_subgraphs_and_escapes_labels() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let mermaid = program.to_mermaid();
+
+    assert!(mermaid.starts_with("flowchart TD\n"), "{mermaid}");
+    assert!(mermaid.contains("subgraph c_n"));
+    assert!(mermaid.contains("[\"main: Main\"]"));
+    assert!(mermaid.contains("inputs([\"inputs<br/>question: string\"])"));
+    assert!(mermaid.contains("[\"drafter<br/>cot Draft @deep\"]"));
+    assert!(mermaid.contains("-->|\"answer → draft\"|"), "{mermaid}");
+    let ends = mermaid.lines().filter(|line| line.trim() == "end").count();
+    assert_eq!(mermaid.matches("subgraph ").count(), ends);
+}
+
+#[test]
+fn every_container_kind_gets_its_cluster() {
+    let program = Program::from_dsrs(KITCHEN).unwrap();
+    let dot = program.to_dot();
+
+    // Route: a diamond on the routed port, one dashed arm edge per case.
+    assert!(
+        dot.contains("[label=\"on classifier.severity\", shape=diamond];"),
+        "{dot}"
+    );
+    assert!(dot.contains("[label=\"Low\", style=dashed];"));
+    assert!(dot.contains("[label=\"else\", style=dashed];"));
+    assert!(dot.contains("\"high\\nagent Reply @core\\ntools [fetch, shout]\""));
+    // Fork/join and refine, with the judge's feedback going back.
+    assert!(dot.contains("label=\"fork\";"));
+    assert!(dot.contains("label=\"refine ≥0.5 ×2\";"));
+    assert!(dot.contains("[label=\"feedback\", style=dashed];"));
+    // Literal bindings are listed on the leaf, not drawn.
+    assert!(dot.contains("\"drafter\\npredict Draft @core\\nfeedback = \\\"start\\\"\""));
+    // Loop: carried reads are dotted and don't pull the layout.
+    assert!(dot.contains("label=\"loop ×3 while improver.keep_going\";"));
+    assert!(dot.contains("[label=\"^ticket\", style=dotted, constraint=false];"));
+    assert!(dot.contains("[label=\"better → ^ticket\"];"));
+    // Fork and loop exports are both `join`, as in the text.
+    assert_eq!(
+        dot.matches("[label=\"join\", shape=ellipse];").count(),
+        2,
+        "{dot}"
+    );
+    // Map: the item node, fed by the mapped list.
+    assert!(dot.contains("label=\"map ∥4\";"));
+    assert!(dot.contains("[label=\"each $.part\", shape=ellipse];"));
+    assert!(dot.contains("[label=\"parts\"];"));
+    assert!(dot.contains("[label=\"part → text\"];"));
+    assert!(dot.contains("label=\"retry ×2, backoff 50 ms\";"));
+    assert!(dot.contains("\"joined\\ntransform Join\""));
+    assert!(dot.contains("\"signoff\\napprove Signoff\""));
+
+    for title in ["main: Main", "route", "fork", "loop", "map", "retry"] {
+        assert!(dot.contains(&format!("label=\"{title}")), "{title}");
+    }
+}
+
+#[test]
+fn overlay_and_trace_annotate_leaves() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let deep = program
+        .models
+        .iter()
+        .find(|(_, m)| &*m.name == "deep")
+        .map(|(id, _)| id)
+        .unwrap();
+    let mut overlay = Overlay::new(&program);
+    overlay
+        .set(
+            &program,
+            program.param_id("researcher.model").unwrap(),
+            ParamValue::ModelRef { model: deep },
+        )
+        .unwrap();
+    overlay
+        .set(
+            &program,
+            program.param_id("researcher.instruction").unwrap(),
+            ParamValue::Instruction {
+                text: "Be skeptical.".to_string(),
+            },
+        )
+        .unwrap();
+    let trace = qa_trace();
+
+    let dot = program.to_dot_with(GraphNotes {
+        overlay: Some(&overlay),
+        trace: Some(&trace),
+    });
+    assert!(dot.contains("\"researcher\\nagent Research @deep\\ntools [search]\\noverlay: instruction, model\\n2 calls, 5 ms, 300 tokens, 1 error\""), "{dot}");
+    assert!(dot.contains("\"drafter\\ncot Draft @deep\\n1 call, 1 ms, 100 tokens\""));
+    // A leaf with no spans and nothing overlaid carries no notes.
+    assert!(dot.contains("[label=\"checker\\nhole CiteCheck\", shape=box"));
+
+    let mermaid = program.to_mermaid_with(GraphNotes {
+        overlay: None,
+        trace: Some(&trace),
+    });
+    assert!(mermaid.contains("[\"researcher<br/>agent Research @fast<br/>tools [search]<br/>2 calls, 5 ms, 300 tokens, 1 error\"]"), "{mermaid}");
+}
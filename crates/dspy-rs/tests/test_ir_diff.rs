@@ -0,0 +1,200 @@
+//! Semantic diffs: `Program::diff` keyed by leaf name, container position,
+//! declaration name, and param path; `Overlay::diff` over the named form;
+//! text and JSON renderings.
+
+use dspy_rs::ir::{Change, Overlay, OverlayDiff, ParamValue, Program};
+use serde_json::json;
+
+const QA: &str = include_str!("fixtures/qa.dsrs");
+
+/// The qa fixture after a review round: a polishing step between research
+/// and the check, a plain `predict` drafter with a confidence output and a
+/// new instruction, the researcher on the deep model, a newer fast model.
+fn revised() -> String {
+    QA.replace(
+        "model fast = \"openai:gpt-4o-mini\"",
+        "model fast = \"openai:gpt-4o\"",
+    )
+    .replace(
+        "\"Draft a thorough, factual answer.\"\n  in  question: string\n  out answer: string\n}",
+        "\"Draft a short, factual answer.\"\n  in  question: string\n  out answer: string\n  out confidence: float\n}\n\nsig Polish {\n  in  draft: string\n  out answer: string\n}",
+    )
+    .replace("drafter = cot Draft @deep", "drafter = predict Draft @deep")
+    .replace("agent Research @fast", "agent Research @deep")
+    .replace(
+        "  checker = hole CiteCheck (draft = drafter.answer,",
+        "  polisher = predict Polish @fast (draft = drafter.answer)\n  checker = hole CiteCheck (draft = polisher.answer,",
+    )
+}
+
+fn change(key: &str, from: Option<&str>, to: Option<&str>) -> Change {
+    Change {
+        key: key.to_string(),
+        from: from.map(str::to_string),
+        to: to.map(str::to_string),
+    }
+}
+
+#[test]
+fn identical_programs_have_no_changes() {
+    let a = Program::from_dsrs(QA).unwrap();
+    let b = Program::from_dsrs(QA).unwrap();
+    let diff = a.diff(&b);
+    assert!(diff.is_empty(), "{diff}");
+    assert!(diff.to_string().ends_with("no changes\n"));
+}
+
+#[test]
+fn diff_reports_nodes_bindings_sigs_models_and_params() {
+    let a = Program::from_dsrs(QA).unwrap();
+    let b = Program::from_dsrs(&revised()).unwrap();
+    let diff = a.diff(&b);
+
+    assert_eq!(diff.from_hash, a.meta.program_hash);
+    assert_eq!(diff.to_hash, b.meta.program_hash);
+    assert!(diff.program.is_empty());
+    assert_eq!(
+        diff.nodes,
+        vec![
+            change("drafter", Some("cot Draft"), Some("predict Draft")),
+            change("polisher", None, Some("predict Polish")),
+        ]
+    );
+    assert_eq!(
+        diff.moved,
+        vec![change("checker", Some("main.2"), Some("main.3"))]
+    );
+    assert_eq!(
+        diff.bindings,
+        vec![change(
+            "checker.draft",
+            Some("drafter.answer"),
+            Some("polisher.answer")
+        )]
+    );
+    assert_eq!(
+        diff.sigs,
+        vec![
+            change(
+                "Draft.instruction",
+                Some("\"Draft a thorough, factual answer.\""),
+                Some("\"Draft a short, factual answer.\"")
+            ),
+            change("Draft.confidence", None, Some("out confidence: float")),
+            change(
+                "Polish",
+                None,
+                Some("(in draft: string, out answer: string)")
+            ),
+        ]
+    );
+    assert!(diff.tools.is_empty());
+    assert_eq!(
+        diff.models,
+        vec![change(
+            "fast",
+            Some("\"openai:gpt-4o-mini\""),
+            Some("\"openai:gpt-4o\"")
+        )]
+    );
+    // Params on both sides only: the new step's slots come with the step.
+    let params: Vec<&str> = diff.params.iter().map(|c| c.key.as_str()).collect();
+    assert_eq!(params, ["drafter.instruction", "researcher.model"]);
+    assert_eq!(
+        diff.params[1].to,
+        Some(json!({ "k": "model_ref", "model": "deep" }))
+    );
+
+    let text = diff.to_string();
+    assert!(
+        text.contains(
+            "nodes:\n  ~ drafter: cot Draft → predict Draft\n  + polisher: predict Polish\n"
+        ),
+        "{text}"
+    );
+    assert!(
+        text.contains("moved:\n  ~ checker: main.2 → main.3\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("  ~ researcher.model: \"fast\" → \"deep\"\n"),
+        "{text}"
+    );
+    assert!(!text.contains("tools:"), "{text}");
+
+    let value = serde_json::to_value(&diff).unwrap();
+    assert_eq!(
+        value["bindings"][0],
+        json!({ "key": "checker.draft", "from": "drafter.answer", "to": "polisher.answer" })
+    );
+    assert_eq!(value["nodes"][1]["from"], json!(null));
+}
+
+#[test]
+fn containers_are_keyed_by_position() {
+    let src = |attempts: u32| {
+        format!(
+            "dsrs 1\nprogram p\n\nmodel m = \"openai:gpt-4o-mini\"\n\n\
+             sig Main {{\n  in q: string\n  out a: string\n}}\n\n\
+             main: Main = seq {{\n  \
+             retried = retry (attempts {attempts}) answerer = predict Main (q = $.q)\n  \
+             out {{ a = answerer.a }}\n}}\n"
+        )
+    };
+    let a = Program::from_dsrs(&src(2)).unwrap();
+    let b = Program::from_dsrs(&src(3)).unwrap();
+    let diff = a.diff(&b);
+    assert_eq!(
+        diff.nodes,
+        vec![change(
+            "main.0",
+            Some("retry (attempts 2)"),
+            Some("retry (attempts 3)")
+        )]
+    );
+    assert!(diff.moved.is_empty() && diff.bindings.is_empty());
+}
+
+#[test]
+fn overlay_diff_by_param_path() {
+    let program = Program::from_dsrs(QA).unwrap();
+    let instruction = program.param_id("drafter.instruction").unwrap();
+    let model = program.param_id("researcher.model").unwrap();
+    let deep = program
+        .models
+        .iter()
+        .find(|(_, m)| &*m.name == "deep")
+        .map(|(id, _)| id)
+        .unwrap();
+    let text = |s: &str| ParamValue::Instruction {
+        text: s.to_string(),
+    };
+
+    let mut before = Overlay::new(&program);
+    before
+        .set(&program, instruction, text("Be brief."))
+        .unwrap();
+    let mut after = Overlay::new(&program);
+    after
+        .set(&program, instruction, text("Be thorough."))
+        .unwrap();
+    after
+        .set(&program, model, ParamValue::ModelRef { model: deep })
+        .unwrap();
+
+    let diff = before.diff(&after, &program);
+    assert_eq!(
+        diff.to_string(),
+        "params:\n  ~ drafter.instruction: \"Be brief.\" → \"Be thorough.\"\n  \
+         + researcher.model: \"deep\"\n"
+    );
+    assert!(before.diff(&before, &program).is_empty());
+
+    // Without a program the named form's ids stay ids.
+    let named = OverlayDiff::named(&before.to_named(&program), &after.to_named(&program));
+    assert_eq!(named.params.len(), 2);
+    assert_eq!(
+        named.params[1].to,
+        Some(json!({ "k": "model_ref", "model": 1 }))
+    );
+}
@@ -192,6 +192,7 @@ fn runtime_only_signature() -> (SignatureDef, TypeTable) {
                     name: (*name).to_string(),
                     rendered_name: (*name).to_string(),
                     docs: None,
+                    aliases: Vec::new(),
                 })
                 .collect(),
         },
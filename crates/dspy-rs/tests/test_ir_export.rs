@@ -0,0 +1,282 @@
+//! Module → program export: leaf graph and field-level dataflow inferred from
+//! traced sample runs, untraced Rust extracted as extern holes, and the
+//! exported text round-tripping through `Program::from_dsrs`.
+
+use dspy_rs::ir::{ExportError, Program, export_module};
+use dspy_rs::{
+    LM, LMClient, Module, Predict, PredictError, Predicted, Signature, TestCompletionModel,
+};
+use rig::completion::AssistantContent;
+use rig::message::Text;
+
+fn response_with_fields(fields: &[(&str, &str)]) -> AssistantContent {
+    let mut response = String::new();
+    for (name, value) in fields {
+        response.push_str(&format!("[[ ## {name} ## ]]\n{value}\n\n"));
+    }
+    response.push_str("[[ ## completed ## ]]\n");
+    AssistantContent::Text(Text { text: response })
+}
+
+async fn make_test_lm(responses: Vec<AssistantContent>) -> LM {
+    let client = TestCompletionModel::new(responses);
+    temp_env::async_with_vars(
+        [("OPENAI_API_KEY", Some("test"))],
+        LM::builder()
+            .model("openai:gpt-4o-mini".to_string())
+            .build(),
+    )
+    .await
+    .unwrap()
+    .with_client(LMClient::Test(client))
+    .await
+    .unwrap()
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Draft an answer.
+struct Draft {
+    #[input]
+    question: String,
+
+    #[output]
+    draft: String,
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Polish the draft in the requested tone.
+struct Polish {
+    #[input]
+    question: String,
+
+    #[input]
+    draft: String,
+
+    #[input]
+    tone: String,
+
+    #[output]
+    answer: String,
+}
+
+struct Pipeline {
+    draft: Predict<Draft>,
+    polish: Predict<Polish>,
+}
+
+dspy_rs::predictors!(Pipeline { draft, polish });
+
+impl Module for Pipeline {
+    type Input = DraftInput;
+    type Output = PolishOutput;
+
+    async fn forward(&self, input: DraftInput) -> Result<Predicted<PolishOutput>, PredictError> {
+        let question = input.question.clone();
+        // Plain Rust the trace cannot see: becomes an extern hole.
+        let tone = if question.ends_with('?') {
+            "curious"
+        } else {
+            "plain"
+        };
+        let draft = self.draft.call(input).await?;
+        self.polish
+            .call(PolishInput {
+                question,
+                draft: draft.draft.clone(),
+                tone: tone.to_string(),
+            })
+            .await
+    }
+}
+
+#[cfg_attr(miri, ignore = "MIRI has issues with tokio's I/O driver")]
+#[tokio::test]
+async fn export_infers_bindings_and_extracts_untraced_inputs_as_holes() {
+    let mut module = Pipeline {
+        draft: Predict::<Draft>::builder()
+            .lm(make_test_lm(vec![
+                response_with_fields(&[("draft", "rust is a language")]),
+                response_with_fields(&[("draft", "tokio schedules tasks")]),
+            ])
+            .await)
+            .build(),
+        polish: Predict::<Polish>::builder()
+            .instruction("Polish it.")
+            .lm(make_test_lm(vec![
+                response_with_fields(&[("answer", "Rust is a language.")]),
+                response_with_fields(&[("answer", "Tokio schedules tasks.")]),
+            ])
+            .await)
+            .build(),
+    };
+
+    let samples = vec![
+        DraftInput {
+            question: "what is rust?".to_string(),
+        },
+        DraftInput {
+            question: "explain tokio".to_string(),
+        },
+    ];
+    let program = export_module(&mut module, "pipeline", samples)
+        .await
+        .expect("export should succeed");
+
+    let text = program.to_dsrs();
+    assert!(text.contains("program pipeline"), "{text}");
+    assert!(
+        text.contains("draft = predict Draft (question = $.question)"),
+        "{text}"
+    );
+    assert!(
+        text.contains("question = $.question, draft = draft.draft"),
+        "{text}"
+    );
+    assert!(text.contains("tone = polish_prep.tone"), "{text}");
+    assert!(
+        text.contains("polish_prep = hole polish_prep_hole"),
+        "{text}"
+    );
+    assert!(text.contains("extern"), "{text}");
+    assert!(text.contains("answer = polish.answer"), "{text}");
+    assert!(text.contains("Polish it."), "{text}");
+
+    let parsed = Program::from_dsrs(&text).expect("exported text should parse");
+    assert_eq!(parsed.meta.program_hash, program.meta.program_hash);
+    assert_eq!(parsed.to_dsrs(), text);
+}
+
+struct Twice {
+    draft: Predict<Draft>,
+}
+
+dspy_rs::predictors!(Twice { draft });
+
+impl Module for Twice {
+    type Input = DraftInput;
+    type Output = DraftOutput;
+
+    async fn forward(&self, input: DraftInput) -> Result<Predicted<DraftOutput>, PredictError> {
+        let first = self.draft.call(input).await?;
+        self.draft
+            .call(DraftInput {
+                question: first.draft.clone(),
+            })
+            .await
+    }
+}
+
+#[cfg_attr(miri, ignore = "MIRI has issues with tokio's I/O driver")]
+#[tokio::test]
+async fn export_refuses_a_leaf_that_runs_twice() {
+    let mut module = Twice {
+        draft: Predict::<Draft>::builder()
+            .lm(make_test_lm(vec![
+                response_with_fields(&[("draft", "one")]),
+                response_with_fields(&[("draft", "two")]),
+            ])
+            .await)
+            .build(),
+    };
+
+    let err = export_module(
+        &mut module,
+        "twice",
+        [DraftInput {
+            question: "q".to_string(),
+        }],
+    )
+    .await
+    .expect_err("a repeated leaf is not exportable");
+    assert!(
+        matches!(&err, ExportError::RepeatedLeaf { sample: 0, leaf } if leaf == "draft"),
+        "{err}"
+    );
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Answer from the optional context.
+struct Ask {
+    #[input]
+    question: String,
+
+    #[input]
+    context: Option<String>,
+
+    #[output]
+    answer: String,
+}
+
+#[derive(Signature, Clone, Debug)]
+/// Review the answer.
+struct Review {
+    #[input]
+    answer: String,
+
+    #[input]
+    feedback: Option<String>,
+
+    #[output]
+    verdict: String,
+}
+
+struct Reviewed {
+    ask: Predict<Ask>,
+    review: Predict<Review>,
+}
+
+dspy_rs::predictors!(Reviewed { ask, review });
+
+impl Module for Reviewed {
+    type Input = AskInput;
+    type Output = ReviewOutput;
+
+    async fn forward(&self, input: AskInput) -> Result<Predicted<ReviewOutput>, PredictError> {
+        let ask = self.ask.call(input).await?;
+        self.review
+            .call(ReviewInput {
+                answer: ask.answer.clone(),
+                feedback: None,
+            })
+            .await
+    }
+}
+
+#[cfg_attr(miri, ignore = "MIRI has issues with tokio's I/O driver")]
+#[tokio::test]
+async fn export_does_not_trace_fields_that_are_null_on_every_sample() {
+    let mut module = Reviewed {
+        ask: Predict::<Ask>::builder()
+            .lm(make_test_lm(vec![
+                response_with_fields(&[("answer", "a language")]),
+                response_with_fields(&[("answer", "a runtime")]),
+            ])
+            .await)
+            .build(),
+        review: Predict::<Review>::builder()
+            .lm(make_test_lm(vec![
+                response_with_fields(&[("verdict", "ok")]),
+                response_with_fields(&[("verdict", "ok")]),
+            ])
+            .await)
+            .build(),
+    };
+
+    let samples = ["what is rust?", "what is tokio?"].map(|question| AskInput {
+        question: question.to_string(),
+        context: None,
+    });
+    let program = export_module(&mut module, "reviewed", samples)
+        .await
+        .expect("export should succeed");
+
+    // `$.context` is null wherever `feedback` is, which proves nothing:
+    // both come from holes instead.
+    let text = program.to_dsrs();
+    assert!(text.contains("context = ask_prep.context"), "{text}");
+    assert!(text.contains("feedback = review_prep.feedback"), "{text}");
+    assert!(!text.contains("feedback = $.context"), "{text}");
+
+    let parsed = Program::from_dsrs(&text).expect("exported text should parse");
+    assert_eq!(parsed.to_dsrs(), text);
+}
@@ -0,0 +1,325 @@
+//! Guardrails: policies bound on `RuntimeEnv` check, rewrite, or block a
+//! run's input, each leaf's prompt and output, and the run's output. Every
+//! decision is recorded on the leaf's span and pushed to observers; a block
+//! fails the run with `RunError::Guardrail`.
+
+use std::sync::{Arc, Mutex};
+
+use dspy_rs::capture;
+use dspy_rs::ir::{
+    Budget, GuardAction, GuardStage, Guardrail, Interpreter, LoadError, Program, RunError,
+    RunEvent, RunOptions, RuntimeEnv, ValueStage, Verdict,
+};
+use dspy_rs::trace::{JsonMap, Span, SpanErrorKind, SpanEvent, Trace};
+use serde_json::json;
+
+const SUPPORT: &str = r#"dsrs 1
+program support
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in ticket: string
+  out reply: string
+}
+
+sig Draft {
+  in ticket: string
+  out draft: string
+}
+
+sig Reply {
+  in text: string
+  out reply: string
+}
+
+main: Main = seq {
+  drafter = predict Draft (ticket = $.ticket)
+  polisher = hole Reply (text = drafter.draft) caps [] extern "00000000000000c4"
+  out { reply = polisher.reply }
+}
+"#;
+
+fn ticket(text: &str) -> JsonMap {
+    [("ticket".to_string(), json!(text))].into_iter().collect()
+}
+
+/// A dry-run interpreter whose `polisher` answers `reply`.
+async fn interpreter(reply: &'static str, guardrails: Vec<Guardrail>) -> Interpreter {
+    let env = guardrails.into_iter().fold(
+        RuntimeEnv::new()
+            .with_dry_run(3)
+            .bind_host_hole("polisher", move |_: JsonMap| async move {
+                Ok(json!({ "reply": reply }))
+            }),
+        RuntimeEnv::with_guardrail,
+    );
+    Interpreter::load(Program::from_dsrs(SUPPORT).unwrap(), env)
+        .await
+        .unwrap()
+}
+
+fn span<'t>(trace: &'t Trace, component: &str) -> &'t Span {
+    trace
+        .spans
+        .iter()
+        .find(|span| trace.component_name(span.component) == component)
+        .unwrap()
+}
+
+fn decisions(span: &Span) -> Vec<(&str, GuardStage, GuardAction)> {
+    span.events
+        .iter()
+        .filter_map(|event| match event {
+            SpanEvent::Guardrail {
+                guard,
+                stage,
+                action,
+                ..
+            } => Some((guard.as_str(), *stage, *action)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn prompt_redaction_reaches_the_span_not_the_original() {
+    let interp = interpreter(
+        "Thanks for writing in.",
+        vec![
+            Guardrail::redact("ssn", GuardStage::Prompt, r"\d{3}-\d{2}-\d{4}", "[ssn]").unwrap(),
+            // Scoped to another leaf: never consulted for `drafter`.
+            Guardrail::blocklist("elsewhere", GuardStage::Prompt, &["ssn"])
+                .unwrap()
+                .only(["summarizer"]),
+        ],
+    )
+    .await;
+
+    let (result, trace) = capture(|| {
+        interp.run(
+            ticket("my ssn is 123-45-6789, please update it"),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    assert_eq!(result.unwrap()["reply"], "Thanks for writing in.");
+
+    let drafter = span(&trace, "drafter");
+    let sent: String = drafter.suffix.iter().map(|m| m.content()).collect();
+    assert!(sent.contains("my ssn is [ssn]"), "{sent}");
+    assert!(!sent.contains("123-45-6789"));
+    assert_eq!(
+        decisions(drafter),
+        [("ssn", GuardStage::Prompt, GuardAction::Rewrite)]
+    );
+}
+
+#[tokio::test]
+async fn an_output_block_fails_the_run_and_closes_the_span() {
+    let interp = interpreter(
+        "We will refund you in full.",
+        vec![
+            Guardrail::blocklist("no-promises", GuardStage::Output, &["(?i)refund"])
+                .unwrap()
+                .only(["polisher"]),
+        ],
+    )
+    .await;
+
+    let (result, trace) =
+        capture(|| interp.run(ticket("where is my parcel"), None, Budget::unlimited())).await;
+    let err = result.unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            RunError::Guardrail { at, guard, stage: GuardStage::Output, reason }
+                if &**at == "polisher" && &**guard == "no-promises" && reason.contains("`reply`")
+        ),
+        "{err}"
+    );
+    assert!(!err.retryable());
+
+    let polisher = span(&trace, "polisher");
+    assert_eq!(
+        polisher.error.as_ref().map(|error| error.kind),
+        Some(SpanErrorKind::Guardrail)
+    );
+    assert!(polisher.output.is_none());
+    assert_eq!(
+        decisions(polisher),
+        [("no-promises", GuardStage::Output, GuardAction::Block)]
+    );
+}
+
+#[tokio::test]
+async fn rewrites_that_break_the_signature_fail_the_run() {
+    let drop = |field: &'static str| {
+        move |_: &str, map: &JsonMap| {
+            let mut value = map.clone();
+            value.remove(field);
+            Verdict::Rewrite {
+                value,
+                reason: format!("dropped `{field}`"),
+            }
+        }
+    };
+
+    // A leaf output rewrite that drops `draft`, which `polisher` reads.
+    let interp = interpreter(
+        "Thanks for writing in.",
+        vec![Guardrail::value("strip", ValueStage::Output, drop("draft")).only(["drafter"])],
+    )
+    .await;
+    let (result, trace) =
+        capture(|| interp.run(ticket("where is my parcel"), None, Budget::unlimited())).await;
+    let err = result.unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            RunError::Guardrail { at, guard, stage: GuardStage::Output, .. }
+                if &**at == "drafter" && &**guard == "strip"
+        ),
+        "{err}"
+    );
+    assert_eq!(
+        span(&trace, "drafter")
+            .error
+            .as_ref()
+            .map(|error| error.kind),
+        Some(SpanErrorKind::Guardrail)
+    );
+    assert!(trace.for_component("polisher").next().is_none());
+
+    // A run input rewrite that drops the required `ticket`.
+    let interp = interpreter(
+        "Thanks for writing in.",
+        vec![Guardrail::value(
+            "strip",
+            ValueStage::RunInput,
+            drop("ticket"),
+        )],
+    )
+    .await;
+    let err = interp
+        .run(ticket("where is my parcel"), None, Budget::unlimited())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            RunError::Guardrail { at, stage: GuardStage::RunInput, reason, .. }
+                if &**at == "$" && reason.contains("`ticket`")
+        ),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn run_stages_flag_and_rewrite_and_observers_see_them() {
+    let interp = interpreter(
+        "Your parcel ships today.",
+        vec![
+            Guardrail::value("urgent", ValueStage::RunInput, |_, input: &JsonMap| {
+                match input["ticket"].as_str() {
+                    Some(text) if text.contains("ASAP") => Verdict::Flag("urgent ticket".into()),
+                    _ => Verdict::Allow,
+                }
+            }),
+            Guardrail::value("sign", ValueStage::RunOutput, |_, output: &JsonMap| {
+                let mut value = output.clone();
+                let reply = format!("{} (Support)", output["reply"].as_str().unwrap());
+                value.insert("reply".into(), json!(reply));
+                Verdict::Rewrite {
+                    value,
+                    reason: "signed".into(),
+                }
+            }),
+        ],
+    )
+    .await;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let run = interp
+        .run_with(
+            ticket("where is my parcel, ASAP"),
+            None,
+            Budget::unlimited(),
+            RunOptions::new().with_observer(move |event: &RunEvent| {
+                if let RunEvent::Guardrail {
+                    node,
+                    guard,
+                    action,
+                    ..
+                } = event
+                {
+                    sink.lock()
+                        .unwrap()
+                        .push((node.clone(), guard.clone(), *action));
+                }
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(run.output["reply"], "Your parcel ships today. (Support)");
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            ("$".to_string(), "urgent".to_string(), GuardAction::Flag),
+            ("$".to_string(), "sign".to_string(), GuardAction::Rewrite),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn javascript_validators_run_in_the_sandbox_and_fail_closed() {
+    let load = |source: &'static str| async move {
+        let env = RuntimeEnv::new()
+            .with_dry_run(3)
+            .with_sandbox(Arc::new(dsrs_tools::QuickJsExecutor::new()))
+            .bind_host_hole("polisher", |_: JsonMap| async {
+                Ok(json!({ "reply": "Call us at 555-0100." }))
+            })
+            .with_guardrail(Guardrail::js("phone", ValueStage::RunOutput, source));
+        Interpreter::load(Program::from_dsrs(SUPPORT).unwrap(), env)
+            .await
+            .unwrap()
+    };
+    let run = |interp: Interpreter| async move {
+        interp
+            .run(ticket("where is my parcel"), None, Budget::unlimited())
+            .await
+    };
+
+    let redacting = load(
+        r#"(args) => /\d{3}-\d{4}/.test(args.value.reply)
+            ? { rewrite: { reply: args.value.reply.replace(/\d{3}-\d{4}/g, "[phone]") }, reason: "phone number" }
+            : null"#,
+    )
+    .await;
+    assert_eq!(
+        run(redacting).await.unwrap()["reply"],
+        "Call us at [phone]."
+    );
+
+    let throwing = load(r#"(args) => { throw new Error("validator down"); }"#).await;
+    let err = run(throwing).await.unwrap_err();
+    assert!(
+        matches!(&err, RunError::Guardrail { at, reason, .. } if &**at == "$" && reason.contains("validator failed")),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn javascript_validators_need_a_sandbox() {
+    let env = RuntimeEnv::new()
+        .with_dry_run(3)
+        .with_guardrail(Guardrail::js("phone", ValueStage::Output, "(args) => null"));
+    let err = Interpreter::load(Program::from_dsrs(SUPPORT).unwrap(), env)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, LoadError::GuardrailSandboxMissing { ref name } if name == "phone"));
+}
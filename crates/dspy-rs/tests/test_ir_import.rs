@@ -0,0 +1,239 @@
+//! Multi-file programs: `import "<path>" as <alias>` and `call` nodes — a QA
+//! program reusing a retrieval sub-pipeline from `lib/retrieve.dsrs`, with
+//! namespaced param paths, content-hash pins, error positions at the import,
+//! and a run through the spliced body.
+
+use std::path::Path;
+
+use dspy_rs::ir::{self, BakeError, Budget, Interpreter, Overlay, Program, RuntimeEnv};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const RETRIEVE: &str = r#"dsrs 1
+program retrieve
+
+model fast = "openai:gpt-4o-mini"
+
+sig Retrieve {
+  in query: string
+  out passage: string
+}
+
+sig Rewrite {
+  "Rewrite the question as a search query."
+  in query: string
+  out search_query: string
+}
+
+main: Retrieve = seq {
+  search = predict Rewrite (query = $.query) @fast
+  fetch = hole Retrieve (query = search.search_query) caps [] extern "00000000000000aa"
+  out { passage = fetch.passage }
+}
+"#;
+
+const QA: &str = r#"dsrs 1
+program qa
+
+import "lib/retrieve.dsrs" as retrieve
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in question: string
+  out answer: string
+}
+
+sig Answer {
+  in question: string
+  in passage: string
+  out answer: string
+}
+
+main: Main = seq {
+  retrieve = call retrieve (query = $.question)
+  answerer = predict Answer (question = $.question, passage = retrieve.passage) @m
+  out { answer = answerer.answer }
+}
+"#;
+
+/// Writes `files` (relative path, contents) under a fresh temp dir.
+fn tree(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, text) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+fn load(dir: &Path, file: &str) -> Result<Program, String> {
+    Program::load_dsrs(dir.join(file)).map_err(|err| err.to_string())
+}
+
+#[test]
+fn calls_splice_the_import_under_namespaced_paths() {
+    let dir = tree(&[("lib/retrieve.dsrs", RETRIEVE), ("qa.dsrs", QA)]);
+    let callee = load(dir.path(), "lib/retrieve.dsrs").unwrap();
+    let program = load(dir.path(), "qa.dsrs").unwrap();
+
+    assert!(program.leaf_id("retrieve.search").is_some());
+    assert!(program.leaf_id("retrieve.fetch").is_some());
+    assert!(
+        program
+            .slot_of::<ir::Instruction>("retrieve.search.instruction")
+            .is_some()
+    );
+    assert!(
+        program
+            .slot_of::<ir::Instruction>("answerer.instruction")
+            .is_some()
+    );
+
+    // The callee prints as its pin, nothing more.
+    let printed = program.to_dsrs();
+    let pin = format!("{:016x}", callee.meta.program_hash);
+    assert!(
+        printed.contains(&format!(
+            "import \"lib/retrieve.dsrs\" as retrieve pin \"{pin}\""
+        )),
+        "{printed}"
+    );
+    assert!(
+        printed.contains("retrieve = call retrieve (query = $.question)"),
+        "{printed}"
+    );
+    assert!(!printed.contains("sig Rewrite"), "{printed}");
+    assert!(!printed.contains("model fast"), "{printed}");
+
+    let reparsed = Program::from_dsrs_in(&printed, dir.path()).unwrap();
+    assert_eq!(reparsed.to_dsrs(), printed);
+    assert_eq!(reparsed.meta.program_hash, program.meta.program_hash);
+}
+
+#[test]
+fn editing_an_import_changes_the_hash_and_breaks_its_pin() {
+    let dir = tree(&[("lib/retrieve.dsrs", RETRIEVE), ("qa.dsrs", QA)]);
+    let before = load(dir.path(), "qa.dsrs").unwrap();
+    std::fs::write(dir.path().join("pinned.dsrs"), before.to_dsrs()).unwrap();
+
+    std::fs::write(
+        dir.path().join("lib/retrieve.dsrs"),
+        RETRIEVE.replace("as a search query.", "as a short search query."),
+    )
+    .unwrap();
+    let after = load(dir.path(), "qa.dsrs").unwrap();
+    assert_ne!(after.meta.program_hash, before.meta.program_hash);
+
+    let err = load(dir.path(), "pinned.dsrs").unwrap_err();
+    assert!(err.contains("import `retrieve` is pinned to"), "{err}");
+    assert!(err.contains("line 4"), "{err}");
+}
+
+#[test]
+fn import_errors_are_reported_at_the_import() {
+    // Errors inside the imported file carry its own position.
+    let broken = RETRIEVE.replace("@fast", "@slow");
+    let dir = tree(&[("lib/retrieve.dsrs", &broken), ("qa.dsrs", QA)]);
+    let err = load(dir.path(), "qa.dsrs").unwrap_err();
+    assert!(
+        err.contains("line 4, column 8: in imported `lib/retrieve.dsrs` (line 18"),
+        "{err}"
+    );
+    assert!(err.contains("unknown model `@slow`"), "{err}");
+
+    // A file importing itself through another one.
+    let looping = RETRIEVE.replace(
+        "program retrieve\n",
+        "program retrieve\n\nimport \"../qa.dsrs\" as qa\n",
+    );
+    let dir = tree(&[("lib/retrieve.dsrs", &looping), ("qa.dsrs", QA)]);
+    let err = load(dir.path(), "qa.dsrs").unwrap_err();
+    assert!(err.contains("import cycle"), "{err}");
+
+    // Plain text has no directory to resolve against.
+    let err = Program::from_dsrs(QA).unwrap_err().to_string();
+    assert!(err.contains("Program::from_dsrs_in"), "{err}");
+
+    // The callee's capabilities must fit the importer's ceiling.
+    let networked = RETRIEVE
+        .replace(
+            "program retrieve\n",
+            "program retrieve\n\ncaps { net:search }\n",
+        )
+        .replace("caps [] extern", "caps [net:search] extern");
+    let dir = tree(&[("lib/retrieve.dsrs", &networked), ("qa.dsrs", QA)]);
+    let err = load(dir.path(), "qa.dsrs").unwrap_err();
+    assert!(
+        err.contains("import `retrieve` requires capability `net:search`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn imported_params_are_overlaid_but_never_baked() {
+    let dir = tree(&[("lib/retrieve.dsrs", RETRIEVE), ("qa.dsrs", QA)]);
+    let program = load(dir.path(), "qa.dsrs").unwrap();
+    let inner = program
+        .slot_of::<ir::Instruction>("retrieve.search.instruction")
+        .unwrap();
+
+    let mut overlay = Overlay::new(&program);
+    overlay.set_instruction(inner, "Rewrite tersely.");
+    let err = program.bake(&overlay, ir::Lineage::default()).unwrap_err();
+    assert!(
+        matches!(&err, BakeError::Imported { path } if path == "retrieve.search.instruction"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn calls_run_their_spliced_body_in_a_fresh_scope() {
+    const FETCH: &str = r#"dsrs 1
+program fetch
+
+sig Fetch {
+  in query: string
+  out passage: string
+}
+
+sig Rewrite {
+  in query: string
+  out search_query: string
+}
+
+main: Fetch = seq {
+  search = transform Rewrite (query = $.query) { search_query = "query ~ ' site:docs'" }
+  fetch = hole Fetch (query = search.search_query) caps [] extern "00000000000000aa"
+  out { passage = fetch.passage }
+}
+"#;
+    const LOOKUP: &str = r#"dsrs 1
+program lookup
+
+import "fetch.dsrs" as fetch
+
+sig Main {
+  in question: string
+  out passage: string
+}
+
+main: Main = seq {
+  docs = call fetch (query = $.question)
+  out { passage = docs.passage }
+}
+"#;
+    let dir = tree(&[("fetch.dsrs", FETCH), ("lookup.dsrs", LOOKUP)]);
+    let program = load(dir.path(), "lookup.dsrs").unwrap();
+    // Host holes inside a call bind by their namespaced leaf name.
+    let env = RuntimeEnv::new().bind_host_hole("docs.fetch", |input: JsonMap| async move {
+        Ok(json!({ "passage": format!("found: {}", input["query"].as_str().unwrap()) }))
+    });
+    let interp = Interpreter::load(program, env).await.unwrap();
+    let input: JsonMap = [("question".to_string(), json!("what is dsrs"))]
+        .into_iter()
+        .collect();
+    let out = interp.run(input, None, Budget::unlimited()).await.unwrap();
+    assert_eq!(out["passage"], json!("found: what is dsrs site:docs"));
+}
@@ -255,6 +255,7 @@ async fn route_selects_the_matching_arm() {
                     name: (*name).to_string(),
                     rendered_name: (*name).to_string(),
                     docs: None,
+                    aliases: Vec::new(),
                 })
                 .collect(),
         },
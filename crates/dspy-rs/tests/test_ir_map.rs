@@ -0,0 +1,270 @@
+//! `Map` nodes: `.dsrs` round trip, element-type validation, bounded
+//! concurrency with element-ordered collection and trace `seq`s, the
+//! fail/skip partial-failure policies, and budget metering across elements.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dspy_rs::ir::{Budget, Interpreter, Program, RunError, RuntimeEnv};
+use dspy_rs::trace::{JsonMap, SpanErrorKind};
+use dspy_rs::{LM, LMClient, TestCompletionModel, capture};
+use rig::completion::AssistantContent;
+use rig::message::Text;
+use serde_json::json;
+
+const SHOUT_ALL: &str = r#"dsrs 1
+program shouting
+
+sig Main {
+  in lines: string[]
+  out loud: string[]
+}
+
+sig Shout {
+  in text: string
+  out loud: string
+}
+
+main: Main = seq {
+  each = map line in $.lines (max_parallel 4) {
+    shouter = hole Shout (text = $.line) caps [] extern "00000000deadbeef"
+  } collect { loud = shouter.loud }
+  out { loud = each.loud }
+}
+"#;
+
+fn obj(pairs: &[(&str, serde_json::Value)]) -> JsonMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+/// Upper-cases `text`; `"slow"` finishes last, `"bad"` fails. Records the
+/// completion order.
+fn shouter_env(finished: Arc<Mutex<Vec<String>>>) -> RuntimeEnv {
+    RuntimeEnv::new().bind_host_hole("shouter", move |input: JsonMap| {
+        let finished = Arc::clone(&finished);
+        async move {
+            let text = input["text"].as_str().unwrap_or_default().to_string();
+            if text == "slow" {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            if text == "bad" {
+                return Err("refused".to_string());
+            }
+            finished.lock().unwrap().push(text.clone());
+            Ok(json!({ "loud": text.to_uppercase() }))
+        }
+    })
+}
+
+fn with_policy(policy: &str) -> Program {
+    Program::from_dsrs(&SHOUT_ALL.replace("(max_parallel 4)", policy)).unwrap()
+}
+
+#[test]
+fn map_round_trips_through_text() {
+    let program = with_policy("(max_parallel 4 on_error skip)");
+    let printed = program.to_dsrs();
+    assert!(
+        printed.contains("each = map line in $.lines (max_parallel 4 on_error skip) {"),
+        "{printed}"
+    );
+    assert!(
+        printed.contains("} collect { loud = shouter.loud }"),
+        "{printed}"
+    );
+    let reparsed = Program::from_dsrs(&printed).unwrap();
+    assert_eq!(reparsed.to_dsrs(), printed);
+    assert_eq!(reparsed.meta.program_hash, program.meta.program_hash);
+
+    // Options are optional: sequential and fail-fast by default.
+    let defaults = with_policy("");
+    assert!(defaults.to_dsrs().contains("(max_parallel 1) {"));
+}
+
+#[test]
+fn map_validates_the_element_type() {
+    let not_a_list = SHOUT_ALL.replace("in lines: string[]", "in lines: string");
+    let err = Program::from_dsrs(&not_a_list).unwrap_err();
+    assert!(err.to_string().contains("must be list-typed"), "{err}");
+
+    // The element is `string`; collected fields are lists of the body's type.
+    let scalar_out = SHOUT_ALL.replace("out loud: string[]\n}", "out loud: string\n}");
+    let err = Program::from_dsrs(&scalar_out).unwrap_err();
+    assert!(err.to_string().contains("loud"), "{err}");
+
+    let shadowing = SHOUT_ALL.replace("map line in", "map lines in");
+    let err = Program::from_dsrs(&shadowing).unwrap_err();
+    assert!(err.to_string().contains("shadows"), "{err}");
+}
+
+#[tokio::test]
+async fn elements_run_concurrently_and_collect_in_element_order() {
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let interp = Interpreter::load(
+        with_policy("(max_parallel 4)"),
+        shouter_env(finished.clone()),
+    )
+    .await
+    .unwrap();
+
+    let (result, trace) = capture(|| {
+        interp.run(
+            obj(&[("lines", json!(["slow", "fast"]))]),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    assert_eq!(result.unwrap()["loud"], json!(["SLOW", "FAST"]));
+    assert_eq!(*finished.lock().unwrap(), ["fast", "slow"]);
+
+    // Trace `seq` follows element order, not completion order.
+    let spans: Vec<_> = trace.for_component("shouter").collect();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(
+        (spans[0].seq, &spans[0].input.as_ref().unwrap()["text"]),
+        (0, &json!("slow"))
+    );
+    assert_eq!(
+        (spans[1].seq, &spans[1].input.as_ref().unwrap()["text"]),
+        (1, &json!("fast"))
+    );
+}
+
+#[tokio::test]
+async fn partial_failures_fail_or_skip_by_policy() {
+    let input = obj(&[("lines", json!(["a", "bad", "c"]))]);
+
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let interp = Interpreter::load(with_policy("(max_parallel 2)"), shouter_env(finished))
+        .await
+        .unwrap();
+    let err = interp
+        .run(input.clone(), None, Budget::unlimited())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, RunError::Hole { ref at, .. } if &**at == "shouter"));
+
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let interp = Interpreter::load(
+        with_policy("(max_parallel 2 on_error skip)"),
+        shouter_env(finished),
+    )
+    .await
+    .unwrap();
+    let output = interp.run(input, None, Budget::unlimited()).await.unwrap();
+    assert_eq!(output["loud"], json!(["A", "C"]));
+}
+
+#[tokio::test]
+async fn a_failed_map_keeps_its_in_flight_elements_in_the_trace() {
+    let finished = Arc::new(Mutex::new(Vec::new()));
+    let interp = Interpreter::load(
+        with_policy("(max_parallel 4)"),
+        shouter_env(finished.clone()),
+    )
+    .await
+    .unwrap();
+
+    let (result, trace) = capture(|| {
+        interp.run(
+            obj(&[("lines", json!(["a", "bad", "slow", "c"]))]),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    assert!(matches!(result, Err(RunError::Hole { .. })));
+    assert!(!finished.lock().unwrap().contains(&"slow".to_string()));
+
+    // Every element that started is traced, in element order: `slow` was
+    // still running when `bad` failed the map, so it closed cancelled.
+    let spans: Vec<_> = trace.for_component("shouter").collect();
+    let texts: Vec<_> = spans
+        .iter()
+        .map(|span| span.input.as_ref().unwrap()["text"].clone())
+        .collect();
+    assert_eq!(texts, [json!("a"), json!("bad"), json!("slow"), json!("c")]);
+    assert!(spans.iter().map(|span| span.seq).eq(0..4));
+    assert!(spans[0].error.is_none());
+    assert!(spans[1].error.is_some());
+    assert_eq!(
+        spans[2].error.as_ref().map(|error| error.kind),
+        Some(SpanErrorKind::Cancelled)
+    );
+    assert!(spans[3].error.is_none());
+}
+
+#[tokio::test]
+async fn elements_share_the_run_budget() {
+    let src = r#"dsrs 1
+program answering
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in questions: string[]
+  out answers: string[]
+}
+
+sig QA {
+  in question: string
+  out answer: string
+}
+
+main: Main = seq {
+  each = map q in $.questions (max_parallel 1 on_error skip) {
+    answerer = predict QA (question = $.q)
+  } collect { answers = answerer.answer }
+  out { answers = each.answers }
+}
+"#;
+    let responses: Vec<AssistantContent> = (0..5)
+        .map(|i| {
+            AssistantContent::Text(Text {
+                text: format!("[[ ## answer ## ]]\n{i}\n\n[[ ## completed ## ]]\n"),
+            })
+        })
+        .collect();
+    let lm = temp_env::async_with_vars(
+        [("OPENAI_API_KEY", Some("test"))],
+        LM::builder()
+            .model("openai:gpt-4o-mini".to_string())
+            .build(),
+    )
+    .await
+    .unwrap()
+    .with_client(LMClient::Test(TestCompletionModel::new(responses)))
+    .await
+    .unwrap();
+    let interp = Interpreter::load(
+        Program::from_dsrs(src).unwrap(),
+        RuntimeEnv::new().bind_model("m", Arc::new(lm)),
+    )
+    .await
+    .unwrap();
+
+    let input = obj(&[("questions", json!(["x", "y", "z"]))]);
+    let output = interp
+        .run(input.clone(), None, Budget::unlimited())
+        .await
+        .unwrap();
+    assert_eq!(output["answers"], json!(["0", "1", "2"]));
+
+    // Budget exhaustion is never skipped: the third element's call is refused.
+    let err = interp
+        .run(
+            input,
+            None,
+            Budget {
+                max_lm_calls: Some(2),
+                ..Budget::unlimited()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, RunError::Budget { ref at } if &**at == "answerer"));
+}
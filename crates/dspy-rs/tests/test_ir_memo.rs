@@ -0,0 +1,234 @@
+//! Node memos: a leaf with `cache { ... }` is served from an earlier
+//! evaluation with the same resolved input and the same resolved parameter
+//! values — across runs and overlays on one interpreter — and still records
+//! a span, marked `SpanEvent::Memo`.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use dspy_rs::capture;
+use dspy_rs::ir::{Budget, Interpreter, MemoPolicy, Overlay, ParamValue, Program, RuntimeEnv};
+use dspy_rs::trace::{JsonMap, SpanEvent, Trace};
+use serde_json::json;
+
+const PIPELINE: &str = r#"dsrs 1
+program pipeline
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in query: string
+  out answer: string
+}
+
+sig Fetch {
+  in query: string
+  out passages: string
+}
+
+sig Summarize {
+  in passages: string
+  out summary: string
+}
+
+sig Answer {
+  in query: string
+  in summary: string
+  out answer: string
+}
+
+main: Main = seq {
+  fetcher = hole Fetch (query = $.query) caps [] extern "00000000000000f1" { cache { ttl_s 3600 } }
+  summarizer = predict Summarize (passages = fetcher.passages) { cache { } }
+  answerer = predict Answer (query = $.query, summary = summarizer.summary)
+  out { answer = answerer.answer }
+}
+"#;
+
+fn query(text: &str) -> JsonMap {
+    [("query".to_string(), json!(text))].into_iter().collect()
+}
+
+/// A dry-run interpreter whose `fetcher` counts its calls.
+async fn interpreter() -> (Interpreter, Arc<AtomicU32>) {
+    interpreter_with(RuntimeEnv::new()).await
+}
+
+async fn interpreter_with(env: RuntimeEnv) -> (Interpreter, Arc<AtomicU32>) {
+    let fetches = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&fetches);
+    let env = env
+        .with_dry_run(11)
+        .bind_host_hole("fetcher", move |input: JsonMap| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(json!({ "passages": format!("about {}", input["query"]) })) }
+        });
+    let program = Program::from_dsrs(PIPELINE).unwrap();
+    (Interpreter::load(program, env).await.unwrap(), fetches)
+}
+
+async fn traced(interp: &Interpreter, input: JsonMap, overlay: Option<Arc<Overlay>>) -> Trace {
+    let (result, trace) = capture(|| interp.run(input, overlay, Budget::unlimited())).await;
+    result.unwrap();
+    trace
+}
+
+/// Whether `component`'s span was served from the memo.
+fn memo_served(trace: &Trace, component: &str) -> bool {
+    let span = trace
+        .spans
+        .iter()
+        .find(|span| trace.component_name(span.component) == component)
+        .unwrap();
+    matches!(span.events.as_slice(), [SpanEvent::Memo { .. }])
+}
+
+fn instruction(program: &Program, leaf: &str, text: &str) -> Arc<Overlay> {
+    let mut overlay = Overlay::new(program);
+    let id = program.param_id(&format!("{leaf}.instruction")).unwrap();
+    overlay
+        .set(
+            program,
+            id,
+            ParamValue::Instruction {
+                text: text.to_string(),
+            },
+        )
+        .unwrap();
+    Arc::new(overlay)
+}
+
+#[tokio::test]
+async fn memoized_leaves_serve_later_runs() {
+    let (interp, fetches) = interpreter().await;
+
+    let first = traced(&interp, query("tides"), None).await;
+    assert!(!memo_served(&first, "fetcher"));
+    assert!(!memo_served(&first, "summarizer"));
+    assert_eq!(interp.memo_len(), 2);
+
+    let second = traced(&interp, query("tides"), None).await;
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    assert!(memo_served(&second, "fetcher"));
+    assert!(memo_served(&second, "summarizer"));
+    // Leaves without a policy always run.
+    assert!(!memo_served(&second, "answerer"));
+
+    // Served spans carry the stored output and no usage.
+    let output = |trace: &Trace, component: &str| {
+        trace
+            .spans
+            .iter()
+            .find(|span| trace.component_name(span.component) == component)
+            .map(|span| (span.output.clone(), span.usage.total_tokens))
+            .unwrap()
+    };
+    assert_eq!(output(&second, "fetcher"), output(&first, "fetcher"));
+    assert_eq!(output(&second, "summarizer").1, 0);
+
+    // A new input misses; clearing the memo forgets everything.
+    traced(&interp, query("currents"), None).await;
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    interp.clear_memo();
+    assert_eq!(interp.memo_len(), 0);
+    traced(&interp, query("tides"), None).await;
+    assert_eq!(fetches.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn overlays_share_entries_for_the_params_a_leaf_does_not_read() {
+    let (interp, fetches) = interpreter().await;
+    let program = Arc::clone(interp.program());
+    traced(&interp, query("tides"), None).await;
+
+    // A candidate that only rewrites the downstream leaf reuses both
+    // upstream leaves.
+    let downstream = instruction(&program, "answerer", "Answer in one word.");
+    let trace = traced(&interp, query("tides"), Some(downstream)).await;
+    assert!(memo_served(&trace, "fetcher"));
+    assert!(memo_served(&trace, "summarizer"));
+
+    // One that rewrites a memoized leaf re-runs it (and only it).
+    let upstream = instruction(&program, "summarizer", "Summarize tersely.");
+    let trace = traced(&interp, query("tides"), Some(upstream)).await;
+    assert!(memo_served(&trace, "fetcher"));
+    assert!(!memo_served(&trace, "summarizer"));
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn a_full_memo_evicts_its_least_recently_used_entries() {
+    // Each run stores two entries (fetcher and summarizer), so four hold
+    // two inputs.
+    let (interp, fetches) = interpreter_with(RuntimeEnv::new().with_memo_capacity(4)).await;
+    traced(&interp, query("tides"), None).await;
+    traced(&interp, query("currents"), None).await;
+    assert_eq!(interp.memo_len(), 4);
+
+    // Serving "tides" makes "currents" the oldest, so a third input evicts
+    // it and the store stays at capacity.
+    assert!(memo_served(
+        &traced(&interp, query("tides"), None).await,
+        "fetcher"
+    ));
+    traced(&interp, query("swells"), None).await;
+    assert_eq!(interp.memo_len(), 4);
+    assert_eq!(fetches.load(Ordering::SeqCst), 3);
+
+    assert!(memo_served(
+        &traced(&interp, query("tides"), None).await,
+        "fetcher"
+    ));
+    assert!(!memo_served(
+        &traced(&interp, query("currents"), None).await,
+        "fetcher"
+    ));
+    assert_eq!(fetches.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn cache_options_print_canonically_and_change_the_hash() {
+    let program = Program::from_dsrs(PIPELINE).unwrap();
+    let text = program.to_dsrs();
+    assert!(
+        text.contains(r#"caps [] extern "00000000000000f1" { cache { ttl_s 3600 } }"#),
+        "{text}"
+    );
+    assert!(text.contains("{ cache { } }"), "{text}");
+    let reparsed = Program::from_dsrs(&text).unwrap();
+    assert_eq!(reparsed.to_dsrs(), text);
+    assert_eq!(reparsed.meta.program_hash, program.meta.program_hash);
+
+    let policy = |program: &Program, leaf: &str| {
+        program
+            .nodes
+            .keys()
+            .find(|&id| program.leaf_name(id) == Some(leaf))
+            .and_then(|id| program.nodes[id].memo())
+    };
+    assert_eq!(
+        policy(&program, "fetcher"),
+        Some(MemoPolicy { ttl_s: Some(3600) })
+    );
+    assert_eq!(policy(&program, "summarizer"), Some(MemoPolicy::default()));
+    assert_eq!(policy(&program, "answerer"), None);
+
+    let uncached = PIPELINE
+        .replace(r#" { cache { ttl_s 3600 } }"#, "")
+        .replace(" { cache { } }", "");
+    assert_ne!(
+        Program::from_dsrs(&uncached).unwrap().meta.program_hash,
+        program.meta.program_hash
+    );
+}
+
+#[test]
+fn malformed_cache_options_are_refused() {
+    let zero = PIPELINE.replace("ttl_s 3600", "ttl_s 0");
+    let err = Program::from_dsrs(&zero).unwrap_err().to_string();
+    assert!(err.contains("`ttl_s` must be at least 1"), "{err}");
+
+    let unknown = PIPELINE.replace("ttl_s 3600", "size 10");
+    let err = Program::from_dsrs(&unknown).unwrap_err().to_string();
+    assert!(err.contains("unknown cache key `size`"), "{err}");
+}
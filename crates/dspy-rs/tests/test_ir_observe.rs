@@ -0,0 +1,211 @@
+//! Observed runs: `RunOptions::with_observer` pushes node start/finish, LM
+//! exchange, route, retry, and budget events to the observer while the run
+//! is happening, in execution order.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dspy_rs::ir::{Budget, Interpreter, Program, RunEvent, RunOptions, RuntimeEnv};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const WATCH: &str = r#"dsrs 1
+program watch
+
+model m = "openai:gpt-4o-mini"
+
+enum Mood {
+  Calm
+  Upset
+}
+
+sig Main {
+  in text: string
+  out reply: string
+}
+
+sig Read {
+  in text: string
+  out mood: Mood
+}
+
+sig Reply {
+  in text: string
+  out reply: string
+}
+
+tool done "Finish with the reply" {
+  in reply: string
+  out reply: string
+}
+
+main: Main = seq {
+  reader = predict Read (text = $.text)
+  router = route reader.mood {
+    Calm -> calm = transform Reply (text = $.text) { reply = "'ok'" }
+    else -> upset = agent Reply (text = $.text) {
+      tools [done]
+      stop_tools [done]
+      max_turns 2
+      until_parse false
+    }
+  }
+  polished = retry (attempts 2) polisher = hole Reply (text = router.reply) caps [] extern "00000000deadbeef"
+  out { reply = polished.reply }
+}
+"#;
+
+/// Loads WATCH as a dry run whose `polisher` hole fails its first call.
+async fn interpreter(seed: u64) -> Interpreter {
+    let calls = Arc::new(AtomicU32::new(0));
+    let env =
+        RuntimeEnv::new()
+            .with_dry_run(seed)
+            .bind_host_hole("polisher", move |input: JsonMap| {
+                let calls = Arc::clone(&calls);
+                async move {
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        return Err("polisher warming up".to_string());
+                    }
+                    Ok(json!({ "reply": input["text"] }))
+                }
+            });
+    Interpreter::load(Program::from_dsrs(WATCH).unwrap(), env)
+        .await
+        .unwrap()
+}
+
+async fn observe(seed: u64) -> (JsonMap, Vec<RunEvent>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let input: JsonMap = [("text".to_string(), json!("where is my parcel"))]
+        .into_iter()
+        .collect();
+    let run = interpreter(seed)
+        .await
+        .run_with(
+            input,
+            None,
+            Budget::unlimited(),
+            RunOptions::new()
+                .with_observer(move |event: &RunEvent| sink.lock().unwrap().push(event.clone())),
+        )
+        .await
+        .unwrap();
+    let events = events.lock().unwrap().clone();
+    (run.output, events)
+}
+
+#[tokio::test]
+async fn events_follow_the_run() {
+    let (output, events) = observe(3).await;
+
+    // The root seq opens first and closes last, without an error.
+    assert!(matches!(
+        &events[0],
+        RunEvent::NodeStart { kind: "seq", .. }
+    ));
+    let Some(RunEvent::NodeFinish {
+        kind: "seq", error, ..
+    }) = events.last()
+    else {
+        panic!("{events:?}");
+    };
+    assert!(error.is_none());
+
+    // Every start has a matching finish, after it.
+    let starts = events
+        .iter()
+        .filter(|e| matches!(e, RunEvent::NodeStart { .. }))
+        .count();
+    let finishes = events
+        .iter()
+        .filter(|e| matches!(e, RunEvent::NodeFinish { .. }))
+        .count();
+    assert_eq!(starts, finishes);
+
+    // The classifier's exchange, then the meter it charged.
+    let position = |pred: &dyn Fn(&RunEvent) -> bool| events.iter().position(pred).unwrap();
+    let exchange =
+        position(&|e| matches!(e, RunEvent::LmExchange { node, .. } if node == "reader"));
+    let RunEvent::LmExchange {
+        model,
+        output: text,
+        ..
+    } = &events[exchange]
+    else {
+        unreachable!()
+    };
+    assert_eq!(model, "openai:gpt-4o-mini");
+    assert!(text.contains("[[ ## mood ## ]]"), "{text}");
+    assert!(matches!(
+        &events[exchange + 1],
+        RunEvent::Budget { node, lm_calls: 1, exhausted: false, .. } if node == "reader"
+    ));
+
+    // The route decision names the arm that ran.
+    let route = position(&|e| matches!(e, RunEvent::Route { .. }));
+    let RunEvent::Route { value, arm, .. } = &events[route] else {
+        unreachable!()
+    };
+    match arm.as_deref() {
+        Some("calm") => {
+            assert_eq!(value, "Calm");
+            assert_eq!(output["reply"], "ok");
+        }
+        Some("upset") => assert_eq!(value, "Upset"),
+        other => panic!("unexpected arm {other:?}"),
+    }
+    assert!(route > exchange);
+
+    // The hole's first failure finishes its node with the error, then the
+    // retry starts attempt 2.
+    let failed = position(
+        &|e| matches!(e, RunEvent::NodeFinish { node, error: Some(_), .. } if node == "polisher"),
+    );
+    assert!(matches!(
+        &events[failed + 1],
+        RunEvent::Retry { attempt: 2, error, .. } if error.contains("polisher warming up")
+    ));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, RunEvent::NodeStart { node, .. } if node == "polisher"))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn the_agent_arm_reports_every_exchange() {
+    // Find a seed whose classifier routes to the agent.
+    for seed in 0..16 {
+        let (_, events) = observe(seed).await;
+        if !events
+            .iter()
+            .any(|e| matches!(e, RunEvent::Route { arm: Some(arm), .. } if arm == "upset"))
+        {
+            continue;
+        }
+        let agent: Vec<&RunEvent> = events
+            .iter()
+            .filter(|e| matches!(e, RunEvent::LmExchange { node, .. } if node == "upset"))
+            .collect();
+        assert_eq!(agent.len(), 1, "{events:?}");
+        let RunEvent::Budget { lm_calls, .. } = events
+            .iter()
+            .rev()
+            .find(|e| matches!(e, RunEvent::Budget { .. }))
+            .unwrap()
+        else {
+            unreachable!()
+        };
+        assert_eq!(*lm_calls, 2);
+
+        let json = serde_json::to_value(agent[0]).unwrap();
+        assert_eq!(json["event"], "lm_exchange");
+        assert_eq!(json["node"], "upset");
+        return;
+    }
+    panic!("no seed routed to the agent");
+}
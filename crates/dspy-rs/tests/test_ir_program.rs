@@ -416,6 +416,7 @@ fn uncovered_route_without_default_is_rejected() {
                     name: (*name).to_string(),
                     rendered_name: (*name).to_string(),
                     docs: None,
+                    aliases: Vec::new(),
                 })
                 .collect(),
         },
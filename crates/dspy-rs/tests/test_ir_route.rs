@@ -0,0 +1,129 @@
+//! Predicate routes: `route <port> { when "<check expr>" -> … else -> … }`
+//! branching on numbers and strings without an enum-producing classifier —
+//! `.dsrs` round trip, first-match evaluation, and load-time refusals.
+
+use dspy_rs::ir::{Budget, Interpreter, Program, RunError, RuntimeEnv};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const GATED: &str = r#"dsrs 1
+program gated
+
+sig Main {
+  in score: float
+  in note: string
+  out verdict: string
+}
+
+sig Verdict {
+  in note: string
+  out verdict: string
+}
+
+main: Main = seq {
+  gate = route $.score {
+    when "this < 0.5" -> redo = transform Verdict (note = $.note) { verdict = "'redo: ' ~ note" }
+    when "this >= 0.9" -> ship = transform Verdict (note = $.note) { verdict = "'ship'" }
+    else -> review = transform Verdict (note = $.note) { verdict = "'review'" }
+  }
+  out { verdict = gate.verdict }
+}
+"#;
+
+fn obj(pairs: &[(&str, serde_json::Value)]) -> JsonMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+async fn run(program: Program, input: JsonMap) -> Result<JsonMap, RunError> {
+    Interpreter::load(program, RuntimeEnv::new())
+        .await
+        .unwrap()
+        .run(input, None, Budget::unlimited())
+        .await
+}
+
+fn load_err(src: &str) -> String {
+    Program::from_dsrs(src).unwrap_err().to_string()
+}
+
+#[test]
+fn predicate_routes_round_trip() {
+    let program = Program::from_dsrs(GATED).unwrap();
+    assert_eq!(program.to_dsrs(), GATED);
+    assert!(program.leaf_id("redo").is_some());
+    assert!(program.leaf_id("review").is_some());
+}
+
+#[tokio::test]
+async fn the_first_holding_predicate_wins() {
+    let program = Program::from_dsrs(GATED).unwrap();
+    for (score, expected) in [(0.2, "redo: too vague"), (0.95, "ship"), (0.7, "review")] {
+        let input = obj(&[("score", json!(score)), ("note", json!("too vague"))]);
+        let out = run(program.clone(), input).await.unwrap();
+        assert_eq!(out["verdict"], json!(expected), "score {score}");
+    }
+}
+
+#[tokio::test]
+async fn predicates_match_strings_like_checks_do() {
+    let src = GATED
+        .replace("route $.score", "route $.note")
+        .replace("this < 0.5", "'refund' in this|lower")
+        .replace("this >= 0.9", "this|length > 20");
+    let program = Program::from_dsrs(&src).unwrap();
+    for (note, expected) in [
+        ("Wants a REFUND", "redo: Wants a REFUND"),
+        ("a long and rambling complaint", "ship"),
+        ("thanks", "review"),
+    ] {
+        let input = obj(&[("score", json!(0.0)), ("note", json!(note))]);
+        let out = run(program.clone(), input).await.unwrap();
+        assert_eq!(out["verdict"], json!(expected), "note {note:?}");
+    }
+}
+
+#[test]
+fn malformed_predicate_routes_are_refused_at_load() {
+    let no_else = GATED.replace(
+        "    else -> review = transform Verdict (note = $.note) { verdict = \"'review'\" }\n",
+        "",
+    );
+    let err = load_err(&no_else);
+    assert!(
+        err.contains("has `when` predicates but no `else` arm"),
+        "{err}"
+    );
+
+    let stray = GATED.replace("this < 0.5", "score < 0.5");
+    let err = load_err(&stray);
+    assert!(
+        err.contains("reads `score`; predicates see only `this`"),
+        "{err}"
+    );
+
+    let broken = GATED.replace("this < 0.5", "this <");
+    let err = load_err(&broken);
+    assert!(err.contains("predicate `this <` does not compile"), "{err}");
+
+    let mixed = GATED.replace("when \"this >= 0.9\" -> ship", "High -> ship");
+    let err = load_err(&mixed);
+    assert!(
+        err.contains("mixes variant arms with `when` predicates"),
+        "{err}"
+    );
+
+    let mismatched = GATED
+        .replace(
+            "sig Verdict {",
+            "sig Score {\n  in note: string\n  out score: float\n}\n\nsig Verdict {",
+        )
+        .replace(
+            "ship = transform Verdict (note = $.note) { verdict = \"'ship'\" }",
+            "ship = transform Score (note = $.note) { score = \"1.0\" }",
+        );
+    let err = load_err(&mismatched);
+    assert!(err.contains("exports a different interface"), "{err}");
+}
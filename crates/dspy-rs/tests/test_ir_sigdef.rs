@@ -38,6 +38,24 @@ struct Graded {
     verdict: String,
 }
 
+#[derive(Signature, Clone, Debug)]
+/// Suggest troubleshooting steps.
+struct Presented {
+    #[input(hidden)]
+    tenant: String,
+
+    #[input]
+    #[prefix("Customer wrote:")]
+    #[order(-1)]
+    #[section("Conversation")]
+    message: String,
+
+    #[output]
+    #[hint("answer as a bullet list")]
+    #[example("- restart the router")]
+    steps: String,
+}
+
 #[derive(Clone, Debug)]
 #[Schema]
 struct Citation {
@@ -122,6 +140,28 @@ fn derive_and_builder_defs_are_equal_with_alias_constraints_format_docs() {
     assert_eq!(*derived, built);
 }
 
+#[test]
+fn derive_and_builder_defs_are_equal_with_presentation() {
+    let derived = SignatureDef::of::<Presented>();
+    let built = SignatureDef::build("Presented")
+        .instruction("Suggest troubleshooting steps.")
+        .input_full(FieldDef::new("tenant", FieldType::String).hidden())
+        .input_full(
+            FieldDef::new("message", FieldType::String)
+                .with_prefix("Customer wrote:")
+                .with_order(-1)
+                .with_section("Conversation"),
+        )
+        .output_full(
+            FieldDef::new("steps", FieldType::String)
+                .with_hint("answer as a bullet list")
+                .with_example("- restart the router"),
+        )
+        .finish()
+        .unwrap();
+    assert_eq!(*derived, built);
+}
+
 #[test]
 fn derive_bridge_carries_class_and_enum_tables() {
     let derived = SignatureDef::of::<Structured>();
@@ -316,6 +356,22 @@ fn builder_rejects_what_the_derive_rejects() {
         Err(SigError::InvalidConstraintExpr { .. })
     ));
 
+    // Presentation metadata on the wrong side.
+    assert!(matches!(
+        SignatureDef::build("s")
+            .input("question", FieldType::String)
+            .output_full(FieldDef::new("answer", FieldType::String).hidden())
+            .finish(),
+        Err(SigError::MisplacedPresentation { what: "hidden", .. })
+    ));
+    assert!(matches!(
+        SignatureDef::build("s")
+            .input_full(FieldDef::new("question", FieldType::String).with_hint("short"))
+            .output("answer", FieldType::String)
+            .finish(),
+        Err(SigError::MisplacedPresentation { what: "hint", .. })
+    ));
+
     // Non-string map keys, however deeply nested.
     assert!(matches!(
         SignatureDef::build("s")
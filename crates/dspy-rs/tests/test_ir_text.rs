@@ -173,7 +173,14 @@ fn scrambled_variant_canonicalizes_to_the_golden() {
     let golden = fixture("qa.dsrs");
     let scrambled = fixture("qa_scrambled.dsrs");
     assert_ne!(golden, scrambled);
-    let parsed = Program::from_dsrs(&scrambled).expect("scrambled variant parses");
+    let mut parsed = Program::from_dsrs(&scrambled).expect("scrambled variant parses");
+    assert!(
+        parsed
+            .to_dsrs()
+            .starts_with("// Same program as qa.dsrs, deliberately non-canonical:\n"),
+        "comments are kept"
+    );
+    parsed.meta.comments = ir::Comments::default();
     assert_eq!(parsed.to_dsrs(), golden, "print . parse = canonical form");
     assert_eq!(
         parsed.meta.program_hash,
@@ -237,6 +244,10 @@ fn kitchen_sink_covers_every_node_kind() {
             ir::Node::Retry(_) => "retry",
             ir::Node::Refine(_) => "refine",
             ir::Node::Loop(_) => "loop",
+            ir::Node::Map(_) => "map",
+            ir::Node::Transform(_) => "transform",
+            ir::Node::Approve(_) => "approve",
+            ir::Node::Call(_) => "call",
             ir::Node::Hole(_) => "hole",
         })
         .collect();
@@ -245,7 +256,18 @@ fn kitchen_sink_covers_every_node_kind() {
     assert_eq!(
         kinds,
         vec![
-            "agent", "fork", "hole", "loop", "predict", "refine", "retry", "route", "seq"
+            "agent",
+            "approve",
+            "fork",
+            "hole",
+            "loop",
+            "map",
+            "predict",
+            "refine",
+            "retry",
+            "route",
+            "seq",
+            "transform"
         ],
         "the kitchen fixture must exercise the whole closed node vocabulary"
     );
@@ -593,6 +615,106 @@ main: Main = seq {
     assert!(err.message.contains("not bound"), "{err}");
 }
 
+#[test]
+fn check_dsrs_reports_every_validation_error_in_source_order() {
+    let src = r#"dsrs 1
+program p
+model only = "openai:gpt-4o-mini"
+sig Main { in question: string out answer: string }
+sig Count { in question: string out count: int }
+sig QA { in question: string out answer: string }
+main: Main = seq {
+  counter = predict Count
+  answerer = predict QA (question = counter.count)
+  checker = predict QA (question = answerer.answer)
+  out { answer = checker.answer }
+}
+"#;
+    let errors = Program::check_dsrs(src, None).expect_err("two errors");
+    let lines: Vec<u32> = errors.iter().map(|err| err.line).collect();
+    assert_eq!(lines, vec![8, 9], "{errors:?}");
+    assert!(errors[0].message.contains("not bound"), "{}", errors[0]);
+    assert!(errors[1].message.contains("type mismatch"), "{}", errors[1]);
+    // The single-error path still reports the first.
+    assert_eq!(parse_err(src), errors[0]);
+
+    // A failed container is not re-reported through every reference to it.
+    let src = r#"dsrs 1
+program p
+model only = "openai:gpt-4o-mini"
+sig Main { in question: string out answer: string }
+sig QA { in question: string out answer: string }
+main: Main = seq {
+  inner = seq {
+    a = predict QA (question = $.question)
+    out { answer = a.nope }
+  }
+  answerer = predict QA (question = inner.answer)
+  out { answer = answerer.answer }
+}
+"#;
+    let errors = Program::check_dsrs(src, None).expect_err("one error");
+    assert_eq!(errors.len(), 1, "{errors:?}");
+    assert!(errors[0].message.contains("nope"), "{}", errors[0]);
+}
+
+#[test]
+fn source_map_points_back_at_nodes_bindings_and_sigs() {
+    let src = r#"dsrs 1
+program p
+model only = "openai:gpt-4o-mini"
+sig Main { in question: string out answer: string }
+main: Main = seq {
+  answerer = predict Main (question = $.question)
+  out { answer = answerer.answer }
+}
+"#;
+    let (program, map) = Program::check_dsrs(src, None).unwrap();
+    let answerer = program.leaf_id("answerer").unwrap();
+    let at = |span: Option<ir::Span>| span.map(|s| (s.line, s.col));
+    assert_eq!(at(map.node(answerer)), Some((6, 3)));
+    assert_eq!(at(map.binding(answerer, "question")), Some((6, 28)));
+    assert_eq!(at(map.node(program.root)), Some((5, 14)));
+    assert_eq!(at(map.binding(program.root, "answer")), Some((7, 9)));
+    assert_eq!(at(map.sig(program.sig)), Some((4, 5)));
+}
+
+#[test]
+fn port_scopes_list_what_each_step_can_read() {
+    let src = r#"dsrs 1
+program p
+model only = "openai:gpt-4o-mini"
+enum Tone {
+  Calm
+  Curt
+}
+sig Main { in question: string out answer: string }
+sig Toned { in question: string out answer: string out tone: Tone }
+main: Main = seq {
+  drafter = predict Toned (question = $.question)
+  answerer = predict Main (question = drafter.answer)
+  out { answer = answerer.answer }
+}
+"#;
+    let (program, map) = Program::check_dsrs(src, None).unwrap();
+    assert_eq!(map.type_decl("Tone").map(|s| (s.line, s.col)), Some((4, 6)));
+
+    let scopes = program.port_scopes();
+    let drafter = program.leaf_id("drafter").unwrap();
+    let answerer = program.leaf_id("answerer").unwrap();
+    assert!(scopes[&drafter].nodes.is_empty());
+    let scope = &scopes[&answerer];
+    assert_eq!(scope.inputs.get("question"), Some(&T::String));
+    assert!(scope.carried.is_empty(), "not in a loop");
+    let (id, iface) = &scope.nodes[0];
+    assert_eq!(*id, drafter);
+    assert_eq!(
+        iface.keys().collect::<Vec<_>>(),
+        vec!["answer", "tone"],
+        "{iface:?}"
+    );
+}
+
 #[test]
 fn unknown_sig_model_and_tool_references_are_named() {
     let err = parse_err("dsrs 1\nprogram p\nmain: Main = seq { out { } }\n");
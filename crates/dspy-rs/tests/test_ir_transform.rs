@@ -0,0 +1,134 @@
+//! `Transform` nodes: `.dsrs` round trip with canonical expression order,
+//! validate-time expression checks, evaluation without an LM, and run-time
+//! output type checks for shapes validation cannot type.
+
+use dspy_rs::ir::{
+    Budget, FieldType, Interpreter, Program, ProgramBuilder, RunError, RuntimeEnv, SignatureDef,
+    input, out, seq, transform,
+};
+use dspy_rs::trace::JsonMap;
+use serde_json::json;
+
+const RESHAPE: &str = r#"dsrs 1
+program reshaping
+
+sig Main {
+  in question: string
+  in parts: string[]
+  out prompt: string
+  out count: int
+}
+
+sig Reshape {
+  in question: string
+  in parts: string[]
+  out prompt: string
+  out count: int
+}
+
+main: Main = seq {
+  shaped = transform Reshape (question = $.question, parts = $.parts) { prompt = "'Q: ' ~ question ~ ' [' ~ parts | join(', ') ~ ']'", count = "parts | length" }
+  out { prompt = shaped.prompt, count = shaped.count }
+}
+"#;
+
+fn obj(pairs: &[(&str, serde_json::Value)]) -> JsonMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+async fn run(program: Program, input: JsonMap) -> Result<JsonMap, RunError> {
+    Interpreter::load(program, RuntimeEnv::new())
+        .await
+        .unwrap()
+        .run(input, None, Budget::unlimited())
+        .await
+}
+
+#[test]
+fn transform_round_trips_in_signature_output_order() {
+    let program = Program::from_dsrs(RESHAPE).unwrap();
+    assert_eq!(program.to_dsrs(), RESHAPE);
+
+    let swapped = RESHAPE.replace(
+        r#"{ prompt = "'Q: ' ~ question ~ ' [' ~ parts | join(', ') ~ ']'", count = "parts | length" }"#,
+        r#"{ count = "parts | length", prompt = "'Q: ' ~ question ~ ' [' ~ parts | join(', ') ~ ']'" }"#,
+    );
+    let reordered = Program::from_dsrs(&swapped).unwrap();
+    assert_eq!(reordered.to_dsrs(), RESHAPE);
+    assert_eq!(reordered.meta.program_hash, program.meta.program_hash);
+}
+
+#[test]
+fn expressions_are_checked_at_validate_time() {
+    let err = |src: &str| Program::from_dsrs(src).unwrap_err().to_string();
+
+    let typo = RESHAPE.replace("count = \"parts | length\"", "count = \"part | length\"");
+    assert!(err(&typo).contains("reads `part`"), "{}", err(&typo));
+
+    let missing = RESHAPE.replace(", count = \"parts | length\"", "");
+    assert!(err(&missing).contains("`count` has no expression"));
+
+    let unknown = RESHAPE.replace("count = ", "total = ");
+    assert!(err(&unknown).contains("`total` is not an output field"));
+
+    let broken = RESHAPE.replace("parts | length", "parts |");
+    assert!(err(&broken).contains("does not compile"));
+
+    // Statically typed shapes must fit the output: `length` is an int.
+    let mistyped = RESHAPE.replace("out count: int\n}\n\nmain", "out count: bool\n}\n\nmain");
+    assert!(
+        err(&mistyped).contains("yields int, expected bool"),
+        "{}",
+        err(&mistyped)
+    );
+}
+
+#[tokio::test]
+async fn transforms_compute_outputs_without_a_model() {
+    let output = run(
+        Program::from_dsrs(RESHAPE).unwrap(),
+        obj(&[("question", json!("why?")), ("parts", json!(["a", "b"]))]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(output["prompt"], json!("Q: why? [a, b]"));
+    assert_eq!(output["count"], json!(2));
+}
+
+#[tokio::test]
+async fn untyped_shapes_are_checked_when_they_run() {
+    let main = SignatureDef::build("Main")
+        .input("parts", FieldType::List(Box::new(FieldType::String)))
+        .output("first", FieldType::String)
+        .finish()
+        .unwrap();
+    let mut b = ProgramBuilder::new("picking");
+    let sig = b.sig(main);
+    let program = b
+        .main(
+            sig,
+            seq([transform("picker", sig)
+                .bind("parts", input("parts"))
+                .expr("first", "parts[0] if parts else none")])
+            .out("first", out("picker", "first")),
+        )
+        .unwrap();
+
+    let output = run(program.clone(), obj(&[("parts", json!(["x", "y"]))]))
+        .await
+        .unwrap();
+    assert_eq!(output["first"], json!("x"));
+
+    // `none` is not a string: refused at run time, and not retryable.
+    let err = run(program, obj(&[("parts", json!([]))]))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, RunError::Transform { ref at, ref field, .. } if &**at == "picker" && &**field == "first"),
+        "{err}"
+    );
+    assert!(!err.retryable());
+}
@@ -0,0 +1,353 @@
+//! Context windows: a model's `context_window` and `tokenizer` have every
+//! leaf prompt counted before it is sent, and the runtime's
+//! `OverflowPolicy` trims demos, truncates inputs, or fails the leaf with
+//! `RunError::ContextWindow`.
+
+use std::sync::Arc;
+
+use dspy_rs::ir::{
+    Budget, Interpreter, LoadError, OverflowPolicy, Program, RunError, RuntimeEnv,
+    TRUNCATION_MARKER,
+};
+use dspy_rs::trace::{JsonMap, Span, SpanErrorKind, SpanEvent, Trace, capture};
+use dspy_rs::{Chat, Tokenizer};
+use serde_json::json;
+
+/// A summarizer on `@m` with `options` as its model options and two long
+/// demos, each far over a 1000-token window on its own.
+fn source(options: &str) -> String {
+    let demo = |tag: &str| {
+        json!({
+            "input": { "doc": format!("{tag} {}", "filler ".repeat(700)) },
+            "output": { "summary": tag },
+        })
+    };
+    format!(
+        r#"dsrs 1
+program digest
+
+model m = "openai:gpt-4o-mini" {{ {options} }}
+
+sig Main {{
+  in doc: string
+  out summary: string
+}}
+
+sig Summarize {{
+  "Summarize the document."
+  in doc: string
+  out summary: string
+}}
+
+main: Main = seq {{
+  summarizer = predict Summarize (doc = $.doc) {{
+    demos [{}, {}]
+  }}
+  out {{ summary = summarizer.summary }}
+}}
+"#,
+        demo("first-demo"),
+        demo("second-demo"),
+    )
+}
+
+/// Room for the bare prompt and a short document, not for a demo.
+const TIGHT: &str = r#"max_tokens 100 context_window 1100 tokenizer "bytes""#;
+
+async fn interpreter(options: &str, env: RuntimeEnv) -> Result<Interpreter, LoadError> {
+    Interpreter::load(
+        Program::from_dsrs(&source(options)).unwrap(),
+        env.with_dry_run(5),
+    )
+    .await
+}
+
+async fn run(interp: &Interpreter, doc: &str) -> (Result<JsonMap, RunError>, Trace) {
+    let input: JsonMap = [("doc".to_string(), json!(doc))].into_iter().collect();
+    capture(|| interp.run(input, None, Budget::unlimited())).await
+}
+
+fn summarizer(trace: &Trace) -> &Span {
+    trace
+        .spans
+        .iter()
+        .find(|span| trace.component_name(span.component) == "summarizer")
+        .unwrap()
+}
+
+/// The span's `PromptTokens` event as (tokenizer, tokens, limit, demos
+/// dropped, truncated inputs).
+fn count(span: &Span) -> (&str, u32, Option<u32>, u32, &[String]) {
+    span.events
+        .iter()
+        .find_map(|event| match event {
+            SpanEvent::PromptTokens {
+                tokenizer,
+                prompt_tokens,
+                limit,
+                demos_dropped,
+                truncated,
+            } => Some((
+                tokenizer.as_str(),
+                *prompt_tokens,
+                *limit,
+                *demos_dropped,
+                truncated.as_slice(),
+            )),
+            _ => None,
+        })
+        .expect("a PromptTokens event")
+}
+
+fn sent(trace: &Trace) -> String {
+    format!("{:?}", trace.prompt(summarizer(trace)))
+}
+
+#[tokio::test]
+async fn prompts_are_counted_against_the_window_less_max_tokens() {
+    let interp = interpreter(
+        r#"max_tokens 100 context_window 8000 tokenizer "bytes""#,
+        RuntimeEnv::new(),
+    )
+    .await
+    .unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+    result.unwrap();
+
+    let (tokenizer, tokens, limit, dropped, truncated) = count(summarizer(&trace));
+    assert_eq!(tokenizer, "bytes");
+    assert_eq!(limit, Some(7900));
+    assert!((701..=7900).contains(&tokens), "{tokens}");
+    assert_eq!((dropped, truncated.len()), (0, 0));
+}
+
+#[tokio::test]
+async fn an_overflowing_prompt_fails_before_it_is_sent() {
+    let interp = interpreter(TIGHT, RuntimeEnv::new()).await.unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+
+    let (at, model, prompt_tokens, limit) = match result {
+        Err(RunError::ContextWindow {
+            at,
+            model,
+            prompt_tokens,
+            limit,
+        }) => (at, model, prompt_tokens, limit),
+        other => panic!("expected a context-window error, got {other:?}"),
+    };
+    assert_eq!(
+        (&*at, model.as_str(), limit),
+        ("summarizer", "openai:gpt-4o-mini", 1000)
+    );
+    assert!(prompt_tokens > limit);
+    assert!(
+        !RunError::ContextWindow {
+            at,
+            model,
+            prompt_tokens,
+            limit
+        }
+        .retryable()
+    );
+
+    let span = summarizer(&trace);
+    assert_eq!(
+        span.error.as_ref().map(|error| error.kind),
+        Some(SpanErrorKind::ContextWindow)
+    );
+    assert_eq!(count(span).1, prompt_tokens);
+    assert!(span.output.is_none());
+}
+
+#[tokio::test]
+async fn trim_demos_drops_demos_until_the_prompt_fits() {
+    let env = RuntimeEnv::new().with_overflow_policy(OverflowPolicy::fail().trim_demos());
+    let interp = interpreter(TIGHT, env).await.unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+    result.unwrap();
+
+    let (_, tokens, limit, dropped, truncated) = count(summarizer(&trace));
+    assert_eq!(dropped, 2);
+    assert!(truncated.is_empty());
+    assert!(tokens <= limit.unwrap());
+    assert!(!sent(&trace).contains("first-demo"));
+}
+
+#[tokio::test]
+async fn truncate_inputs_shortens_the_longest_input() {
+    let env = RuntimeEnv::new()
+        .with_overflow_policy(OverflowPolicy::fail().trim_demos().truncate_inputs());
+    let interp = interpreter(TIGHT, env).await.unwrap();
+    let doc = format!("opening {}closing", "lorem ipsum ".repeat(600));
+    let (result, trace) = run(&interp, &doc).await;
+    result.unwrap();
+
+    let (_, tokens, limit, dropped, truncated) = count(summarizer(&trace));
+    assert_eq!(dropped, 2);
+    assert_eq!(truncated, ["doc"]);
+    assert!(tokens <= limit.unwrap(), "{tokens}");
+    let prompt = sent(&trace);
+    assert!(prompt.contains("opening lorem ipsum"));
+    assert!(prompt.contains(TRUNCATION_MARKER));
+    assert!(!prompt.contains("closing"));
+}
+
+#[tokio::test]
+async fn truncating_fails_when_there_is_nothing_left_to_cut() {
+    // Demos kept and no string input long enough to matter.
+    let env = RuntimeEnv::new().with_overflow_policy(OverflowPolicy::fail().truncate_inputs());
+    let interp = interpreter(TIGHT, env).await.unwrap();
+    let (result, _) = run(&interp, "short").await;
+    assert!(
+        matches!(result, Err(RunError::ContextWindow { .. })),
+        "{result:?}"
+    );
+}
+
+/// One token per whitespace-separated word.
+struct Words;
+
+impl Tokenizer for Words {
+    fn name(&self) -> &str {
+        "words"
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+#[tokio::test]
+async fn models_name_bound_tokenizers() {
+    let options = r#"context_window 100000 tokenizer "words""#;
+    let err = interpreter(options, RuntimeEnv::new()).await.unwrap_err();
+    assert!(
+        matches!(&err, LoadError::UnknownTokenizer { model, name } if model == "m" && name == "words"),
+        "{err}"
+    );
+
+    let interp = interpreter(
+        options,
+        RuntimeEnv::new().bind_tokenizer("words", Arc::new(Words)),
+    )
+    .await
+    .unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+    result.unwrap();
+    let (tokenizer, tokens, ..) = count(summarizer(&trace));
+    assert_eq!(tokenizer, "words");
+    // ~700 filler words per demo.
+    assert!((1401..3000).contains(&tokens), "{tokens}");
+}
+
+#[cfg(feature = "tiktoken")]
+#[tokio::test]
+async fn the_model_family_picks_the_default_tokenizer() {
+    let interp = interpreter("context_window 128000", RuntimeEnv::new())
+        .await
+        .unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+    result.unwrap();
+    assert_eq!(count(summarizer(&trace)).0, "o200k_base");
+}
+
+#[tokio::test]
+async fn models_without_a_window_are_not_counted() {
+    let interp = interpreter("max_tokens 100", RuntimeEnv::new())
+        .await
+        .unwrap();
+    let (result, trace) = run(&interp, "a short document").await;
+    result.unwrap();
+    assert!(
+        !summarizer(&trace)
+            .events
+            .iter()
+            .any(|event| matches!(event, SpanEvent::PromptTokens { .. }))
+    );
+}
+
+#[test]
+fn window_options_round_trip_through_dsrs() {
+    let program = Program::from_dsrs(&source(
+        r#"max_tokens 100 context_window 128000 tokenizer "o200k_base""#,
+    ))
+    .unwrap();
+    let printed = program.to_dsrs();
+    assert!(
+        printed.contains(
+            r#"model m = "openai:gpt-4o-mini" { max_tokens 100 context_window 128000 tokenizer "o200k_base" }"#
+        ),
+        "{printed}"
+    );
+    assert_eq!(Program::from_dsrs(&printed).unwrap().to_dsrs(), printed);
+
+    let config = &program.models.values().next().unwrap().config;
+    assert_eq!(config.context_window, Some(128000));
+    assert_eq!(config.tokenizer.as_deref(), Some("o200k_base"));
+
+    let err = Program::from_dsrs(&source("context_window 0"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("at least 1"), "{err}");
+}
+
+/// A one-agent program with a tool, for the conversation surface.
+const HELPDESK: &str = r#"dsrs 1
+program helpdesk
+
+model m = "openai:gpt-4o-mini" { max_tokens 100 context_window 100000 tokenizer "bytes" }
+
+sig Main {
+  in question: string
+  out answer: string
+}
+
+tool search "Search the help center for articles matching the query." {
+  in query: string
+  out results: string
+}
+
+main: Main = seq {
+  helper = agent Main @m (question = $.question) {
+    tools [search]
+    max_turns 2
+  }
+  out { answer = helper.answer }
+}
+"#;
+
+#[tokio::test]
+async fn conversation_openings_count_agent_tools_like_runs() {
+    let interp = Interpreter::load(
+        Program::from_dsrs(HELPDESK).unwrap(),
+        RuntimeEnv::new().with_dry_run(5),
+    )
+    .await
+    .unwrap();
+    let input: JsonMap = [("question".to_string(), json!("how do I reset my password?"))]
+        .into_iter()
+        .collect();
+    let tokens = |trace: &Trace| {
+        let helper = trace
+            .spans
+            .iter()
+            .find(|span| trace.component_name(span.component) == "helper")
+            .unwrap();
+        count(helper).1
+    };
+
+    let (result, run_trace) =
+        capture(|| interp.run(input.clone(), None, Budget::unlimited())).await;
+    result.unwrap();
+    let (result, turn_trace) = capture(|| {
+        interp.run_conversation(
+            Chat::new(Vec::new()),
+            Some(input),
+            None,
+            Budget::unlimited(),
+        )
+    })
+    .await;
+    result.unwrap();
+    assert_eq!(tokens(&turn_trace), tokens(&run_trace));
+}
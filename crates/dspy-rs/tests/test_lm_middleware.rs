@@ -0,0 +1,248 @@
+//! LM middleware: interceptors that see every provider round trip and every
+//! finished call, in chain order, inside the transient-failure retry — on a
+//! bare `LM` and on every model an interpreter loads.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use dspy_rs::ir::{Budget, Interpreter, Program, RuntimeEnv};
+use dspy_rs::trace::JsonMap;
+use dspy_rs::{Chat, LM, LMClient, LMResponse, LmMiddleware, Message, Next, TestCompletionModel};
+use rig::OneOrMany;
+use rig::completion::{
+    AssistantContent, CompletionError, CompletionRequest, CompletionResponse, Usage,
+};
+use rig::message::Text;
+use serde_json::json;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+fn text(text: &str) -> AssistantContent {
+    AssistantContent::Text(Text {
+        text: text.to_string(),
+    })
+}
+
+async fn make_test_lm(client: TestCompletionModel, max_retries: u32) -> LM {
+    temp_env::async_with_vars(
+        [("OPENAI_API_KEY", Some("test"))],
+        LM::builder()
+            .model("openai:gpt-4o-mini".to_string())
+            .max_retries(max_retries)
+            .retry_base_delay_ms(1)
+            .build(),
+    )
+    .await
+    .unwrap()
+    .with_client(LMClient::Test(client))
+    .await
+    .unwrap()
+}
+
+/// Logs its name around each round trip and each finished call, and tags
+/// the request's preamble so later links can see who ran before them.
+struct Tracer {
+    name: &'static str,
+    log: Log,
+}
+
+#[async_trait::async_trait]
+impl LmMiddleware for Tracer {
+    async fn complete(
+        &self,
+        mut request: CompletionRequest,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse<()>, CompletionError> {
+        self.log.lock().unwrap().push(format!("{} >", self.name));
+        let preamble = request.preamble.take().unwrap_or_default();
+        request.preamble = Some(format!("{preamble}[{}]", self.name));
+        let response = next.run(request).await;
+        self.log.lock().unwrap().push(format!("{} <", self.name));
+        response
+    }
+
+    fn respond(&self, response: LMResponse) -> Result<LMResponse> {
+        self.log.lock().unwrap().push(format!("{} done", self.name));
+        Ok(response)
+    }
+}
+
+/// Fails the first `failures` round trips with a rate-limit error.
+struct Flaky {
+    failures: Mutex<u32>,
+}
+
+#[async_trait::async_trait]
+impl LmMiddleware for Flaky {
+    async fn complete(
+        &self,
+        request: CompletionRequest,
+        next: Next<'_>,
+    ) -> Result<CompletionResponse<()>, CompletionError> {
+        {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(CompletionError::ProviderError(
+                    "429 too many requests".to_string(),
+                ));
+            }
+        }
+        next.run(request).await
+    }
+}
+
+/// Answers every round trip itself, never reaching the provider.
+struct Canned(&'static str);
+
+#[async_trait::async_trait]
+impl LmMiddleware for Canned {
+    async fn complete(
+        &self,
+        _request: CompletionRequest,
+        _next: Next<'_>,
+    ) -> Result<CompletionResponse<()>, CompletionError> {
+        Ok(CompletionResponse {
+            choice: OneOrMany::one(text(self.0)),
+            usage: Usage::new(),
+            raw_response: (),
+            message_id: None,
+        })
+    }
+}
+
+/// Upper-cases the final answer.
+struct Shout;
+
+impl LmMiddleware for Shout {
+    fn respond(&self, mut response: LMResponse) -> Result<LMResponse> {
+        response.output = Message::assistant(response.output.content().to_uppercase());
+        Ok(response)
+    }
+}
+
+fn chat(text: &str) -> Chat {
+    Chat::new(vec![Message::system("Be brief."), Message::user(text)])
+}
+
+#[tokio::test]
+async fn middlewares_wrap_each_round_trip_in_the_order_added() {
+    let client = TestCompletionModel::new([text("hello")]);
+    let log = Log::default();
+    let lm = make_test_lm(client.clone(), 0)
+        .await
+        .with_middleware(Tracer {
+            name: "outer",
+            log: Arc::clone(&log),
+        })
+        .with_middleware(Tracer {
+            name: "inner",
+            log: Arc::clone(&log),
+        })
+        .with_middleware(Shout);
+    assert_eq!(lm.middleware().len(), 3);
+
+    let response = lm.call(chat("hi"), Vec::new()).await.unwrap();
+    assert_eq!(response.output.content(), "HELLO");
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "outer >",
+            "inner >",
+            "inner <",
+            "outer <",
+            "inner done",
+            "outer done"
+        ]
+    );
+    // The provider saw the request as rewritten by both links, in order.
+    assert_eq!(
+        client.last_request().unwrap().preamble.as_deref(),
+        Some("Be brief.[outer][inner]")
+    );
+}
+
+#[tokio::test]
+async fn injected_faults_are_retried_like_provider_errors() {
+    let client = TestCompletionModel::new([text("recovered")]);
+    let lm = make_test_lm(client, 2).await.with_middleware(Flaky {
+        failures: Mutex::new(2),
+    });
+    let response = lm.call(chat("hi"), Vec::new()).await.unwrap();
+    assert_eq!(response.output.content(), "recovered");
+
+    let lm = make_test_lm(TestCompletionModel::new([text("never")]), 1)
+        .await
+        .with_middleware(Flaky {
+            failures: Mutex::new(2),
+        });
+    let err = lm.call(chat("hi"), Vec::new()).await.unwrap_err();
+    assert!(err.to_string().contains("429"), "{err}");
+}
+
+#[tokio::test]
+async fn a_middleware_can_answer_without_the_provider() {
+    // The provider's queue is empty: any round trip reaching it would fail.
+    let client = TestCompletionModel::new(Vec::<AssistantContent>::new());
+    let lm = make_test_lm(client.clone(), 0)
+        .await
+        .with_middleware(Canned("from the middleware"));
+    let response = lm.call(chat("hi"), Vec::new()).await.unwrap();
+    assert_eq!(response.output.content(), "from the middleware");
+    assert!(client.last_request().is_none());
+}
+
+#[tokio::test]
+async fn runtime_env_middleware_wraps_every_bound_model() {
+    let src = r#"dsrs 1
+program echo
+
+model m = "openai:gpt-4o-mini"
+
+sig Main {
+  in question: string
+  out answer: string
+}
+
+main: Main = seq {
+  answerer = predict Main (question = $.question)
+  out { answer = answerer.answer }
+}
+"#;
+    let log = Log::default();
+    let lm = make_test_lm(
+        TestCompletionModel::new([text("[[ ## answer ## ]]\nyes\n\n[[ ## completed ## ]]\n")]),
+        0,
+    )
+    .await
+    .with_middleware(Tracer {
+        name: "model",
+        log: Arc::clone(&log),
+    });
+    let env = RuntimeEnv::new()
+        .bind_model("m", Arc::new(lm))
+        .with_lm_middleware(Tracer {
+            name: "env",
+            log: Arc::clone(&log),
+        });
+    let interp = Interpreter::load(Program::from_dsrs(src).unwrap(), env)
+        .await
+        .unwrap();
+
+    let input: JsonMap = [("question".to_string(), json!("ok?"))]
+        .into_iter()
+        .collect();
+    let output = interp.run(input, None, Budget::unlimited()).await.unwrap();
+    assert_eq!(output["answer"], "yes");
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "env >",
+            "model >",
+            "model <",
+            "env <",
+            "model done",
+            "env done"
+        ]
+    );
+}
@@ -1,15 +1,18 @@
 //! `dsrs check`: parse + validate a `.dsrs` artifact.
 //!
 //! The error path is the product: `.dsrs` programs are written by humans and
-//! LLMs, and [`DsrsFileError`] already carries the artifact path plus the
-//! parser's `line N, column M: expected …` message — this command just puts
-//! that on stderr with a non-zero exit code, making it a regeneration signal
-//! for a model loop and a pre-commit gate for a human.
+//! LLMs, and every error carries the artifact path plus the parser's
+//! `line N, column M: expected …` message, followed by the source line with
+//! carets under the culprit. Validation errors are reported all at once
+//! ([`Program::check_dsrs`]), so a model loop fixes a program in one
+//! regeneration instead of one per error. Output goes to stderr with a
+//! non-zero exit code — a regeneration signal for a model loop and a
+//! pre-commit gate for a human.
 
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use dspy_rs::ir::{DsrsFileError, Program};
+use dspy_rs::ir::{DsrsFileError, ParseError, Program};
 
 /// What `dsrs check` reports on success.
 #[derive(Debug)]
@@ -37,11 +40,68 @@ impl fmt::Display for CheckReport {
     }
 }
 
-/// Parses and validates the artifact at `path`. `Program::load_dsrs` runs the
-/// full pipeline: lex/parse (with positions), lowering, `Program::validate`,
-/// hash sealing — exactly what `Interpreter::load` would accept.
-pub fn check_file(path: impl AsRef<Path>) -> Result<CheckReport, DsrsFileError> {
-    let program = Program::load_dsrs(path)?;
+/// Why `dsrs check` refused an artifact.
+#[derive(Debug)]
+pub enum CheckError {
+    /// The file could not be read, or is not text.
+    File(DsrsFileError),
+    /// Parse or validation errors, sorted by position.
+    Invalid {
+        path: PathBuf,
+        source: String,
+        errors: Vec<ParseError>,
+    },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::File(err) => write!(f, "{err}"),
+            CheckError::Invalid {
+                path,
+                source,
+                errors,
+            } => {
+                let blocks: Vec<String> = errors
+                    .iter()
+                    .map(|err| match err.snippet(source) {
+                        snippet if snippet.is_empty() => format!("{}: {err}", path.display()),
+                        snippet => format!("{}: {err}\n{snippet}", path.display()),
+                    })
+                    .collect();
+                write!(f, "{}", blocks.join("\n\n"))?;
+                if errors.len() > 1 {
+                    write!(f, "\n\n{} errors", errors.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// Parses and validates the artifact at `path`: lex/parse (with positions),
+/// lowering, validation, hash sealing — exactly what `Program::load_dsrs`
+/// and `Interpreter::load` would accept, but with every validation error
+/// reported.
+pub fn check_file(path: impl AsRef<Path>) -> Result<CheckReport, CheckError> {
+    let path = path.as_ref();
+    let display = path.display().to_string();
+    let bytes = std::fs::read(path).map_err(|source| {
+        CheckError::File(DsrsFileError::Io {
+            path: display.clone(),
+            source,
+        })
+    })?;
+    let source = String::from_utf8(bytes)
+        .map_err(|_| CheckError::File(DsrsFileError::NotText { path: display }))?;
+    let (program, _) =
+        Program::check_dsrs(&source, Some(path)).map_err(|errors| CheckError::Invalid {
+            path: path.to_path_buf(),
+            source: source.clone(),
+            errors,
+        })?;
     Ok(CheckReport {
         name: program.meta.name.to_string(),
         program_hash: program.meta.program_hash,
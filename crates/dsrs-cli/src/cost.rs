@@ -0,0 +1,63 @@
+//! `dsrs cost`: worst-case and expected LM calls, tokens, dollars, and
+//! latency of one run of a `.dsrs` program, before it is promoted.
+//!
+//! The analysis is `Program::cost_with`. An overlay (named form, as
+//! `dsrs serve --overlay`) resolves models, instructions, demos, and tool
+//! sets as a run would; a price table (`{"<model>": {"input": <usd per
+//! million tokens>, "output": ...}}`, keyed by declared model name or provider
+//! model string) turns tokens into dollars. Flags for what only runtime can
+//! tell — unbudgeted agents, `map` lengths, extern holes, unpriced models —
+//! print after the totals.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use dspy_rs::ir::{CostAssumptions, CostOptions, Pricing, Program};
+
+/// What to analyze, and the assumptions to override.
+#[derive(Clone, Debug)]
+pub struct CostConfig {
+    pub program: PathBuf,
+    /// Named-form overlay JSON (`{"<param path>": <value>, ...}`).
+    pub overlay: Option<PathBuf>,
+    /// Price table JSON.
+    pub prices: Option<PathBuf>,
+    pub assumptions: CostAssumptions,
+    /// Render as JSON instead of text.
+    pub json: bool,
+}
+
+/// Loads the program (overlay, prices) and renders its cost report.
+pub fn cost_file(config: &CostConfig) -> anyhow::Result<String> {
+    let program = Program::load_dsrs(&config.program)?;
+    let overlay = match &config.overlay {
+        Some(path) => Some(crate::serve::load_overlay(&program, path)?),
+        None => None,
+    };
+    let pricing = match &config.prices {
+        Some(path) => Some(load_prices(path)?),
+        None => None,
+    };
+    let report = program.cost_with(CostOptions {
+        overlay: overlay.as_ref(),
+        pricing: pricing.as_ref(),
+        assumptions: config.assumptions,
+    });
+    if config.json {
+        Ok(format!("{}\n", serde_json::to_string_pretty(&report)?))
+    } else {
+        Ok(report.to_string())
+    }
+}
+
+fn load_prices(path: &Path) -> anyhow::Result<Pricing> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read prices `{}`", path.display()))?;
+    serde_json::from_str(&text).with_context(|| {
+        format!(
+            "prices `{}` is not a price table \
+             ({{\"<model>\": {{\"input\": <usd/Mtok>, \"output\": <usd/Mtok>}}, ...}})",
+            path.display()
+        )
+    })
+}
@@ -0,0 +1,67 @@
+//! `dsrs diff`: what changed between two `.dsrs` programs, or between two
+//! named overlays.
+//!
+//! Programs are compared with `Program::diff` (nodes, bindings, signatures,
+//! tools, models, param defaults — by name and param path, not by line).
+//! With `--overlay` the two paths are named-form overlay JSON files; given
+//! `--program`, both are verified against it and model/tool ids print as
+//! names (`Overlay::diff`), otherwise the named maps are compared as they are
+//! (`OverlayDiff::named`).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use dspy_rs::ir::{OverlayDiff, ParamValue, Program};
+
+/// What to compare.
+#[derive(Clone, Debug)]
+pub struct DiffConfig {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// `from` and `to` are named overlays rather than programs.
+    pub overlay: bool,
+    /// With `overlay`: the program both overlays apply to.
+    pub program: Option<PathBuf>,
+    /// Render as JSON instead of text.
+    pub json: bool,
+}
+
+/// Loads both sides and renders their diff (`no changes` when equal).
+pub fn diff_files(config: &DiffConfig) -> anyhow::Result<String> {
+    if !config.overlay {
+        let from = Program::load_dsrs(&config.from)?;
+        let to = Program::load_dsrs(&config.to)?;
+        return render(&from.diff(&to), config.json);
+    }
+    let diff = match &config.program {
+        Some(program) => {
+            let program = Program::load_dsrs(program)?;
+            let from = crate::serve::load_overlay(&program, &config.from)?;
+            let to = crate::serve::load_overlay(&program, &config.to)?;
+            from.diff(&to, &program)
+        }
+        None => OverlayDiff::named(&read_named(&config.from)?, &read_named(&config.to)?),
+    };
+    render(&diff, config.json)
+}
+
+fn render<T: serde::Serialize + std::fmt::Display>(diff: &T, json: bool) -> anyhow::Result<String> {
+    if json {
+        Ok(format!("{}\n", serde_json::to_string_pretty(diff)?))
+    } else {
+        Ok(diff.to_string())
+    }
+}
+
+fn read_named(path: &Path) -> anyhow::Result<BTreeMap<String, ParamValue>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read overlay `{}`", path.display()))?;
+    serde_json::from_str(&text).with_context(|| {
+        format!(
+            "overlay `{}` is not a named overlay JSON object \
+             ({{\"<param path>\": <value>, ...}})",
+            path.display()
+        )
+    })
+}
@@ -0,0 +1,76 @@
+//! `dsrs graph`: draw a `.dsrs` program as a Graphviz or Mermaid diagram.
+//!
+//! The drawing is `Program::to_dot_with` / `Program::to_mermaid_with`. An
+//! overlay (named form, as `dsrs serve --overlay`) resolves models and tool
+//! sets and marks the slots it sets; a `.trace.jsonl` adds per-step calls,
+//! latency, tokens, and errors.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+use dspy_rs::ir::{GraphNotes, Program};
+use dspy_rs::trace::Trace;
+
+/// Output notation for [`graph_file`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz `digraph` (`dot -Tsvg`).
+    #[default]
+    Dot,
+    /// Mermaid `flowchart` (renders inline in Markdown).
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            other => Err(format!(
+                "unknown format `{other}` (expected `dot` or `mermaid`)"
+            )),
+        }
+    }
+}
+
+/// What to draw: the program plus optional annotation sources.
+#[derive(Clone, Debug)]
+pub struct GraphConfig {
+    pub program: PathBuf,
+    pub format: GraphFormat,
+    /// Named-form overlay JSON (`{"<param path>": <value>, ...}`).
+    pub overlay: Option<PathBuf>,
+    /// A `.trace.jsonl` artifact from a run of this program.
+    pub trace: Option<PathBuf>,
+}
+
+/// Loads the program (and annotations) and renders the diagram text.
+pub fn graph_file(config: &GraphConfig) -> anyhow::Result<String> {
+    let program = Program::load_dsrs(&config.program)?;
+    let overlay = match &config.overlay {
+        Some(path) => Some(crate::serve::load_overlay(&program, path)?),
+        None => None,
+    };
+    let trace = match &config.trace {
+        Some(path) => Some(load_trace(path)?),
+        None => None,
+    };
+    let notes = GraphNotes {
+        overlay: overlay.as_ref(),
+        trace: trace.as_ref(),
+    };
+    Ok(match config.format {
+        GraphFormat::Dot => program.to_dot_with(notes),
+        GraphFormat::Mermaid => program.to_mermaid_with(notes),
+    })
+}
+
+fn load_trace(path: &Path) -> anyhow::Result<Trace> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read trace `{}`", path.display()))?;
+    Trace::from_jsonl(&text)
+        .with_context(|| format!("trace `{}` is not trace JSONL", path.display()))
+}
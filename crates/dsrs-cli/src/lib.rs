@@ -2,18 +2,34 @@
 //! program artifacts.
 //!
 //! - [`check`] — `dsrs check program.dsrs`: parse + validate, LLM-friendly
-//!   errors (line/column, what was expected), exit code.
+//!   errors (line/column, what was expected, a caret under the source), all
+//!   validation errors at once, exit code.
 //! - [`fmt`] — `dsrs fmt program.dsrs [--write]`: canonical print
 //!   (`Program::to_dsrs`), the format's one true form.
+//! - [`diff`] — `dsrs diff a.dsrs b.dsrs [--overlay] [--json]`: what
+//!   changed between two programs (or two named overlays), by name and param
+//!   path.
+//! - [`graph`] — `dsrs graph program.dsrs [--format dot|mermaid]`: the
+//!   program as a Graphviz or Mermaid diagram, optionally annotated from an
+//!   overlay and a trace.
+//! - [`cost`] — `dsrs cost program.dsrs [--prices p.json]`: worst-case and
+//!   expected LM calls, tokens, dollars, and latency of one run, from the
+//!   graph's bounds and budgets.
 //! - [`serve`] — `dsrs serve program.dsrs`: the serving host. Loads the
 //!   program (plus an optional named-form overlay), binds models from the
 //!   environment through [`dspy_rs::ir::RuntimeEnv`], and exposes the program
 //!   over HTTP.
+//! - [`lsp`] — `dsrs lsp`: a language server over stdio — diagnostics,
+//!   hover, go to definition, port completion, step rename, formatting.
 //!
 //! Everything is a plain library function so tests drive the exact code the
 //! binary runs — the binary in `main.rs` is argument parsing plus process
 //! exit codes, nothing else.
 
 pub mod check;
+pub mod cost;
+pub mod diff;
 pub mod fmt;
+pub mod graph;
+pub mod lsp;
 pub mod serve;
@@ -0,0 +1,769 @@
+//! `dsrs lsp`: a Language Server Protocol server for `.dsrs` artifacts, over
+//! stdio.
+//!
+//! - Diagnostics on open and change: the structural grammar
+//!   ([`dsrs_syntax::check`]) first, then every capability and validation
+//!   error from [`Program::check_dsrs`] — the same list `dsrs check` prints.
+//! - Hover: a step's keyword, signature, and resolved field types; a
+//!   signature, tool, class, or enum by name; the type of a `$.field`,
+//!   `^field`, or `step.field` port.
+//! - Go to definition for step, signature, tool, class, and enum names.
+//! - Completion of the ports in scope after `$.`, `^`, and `step.`
+//!   ([`Program::port_scopes`]).
+//! - Rename of a step: its declaration and every `step.field` reference,
+//!   plus the `"<step>.<slot>"` keys of the named overlays
+//!   (`Overlay::to_named` JSON) next to the file. Refused unless the renamed
+//!   text still validates.
+//! - Formatting: the canonical printer (`Program::to_dsrs`), as `dsrs fmt`.
+//!
+//! Documents sync as full text. A document that stops validating keeps the
+//! analysis of its last valid version, so hover and completion still answer
+//! while a line is half typed; rename and formatting need the current text
+//! to validate.
+//!
+//! The analysis lives on [`Document`], independent of the transport, so
+//! tests drive it directly; [`serve_connection`] is the message loop.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use dspy_rs::FieldType;
+use dspy_rs::ir::{Node, NodeId, Overlay, ParamValue, PortScope, Program, SigId, SourceMap, Span};
+use dspy_rs::typesys::type_name;
+use dsrs_syntax::lex::{Lexer, Tok};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, Diagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, GotoDefinitionParams, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, Location, MarkupContent, MarkupKind,
+    OneOf, Position, PublishDiagnosticsParams, Range, RenameParams, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+
+/// What the server announces at `initialize`.
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string(), "^".to_string()]),
+            ..Default::default()
+        }),
+        rename_provider: Some(OneOf::Left(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+/// Runs the server over stdin/stdout until the client shuts it down.
+pub fn run() -> anyhow::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    serve_connection(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// The message loop over an established connection: the `initialize`
+/// handshake, then requests and notifications until `shutdown`/`exit`.
+pub fn serve_connection(connection: &Connection) -> anyhow::Result<()> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    let mut docs: HashMap<Url, Document> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    return Ok(());
+                }
+                connection.sender.send(respond(&docs, req).into())?;
+            }
+            Message::Notification(note) => {
+                if let Some(publish) = notify(&mut docs, note) {
+                    connection.sender.send(publish.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Answers one request against the open documents.
+fn respond(docs: &HashMap<Url, Document>, req: Request) -> Response {
+    fn params<P: serde::de::DeserializeOwned>(req: &Request) -> Result<P, Response> {
+        serde_json::from_value(req.params.clone()).map_err(|err| {
+            Response::new_err(
+                req.id.clone(),
+                ErrorCode::InvalidParams as i32,
+                err.to_string(),
+            )
+        })
+    }
+    fn doc<'d>(
+        docs: &'d HashMap<Url, Document>,
+        req: &Request,
+        uri: &Url,
+    ) -> Result<&'d Document, Response> {
+        docs.get(uri).ok_or_else(|| {
+            Response::new_err(
+                req.id.clone(),
+                ErrorCode::InvalidParams as i32,
+                format!("`{uri}` is not open"),
+            )
+        })
+    }
+
+    let id = req.id.clone();
+    let answer = match req.method.as_str() {
+        "textDocument/hover" => params::<HoverParams>(&req).and_then(|p| {
+            let at = p.text_document_position_params;
+            let doc = doc(docs, &req, &at.text_document.uri)?;
+            Ok(Response::new_ok(id.clone(), doc.hover(at.position)))
+        }),
+        "textDocument/definition" => params::<GotoDefinitionParams>(&req).and_then(|p| {
+            let at = p.text_document_position_params;
+            let doc = doc(docs, &req, &at.text_document.uri)?;
+            Ok(Response::new_ok(id.clone(), doc.definition(at.position)))
+        }),
+        "textDocument/completion" => params::<CompletionParams>(&req).and_then(|p| {
+            let at = p.text_document_position;
+            let doc = doc(docs, &req, &at.text_document.uri)?;
+            Ok(Response::new_ok(id.clone(), doc.completions(at.position)))
+        }),
+        "textDocument/rename" => params::<RenameParams>(&req).and_then(|p| {
+            let at = p.text_document_position;
+            let doc = doc(docs, &req, &at.text_document.uri)?;
+            Ok(match doc.rename(at.position, &p.new_name) {
+                Ok(edit) => Response::new_ok(id.clone(), edit),
+                Err(message) => {
+                    Response::new_err(id.clone(), ErrorCode::RequestFailed as i32, message)
+                }
+            })
+        }),
+        "textDocument/formatting" => params::<DocumentFormattingParams>(&req).and_then(|p| {
+            let doc = doc(docs, &req, &p.text_document.uri)?;
+            Ok(Response::new_ok(id.clone(), doc.formatting()))
+        }),
+        other => Ok(Response::new_err(
+            id.clone(),
+            ErrorCode::MethodNotFound as i32,
+            format!("unsupported request `{other}`"),
+        )),
+    };
+    answer.unwrap_or_else(|err| err)
+}
+
+/// Applies one notification; returns the diagnostics to publish, if any.
+fn notify(docs: &mut HashMap<Url, Document>, note: Notification) -> Option<Notification> {
+    let uri = match note.method.as_str() {
+        "textDocument/didOpen" => {
+            let p: DidOpenTextDocumentParams = serde_json::from_value(note.params).ok()?;
+            let uri = p.text_document.uri;
+            docs.insert(
+                uri.clone(),
+                Document::new(uri.clone(), p.text_document.text),
+            );
+            uri
+        }
+        "textDocument/didChange" => {
+            let p: DidChangeTextDocumentParams = serde_json::from_value(note.params).ok()?;
+            let uri = p.text_document.uri;
+            let text = p.content_changes.into_iter().last()?.text;
+            let doc = match docs.remove(&uri) {
+                Some(doc) => doc.changed(text),
+                None => Document::new(uri.clone(), text),
+            };
+            docs.insert(uri.clone(), doc);
+            uri
+        }
+        "textDocument/didClose" => {
+            let p: DidCloseTextDocumentParams = serde_json::from_value(note.params).ok()?;
+            docs.remove(&p.text_document.uri);
+            return Some(publish(p.text_document.uri, Vec::new()));
+        }
+        _ => return None,
+    };
+    let diagnostics = docs[&uri].diagnostics().to_vec();
+    Some(publish(uri, diagnostics))
+}
+
+fn publish(uri: Url, diagnostics: Vec<Diagnostic>) -> Notification {
+    Notification::new(
+        "textDocument/publishDiagnostics".to_string(),
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        },
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Analysis
+// ---------------------------------------------------------------------------
+
+/// A validated program and where its parts sit in the text.
+struct Analysis {
+    program: Program,
+    source: SourceMap,
+    scopes: HashMap<NodeId, PortScope>,
+}
+
+/// One open `.dsrs` document and what the server knows about it.
+pub struct Document {
+    uri: Url,
+    text: String,
+    diagnostics: Vec<Diagnostic>,
+    analysis: Option<Analysis>,
+    /// Whether `analysis` is of the current text (not a stale valid one).
+    fresh: bool,
+}
+
+impl Document {
+    /// Checks `text`. A `file:` URI lets imports resolve and rename reach
+    /// the overlays next to the file.
+    pub fn new(uri: Url, text: String) -> Self {
+        let path = uri.to_file_path().ok();
+        let (errors, analysis) = match dsrs_syntax::check(&text) {
+            Err(err) => (vec![err], None),
+            Ok(()) => match Program::check_dsrs(&text, path.as_deref()) {
+                Ok((program, source)) => {
+                    let scopes = program.port_scopes();
+                    let analysis = Analysis {
+                        program,
+                        source,
+                        scopes,
+                    };
+                    (Vec::new(), Some(analysis))
+                }
+                Err(errors) => (errors, None),
+            },
+        };
+        let diagnostics = errors
+            .into_iter()
+            .map(|err| Diagnostic {
+                range: ident_range(
+                    &text,
+                    Span {
+                        line: err.line,
+                        col: err.col,
+                    },
+                ),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("dsrs".to_string()),
+                message: err.message,
+                ..Default::default()
+            })
+            .collect();
+        let fresh = analysis.is_some();
+        Self {
+            uri,
+            text,
+            diagnostics,
+            analysis,
+            fresh,
+        }
+    }
+
+    /// The document after an edit. When the new text does not validate, the
+    /// last valid analysis is kept (stale) for hover, definition, and
+    /// completion.
+    pub fn changed(self, text: String) -> Self {
+        let mut next = Self::new(self.uri, text);
+        if next.analysis.is_none() {
+            next.analysis = self.analysis;
+        }
+        next
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Every syntax, capability, and validation error, sorted by position.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The analysis of the current text, if it validates.
+    fn current(&self) -> Option<&Analysis> {
+        self.analysis.as_ref().filter(|_| self.fresh)
+    }
+
+    pub fn hover(&self, pos: Position) -> Option<Hover> {
+        let analysis = self.analysis.as_ref()?;
+        let p = &analysis.program;
+        let at = offset(&self.text, pos)?;
+        let (start, end) = word_at(&self.text, at)?;
+        let word = &self.text[start..end];
+        let markdown = if let Some(ty) = self.port_type(analysis, pos, start, word) {
+            let port = &self.text[self.port_start(start)..end];
+            format!("```dsrs\n{port}: {}\n```", type_name(&ty, Some(&p.types)))
+        } else if let Some(id) = p.leaf_id(word) {
+            let (keyword, sig) = leaf_sig(&p.nodes[id])?;
+            let name = &p.sigs[sig].name;
+            format!("```dsrs\n{word} = {keyword} {name}\n{}```", fields(p, sig))
+        } else if let Some(sig) = tool_sig(p, word) {
+            format!("```dsrs\ntool {word}\n{}```", fields(p, sig))
+        } else if let Some(sig) = named_sig(p, word) {
+            let def = &p.sigs[sig];
+            let mut text = format!("```dsrs\nsig {word}\n{}```", fields(p, sig));
+            if !def.instruction.is_empty() {
+                text.push_str(&format!("\n\n{}", def.instruction));
+            }
+            text
+        } else if let Some(class) = p.types.classes.get(word) {
+            let mut text = format!("```dsrs\nclass {word} {{\n");
+            for field in &class.fields {
+                let ty = type_name(&field.field_type, Some(&p.types));
+                text.push_str(&format!("  {}: {ty}\n", field.name));
+            }
+            text.push_str("}\n```");
+            text
+        } else if let Some(def) = p.types.enums.get(word) {
+            let values: Vec<&str> = def.values.iter().map(|v| v.name.as_str()).collect();
+            format!("```dsrs\nenum {word} {{ {} }}\n```", values.join(" "))
+        } else {
+            return None;
+        };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: markdown,
+            }),
+            range: Some(Range::new(
+                position_at(&self.text, start),
+                position_at(&self.text, end),
+            )),
+        })
+    }
+
+    /// Where the step, signature, tool, class, or enum under the cursor is
+    /// declared.
+    pub fn definition(&self, pos: Position) -> Option<Location> {
+        let analysis = self.analysis.as_ref()?;
+        let p = &analysis.program;
+        let (start, end) = word_at(&self.text, offset(&self.text, pos)?)?;
+        let word = &self.text[start..end];
+        let span = match p.leaf_id(word) {
+            Some(id) => analysis.source.node(id),
+            None => match tool_sig(p, word).or_else(|| named_sig(p, word)) {
+                Some(sig) => analysis.source.sig(sig),
+                None => analysis.source.type_decl(word),
+            },
+        }?;
+        Some(Location::new(
+            self.uri.clone(),
+            ident_range(&self.text, span),
+        ))
+    }
+
+    /// The ports in scope after `$.`, `^`, or `step.`, with their types.
+    pub fn completions(&self, pos: Position) -> Vec<CompletionItem> {
+        let Some(analysis) = &self.analysis else {
+            return Vec::new();
+        };
+        let Some(at) = offset(&self.text, pos) else {
+            return Vec::new();
+        };
+        let Some(scope) = scope_at(analysis, &self.text, at) else {
+            return Vec::new();
+        };
+        let lead = self.text[..at].trim_end_matches(is_ident);
+        let fields = if lead.ends_with("$.") {
+            Some(&scope.inputs)
+        } else if lead.ends_with('^') {
+            Some(&scope.carried)
+        } else if let Some(head) = lead.strip_suffix('.') {
+            let name = &head[head.trim_end_matches(is_ident).len()..];
+            analysis.program.leaf_id(name).and_then(|id| {
+                scope
+                    .nodes
+                    .iter()
+                    .find(|(node, _)| *node == id)
+                    .map(|(_, iface)| iface)
+            })
+        } else {
+            None
+        };
+        let types = &analysis.program.types;
+        fields
+            .into_iter()
+            .flatten()
+            .map(|(name, ty)| CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: Some(type_name(ty, Some(types))),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Renames the step under the cursor: its declaration, every
+    /// `step.field` reference, and the `"<step>.<slot>"` keys of the named
+    /// overlays in the file's directory that apply to this program.
+    pub fn rename(&self, pos: Position, new_name: &str) -> Result<WorkspaceEdit, String> {
+        let analysis = self
+            .current()
+            .ok_or("the document has errors: fix them before renaming")?;
+        let p = &analysis.program;
+        let at = offset(&self.text, pos).ok_or("position is outside the document")?;
+        let (start, end) = word_at(&self.text, at).ok_or("nothing to rename here")?;
+        let old = &self.text[start..end];
+        let id = p.leaf_id(old).ok_or("only step names can be renamed")?;
+        let valid = new_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && new_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("`{new_name}` is not a step name"));
+        }
+        if p.leaf_id(new_name).is_some() {
+            return Err(format!("a step named `{new_name}` already exists"));
+        }
+        let decl = analysis
+            .source
+            .node(id)
+            .map(|span| span_offset(&self.text, span))
+            .ok_or("the step is not declared in this file")?;
+
+        let starts = step_references(&self.text, old, decl)?;
+        let mut renamed = self.text.clone();
+        for &at in starts.iter().rev() {
+            renamed.replace_range(at..at + old.len(), new_name);
+        }
+        let path = self.uri.to_file_path().ok();
+        if let Err(errors) = Program::check_dsrs(&renamed, path.as_deref()) {
+            return Err(format!(
+                "renaming `{old}` to `{new_name}` breaks the program: {}",
+                errors[0]
+            ));
+        }
+
+        let mut changes = HashMap::new();
+        let edits = starts
+            .iter()
+            .map(|&at| {
+                TextEdit::new(
+                    Range::new(
+                        position_at(&self.text, at),
+                        position_at(&self.text, at + old.len()),
+                    ),
+                    new_name.to_string(),
+                )
+            })
+            .collect();
+        changes.insert(self.uri.clone(), edits);
+        if let Some(dir) = path.as_deref().and_then(Path::parent) {
+            for (overlay, edits) in overlay_renames(p, dir, old, new_name) {
+                if let Ok(uri) = Url::from_file_path(&overlay) {
+                    changes.insert(uri, edits);
+                }
+            }
+        }
+        Ok(WorkspaceEdit::new(changes))
+    }
+
+    /// The canonical form as one whole-document edit (none when already
+    /// canonical); `None` when the text does not validate.
+    pub fn formatting(&self) -> Option<Vec<TextEdit>> {
+        let canonical = self.current()?.program.to_dsrs();
+        if canonical == self.text {
+            return Some(Vec::new());
+        }
+        let whole = Range::new(
+            Position::new(0, 0),
+            position_at(&self.text, self.text.len()),
+        );
+        Some(vec![TextEdit::new(whole, canonical)])
+    }
+
+    /// The type of the port whose field name is `word` (at byte `start`):
+    /// `$.word`, `^word`, or `step.word`.
+    fn port_type(
+        &self,
+        analysis: &Analysis,
+        pos: Position,
+        start: usize,
+        word: &str,
+    ) -> Option<FieldType> {
+        let scope = scope_at(analysis, &self.text, offset(&self.text, pos)?)?;
+        let lead = &self.text[..start];
+        let fields = if lead.ends_with("$.") {
+            &scope.inputs
+        } else if lead.ends_with('^') {
+            &scope.carried
+        } else {
+            let head = lead.strip_suffix('.')?;
+            let name = &head[head.trim_end_matches(is_ident).len()..];
+            let id = analysis.program.leaf_id(name)?;
+            &scope.nodes.iter().find(|(node, _)| *node == id)?.1
+        };
+        fields.get(word).cloned()
+    }
+
+    /// Where the port ending in the field at `start` begins (`$`, `^`, or
+    /// the step name).
+    fn port_start(&self, start: usize) -> usize {
+        let lead = &self.text[..start];
+        if lead.ends_with("$.") {
+            start - 2
+        } else if lead.ends_with('^') {
+            start - 1
+        } else {
+            let head = &lead[..lead.len() - 1];
+            head.trim_end_matches(is_ident).len()
+        }
+    }
+}
+
+/// The keyword and signature of a leaf step.
+fn leaf_sig(node: &Node) -> Option<(&'static str, SigId)> {
+    match node {
+        Node::Predict(n) => Some(("predict", n.sig)),
+        Node::AgentLoop(n) => Some(("agent", n.sig)),
+        Node::Call(n) => Some(("call", n.sig)),
+        Node::Transform(n) => Some(("transform", n.sig)),
+        Node::Approve(n) => Some(("approve", n.sig)),
+        Node::Hole(n) => Some(("hole", n.sig)),
+        _ => None,
+    }
+}
+
+fn tool_sig(p: &Program, name: &str) -> Option<SigId> {
+    p.tools
+        .values()
+        .find(|tool| p.syms.get(tool.name) == name)
+        .map(|tool| tool.sig)
+}
+
+fn named_sig(p: &Program, name: &str) -> Option<SigId> {
+    p.sigs
+        .iter()
+        .find(|(_, def)| &*def.name == name)
+        .map(|(id, _)| id)
+}
+
+/// A signature's fields, one `in`/`out` line each.
+fn fields(p: &Program, sig: SigId) -> String {
+    let def = &p.sigs[sig];
+    let mut text = String::new();
+    for (dir, list) in [("in ", &def.inputs), ("out", &def.outputs)] {
+        for field in list.iter() {
+            let ty = type_name(&field.ty, Some(&p.types));
+            text.push_str(&format!("  {dir} {}: {ty}\n", field.name));
+        }
+    }
+    text
+}
+
+/// The ports readable at byte `at`: the scope of the last step declared at
+/// or before it, plus that step itself once the cursor is past its line
+/// (its `out { … }` block, or the steps after it).
+fn scope_at(analysis: &Analysis, text: &str, at: usize) -> Option<PortScope> {
+    let (id, span) = analysis
+        .scopes
+        .keys()
+        .filter_map(|id| analysis.source.node(*id).map(|span| (*id, span)))
+        .filter(|(_, span)| span_offset(text, *span) <= at)
+        .max_by_key(|(_, span)| (span.line, span.col))?;
+    let mut scope = analysis.scopes[&id].clone();
+    let cursor_line = text[..at].matches('\n').count() as u32 + 1;
+    if cursor_line > span.line {
+        let seen = analysis
+            .scopes
+            .values()
+            .find_map(|s| s.nodes.iter().find(|(node, _)| *node == id));
+        if let Some(own) = seen {
+            scope.nodes.push(own.clone());
+        } else if let Some((_, sig)) = leaf_sig(&analysis.program.nodes[id]) {
+            // The last step: no later scope shows its interface, its
+            // signature outputs stand in.
+            let outputs = analysis.program.sigs[sig].outputs.iter();
+            let iface = outputs
+                .map(|field| (field.name.to_string(), field.ty.clone()))
+                .collect();
+            scope.nodes.push((id, iface));
+        }
+    }
+    Some(scope)
+}
+
+/// Byte offsets of the step name `name` in `text`: its declaration at
+/// `decl` and every `name.field` port. Fenced code is skipped.
+fn step_references(text: &str, name: &str, decl: usize) -> Result<Vec<usize>, String> {
+    let mut lx = Lexer::new(text);
+    let mut starts = Vec::new();
+    let mut prev = Tok::Eof;
+    let mut cur = lx.next_token().map_err(|err| err.to_string())?;
+    while cur.tok != Tok::Eof {
+        if cur.tok == Tok::Fence {
+            let (_, end) = lx
+                .scan_code_fence(cur.start)
+                .map_err(|err| err.to_string())?;
+            lx.seek(end, lx.span_at(end));
+            prev = Tok::Fence;
+            cur = lx.next_token().map_err(|err| err.to_string())?;
+            continue;
+        }
+        let next = lx.next_token().map_err(|err| err.to_string())?;
+        if matches!(&cur.tok, Tok::Ident(word) if word == name) {
+            let port = next.tok == Tok::Dot && !matches!(prev, Tok::Dot | Tok::Dollar);
+            if cur.start == decl || port {
+                starts.push(cur.start);
+            }
+        }
+        prev = cur.tok;
+        cur = next;
+    }
+    Ok(starts)
+}
+
+/// Edits renaming the `"<old>.<slot>"` keys of every named overlay in `dir`
+/// that applies to `p`.
+fn overlay_renames(p: &Program, dir: &Path, old: &str, new: &str) -> Vec<(PathBuf, Vec<TextEdit>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let prefix = format!("{old}.");
+    let mut out = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(named) = serde_json::from_str::<BTreeMap<String, ParamValue>>(&text) else {
+            continue;
+        };
+        let keys: Vec<String> = named
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        if keys.is_empty() || Overlay::from_named(p, named).is_err() {
+            continue;
+        }
+        let mut edits = Vec::new();
+        for key in keys {
+            let quoted = format!("\"{key}\"");
+            let found = text
+                .match_indices(&quoted)
+                .find(|(at, _)| text[at + quoted.len()..].trim_start().starts_with(':'));
+            if let Some((at, _)) = found {
+                let name_start = at + 1;
+                edits.push(TextEdit::new(
+                    Range::new(
+                        position_at(&text, name_start),
+                        position_at(&text, name_start + old.len()),
+                    ),
+                    new.to_string(),
+                ));
+            }
+        }
+        out.push((path, edits));
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Positions: `.dsrs` spans are 1-based lines and byte columns, LSP positions
+// are 0-based lines and UTF-16 columns.
+// ---------------------------------------------------------------------------
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The byte offset of a span.
+fn span_offset(text: &str, span: Span) -> usize {
+    let mut start = 0;
+    for _ in 1..span.line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+    let mut at = (start + span.col.saturating_sub(1) as usize).min(line_end);
+    while !text.is_char_boundary(at) {
+        at -= 1;
+    }
+    at
+}
+
+/// The LSP position of a byte offset.
+fn position_at(text: &str, at: usize) -> Position {
+    let before = &text[..at];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+/// The byte offset of an LSP position, clamped to its line.
+fn offset(text: &str, pos: Position) -> Option<usize> {
+    let mut start = 0;
+    for _ in 0..pos.line {
+        start += text[start..].find('\n')? + 1;
+    }
+    let line = text[start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= pos.character {
+            return Some(start + i);
+        }
+        units += c.len_utf16() as u32;
+    }
+    Some(start + line.len())
+}
+
+/// The identifier (`a::b` qualified names included) touching byte `at`.
+fn word_at(text: &str, at: usize) -> Option<(usize, usize)> {
+    let mut start = text[..at].trim_end_matches(is_ident).len();
+    let mut end = at + text[at..].find(|c| !is_ident(c)).unwrap_or(text.len() - at);
+    while text[..start].ends_with("::") {
+        let head = &text[..start - 2];
+        let prev = head.trim_end_matches(is_ident).len();
+        if prev == head.len() {
+            break;
+        }
+        start = prev;
+    }
+    while text[end..].starts_with("::") {
+        let tail = &text[end + 2..];
+        let len = tail.find(|c| !is_ident(c)).unwrap_or(tail.len());
+        if len == 0 {
+            break;
+        }
+        end += 2 + len;
+    }
+    (start < end).then_some((start, end))
+}
+
+/// The range of the identifier at `span` (at least one character wide), as
+/// `ParseError::snippet` underlines it.
+fn ident_range(text: &str, span: Span) -> Range {
+    let start = span_offset(text, span);
+    let width = text[start..]
+        .find(|c| !is_ident(c))
+        .unwrap_or(text.len() - start);
+    let end = if width == 0 {
+        text[start..]
+            .chars()
+            .next()
+            .filter(|c| *c != '\n')
+            .map_or(start, |c| start + c.len_utf8())
+    } else {
+        start + width
+    };
+    let mut range = Range::new(position_at(text, start), position_at(text, end));
+    if range.start == range.end {
+        range.end.character += 1;
+    }
+    range
+}
@@ -6,14 +6,18 @@ use std::path::PathBuf;
 use std::process::ExitCode;
 
 use clap::{Parser, Subcommand};
+use dspy_rs::ir::CostAssumptions;
+use dsrs_cli::cost::CostConfig;
+use dsrs_cli::diff::DiffConfig;
 use dsrs_cli::fmt::FmtOutcome;
+use dsrs_cli::graph::{GraphConfig, GraphFormat};
 use dsrs_cli::serve::ServeConfig;
 
 #[derive(Parser)]
 #[command(
     name = "dsrs",
     version,
-    about = "DSRs .dsrs program toolchain: check, fmt, serve (RFC 0002 IR-7)"
+    about = "DSRs .dsrs program toolchain: check, fmt, diff, graph, cost, serve, lsp (RFC 0002 IR-7)"
 )]
 struct Cli {
     #[command(subcommand)]
@@ -22,7 +26,8 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Cmd {
-    /// Parse + validate a .dsrs artifact; errors carry line/column positions.
+    /// Parse + validate a .dsrs artifact; every error is shown with its
+    /// line/column and the offending source line.
     Check {
         /// Path to the .dsrs artifact.
         program: PathBuf,
@@ -35,6 +40,66 @@ enum Cmd {
         #[arg(long)]
         write: bool,
     },
+    /// Show what changed between two .dsrs programs (or, with --overlay, two
+    /// named overlays): nodes, bindings, signatures, tools, models, params.
+    Diff {
+        /// The old side.
+        from: PathBuf,
+        /// The new side.
+        to: PathBuf,
+        /// Compare two overlay JSON files in the named form instead.
+        #[arg(long)]
+        overlay: bool,
+        /// With --overlay: the .dsrs program both overlays apply to; model
+        /// and tool ids then print as names.
+        #[arg(long, requires = "overlay")]
+        program: Option<PathBuf>,
+        /// Print JSON instead of text.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a .dsrs program as a Graphviz (dot) or Mermaid diagram.
+    Graph {
+        /// Path to the .dsrs artifact.
+        program: PathBuf,
+        /// `dot` or `mermaid`.
+        #[arg(long, default_value = "dot")]
+        format: GraphFormat,
+        /// Overlay JSON in the named form; resolves models/tool sets and
+        /// marks the slots it sets.
+        #[arg(long)]
+        overlay: Option<PathBuf>,
+        /// A .trace.jsonl artifact; adds per-step calls, latency, tokens,
+        /// and errors.
+        #[arg(long)]
+        trace: Option<PathBuf>,
+    },
+    /// Worst-case and expected LM calls, tokens, cost, and latency of one
+    /// run of a .dsrs program.
+    Cost {
+        /// Path to the .dsrs artifact.
+        program: PathBuf,
+        /// Overlay JSON in the named form; resolves models, instructions,
+        /// demos, and tool sets.
+        #[arg(long)]
+        overlay: Option<PathBuf>,
+        /// Price table JSON: {"<model>": {"input": <usd/Mtok>, "output":
+        /// <usd/Mtok>}, ...}, by declared name or provider model string.
+        #[arg(long)]
+        prices: Option<PathBuf>,
+        /// Assumed length of every `map` list (default 8).
+        #[arg(long)]
+        map_items: Option<u32>,
+        /// Expected agent turns before an answer (default 3).
+        #[arg(long)]
+        agent_turns: Option<u32>,
+        /// Wall time of one LM call in milliseconds (default 2000).
+        #[arg(long)]
+        call_ms: Option<u64>,
+        /// Print JSON instead of text.
+        #[arg(long)]
+        json: bool,
+    },
     /// Serve a .dsrs program over HTTP (POST /run, GET /schema, GET /program,
     /// GET /healthz).
     Serve {
@@ -51,6 +116,8 @@ enum Cmd {
         #[arg(long = "allow", value_name = "CAP")]
         allow: Vec<String>,
     },
+    /// Run the .dsrs language server over stdio (for editors).
+    Lsp,
 }
 
 fn main() -> ExitCode {
@@ -83,6 +150,91 @@ fn main() -> ExitCode {
                 ExitCode::FAILURE
             }
         },
+        Cmd::Diff {
+            from,
+            to,
+            overlay,
+            program,
+            json,
+        } => {
+            let config = DiffConfig {
+                from,
+                to,
+                overlay,
+                program,
+                json,
+            };
+            match dsrs_cli::diff::diff_files(&config) {
+                Ok(text) => {
+                    print!("{text}");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Cmd::Graph {
+            program,
+            format,
+            overlay,
+            trace,
+        } => {
+            let config = GraphConfig {
+                program,
+                format,
+                overlay,
+                trace,
+            };
+            match dsrs_cli::graph::graph_file(&config) {
+                Ok(text) => {
+                    print!("{text}");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Cmd::Cost {
+            program,
+            overlay,
+            prices,
+            map_items,
+            agent_turns,
+            call_ms,
+            json,
+        } => {
+            let mut assumptions = CostAssumptions::default();
+            if let Some(map_items) = map_items {
+                assumptions.map_items = map_items;
+            }
+            if let Some(agent_turns) = agent_turns {
+                assumptions.agent_turns = agent_turns;
+            }
+            if let Some(call_ms) = call_ms {
+                assumptions.call_ms = call_ms;
+            }
+            let config = CostConfig {
+                program,
+                overlay,
+                prices,
+                assumptions,
+                json,
+            };
+            match dsrs_cli::cost::cost_file(&config) {
+                Ok(text) => {
+                    print!("{text}");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
         Cmd::Serve {
             program,
             host,
@@ -110,5 +262,12 @@ fn main() -> ExitCode {
                 }
             }
         }
+        Cmd::Lsp => match dsrs_cli::lsp::run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err:#}");
+                ExitCode::FAILURE
+            }
+        },
     }
 }
@@ -27,7 +27,7 @@
 //! "unbound" error.
 
 use std::collections::{BTreeMap, HashMap};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Context;
@@ -77,6 +77,26 @@ impl std::fmt::Debug for App {
     }
 }
 
+/// Reads a named-form overlay (`Overlay::to_named` JSON) and verifies it
+/// against `program`: unknown paths and kind mismatches are errors.
+pub fn load_overlay(program: &Program, path: &Path) -> anyhow::Result<Overlay> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read overlay `{}`", path.display()))?;
+    let named: BTreeMap<String, ParamValue> = serde_json::from_str(&text).with_context(|| {
+        format!(
+            "overlay `{}` is not a named overlay JSON object \
+             ({{\"<param path>\": <value>, ...}})",
+            path.display()
+        )
+    })?;
+    Overlay::from_named(program, named).with_context(|| {
+        format!(
+            "overlay `{}` does not apply to this program",
+            path.display()
+        )
+    })
+}
+
 /// Loads the program and builds the app state. `env` carries host-supplied
 /// bindings — the CLI passes `RuntimeEnv::new()` (models constructed from
 /// their artifact configs, secrets from provider env vars); tests pre-bind
@@ -86,21 +106,7 @@ pub async fn load(config: &ServeConfig, env: dspy_rs::ir::RuntimeEnv) -> anyhow:
     let program = Program::load_dsrs(&config.program)?;
 
     let overlay = match &config.overlay {
-        Some(path) => {
-            let text = std::fs::read_to_string(path)
-                .with_context(|| format!("failed to read overlay `{}`", path.display()))?;
-            let named: BTreeMap<String, ParamValue> = serde_json::from_str(&text)
-                .with_context(|| {
-                    format!(
-                        "overlay `{}` is not a named overlay JSON object \
-                         ({{\"<param path>\": <value>, ...}})",
-                        path.display()
-                    )
-                })?;
-            Some(Arc::new(Overlay::from_named(&program, named).with_context(
-                || format!("overlay `{}` does not apply to this program", path.display()),
-            )?))
-        }
+        Some(path) => Some(Arc::new(load_overlay(&program, path)?)),
         None => None,
     };
 
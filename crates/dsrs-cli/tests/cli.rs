@@ -1,18 +1,27 @@
-//! IR-7 CLI coverage (RFC 0002 §6.2): `check`/`fmt` through the exact
-//! library functions the binary calls, and the serving host end-to-end on an
+//! IR-7 CLI coverage (RFC 0002 §6.2): `check`/`fmt`/`diff`/`graph`/`cost` through the exact
+//! library functions the binary calls, the serving host end-to-end on an
 //! ephemeral port with canned LM responses (`TestCompletionModel` pre-bound
 //! into `RuntimeEnv` — the same injection point a production host uses for
-//! real models).
+//! real models), and the language server's analysis plus its message loop
+//! over an in-memory connection.
 
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use dspy_rs::ir::RuntimeEnv;
+use dspy_rs::ir::{CostAssumptions, RuntimeEnv};
 use dspy_rs::{LM, LMClient, LMConfig, TestCompletionModel};
 use dsrs_cli::check::check_file;
+use dsrs_cli::cost::{CostConfig, cost_file};
+use dsrs_cli::diff::{DiffConfig, diff_files};
 use dsrs_cli::fmt::{FmtOutcome, fmt_file};
+use dsrs_cli::graph::{GraphConfig, GraphFormat, graph_file};
+use dsrs_cli::lsp::Document;
 use dsrs_cli::serve::{self, ServeConfig};
+use lsp_server::{Connection, Message, Notification, Request};
+use lsp_types::{
+    CompletionItem, DiagnosticSeverity, Hover, HoverContents, Position, TextEdit, Url,
+};
 use rig::completion::AssistantContent;
 use rig::message::Text;
 use serde_json::{Value, json};
@@ -53,6 +62,31 @@ fn check_rejects_with_path_and_position() {
     assert!(message.contains("unknown top-level keyword"), "{message}");
 }
 
+#[test]
+fn check_reports_every_validation_error_with_a_snippet() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("twice.dsrs");
+    let src = "dsrs 1\nprogram twice\nmodel m = \"openai:gpt-4o-mini\"\n\
+               sig Main { in q: string out a: string }\n\
+               main: Main = seq {\n  first = predict Main\n  second = predict Main\n  \
+               out { a = second.a }\n}\n";
+    std::fs::write(&path, src).expect("write");
+    let message = check_file(&path).expect_err("unbound inputs").to_string();
+    assert!(
+        message.contains("twice.dsrs: line 6, column 3:"),
+        "{message}"
+    );
+    assert!(
+        message.contains("twice.dsrs: line 7, column 3:"),
+        "{message}"
+    );
+    assert!(
+        message.contains("6 |   first = predict Main\n  |   ^^^^^"),
+        "{message}"
+    );
+    assert!(message.ends_with("2 errors"), "{message}");
+}
+
 // ---------------------------------------------------------------------------
 // fmt
 // ---------------------------------------------------------------------------
@@ -60,7 +94,8 @@ fn check_rejects_with_path_and_position() {
 #[test]
 fn fmt_canonicalizes_and_is_idempotent() {
     let golden = std::fs::read_to_string(fixture("qa.dsrs")).expect("golden");
-    let scrambled = std::fs::read_to_string(fixture("qa_scrambled.dsrs")).expect("scrambled");
+    // `fmt` keeps comments (see below), so scramble without them.
+    let scrambled = std::fs::read_to_string(fixture("qa_scrambled_bare.dsrs")).expect("scrambled");
     assert_ne!(golden, scrambled);
 
     let dir = tempfile::tempdir().expect("tempdir");
@@ -90,6 +125,26 @@ fn fmt_canonicalizes_and_is_idempotent() {
     ));
 }
 
+#[test]
+fn fmt_keeps_comments() {
+    let kitchen = std::fs::read_to_string(fixture("kitchen.dsrs")).expect("kitchen");
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("kitchen.dsrs");
+    std::fs::write(&path, &kitchen).expect("write");
+
+    fmt_file(&path, true).expect("formats");
+    let formatted = std::fs::read_to_string(&path).expect("read");
+    assert!(
+        formatted
+            .starts_with("// Kitchen sink: every node kind, classes/enums, exotic types, field\n"),
+        "{formatted}"
+    );
+    assert!(matches!(
+        fmt_file(&path, true).expect("formats"),
+        FmtOutcome::Unchanged
+    ));
+}
+
 #[test]
 fn fmt_fails_on_invalid_input() {
     let dir = tempfile::tempdir().expect("tempdir");
@@ -99,6 +154,145 @@ fn fmt_fails_on_invalid_input() {
     assert!(err.to_string().contains("nope.dsrs"), "{err:#}");
 }
 
+// ---------------------------------------------------------------------------
+// diff
+// ---------------------------------------------------------------------------
+
+#[test]
+fn diff_compares_programs_and_overlays() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let golden = std::fs::read_to_string(fixture("qa.dsrs")).expect("golden");
+    let child = dir.path().join("child.dsrs");
+    std::fs::write(
+        &child,
+        golden.replace("agent Research @fast", "agent Research @deep"),
+    )
+    .expect("write");
+    let mut config = DiffConfig {
+        from: fixture("qa.dsrs"),
+        to: child,
+        overlay: false,
+        program: None,
+        json: false,
+    };
+    let text = diff_files(&config).expect("diffs");
+    assert!(
+        text.contains("params:\n  ~ researcher.model: \"fast\" → \"deep\"\n"),
+        "{text}"
+    );
+    config.json = true;
+    let value: Value = serde_json::from_str(&diff_files(&config).expect("diffs")).expect("json");
+    assert_eq!(value["params"][0]["key"], "researcher.model");
+
+    let (before, after) = (dir.path().join("a.json"), dir.path().join("b.json"));
+    let instruction = |text: &str| {
+        json!({ "drafter.instruction": { "k": "instruction", "text": text } }).to_string()
+    };
+    std::fs::write(&before, instruction("Be brief.")).expect("write");
+    std::fs::write(&after, instruction("Be thorough.")).expect("write");
+    let config = DiffConfig {
+        from: before,
+        to: after,
+        overlay: true,
+        program: Some(fixture("qa.dsrs")),
+        json: false,
+    };
+    assert_eq!(
+        diff_files(&config).expect("diffs"),
+        "params:\n  ~ drafter.instruction: \"Be brief.\" → \"Be thorough.\"\n"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// graph
+// ---------------------------------------------------------------------------
+
+#[test]
+fn graph_renders_dot_and_mermaid_with_an_overlay() {
+    let mut config = GraphConfig {
+        program: fixture("qa.dsrs"),
+        format: GraphFormat::Dot,
+        overlay: None,
+        trace: None,
+    };
+    let dot = graph_file(&config).expect("renders");
+    assert!(dot.starts_with("digraph \"qa\" {"), "{dot}");
+    assert!(dot.contains("agent Research @fast"), "{dot}");
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let overlay = dir.path().join("overlay.json");
+    std::fs::write(
+        &overlay,
+        json!({ "researcher.model": { "k": "model_ref", "model": 1 } }).to_string(),
+    )
+    .expect("write");
+    config.overlay = Some(overlay);
+    config.format = "mermaid".parse().expect("known format");
+    let mermaid = graph_file(&config).expect("renders");
+    assert!(mermaid.starts_with("flowchart TD\n"), "{mermaid}");
+    assert!(
+        mermaid.contains("agent Research @deep<br/>tools [search]<br/>overlay: model"),
+        "{mermaid}"
+    );
+
+    assert!("svg".parse::<GraphFormat>().is_err());
+    config.trace = Some(dir.path().join("missing.trace.jsonl"));
+    let err = graph_file(&config).expect_err("missing trace");
+    assert!(
+        format!("{err:#}").contains("failed to read trace"),
+        "{err:#}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// cost
+// ---------------------------------------------------------------------------
+
+#[test]
+fn cost_reports_bounds_with_prices() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let prices = dir.path().join("prices.json");
+    std::fs::write(
+        &prices,
+        json!({
+            "fast": { "input": 0.15, "output": 0.6 },
+            "anthropic:claude-sonnet-4-5": { "input": 3.0, "output": 15.0 },
+        })
+        .to_string(),
+    )
+    .expect("write");
+    let mut config = CostConfig {
+        program: fixture("qa.dsrs"),
+        overlay: None,
+        prices: Some(prices),
+        assumptions: CostAssumptions::default(),
+        json: false,
+    };
+    let text = cost_file(&config).expect("analyzes");
+    assert!(
+        text.starts_with("program qa: one run, worst / expected\n  lm calls  8 / 4\n"),
+        "{text}"
+    );
+    assert!(text.contains("  cost      $"), "{text}");
+    assert!(!text.contains("flags:"), "{text}");
+
+    config.json = true;
+    config.assumptions.call_ms = 1_000;
+    let report: Value = serde_json::from_str(&cost_file(&config).expect("analyzes")).unwrap();
+    assert_eq!(report["total"]["worst"]["latency_ms"], 8_000.0);
+    assert_eq!(report["nodes"][2]["node"], "researcher");
+    assert!(report["total"]["worst"]["usd"].as_f64().unwrap() > 0.0);
+
+    let bad = dir.path().join("bad.json");
+    std::fs::write(&bad, "[1, 2]").expect("write");
+    config.prices = Some(bad);
+    let err = cost_file(&config).expect_err("not a price table");
+    assert!(
+        format!("{err:#}").contains("is not a price table"),
+        "{err:#}"
+    );
+}
+
 // ---------------------------------------------------------------------------
 // serve
 // ---------------------------------------------------------------------------
@@ -400,3 +594,238 @@ async fn serve_rejects_stale_overlays() {
         "{err:#}"
     );
 }
+
+// ---------------------------------------------------------------------------
+// lsp
+// ---------------------------------------------------------------------------
+
+/// The LSP position of the `nth` occurrence of `needle` in (ASCII) `text`,
+/// `delta` bytes in.
+fn pos_of(text: &str, needle: &str, nth: usize, delta: usize) -> Position {
+    let at = text
+        .match_indices(needle)
+        .nth(nth)
+        .expect("needle present")
+        .0
+        + delta;
+    let line = text[..at].matches('\n').count() as u32;
+    let col = at - text[..at].rfind('\n').map_or(0, |i| i + 1);
+    Position::new(line, col as u32)
+}
+
+/// `text` with `edits` applied (ASCII, non-overlapping).
+fn apply(text: &str, edits: &[TextEdit]) -> String {
+    let offset = |pos: Position| {
+        let start: usize = text
+            .split_inclusive('\n')
+            .take(pos.line as usize)
+            .map(str::len)
+            .sum();
+        start + pos.character as usize
+    };
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| std::cmp::Reverse(offset(edit.range.start)));
+    let mut out = text.to_string();
+    for edit in edits {
+        out.replace_range(
+            offset(edit.range.start)..offset(edit.range.end),
+            &edit.new_text,
+        );
+    }
+    out
+}
+
+fn hover_text(hover: Option<Hover>) -> String {
+    match hover.expect("hover").contents {
+        HoverContents::Markup(markup) => markup.value,
+        other => panic!("markdown hover expected, got {other:?}"),
+    }
+}
+
+#[test]
+fn lsp_hovers_navigates_and_completes() {
+    let path = fixture("qa.dsrs");
+    let text = std::fs::read_to_string(&path).expect("fixture");
+    let doc = Document::new(Url::from_file_path(&path).expect("uri"), text.clone());
+    assert!(doc.diagnostics().is_empty(), "{:?}", doc.diagnostics());
+
+    let hover = hover_text(doc.hover(pos_of(&text, "researcher = agent", 0, 2)));
+    assert!(hover.contains("researcher = agent Research"), "{hover}");
+    assert!(hover.contains("in  draft: string"), "{hover}");
+    assert!(hover.contains("out evidence: string[]"), "{hover}");
+    let hover = hover_text(doc.hover(pos_of(&text, "researcher.evidence", 0, 12)));
+    assert!(hover.contains("researcher.evidence: string[]"), "{hover}");
+    let hover = hover_text(doc.hover(pos_of(&text, "[search]", 0, 2)));
+    assert!(hover.contains("tool search"), "{hover}");
+
+    let def = doc
+        .definition(pos_of(&text, "agent Research", 0, 8))
+        .expect("sig definition");
+    assert_eq!(def.range.start, pos_of(&text, "Research {", 0, 0));
+    let def = doc
+        .definition(pos_of(&text, "drafter.answer", 1, 1))
+        .expect("step definition");
+    assert_eq!(def.range.start, pos_of(&text, "drafter = cot", 0, 0));
+
+    let labels = |items: Vec<CompletionItem>| -> Vec<String> {
+        items.into_iter().map(|item| item.label).collect()
+    };
+    let inputs = doc.completions(pos_of(&text, "$.question", 1, 2));
+    assert_eq!(labels(inputs), vec!["question"]);
+    let fields = labels(doc.completions(pos_of(&text, "researcher.evidence", 0, 11)));
+    assert!(fields.contains(&"evidence".to_string()), "{fields:?}");
+    let outer = labels(doc.completions(pos_of(&text, "checker.answer", 0, 8)));
+    assert!(outer.contains(&"answer".to_string()), "{outer:?}");
+
+    // Half-typed edits keep the last valid analysis for completion.
+    let typing = text.replace("draft = drafter.answer)", "draft = drafter.)");
+    let doc = doc.changed(typing.clone());
+    assert!(!doc.diagnostics().is_empty());
+    let fields = labels(doc.completions(pos_of(&typing, "drafter.)", 0, 8)));
+    assert!(fields.contains(&"answer".to_string()), "{fields:?}");
+    assert!(doc.formatting().is_none(), "stale analysis never formats");
+}
+
+#[test]
+fn lsp_diagnostics_carry_ranges() {
+    let uri = Url::parse("file:///nowhere/twice.dsrs").expect("uri");
+    let src = ECHO.replace("answerer.answer", "answerer.nope");
+    let doc = Document::new(uri, src);
+    let diagnostics = doc.diagnostics();
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diagnostics[0].range.start.line, 12);
+    assert!(diagnostics[0].range.end.character > diagnostics[0].range.start.character);
+}
+
+#[test]
+fn lsp_renames_a_step_and_its_overlay_keys() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = write_program(&dir, "echo.dsrs", ECHO);
+    let overlay_path = dir.path().join("tuned.ovl.json");
+    let overlay = serde_json::to_string_pretty(&json!({
+        "answerer.instruction": {"k": "instruction", "text": "Answer the answerer way."}
+    }))
+    .expect("overlay json");
+    std::fs::write(&overlay_path, &overlay).expect("write overlay");
+    std::fs::write(dir.path().join("unrelated.json"), r#"{"answerer.x": 1}"#).expect("write");
+
+    let uri = Url::from_file_path(&path).expect("uri");
+    let doc = Document::new(uri.clone(), ECHO.to_string());
+    let edit = doc
+        .rename(pos_of(ECHO, "answerer.answer", 0, 3), "responder")
+        .expect("rename");
+    let changes = edit.changes.expect("changes");
+    assert_eq!(changes.len(), 2, "{changes:?}");
+
+    let renamed = apply(ECHO, &changes[&uri]);
+    assert!(renamed.contains("responder = predict Main"), "{renamed}");
+    assert!(renamed.contains("answer = responder.answer"), "{renamed}");
+    let program = dspy_rs::ir::Program::from_dsrs(&renamed).expect("renamed program validates");
+
+    let overlay_uri = Url::from_file_path(&overlay_path).expect("uri");
+    let retuned = apply(&overlay, &changes[&overlay_uri]);
+    assert!(retuned.contains("\"responder.instruction\""), "{retuned}");
+    assert!(retuned.contains("Answer the answerer way."), "{retuned}");
+    let named = serde_json::from_str(&retuned).expect("overlay json");
+    dspy_rs::ir::Overlay::from_named(&program, named).expect("overlay applies after rename");
+
+    let err = doc
+        .rename(pos_of(ECHO, "$.question", 0, 3), "query")
+        .expect_err("only steps rename");
+    assert!(err.contains("only step names"), "{err}");
+    let err = doc
+        .rename(pos_of(ECHO, "answerer =", 0, 0), "9lives")
+        .expect_err("not an identifier");
+    assert!(err.contains("not a step name"), "{err}");
+}
+
+#[test]
+fn lsp_speaks_the_protocol_over_a_connection() {
+    let (server, client) = Connection::memory();
+    let handle = std::thread::spawn(move || dsrs_cli::lsp::serve_connection(&server));
+    let recv = || client.receiver.recv().expect("server message");
+    let send = |message: Message| client.sender.send(message).expect("send");
+
+    send(
+        Request::new(
+            1.into(),
+            "initialize".to_string(),
+            json!({"capabilities": {}}),
+        )
+        .into(),
+    );
+    let Message::Response(init) = recv() else {
+        panic!("initialize response expected");
+    };
+    let caps = init.result.expect("capabilities");
+    assert_eq!(caps["capabilities"]["renameProvider"], true);
+    send(Notification::new("initialized".to_string(), json!({})).into());
+
+    let uri = "file:///nowhere/echo.dsrs";
+    let published = |message: Message| -> Vec<Value> {
+        let Message::Notification(note) = message else {
+            panic!("diagnostics expected, got {message:?}");
+        };
+        assert_eq!(note.method, "textDocument/publishDiagnostics");
+        note.params["diagnostics"]
+            .as_array()
+            .expect("array")
+            .clone()
+    };
+    send(
+        Notification::new(
+            "textDocument/didOpen".to_string(),
+            json!({"textDocument": {
+                "uri": uri, "languageId": "dsrs", "version": 1,
+                "text": "dsrs 1\nprogram broken\nwidget Oops { }\n"
+            }}),
+        )
+        .into(),
+    );
+    let diagnostics = published(recv());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 2);
+    send(
+        Notification::new(
+            "textDocument/didChange".to_string(),
+            json!({
+                "textDocument": {"uri": uri, "version": 2},
+                "contentChanges": [{"text": ECHO}]
+            }),
+        )
+        .into(),
+    );
+    assert!(published(recv()).is_empty());
+
+    send(
+        Request::new(
+            2.into(),
+            "textDocument/formatting".to_string(),
+            json!({
+                "textDocument": {"uri": uri},
+                "options": {"tabSize": 2, "insertSpaces": true}
+            }),
+        )
+        .into(),
+    );
+    let Message::Response(formatted) = recv() else {
+        panic!("formatting response expected");
+    };
+    let edits: Vec<TextEdit> =
+        serde_json::from_value(formatted.result.expect("edits")).expect("text edits");
+    let canonical = dspy_rs::ir::Program::from_dsrs(ECHO)
+        .expect("echo")
+        .to_dsrs();
+    assert_eq!(apply(ECHO, &edits), canonical);
+
+    send(Request::new(3.into(), "shutdown".to_string(), Value::Null).into());
+    let Message::Response(_) = recv() else {
+        panic!("shutdown response expected");
+    };
+    send(Notification::new("exit".to_string(), Value::Null).into());
+    handle
+        .join()
+        .expect("server thread")
+        .expect("clean shutdown");
+}
@@ -6,10 +6,18 @@
 //! the same lexer `Program::from_dsrs` uses); **semantics** are validated by
 //! the full parser at first use of the emitted `LazyLock`, and forced at CI
 //! time by an emitted `#[cfg(test)]` test — the sqlx-offline analogue.
+//!
+//! The module also carries a **typed surface** ([`typed_surface`]): `Input`/
+//! `Output` structs for the `main` signature, one struct per `class`, one
+//! enum per `enum`, and `run`/`run_with` wrappers over `Interpreter::run`.
+//! They are generated from the declaration outline ([`dsrs_syntax::outline`]),
+//! so a field rename in the artifact is a compile error at every call site.
 
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 
-use proc_macro2::Span;
+use dsrs_syntax::{FieldOutline, Outline, TypeExpr};
+use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
 
 use crate::runtime_path::resolve_dspy_rs_path;
@@ -94,8 +102,9 @@ pub(crate) fn expand(
 
     // Build-time gate: syntax only, through the shared dsrs-syntax grammar.
     // Semantic validation happens through the full parser in the emitted
-    // runtime/test code below.
-    dsrs_syntax::check(&text).map_err(|e| {
+    // runtime/test code below. The outline pass is the structural check plus
+    // the declaration bodies the typed surface is generated from.
+    let outline = dsrs_syntax::outline(&text).map_err(|e| {
         err_at(format!(
             "include_program!(\"{rel}\"): line {}, column {}: {}",
             e.line, e.col, e.message
@@ -123,6 +132,8 @@ pub(crate) fn expand(
     })?;
 
     let krate = resolve_dspy_rs_path()?;
+    let typed = typed_surface(&outline, &krate)
+        .map_err(|e| err_at(format!("include_program!(\"{rel}\"): {e}")))?;
     let mod_docs = format!(
         "Program module generated by `include_program!(\"{rel}\")`.\n\n\
          The `.dsrs` text is embedded via `include_str!` (rustc re-expands on \
@@ -172,6 +183,8 @@ pub(crate) fn expand(
                 ::std::sync::LazyLock::force(&__PROGRAM).as_ref()
             }
 
+            #typed
+
             /// Generated by `include_program!`: forces full parse+validation
             /// under `cargo test`, so semantic artifact errors fail CI even
             /// if the program is never accessed at test time (the
@@ -192,3 +205,278 @@ pub(crate) fn expand(
         }
     })
 }
+
+// ---------------------------------------------------------------------------
+// Typed surface
+// ---------------------------------------------------------------------------
+
+/// Generates the typed Rust surface of `outline`: one struct per class, one
+/// enum per enum, `Input`/`Output` for the `main` signature, and the typed
+/// `run`/`run_with` wrappers.
+///
+/// JSON shapes follow the interpreter's run maps exactly: signature and
+/// class fields are keyed by their declared name (aliases only change what
+/// the model sees), enum values serialize as their declared value name.
+/// Errors are plain messages; the caller positions them at the path literal.
+fn typed_surface(outline: &Outline, krate: &syn::Path) -> Result<TokenStream, String> {
+    let main = outline.main_sig().ok_or_else(|| {
+        format!(
+            "`main: {}` names a signature that is not declared in the artifact",
+            outline.main
+        )
+    })?;
+    let names = type_names(outline)?;
+
+    let serde = quote! { #krate::__macro_support::serde };
+    let serde_json = quote! { #krate::__macro_support::serde_json };
+    let serde_crate = format!(
+        "{}::__macro_support::serde",
+        quote!(#krate).to_string().replace(' ', "")
+    );
+    let serde_crate = syn::LitStr::new(&serde_crate, Span::call_site());
+    let derives = quote! {
+        #[derive(Debug, Clone, PartialEq, #serde::Serialize, #serde::Deserialize)]
+        #[serde(crate = #serde_crate)]
+    };
+
+    let mut items = Vec::new();
+    for class in &outline.classes {
+        let ident = &names[&class.name];
+        let docs = class
+            .docs
+            .clone()
+            .unwrap_or_else(|| format!("The `{}` class of the embedded program.", class.name));
+        let fields = struct_fields(&class.fields, &names, &serde_json)
+            .map_err(|e| format!("class `{}`: {e}", class.name))?;
+        items.push(quote! {
+            #[doc = #docs]
+            #derives
+            #[allow(non_snake_case)]
+            pub struct #ident {
+                #(#fields),*
+            }
+        });
+    }
+    for enm in &outline.enums {
+        let ident = &names[&enm.name];
+        let docs = enm
+            .docs
+            .clone()
+            .unwrap_or_else(|| format!("The `{}` enum of the embedded program.", enm.name));
+        let mut variants = Vec::new();
+        for value in &enm.values {
+            let (variant, rename) =
+                rust_ident(&value.name).map_err(|e| format!("enum `{}` value: {e}", enm.name))?;
+            let doc = value.docs.iter();
+            variants.push(quote! {
+                #(#[doc = #doc])*
+                #rename
+                #variant
+            });
+        }
+        items.push(quote! {
+            #[doc = #docs]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, #serde::Serialize, #serde::Deserialize)]
+            #[serde(crate = #serde_crate)]
+            #[allow(non_camel_case_types)]
+            pub enum #ident {
+                #(#variants),*
+            }
+        });
+    }
+
+    let input_fields = struct_fields(&main.inputs, &names, &serde_json)
+        .map_err(|e| format!("sig `{}` input: {e}", main.name))?;
+    let output_fields = struct_fields(&main.outputs, &names, &serde_json)
+        .map_err(|e| format!("sig `{}` output: {e}", main.name))?;
+    let input_docs = format!(
+        "Typed input of the program's `main: {}` signature.",
+        main.name
+    );
+    let output_docs = format!(
+        "Typed output of the program's `main: {}` signature.",
+        main.name
+    );
+
+    Ok(quote! {
+        #(#items)*
+
+        #[doc = #input_docs]
+        #derives
+        #[allow(non_snake_case)]
+        pub struct Input {
+            #(#input_fields),*
+        }
+
+        #[doc = #output_docs]
+        #derives
+        #[allow(non_snake_case)]
+        pub struct Output {
+            #(#output_fields),*
+        }
+
+        /// Runs the program on `interp` with typed input and output: no
+        /// overlay, unlimited budget. See [`run_with`].
+        pub async fn run(
+            interp: &#krate::ir::Interpreter,
+            input: Input,
+        ) -> ::std::result::Result<Output, #krate::ir::RunError> {
+            run_with(interp, input, ::std::option::Option::None, #krate::ir::Budget::unlimited())
+                .await
+        }
+
+        /// Typed `Interpreter::run`: `input` is serialized to the run's input
+        /// map and the output map is deserialized into [`Output`]. `interp`
+        /// should be loaded from this artifact (or one sharing its `main`
+        /// signature and types); an output map that does not fit [`Output`]
+        /// surfaces as `RunError::Internal`.
+        pub async fn run_with(
+            interp: &#krate::ir::Interpreter,
+            input: Input,
+            overlay: ::std::option::Option<::std::sync::Arc<#krate::ir::Overlay>>,
+            budget: #krate::ir::Budget,
+        ) -> ::std::result::Result<Output, #krate::ir::RunError> {
+            let input = match #serde_json::to_value(&input) {
+                ::std::result::Result::Ok(#serde_json::Value::Object(map)) => map,
+                ::std::result::Result::Ok(other) => {
+                    return ::std::result::Result::Err(#krate::ir::RunError::Input {
+                        at: "$".into(),
+                        message: ::std::format!("typed input serialized to a non-object: {other}"),
+                    });
+                }
+                ::std::result::Result::Err(err) => {
+                    return ::std::result::Result::Err(#krate::ir::RunError::Input {
+                        at: "$".into(),
+                        message: ::std::format!("typed input failed to serialize: {err}"),
+                    });
+                }
+            };
+            let output = interp.run(input, overlay, budget).await?;
+            #serde_json::from_value(#serde_json::Value::Object(output)).map_err(|err| {
+                #krate::ir::RunError::Internal {
+                    at: "$".into(),
+                    message: ::std::format!(
+                        "program output does not match the generated `Output` type: {err}"
+                    ),
+                }
+            })
+        }
+    })
+}
+
+/// Maps every class/enum token to its Rust type ident: the last `::` segment
+/// when that is unambiguous, else the whole path joined with `_`.
+fn type_names(outline: &Outline) -> Result<HashMap<String, syn::Ident>, String> {
+    let tokens: Vec<(&str, &str)> = outline
+        .classes
+        .iter()
+        .map(|c| ("class", c.name.as_str()))
+        .chain(outline.enums.iter().map(|e| ("enum", e.name.as_str())))
+        .collect();
+    let last = |token: &str| token.rsplit("::").next().unwrap_or(token).to_string();
+    let mut short_counts: HashMap<String, usize> = HashMap::new();
+    for (_, token) in &tokens {
+        *short_counts.entry(last(token)).or_default() += 1;
+    }
+
+    let mut names = HashMap::new();
+    let mut taken: BTreeSet<String> = ["Input", "Output"].map(String::from).into();
+    for (kind, token) in tokens {
+        let short = last(token);
+        let rust = if short_counts[&short] == 1 {
+            short
+        } else {
+            token.replace("::", "_")
+        };
+        if !taken.insert(rust.clone()) {
+            return Err(format!(
+                "{kind} `{token}` would generate the Rust type `{rust}`, which is already \
+                 taken (`Input`/`Output` are reserved for the main signature); rename the {kind}"
+            ));
+        }
+        let (ident, _) = rust_ident(&rust)?;
+        names.insert(token.to_string(), ident);
+    }
+    Ok(names)
+}
+
+/// A field/variant ident for a declared name. Keywords become raw idents
+/// (serde strips the `r#`); names that cannot be raw (`self`, `crate`, …)
+/// get a trailing `_` plus a `#[serde(rename)]` back to the declared name.
+fn rust_ident(name: &str) -> Result<(syn::Ident, TokenStream), String> {
+    if let Ok(ident) = syn::parse_str::<syn::Ident>(name) {
+        return Ok((ident, quote! {}));
+    }
+    if matches!(name, "self" | "Self" | "super" | "crate" | "_") {
+        let ident = format_ident!("{}_", name);
+        return Ok((ident, quote! { #[serde(rename = #name)] }));
+    }
+    if syn::parse_str::<syn::Ident>(&format!("r#{name}")).is_ok() {
+        return Ok((syn::Ident::new_raw(name, Span::call_site()), quote! {}));
+    }
+    Err(format!("`{name}` is not a valid Rust identifier"))
+}
+
+fn struct_fields(
+    fields: &[FieldOutline],
+    names: &HashMap<String, syn::Ident>,
+    serde_json: &TokenStream,
+) -> Result<Vec<TokenStream>, String> {
+    fields
+        .iter()
+        .map(|field| {
+            let (ident, rename) = rust_ident(&field.name)?;
+            let ty = rust_type(&field.ty, names, serde_json)
+                .map_err(|e| format!("field `{}`: {e}", field.name))?;
+            let optional = if matches!(field.ty, TypeExpr::Optional(_)) {
+                quote! { #[serde(default, skip_serializing_if = "::std::option::Option::is_none")] }
+            } else {
+                quote! {}
+            };
+            let doc = field.docs.iter();
+            Ok(quote! {
+                #(#[doc = #doc])*
+                #rename
+                #optional
+                pub #ident: #ty
+            })
+        })
+        .collect()
+}
+
+/// The Rust type of a `.dsrs` type. Literals and all-literal unions are
+/// strings; any other union is an untyped `serde_json::Value`.
+fn rust_type(
+    ty: &TypeExpr,
+    names: &HashMap<String, syn::Ident>,
+    serde_json: &TokenStream,
+) -> Result<TokenStream, String> {
+    Ok(match ty {
+        TypeExpr::String | TypeExpr::Literal(_) => quote! { ::std::string::String },
+        TypeExpr::Int => quote! { i64 },
+        TypeExpr::Float => quote! { f64 },
+        TypeExpr::Bool => quote! { bool },
+        TypeExpr::Map(value) => {
+            let value = rust_type(value, names, serde_json)?;
+            quote! { ::std::collections::HashMap<::std::string::String, #value> }
+        }
+        TypeExpr::List(item) => {
+            let item = rust_type(item, names, serde_json)?;
+            quote! { ::std::vec::Vec<#item> }
+        }
+        TypeExpr::Optional(inner) => {
+            let inner = rust_type(inner, names, serde_json)?;
+            quote! { ::std::option::Option<#inner> }
+        }
+        TypeExpr::Union(units) if units.iter().all(|u| matches!(u, TypeExpr::Literal(_))) => {
+            quote! { ::std::string::String }
+        }
+        TypeExpr::Union(_) => quote! { #serde_json::Value },
+        TypeExpr::Named(token) => {
+            let ident = names.get(token).ok_or_else(|| {
+                format!("type `{token}` is not declared as a `class` or `enum` in the artifact")
+            })?;
+            quote! { #ident }
+        }
+    })
+}
@@ -1,5 +1,5 @@
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 use std::collections::{HashMap, HashSet};
 use syn::{
     Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, LitStr, Meta, MetaNameValue,
@@ -96,6 +96,10 @@ pub fn module(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// - `program() -> &'static Program` — parse+validate on first access,
 ///   panicking on semantic errors;
 /// - `try_program()` — the non-panicking form;
+/// - the typed surface: `Input`/`Output` structs for the `main` signature,
+///   one struct per `class` and one enum per `enum`, and
+///   `run(&Interpreter, Input) -> Result<Output, RunError>` (plus `run_with`
+///   taking an overlay and budget) over `Interpreter::run`;
 /// - a generated `#[cfg(test)]` test that forces full validation under
 ///   `cargo test`.
 ///
@@ -162,7 +166,10 @@ pub fn derive_example(input: TokenStream) -> TokenStream {
 
 #[proc_macro_derive(
     Signature,
-    attributes(input, output, check, assert, alias, format, render, flatten)
+    attributes(
+        signature, input, output, check, assert, alias, format, render, flatten, prefix, hint,
+        example, order, section
+    )
 )]
 pub fn derive_signature(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -182,6 +189,9 @@ pub fn derive_signature(input: TokenStream) -> TokenStream {
 /// Expands to `#[derive(facet::Facet, serde::Serialize, serde::Deserialize)]` (plus the
 /// crate-path attrs), which is all a type needs to satisfy the blanket `Schema` impl.
 /// Replaced the old BAML `#[BamlType]` attribute (the compat alias is gone).
+///
+/// Enum variants may declare extra spellings the output coercer accepts with
+/// `#[alias("neg", "bad")]`.
 #[proc_macro_attribute]
 #[allow(non_snake_case)]
 pub fn Schema(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -189,11 +199,16 @@ pub fn Schema(_attr: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 fn expand_schema_attr(item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as DeriveInput);
+    let mut input = parse_macro_input!(item as DeriveInput);
     let runtime = match resolve_dspy_rs_path() {
         Ok(path) => path,
         Err(err) => return err.to_compile_error().into(),
     };
+    if let Data::Enum(data) = &mut input.data
+        && let Err(err) = lower_variant_aliases(data)
+    {
+        return err.to_compile_error().into();
+    }
 
     let facet = quote! { #runtime::__macro_support::facet };
     let serde = quote! { #runtime::__macro_support::serde };
@@ -224,6 +239,35 @@ fn expand_schema_attr(item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Rewrites `#[alias("neg", "bad")]` on enum variants into one
+/// `#[facet(alias = "...")]` per spelling, where typesys reads them back as the
+/// value's coercion aliases.
+fn lower_variant_aliases(data: &mut syn::DataEnum) -> syn::Result<()> {
+    for variant in &mut data.variants {
+        let mut attrs = Vec::with_capacity(variant.attrs.len());
+        for attr in std::mem::take(&mut variant.attrs) {
+            if !attr.path().is_ident("alias") {
+                attrs.push(attr);
+                continue;
+            }
+            let aliases = attr.parse_args_with(
+                syn::punctuated::Punctuated::<LitStr, Token![,]>::parse_terminated,
+            )?;
+            if aliases.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected #[alias(\"...\", ...)] with at least one spelling",
+                ));
+            }
+            for alias in aliases {
+                attrs.push(syn::parse_quote! { #[facet(alias = #alias)] });
+            }
+        }
+        variant.attrs = attrs;
+    }
+    Ok(())
+}
+
 /// Declares an LM call as a bodyless function — the function *is* the signature.
 ///
 /// ```ignore
@@ -449,10 +493,149 @@ fn expand_signature(
         }
     };
 
-    let parsed = parse_signature_fields(fields, &input.attrs)?;
+    validate_signature_generics(&input.generics)?;
+    let options = parse_signature_options(&input.attrs)?;
+    if let Some(base) = &options.extends {
+        validate_extends_base(base, &input.generics)?;
+    }
+    let parsed = parse_signature_fields(fields, &input.attrs, options.extends.as_ref())?;
     generate_signature_code(input, &parsed, runtime)
 }
 
+/// Struct-level `#[signature(...)]` options.
+#[derive(Default)]
+struct SignatureOptions {
+    /// `extends = Base`: inherit `Base`'s input/output fields and instruction.
+    extends: Option<syn::Path>,
+}
+
+impl Parse for SignatureOptions {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut options = SignatureOptions::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "extends" {
+                return Err(syn::Error::new_spanned(
+                    &key,
+                    format!(
+                        "unsupported #[signature] option `{key}`; only extends = BaseSignature is allowed"
+                    ),
+                ));
+            }
+            if options.extends.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &key,
+                    "#[signature(extends = ...)] can only be specified once",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            options.extends = Some(input.parse()?);
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(options)
+    }
+}
+
+fn parse_signature_options(attrs: &[Attribute]) -> syn::Result<SignatureOptions> {
+    let mut options = SignatureOptions::default();
+    for attr in attrs {
+        if !attr.path().is_ident("signature") {
+            continue;
+        }
+        let parsed: SignatureOptions = attr.parse_args()?;
+        if let Some(base) = parsed.extends {
+            if options.extends.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[signature(extends = ...)] can only be specified once",
+                ));
+            }
+            options.extends = Some(base);
+        }
+    }
+    Ok(options)
+}
+
+/// Signatures are monomorphized per type argument (one `StaticSigCache` entry
+/// per instantiation), so only type parameters make sense: a borrowed field
+/// can't satisfy `Signature: 'static`, and const params never reach the schema.
+fn validate_signature_generics(generics: &syn::Generics) -> syn::Result<()> {
+    for param in &generics.params {
+        match param {
+            syn::GenericParam::Type(_) => {}
+            syn::GenericParam::Lifetime(lifetime) => {
+                return Err(syn::Error::new_spanned(
+                    lifetime,
+                    "lifetime parameters are not supported on #[derive(Signature)]; hint: signatures are 'static, use owned field types",
+                ));
+            }
+            syn::GenericParam::Const(konst) => {
+                return Err(syn::Error::new_spanned(
+                    konst,
+                    "const parameters are not supported on #[derive(Signature)]; hint: use a type parameter",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The inherited metadata lives in a module-level static, which cannot name the
+/// deriving struct's own type parameters.
+fn validate_extends_base(base: &syn::Path, generics: &syn::Generics) -> syn::Result<()> {
+    let mut collector = TypeParamUsageCollector {
+        tracked: generics
+            .type_params()
+            .map(|param| param.ident.to_string())
+            .collect(),
+        used: HashSet::new(),
+    };
+    collector.visit_path(base);
+    if let Some(param) = collector.used.iter().next() {
+        return Err(syn::Error::new_spanned(
+            base,
+            format!(
+                "#[signature(extends = ...)] cannot use the signature's own type parameter `{param}`; hint: extend a concrete instantiation"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// `path::Base<T>` → `path::Base{suffix}<T>`: the derive-generated helper struct
+/// for a base signature, resolved the same way the base's own name is.
+fn base_helper_path(base: &syn::Path, suffix: &str) -> syn::Path {
+    let mut path = base.clone();
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = format_ident!("{}{}", last.ident, suffix);
+    }
+    path
+}
+
+/// The synthetic `#[flatten] base: BaseInput` / `BaseOutput` field an
+/// `extends` signature carries on each side.
+fn inherited_base_field(base: &syn::Path, suffix: &str) -> ParsedField {
+    let helper = base_helper_path(base, suffix);
+    ParsedField {
+        ident: format_ident!("{}", EXTENDS_FIELD),
+        ty: syn::parse_quote!(#helper),
+        is_input: suffix == "Input",
+        is_output: suffix == "Output",
+        is_flatten: true,
+        description: String::new(),
+        alias: None,
+        input_render: ParsedInputRender::Default,
+        presentation: ParsedPresentation::default(),
+        constraints: Vec::new(),
+    }
+}
+
+/// Field name the inherited base fields are flattened under.
+const EXTENDS_FIELD: &str = "base";
+
 #[derive(Clone)]
 struct ParsedField {
     ident: Ident,
@@ -463,9 +646,33 @@ struct ParsedField {
     description: String,
     alias: Option<String>,
     input_render: ParsedInputRender,
+    presentation: ParsedPresentation,
     constraints: Vec<ParsedConstraint>,
 }
 
+/// `#[input(hidden)]`, `#[prefix]`, `#[hint]`, `#[example]`, `#[order]`,
+/// `#[section]`.
+#[derive(Clone, Default)]
+struct ParsedPresentation {
+    hidden: bool,
+    prefix: Option<String>,
+    hint: Option<String>,
+    examples: Vec<String>,
+    order: Option<i32>,
+    section: Option<String>,
+}
+
+impl ParsedPresentation {
+    fn is_default(&self) -> bool {
+        !self.hidden
+            && self.prefix.is_none()
+            && self.hint.is_none()
+            && self.examples.is_empty()
+            && self.order.is_none()
+            && self.section.is_none()
+    }
+}
+
 #[derive(Clone)]
 enum ParsedInputRender {
     Default,
@@ -490,6 +697,7 @@ struct ParsedSignature {
     input_fields: Vec<ParsedField>,
     output_fields: Vec<ParsedField>,
     instruction: String,
+    extends: Option<syn::Path>,
 }
 
 struct ConstraintArgs {
@@ -532,13 +740,29 @@ impl Parse for ConstraintArgs {
 fn parse_signature_fields(
     fields: &syn::punctuated::Punctuated<syn::Field, Token![,]>,
     attrs: &[Attribute],
+    extends: Option<&syn::Path>,
 ) -> syn::Result<ParsedSignature> {
     let mut input_fields = Vec::new();
     let mut output_fields = Vec::new();
 
+    // Inherited fields come first, in the base's declaration order.
+    if let Some(base) = extends {
+        input_fields.push(inherited_base_field(base, "Input"));
+        output_fields.push(inherited_base_field(base, "Output"));
+    }
+
     for field in fields {
         let parsed = parse_single_field(field)?;
 
+        if extends.is_some() && parsed.ident == EXTENDS_FIELD {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!(
+                    "field name `{EXTENDS_FIELD}` is reserved for the fields inherited via #[signature(extends = ...)]"
+                ),
+            ));
+        }
+
         if parsed.is_input && parsed.is_output {
             return Err(syn::Error::new_spanned(
                 field,
@@ -578,6 +802,7 @@ fn parse_signature_fields(
         input_fields,
         output_fields,
         instruction: collect_doc_comment(attrs),
+        extends: extends.cloned(),
     })
 }
 
@@ -614,18 +839,64 @@ fn parse_single_field(field: &syn::Field) -> syn::Result<ParsedField> {
     let mut render_jinja = None;
     let mut constraints = Vec::new();
     let mut desc_override = None;
+    let mut presentation = ParsedPresentation::default();
 
     for attr in &field.attrs {
         if attr.path().is_ident("input") {
             is_input = true;
-            if let Some(desc) = parse_desc_from_attr(attr, "input")? {
+            let args = parse_role_attr(attr, "input")?;
+            if let Some(desc) = args.desc {
                 desc_override = Some(desc);
             }
+            presentation.hidden |= args.hidden;
         } else if attr.path().is_ident("output") {
             is_output = true;
-            if let Some(desc) = parse_desc_from_attr(attr, "output")? {
+            let args = parse_role_attr(attr, "output")?;
+            if args.hidden {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`hidden` is only supported on #[input] fields",
+                ));
+            }
+            if let Some(desc) = args.desc {
                 desc_override = Some(desc);
             }
+        } else if attr.path().is_ident("prefix") {
+            if presentation.prefix.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[prefix] can only be specified once per field",
+                ));
+            }
+            presentation.prefix = Some(parse_string_attr(attr, "prefix")?);
+        } else if attr.path().is_ident("hint") {
+            if presentation.hint.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[hint] can only be specified once per field",
+                ));
+            }
+            presentation.hint = Some(parse_string_attr(attr, "hint")?);
+        } else if attr.path().is_ident("example") {
+            presentation
+                .examples
+                .push(parse_string_attr(attr, "example")?);
+        } else if attr.path().is_ident("order") {
+            if presentation.order.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[order] can only be specified once per field",
+                ));
+            }
+            presentation.order = Some(parse_order_attr(attr)?);
+        } else if attr.path().is_ident("section") {
+            if presentation.section.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[section] can only be specified once per field",
+                ));
+            }
+            presentation.section = Some(parse_string_attr(attr, "section")?);
         } else if attr.path().is_ident("alias") {
             alias = Some(parse_string_attr(attr, "alias")?);
         } else if attr.path().is_ident("format") {
@@ -681,6 +952,18 @@ fn parse_single_field(field: &syn::Field) -> syn::Result<ParsedField> {
             "#[render] is only supported on #[input] fields",
         ));
     }
+    if presentation.hint.is_some() && !is_output {
+        return Err(syn::Error::new_spanned(
+            field,
+            "#[hint] is only supported on #[output] fields",
+        ));
+    }
+    if !presentation.examples.is_empty() && !is_output {
+        return Err(syn::Error::new_spanned(
+            field,
+            "#[example] is only supported on #[output] fields",
+        ));
+    }
 
     let input_render = if let Some(template) = render_jinja {
         ParsedInputRender::Jinja(template)
@@ -701,11 +984,12 @@ fn parse_single_field(field: &syn::Field) -> syn::Result<ParsedField> {
     if is_flatten
         && (alias.is_some()
             || !matches!(input_render, ParsedInputRender::Default)
+            || !presentation.is_default()
             || !constraints.is_empty())
     {
         return Err(syn::Error::new_spanned(
             field,
-            "#[flatten] cannot be combined with #[alias], #[format], #[render], #[check], or #[assert]",
+            "#[flatten] cannot be combined with #[alias], #[format], #[render], #[prefix], #[hint], #[example], #[order], #[section], hidden, #[check], or #[assert]",
         ));
     }
 
@@ -723,35 +1007,46 @@ fn parse_single_field(field: &syn::Field) -> syn::Result<ParsedField> {
         description,
         alias,
         input_render,
+        presentation,
         constraints,
     })
 }
 
-fn parse_desc_from_attr(attr: &Attribute, attr_name: &str) -> syn::Result<Option<String>> {
+/// Arguments of `#[input(...)]` / `#[output(...)]`.
+#[derive(Default)]
+struct RoleArgs {
+    desc: Option<String>,
+    hidden: bool,
+}
+
+fn parse_role_attr(attr: &Attribute, attr_name: &str) -> syn::Result<RoleArgs> {
     match &attr.meta {
-        Meta::Path(_) => Ok(None),
+        Meta::Path(_) => Ok(RoleArgs::default()),
         Meta::List(list) => {
             let metas = list.parse_args_with(
                 syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated,
             )?;
 
-            if metas.is_empty() {
-                return Ok(None);
-            }
-
-            if metas.len() == 1
-                && let Some(Meta::NameValue(meta)) = metas.first()
-                && meta.path.is_ident("desc")
-            {
-                return Ok(Some(parse_string_expr(&meta.value, meta)?));
+            let mut args = RoleArgs::default();
+            for meta in &metas {
+                match meta {
+                    Meta::NameValue(meta) if meta.path.is_ident("desc") && args.desc.is_none() => {
+                        args.desc = Some(parse_string_expr(&meta.value, meta)?);
+                    }
+                    Meta::Path(path) if path.is_ident("hidden") && !args.hidden => {
+                        args.hidden = true;
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            attr,
+                            format!(
+                                "unsupported arguments for #[{attr_name}(...)]; only desc = \"...\" and hidden are allowed"
+                            ),
+                        ));
+                    }
+                }
             }
-
-            Err(syn::Error::new_spanned(
-                attr,
-                format!(
-                    "unsupported arguments for #[{attr_name}(...)]; only desc = \"...\" is allowed"
-                ),
-            ))
+            Ok(args)
         }
         _ => Err(syn::Error::new_spanned(
             attr,
@@ -774,6 +1069,31 @@ fn parse_string_attr(attr: &Attribute, attr_name: &str) -> syn::Result<String> {
     }
 }
 
+fn parse_order_attr(attr: &Attribute) -> syn::Result<i32> {
+    let lit: syn::LitInt = match &attr.meta {
+        Meta::List(list) => list.parse_args()?,
+        Meta::NameValue(meta) => match &meta.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(lit),
+                ..
+            }) => lit.clone(),
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected an integer literal",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected #[order(N)] or #[order = N]",
+            ));
+        }
+    };
+    lit.base10_parse()
+}
+
 fn parse_render_jinja_attr(attr: &Attribute) -> syn::Result<String> {
     match &attr.meta {
         Meta::List(list) => {
@@ -1085,15 +1405,32 @@ fn generate_signature_code(
     let generics = &input.generics;
 
     let helper_structs = generate_helper_structs(name, generics, parsed, vis, runtime)?;
-    let input_metadata = generate_field_metadata(name, &parsed.input_fields, "INPUT", runtime)?;
-    let output_metadata = generate_field_metadata(name, &parsed.output_fields, "OUTPUT", runtime)?;
+    let input_metadata = generate_field_metadata(
+        name,
+        &parsed.input_fields,
+        "INPUT",
+        parsed.extends.as_ref(),
+        runtime,
+    )?;
+    let output_metadata = generate_field_metadata(
+        name,
+        &parsed.output_fields,
+        "OUTPUT",
+        parsed.extends.as_ref(),
+        runtime,
+    )?;
     let signature_impl = generate_signature_impl(name, generics, parsed, runtime);
+    let extends_assertion = parsed
+        .extends
+        .as_ref()
+        .map(|base| generate_extends_assertion(base, runtime));
 
     Ok(quote! {
         #helper_structs
         #input_metadata
         #output_metadata
         #signature_impl
+        #extends_assertion
     })
 }
 
@@ -1157,6 +1494,19 @@ fn generate_helper_structs(
     );
     let serde_crate = LitStr::new(&serde_crate, proc_macro2::Span::call_site());
 
+    let input_declares = declares_tokens(
+        &parsed.input_fields,
+        parsed.extends.as_ref(),
+        "Input",
+        runtime,
+    );
+    let output_declares = declares_tokens(
+        &parsed.output_fields,
+        parsed.extends.as_ref(),
+        "Output",
+        runtime,
+    );
+
     Ok(quote! {
         #[derive(Debug, Clone, #facet::Facet, #serde::Serialize, #serde::Deserialize)]
         #[facet(crate = #facet)]
@@ -1171,6 +1521,8 @@ fn generate_helper_structs(
                     #(#input_new_fields),*
                 }
             }
+
+            #input_declares
         }
 
         #[derive(Debug, Clone, #facet::Facet, #serde::Serialize, #serde::Deserialize)]
@@ -1186,11 +1538,49 @@ fn generate_helper_structs(
                     #(#output_new_fields),*
                 }
             }
+
+            #output_declares
         }
 
     })
 }
 
+/// LM name of a declared field: its alias, else its Rust name.
+fn lm_name(field: &ParsedField) -> String {
+    field
+        .alias
+        .clone()
+        .unwrap_or_else(|| field.ident.to_string())
+}
+
+/// A hidden `const fn __dsrs_declares(name)` on one helper struct: whether
+/// the side declares the LM name `name`, inherited fields included. An
+/// `extends` signature checks its own fields against its base's at compile
+/// time. Fields under a user `#[flatten]` are not listed; those collisions
+/// still surface when the schema is built.
+fn declares_tokens(
+    fields: &[ParsedField],
+    extends: Option<&syn::Path>,
+    suffix: &str,
+    runtime: &syn::Path,
+) -> proc_macro2::TokenStream {
+    let names = fields
+        .iter()
+        .filter(|field| !field.is_flatten)
+        .map(|field| LitStr::new(&lm_name(field), proc_macro2::Span::call_site()));
+    let inherited = extends.map(|base| {
+        let helper = base_helper_path(base, suffix);
+        quote! { || <#helper>::__dsrs_declares(name) }
+    });
+    quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub const fn __dsrs_declares(name: &str) -> bool {
+            #runtime::__macro_support::declares(&[#(#names),*], name) #inherited
+        }
+    }
+}
+
 fn unconstrained_generics(generics: &syn::Generics) -> syn::Generics {
     let mut helper_generics = generics.clone();
 
@@ -1318,6 +1708,7 @@ fn generate_field_metadata(
     name: &Ident,
     fields: &[ParsedField],
     kind: &str,
+    extends: Option<&syn::Path>,
     runtime: &syn::Path,
 ) -> syn::Result<proc_macro2::TokenStream> {
     let metadata_array_name =
@@ -1389,16 +1780,96 @@ fn generate_field_metadata(
             });
         }
 
+        let presentation = {
+            let optional = |value: &Option<String>| match value {
+                Some(value) => {
+                    let lit = LitStr::new(value, proc_macro2::Span::call_site());
+                    quote! { Some(#lit) }
+                }
+                None => quote! { None },
+            };
+            let hidden = field.presentation.hidden;
+            let prefix = optional(&field.presentation.prefix);
+            let hint = optional(&field.presentation.hint);
+            let examples = field
+                .presentation
+                .examples
+                .iter()
+                .map(|example| LitStr::new(example, proc_macro2::Span::call_site()));
+            let order = match field.presentation.order {
+                Some(order) => quote! { Some(#order) },
+                None => quote! { None },
+            };
+            let section = optional(&field.presentation.section);
+            quote! {
+                #runtime::FieldPresentationSpec {
+                    hidden: #hidden,
+                    prefix: #prefix,
+                    hint: #hint,
+                    examples: &[#(#examples),*],
+                    order: #order,
+                    section: #section,
+                }
+            }
+        };
+
         metadata_specs.push(quote! {
             #runtime::FieldMetadataSpec {
                 rust_name: #rust_name,
                 alias: #alias,
                 constraints: #constraints_name,
                 input_render: #input_render,
+                presentation: #presentation,
             }
         });
     }
 
+    // Flattened fields are matched against metadata by leaf name, so an
+    // `extends` signature re-exports its base's specs ahead of its own — and
+    // refuses, at compile time, a field whose LM name the base already uses.
+    if let Some(base) = extends {
+        let (base_metadata, base_helper) = match kind {
+            "INPUT" => (
+                quote! { input_field_metadata },
+                base_helper_path(base, "Input"),
+            ),
+            _ => (
+                quote! { output_field_metadata },
+                base_helper_path(base, "Output"),
+            ),
+        };
+        let side = kind.to_lowercase();
+        let collision_checks = fields
+            .iter()
+            .filter(|field| !field.is_flatten)
+            .map(|field| {
+                let span = field.ident.span();
+                let name = lm_name(field);
+                let message = format!(
+                    "{side} field `{name}` collides with a field inherited via #[signature(extends = ...)]; hint: rename it or give it an #[alias]"
+                );
+                let message = LitStr::new(&message, span);
+                let name = LitStr::new(&name, span);
+                quote_spanned! {span=>
+                    const _: () = assert!(!<#base_helper>::__dsrs_declares(#name), #message);
+                }
+            });
+        return Ok(quote! {
+            #(#constraint_arrays)*
+            #(#collision_checks)*
+
+            static #metadata_array_name: ::std::sync::LazyLock<
+                ::std::vec::Vec<#runtime::FieldMetadataSpec>,
+            > = ::std::sync::LazyLock::new(|| {
+                let mut specs = <#base as #runtime::Signature>::#base_metadata().to_vec();
+                specs.extend_from_slice(&[
+                    #(#metadata_specs),*
+                ]);
+                specs
+            });
+        });
+    }
+
     Ok(quote! {
         #(#constraint_arrays)*
 
@@ -1408,6 +1879,29 @@ fn generate_field_metadata(
     })
 }
 
+/// Ties the `BaseInput`/`BaseOutput` helper names the derive assumes to the
+/// base's actual `Signature` associated types, so a non-signature base (or a
+/// hand-written impl with differently named helpers) fails at the attribute.
+fn generate_extends_assertion(base: &syn::Path, runtime: &syn::Path) -> proc_macro2::TokenStream {
+    let base_input = base_helper_path(base, "Input");
+    let base_output = base_helper_path(base, "Output");
+    quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            fn __dsrs_assert_extends<S, I, O>()
+            where
+                S: #runtime::Signature<Input = I, Output = O>,
+            {
+            }
+
+            #[allow(dead_code)]
+            fn __dsrs_check_extends() {
+                __dsrs_assert_extends::<#base, #base_input, #base_output>();
+            }
+        };
+    }
+}
+
 fn generate_signature_impl(
     name: &Ident,
     generics: &syn::Generics,
@@ -1418,7 +1912,15 @@ fn generate_signature_impl(
     let output_name = format_ident!("{}Output", name);
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let instruction = LitStr::new(&parsed.instruction, proc_macro2::Span::call_site());
+    // An extending signature without its own doc comment inherits the base's
+    // instruction; a doc comment overrides it.
+    let instruction = match (&parsed.extends, parsed.instruction.is_empty()) {
+        (Some(base), true) => quote! { <#base as #runtime::Signature>::instruction() },
+        _ => {
+            let lit = LitStr::new(&parsed.instruction, proc_macro2::Span::call_site());
+            quote! { #lit }
+        }
+    };
 
     let input_metadata_static =
         format_ident!("__{}_INPUT_METADATA", name.to_string().to_uppercase());
@@ -1446,11 +1948,11 @@ fn generate_signature_impl(
             }
 
             fn input_field_metadata() -> &'static [#runtime::FieldMetadataSpec] {
-                &#input_metadata_static
+                &#input_metadata_static[..]
             }
 
             fn output_field_metadata() -> &'static [#runtime::FieldMetadataSpec] {
-                &#output_metadata_static
+                &#output_metadata_static[..]
             }
         }
     }
@@ -1612,7 +2114,14 @@ fn parse_augmentation_fields(
         for attr in &field.attrs {
             if attr.path().is_ident("output") {
                 is_output = true;
-                if let Some(desc) = parse_desc_from_attr(attr, "output")? {
+                let args = parse_role_attr(attr, "output")?;
+                if args.hidden {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`hidden` is only supported on #[input] fields",
+                    ));
+                }
+                if let Some(desc) = args.desc {
                     desc_override = Some(desc);
                 }
             } else if attr.path().is_ident("input") {
@@ -600,6 +600,7 @@ fn expand_agent_inner(
                             max_history_turns: #ctx_history,
                             tool_result_max_bytes: #ctx_bytes,
                             playbook: #ctx_playbook,
+                            ..::core::default::Default::default()
                         },
                     }),
                 }
@@ -32,6 +32,21 @@ fn try_program_is_ok_and_cached() {
     assert!(std::ptr::eq(a, b), "LazyLock caches one Program value");
 }
 
+#[test]
+fn typed_surface_mirrors_the_main_signature() {
+    // Compile-time field checking: renaming a field in the artifact breaks
+    // these struct literals.
+    let input = qa::Input {
+        question: "What is DSRs?".to_string(),
+    };
+    let output = qa::Output {
+        answer: "A Rust port of DSPy.".to_string(),
+        sources: vec!["https://dsrs.herumbshandilya.com".to_string()],
+    };
+    assert_eq!(input.clone(), input);
+    assert_eq!(output.sources.len(), 1);
+}
+
 // A second inclusion under a different stem must coexist (distinct modules).
 mod nested {
     dsrs_macros::include_program!("tests/programs/qa.dsrs");
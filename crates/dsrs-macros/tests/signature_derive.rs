@@ -1,4 +1,7 @@
-use dspy_rs::{Facet, InputRenderSpec, Schema, Signature as SignatureTrait, SignatureSchema};
+use dspy_rs::{
+    Facet, FieldPresentationSpec, FieldType, InputRenderSpec, Schema, Signature as SignatureTrait,
+    SignatureSchema,
+};
 
 /// Test instruction
 #[derive(dsrs_macros::Signature, Clone, Debug)]
@@ -50,6 +53,24 @@ struct RenderSpecSig {
     answer: String,
 }
 
+#[derive(dsrs_macros::Signature, Clone, Debug)]
+struct PresentationSig {
+    #[input(hidden, desc = "Tenant the metric scores against")]
+    tenant_id: String,
+
+    #[input]
+    #[prefix("Customer wrote:")]
+    #[order(-1)]
+    #[section("Conversation")]
+    message: String,
+
+    #[output]
+    #[hint("answer as a bullet list")]
+    #[example("- restart the router")]
+    #[example("- check the cable")]
+    steps: String,
+}
+
 #[derive(Clone, Debug)]
 #[Schema]
 struct GenericCtx {
@@ -66,6 +87,51 @@ struct GenericFlattenSig<T: Schema + for<'a> Facet<'a> + Clone + Send + Sync> {
     answer: String,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[Schema]
+enum Sentiment {
+    Positive,
+    Negative,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[Schema]
+enum Topic {
+    Billing,
+    Shipping,
+    Returns,
+}
+
+/// Classify the text.
+#[derive(dsrs_macros::Signature, Clone, Debug)]
+struct Classify<L: Schema + for<'a> Facet<'a> + Clone + Send + Sync> {
+    #[input]
+    #[alias("document")]
+    text: String,
+
+    #[output]
+    label: L,
+}
+
+#[derive(dsrs_macros::Signature, Clone, Debug)]
+#[signature(extends = Classify<Topic>)]
+struct ClassifyWithConfidence {
+    #[output]
+    #[check("this >= 0.0 and this <= 1.0", label = "confidence_range")]
+    confidence: f64,
+}
+
+/// Classify the ticket, citing the customer's words.
+#[derive(dsrs_macros::Signature, Clone, Debug)]
+#[signature(extends = ClassifyWithConfidence)]
+struct ClassifyTicket {
+    #[input]
+    customer_tier: String,
+
+    #[output]
+    quote: String,
+}
+
 #[test]
 fn generates_typed_input_and_output_helpers() {
     let input = TestSigInput::new("test".to_string());
@@ -157,3 +223,104 @@ fn emits_input_render_metadata() {
         Some(InputRenderSpec::Format("yaml"))
     );
 }
+
+#[test]
+fn emits_presentation_metadata() {
+    let input_meta = <PresentationSig as SignatureTrait>::input_field_metadata();
+    assert_eq!(
+        input_meta[0].presentation,
+        FieldPresentationSpec {
+            hidden: true,
+            ..FieldPresentationSpec::default()
+        }
+    );
+    assert_eq!(input_meta[1].presentation.prefix, Some("Customer wrote:"));
+    assert_eq!(input_meta[1].presentation.order, Some(-1));
+    assert_eq!(input_meta[1].presentation.section, Some("Conversation"));
+    assert!(!input_meta[1].presentation.hidden);
+
+    let output_meta = <PresentationSig as SignatureTrait>::output_field_metadata();
+    assert_eq!(
+        output_meta[0].presentation.hint,
+        Some("answer as a bullet list")
+    );
+    assert_eq!(
+        output_meta[0].presentation.examples,
+        &["- restart the router", "- check the cable"]
+    );
+
+    let schema = SignatureSchema::of::<PresentationSig>();
+    assert!(schema.input_fields()[0].presentation.hidden);
+    assert_eq!(
+        schema.input_fields()[0].docs,
+        "Tenant the metric scores against"
+    );
+}
+
+#[test]
+fn generic_signatures_monomorphize_into_distinct_schemas() {
+    let sentiment = SignatureSchema::of::<Classify<Sentiment>>();
+    let topic = SignatureSchema::of::<Classify<Topic>>();
+    assert!(!std::ptr::eq(sentiment, topic));
+
+    assert_eq!(sentiment.instruction(), "Classify the text.");
+    assert_eq!(sentiment.input_fields()[0].lm_name, "document");
+    let label_enum = |schema: &SignatureSchema| match &schema.output_fields()[0].type_ir {
+        FieldType::Enum(name) => name.clone(),
+        other => panic!("expected an enum label, got {other:?}"),
+    };
+    assert!(label_enum(sentiment).ends_with("Sentiment"));
+    assert!(label_enum(topic).ends_with("Topic"));
+
+    let _output = ClassifyOutput::<Sentiment>::new(Sentiment::Negative);
+}
+
+#[test]
+fn extends_inherits_fields_metadata_and_instruction() {
+    let schema = SignatureSchema::of::<ClassifyWithConfidence>();
+    assert_eq!(schema.instruction(), "Classify the text.");
+
+    let inputs: Vec<&str> = schema.input_fields().iter().map(|f| f.lm_name).collect();
+    assert_eq!(inputs, vec!["document"]);
+    let outputs: Vec<&str> = schema.output_fields().iter().map(|f| f.lm_name).collect();
+    assert_eq!(outputs, vec!["label", "confidence"]);
+    assert_eq!(schema.output_fields()[1].constraints.len(), 1);
+
+    let input = ClassifyWithConfidenceInput::new(ClassifyInput::new("late parcel".to_string()));
+    assert_eq!(input.base.text, "late parcel");
+}
+
+#[test]
+fn extends_chains_and_own_doc_overrides_instruction() {
+    let schema = SignatureSchema::of::<ClassifyTicket>();
+    assert_eq!(
+        schema.instruction(),
+        "Classify the ticket, citing the customer's words."
+    );
+
+    let inputs: Vec<&str> = schema.input_fields().iter().map(|f| f.lm_name).collect();
+    assert_eq!(inputs, vec!["document", "customer_tier"]);
+    let outputs: Vec<&str> = schema.output_fields().iter().map(|f| f.lm_name).collect();
+    assert_eq!(outputs, vec!["label", "confidence", "quote"]);
+
+    let output_paths: Vec<Vec<&str>> = schema
+        .output_fields()
+        .iter()
+        .map(|field| field.path().iter().collect())
+        .collect();
+    assert_eq!(
+        output_paths,
+        vec![
+            vec!["base", "base", "label"],
+            vec!["base", "confidence"],
+            vec!["quote"],
+        ]
+    );
+
+    let metadata = <ClassifyTicket as SignatureTrait>::output_field_metadata();
+    assert!(
+        metadata
+            .iter()
+            .any(|spec| spec.rust_name == "confidence" && spec.constraints.len() == 1)
+    );
+}
@@ -0,0 +1,22 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+struct Base {
+    #[input]
+    question: String,
+
+    #[output]
+    answer: String,
+}
+
+#[derive(Signature)]
+#[signature(extends = Base)]
+struct Derived {
+    #[input]
+    context: String,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
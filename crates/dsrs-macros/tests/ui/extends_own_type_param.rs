@@ -0,0 +1,13 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+#[signature(extends = Classify<L>)]
+struct Relabel<L> {
+    #[input]
+    hint: String,
+
+    #[output]
+    label: L,
+}
+
+fn main() {}
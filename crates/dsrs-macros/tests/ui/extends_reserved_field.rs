@@ -0,0 +1,13 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+#[signature(extends = Base)]
+struct Derived {
+    #[input]
+    base: String,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
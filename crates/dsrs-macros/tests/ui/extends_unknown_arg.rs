@@ -0,0 +1,13 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+#[signature(inherits = Base)]
+struct Derived {
+    #[input]
+    question: String,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
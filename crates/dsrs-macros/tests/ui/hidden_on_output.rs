@@ -0,0 +1,12 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+struct HiddenOutput {
+    #[input]
+    question: String,
+
+    #[output(hidden)]
+    answer: String,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+struct HintOnInput {
+    #[input]
+    #[hint("one sentence")]
+    question: String,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
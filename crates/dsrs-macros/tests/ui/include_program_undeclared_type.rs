@@ -0,0 +1,5 @@
+// Syntactically valid, but the main signature names a type no `class`/`enum`
+// declares — the typed surface cannot be generated, so the macro refuses.
+dsrs_macros::include_program!("include_program_undeclared_type.dsrs");
+
+fn main() {}
@@ -0,0 +1,12 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+struct FixedSig<const N: usize> {
+    #[input]
+    question: String,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use dsrs_macros::Signature;
+
+#[derive(Signature)]
+struct BorrowedSig<'a> {
+    #[input]
+    question: &'a str,
+
+    #[output]
+    answer: String,
+}
+
+fn main() {}
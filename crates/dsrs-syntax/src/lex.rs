@@ -94,12 +94,24 @@ pub struct Lexed {
     pub start: usize,
 }
 
+/// A `//` comment skipped as trivia, kept so formatters can reprint it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub span: Span,
+    /// The comment text after `//`, trailing whitespace trimmed.
+    pub text: String,
+    /// Whether code precedes the comment on its line (`x = 1 // why`).
+    pub trailing: bool,
+}
+
 pub struct Lexer<'a> {
     src: &'a str,
     bytes: &'a [u8],
     pos: usize,
     line: u32,
     col: u32,
+    /// Comments skipped since the last [`Lexer::take_comments`].
+    comments: Vec<Comment>,
 }
 
 impl<'a> Lexer<'a> {
@@ -110,9 +122,17 @@ impl<'a> Lexer<'a> {
             pos: 0,
             line: 1,
             col: 1,
+            comments: Vec::new(),
         }
     }
 
+    /// Drains the comments skipped so far, in source order. The parser holds
+    /// one token of lookahead, so these include any comment before the
+    /// current token.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
+    }
+
     fn span(&self) -> Span {
         Span {
             line: self.line,
@@ -144,12 +164,20 @@ impl<'a> Lexer<'a> {
                     self.bump();
                 }
                 Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    let span = self.span();
+                    let start = self.pos;
                     while let Some(b) = self.peek_byte() {
                         if b == b'\n' {
                             break;
                         }
                         self.bump();
                     }
+                    let line_start = self.src[..start].rfind('\n').map_or(0, |i| i + 1);
+                    self.comments.push(Comment {
+                        span,
+                        text: self.src[start + 2..self.pos].trim_end().to_string(),
+                        trailing: !self.src[line_start..start].trim().is_empty(),
+                    });
                 }
                 _ => break,
             }
@@ -8,7 +8,8 @@
 //!   [`lex`] and lowers them through its program builder; types, dataflow,
 //!   and every other semantic rule live there.
 //! - `dsrs_macros` — `include_program!` validates artifacts at macro
-//!   expansion via [`check`], the syntax-only structural pass. The macro
+//!   expansion via [`check`], the syntax-only structural pass, and generates
+//!   typed Rust from the declarations read by [`outline`]. The macro
 //!   crate cannot depend on `dspy-rs` (which depends on it), so this leaf
 //!   crate is what breaks the cycle.
 //!
@@ -17,8 +18,13 @@
 //! made **here once** and both frontends pick them up.
 
 pub mod lex;
+mod outline;
 mod structure;
 
+pub use outline::{
+    ClassOutline, EnumOutline, EnumValueOutline, FieldOutline, Outline, SigOutline, TypeExpr,
+    outline,
+};
 pub use structure::check;
 
 /// A parse failure with the source position and what was expected — designed
@@ -49,4 +55,92 @@ impl ParseError {
             message: message.into(),
         }
     }
+
+    /// The offending line of `src` with carets under the error, rustc-style:
+    ///
+    /// ```text
+    ///    |
+    /// 12 |   answerer = predict Answer (passage = $.passage)
+    ///    |   ^^^^^^^^
+    /// ```
+    ///
+    /// The carets span the identifier at the error column (one caret on
+    /// punctuation). Empty when the position is not inside `src`.
+    pub fn snippet(&self, src: &str) -> String {
+        let Some(text) = src.lines().nth(self.line.saturating_sub(1) as usize) else {
+            return String::new();
+        };
+        let mut start = (self.col.saturating_sub(1) as usize).min(text.len());
+        while !text.is_char_boundary(start) {
+            start -= 1;
+        }
+        let (before, rest) = text.split_at(start);
+        // Keep tabs so the carets line up with the source as displayed.
+        let pad: String = before
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .count()
+            .max(1);
+        let gutter = self.line.to_string().len();
+        format!(
+            "{:gutter$} |\n{} | {text}\n{:gutter$} | {pad}{}",
+            "",
+            self.line,
+            "",
+            "^".repeat(width)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseError;
+
+    #[test]
+    fn snippet_underlines_the_identifier_at_the_error() {
+        let src = "dsrs 1\nprogram p\n\nmain: Main = seq {\n  answerer = predict QA ()\n}\n";
+        let err = ParseError {
+            line: 5,
+            col: 3,
+            message: "unbound input".to_string(),
+        };
+        assert_eq!(
+            err.snippet(src),
+            "  |\n5 |   answerer = predict QA ()\n  |   ^^^^^^^^"
+        );
+        let punct = ParseError {
+            col: 25,
+            ..err.clone()
+        };
+        assert!(
+            punct
+                .snippet(src)
+                .ends_with(&format!("| {}^", " ".repeat(24)))
+        );
+        assert_eq!(ParseError { line: 40, ..err }.snippet(src), "");
+    }
+
+    #[test]
+    fn the_lexer_keeps_comments_as_trivia() {
+        use crate::lex::{Lexer, Tok};
+
+        let mut lx = Lexer::new("// header\nsig A { // why  \n}\n");
+        let mut comments = Vec::new();
+        loop {
+            let tok = lx.next_token().unwrap().tok;
+            comments.extend(lx.take_comments());
+            if tok == Tok::Eof {
+                break;
+            }
+        }
+        let seen: Vec<(&str, bool, u32)> = comments
+            .iter()
+            .map(|c| (c.text.as_str(), c.trailing, c.span.line))
+            .collect();
+        assert_eq!(seen, [(" header", false, 1), (" why", true, 2)]);
+    }
 }
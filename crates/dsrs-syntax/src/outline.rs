@@ -0,0 +1,502 @@
+//! Declaration **outline** of a `.dsrs` artifact: the program's signatures,
+//! classes, and enums with their field types, read at the syntax layer.
+//!
+//! This is what `include_program!` generates typed Rust from (`Input`/
+//! `Output` structs for the main signature, one struct per class, one enum
+//! per enum). It rides on the structural checker ([`crate::check`]) — every
+//! artifact [`outline`] accepts also passes `check` — and reads the
+//! declaration bodies with the same field/type grammar as the full parser.
+//! Everything else (models, tools, lineage, the `main` body) is skipped
+//! structurally, exactly as `check` does.
+//!
+//! Like `check`, this is syntax only: an outline may name a type that no
+//! declaration defines, or a `main` signature that does not exist. Resolution
+//! is the consumer's job ([`Outline::main_sig`] returns `None`; the macro
+//! reports it), and the full parser remains the authority on semantics.
+
+use crate::ParseError;
+use crate::lex::{Span, Tok};
+use crate::structure::Checker;
+
+/// Reads the declaration outline of `src`. Fails with the same positioned
+/// errors as [`crate::check`], plus field/type syntax errors inside `sig`,
+/// `class`, and `enum` bodies.
+pub fn outline(src: &str) -> Result<Outline, ParseError> {
+    Ok(Checker::new(src)?
+        .outlining()
+        .file()?
+        .expect("outline mode returns an outline"))
+}
+
+/// The declarations of one `.dsrs` program, in source order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    /// The `program <name>` header.
+    pub program: String,
+    /// The signature name after `main:`.
+    pub main: String,
+    pub sigs: Vec<SigOutline>,
+    pub classes: Vec<ClassOutline>,
+    pub enums: Vec<EnumOutline>,
+}
+
+impl Outline {
+    pub fn sig(&self, name: &str) -> Option<&SigOutline> {
+        self.sigs.iter().find(|sig| sig.name == name)
+    }
+
+    /// The program's external signature (`main: <Sig>`), if declared.
+    pub fn main_sig(&self) -> Option<&SigOutline> {
+        self.sig(&self.main)
+    }
+}
+
+/// A `sig Name { … }` declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigOutline {
+    pub name: String,
+    pub instruction: Option<String>,
+    pub inputs: Vec<FieldOutline>,
+    pub outputs: Vec<FieldOutline>,
+    pub span: Span,
+}
+
+/// A `class Name { … }` declaration. `name` keeps `::` qualification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassOutline {
+    pub name: String,
+    pub docs: Option<String>,
+    pub fields: Vec<FieldOutline>,
+    pub span: Span,
+}
+
+/// An `enum Name { … }` declaration. `name` keeps `::` qualification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumOutline {
+    pub name: String,
+    pub docs: Option<String>,
+    pub values: Vec<EnumValueOutline>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueOutline {
+    pub name: String,
+    pub docs: Option<String>,
+}
+
+/// One signature or class field: its declared name (the JSON key at run
+/// time — aliases only affect what the model sees) and type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldOutline {
+    pub name: String,
+    pub ty: TypeExpr,
+    pub docs: Option<String>,
+    pub span: Span,
+}
+
+/// A field type as written. `Named` is a class *or* enum token; the syntax
+/// layer does not tell them apart (the full parser resolves it against the
+/// declarations).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr {
+    String,
+    Int,
+    Float,
+    Bool,
+    /// `map<T>` — string keys.
+    Map(Box<TypeExpr>),
+    /// `T[]`
+    List(Box<TypeExpr>),
+    /// `T?`
+    Optional(Box<TypeExpr>),
+    /// `A | B | …`
+    Union(Vec<TypeExpr>),
+    /// `"value"`
+    Literal(String),
+    Named(String),
+}
+
+impl Checker<'_> {
+    fn ident(&mut self, context: &str) -> Result<(String, Span), ParseError> {
+        match &self.cur.tok {
+            Tok::Ident(name) => {
+                let out = (name.clone(), self.cur.span);
+                self.bump()?;
+                Ok(out)
+            }
+            other => Err(self.err(format!(
+                "expected a name {context}, found {}",
+                other.describe()
+            ))),
+        }
+    }
+
+    fn string(&mut self, context: &str) -> Result<String, ParseError> {
+        match &self.cur.tok {
+            Tok::Str(value) => {
+                let value = value.clone();
+                self.bump()?;
+                Ok(value)
+            }
+            other => Err(self.err(format!(
+                "expected a string {context}, found {}",
+                other.describe()
+            ))),
+        }
+    }
+
+    fn opt_string(&mut self) -> Result<Option<String>, ParseError> {
+        match &self.cur.tok {
+            Tok::Str(value) => {
+                let value = value.clone();
+                self.bump()?;
+                Ok(Some(value))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// `IDENT ("::" IDENT)*`
+    fn qualified_name(&mut self, context: &str) -> Result<(String, Span), ParseError> {
+        let (mut name, span) = self.ident(context)?;
+        while self.cur.tok == Tok::ColonColon {
+            self.bump()?;
+            let (part, _) = self.ident("after `::`")?;
+            name.push_str("::");
+            name.push_str(&part);
+        }
+        Ok((name, span))
+    }
+
+    pub(crate) fn sig_outline(&mut self) -> Result<SigOutline, ParseError> {
+        self.bump()?; // sig
+        let (name, span) = self.ident("after `sig`")?;
+        self.expect_tok(Tok::LBrace, "after the sig name")?;
+        let instruction = self.opt_string()?;
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        while self.cur.tok != Tok::RBrace {
+            let is_input = if self.at_kw("in") {
+                true
+            } else if self.at_kw("out") {
+                false
+            } else {
+                return Err(self.err(format!(
+                    "expected `in` or `out` to declare a field, found {}",
+                    self.cur.tok.describe()
+                )));
+            };
+            self.bump()?;
+            let field = self.field_outline(true)?;
+            if is_input {
+                inputs.push(field);
+            } else {
+                outputs.push(field);
+            }
+        }
+        self.bump()?; // }
+        Ok(SigOutline {
+            name,
+            instruction,
+            inputs,
+            outputs,
+            span,
+        })
+    }
+
+    pub(crate) fn class_outline(&mut self) -> Result<ClassOutline, ParseError> {
+        self.bump()?; // class
+        let (name, span) = self.qualified_name("after `class`")?;
+        if self.at_kw("alias") {
+            self.bump()?;
+            self.string("after `alias`")?;
+        }
+        self.expect_tok(Tok::LBrace, "after the class name")?;
+        let docs = self.opt_string()?;
+        let mut fields = Vec::new();
+        while self.cur.tok != Tok::RBrace {
+            fields.push(self.field_outline(false)?);
+        }
+        self.bump()?; // }
+        Ok(ClassOutline {
+            name,
+            docs,
+            fields,
+            span,
+        })
+    }
+
+    pub(crate) fn enum_outline(&mut self) -> Result<EnumOutline, ParseError> {
+        self.bump()?; // enum
+        let (name, span) = self.qualified_name("after `enum`")?;
+        if self.at_kw("alias") {
+            self.bump()?;
+            self.string("after `alias`")?;
+        }
+        self.expect_tok(Tok::LBrace, "after the enum name")?;
+        let docs = self.opt_string()?;
+        let mut values = Vec::new();
+        while self.cur.tok != Tok::RBrace {
+            let (value, _) = self.ident("as an enum value")?;
+            // `alias "x"` (LM-facing rename) and/or `alias ["x" "y"]` (extra
+            // spellings coercion accepts).
+            while self.at_kw("alias") {
+                self.bump()?;
+                if self.cur.tok == Tok::LBracket {
+                    self.bump()?;
+                    while self.cur.tok != Tok::RBracket {
+                        self.string("inside `alias [ ... ]`")?;
+                    }
+                    self.bump()?; // ]
+                } else {
+                    self.string("after `alias`")?;
+                }
+            }
+            let docs = self.opt_string()?;
+            values.push(EnumValueOutline { name: value, docs });
+        }
+        self.bump()?; // }
+        Ok(EnumOutline {
+            name,
+            docs,
+            values,
+            span,
+        })
+    }
+
+    /// `name: type metadata*`. Signature fields additionally accept the
+    /// render and presentation metadata (`format`, `jinja`, `prefix`,
+    /// `hint`, `example`, `hidden`, `order`, `section`); class fields do not.
+    fn field_outline(&mut self, sig_field: bool) -> Result<FieldOutline, ParseError> {
+        let (name, span) = self.ident("as the field name")?;
+        self.expect_tok(Tok::Colon, "after the field name")?;
+        let ty = self.type_expr()?;
+        let mut docs = None;
+        loop {
+            match &self.cur.tok {
+                Tok::Str(text) => {
+                    docs = Some(text.clone());
+                    self.bump()?;
+                }
+                Tok::Ident(word) if word == "alias" => {
+                    self.bump()?;
+                    self.string("after `alias`")?;
+                }
+                Tok::Ident(word)
+                    if sig_field
+                        && matches!(
+                            word.as_str(),
+                            "format" | "jinja" | "prefix" | "hint" | "example" | "section"
+                        ) =>
+                {
+                    let context = format!("after `{word}`");
+                    self.bump()?;
+                    self.string(&context)?;
+                }
+                Tok::Ident(word) if sig_field && word == "hidden" => {
+                    self.bump()?;
+                }
+                Tok::Ident(word) if sig_field && word == "order" => {
+                    self.bump()?;
+                    if !matches!(self.cur.tok, Tok::Num(_)) {
+                        return Err(self.err(format!(
+                            "expected an integer after `order`, found {}",
+                            self.cur.tok.describe()
+                        )));
+                    }
+                    self.bump()?;
+                }
+                Tok::Ident(word) if word == "check" || word == "assert" => {
+                    self.bump()?;
+                    if self.cur.tok != Tok::LParen {
+                        return Err(self.err(format!(
+                            "expected `(` after `check`/`assert`, found {}",
+                            self.cur.tok.describe()
+                        )));
+                    }
+                    self.skip_balanced("after `check`/`assert`")?;
+                }
+                _ => break,
+            }
+        }
+        Ok(FieldOutline {
+            name,
+            ty,
+            docs,
+            span,
+        })
+    }
+
+    fn type_expr(&mut self) -> Result<TypeExpr, ParseError> {
+        let mut units = vec![self.type_unit()?];
+        while self.cur.tok == Tok::Pipe {
+            self.bump()?;
+            units.push(self.type_unit()?);
+        }
+        Ok(if units.len() == 1 {
+            units.pop().expect("one unit")
+        } else {
+            TypeExpr::Union(units)
+        })
+    }
+
+    fn type_unit(&mut self) -> Result<TypeExpr, ParseError> {
+        let mut ty = self.type_prim()?;
+        loop {
+            match &self.cur.tok {
+                Tok::LBracket => {
+                    self.bump()?;
+                    self.expect_tok(Tok::RBracket, "to close `[]`")?;
+                    ty = TypeExpr::List(Box::new(ty));
+                }
+                Tok::Question => {
+                    self.bump()?;
+                    ty = TypeExpr::Optional(Box::new(ty));
+                }
+                _ => break,
+            }
+        }
+        Ok(ty)
+    }
+
+    fn type_prim(&mut self) -> Result<TypeExpr, ParseError> {
+        match &self.cur.tok {
+            Tok::Ident(word) => {
+                let prim = match word.as_str() {
+                    "string" => Some(TypeExpr::String),
+                    "int" => Some(TypeExpr::Int),
+                    "float" => Some(TypeExpr::Float),
+                    "bool" => Some(TypeExpr::Bool),
+                    _ => None,
+                };
+                if let Some(prim) = prim {
+                    self.bump()?;
+                    return Ok(prim);
+                }
+                if word == "map" {
+                    self.bump()?;
+                    self.expect_tok(Tok::Lt, "after `map`")?;
+                    let value = self.type_expr()?;
+                    self.expect_tok(Tok::Gt, "to close `map<...>`")?;
+                    return Ok(TypeExpr::Map(Box::new(value)));
+                }
+                let (token, _) = self.qualified_name("as a type")?;
+                Ok(TypeExpr::Named(token))
+            }
+            Tok::Str(value) => {
+                let value = value.clone();
+                self.bump()?;
+                Ok(TypeExpr::Literal(value))
+            }
+            Tok::LParen => {
+                self.bump()?;
+                let ty = self.type_expr()?;
+                self.expect_tok(Tok::RParen, "to close the type group")?;
+                Ok(ty)
+            }
+            other => Err(self.err(format!(
+                "expected a type (string, int, float, bool, map<...>, a class/enum name, or a \
+                 \"literal\"), found {}",
+                other.describe()
+            ))),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{TypeExpr, outline};
+
+    const TYPED: &str = r#"
+dsrs 1
+program typed
+
+class shop::Item alias "Item" {
+  "One line item."
+  sku: string "stock keeping unit"
+  qty: int check("this >= 0", "non-negative")
+  tags: string[]?
+  kind: "gold" | "basic"
+}
+
+enum Priority {
+  Low alias "low" "can wait"
+  High
+}
+
+sig Main {
+  "Triage an order."
+  in order: shop::Item[]
+  in notes: map<string>? alias "remarks"
+  out priority: Priority format "json"
+  out score: float
+}
+
+main: Main = seq {
+  a = predict Main (order = $.order, notes = $.notes)
+  out { priority = a.priority, score = a.score }
+}
+"#;
+
+    #[test]
+    fn reads_sigs_classes_and_enums() {
+        let outline = outline(TYPED).expect("outline");
+        assert_eq!(outline.program, "typed");
+        assert_eq!(outline.main, "Main");
+
+        let main = outline.main_sig().expect("main sig");
+        assert_eq!(main.instruction.as_deref(), Some("Triage an order."));
+        let inputs: Vec<_> = main.inputs.iter().map(|f| (&*f.name, &f.ty)).collect();
+        assert_eq!(
+            inputs,
+            [
+                (
+                    "order",
+                    &TypeExpr::List(Box::new(TypeExpr::Named("shop::Item".into())))
+                ),
+                (
+                    "notes",
+                    &TypeExpr::Optional(Box::new(TypeExpr::Map(Box::new(TypeExpr::String))))
+                ),
+            ]
+        );
+        assert_eq!(main.outputs[0].ty, TypeExpr::Named("Priority".into()));
+        assert_eq!(main.outputs[1].ty, TypeExpr::Float);
+
+        let class = &outline.classes[0];
+        assert_eq!(class.name, "shop::Item");
+        assert_eq!(class.docs.as_deref(), Some("One line item."));
+        assert_eq!(class.fields[0].docs.as_deref(), Some("stock keeping unit"));
+        assert_eq!(
+            class.fields[3].ty,
+            TypeExpr::Union(vec![
+                TypeExpr::Literal("gold".into()),
+                TypeExpr::Literal("basic".into())
+            ])
+        );
+
+        let values: Vec<_> = outline.enums[0]
+            .values
+            .iter()
+            .map(|v| (&*v.name, v.docs.as_deref()))
+            .collect();
+        assert_eq!(values, [("Low", Some("can wait")), ("High", None)]);
+    }
+
+    #[test]
+    fn reports_field_syntax_errors_with_positions() {
+        let src = "dsrs 1\nprogram x\nsig Main {\n  in q: map<string\n}\nmain: Main = seq { }";
+        let err = outline(src).expect_err("unclosed map type");
+        assert_eq!(err.line, 5);
+        assert!(
+            err.message.contains("to close `map<...>`"),
+            "{}",
+            err.message
+        );
+    }
+}
@@ -17,9 +17,11 @@
 //! Checked at build time — the classic authoring slips, with line/column:
 //! - the `dsrs 1` pragma (and format-major rejection),
 //! - `program <name>`,
-//! - top-level keyword vocabulary (`caps model sig class enum tool lineage
-//!   main`; unknown top-level keywords are rejected, per RFC 0002 §5 semver),
-//! - the shape of each declaration header (`model x = "…"`,
+//! - top-level keyword vocabulary (`import caps model sig class enum tool
+//!   lineage main`; unknown top-level keywords are rejected, per RFC 0002 §5
+//!   semver),
+//! - the shape of each declaration header (`import "…" as x pin "…"`,
+//!   `model x = "…"`,
 //!   `tool x "…" caps [ … ] { … } js ``` … ````, `main: Sig = seq { … }`),
 //! - balanced `{ } ( ) [ ]` with fence-aware raw regions,
 //! - lexical validity: JSON strings, numbers, `js` code fences, comments,
@@ -34,36 +36,51 @@
 
 use crate::ParseError;
 use crate::lex::{Lexed, Lexer, Span, Tok};
+use crate::outline::Outline;
 
 /// Checks `.dsrs` source for structural syntax validity. See the module docs
 /// for the exact contract: this is syntax-only, and strictly more permissive
 /// than `dspy_rs::ir::Program::from_dsrs`.
 pub fn check(src: &str) -> Result<(), ParseError> {
-    Checker::new(src)?.file()
+    Checker::new(src)?.file().map(|_| ())
 }
 
-struct Checker<'a> {
+pub(crate) struct Checker<'a> {
     lx: Lexer<'a>,
-    cur: Lexed,
+    pub(crate) cur: Lexed,
+    /// When set, `sig`/`class`/`enum` bodies are read into the outline
+    /// instead of skipped (see [`crate::outline`]).
+    outline: Option<Outline>,
 }
 
 impl<'a> Checker<'a> {
-    fn new(src: &'a str) -> Result<Self, ParseError> {
+    pub(crate) fn new(src: &'a str) -> Result<Self, ParseError> {
         let mut lx = Lexer::new(src);
         let cur = lx.next_token()?;
-        Ok(Self { lx, cur })
+        Ok(Self {
+            lx,
+            cur,
+            outline: None,
+        })
     }
 
-    fn bump(&mut self) -> Result<Lexed, ParseError> {
+    /// Switches the checker to outline mode: [`file`](Self::file) returns the
+    /// declaration outline alongside the structural verdict.
+    pub(crate) fn outlining(mut self) -> Self {
+        self.outline = Some(Outline::default());
+        self
+    }
+
+    pub(crate) fn bump(&mut self) -> Result<Lexed, ParseError> {
         let cur = std::mem::replace(&mut self.cur, self.lx.next_token()?);
         Ok(cur)
     }
 
-    fn err(&self, message: impl Into<String>) -> ParseError {
+    pub(crate) fn err(&self, message: impl Into<String>) -> ParseError {
         ParseError::at(self.cur.span, message)
     }
 
-    fn at_kw(&self, kw: &str) -> bool {
+    pub(crate) fn at_kw(&self, kw: &str) -> bool {
         matches!(&self.cur.tok, Tok::Ident(word) if word == kw)
     }
 
@@ -105,7 +122,7 @@ impl<'a> Checker<'a> {
         }
     }
 
-    fn expect_tok(&mut self, tok: Tok, context: &str) -> Result<(), ParseError> {
+    pub(crate) fn expect_tok(&mut self, tok: Tok, context: &str) -> Result<(), ParseError> {
         if self.cur.tok == tok {
             self.bump()?;
             Ok(())
@@ -131,7 +148,7 @@ impl<'a> Checker<'a> {
     /// Consumes a balanced `{ … }` / `[ … ]` / `( … )` region, fence-aware.
     /// The current token must be the opening delimiter. Content is not
     /// inspected — everything semantic is the full parser's job.
-    fn skip_balanced(&mut self, context: &str) -> Result<(), ParseError> {
+    pub(crate) fn skip_balanced(&mut self, context: &str) -> Result<(), ParseError> {
         let open = match self.cur.tok {
             Tok::LBrace | Tok::LBracket | Tok::LParen => self.cur.tok.clone(),
             _ => {
@@ -191,7 +208,7 @@ impl<'a> Checker<'a> {
         }
     }
 
-    fn file(mut self) -> Result<(), ParseError> {
+    pub(crate) fn file(mut self) -> Result<Option<Outline>, ParseError> {
         // dsrs 1
         self.expect_kw("dsrs", "at the start of the file (`dsrs 1`)")?;
         match &self.cur.tok {
@@ -214,12 +231,25 @@ impl<'a> Checker<'a> {
 
         // program <name>
         self.expect_kw("program", "after the `dsrs 1` pragma")?;
+        if let (Some(outline), Tok::Ident(name)) = (self.outline.as_mut(), &self.cur.tok) {
+            outline.program = name.clone();
+        }
         self.expect_ident("after `program`")?;
 
         // Top-level declarations until `main`.
         loop {
             match &self.cur.tok {
                 Tok::Ident(word) => match word.as_str() {
+                    "import" => {
+                        self.bump()?;
+                        self.expect_str("after `import` (the imported file's path)")?;
+                        self.expect_kw("as", "after the import path")?;
+                        self.expect_ident("after `as` (the import alias)")?;
+                        if self.at_kw("pin") {
+                            self.bump()?;
+                            self.expect_str("after `pin` (the imported program's hash)")?;
+                        }
+                    }
                     "caps" => {
                         self.bump()?;
                         self.skip_balanced("after `caps`")?;
@@ -233,16 +263,32 @@ impl<'a> Checker<'a> {
                             self.skip_balanced("to open the model options")?;
                         }
                     }
+                    "class" if self.outline.is_some() => {
+                        let class = self.class_outline()?;
+                        self.outline
+                            .as_mut()
+                            .expect("outline mode")
+                            .classes
+                            .push(class);
+                    }
                     "class" => {
                         self.bump()?;
                         self.expect_ident("after `class`")?;
                         self.skip_balanced("to open the class body")?;
                     }
+                    "enum" if self.outline.is_some() => {
+                        let enm = self.enum_outline()?;
+                        self.outline.as_mut().expect("outline mode").enums.push(enm);
+                    }
                     "enum" => {
                         self.bump()?;
                         self.expect_ident("after `enum`")?;
                         self.skip_balanced("to open the enum body")?;
                     }
+                    "sig" if self.outline.is_some() => {
+                        let sig = self.sig_outline()?;
+                        self.outline.as_mut().expect("outline mode").sigs.push(sig);
+                    }
                     "sig" => {
                         self.bump()?;
                         self.expect_ident("after `sig`")?;
@@ -281,8 +327,8 @@ impl<'a> Checker<'a> {
                     "main" => break,
                     other => {
                         return Err(self.err(format!(
-                            "unknown top-level keyword `{other}`: expected one of `caps`, \
-                             `model`, `sig`, `class`, `enum`, `tool`, `lineage`, `main`"
+                            "unknown top-level keyword `{other}`: expected one of `import`, \
+                             `caps`, `model`, `sig`, `class`, `enum`, `tool`, `lineage`, `main`"
                         )));
                     }
                 },
@@ -303,6 +349,9 @@ impl<'a> Checker<'a> {
         // main: <Sig> = seq { … }
         self.expect_kw("main", "")?;
         self.expect_tok(Tok::Colon, "after `main`")?;
+        if let (Some(outline), Tok::Ident(name)) = (self.outline.as_mut(), &self.cur.tok) {
+            outline.main = name.clone();
+        }
         self.expect_ident("after `main:` (the program signature name)")?;
         self.expect_tok(Tok::Eq, "after the main signature name")?;
         if !self.at_kw("seq") {
@@ -320,7 +369,7 @@ impl<'a> Checker<'a> {
                 self.cur.tok.describe()
             )));
         }
-        Ok(())
+        Ok(self.outline)
     }
 }
 
@@ -388,6 +437,19 @@ main: Main = seq {
         );
     }
 
+    #[test]
+    fn accepts_imports_and_rejects_a_missing_alias() {
+        let src = MINI.replace(
+            "program mini\n",
+            "program mini\nimport \"retrieve.dsrs\" as retrieve pin \"00ff\"\n",
+        );
+        check(&src).expect("imports are top-level declarations");
+
+        let err = check_err("dsrs 1\nprogram x\nimport \"r.dsrs\"\nmain: M = seq { }");
+        assert_eq!((err.line, err.col), (4, 1));
+        assert!(err.message.contains("expected `as`"), "{}", err.message);
+    }
+
     #[test]
     fn rejects_unbalanced_brace_with_open_position() {
         let err = check_err("dsrs 1\nprogram x\nsig Main {\n  in q: string\n");
@@ -23,3 +23,23 @@ fn parity_accepts_everything_the_full_parser_accepts() {
     }
     assert!(seen >= 3, "expected the golden .dsrs fixtures, found {seen}");
 }
+
+#[test]
+fn outline_reads_every_fixture_declaration() {
+    let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+    for entry in std::fs::read_dir(fixtures).expect("fixtures dir readable") {
+        let path = entry.expect("dir entry").path();
+        if path.extension().and_then(|e| e.to_str()) != Some("dsrs") {
+            continue;
+        }
+        let src = std::fs::read_to_string(&path).expect("fixture readable");
+        let outline = dsrs_syntax::outline(&src)
+            .unwrap_or_else(|e| panic!("outline rejected {}: {e}", path.display()));
+        assert!(
+            outline.main_sig().is_some(),
+            "{}: main sig `{}` not among the outlined sigs",
+            path.display(),
+            outline.main
+        );
+    }
+}